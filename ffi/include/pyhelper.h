@@ -0,0 +1,43 @@
+/*
+ * C interface to the pyhelper conflict engine (libpyhelper_ffi).
+ *
+ * Strings are NUL-terminated UTF-8. Returned strings belong to the caller and
+ * are released with pyhelper_string_free(). A failed call returns NULL (or
+ * PYHELPER_ERROR), and pyhelper_last_error() describes the failure.
+ */
+#ifndef PYHELPER_H
+#define PYHELPER_H
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define PYHELPER_OK 0
+#define PYHELPER_CONFLICT 1
+#define PYHELPER_ERROR 2
+
+/* Whether two requirements on the same package conflict. */
+int pyhelper_check_conflict(const char *req1, const char *req2);
+
+/* A PEP 508 requirement as JSON. */
+char *pyhelper_parse_requirement(const char *input);
+
+/* The JSON of `pyhelper check REQ1 REQ2 --format json`. */
+char *pyhelper_check_pair_json(const char *req1, const char *req2);
+
+/* The JSON of `pyhelper check -r FILE --format json`, following includes. */
+char *pyhelper_check_file_json(const char *path);
+
+/* Like pyhelper_check_file_json(), for the content of a requirements file. */
+char *pyhelper_check_requirements_json(const char *content);
+
+/* The error of the last failed call on this thread, or NULL. Owned by the library. */
+const char *pyhelper_last_error(void);
+
+void pyhelper_string_free(char *s);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PYHELPER_H */
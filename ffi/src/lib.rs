@@ -0,0 +1,197 @@
+//! C bindings for the pyhelper conflict engine, declared in `include/pyhelper.h`.
+//!
+//! Strings cross the boundary as NUL-terminated UTF-8. Functions returning a
+//! string hand over ownership, and the caller frees it with
+//! [`pyhelper_string_free`]. Failures return `NULL` (or
+//! [`PYHELPER_ERROR`]), and [`pyhelper_last_error`] describes them.
+
+use anyhow::{anyhow, Result};
+use serde_json::Value;
+use std::cell::RefCell;
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::path::Path;
+use std::ptr;
+
+use pyhelper::output::json;
+use pyhelper::prerelease::PreReleasePolicy;
+use pyhelper::requirements;
+use pyhelper::{ConflictReport, PythonPackage};
+
+/// The requirements are compatible.
+pub const PYHELPER_OK: c_int = 0;
+/// The requirements conflict.
+pub const PYHELPER_CONFLICT: c_int = 1;
+/// An argument was invalid; see [`pyhelper_last_error`].
+pub const PYHELPER_ERROR: c_int = 2;
+
+thread_local! {
+    static LAST_ERROR: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Whether two requirements on the same package conflict: [`PYHELPER_OK`],
+/// [`PYHELPER_CONFLICT`] or [`PYHELPER_ERROR`]. Requirements on different
+/// packages never conflict.
+///
+/// # Safety
+///
+/// Both arguments must be valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn pyhelper_check_conflict(req1: *const c_char, req2: *const c_char) -> c_int {
+    let result = (|| {
+        let (a, b) = (parse_package(req1)?, parse_package(req2)?);
+        Ok(a.canonical_name() == b.canonical_name() && a.conflicts_with(&b))
+    })();
+    match recorded(result) {
+        Some(true) => PYHELPER_CONFLICT,
+        Some(false) => PYHELPER_OK,
+        None => PYHELPER_ERROR,
+    }
+}
+
+/// Parses a PEP 508 requirement into JSON: its name, extras, specifiers,
+/// marker and the versions it allows.
+///
+/// # Safety
+///
+/// `input` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pyhelper_parse_requirement(input: *const c_char) -> *mut c_char {
+    json_result(parse_package(input).map(|package| json::package(&package)))
+}
+
+/// Compares two requirements, returning the JSON of
+/// `pyhelper check REQ1 REQ2 --format json`.
+///
+/// # Safety
+///
+/// Both arguments must be valid NUL-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn pyhelper_check_pair_json(req1: *const c_char, req2: *const c_char) -> *mut c_char {
+    json_result((|| {
+        let (a, b) = (parse_package(req1)?, parse_package(req2)?);
+        Ok(json::pair(&a, &b, None, PreReleasePolicy::default()))
+    })())
+}
+
+/// Checks a requirements file, following its includes, returning the JSON of
+/// `pyhelper check -r FILE --format json`.
+///
+/// # Safety
+///
+/// `path` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pyhelper_check_file_json(path: *const c_char) -> *mut c_char {
+    json_result((|| {
+        let path = string(path)?;
+        let requirements = requirements::parse_file(Path::new(path))?;
+        Ok(json::report(&[path.to_string()], &ConflictReport::check(&requirements), None))
+    })())
+}
+
+/// Like [`pyhelper_check_file_json`], for the content of a requirements file.
+/// Includes cannot be followed and are an error.
+///
+/// # Safety
+///
+/// `content` must be a valid NUL-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn pyhelper_check_requirements_json(content: *const c_char) -> *mut c_char {
+    const SOURCE: &str = "requirements.txt";
+    json_result((|| {
+        let requirements = requirements::parse_str(string(content)?, Path::new(SOURCE))?;
+        Ok(json::report(&[SOURCE.to_string()], &ConflictReport::check(&requirements), None))
+    })())
+}
+
+/// The error of the last call failing on this thread, or `NULL`. The string
+/// stays owned by the library and is valid until the next call.
+#[no_mangle]
+pub extern "C" fn pyhelper_last_error() -> *const c_char {
+    LAST_ERROR.with(|error| error.borrow().as_ref().map_or(ptr::null(), |message| message.as_ptr()))
+}
+
+/// Frees a string returned by the library. `NULL` is ignored.
+///
+/// # Safety
+///
+/// `s` must be `NULL` or a string returned by this library, not freed before.
+#[no_mangle]
+pub unsafe extern "C" fn pyhelper_string_free(s: *mut c_char) {
+    if !s.is_null() {
+        drop(CString::from_raw(s));
+    }
+}
+
+unsafe fn string<'a>(s: *const c_char) -> Result<&'a str> {
+    if s.is_null() {
+        return Err(anyhow!("Unexpected NULL string"));
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| anyhow!("String is not valid UTF-8"))
+}
+
+unsafe fn parse_package(s: *const c_char) -> Result<PythonPackage> {
+    PythonPackage::parse(string(s)?)
+}
+
+/// The value of a successful call, recording the error of a failed one.
+fn recorded<T>(result: Result<T>) -> Option<T> {
+    LAST_ERROR.with(|error| match result {
+        Ok(value) => {
+            *error.borrow_mut() = None;
+            Some(value)
+        }
+        Err(e) => {
+            // Messages cannot hold NUL bytes, but input echoed into them could
+            let message = format!("{:#}", e).replace('\0', "");
+            *error.borrow_mut() = CString::new(message).ok();
+            None
+        }
+    })
+}
+
+fn json_result(result: Result<Value>) -> *mut c_char {
+    recorded(result)
+        .and_then(|value| CString::new(value.to_string()).ok())
+        .map_or(ptr::null_mut(), CString::into_raw)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn owned(s: *mut c_char) -> Value {
+        assert!(!s.is_null());
+        let value = serde_json::from_str(unsafe { CStr::from_ptr(s) }.to_str().unwrap()).unwrap();
+        unsafe { pyhelper_string_free(s) };
+        value
+    }
+
+    fn last_error() -> String {
+        unsafe { CStr::from_ptr(pyhelper_last_error()) }.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn test_check_conflict() {
+        let check = |a: &CStr, b: &CStr| unsafe { pyhelper_check_conflict(a.as_ptr(), b.as_ptr()) };
+        assert_eq!(check(c"django>=4.0", c"django<3.0"), PYHELPER_CONFLICT);
+        assert_eq!(check(c"flask>=2.0", c"Flask<3.0"), PYHELPER_OK);
+        assert!(pyhelper_last_error().is_null());
+        assert_eq!(check(c"django>=4.0", c"django>>3"), PYHELPER_ERROR);
+        assert!(last_error().contains(">>3"), "{}", last_error());
+        assert_eq!(unsafe { pyhelper_check_conflict(ptr::null(), c"six".as_ptr()) }, PYHELPER_ERROR);
+    }
+
+    #[test]
+    fn test_json_results() {
+        let parsed = owned(unsafe { pyhelper_parse_requirement(c"Requests[socks]>=2.0".as_ptr()) });
+        assert_eq!(parsed["canonical_name"], "requests");
+        let pair = owned(unsafe { pyhelper_check_pair_json(c"six<1".as_ptr(), c"six>=2".as_ptr()) });
+        assert_eq!(pair["conflict"], true);
+        let report = owned(unsafe { pyhelper_check_requirements_json(c"six<1\nsix>=2\nflask\n".as_ptr()) });
+        assert_eq!(report["checked"], 3);
+        assert_eq!(report["conflict"], true);
+
+        assert!(unsafe { pyhelper_check_file_json(c"/nonexistent/requirements.txt".as_ptr()) }.is_null());
+        assert!(last_error().contains("Cannot read requirements file"), "{}", last_error());
+    }
+}
@@ -0,0 +1,20 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pyhelper::marker::{MarkerEnvironment, MarkerTree};
+use std::collections::HashMap;
+
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let Ok(marker) = MarkerTree::parse(text) else {
+        return;
+    };
+    let shown = marker.to_string();
+    let again = MarkerTree::parse(&shown).unwrap_or_else(|e| panic!("{:?} displays as invalid {:?}: {}", text, shown, e));
+    assert_eq!(again.to_string(), shown, "{:?} does not read back", text);
+    let _ = marker.is_satisfiable();
+    let _ = marker.overlaps(&again);
+    let _ = marker.evaluate(&MarkerEnvironment::new(HashMap::new()));
+});
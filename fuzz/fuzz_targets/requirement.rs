@@ -0,0 +1,28 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pyhelper::{parse_requirement_lossy, requirements, PythonPackage};
+use std::path::Path;
+
+// A requirement, and the same text as a requirements file
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let lossy = parse_requirement_lossy(text);
+    match PythonPackage::parse(text) {
+        Ok(package) => {
+            assert!(lossy.errors.is_empty(), "{:?} is valid, yet {:?}", text, lossy.errors);
+            let _ = package.to_string();
+            let _ = package.specifiers.range();
+        }
+        Err(_) => assert!(!lossy.errors.is_empty(), "{:?} is invalid, yet nothing was dropped", text),
+    }
+    if let Some(package) = lossy.package {
+        let _ = package.to_string();
+        let _ = package.specifiers.range();
+    }
+    if let Ok(lines) = requirements::parse_str(text, Path::new("requirements.txt")) {
+        let _ = pyhelper::ConflictReport::check(&lines);
+    }
+});
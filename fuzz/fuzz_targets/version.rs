@@ -0,0 +1,23 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pyhelper::{Pep440Version, SpecifierSet};
+
+// A version, and the same text as a specifier set
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    if let Ok(version) = Pep440Version::parse(text) {
+        let shown = version.to_string();
+        let again = Pep440Version::parse(&shown).unwrap_or_else(|e| panic!("{:?} displays as invalid {:?}: {}", text, shown, e));
+        assert!(again == version, "{:?} displays as {:?}", text, shown);
+    }
+    if let Ok(set) = SpecifierSet::parse(text) {
+        let _ = set.to_string();
+        let _ = set.simplified();
+        for interval in &set.range().intervals {
+            let _ = pyhelper::specifier::as_written(interval).to_string();
+        }
+    }
+});
@@ -0,0 +1,518 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+#[cfg(feature = "network")]
+use serde_json::json;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+use std::path::PathBuf;
+
+use crate::error::PyhelperError;
+#[cfg(feature = "network")]
+use crate::fetch::{Fetcher, NetworkOptions, DEFAULT_CONCURRENCY};
+use crate::freeze::pinned_version;
+use crate::lock::LockedPackage;
+use crate::package::canonicalize_name;
+use crate::pep440::Pep440Version;
+#[cfg(feature = "network")]
+use crate::prerelease::PreReleasePolicy;
+#[cfg(feature = "network")]
+use crate::pypi::{self, PypiClient};
+use crate::requirements::RequirementLine;
+
+/// Base URL of the OSV.dev vulnerability database API.
+pub const DEFAULT_OSV_URL: &str = "https://api.osv.dev";
+
+#[cfg(feature = "network")]
+/// The most queries OSV.dev accepts in one batch request.
+const BATCH_SIZE: usize = 1000;
+
+/// How severe an advisory is, from its CVSS v3 score or the rating of the
+/// database that published it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+impl Severity {
+    fn from_score(score: f64) -> Option<Self> {
+        match score {
+            s if s >= 9.0 => Some(Severity::Critical),
+            s if s >= 7.0 => Some(Severity::High),
+            s if s >= 4.0 => Some(Severity::Medium),
+            s if s > 0.0 => Some(Severity::Low),
+            _ => None,
+        }
+    }
+
+    fn from_rating(rating: &str) -> Option<Self> {
+        match rating.to_ascii_uppercase().as_str() {
+            "CRITICAL" => Some(Severity::Critical),
+            "HIGH" => Some(Severity::High),
+            "MODERATE" | "MEDIUM" => Some(Severity::Medium),
+            "LOW" => Some(Severity::Low),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Severity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Severity::from_rating(s).ok_or_else(|| {
+            PyhelperError::Invalid(format!("Unknown severity: {} (expected low, medium, high or critical)", s)).into()
+        })
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Severity::Low => "LOW",
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+            Severity::Critical => "CRITICAL",
+        })
+    }
+}
+
+/// A published vulnerability affecting a package.
+#[derive(Debug, Clone)]
+pub struct Advisory {
+    /// The OSV identifier, e.g. `GHSA-...` or `PYSEC-...`.
+    pub id: String,
+    /// Other identifiers of the same vulnerability, e.g. its CVE.
+    pub aliases: Vec<String>,
+    pub summary: Option<String>,
+    pub severity: Option<Severity>,
+    /// The CVSS v3 base score, when the advisory carries a vector.
+    pub score: Option<f64>,
+    /// The versions in which the vulnerability was fixed, oldest first.
+    pub fixed: Vec<Pep440Version>,
+}
+
+impl Advisory {
+    /// The first fixed version above `version`, the nearest upgrade that is not affected.
+    pub fn fixed_after(&self, version: &Pep440Version) -> Option<&Pep440Version> {
+        self.fixed.iter().find(|fixed| *fixed > version)
+    }
+}
+
+/// A package version to audit and where it comes from.
+#[derive(Debug, Clone)]
+pub struct Audited {
+    pub name: String,
+    pub version: Pep440Version,
+    pub file: PathBuf,
+    pub line: usize,
+    /// The columns of the requirement on `line`, as in [`RequirementLine`].
+    pub columns: Option<Range<usize>>,
+    /// Set when the version was picked from the index rather than pinned.
+    pub resolved: bool,
+}
+
+impl From<&LockedPackage> for Audited {
+    fn from(locked: &LockedPackage) -> Self {
+        Audited {
+            name: locked.name.clone(),
+            version: locked.version.clone(),
+            file: locked.file.clone(),
+            line: locked.line,
+            columns: None,
+            resolved: false,
+        }
+    }
+}
+
+/// An audited package with the advisories affecting its version.
+#[derive(Debug, Clone)]
+pub struct Finding {
+    pub package: Audited,
+    pub advisories: Vec<Advisory>,
+}
+
+/// Splits requirements into the pinned versions to audit (`==` without a
+/// wildcard) and the requirements that need a version picked first.
+pub fn pinned(requirements: &[RequirementLine]) -> (Vec<Audited>, Vec<RequirementLine>) {
+    let mut audited = Vec::new();
+    let mut unpinned = Vec::new();
+    for requirement in requirements {
+        match pinned_version(requirement) {
+            Some(version) => audited.push(Audited {
+                name: requirement.package.name.clone(),
+                version: version.clone(),
+                file: requirement.file.clone(),
+                line: requirement.line,
+                columns: requirement.columns.clone(),
+                resolved: false,
+            }),
+            None => unpinned.push(requirement.clone()),
+        }
+    }
+    (audited, unpinned)
+}
+
+#[cfg(feature = "network")]
+/// Picks the release pip would install for every requirement. Requirements no
+/// release satisfies are returned separately.
+pub fn resolve(
+    client: &PypiClient,
+    requirements: &[RequirementLine],
+    policy: PreReleasePolicy,
+) -> Result<(Vec<Audited>, Vec<RequirementLine>)> {
+    let names: Vec<&str> = requirements.iter().map(|r| r.package.name.as_str()).collect();
+    let mut audited = Vec::new();
+    let mut unresolved = Vec::new();
+    for (requirement, releases) in requirements.iter().zip(client.releases_all(&names)?) {
+        match pypi::best_match(&releases, &requirement.package.specifiers, policy) {
+            Some(version) => audited.push(Audited {
+                name: requirement.package.name.clone(),
+                version,
+                file: requirement.file.clone(),
+                line: requirement.line,
+                columns: requirement.columns.clone(),
+                resolved: true,
+            }),
+            None => unresolved.push(requirement.clone()),
+        }
+    }
+    Ok((audited, unresolved))
+}
+
+#[cfg(feature = "network")]
+#[derive(Debug, Deserialize)]
+struct BatchResponse {
+    #[serde(default)]
+    results: Vec<BatchResult>,
+}
+
+#[cfg(feature = "network")]
+#[derive(Debug, Deserialize)]
+struct BatchResult {
+    #[serde(default)]
+    vulns: Vec<VulnerabilityId>,
+}
+
+#[cfg(feature = "network")]
+#[derive(Debug, Deserialize)]
+struct VulnerabilityId {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Vulnerability {
+    id: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    summary: Option<String>,
+    #[serde(default)]
+    severity: Vec<SeverityEntry>,
+    #[serde(default)]
+    affected: Vec<Affected>,
+    database_specific: Option<DatabaseSpecific>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SeverityEntry {
+    #[serde(rename = "type")]
+    kind: String,
+    score: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Affected {
+    package: Option<AffectedPackage>,
+    #[serde(default)]
+    ranges: Vec<AffectedRange>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AffectedPackage {
+    ecosystem: String,
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AffectedRange {
+    #[serde(default)]
+    events: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DatabaseSpecific {
+    severity: Option<String>,
+}
+
+#[cfg(feature = "network")]
+/// Client for the OSV.dev API.
+pub struct OsvClient {
+    url: String,
+    fetcher: Fetcher,
+}
+
+#[cfg(feature = "network")]
+impl OsvClient {
+    pub fn new(url: &str) -> Result<Self> {
+        OsvClient::with_options(url, &NetworkOptions::default())
+    }
+
+    pub fn with_options(url: &str, options: &NetworkOptions) -> Result<Self> {
+        let mut fetcher = Fetcher::with_options(DEFAULT_CONCURRENCY, options)?;
+        Ok(OsvClient {
+            url: fetcher.authenticate(url.trim_end_matches('/')),
+            fetcher,
+        })
+    }
+
+    /// Looks up every package version and returns those affected by at least
+    /// one advisory, in the order given.
+    pub fn audit(&self, packages: &[Audited]) -> Result<Vec<Finding>> {
+        let batch_url = format!("{}/v1/querybatch", self.url);
+        let mut ids: Vec<Vec<String>> = Vec::new();
+        for chunk in packages.chunks(BATCH_SIZE) {
+            let queries: Vec<_> = chunk
+                .iter()
+                .map(|p| json!({"package": {"name": p.name, "ecosystem": "PyPI"}, "version": p.version.to_string()}))
+                .collect();
+            let body = self
+                .fetcher
+                .post_json(&batch_url, &json!({"queries": queries}).to_string())?
+                .ok_or_else(|| PyhelperError::NotFound(format!("Vulnerability database not found: {}", batch_url)))?;
+            let response: BatchResponse = serde_json::from_str(&body)
+                .map_err(|e| {
+                    let message = format!("Invalid vulnerability database response: {}", batch_url);
+                    PyhelperError::network(&batch_url, message, Some(Box::new(e)))
+                })?;
+            if response.results.len() != chunk.len() {
+                let message = format!("Vulnerability database answered {} of {} queries", response.results.len(), chunk.len());
+                return Err(PyhelperError::network(&batch_url, message, None).into());
+            }
+            ids.extend(response.results.into_iter().map(|r| r.vulns.into_iter().map(|v| v.id).collect()));
+        }
+
+        let mut unique: Vec<&String> = ids.iter().flatten().collect();
+        unique.sort();
+        unique.dedup();
+        let urls: Vec<String> = unique.iter().map(|id| format!("{}/v1/vulns/{}", self.url, id)).collect();
+        let mut bodies = Vec::new();
+        for (url, body) in urls.iter().zip(self.fetcher.get_all(&urls)) {
+            bodies.push(body?.ok_or_else(|| PyhelperError::NotFound(format!("Advisory not found: {}", url)))?);
+        }
+
+        let mut findings = Vec::new();
+        for (package, ids) in packages.iter().zip(&ids) {
+            let advisories = ids
+                .iter()
+                .map(|id| {
+                    let body = &bodies[unique.binary_search(&id).expect("every advisory was fetched")];
+                    parse_vulnerability(body, &package.name)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            if !advisories.is_empty() {
+                findings.push(Finding {
+                    package: package.clone(),
+                    advisories,
+                });
+            }
+        }
+        Ok(findings)
+    }
+}
+
+/// Reads an OSV vulnerability record, keeping the fixed versions listed for `name`.
+pub fn parse_vulnerability(body: &str, name: &str) -> Result<Advisory> {
+    let vulnerability: Vulnerability = serde_json::from_str(body).context("Invalid advisory")?;
+    let score = vulnerability
+        .severity
+        .iter()
+        .filter(|entry| entry.kind == "CVSS_V3")
+        .find_map(|entry| cvss3_score(&entry.score));
+    let rating = vulnerability.database_specific.and_then(|d| d.severity);
+    let severity = score
+        .and_then(Severity::from_score)
+        .or_else(|| rating.as_deref().and_then(Severity::from_rating));
+
+    let name = canonicalize_name(name);
+    let mut fixed: Vec<Pep440Version> = vulnerability
+        .affected
+        .iter()
+        .filter(|affected| {
+            affected
+                .package
+                .as_ref()
+                .is_some_and(|p| p.ecosystem == "PyPI" && canonicalize_name(&p.name) == name)
+        })
+        .flat_map(|affected| affected.ranges.iter().flat_map(|range| &range.events))
+        .filter_map(|event| Pep440Version::parse(event.get("fixed")?.as_str()?).ok())
+        .collect();
+    fixed.sort();
+    fixed.dedup();
+
+    Ok(Advisory {
+        id: vulnerability.id,
+        aliases: vulnerability.aliases,
+        summary: vulnerability.summary,
+        severity,
+        score,
+        fixed,
+    })
+}
+
+/// The base score of a CVSS v3.0 or v3.1 vector, e.g.
+/// `CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H` scores 9.8.
+fn cvss3_score(vector: &str) -> Option<f64> {
+    let mut parts = vector.split('/');
+    if !parts.next()?.starts_with("CVSS:3") {
+        return None;
+    }
+    let metrics: Vec<(&str, &str)> = parts.filter_map(|part| part.split_once(':')).collect();
+    let metric = |name: &str| metrics.iter().find(|(key, _)| *key == name).map(|(_, value)| *value);
+
+    let changed = match metric("S")? {
+        "U" => false,
+        "C" => true,
+        _ => return None,
+    };
+    let attack_vector = match metric("AV")? {
+        "N" => 0.85,
+        "A" => 0.62,
+        "L" => 0.55,
+        "P" => 0.2,
+        _ => return None,
+    };
+    let complexity = match metric("AC")? {
+        "L" => 0.77,
+        "H" => 0.44,
+        _ => return None,
+    };
+    let privileges = match (metric("PR")?, changed) {
+        ("N", _) => 0.85,
+        ("L", false) => 0.62,
+        ("L", true) => 0.68,
+        ("H", false) => 0.27,
+        ("H", true) => 0.5,
+        _ => return None,
+    };
+    let interaction = match metric("UI")? {
+        "N" => 0.85,
+        "R" => 0.62,
+        _ => return None,
+    };
+    let impact = |name: &str| match metric(name)? {
+        "H" => Some(0.56),
+        "L" => Some(0.22),
+        "N" => Some(0.0),
+        _ => None,
+    };
+
+    let base = 1.0 - (1.0 - impact("C")?) * (1.0 - impact("I")?) * (1.0 - impact("A")?);
+    let impact = if changed {
+        7.52 * (base - 0.029) - 3.25 * (base - 0.02_f64).powi(15)
+    } else {
+        6.42 * base
+    };
+    if impact <= 0.0 {
+        return Some(0.0);
+    }
+    let exploitability = 8.22 * attack_vector * complexity * privileges * interaction;
+    let score = if changed {
+        1.08 * (impact + exploitability)
+    } else {
+        impact + exploitability
+    };
+    Some(round_up(score.min(10.0)))
+}
+
+/// Rounds up to one decimal the way the CVSS v3.1 specification does, avoiding
+/// floating point artifacts such as 4.000000001 becoming 4.1.
+fn round_up(value: f64) -> f64 {
+    let scaled = (value * 100_000.0).round() as i64;
+    if scaled % 10_000 == 0 {
+        scaled as f64 / 100_000.0
+    } else {
+        (scaled / 10_000 + 1) as f64 / 10.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::PythonPackage;
+
+    #[test]
+    fn test_cvss3_score() {
+        assert_eq!(cvss3_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:H/I:H/A:H"), Some(9.8));
+        assert_eq!(cvss3_score("CVSS:3.1/AV:N/AC:L/PR:N/UI:R/S:C/C:L/I:L/A:N"), Some(6.1));
+        assert_eq!(cvss3_score("CVSS:3.0/AV:N/AC:H/PR:N/UI:N/S:U/C:N/I:N/A:H"), Some(5.9));
+        assert_eq!(cvss3_score("CVSS:3.1/AV:L/AC:L/PR:H/UI:N/S:U/C:N/I:N/A:N"), Some(0.0));
+        assert_eq!(cvss3_score("CVSS:4.0/AV:N/AC:L/AT:N/PR:N/UI:N/VC:H/VI:H/VA:H/SC:N/SI:N/SA:N"), None);
+        assert_eq!(cvss3_score("CVSS:3.1/AV:N/AC:L"), None);
+    }
+
+    #[test]
+    fn test_parse_vulnerability() -> Result<()> {
+        let body = r#"{
+            "id": "GHSA-jh3w-4vvf-mjgr",
+            "summary": "Django denial of service in file uploads",
+            "aliases": ["CVE-2023-24580"],
+            "severity": [{"type": "CVSS_V3", "score": "CVSS:3.1/AV:N/AC:L/PR:N/UI:N/S:U/C:N/I:N/A:H"}],
+            "affected": [
+                {
+                    "package": {"ecosystem": "PyPI", "name": "Django"},
+                    "ranges": [{"type": "ECOSYSTEM", "events": [
+                        {"introduced": "4.0"}, {"fixed": "4.0.10"},
+                        {"introduced": "4.1"}, {"fixed": "4.1.7"}
+                    ]}]
+                },
+                {
+                    "package": {"ecosystem": "PyPI", "name": "django"},
+                    "ranges": [{"type": "ECOSYSTEM", "events": [{"introduced": "0"}, {"fixed": "3.2.18"}]}]
+                },
+                {
+                    "package": {"ecosystem": "Debian", "name": "python-django"},
+                    "ranges": [{"type": "ECOSYSTEM", "events": [{"fixed": "1.0"}]}]
+                }
+            ],
+            "database_specific": {"severity": "MODERATE"}
+        }"#;
+        let advisory = parse_vulnerability(body, "django")?;
+        assert_eq!(advisory.aliases, vec!["CVE-2023-24580"]);
+        assert_eq!(advisory.score, Some(7.5));
+        assert_eq!(advisory.severity, Some(Severity::High));
+        let fixed: Vec<String> = advisory.fixed.iter().map(|v| v.to_string()).collect();
+        assert_eq!(fixed, vec!["3.2.18", "4.0.10", "4.1.7"]);
+        assert_eq!(advisory.fixed_after(&Pep440Version::parse("4.0.2")?).map(|v| v.to_string()), Some("4.0.10".into()));
+
+        let advisory = parse_vulnerability(r#"{"id": "PYSEC-1", "database_specific": {"severity": "LOW"}}"#, "x")?;
+        assert_eq!(advisory.severity, Some(Severity::Low));
+        assert!(advisory.fixed_after(&Pep440Version::parse("1.0")?).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_pinned() -> Result<()> {
+        let requirements: Vec<RequirementLine> = ["django==3.2.0", "flask>=2.0", "requests==2.*"]
+            .iter()
+            .enumerate()
+            .map(|(i, req)| {
+                Ok(RequirementLine {
+                    package: PythonPackage::parse(req)?,
+                    file: PathBuf::from("requirements.txt"),
+                    line: i + 1,
+                    columns: None,
+                    hashes: Vec::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        let (audited, unpinned) = pinned(&requirements);
+        assert_eq!(audited.len(), 1);
+        assert_eq!((audited[0].version.to_string(), audited[0].line), ("3.2.0".to_string(), 1));
+        assert_eq!(unpinned.len(), 2);
+        Ok(())
+    }
+}
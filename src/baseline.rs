@@ -0,0 +1,195 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+
+use crate::audit::Finding;
+use crate::conflict::PackageConflict;
+use crate::ignore::{Acknowledged, IgnoreRule};
+use crate::output::sarif::CONFLICT_RULE;
+use crate::package::canonicalize_name;
+use crate::requirements::RequirementLine;
+
+/// The justification shown for the problems a baseline acknowledges.
+pub const BASELINE_REASON: &str = "recorded in the baseline";
+
+/// The problems a project had when it was recorded, which no longer fail a
+/// run while any new one does.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+pub struct Baseline {
+    #[serde(default)]
+    pub conflicts: Vec<RecordedConflict>,
+    #[serde(default)]
+    pub advisories: Vec<RecordedAdvisory>,
+}
+
+/// A conflict between requirements on one package.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RecordedConflict {
+    /// PEP 503 normalized package name.
+    pub package: String,
+    /// The requirements involved, with the normalized name and sorted, so
+    /// moving them around a file does not make the conflict new.
+    pub requirements: Vec<String>,
+}
+
+/// An advisory affecting a package, whatever its version.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct RecordedAdvisory {
+    /// PEP 503 normalized package name.
+    pub package: String,
+    pub id: String,
+    #[serde(default)]
+    pub aliases: Vec<String>,
+}
+
+impl Baseline {
+    /// Records the given conflicts and advisory findings.
+    pub fn record(conflicts: &[PackageConflict], findings: &[Finding]) -> Self {
+        let mut baseline = Baseline::default();
+        for conflict in conflicts {
+            baseline.conflicts.push(RecordedConflict { package: conflict.name.clone(), requirements: requirements(&conflict.requirements) });
+        }
+        for finding in findings {
+            let package = canonicalize_name(&finding.package.name);
+            for advisory in &finding.advisories {
+                let recorded = RecordedAdvisory { package: package.clone(), id: advisory.id.clone(), aliases: advisory.aliases.clone() };
+                if !baseline.advisories.contains(&recorded) {
+                    baseline.advisories.push(recorded);
+                }
+            }
+        }
+        baseline.conflicts.sort_by(|a, b| (&a.package, &a.requirements).cmp(&(&b.package, &b.requirements)));
+        baseline.conflicts.dedup();
+        baseline.advisories.sort_by(|a, b| (&a.package, &a.id).cmp(&(&b.package, &b.id)));
+        baseline
+    }
+
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(serde_json::from_str(content)?)
+    }
+
+    pub fn parse_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Cannot read baseline: {}", path.display()))?;
+        Baseline::parse(&content).with_context(|| format!("Invalid baseline: {}", path.display()))
+    }
+
+    /// Whether the conflict was recorded: on the same package, between
+    /// requirements that were all involved then. A conflict a new
+    /// requirement takes part in is new.
+    pub fn has_conflict(&self, conflict: &PackageConflict) -> bool {
+        let current = requirements(&conflict.requirements);
+        self.conflicts
+            .iter()
+            .any(|recorded| recorded.package == conflict.name && current.iter().all(|r| recorded.requirements.contains(r)))
+    }
+
+    /// Whether an advisory known by any of `ids` was recorded for `package`.
+    pub fn has_advisory(&self, package: &str, ids: &[&str]) -> bool {
+        let package = canonicalize_name(package);
+        self.advisories.iter().any(|recorded| {
+            recorded.package == package
+                && std::iter::once(&recorded.id).chain(&recorded.aliases).any(|known| ids.iter().any(|id| id.eq_ignore_ascii_case(known)))
+        })
+    }
+
+    /// Whether the conflict was recorded, noting it in `acknowledged` when it was.
+    pub fn acknowledge_conflict(&self, conflict: &PackageConflict, acknowledged: &mut Vec<Acknowledged>) -> bool {
+        if !self.has_conflict(conflict) {
+            return false;
+        }
+        acknowledged.push(acknowledgement(&conflict.name, CONFLICT_RULE));
+        true
+    }
+
+    /// Whether an advisory known by any of `ids` was recorded for `package`,
+    /// noting it in `acknowledged` when it was. The first ID is the one noted.
+    pub fn acknowledge_advisory(&self, package: &str, ids: &[&str], acknowledged: &mut Vec<Acknowledged>) -> bool {
+        if !self.has_advisory(package, ids) {
+            return false;
+        }
+        acknowledged.push(acknowledgement(package, ids.first().copied().unwrap_or_default()));
+        true
+    }
+}
+
+fn acknowledgement(package: &str, id: &str) -> Acknowledged {
+    let by = IgnoreRule {
+        package: canonicalize_name(package),
+        rule: Some(id.to_string()),
+        expires: None,
+        reason: Some(BASELINE_REASON.to_string()),
+    };
+    Acknowledged { package: package.to_string(), id: id.to_string(), by }
+}
+
+/// The requirements of a conflict as recorded: normalized names, sorted and
+/// without repeats.
+fn requirements(lines: &[RequirementLine]) -> Vec<String> {
+    let mut requirements: Vec<String> = lines
+        .iter()
+        .map(|line| {
+            let mut package = line.package.clone();
+            package.name = package.canonical_name();
+            // Displayed as `requests >=2.31`, recorded as `requests>=2.31`
+            let text = package.to_string();
+            let text = if package.url.is_none() { text.replacen(' ', "", 1) } else { text };
+            text.trim_end().to_string()
+        })
+        .collect();
+    requirements.sort();
+    requirements.dedup();
+    requirements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{Advisory, Audited};
+    use crate::conflict::ConflictReport;
+    use crate::output::json;
+    use crate::pep440::Pep440Version;
+    use crate::requirements;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_ratchet() -> Result<()> {
+        let path = Path::new("requirements.txt");
+        let legacy = requirements::parse_str("Requests>=2.31\nrequests<2.30\nsix\n", path)?;
+        let finding = Finding {
+            package: Audited {
+                name: "urllib3".to_string(),
+                version: Pep440Version::parse("1.26.0")?,
+                file: PathBuf::from("requirements.txt"),
+                line: 4,
+                columns: None,
+                resolved: false,
+            },
+            advisories: vec![Advisory {
+                id: "GHSA-v845-jxx5-vc9f".to_string(),
+                aliases: vec!["CVE-2023-43804".to_string()],
+                summary: None,
+                severity: None,
+                score: None,
+                fixed: Vec::new(),
+            }],
+        };
+        let recorded = Baseline::record(&ConflictReport::check(&legacy).conflicts, &[finding]);
+        let baseline = Baseline::parse(&json::baseline(&recorded).to_string())?;
+        assert_eq!(baseline, recorded);
+        assert_eq!(baseline.conflicts[0].requirements, ["requests<2.30", "requests>=2.31"]);
+
+        // Moved around and reformatted, the same conflict is not new
+        let moved = requirements::parse_str("six\nrequests <2.30\n\nrequests >= 2.31\n", path)?;
+        let mut acknowledged = Vec::new();
+        let conflicts = ConflictReport::check(&moved).conflicts;
+        assert!(baseline.acknowledge_conflict(&conflicts[0], &mut acknowledged));
+        assert_eq!(acknowledged[0].by.reason.as_deref(), Some(BASELINE_REASON));
+
+        let worse = requirements::parse_str("requests>=2.31\nrequests<2.30\nrequests==2.0\n", path)?;
+        assert!(!baseline.has_conflict(&ConflictReport::check(&worse).conflicts[0]));
+        assert!(baseline.has_advisory("URLLIB3", &["PYSEC-2023-192", "cve-2023-43804"]));
+        assert!(!baseline.has_advisory("urllib3", &["GHSA-g4mx-q9vg-27p4"]));
+        Ok(())
+    }
+}
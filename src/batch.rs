@@ -0,0 +1,65 @@
+use anyhow::Result;
+use std::path::PathBuf;
+
+use crate::package::PythonPackage;
+use crate::requirements::RequirementLine;
+
+/// The file name recorded for requirements read in batch mode.
+pub const STDIN_NAME: &str = "<stdin>";
+
+/// Whether a batch input line holds nothing to check.
+pub fn is_blank(input: &str) -> bool {
+    let input = input.trim();
+    input.is_empty() || input.starts_with('#')
+}
+
+/// Parses one line of batch input into the requirements to check against each
+/// other. Requirements are separated by `|` when the line contains one, which
+/// allows spaces and markers inside them, and by whitespace otherwise, e.g.
+/// `requests>=2.0 requests<3`.
+pub fn parse_line(input: &str, line: usize) -> Result<Vec<RequirementLine>> {
+    let requirements: Vec<&str> = if input.contains('|') {
+        input.split('|').map(str::trim).filter(|r| !r.is_empty()).collect()
+    } else {
+        input.split_whitespace().collect()
+    };
+    requirements
+        .into_iter()
+        .map(|requirement| {
+            Ok(RequirementLine {
+                package: PythonPackage::parse(requirement)?,
+                file: PathBuf::from(STDIN_NAME),
+                line,
+                columns: None,
+                hashes: Vec::new(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::ConflictReport;
+
+    #[test]
+    fn test_parse_line() -> Result<()> {
+        let names = |input: &str| -> Result<Vec<String>> {
+            Ok(parse_line(input, 3)?.iter().map(|r| r.package.to_string()).collect())
+        };
+        assert_eq!(names("requests>=2.0  requests<3")?, vec!["requests >=2.0", "requests <3"]);
+        assert_eq!(
+            names("pywin32 >= 300; sys_platform == 'win32' | pywin32<300")?,
+            vec!["pywin32 >=300; sys_platform == \"win32\"", "pywin32 <300"]
+        );
+        assert!(parse_line("requests>=2.0 requests<<3", 1).is_err());
+
+        let report = ConflictReport::check(&parse_line("django>=4.0 django<3.0 flask", 7)?);
+        assert_eq!(report.conflicts[0].requirements[1].line, 7);
+
+        assert!(is_blank("   # extracted from services/api"));
+        assert!(!is_blank("requests"));
+
+        Ok(())
+    }
+}
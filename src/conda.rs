@@ -0,0 +1,219 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+use tracing::{debug, instrument};
+
+use crate::error::PyhelperError;
+use crate::package::PythonPackage;
+use crate::requirements::{self, RequirementLine};
+use crate::specifier::SpecifierSet;
+
+/// Reads the `dependencies` of a conda `environment.yml`: conda match specs
+/// such as `numpy=1.24*`, and the pip requirements nested under `- pip:`.
+/// Channel prefixes and build strings are dropped. `-r` lines of the pip
+/// section are followed relative to the environment file.
+///
+/// Only the block style conda writes is understood, not YAML in general.
+#[instrument(skip_all, err, fields(file = %path.display()))]
+pub fn parse_file(path: &Path) -> Result<Vec<RequirementLine>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Cannot read environment file: {}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new("."));
+
+    let mut requirements = Vec::new();
+    let mut in_dependencies = false;
+    // The indentation of the `- pip:` item while inside its list
+    let mut pip_indent: Option<usize> = None;
+    for (index, raw) in content.lines().enumerate() {
+        let line = index + 1;
+        let text = strip_comment(raw);
+        if text.trim().is_empty() {
+            continue;
+        }
+        let indent = text.len() - text.trim_start().len();
+        let text = text.trim();
+
+        if indent == 0 && !text.starts_with('-') {
+            in_dependencies = text == "dependencies:";
+            pip_indent = None;
+            continue;
+        }
+        let Some(item) = text.strip_prefix('-') else {
+            continue;
+        };
+        if !in_dependencies {
+            continue;
+        }
+        if pip_indent.is_some_and(|pip| indent <= pip) {
+            pip_indent = None;
+        }
+        let item = unquote(item.trim());
+        if item.strip_suffix(':').is_some_and(|key| key.trim() == "pip") {
+            pip_indent = Some(indent);
+            continue;
+        }
+        let columns = requirements::columns_of(&content, line, item);
+        let column = columns.as_ref().map_or(1, |c| c.start);
+        let located = |e: anyhow::Error| PyhelperError::parse(path, line, Some(column), e);
+
+        let package = if pip_indent.is_some() {
+            if let Some(target) = item.strip_prefix("-r ").or_else(|| item.strip_prefix("--requirement ")) {
+                requirements.extend(requirements::parse_file(&base.join(target.trim())).map_err(located)?);
+                continue;
+            }
+            // Other pip options, editables and URLs cannot be checked
+            if item.starts_with('-') || item.contains("://") {
+                continue;
+            }
+            PythonPackage::parse(item).map_err(located)?
+        } else {
+            parse_match_spec(item).map_err(located)?
+        };
+        requirements.push(RequirementLine {
+            package,
+            file: path.to_path_buf(),
+            line,
+            columns,
+            hashes: Vec::new(),
+        });
+    }
+
+    debug!(requirements = requirements.len(), "parsed");
+    Ok(requirements)
+}
+
+/// Translates a conda match spec into a requirement: `numpy=1.24` and
+/// `numpy=1.24*` mean `==1.24.*`, `numpy 1.24` and `numpy==1.24` are exact,
+/// and operators such as `>=1.2,<2` carry over.
+pub fn parse_match_spec(spec: &str) -> Result<PythonPackage> {
+    let spec = spec.rsplit("::").next().unwrap_or(spec).trim();
+    let name_end = spec
+        .find(|c: char| !(c.is_ascii_alphanumeric() || "._-".contains(c)))
+        .unwrap_or(spec.len());
+    let (name, rest) = spec.split_at(name_end);
+    if name.is_empty() {
+        bail!(PyhelperError::Specifier(format!("Invalid conda package: {}", spec)));
+    }
+    // `>=1.2, <2` is one version spec
+    let rest = rest.split(',').map(str::trim).collect::<Vec<_>>().join(",");
+    if rest.trim_start().starts_with('[') {
+        bail!(PyhelperError::Specifier(format!("Bracketed conda match specs are not supported: {}", spec)));
+    }
+
+    let (version, fuzzy) = match rest.strip_prefix('=') {
+        // `name=version=build`
+        Some(fuzzy) if !fuzzy.starts_with('=') => (fuzzy.split('=').next().unwrap_or_default(), true),
+        // `name version build`, or an operator directly after the name
+        _ => (rest.split_whitespace().next().unwrap_or_default(), false),
+    };
+    if version.contains('|') {
+        bail!(PyhelperError::Specifier(format!("Alternative versions with `|` are not supported: {}", spec)));
+    }
+
+    let mut clauses = Vec::new();
+    for clause in version.split(',').map(str::trim).filter(|c| !c.is_empty() && *c != "*") {
+        let operator_end = clause.find(|c: char| !"<>=!~".contains(c)).unwrap_or(clause.len());
+        let (operator, number) = clause.split_at(operator_end);
+        let prefix = number.strip_suffix('*').map(|n| n.trim_end_matches('.'));
+        clauses.push(match (operator, prefix) {
+            ("" | "==" | "!=", Some(prefix)) => format!("{}{}.*", if operator.is_empty() { "==" } else { operator }, prefix),
+            ("", None) if fuzzy => format!("=={}.*", number),
+            ("", None) => format!("=={}", number),
+            (operator, Some(prefix)) => format!("{}{}", operator, prefix),
+            (operator, None) => format!("{}{}", operator, number),
+        });
+    }
+    let specifiers = SpecifierSet::parse(&clauses.join(","))
+        .map_err(|_| PyhelperError::Specifier(format!("Invalid conda version: {}", version)))?;
+
+    Ok(PythonPackage {
+        name: name.to_string(),
+        extras: Vec::new(),
+        specifiers,
+        marker: None,
+        url: None,
+    })
+}
+
+/// Drops a `#` comment, which YAML only starts at the line start or after whitespace.
+/// The indexes the pip section of an environment file points pip at, as
+/// [`requirements::index_urls`] reads them.
+pub fn index_urls(content: &str) -> (Option<String>, Vec<String>) {
+    let options: Vec<&str> = content
+        .lines()
+        .filter_map(|raw| strip_comment(raw).trim().strip_prefix('-'))
+        .map(|item| unquote(item.trim()))
+        .filter(|item| item.starts_with('-'))
+        .collect();
+    requirements::index_urls(&options.join("\n"))
+}
+
+fn strip_comment(line: &str) -> &str {
+    if line.trim_start().starts_with('#') {
+        return "";
+    }
+    line.find(" #").map_or(line, |i| &line[..i])
+}
+
+fn unquote(value: &str) -> &str {
+    for quote in ['"', '\''] {
+        if let Some(inner) = value.strip_prefix(quote).and_then(|v| v.strip_suffix(quote)) {
+            return inner;
+        }
+    }
+    value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(input: &str) -> String {
+        let package = parse_match_spec(input).unwrap();
+        format!("{} {}", package.name, package.version_spec())
+    }
+
+    #[test]
+    fn test_parse_match_spec() {
+        assert_eq!(spec("numpy=1.24*"), "numpy ==1.24.*");
+        assert_eq!(spec("numpy=1.24"), "numpy ==1.24.*");
+        assert_eq!(spec("numpy=1.24.2=py311h_0"), "numpy ==1.24.2.*");
+        assert_eq!(spec("numpy==1.24.2"), "numpy ==1.24.2");
+        assert_eq!(spec("numpy 1.24.2 py311h_0"), "numpy ==1.24.2");
+        assert_eq!(spec("conda-forge::scipy>=1.10, <2"), "scipy >=1.10,<2");
+        assert_eq!(spec("pandas !=2.0*"), "pandas !=2.0.*");
+        assert_eq!(spec("python"), "python *");
+        assert!(parse_match_spec("numpy 1.23|1.24").is_err());
+    }
+
+    #[test]
+    fn test_parse_file() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("pyhelper-conda-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("requirements.txt"), "attrs>=22\n")?;
+        let path = dir.join("environment.yml");
+        fs::write(
+            &path,
+            "name: demo\nchannels:\n  - conda-forge\ndependencies:\n  - python=3.11\n  - \"numpy>=1.24\"  # arrays\n  - pip\n  - pip:\n      - requests[socks]>=2.31\n      - -r requirements.txt\n      - --index-url https://example.com/simple\n  - pandas\nvariables:\n  - ignored\n",
+        )?;
+
+        let requirements = parse_file(&path)?;
+        let found: Vec<(String, String, usize)> = requirements
+            .iter()
+            .map(|r| (r.package.name.clone(), r.package.version_spec(), r.line))
+            .collect();
+        let expected = [
+            ("python", "==3.11.*", 5),
+            ("numpy", ">=1.24", 6),
+            ("pip", "*", 7),
+            ("requests", ">=2.31", 9),
+            ("attrs", ">=22", 1),
+            ("pandas", "*", 12),
+        ];
+        let expected: Vec<(String, String, usize)> =
+            expected.iter().map(|(n, s, l)| (n.to_string(), s.to_string(), *l)).collect();
+        assert_eq!(found, expected);
+        assert_eq!(requirements[3].package.extras, vec!["socks"]);
+        Ok(())
+    }
+}
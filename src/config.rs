@@ -0,0 +1,236 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::audit::Severity;
+use crate::error::PyhelperError;
+use crate::ignore::IgnoreRule;
+use crate::lint::PinRule;
+use crate::pep440::Pep440Version;
+use crate::plugin::PluginConfig;
+use crate::severity::{Level, SeverityPolicy};
+
+/// The name of the configuration file looked for next to the project.
+pub const CONFIG_FILE_NAME: &str = "pyhelper.toml";
+
+/// Defaults for command-line options. Every setting left out keeps the
+/// built-in default, and options given on the command line always win.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Settings {
+    /// Base URL of the package index's JSON API.
+    pub index_url: Option<String>,
+    /// Indexes looked up after the main one.
+    pub extra_index_url: Option<Vec<String>>,
+    /// Proxy for every request.
+    pub proxy: Option<String>,
+    /// The Python version requirement markers are evaluated for, e.g. `3.11`.
+    #[serde(default, deserialize_with = "parsed")]
+    pub python_version: Option<Pep440Version>,
+    /// The platform tag online checks look for installable files for.
+    pub platform: Option<String>,
+    /// Packages, or problems of them, no report fails for.
+    pub ignore: Option<Vec<IgnoreRule>>,
+    /// Output format, for the commands supporting it.
+    pub format: Option<String>,
+    /// The least severe advisory that fails `audit`.
+    #[serde(default, deserialize_with = "parsed")]
+    pub fail_on: Option<Severity>,
+    /// Names of the organization's own packages, which `audit` looks for on
+    /// the public index.
+    pub internal: Option<Vec<String>>,
+    /// Pin rules of single packages or name prefixes for `lint`, taking
+    /// precedence over `--require-pins` and `--forbid-pins`.
+    pub pin_policy: Option<BTreeMap<String, PinRule>>,
+    /// Packages `lint --forbid-caps` accepts upper caps on.
+    pub allow_caps: Option<Vec<String>>,
+    /// Programs adding advisory sources or report destinations.
+    pub plugins: Option<Vec<PluginConfig>>,
+    /// The levels of rules, everywhere or for some packages.
+    pub severity: Option<SeverityPolicy>,
+    /// The most severe level findings may have without failing a run.
+    #[serde(default, deserialize_with = "parsed")]
+    pub max_severity: Option<Level>,
+}
+
+impl Settings {
+    /// These settings with the ones `other` sets taking precedence.
+    pub fn overridden_by(&self, other: &Settings) -> Settings {
+        Settings {
+            index_url: other.index_url.clone().or_else(|| self.index_url.clone()),
+            extra_index_url: other.extra_index_url.clone().or_else(|| self.extra_index_url.clone()),
+            proxy: other.proxy.clone().or_else(|| self.proxy.clone()),
+            python_version: other.python_version.clone().or_else(|| self.python_version.clone()),
+            platform: other.platform.clone().or_else(|| self.platform.clone()),
+            ignore: other.ignore.clone().or_else(|| self.ignore.clone()),
+            format: other.format.clone().or_else(|| self.format.clone()),
+            fail_on: other.fail_on.or(self.fail_on),
+            internal: other.internal.clone().or_else(|| self.internal.clone()),
+            pin_policy: other.pin_policy.clone().or_else(|| self.pin_policy.clone()),
+            allow_caps: other.allow_caps.clone().or_else(|| self.allow_caps.clone()),
+            plugins: other.plugins.clone().or_else(|| self.plugins.clone()),
+            severity: other.severity.clone().or_else(|| self.severity.clone()),
+            max_severity: other.max_severity.or(self.max_severity),
+        }
+    }
+}
+
+/// A configuration file: top-level settings and named profiles refining them.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(flatten)]
+    pub settings: Settings,
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Settings>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Pyproject {
+    tool: Option<Tool>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Tool {
+    pyhelper: Option<toml::Value>,
+}
+
+impl Config {
+    pub fn parse(content: &str) -> Result<Self> {
+        Ok(toml::from_str(content)?)
+    }
+
+    /// Reads a `pyhelper.toml`, or the `[tool.pyhelper]` table of a `pyproject.toml`.
+    pub fn parse_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("Cannot read configuration file: {}", path.display()))?;
+        let invalid = || format!("Invalid configuration file: {}", path.display());
+        if path.file_name().is_some_and(|name| name == "pyproject.toml") {
+            let pyproject: Pyproject = toml::from_str(&content).with_context(invalid)?;
+            let table = pyproject.tool.and_then(|tool| tool.pyhelper);
+            return match table {
+                Some(table) => table.try_into().with_context(invalid),
+                None => Ok(Config::default()),
+            };
+        }
+        Config::parse(&content).with_context(invalid)
+    }
+
+    /// Looks for the configuration of the project `dir` belongs to: the
+    /// nearest `pyhelper.toml`, or `pyproject.toml` with a `[tool.pyhelper]`
+    /// table, in `dir` or one of its parents.
+    pub fn discover(dir: &Path) -> Result<Option<(PathBuf, Self)>> {
+        for dir in dir.ancestors() {
+            let file = dir.join(CONFIG_FILE_NAME);
+            if file.is_file() {
+                return Ok(Some((file.clone(), Config::parse_file(&file)?)));
+            }
+            let pyproject = dir.join("pyproject.toml");
+            if pyproject.is_file() && has_tool_table(&pyproject) {
+                return Ok(Some((pyproject.clone(), Config::parse_file(&pyproject)?)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// The settings in effect: the top-level ones, refined by a profile.
+    pub fn settings(&self, profile: Option<&str>) -> Result<Settings> {
+        let Some(name) = profile else {
+            return Ok(self.settings.clone());
+        };
+        let profile = self.profiles.get(name).ok_or_else(|| {
+            let known: Vec<&str> = self.profiles.keys().map(|k| k.as_str()).collect();
+            let known = if known.is_empty() { "none".to_string() } else { known.join(", ") };
+            PyhelperError::Invalid(format!("Unknown profile: {} (defined: {})", name, known))
+        })?;
+        Ok(self.settings.overridden_by(profile))
+    }
+}
+
+/// Deserializes a string with its `FromStr` implementation.
+fn parsed<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: std::str::FromStr<Err = anyhow::Error>,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(text) => text.parse().map(Some).map_err(serde::de::Error::custom),
+        None => Ok(None),
+    }
+}
+
+fn has_tool_table(pyproject: &Path) -> bool {
+    let Ok(content) = fs::read_to_string(pyproject) else {
+        return false;
+    };
+    toml::from_str::<Pyproject>(&content).is_ok_and(|p| p.tool.is_some_and(|tool| tool.pyhelper.is_some()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() -> Result<()> {
+        let config = Config::parse(
+            r#"
+index-url = "https://pypi.example.com/pypi"
+extra-index-url = ["https://nexus.example.com/repository/pypi/pypi"]
+ignore = ["setuptools"]
+fail-on = "medium"
+
+[profiles.strict-ci]
+format = "json"
+fail-on = "low"
+
+[profiles.local-dev]
+ignore = []
+"#,
+        )?;
+        let base = config.settings(None)?;
+        assert_eq!(base.fail_on, Some(Severity::Medium));
+        assert_eq!(base.format, None);
+
+        let strict = config.settings(Some("strict-ci"))?;
+        assert_eq!(strict.index_url.as_deref(), Some("https://pypi.example.com/pypi"));
+        assert_eq!(strict.extra_index_url, Some(vec!["https://nexus.example.com/repository/pypi/pypi".to_string()]));
+        assert_eq!(strict.format.as_deref(), Some("json"));
+        assert_eq!(strict.fail_on, Some(Severity::Low));
+        assert_eq!(strict.ignore, Some(vec!["setuptools".parse()?]));
+        assert_eq!(config.settings(Some("local-dev"))?.ignore, Some(Vec::new()));
+
+        let err = config.settings(Some("release")).unwrap_err().to_string();
+        assert!(err.contains("local-dev, strict-ci"), "{}", err);
+        assert!(Config::parse("index_url = \"x\"").is_err());
+        assert!(Config::parse("fail-on = \"severe\"").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_discover() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("pyhelper-config-{}", std::process::id()));
+        let nested = root.join("src").join("app");
+        fs::create_dir_all(&nested)?;
+        fs::write(root.join("pyproject.toml"), "[project]\nname = \"demo\"\n")?;
+        assert!(Config::discover(&nested)?.is_none_or(|(path, _)| !path.starts_with(&root)));
+
+        fs::write(
+            root.join("pyproject.toml"),
+            "[project]\nname = \"demo\"\n\n[tool.pyhelper]\npython-version = \"3.11\"\n",
+        )?;
+        let (path, config) = Config::discover(&nested)?.unwrap();
+        assert_eq!(path, root.join("pyproject.toml"));
+        assert_eq!(config.settings.python_version, Some(Pep440Version::parse("3.11")?));
+
+        // pyhelper.toml takes precedence inside the same directory
+        fs::write(root.join(CONFIG_FILE_NAME), "format = \"json\"\n")?;
+        let (path, config) = Config::discover(&nested)?.unwrap();
+        assert_eq!(path, root.join(CONFIG_FILE_NAME));
+        assert_eq!(config.settings.format.as_deref(), Some("json"));
+
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+}
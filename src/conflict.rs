@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use crate::marker::MarkerTree;
+use crate::pep440::Pep440Version;
+use crate::prerelease::{self, PreReleasePolicy};
+use crate::range::IntervalSet;
+use crate::requirements::RequirementLine;
+use crate::specifier::{as_written, SpecifierSet};
+
+/// Every requirement on one package and the versions they jointly allow.
+#[derive(Debug, Clone)]
+pub struct PackageSummary {
+    /// PEP 503 normalized package name.
+    pub name: String,
+    pub requirements: Vec<RequirementLine>,
+    /// Intersection of all the requirements' specifiers, ignoring markers.
+    pub allowed: IntervalSet<Pep440Version>,
+    /// Whether pre-releases in `allowed` satisfy the requirements.
+    pub prereleases: bool,
+}
+
+/// Requirements on one package that no single version can satisfy together.
+#[derive(Debug, Clone)]
+pub struct PackageConflict {
+    /// PEP 503 normalized package name.
+    pub name: String,
+    pub requirements: Vec<RequirementLine>,
+}
+
+/// Result of checking a set of requirements against each other.
+#[derive(Debug, Clone, Default)]
+pub struct ConflictReport {
+    /// Number of requirements that were examined.
+    pub checked: usize,
+    pub packages: Vec<PackageSummary>,
+    pub conflicts: Vec<PackageConflict>,
+}
+
+impl ConflictReport {
+    /// Groups requirements by package name and records, for each package that
+    /// cannot be satisfied, the requirements involved in the conflict. Requirements
+    /// whose markers can never hold at the same time are not compared.
+    pub fn check(requirements: &[RequirementLine]) -> Self {
+        Self::check_with(requirements, PreReleasePolicy::default())
+    }
+
+    /// Like [`check`](Self::check), counting pre-releases as `policy` says.
+    pub fn check_with(requirements: &[RequirementLine], policy: PreReleasePolicy) -> Self {
+        let mut by_name: BTreeMap<String, Vec<&RequirementLine>> = BTreeMap::new();
+        for requirement in requirements {
+            by_name
+                .entry(requirement.package.canonical_name())
+                .or_default()
+                .push(requirement);
+        }
+
+        let mut packages = Vec::new();
+        let mut conflicts = Vec::new();
+        for (name, lines) in by_name {
+            let allowed = lines
+                .iter()
+                .fold(IntervalSet::full(), |acc, r| acc.intersect(&r.package.specifiers.range()));
+            let specifiers: Vec<&SpecifierSet> = lines.iter().map(|r| &r.package.specifiers).collect();
+            let prereleases = policy.allows(&specifiers);
+            packages.push(PackageSummary {
+                name: name.clone(),
+                requirements: lines.iter().map(|r| (*r).clone()).collect(),
+                allowed: allowed.clone(),
+                prereleases,
+            });
+
+            let mut involved = vec![false; lines.len()];
+            for (i, a) in lines.iter().enumerate() {
+                let own = &a.package.specifiers;
+                if own.excludes_literal(own) || !prerelease::is_satisfiable(&own.range(), policy.allows(&[own])) {
+                    involved[i] = true;
+                }
+                for (j, b) in lines.iter().enumerate().skip(i + 1) {
+                    if a.package.conflicts_under(&b.package, policy) {
+                        involved[i] = true;
+                        involved[j] = true;
+                    }
+                }
+            }
+
+            // Pairwise-compatible requirements can still exclude each other as a whole
+            if !involved.contains(&true) {
+                let markers: Vec<MarkerTree> =
+                    lines.iter().filter_map(|r| r.package.marker.clone()).collect();
+                if !prerelease::is_satisfiable(&allowed, prereleases) && MarkerTree::And(markers).is_satisfiable() {
+                    involved = vec![true; lines.len()];
+                }
+            }
+
+            if involved.contains(&true) {
+                let requirements = lines
+                    .into_iter()
+                    .zip(involved)
+                    .filter(|(_, involved)| *involved)
+                    .map(|(line, _)| line.clone())
+                    .collect();
+                conflicts.push(PackageConflict { name, requirements });
+            }
+        }
+
+        ConflictReport {
+            checked: requirements.len(),
+            packages,
+            conflicts,
+        }
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        !self.conflicts.is_empty()
+    }
+
+    pub fn conflict_for(&self, name: &str) -> Option<&PackageConflict> {
+        self.conflicts.iter().find(|c| c.name == name)
+    }
+}
+
+/// A version that every set in `specifiers` accepts, as text: the one a `===`
+/// clause pins, as only that text matches it, and otherwise a [`witness`] of
+/// the range they allow together.
+pub fn example(specifiers: &[&SpecifierSet], prereleases: bool) -> Option<String> {
+    let joint = SpecifierSet {
+        specifiers: specifiers.iter().flat_map(|s| s.specifiers.iter().cloned()).collect(),
+    };
+    match joint.literal() {
+        Some(_) if joint.excludes_literal(&joint) => None,
+        Some(pinned) => Some(pinned.to_string()),
+        None => witness(&joint.range(), prereleases).map(|v| v.to_string()),
+    }
+}
+
+/// Picks a concrete version inside the range, preferring the lowest bound,
+/// and a final release unless `prereleases` are acceptable. Returns `None`
+/// for an empty range or when no simple candidate falls inside.
+pub fn witness(range: &IntervalSet<Pep440Version>, prereleases: bool) -> Option<Pep440Version> {
+    range.intervals.iter().find_map(|interval| {
+        let written = as_written(interval);
+        let mut candidates = Vec::new();
+        match &written.lower {
+            Bound::Included(v) => candidates.push(v.clone()),
+            // The version right after `v` with one more release segment, e.g. 2.0 -> 2.0.1
+            Bound::Excluded(v) => {
+                let mut release = v.release.clone();
+                release.push(1);
+                candidates.push(Pep440Version {
+                    epoch: v.epoch,
+                    release,
+                    pre: None,
+                    post: None,
+                    dev: None,
+                    local: Vec::new(),
+                });
+            }
+            Bound::Unbounded => candidates.push(Pep440Version::parse("0").ok()?),
+        }
+        // A computed end past the post-releases is no version anyone releases
+        match &written.upper {
+            Bound::Included(v) if v.post != Some(u64::MAX) => candidates.push(v.clone()),
+            _ => {}
+        }
+        candidates.push(prerelease::first_final(interval));
+        candidates
+            .into_iter()
+            .find(|c| interval.contains(c) && (prereleases || !c.is_prerelease()))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::PythonPackage;
+    use crate::specifier::SpecifierSet;
+    use anyhow::Result;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_find_conflicts() -> Result<()> {
+        let requirements: Vec<RequirementLine> = ["django>=4.0", "requests", "Django<3.0", "requests<3"]
+            .iter()
+            .enumerate()
+            .map(|(i, req)| {
+                Ok(RequirementLine {
+                    package: PythonPackage::parse(req)?,
+                    file: PathBuf::from("requirements.txt"),
+                    line: i + 1,
+                    columns: None,
+                    hashes: Vec::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let report = ConflictReport::check(&requirements);
+        assert!(report.has_conflicts());
+        assert_eq!(report.checked, 4);
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].name, "django");
+        let lines: Vec<usize> = report.conflicts[0].requirements.iter().map(|r| r.line).collect();
+        assert_eq!(lines, vec![1, 3]);
+
+        assert_eq!(report.packages.len(), 2);
+        assert_eq!(report.packages[1].name, "requests");
+        assert_eq!(report.packages[1].requirements.len(), 2);
+        assert!(report.conflict_for("requests").is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prerelease_policy() -> Result<()> {
+        let requirements: Vec<RequirementLine> = ["httpx==1.0.*", "httpx<1.0.0rc1", "rich==14.0rc2"]
+            .iter()
+            .map(|req| {
+                Ok(RequirementLine {
+                    package: PythonPackage::parse(req)?,
+                    file: PathBuf::from("requirements.txt"),
+                    line: 1,
+                    columns: None,
+                    hashes: Vec::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        let conflicts = |policy: PreReleasePolicy| -> Vec<String> {
+            let report = ConflictReport::check_with(&requirements, policy);
+            report.conflicts.into_iter().map(|c| c.name).collect()
+        };
+
+        // `rich==14.0rc2` opts in on its own; `httpx==1.0.*` does not
+        assert_eq!(conflicts(PreReleasePolicy::Auto), vec!["httpx"]);
+        assert!(conflicts(PreReleasePolicy::Allow).is_empty());
+        assert_eq!(conflicts(PreReleasePolicy::Deny), vec!["httpx", "rich"]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_witness() -> Result<()> {
+        let pick = |spec: &str| -> Result<Option<String>> {
+            Ok(witness(&SpecifierSet::parse(spec)?.range(), false).map(|v| v.to_string()))
+        };
+        assert_eq!(pick(">=2.0,<3")?, Some("2.0".to_string()));
+        assert_eq!(pick(">2.0,<3")?, Some("2.0.1".to_string()));
+        assert_eq!(pick("<=1.5")?, Some("0".to_string()));
+        assert_eq!(pick(">2.0,<=2.0.0.5")?, Some("2.0.0.5".to_string()));
+        assert_eq!(pick(">1.0,<1.0.0.0.1")?, Some("1.0.0.0.0.1".to_string()));
+        assert_eq!(pick(">=3,<2")?, None);
+        // Pre-releases are only picked when they are acceptable
+        assert_eq!(pick("!=1.0,==1.*")?, Some("1.0.1".to_string()));
+        assert_eq!(pick(">=2.0rc1,<3")?, Some("2.0".to_string()));
+        assert_eq!(pick(">=2.0rc1,<2.0")?, None);
+        let range = SpecifierSet::parse(">=2.0rc1,<3")?.range();
+        assert_eq!(witness(&range, true).map(|v| v.to_string()), Some("2.0rc1".to_string()));
+
+        let literal = |a: &str, b: &str| -> Result<Option<String>> {
+            Ok(example(&[&SpecifierSet::parse(a)?, &SpecifierSet::parse(b)?], false))
+        };
+        assert_eq!(literal("===1.0-custom", "===1.0-CUSTOM")?, Some("1.0-custom".to_string()));
+        assert_eq!(literal("===1.0-custom", "===2.0-custom")?, None);
+        assert_eq!(literal("===1.0-custom", "<1")?, None);
+        assert_eq!(literal("===1.0", "===1.0.0")?, None);
+        assert_eq!(literal("===1.0", ">=0.5")?, Some("1.0".to_string()));
+        assert_eq!(literal(">2.0", "<3")?, Some("2.0.1".to_string()));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,97 @@
+use anyhow::Result;
+use std::fmt;
+
+use crate::package::matches_name;
+use crate::pep440::Pep440Version;
+use crate::pypi::PypiClient;
+
+/// An internal package whose name is also taken on a public index, where an
+/// attacker may publish a release for installers to pick up instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Lookalike {
+    pub name: String,
+    /// The version the file pins, if it pins one.
+    pub pinned: Option<Pep440Version>,
+    /// The newest release on the public index, `None` when it lists the
+    /// name without any release.
+    pub public_latest: Option<Pep440Version>,
+}
+
+impl Lookalike {
+    /// Whether the public index has a release newer than the pin, which pip
+    /// prefers when it searches both indexes; unpinned names always risk it.
+    pub fn outranks(&self) -> bool {
+        match (&self.pinned, &self.public_latest) {
+            (Some(pinned), Some(latest)) => latest > pinned,
+            (None, Some(_)) => true,
+            (_, None) => false,
+        }
+    }
+}
+
+impl fmt::Display for Lookalike {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.public_latest {
+            Some(latest) if self.outranks() => {
+                write!(f, "{} is published on the public index up to {}", self.name, latest)?;
+                match &self.pinned {
+                    Some(pinned) => write!(f, ", newer than the pinned {}", pinned),
+                    None => write!(f, ", and the requirement is not pinned"),
+                }
+            }
+            Some(latest) => write!(f, "{} is published on the public index up to {}", self.name, latest),
+            None => write!(f, "{} is registered on the public index", self.name),
+        }
+    }
+}
+
+/// Whether a package name matches one of the internal name patterns: a
+/// name, or a prefix ending in `*` such as `acme-*`.
+pub fn is_internal(name: &str, patterns: &[String]) -> bool {
+    patterns.iter().any(|pattern| matches_name(pattern, name))
+}
+
+/// Looks up every internal package on the `public` index, returning those
+/// it has, in the order given.
+pub fn check(public: &PypiClient, packages: &[(&str, Option<&Pep440Version>)]) -> Result<Vec<Lookalike>> {
+    let names: Vec<&str> = packages.iter().map(|(name, _)| *name).collect();
+    Ok(packages
+        .iter()
+        .zip(public.published_all(&names)?)
+        .filter_map(|((name, pinned), releases)| {
+            let releases = releases?;
+            Some(Lookalike {
+                name: name.to_string(),
+                pinned: pinned.cloned(),
+                public_latest: releases.iter().rev().find(|r| !r.yanked).map(|r| r.version.clone()),
+            })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookalike() -> anyhow::Result<()> {
+        let patterns = vec!["acme-*".to_string(), "Billing_Core".to_string()];
+        assert!(is_internal("acme_utils", &patterns) && is_internal("billing.core", &patterns));
+        assert!(!is_internal("acmeutils", &patterns) && !is_internal("requests", &patterns));
+
+        let lookalike = |pinned: Option<&str>, latest: Option<&str>| -> anyhow::Result<Lookalike> {
+            Ok(Lookalike {
+                name: "acme-utils".to_string(),
+                pinned: pinned.map(Pep440Version::parse).transpose()?,
+                public_latest: latest.map(Pep440Version::parse).transpose()?,
+            })
+        };
+        let newer = lookalike(Some("1.4.0"), Some("99.0"))?;
+        assert!(newer.outranks());
+        assert_eq!(newer.to_string(), "acme-utils is published on the public index up to 99.0, newer than the pinned 1.4.0");
+        assert!(!lookalike(Some("1.4.0"), Some("0.0.1"))?.outranks());
+        assert!(lookalike(None, Some("0.0.1"))?.outranks());
+        assert_eq!(lookalike(None, None)?.to_string(), "acme-utils is registered on the public index");
+        Ok(())
+    }
+}
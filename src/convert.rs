@@ -0,0 +1,323 @@
+use anyhow::{bail, Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::error::PyhelperError;
+use crate::merge;
+use crate::package::PythonPackage;
+use crate::requirements::{self, RequirementLine};
+use crate::specifier::{Operator, SpecifierSet};
+use crate::{conda, pipfile, pyproject, scan};
+
+/// A package index a dependency file installs from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Index {
+    pub name: Option<String>,
+    pub url: String,
+    /// Whether it replaces PyPI rather than adding to it.
+    pub primary: bool,
+}
+
+/// What a dependency file declares, as far as `convert` carries it over.
+#[derive(Debug, Clone)]
+pub struct Manifest {
+    pub requirements: Vec<RequirementLine>,
+    /// The Python versions the project supports, if the file says.
+    pub python: Option<SpecifierSet>,
+    pub indexes: Vec<Index>,
+}
+
+/// A dependency file format `convert` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Requirements,
+    Pyproject,
+    Pipfile,
+    Conda,
+}
+
+/// A manifest written in another format.
+#[derive(Debug, Clone)]
+pub struct Converted {
+    pub content: String,
+    /// What the format cannot express and was left out.
+    pub dropped: Vec<String>,
+}
+
+/// Reads the requirements of a dependency file, as [`scan::parse_file`]
+/// does, along with its package indexes and supported Python versions. The
+/// `python` and `pip` packages of a conda environment are the interpreter
+/// and its installer, not requirements.
+pub fn read(path: &Path) -> Result<Manifest> {
+    let content = fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let located = |e: anyhow::Error| PyhelperError::parse(path, 1, None, e);
+    let mut requirements = scan::parse_file(path)?;
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let (python, indexes) = match file_name {
+        "pyproject.toml" => (
+            pyproject::requires_python(&content).map_err(located)?,
+            pyproject::indexes(&content).map_err(located)?,
+        ),
+        "Pipfile" => (
+            pipfile::requires_python(&content).map_err(located)?,
+            pipfile::sources(&content).map_err(located)?,
+        ),
+        "environment.yml" | "environment.yaml" => {
+            let python = requirements
+                .iter()
+                .find(|requirement| requirement.package.canonical_name() == "python")
+                .map(|requirement| requirement.package.specifiers.clone());
+            requirements.retain(|requirement| !matches!(requirement.package.canonical_name().as_str(), "python" | "pip"));
+            (python, pip_indexes(conda::index_urls(&content)))
+        }
+        "setup.cfg" | "setup.py" => (None, Vec::new()),
+        _ => (None, pip_indexes(requirements::index_urls(&content))),
+    };
+    Ok(Manifest { requirements, python, indexes })
+}
+
+fn pip_indexes((index_url, extra_index_urls): (Option<String>, Vec<String>)) -> Vec<Index> {
+    let primary = index_url.map(|url| Index { name: None, url, primary: true });
+    let extra = extra_index_urls.into_iter().map(|url| Index { name: None, url, primary: false });
+    primary.into_iter().chain(extra).collect()
+}
+
+/// Writes `manifest` as a dependency file of `format`, for a project called
+/// `name`. Every requirement keeps its specifiers, extras, marker and URL,
+/// and goes to the main dependencies: the groups of the source are not
+/// carried over. `source` names the file in a heading comment.
+pub fn render(manifest: &Manifest, format: Format, name: &str, source: &str) -> Result<Converted> {
+    let mut dropped = Vec::new();
+    let hashed = manifest.requirements.iter().filter(|requirement| !requirement.hashes.is_empty()).count();
+    if hashed > 0 && format != Format::Requirements {
+        dropped.push(format!("the hashes of {} requirement(s), which belong in a lock file", hashed));
+    }
+    let mut out = format!("# Converted by pyhelper from {}\n", source);
+    match format {
+        Format::Requirements => {
+            if let Some(python) = &manifest.python {
+                dropped.push(format!("the supported Python versions ({})", python));
+            }
+            for index in &manifest.indexes {
+                let option = if index.primary { "--index-url" } else { "--extra-index-url" };
+                out.push_str(&format!("{} {}\n", option, index.url));
+            }
+            for requirement in &manifest.requirements {
+                out.push_str(&requirement_line(requirement));
+                out.push('\n');
+            }
+        }
+        Format::Pyproject => {
+            out.push_str(&format!("[project]\nname = {}\nversion = \"0.1.0\"\n", toml_string(name)));
+            if let Some(python) = &manifest.python {
+                out.push_str(&format!("requires-python = {}\n", toml_string(&python.to_string())));
+            }
+            out.push_str("dependencies = [\n");
+            for requirement in &manifest.requirements {
+                out.push_str(&format!("    {},\n", toml_string(&merge::line(&requirement.package))));
+            }
+            out.push_str("]\n");
+            for index in &manifest.indexes {
+                out.push_str("\n[[tool.uv.index]]\n");
+                if let Some(name) = &index.name {
+                    out.push_str(&format!("name = {}\n", toml_string(name)));
+                }
+                out.push_str(&format!("url = {}\n", toml_string(&index.url)));
+                if index.primary {
+                    out.push_str("default = true\n");
+                }
+            }
+        }
+        Format::Pipfile => {
+            // pipenv installs from the first source, PyPI unless another replaces it
+            let mut sources = manifest.indexes.clone();
+            sources.sort_by_key(|index| !index.primary);
+            if !sources.first().is_some_and(|index| index.primary) {
+                sources.insert(0, Index { name: Some("pypi".to_string()), url: "https://pypi.org/simple".to_string(), primary: true });
+            }
+            for index in &sources {
+                let name = index.name.clone().unwrap_or_else(|| host(&index.url));
+                out.push_str(&format!(
+                    "[[source]]\nname = {}\nurl = {}\nverify_ssl = true\n\n",
+                    toml_string(&name),
+                    toml_string(&index.url)
+                ));
+            }
+            out.push_str("[packages]\n");
+            for package in pipfile_packages(&manifest.requirements)? {
+                out.push_str(&format!("{} = {}\n", toml_key(&package.name), pipfile_entry(&package)));
+            }
+            if let Some(python) = &manifest.python {
+                match python.specifiers.as_slice() {
+                    [spec] if spec.op == Operator::Equal && spec.wildcard => {
+                        out.push_str(&format!("\n[requires]\npython_version = {}\n", toml_string(&spec.version.to_string())));
+                    }
+                    [spec] if spec.op == Operator::Equal => {
+                        out.push_str(&format!("\n[requires]\npython_full_version = {}\n", toml_string(&spec.version.to_string())));
+                    }
+                    _ => dropped.push(format!("the supported Python versions ({}), as a Pipfile only requires one version", python)),
+                }
+            }
+        }
+        Format::Conda => {
+            out.push_str(&format!("name: {}\ndependencies:\n", yaml_string(name)));
+            if let Some(python) = &manifest.python {
+                let spec = match python.specifiers.as_slice() {
+                    [spec] if spec.op == Operator::Equal && spec.wildcard => format!("={}", spec.version),
+                    _ => python.to_string(),
+                };
+                out.push_str(&format!("  - {}\n", yaml_string(&format!("python{}", spec))));
+            }
+            out.push_str("  - pip\n  - pip:\n");
+            for index in &manifest.indexes {
+                let option = if index.primary { "--index-url" } else { "--extra-index-url" };
+                out.push_str(&format!("      - {} {}\n", option, index.url));
+            }
+            for requirement in &manifest.requirements {
+                out.push_str(&format!("      - {}\n", yaml_string(&merge::line(&requirement.package))));
+            }
+        }
+    }
+    Ok(Converted { content: out, dropped })
+}
+
+/// A requirements-file line for `requirement`, with a continuation line per
+/// `--hash` option.
+fn requirement_line(requirement: &RequirementLine) -> String {
+    let mut line = merge::line(&requirement.package);
+    for hash in &requirement.hashes {
+        line.push_str(&format!(" \\\n    --hash={}", hash));
+    }
+    line
+}
+
+/// One requirement per package, as a Pipfile has one entry per name.
+/// Requirements on the same package are combined when they share a marker.
+fn pipfile_packages(requirements: &[RequirementLine]) -> Result<Vec<PythonPackage>> {
+    let mut by_name: BTreeMap<String, Vec<PythonPackage>> = BTreeMap::new();
+    for requirement in requirements {
+        by_name.entry(requirement.package.canonical_name()).or_default().push(requirement.package.clone());
+    }
+    let marker = |package: &PythonPackage| package.marker.as_ref().map(|marker| marker.to_string());
+    by_name
+        .into_values()
+        .map(|packages| match packages.as_slice() {
+            [package] => Ok(package.clone()),
+            [first, ..] if packages.iter().all(|package| marker(package) == marker(first)) => merge::intersect(&packages),
+            _ => bail!(PyhelperError::Invalid(format!(
+                "A Pipfile has one entry per package, but {} is required under different markers",
+                packages[0].name
+            ))),
+        })
+        .collect()
+}
+
+/// The value of a `[packages]` entry: the specifiers alone, or a table with
+/// the extras, the marker and where a direct reference points.
+fn pipfile_entry(package: &PythonPackage) -> String {
+    if package.extras.is_empty() && package.marker.is_none() && package.url.is_none() {
+        return toml_string(&package.version_spec());
+    }
+    let mut fields = Vec::new();
+    match &package.url {
+        Some(url) => match url.strip_prefix("git+") {
+            // A VCS reference names its revision after the last `@` of the path
+            Some(git) => match git.rsplit_once('@').filter(|(repository, _)| repository.contains("://")) {
+                Some((repository, revision)) if !revision.contains('/') => {
+                    fields.push(format!("git = {}", toml_string(repository)));
+                    fields.push(format!("ref = {}", toml_string(revision)));
+                }
+                _ => fields.push(format!("git = {}", toml_string(git))),
+            },
+            None => fields.push(format!("file = {}", toml_string(url))),
+        },
+        None => fields.push(format!("version = {}", toml_string(&package.version_spec()))),
+    }
+    if !package.extras.is_empty() {
+        let extras: Vec<String> = package.extras.iter().map(|extra| toml_string(extra)).collect();
+        fields.push(format!("extras = [{}]", extras.join(", ")));
+    }
+    if let Some(marker) = &package.marker {
+        fields.push(format!("markers = {}", toml_string(&marker.to_string())));
+    }
+    format!("{{{}}}", fields.join(", "))
+}
+
+/// The host of an index URL, without credentials, to name a Pipfile source.
+fn host(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let authority = rest.split('/').next().unwrap_or(rest);
+    authority.rsplit('@').next().unwrap_or(authority).to_string()
+}
+
+fn toml_string(value: &str) -> String {
+    toml::Value::String(value.to_string()).to_string()
+}
+
+fn toml_key(key: &str) -> String {
+    if !key.is_empty() && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return key.to_string();
+    }
+    toml_string(key)
+}
+
+/// `value` as a YAML scalar, quoted only when it would not read back as
+/// itself.
+fn yaml_string(value: &str) -> String {
+    let indicator = value.starts_with(|c: char| "[]{}!&*|>'\"%@`#,?:".contains(c));
+    if indicator || value.contains(": ") || value.contains(" #") || value.ends_with(':') {
+        return format!("'{}'", value.replace('\'', "''"));
+    }
+    value.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() -> Result<()> {
+        let path = Path::new("requirements.txt");
+        let content = "--index-url https://nexus.example.com/simple\n--extra-index-url https://pypi.org/simple\n\
+                       requests[socks]>=2.31,<3 ; python_version < \"3.12\"\n\
+                       six==1.16.0 --hash=sha256:abc\nzope.interface\n\
+                       pkg @ git+https://github.com/org/pkg.git@v1.0\n";
+        let manifest = Manifest {
+            requirements: requirements::parse_str(content, path)?,
+            python: Some(SpecifierSet::parse("==3.11.*")?),
+            indexes: pip_indexes(requirements::index_urls(content)),
+        };
+
+        let pyproject = render(&manifest, Format::Pyproject, "demo", "requirements.txt")?;
+        let dir = std::env::temp_dir().join(format!("pyhelper-convert-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("pyproject.toml"), &pyproject.content)?;
+        let converted = read(&dir.join("pyproject.toml"))?;
+        let lines: Vec<String> = converted.requirements.iter().map(|r| merge::line(&r.package)).collect();
+        let original: Vec<String> = manifest.requirements.iter().map(|r| merge::line(&r.package)).collect();
+        assert_eq!(lines, original);
+        assert_eq!(converted.python.map(|python| python.to_string()).as_deref(), Some("==3.11.*"));
+        assert_eq!(converted.indexes, manifest.indexes);
+        assert_eq!(pyproject.dropped.len(), 1);
+
+        let pipfile = render(&manifest, Format::Pipfile, "demo", "requirements.txt")?.content;
+        assert!(pipfile.contains("[[source]]\nname = \"nexus.example.com\"\nurl = \"https://nexus.example.com/simple\""));
+        assert!(pipfile.contains("requests = {version = \">=2.31,<3\", extras = [\"socks\"], markers = 'python_version < \"3.12\"'}"));
+        assert!(pipfile.contains("pkg = {git = \"https://github.com/org/pkg.git\", ref = \"v1.0\"}"));
+        assert!(pipfile.contains("\"zope.interface\" = \"*\"\n"));
+        assert!(pipfile.ends_with("[requires]\npython_version = \"3.11\"\n"));
+
+        let conda = render(&manifest, Format::Conda, "demo", "requirements.txt")?.content;
+        fs::write(dir.join("environment.yml"), &conda)?;
+        let converted = read(&dir.join("environment.yml"))?;
+        assert_eq!(converted.requirements.len(), 3);
+        assert_eq!(converted.indexes, manifest.indexes);
+        assert!(conda.contains("  - python=3.11\n") && conda.contains("      - six==1.16.0\n"));
+
+        let requirements = render(&manifest, Format::Requirements, "demo", "requirements.txt")?;
+        assert!(requirements.content.contains("\nsix==1.16.0 \\\n    --hash=sha256:abc\n"));
+        assert_eq!(requirements.dropped, ["the supported Python versions (==3.11.*)"]);
+        Ok(())
+    }
+}
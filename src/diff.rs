@@ -0,0 +1,176 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::pep440::Pep440Version;
+use crate::range::IntervalSet;
+use crate::requirements::RequirementLine;
+
+/// How the requirements on a package changed between two files.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstraintChange {
+    Added,
+    Removed,
+    /// The new requirements allow only some of the versions allowed before.
+    Tightened,
+    /// The new requirements allow every version allowed before, and more.
+    Loosened,
+    /// Each side allows versions the other does not.
+    Shifted,
+    /// The same versions are allowed, but extras or markers changed.
+    Changed,
+}
+
+impl ConstraintChange {
+    /// Whether the new file can reject an installation the old one accepted:
+    /// versions that are no longer allowed, or a package that is gone.
+    pub fn is_breaking(self) -> bool {
+        matches!(self, ConstraintChange::Removed | ConstraintChange::Tightened | ConstraintChange::Shifted)
+    }
+}
+
+impl fmt::Display for ConstraintChange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            ConstraintChange::Added => "added",
+            ConstraintChange::Removed => "removed",
+            ConstraintChange::Tightened => "tightened",
+            ConstraintChange::Loosened => "loosened",
+            ConstraintChange::Shifted => "shifted",
+            ConstraintChange::Changed => "changed",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// One package whose requirements differ between the old and new files.
+#[derive(Debug, Clone)]
+pub struct ConstraintDiff {
+    /// PEP 503 normalized package name.
+    pub name: String,
+    pub old: Vec<RequirementLine>,
+    pub new: Vec<RequirementLine>,
+    pub kind: ConstraintChange,
+}
+
+impl ConstraintDiff {
+    /// The versions the old requirements allow together, ignoring markers.
+    pub fn old_allowed(&self) -> IntervalSet<Pep440Version> {
+        allowed(&self.old)
+    }
+
+    /// The versions the new requirements allow together, ignoring markers.
+    pub fn new_allowed(&self) -> IntervalSet<Pep440Version> {
+        allowed(&self.new)
+    }
+}
+
+/// Compares the requirements of two files package by package, comparing the
+/// versions each side allows rather than how the specifiers are spelled.
+pub fn diff(old: &[RequirementLine], new: &[RequirementLine]) -> Vec<ConstraintDiff> {
+    let group = |lines: &[RequirementLine]| {
+        let mut by_name: BTreeMap<String, Vec<RequirementLine>> = BTreeMap::new();
+        for line in lines {
+            by_name.entry(line.package.canonical_name()).or_default().push(line.clone());
+        }
+        by_name
+    };
+    let mut old = group(old);
+    let new = group(new);
+
+    let mut diffs = Vec::new();
+    for (name, after) in new {
+        let before = old.remove(&name).unwrap_or_default();
+        let kind = if before.is_empty() {
+            ConstraintChange::Added
+        } else {
+            let (was, now) = (allowed(&before), allowed(&after));
+            match (is_subset(&now, &was), is_subset(&was, &now)) {
+                (true, true) if details(&before) == details(&after) => continue,
+                (true, true) => ConstraintChange::Changed,
+                (true, false) => ConstraintChange::Tightened,
+                (false, true) => ConstraintChange::Loosened,
+                (false, false) => ConstraintChange::Shifted,
+            }
+        };
+        diffs.push(ConstraintDiff {
+            name,
+            old: before,
+            new: after,
+            kind,
+        });
+    }
+    diffs.extend(old.into_iter().map(|(name, before)| ConstraintDiff {
+        name,
+        old: before,
+        new: Vec::new(),
+        kind: ConstraintChange::Removed,
+    }));
+    diffs.sort_by(|a, b| a.name.cmp(&b.name));
+    diffs
+}
+
+pub(crate) fn allowed(lines: &[RequirementLine]) -> IntervalSet<Pep440Version> {
+    lines
+        .iter()
+        .fold(IntervalSet::full(), |acc, line| acc.intersect(&line.package.specifiers.range()))
+}
+
+pub(crate) fn is_subset(a: &IntervalSet<Pep440Version>, b: &IntervalSet<Pep440Version>) -> bool {
+    a.intersect(&b.complement()).is_empty()
+}
+
+/// The extras and markers of the requirements, in a comparable form.
+fn details(lines: &[RequirementLine]) -> Vec<(Vec<String>, Option<String>)> {
+    let mut details: Vec<(Vec<String>, Option<String>)> = lines
+        .iter()
+        .map(|line| {
+            let mut extras = line.package.extras.clone();
+            extras.sort();
+            (extras, line.package.marker.as_ref().map(|m| m.to_string()))
+        })
+        .collect();
+    details.sort();
+    details.dedup();
+    details
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::PythonPackage;
+    use std::path::PathBuf;
+
+    fn lines(inputs: &[&str]) -> Vec<RequirementLine> {
+        inputs
+            .iter()
+            .enumerate()
+            .map(|(i, input)| RequirementLine {
+                package: PythonPackage::parse(input).unwrap(),
+                file: PathBuf::from("requirements.txt"),
+                line: i + 1,
+                columns: None,
+                hashes: Vec::new(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = lines(&["requests>=2.0", "flask<3", "django>=3.2,<4", "six", "attrs>=22", "rich"]);
+        let new = lines(&["requests>=2.28,<3", "Flask<4", "django>=4.2,<5", "attrs >= 22.0", "rich[jupyter]", "httpx"]);
+        let found: Vec<(String, ConstraintChange)> = diff(&old, &new).into_iter().map(|d| (d.name, d.kind)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("django".to_string(), ConstraintChange::Shifted),
+                ("flask".to_string(), ConstraintChange::Loosened),
+                ("httpx".to_string(), ConstraintChange::Added),
+                ("requests".to_string(), ConstraintChange::Tightened),
+                ("rich".to_string(), ConstraintChange::Changed),
+                ("six".to_string(), ConstraintChange::Removed),
+            ]
+        );
+        assert!(ConstraintChange::Shifted.is_breaking());
+        assert!(!ConstraintChange::Loosened.is_breaking());
+    }
+}
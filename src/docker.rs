@@ -0,0 +1,611 @@
+use anyhow::{anyhow, bail, Context, Result};
+use flate2::read::GzDecoder;
+#[cfg(feature = "network")]
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+#[cfg(feature = "network")]
+use std::sync::OnceLock;
+use tracing::instrument;
+
+use crate::audit::Audited;
+use crate::env::{parse_metadata, Environment, InstalledDistribution};
+use crate::error::PyhelperError;
+#[cfg(feature = "network")]
+use crate::fetch::{Fetcher, NetworkOptions};
+use crate::marker::MarkerEnvironment;
+
+/// The registry of image references without one, such as `python:3.12`.
+pub const DEFAULT_REGISTRY: &str = "registry-1.docker.io";
+
+/// The platform picked from a multi-platform image by default.
+pub const DEFAULT_PLATFORM: &str = "linux/amd64";
+
+/// Entries of an archive up to this size are kept in memory, as they may be
+/// the manifests and configuration naming the layers; larger ones are layers.
+const SMALL_ENTRY: u64 = 1 << 20;
+
+#[cfg(feature = "network")]
+/// Every kind of manifest a registry may answer a reference with.
+const MANIFEST_TYPES: &str = "application/vnd.oci.image.index.v1+json, \
+    application/vnd.docker.distribution.manifest.list.v2+json, \
+    application/vnd.oci.image.manifest.v1+json, \
+    application/vnd.docker.distribution.manifest.v2+json";
+
+/// The distributions installed in a container image, read from the
+/// `.dist-info/METADATA` files its layers leave in place.
+#[derive(Debug, Clone)]
+pub struct Image {
+    /// The reference pulled or the archive read.
+    pub source: String,
+    /// `os/architecture`, with a variant such as `v8` when the image has one.
+    pub platform: String,
+    /// The site-packages directories of the image, as absolute paths within
+    /// it. Markers are known for Linux images with a single Python version.
+    pub environment: Environment,
+    /// Every distribution, with its `.dist-info` path within the image.
+    pub distributions: Vec<InstalledDistribution>,
+    /// The line of the `Version` header of each distribution's METADATA.
+    version_lines: Vec<usize>,
+}
+
+impl Image {
+    /// The installed versions, located at the `Version` header of their METADATA.
+    pub fn audited(&self) -> Vec<Audited> {
+        self.distributions
+            .iter()
+            .zip(&self.version_lines)
+            .map(|(distribution, line)| Audited {
+                name: distribution.name.clone(),
+                version: distribution.version.clone(),
+                file: distribution.path.join("METADATA"),
+                line: *line,
+                columns: None,
+                resolved: false,
+            })
+            .collect()
+    }
+}
+
+/// A reference such as `python:3.12-slim`, `ghcr.io/acme/app:1.4` or
+/// `localhost:5000/app@sha256:...`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reference {
+    pub registry: String,
+    pub repository: String,
+    pub tag: Option<String>,
+    pub digest: Option<String>,
+}
+
+impl Reference {
+    /// The tag or digest manifests are asked for, `latest` without either.
+    pub fn version(&self) -> &str {
+        self.digest.as_deref().or(self.tag.as_deref()).unwrap_or("latest")
+    }
+}
+
+impl FromStr for Reference {
+    type Err = PyhelperError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || PyhelperError::Invalid(format!("Invalid image reference: {}", s));
+        let (name, digest) = match s.split_once('@') {
+            Some((name, digest)) => (name, Some(digest.to_string())),
+            None => (s, None),
+        };
+        // A colon after the last slash starts a tag, one before it a port
+        let (name, tag) = match name.rfind(':') {
+            Some(colon) if !name[colon..].contains('/') => (&name[..colon], Some(name[colon + 1..].to_string())),
+            _ => (name, None),
+        };
+        let (registry, repository) = match name.split_once('/') {
+            Some((host, path)) if host.contains(['.', ':']) || host == "localhost" => (host.to_string(), path.to_string()),
+            _ if name.contains('/') => (DEFAULT_REGISTRY.to_string(), name.to_string()),
+            // Official images of Docker Hub live under `library/`
+            _ => (DEFAULT_REGISTRY.to_string(), format!("library/{}", name)),
+        };
+        let registry = if registry == "docker.io" { DEFAULT_REGISTRY.to_string() } else { registry };
+        let valid = |part: &str| !part.is_empty() && !part.contains(char::is_whitespace);
+        if !repository.split('/').all(valid) || !tag.as_deref().is_none_or(valid) || !digest.as_deref().is_none_or(valid) {
+            return Err(invalid());
+        }
+        Ok(Reference { registry, repository, tag, digest })
+    }
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.registry, self.repository)?;
+        if let Some(tag) = &self.tag {
+            write!(f, ":{}", tag)?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{}", digest)?;
+        }
+        Ok(())
+    }
+}
+
+/// An image index or manifest, telling apart by which lists it has.
+#[derive(Debug, Deserialize)]
+struct Manifest {
+    /// Of an index, the manifest of each platform.
+    #[serde(default)]
+    manifests: Vec<Descriptor>,
+    config: Option<Descriptor>,
+    #[serde(default)]
+    layers: Vec<Descriptor>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Descriptor {
+    digest: String,
+    platform: Option<Platform>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Platform {
+    os: String,
+    architecture: String,
+    variant: Option<String>,
+}
+
+impl fmt::Display for Platform {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.os, self.architecture)?;
+        if let Some(variant) = &self.variant {
+            write!(f, "/{}", variant)?;
+        }
+        Ok(())
+    }
+}
+
+/// The image configuration, of which only the platform and environment matter.
+#[derive(Debug, Deserialize)]
+struct Config {
+    #[serde(flatten)]
+    platform: Platform,
+    #[serde(default)]
+    config: ContainerConfig,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct ContainerConfig {
+    #[serde(rename = "Env", default)]
+    env: Option<Vec<String>>,
+}
+
+/// An image of the `manifest.json` of a `docker save` archive.
+#[derive(Debug, Deserialize)]
+struct SavedImage {
+    #[serde(rename = "Config")]
+    config: String,
+    #[serde(rename = "Layers")]
+    layers: Vec<String>,
+}
+
+/// What a layer adds of the installed distributions, and what it removes
+/// from the layers below it.
+#[derive(Debug, Clone, Default)]
+struct Layer {
+    /// The content of every METADATA file, by path.
+    metadata: BTreeMap<String, String>,
+    /// Paths deleted by `.wh.` whiteout files.
+    whiteouts: Vec<String>,
+    /// Directories whose content below is hidden by a `.wh..wh..opq` file.
+    opaque: Vec<String>,
+}
+
+/// Reads the archive `docker save` writes, or an OCI image layout archive
+/// such as `skopeo copy ... oci-archive:` writes, picking `platform` from a
+/// multi-platform image.
+#[instrument(skip_all, fields(file = %path.display()))]
+pub fn read_archive(path: &Path, platform: &str) -> Result<Image> {
+    let file = File::open(path).with_context(|| format!("Cannot open image archive: {}", path.display()))?;
+    let mut archive = tar::Archive::new(BufReader::new(file));
+    let mut small: HashMap<String, Vec<u8>> = HashMap::new();
+    let mut large: HashMap<String, Result<Layer>> = HashMap::new();
+    let entries = archive.entries().with_context(|| format!("Cannot read image archive: {}", path.display()))?;
+    for entry in entries {
+        let mut entry = entry.with_context(|| format!("Cannot read image archive: {}", path.display()))?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = normalize(&entry.path()?.to_string_lossy());
+        if entry.size() <= SMALL_ENTRY {
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            small.insert(name, bytes);
+        } else {
+            // Layers are read as they stream by, keeping only what matters
+            large.insert(name, read_layer(BufReader::new(&mut entry)));
+        }
+    }
+    let json = |name: &str| small.get(name).ok_or_else(|| anyhow!("{} is missing from {}", name, path.display()));
+    let (config, names) = if let Some(manifest) = small.get("manifest.json") {
+        let images: Vec<SavedImage> = serde_json::from_slice(manifest).context("Invalid manifest.json")?;
+        let image = match &images[..] {
+            [image] => image,
+            _ => bail!("{} holds {} images; save one at a time", path.display(), images.len()),
+        };
+        (json(&normalize(&image.config))?, image.layers.iter().map(|name| normalize(name)).collect())
+    } else if let Some(index) = small.get("index.json") {
+        let blob = |digest: &str| json(&blob_path(digest)).cloned();
+        let manifest = select(serde_json::from_slice(index).context("Invalid index.json")?, platform, blob)?;
+        let config = manifest.config.ok_or_else(|| anyhow!("The image manifest has no configuration"))?;
+        (json(&blob_path(&config.digest))?, manifest.layers.iter().map(|layer| blob_path(&layer.digest)).collect::<Vec<_>>())
+    } else {
+        bail!("{} is neither a `docker save` archive nor an OCI image layout", path.display());
+    };
+    let layers = names
+        .iter()
+        .map(|name| match (large.get(name), small.get(name)) {
+            (Some(Ok(layer)), _) => Ok(layer.clone()),
+            (Some(Err(e)), _) => Err(anyhow!("Cannot read layer {}: {:#}", name, e)),
+            (None, Some(bytes)) => read_layer(&bytes[..]).with_context(|| format!("Cannot read layer {}", name)),
+            (None, None) => Err(anyhow!("Layer {} is missing from {}", name, path.display())),
+        })
+        .collect::<Result<Vec<Layer>>>()?;
+    image(path.display().to_string(), config, layers)
+}
+
+#[cfg(feature = "network")]
+/// Pulls images from registries speaking the OCI distribution API, such as
+/// Docker Hub, GHCR or a local `registry:2`, to read their layers without
+/// running them. Anonymous tokens are asked for when a registry wants one,
+/// with the credentials of `.netrc` for the token service when it has them.
+pub struct Registry {
+    fetcher: Fetcher,
+}
+
+#[cfg(feature = "network")]
+impl Registry {
+    pub fn new() -> Result<Self> {
+        Registry::with_options(&NetworkOptions::default())
+    }
+
+    pub fn with_options(options: &NetworkOptions) -> Result<Self> {
+        Ok(Registry {
+            fetcher: Fetcher::with_options(1, options)?,
+        })
+    }
+
+    /// Downloads the manifest, configuration and layers of an image, picking
+    /// `platform` from a multi-platform one. Layers are held in memory one
+    /// at a time.
+    #[instrument(skip_all, fields(image = %reference))]
+    pub fn pull(&self, reference: &Reference, platform: &str) -> Result<Image> {
+        let mut session = Session {
+            fetcher: &self.fetcher,
+            reference,
+            token: None,
+        };
+        let root = session.get(&format!("manifests/{}", reference.version()), MANIFEST_TYPES)?;
+        let root: Manifest = serde_json::from_slice(&root).with_context(|| format!("Invalid manifest of {}", reference))?;
+        let manifest = select(root, platform, |digest| session.get(&format!("manifests/{}", digest), MANIFEST_TYPES))?;
+        let config = manifest.config.ok_or_else(|| anyhow!("The manifest of {} has no configuration", reference))?;
+        let config = session.get(&format!("blobs/{}", config.digest), "*/*")?;
+        let progress = self.fetcher.progress();
+        if let Some(progress) = progress {
+            progress.start(manifest.layers.len());
+        }
+        let layers = manifest
+            .layers
+            .iter()
+            .map(|layer| {
+                let blob = session.get(&format!("blobs/{}", layer.digest), "*/*")?;
+                if let Some(progress) = progress {
+                    progress.advance(&layer.digest);
+                }
+                read_layer(&blob[..]).with_context(|| format!("Cannot read layer {}", layer.digest))
+            })
+            .collect::<Result<Vec<Layer>>>();
+        if let Some(progress) = progress {
+            progress.finish();
+        }
+        image(reference.to_string(), &config, layers?)
+    }
+}
+
+#[cfg(feature = "network")]
+/// The requests of one pull, sharing the token the registry handed out.
+struct Session<'a> {
+    fetcher: &'a Fetcher,
+    reference: &'a Reference,
+    token: Option<String>,
+}
+
+#[cfg(feature = "network")]
+impl Session<'_> {
+    /// Fetches `path` under the repository, first asking for a token when
+    /// the registry answers with a bearer challenge.
+    fn get(&mut self, path: &str, accept: &str) -> Result<Vec<u8>> {
+        let reference = self.reference;
+        let local = ["localhost", "127.0.0.1", "[::1]"].iter().any(|host| reference.registry.split(':').next() == Some(*host));
+        let scheme = if local { "http" } else { "https" };
+        let url = format!("{}://{}/v2/{}/{}", scheme, reference.registry, reference.repository, path);
+        loop {
+            let bearer = self.token.as_ref().map(|token| format!("Bearer {}", token));
+            let mut headers = vec![("Accept", accept)];
+            if let Some(bearer) = &bearer {
+                headers.push(("Authorization", bearer));
+            }
+            let response = self.fetcher.get_response(&url, &headers)?;
+            match response.status {
+                200..=299 => return Ok(response.body),
+                401 if self.token.is_none() => {
+                    let challenge = response.headers.get("www-authenticate").map(String::as_str).unwrap_or_default();
+                    self.token = Some(self.authorize(challenge)?);
+                }
+                401 | 403 => bail!("{} refused access to {}: give credentials for its token service in ~/.netrc", reference.registry, reference),
+                404 => bail!("{} not found on {}", reference, reference.registry),
+                status => bail!("Cannot fetch {}: status {}", url, status),
+            }
+        }
+    }
+
+    /// Asks the token service named by a `Bearer realm=...` challenge for a token.
+    fn authorize(&self, challenge: &str) -> Result<String> {
+        static PARAMETER: OnceLock<Regex> = OnceLock::new();
+        let parameter = PARAMETER.get_or_init(|| Regex::new(r#"(\w+)="([^"]*)""#).unwrap());
+        let Some(parameters) = challenge.strip_prefix("Bearer ").or_else(|| challenge.strip_prefix("bearer ")) else {
+            bail!("{} wants credentials: give them in ~/.netrc", self.reference.registry);
+        };
+        let parameters: HashMap<&str, &str> = parameter
+            .captures_iter(parameters)
+            .map(|c| (c.get(1).unwrap().as_str(), c.get(2).unwrap().as_str()))
+            .collect();
+        let realm = parameters.get("realm").ok_or_else(|| anyhow!("Invalid authentication challenge: {}", challenge))?;
+        let scope = format!("repository:{}:pull", self.reference.repository);
+        let mut url = format!("{}?scope={}", realm, parameters.get("scope").copied().unwrap_or(&scope));
+        if let Some(service) = parameters.get("service") {
+            url.push_str(&format!("&service={}", service));
+        }
+        let response = self.fetcher.get_response(&url, &[("Accept", "application/json")])?;
+        if !(200..300).contains(&response.status) {
+            bail!("The token service {} answered {}: give its credentials in ~/.netrc", realm, response.status);
+        }
+        #[derive(Deserialize)]
+        struct Token {
+            token: Option<String>,
+            access_token: Option<String>,
+        }
+        let token: Token = serde_json::from_slice(&response.body).with_context(|| format!("Invalid token from {}", realm))?;
+        token.token.or(token.access_token).ok_or_else(|| anyhow!("No token in the answer of {}", realm))
+    }
+}
+
+/// Follows an index, and any index it lists, down to the manifest of
+/// `platform`, fetching each with `get` by digest.
+fn select(mut manifest: Manifest, platform: &str, mut get: impl FnMut(&str) -> Result<Vec<u8>>) -> Result<Manifest> {
+    while !manifest.manifests.is_empty() {
+        let wanted: Vec<&str> = platform.split('/').collect();
+        let matches = |p: &Platform| {
+            let variant = p.variant.as_deref();
+            wanted.len() >= 2 && p.os == wanted[0] && p.architecture == wanted[1] && wanted.get(2).is_none_or(|v| variant == Some(*v))
+        };
+        // An index without platforms, as in a saved single-platform image, has one manifest
+        let chosen = match &manifest.manifests[..] {
+            [only] if only.platform.is_none() => only,
+            manifests => manifests.iter().find(|d| d.platform.as_ref().is_some_and(matches)).ok_or_else(|| {
+                let available: BTreeSet<String> = manifests.iter().filter_map(|d| d.platform.as_ref()).map(|p| p.to_string()).collect();
+                let available: Vec<String> = available.into_iter().collect();
+                anyhow!("The image has no {} variant; it has {}", platform, available.join(", "))
+            })?,
+        };
+        let bytes = get(&chosen.digest)?;
+        manifest = serde_json::from_slice(&bytes).with_context(|| format!("Invalid manifest {}", chosen.digest))?;
+    }
+    Ok(manifest)
+}
+
+/// Where an OCI image layout keeps the blob of a digest.
+fn blob_path(digest: &str) -> String {
+    format!("blobs/{}", digest.replacen(':', "/", 1))
+}
+
+/// A path within a tar archive, without its leading `./` or `/`.
+fn normalize(path: &str) -> String {
+    path.trim_start_matches("./").trim_start_matches('/').to_string()
+}
+
+/// Reads the METADATA files and whiteouts of a layer, a tar archive either
+/// uncompressed or gzipped.
+fn read_layer(mut reader: impl BufRead) -> Result<Layer> {
+    let magic = reader.fill_buf()?;
+    if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        bail!("zstd-compressed layers are not supported");
+    }
+    let reader: Box<dyn Read + '_> = if magic.starts_with(&[0x1f, 0x8b]) { Box::new(GzDecoder::new(reader)) } else { Box::new(reader) };
+    let mut layer = Layer::default();
+    for entry in tar::Archive::new(reader).entries()? {
+        let mut entry = entry?;
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let path = normalize(&entry.path()?.to_string_lossy());
+        let (dir, name) = path.rsplit_once('/').unwrap_or(("", &path));
+        if name == ".wh..wh..opq" {
+            layer.opaque.push(dir.to_string());
+        } else if let Some(hidden) = name.strip_prefix(".wh.") {
+            layer.whiteouts.push(if dir.is_empty() { hidden.to_string() } else { format!("{}/{}", dir, hidden) });
+        } else if is_metadata(&path) {
+            let mut content = String::new();
+            entry.read_to_string(&mut content).with_context(|| format!("Cannot read {}", path))?;
+            layer.metadata.insert(path, content);
+        }
+    }
+    Ok(layer)
+}
+
+/// Whether a path is the METADATA of a distribution in a site-packages (or
+/// Debian's dist-packages) directory.
+fn is_metadata(path: &str) -> bool {
+    let parts: Vec<&str> = path.rsplitn(4, '/').collect();
+    matches!(parts[..], ["METADATA", dist_info, "site-packages" | "dist-packages", ..] if dist_info.ends_with(".dist-info"))
+}
+
+/// Applies the layers in order and reads the distributions left.
+fn image(source: String, config: &[u8], layers: Vec<Layer>) -> Result<Image> {
+    let config: Config = serde_json::from_slice(config).context("Invalid image configuration")?;
+    let mut files: BTreeMap<String, String> = BTreeMap::new();
+    for layer in layers {
+        // Whiteouts only hide what the layers below added
+        let hidden = |path: &str| {
+            let below = |dir: &str| path.strip_prefix(dir).is_some_and(|rest| rest.starts_with('/'));
+            layer.whiteouts.iter().any(|w| path == w || below(w)) || layer.opaque.iter().any(|dir| dir.is_empty() || below(dir))
+        };
+        files.retain(|path, _| !hidden(path));
+        files.extend(layer.metadata);
+    }
+
+    let mut distributions = Vec::new();
+    let mut version_lines = Vec::new();
+    let mut site_packages: BTreeSet<PathBuf> = BTreeSet::new();
+    for (path, content) in &files {
+        let dist_info = Path::new("/").join(path.trim_end_matches("/METADATA"));
+        let distribution = parse_metadata(content, &dist_info).map_err(|e| anyhow!("/{}: {}", path, e))?;
+        if let Some(dir) = dist_info.parent() {
+            site_packages.insert(dir.to_path_buf());
+        }
+        let line = content.lines().position(|line| line.starts_with("Version:")).map_or(1, |i| i + 1);
+        distributions.push(distribution);
+        version_lines.push(line);
+    }
+    let site_packages: Vec<PathBuf> = site_packages.into_iter().collect();
+    let env = config.config.env.unwrap_or_default();
+    Ok(Image {
+        source,
+        platform: config.platform.to_string(),
+        environment: Environment {
+            markers: markers(&config.platform, &env, &site_packages),
+            site_packages,
+        },
+        distributions,
+        version_lines,
+    })
+}
+
+/// The marker values of a Linux image whose site-packages directories are
+/// all of one Python version, such as `lib/python3.12/site-packages`. The
+/// full version is the `PYTHON_VERSION` variable of the official images,
+/// when set.
+fn markers(platform: &Platform, env: &[String], site_packages: &[PathBuf]) -> Option<MarkerEnvironment> {
+    if platform.os != "linux" {
+        return None;
+    }
+    let versions: BTreeSet<&str> = site_packages
+        .iter()
+        .flat_map(|dir| dir.iter())
+        .filter_map(|part| part.to_str()?.strip_prefix("python"))
+        .filter(|version| version.contains('.'))
+        .collect();
+    let version = match versions.into_iter().collect::<Vec<_>>()[..] {
+        [version] => version,
+        _ => return None,
+    };
+    let full = env
+        .iter()
+        .filter_map(|variable| variable.strip_prefix("PYTHON_VERSION="))
+        .find(|full| full.strip_prefix(version).is_some_and(|rest| rest.is_empty() || rest.starts_with('.')))
+        .unwrap_or(version);
+    let machine = match platform.architecture.as_str() {
+        "amd64" => "x86_64",
+        "arm64" => "aarch64",
+        "386" => "i686",
+        "arm" => "armv7l",
+        other => other,
+    };
+    let values = [
+        ("implementation_name", "cpython"),
+        ("os_name", "posix"),
+        ("platform_machine", machine),
+        ("platform_python_implementation", "CPython"),
+        ("platform_system", "Linux"),
+        ("python_full_version", full),
+        ("python_version", version),
+        ("sys_platform", "linux"),
+    ];
+    Some(MarkerEnvironment::new(values.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_reference() -> Result<()> {
+        let reference: Reference = "python:3.12-slim".parse()?;
+        assert_eq!((reference.registry.as_str(), reference.repository.as_str(), reference.version()), (DEFAULT_REGISTRY, "library/python", "3.12-slim"));
+        let reference: Reference = "localhost:5000/acme/app@sha256:abc".parse()?;
+        assert_eq!((reference.registry.as_str(), reference.repository.as_str(), reference.tag.as_deref()), ("localhost:5000", "acme/app", None));
+        assert_eq!(reference.to_string(), "localhost:5000/acme/app@sha256:abc");
+        assert_eq!("ghcr.io/acme/app".parse::<Reference>()?.version(), "latest");
+        assert!("app:".parse::<Reference>().is_err());
+        Ok(())
+    }
+
+    /// A tar archive of `(path, content)` files.
+    fn tar(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut builder = tar::Builder::new(Vec::new());
+        for (path, content) in files {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(content.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, path, *content).unwrap();
+        }
+        builder.into_inner().unwrap()
+    }
+
+    #[test]
+    fn test_read_archive() -> Result<()> {
+        let site = "usr/local/lib/python3.12/site-packages";
+        let metadata = |name: &str, version: &str, requires: &str| {
+            format!("Metadata-Version: 2.1\nName: {}\nVersion: {}\n{}\nBody\n", name, version, requires).into_bytes()
+        };
+        let base = tar(&[
+            (&format!("{}/django-4.2.0.dist-info/METADATA", site), &metadata("Django", "4.2.0", "Requires-Dist: sqlparse>=0.3.1")),
+            (&format!("{}/six-1.16.0.dist-info/METADATA", site), &metadata("six", "1.16.0", "")),
+            ("usr/share/doc/README", b"not a distribution"),
+        ]);
+        let mut gzipped = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        std::io::Write::write_all(
+            &mut gzipped,
+            &tar(&[
+                (&format!("{}/.wh.six-1.16.0.dist-info", site), b""),
+                (&format!("./{}/sqlparse-0.2.0.dist-info/METADATA", site), &metadata("sqlparse", "0.2.0", "")),
+            ]),
+        )?;
+        let config = br#"{"architecture":"arm64","os":"linux","variant":"v8","config":{"Env":["PYTHON_VERSION=3.12.3"]}}"#;
+        let archive = tar(&[
+            ("manifest.json", br#"[{"Config":"config.json","RepoTags":["app:1"],"Layers":["base/layer.tar","top/layer.tar"]}]"#),
+            ("config.json", config),
+            ("base/layer.tar", &base),
+            ("top/layer.tar", &gzipped.finish()?),
+        ]);
+        let dir = std::env::temp_dir().join(format!("pyhelper-docker-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("app.tar");
+        fs::write(&path, archive)?;
+
+        let image = read_archive(&path, DEFAULT_PLATFORM)?;
+        fs::remove_dir_all(&dir)?;
+        let names: Vec<(&str, String)> = image.distributions.iter().map(|d| (d.name.as_str(), d.version.to_string())).collect();
+        assert_eq!(names, [("Django", "4.2.0".to_string()), ("sqlparse", "0.2.0".to_string())]);
+        assert_eq!(image.platform, "linux/arm64/v8");
+        assert_eq!(image.environment.site_packages, [PathBuf::from("/usr/local/lib/python3.12/site-packages")]);
+        let markers = image.environment.markers.as_ref().unwrap();
+        assert_eq!((markers.get("python_full_version"), markers.get("platform_machine")), ("3.12.3", "aarch64"));
+        let audited = image.audited();
+        assert_eq!((audited[0].file.display().to_string(), audited[0].line), (format!("/{}/django-4.2.0.dist-info/METADATA", site), 3));
+        assert_eq!(crate::env::check(&image.distributions, Some(markers)).len(), 1);
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use anyhow::{Context, Result};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::freeze::pinned_version;
+use crate::lock::{self, LockIssue, LockReport, LockedPackage};
+use crate::package::canonicalize_name;
+use crate::requirements::{self, RequirementLine};
+use crate::{pipfile, scan};
+
+/// Manifests paired with every lock file of their directory. A `*.in` file
+/// is only paired with the `*.txt` file compiled from it.
+pub const MANIFEST_NAMES: &[&str] =
+    &["pyproject.toml", "Pipfile", "setup.cfg", "setup.py", "environment.yml", "environment.yaml"];
+
+/// How a lock file disagrees with a manifest.
+#[derive(Debug)]
+pub struct Drift {
+    pub manifest: PathBuf,
+    pub lock: PathBuf,
+    /// Number of declared requirements that were examined.
+    pub checked: usize,
+    /// Declared requirements missing from the lock file or locked outside
+    /// their range.
+    pub issues: Vec<LockIssue>,
+    /// Locked packages no declared requirement pulls in, or `None` for lock
+    /// files that do not record dependencies, such as Pipfile.lock.
+    pub undeclared: Option<Vec<LockedPackage>>,
+}
+
+impl Drift {
+    pub fn has_drift(&self) -> bool {
+        !self.issues.is_empty() || self.undeclared.as_ref().is_some_and(|undeclared| !undeclared.is_empty())
+    }
+}
+
+/// Pairs the manifests of `dir` with its lock files: poetry.lock, uv.lock,
+/// pdm.lock, Pipfile.lock and `*.txt` files pinning every requirement with
+/// `==`, as pip-compile and `pip freeze` write them.
+pub fn find_pairs(dir: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let entries = fs::read_dir(dir).with_context(|| format!("Cannot read directory: {}", dir.display()))?;
+    let mut files: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok())
+        // Entries of `.` are shown without the `./` prefix
+        .map(|entry| if dir == Path::new(".") { PathBuf::from(entry.file_name()) } else { entry.path() })
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+
+    let name = |path: &Path| path.file_name().and_then(|name| name.to_str()).unwrap_or_default().to_string();
+    let mut manifests = Vec::new();
+    let mut locks = Vec::new();
+    for path in files {
+        let file_name = name(&path);
+        if MANIFEST_NAMES.contains(&file_name.as_str()) || file_name.ends_with(".in") {
+            manifests.push(path);
+        } else if lock::LOCK_FILE_NAMES.contains(&file_name.as_str())
+            || file_name == "Pipfile.lock"
+            || (file_name.ends_with(".txt") && is_compiled(&path))
+        {
+            locks.push(path);
+        }
+    }
+
+    let compiled_from = |lock: &Path| Some(lock.with_extension("in")).filter(|source| manifests.contains(source));
+    let mut pairs = Vec::new();
+    for manifest in &manifests {
+        for lock in &locks {
+            let paired = match compiled_from(lock) {
+                Some(source) => &source == manifest,
+                None => !name(manifest).ends_with(".in"),
+            };
+            if paired {
+                pairs.push((manifest.clone(), lock.clone()));
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+/// Whether every requirement of a requirements file is pinned with `==`.
+fn is_compiled(path: &Path) -> bool {
+    requirements::parse_file(path)
+        .is_ok_and(|lines| !lines.is_empty() && lines.iter().all(|line| pinned_version(line).is_some()))
+}
+
+/// The packages pinned by a lock file of any supported kind. Unpinned
+/// requirements of a `*.txt` file are left out.
+pub fn read_lock(path: &Path) -> Result<Vec<LockedPackage>> {
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    if lock::LOCK_FILE_NAMES.contains(&file_name) {
+        return lock::parse_file(path);
+    }
+    let lines = if file_name == "Pipfile.lock" { pipfile::parse_lock(path)? } else { requirements::parse_file(path)? };
+    Ok(lines
+        .iter()
+        .filter_map(|line| {
+            Some(LockedPackage {
+                name: line.package.name.clone(),
+                version: pinned_version(line)?.clone(),
+                file: line.file.clone(),
+                line: line.line,
+                hashes: line.hashes.clone(),
+                dependencies: Vec::new(),
+                local: false,
+            })
+        })
+        .collect())
+}
+
+/// Compares the requirements of `manifest` with the packages `lock` pins.
+/// A locked package counts as declared when a declared requirement depends
+/// on it through the lock file, so transitive dependencies are not reported.
+/// The interpreter a conda environment declares is not a locked package and
+/// is skipped.
+pub fn check(manifest: &Path, lock: &Path) -> Result<Drift> {
+    let mut requirements = scan::parse_file(manifest)?;
+    requirements.retain(|requirement| requirement.package.canonical_name() != "python");
+    let locked = read_lock(lock)?;
+    let file_name = lock.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let undeclared = lock::LOCK_FILE_NAMES.contains(&file_name).then(|| undeclared(&requirements, &locked));
+    Ok(Drift {
+        manifest: manifest.to_path_buf(),
+        lock: lock.to_path_buf(),
+        checked: requirements.len(),
+        issues: LockReport::check(&requirements, &locked).issues,
+        undeclared,
+    })
+}
+
+/// The locked packages neither declared nor reached from a declared
+/// requirement or the locked project itself.
+fn undeclared(requirements: &[RequirementLine], locked: &[LockedPackage]) -> Vec<LockedPackage> {
+    let mut by_name: BTreeMap<String, Vec<&LockedPackage>> = BTreeMap::new();
+    for package in locked {
+        by_name.entry(canonicalize_name(&package.name)).or_default().push(package);
+    }
+    let mut reached: BTreeSet<String> = requirements
+        .iter()
+        .map(|requirement| requirement.package.canonical_name())
+        .chain(locked.iter().filter(|package| package.local).map(|package| canonicalize_name(&package.name)))
+        .collect();
+    let mut pending: Vec<String> = reached.iter().cloned().collect();
+    while let Some(name) = pending.pop() {
+        for package in by_name.get(&name).into_iter().flatten() {
+            for dependency in &package.dependencies {
+                if reached.insert(dependency.clone()) {
+                    pending.push(dependency.clone());
+                }
+            }
+        }
+    }
+    locked
+        .iter()
+        .filter(|package| !reached.contains(&canonicalize_name(&package.name)))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("pyhelper-drift-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        fs::write(
+            dir.join("pyproject.toml"),
+            "[project]\nname = \"demo\"\ndependencies = [\"requests>=2.32\", \"rich\", \"attrs\"]\n",
+        )?;
+        fs::write(
+            dir.join("uv.lock"),
+            r#"version = 1
+
+[[package]]
+name = "demo"
+version = "0.1.0"
+source = { editable = "." }
+dependencies = [{ name = "requests" }, { name = "attrs" }]
+
+[package.dev-dependencies]
+dev = [{ name = "pytest" }]
+
+[[package]]
+name = "requests"
+version = "2.31.0"
+dependencies = [{ name = "idna" }]
+
+[[package]]
+name = "idna"
+version = "3.6"
+
+[[package]]
+name = "attrs"
+version = "23.1.0"
+
+[[package]]
+name = "pytest"
+version = "8.0.0"
+
+[[package]]
+name = "six"
+version = "1.16.0"
+"#,
+        )?;
+        fs::write(dir.join("requirements.in"), "attrs\n")?;
+        fs::write(dir.join("requirements.txt"), "attrs==23.1.0\nsix==1.16.0\n")?;
+        fs::write(dir.join("requirements-dev.txt"), "pytest\n")?;
+
+        let pairs = find_pairs(&dir)?;
+        let name = |path: &Path| path.file_name().unwrap().to_string_lossy().into_owned();
+        let names: Vec<(String, String)> = pairs.iter().map(|(manifest, lock)| (name(manifest), name(lock))).collect();
+        assert_eq!(
+            names,
+            [("pyproject.toml".to_string(), "uv.lock".to_string()), ("requirements.in".to_string(), "requirements.txt".to_string())]
+        );
+
+        let drift = check(&pairs[0].0, &pairs[0].1)?;
+        let issues: Vec<(&str, bool)> = drift
+            .issues
+            .iter()
+            .map(|issue| (issue.requirement().package.name.as_str(), matches!(issue, LockIssue::Missing { .. })))
+            .collect();
+        assert_eq!(issues, [("requests", false), ("rich", true)]);
+        let undeclared: Vec<&str> = drift.undeclared.iter().flatten().map(|package| package.name.as_str()).collect();
+        assert_eq!(undeclared, ["six"]);
+
+        // A compiled requirements file does not record why a package is pinned
+        let compiled = check(&pairs[1].0, &pairs[1].1)?;
+        assert!(compiled.undeclared.is_none() && !compiled.has_drift());
+        Ok(())
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::PyhelperError;
+use crate::marker::MarkerEnvironment;
+use crate::package::{canonicalize_name, PythonPackage};
+use crate::pep440::Pep440Version;
+
+/// Defines `markers()`, the PEP 508 marker values of the running interpreter.
+const MARKERS_SCRIPT: &str = r#"
+import json, os, platform, sys, sysconfig
+
+def version(info):
+    v = "{0.major}.{0.minor}.{0.micro}".format(info)
+    if info.releaselevel != "final":
+        v += info.releaselevel[0] + str(info.serial)
+    return v
+
+def markers():
+    return {
+        "implementation_name": sys.implementation.name,
+        "implementation_version": version(sys.implementation.version),
+        "os_name": os.name,
+        "platform_machine": platform.machine(),
+        "platform_python_implementation": platform.python_implementation(),
+        "platform_release": platform.release(),
+        "platform_system": platform.system(),
+        "platform_version": platform.version(),
+        "python_full_version": platform.python_version(),
+        "python_version": ".".join(platform.python_version_tuple()[:2]),
+        "sys_platform": sys.platform,
+    }
+"#;
+
+/// Asks an interpreter for its site-packages directories and marker values.
+const INTERPRETER_SCRIPT: &str = r#"
+paths = sysconfig.get_paths()
+print(json.dumps({
+    "site_packages": list(dict.fromkeys([paths["purelib"], paths["platlib"]])),
+    "markers": markers(),
+}))
+"#;
+
+/// Asks an interpreter for every distribution `importlib.metadata` finds on
+/// its `sys.path`, wherever it is: in a zipapp, a pex or a Nix store. Of a
+/// name found twice, the first is the one imported.
+const IMPORTLIB_SCRIPT: &str = r#"
+import re
+from importlib import metadata
+
+distributions, seen = [], set()
+for dist in metadata.distributions():
+    name = dist.metadata["Name"]
+    key = re.sub(r"[-_.]+", "-", name or "").lower()
+    if not name or key in seen:
+        continue
+    seen.add(key)
+    path = getattr(dist, "_path", None)
+    distributions.append({
+        "name": name,
+        "version": dist.version,
+        "requires": dist.requires or [],
+        "path": os.path.normpath(str(path if path is not None else dist.locate_file(""))),
+        "location": os.path.normpath(str(dist.locate_file(""))),
+    })
+print(json.dumps({"markers": markers(), "distributions": distributions}))
+"#;
+
+/// An installed distribution, read from its `.dist-info/METADATA`.
+#[derive(Debug, Clone)]
+pub struct InstalledDistribution {
+    pub name: String,
+    pub version: Pep440Version,
+    /// The `Requires-Dist` entries, extras-only ones included.
+    pub requires: Vec<PythonPackage>,
+    /// The `.dist-info` directory the metadata was read from.
+    pub path: PathBuf,
+}
+
+/// Where packages are installed and the marker values of the interpreter using them.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    pub site_packages: Vec<PathBuf>,
+    /// Unknown when the directories were given without an interpreter; requirements
+    /// guarded by a marker are then skipped.
+    pub markers: Option<MarkerEnvironment>,
+}
+
+#[derive(Debug, Deserialize)]
+struct InterpreterInfo {
+    site_packages: Vec<PathBuf>,
+    markers: HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportlibInfo {
+    markers: HashMap<String, String>,
+    distributions: Vec<ImportlibDistribution>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImportlibDistribution {
+    name: String,
+    version: String,
+    requires: Vec<String>,
+    path: PathBuf,
+    /// The `sys.path` entry it was found in.
+    location: PathBuf,
+}
+
+impl Environment {
+    /// Queries a Python interpreter for its environment.
+    pub fn from_interpreter(python: &Path) -> Result<Self> {
+        let output = run_script(&[python.as_os_str().to_os_string()], INTERPRETER_SCRIPT)?;
+        let info: InterpreterInfo = serde_json::from_slice(&output)
+            .with_context(|| format!("Unexpected output from {}", python.display()))?;
+        Ok(Environment {
+            site_packages: info.site_packages,
+            markers: Some(MarkerEnvironment::new(info.markers)),
+        })
+    }
+
+    /// Runs `command`, an interpreter and any arguments preceding them such
+    /// as `nix develop --command python`, to list its distributions through
+    /// `importlib.metadata` instead of reading site-packages directories.
+    /// The environment's directories are the `sys.path` entries holding any.
+    pub fn from_importlib(command: &[OsString]) -> Result<(Self, Vec<InstalledDistribution>)> {
+        let output = run_script(command, IMPORTLIB_SCRIPT)?;
+        parse_importlib(&output).with_context(|| format!("Unexpected output from {}", shown(command)))
+    }
+
+    /// Reads every distribution installed in the environment's site-packages.
+    pub fn distributions(&self) -> Result<Vec<InstalledDistribution>> {
+        let mut distributions = Vec::new();
+        for dir in &self.site_packages {
+            // A venv may not have a separate platlib directory
+            if dir.is_dir() {
+                distributions.extend(scan_site_packages(dir)?);
+            }
+        }
+        Ok(distributions)
+    }
+}
+
+/// Runs `command` with `-c` and the marker functions followed by `script`,
+/// returning what it prints. A pex acts as an interpreter when asked to.
+fn run_script(command: &[OsString], script: &str) -> Result<Vec<u8>> {
+    let (program, args) = command.split_first().ok_or_else(|| anyhow!("No interpreter to run"))?;
+    let output = Command::new(program)
+        .args(args)
+        .args(["-c", &[MARKERS_SCRIPT, script].concat()])
+        .env("PEX_INTERPRETER", "1")
+        .output()
+        .with_context(|| format!("Cannot run Python interpreter: {}", shown(command)))?;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "Python interpreter {} failed: {}",
+            shown(command),
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(output.stdout)
+}
+
+fn shown(command: &[OsString]) -> String {
+    let words: Vec<String> = command.iter().map(|word| word.to_string_lossy().into_owned()).collect();
+    words.join(" ")
+}
+
+fn parse_importlib(output: &[u8]) -> Result<(Environment, Vec<InstalledDistribution>)> {
+    let info: ImportlibInfo = serde_json::from_slice(output)?;
+    let mut site_packages: Vec<PathBuf> = Vec::new();
+    let mut distributions = Vec::new();
+    for distribution in info.distributions {
+        let invalid = |e| anyhow!("{}: {}", distribution.path.display(), e);
+        let requires = distribution.requires.iter().map(|r| PythonPackage::parse(r)).collect::<Result<_, _>>();
+        distributions.push(InstalledDistribution {
+            version: Pep440Version::parse(&distribution.version).map_err(invalid)?,
+            requires: requires.map_err(invalid)?,
+            name: distribution.name,
+            path: distribution.path,
+        });
+        if !site_packages.contains(&distribution.location) {
+            site_packages.push(distribution.location);
+        }
+    }
+    let environment = Environment {
+        site_packages,
+        markers: Some(MarkerEnvironment::new(info.markers)),
+    };
+    Ok((environment, distributions))
+}
+
+/// An installed distribution whose dependency is not satisfied.
+#[derive(Debug, Clone)]
+pub struct EnvIssue {
+    /// Name and version of the distribution declaring the requirement.
+    pub dependent: String,
+    pub dependent_version: Pep440Version,
+    pub requirement: PythonPackage,
+    /// The installed version of the dependency, `None` when it is missing.
+    pub installed: Option<Pep440Version>,
+}
+
+/// Reads the `*.dist-info/METADATA` files of one site-packages directory.
+pub fn scan_site_packages(dir: &Path) -> Result<Vec<InstalledDistribution>> {
+    let entries = fs::read_dir(dir)
+        .with_context(|| format!("Cannot read site-packages directory: {}", dir.display()))?;
+    let mut dist_infos: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "dist-info"))
+        .collect();
+    dist_infos.sort();
+
+    let mut distributions = Vec::new();
+    for path in dist_infos {
+        let metadata = path.join("METADATA");
+        let Ok(content) = fs::read_to_string(&metadata) else {
+            continue;
+        };
+        distributions.push(
+            parse_metadata(&content, &path)
+                .map_err(|e| anyhow!("{}: {}", metadata.display(), e))?,
+        );
+    }
+    Ok(distributions)
+}
+
+/// Parses the header section of a core metadata file.
+pub(crate) fn parse_metadata(content: &str, path: &Path) -> Result<InstalledDistribution> {
+    let mut name = None;
+    let mut version = None;
+    let mut requires = Vec::new();
+    // Headers end at the first blank line, where the description body starts
+    for line in content.lines().take_while(|line| !line.trim().is_empty()) {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key {
+            "Name" => name = Some(value.to_string()),
+            "Version" => version = Some(Pep440Version::parse(value)?),
+            "Requires-Dist" => requires.push(PythonPackage::parse(value)?),
+            _ => {}
+        }
+    }
+
+    Ok(InstalledDistribution {
+        name: name.ok_or_else(|| PyhelperError::Invalid("Missing Name field".to_string()))?,
+        version: version.ok_or_else(|| PyhelperError::Invalid("Missing Version field".to_string()))?,
+        requires,
+        path: path.to_path_buf(),
+    })
+}
+
+/// Finds every requirement of an installed distribution that the installed
+/// versions do not satisfy, like `pip check`. Requirements only pulled in by an
+/// extra are ignored.
+pub fn check(distributions: &[InstalledDistribution], markers: Option<&MarkerEnvironment>) -> Vec<EnvIssue> {
+    let installed: BTreeMap<String, &InstalledDistribution> = distributions
+        .iter()
+        .map(|d| (canonicalize_name(&d.name), d))
+        .collect();
+    let mut issues = Vec::new();
+    for distribution in distributions {
+        for requirement in &distribution.requires {
+            let applies = match markers {
+                Some(env) => requirement.applies_in(env, &[]),
+                None => requirement.marker.is_none(),
+            };
+            if !applies {
+                continue;
+            }
+            let dependency = installed.get(&requirement.canonical_name());
+            let satisfied = dependency.is_some_and(|d| requirement.specifiers.range().contains(&d.version));
+            if !satisfied {
+                issues.push(EnvIssue {
+                    dependent: distribution.name.clone(),
+                    dependent_version: distribution.version.clone(),
+                    requirement: requirement.clone(),
+                    installed: dependency.map(|d| d.version.clone()),
+                });
+            }
+        }
+    }
+    issues
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn install(site: &Path, name: &str, version: &str, requires: &[&str]) {
+        let dir = site.join(format!("{}-{}.dist-info", name, version));
+        fs::create_dir_all(&dir).unwrap();
+        let mut metadata = format!("Metadata-Version: 2.1\nName: {}\nVersion: {}\n", name, version);
+        for requirement in requires {
+            metadata.push_str(&format!("Requires-Dist: {}\n", requirement));
+        }
+        metadata.push_str("\nRequires-Dist: not-a-header\n");
+        fs::write(dir.join("METADATA"), metadata).unwrap();
+    }
+
+    #[test]
+    fn test_check_environment() -> Result<()> {
+        let site = std::env::temp_dir().join(format!("pyhelper-env-{}", std::process::id()));
+        install(
+            &site,
+            "requests",
+            "2.31.0",
+            &[
+                "urllib3 (<3,>=1.21.1)",
+                "idna<4,>=2.5",
+                "PySocks!=1.5.7,>=1.5.6; extra == \"socks\"",
+                "win-inet-pton; sys_platform == \"win32\"",
+            ],
+        );
+        install(&site, "urllib3", "3.0.0", &[]);
+        fs::create_dir_all(site.join("requests"))?;
+
+        let distributions = scan_site_packages(&site)?;
+        let names: Vec<&str> = distributions.iter().map(|d| d.name.as_str()).collect();
+        assert_eq!(names, vec!["requests", "urllib3"]);
+        assert_eq!(distributions[0].requires.len(), 4);
+
+        let mut markers = MarkerEnvironment::default();
+        markers.set("sys_platform", "win32");
+        let issues = check(&distributions, Some(&markers));
+        let found: Vec<(String, Option<String>)> = issues
+            .iter()
+            .map(|i| (i.requirement.name.clone(), i.installed.as_ref().map(|v| v.to_string())))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("urllib3".to_string(), Some("3.0.0".to_string())),
+                ("idna".to_string(), None),
+                ("win-inet-pton".to_string(), None),
+            ]
+        );
+
+        // Without marker values only unconditional requirements are checked
+        assert_eq!(check(&distributions, None).len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_importlib() -> Result<()> {
+        let output = br#"{
+            "markers": {"sys_platform": "linux", "python_version": "3.11"},
+            "distributions": [
+                {"name": "requests", "version": "2.31.0", "requires": ["idna<4,>=2.5", "chardet<6; python_version < \"3\""],
+                 "path": "/app.pex/.deps/requests-2.31.0.dist-info", "location": "/app.pex/.deps"},
+                {"name": "idna", "version": "3.6", "requires": [],
+                 "path": "/nix/store/abc-idna/lib/python3.11/site-packages/idna-3.6.dist-info",
+                 "location": "/nix/store/abc-idna/lib/python3.11/site-packages"}
+            ]
+        }"#;
+        let (environment, distributions) = parse_importlib(output)?;
+        assert_eq!(environment.site_packages.len(), 2);
+        assert_eq!(distributions[0].requires[1].name, "chardet");
+        assert!(check(&distributions, environment.markers.as_ref()).is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,100 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+
+/// The kinds of failure of the library. Functions return [`anyhow::Result`];
+/// callers handling some kinds differently find the kind with
+/// `error.downcast_ref::<PyhelperError>()`, which also sees through context
+/// added on the way up.
+#[derive(Debug, Error)]
+pub enum PyhelperError {
+    /// A line of a file that cannot be parsed.
+    #[error("{}: {message}", location(file, *line, *column))]
+    Parse {
+        file: PathBuf,
+        line: usize,
+        /// The 1-based column the line's content starts at, when known.
+        column: Option<usize>,
+        message: String,
+    },
+    /// A version, version specifier or requirement that is not valid PEP 440
+    /// or PEP 508, or a Conda match spec that cannot be read.
+    #[error("{0}")]
+    Specifier(String),
+    /// An environment marker that is not valid PEP 508.
+    #[error("{0}")]
+    Marker(String),
+    /// An SPDX license expression that cannot be parsed.
+    #[error("{0}")]
+    License(String),
+    /// A request to an index or vulnerability database that failed, or that
+    /// answered with something else than expected.
+    #[error("{message}")]
+    Network {
+        url: String,
+        message: String,
+        #[source]
+        source: Option<Box<dyn std::error::Error + Send + Sync>>,
+    },
+    /// A package or release the index does not have.
+    #[error("{0}")]
+    NotFound(String),
+    /// Requirements that cannot be combined, or versions that cannot be
+    /// chosen for them.
+    #[error("{0}")]
+    Resolution(String),
+    /// An argument, rule or name the library cannot use.
+    #[error("{0}")]
+    Invalid(String),
+}
+
+impl PyhelperError {
+    /// A parse error at `line` of `file`, describing `error` and its causes.
+    pub fn parse(file: &Path, line: usize, column: Option<usize>, error: impl fmt::Display) -> Self {
+        PyhelperError::Parse {
+            file: file.to_path_buf(),
+            line,
+            column,
+            message: format!("{:#}", error),
+        }
+    }
+
+    /// A failed request to `url`, caused by `source` if given.
+    pub fn network(url: &str, message: String, source: Option<Box<dyn std::error::Error + Send + Sync>>) -> Self {
+        PyhelperError::Network {
+            url: url.to_string(),
+            message,
+            source,
+        }
+    }
+}
+
+fn location(file: &Path, line: usize, column: Option<usize>) -> String {
+    match column {
+        Some(column) => format!("{}:{}:{}", file.display(), line, column),
+        None => format!("{}:{}", file.display(), line),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::PythonPackage;
+
+    #[test]
+    fn test_kinds() {
+        let err = PythonPackage::parse("django>>4").unwrap_err();
+        assert!(matches!(err.downcast_ref::<PyhelperError>(), Some(PyhelperError::Specifier(_))));
+        assert_eq!(err.to_string(), "Invalid version requirement: >>4");
+
+        let err = crate::requirements::parse_str("six\ndjango>>4\n", Path::new("requirements.txt")).unwrap_err();
+        match err.downcast_ref::<PyhelperError>() {
+            Some(PyhelperError::Parse { line, column, message, .. }) => {
+                assert_eq!((*line, *column), (2, Some(1)));
+                assert_eq!(message, "Invalid version requirement: >>4");
+            }
+            other => panic!("{:?}", other),
+        }
+        assert_eq!(err.to_string(), "requirements.txt:2:1: Invalid version requirement: >>4");
+    }
+}
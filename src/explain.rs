@@ -0,0 +1,213 @@
+use std::fmt;
+use std::ops::Bound;
+
+use crate::package::PythonPackage;
+use crate::pep440::Pep440Version;
+use crate::prerelease;
+use crate::pypi::Release;
+use crate::range::{cmp_lower, cmp_upper, Interval, IntervalSet};
+use crate::specifier::{as_written, SpecifierSet};
+
+/// Why a set of requirements on one package cannot be satisfied together.
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    /// Each requirement with the versions it allows on its own.
+    pub requirements: Vec<(String, IntervalSet<Pep440Version>)>,
+    pub intersection: IntervalSet<Pep440Version>,
+    /// The versions separating a requirement that only allows older versions
+    /// from one that only allows newer ones, when the conflict is that simple.
+    pub gap: Option<Interval<Pep440Version>>,
+    /// The releases closest to the gap (or to the intersection when no release
+    /// falls inside it), known only when release data was available.
+    pub nearest: Option<(Option<Pep440Version>, Option<Pep440Version>)>,
+    /// The text a requirement pins with `===`, which only that text matches,
+    /// and whether every requirement allows it.
+    pub literal: Option<(String, bool)>,
+}
+
+/// Explains the requirements, optionally pointing at the nearest published releases.
+pub fn explain(packages: &[&PythonPackage], releases: Option<&[Release]>) -> Explanation {
+    let requirements: Vec<(String, IntervalSet<Pep440Version>)> = packages
+        .iter()
+        .map(|p| (p.to_string(), p.specifiers.range()))
+        .collect();
+    let intersection = requirements
+        .iter()
+        .fold(IntervalSet::full(), |acc, (_, range)| acc.intersect(range));
+
+    let hulls: Vec<Interval<Pep440Version>> =
+        requirements.iter().filter_map(|(_, range)| range.hull()).collect();
+    // The requirement ending lowest and the one starting highest
+    let lowest = hulls.iter().min_by(|a, b| cmp_upper(&a.upper, &b.upper));
+    let highest = hulls.iter().max_by(|a, b| cmp_lower(&a.lower, &b.lower));
+    let gap = match (lowest, highest) {
+        (Some(lowest), Some(highest)) if intersection.is_empty() => {
+            match (lowest.above(), highest.below()) {
+                (Some(above), Some(below)) => {
+                    let gap = above.intersect(&below);
+                    (!gap.is_empty()).then_some(gap)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    };
+
+    let nearest = releases.map(|releases| {
+        let published = releases.iter().filter(|r| !r.yanked).map(|r| &r.version);
+        let (below, above) = match (&gap, intersection.hull()) {
+            (Some(gap), _) => (gap.below(), gap.above()),
+            (None, Some(hull)) => (hull.below(), hull.above()),
+            (None, None) => (None, None),
+        };
+        let below = below.and_then(|b| published.clone().filter(|v| b.contains(v)).max().cloned());
+        let above = above.and_then(|a| published.filter(|v| a.contains(v)).min().cloned());
+        (below, above)
+    });
+
+    let literal = packages.iter().find_map(|p| p.specifiers.literal()).map(|pinned| {
+        let joint = SpecifierSet {
+            specifiers: packages.iter().flat_map(|p| p.specifiers.specifiers.iter().cloned()).collect(),
+        };
+        (pinned.to_string(), !joint.excludes_literal(&joint))
+    });
+
+    Explanation {
+        requirements,
+        intersection,
+        gap,
+        nearest,
+        literal,
+    }
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let width = self.requirements.iter().map(|(r, _)| r.len()).max().unwrap_or(0);
+        for (requirement, range) in &self.requirements {
+            writeln!(f, "  {:width$}  allows {}", requirement, versions(range), width = width)?;
+        }
+        match &self.literal {
+            Some((pinned, true)) => writeln!(f, "  together they allow only {}", pinned)?,
+            Some((pinned, false)) if Pep440Version::parse(pinned).is_err() => {
+                writeln!(f, "  {} is not a PEP 440 version; only a === pin on the same text allows it", pinned)?;
+                writeln!(f, "  together they allow no version")?;
+            }
+            Some((pinned, false)) => {
+                writeln!(f, "  ==={} matches only that text, which the other requirements do not all allow", pinned)?;
+                writeln!(f, "  together they allow no version")?;
+            }
+            None => writeln!(f, "  together they allow {}", versions(&self.intersection))?,
+        }
+        if !self.intersection.is_empty() && !prerelease::is_satisfiable(&self.intersection, false) {
+            writeln!(f, "  only pre-releases fall in that range; --pre allows them")?;
+        }
+        if let Some(gap) = &self.gap {
+            match interval(gap) {
+                words if words.starts_with("the ") => writeln!(f, "  {} separate the allowed ranges", words)?,
+                gap => writeln!(f, "  the gap {} separates the allowed ranges", gap)?,
+            }
+        }
+        if let Some((below, above)) = &self.nearest {
+            let show = |v: &Option<Pep440Version>| v.as_ref().map_or("none".to_string(), |v| v.to_string());
+            writeln!(f, "  nearest releases: {} below, {} above", show(below), show(above))?;
+        }
+        Ok(())
+    }
+}
+
+/// The versions of a range, e.g. `[1.0, 2.0) or [3.0, +inf)`.
+pub fn versions(range: &IntervalSet<Pep440Version>) -> String {
+    if range.is_empty() {
+        return "no version".to_string();
+    }
+    let intervals: Vec<String> = range.intervals.iter().map(interval).collect();
+    intervals.join(" or ")
+}
+
+/// The versions of an interval, with its bounds as written, e.g. `[1.0, 2.0)`,
+/// and in words where a bound only stands for the end of the post-releases.
+pub fn interval(interval: &Interval<Pep440Version>) -> String {
+    match as_written(interval) {
+        // Between `<=2.0` and `>2.0`, which both leave them out
+        Interval { lower: Bound::Excluded(low), upper: Bound::Included(high) } if low == high => {
+            format!("the post-releases of {}", low)
+        }
+        // Between `<=2.0.post5` and `>2.0`, ending past the post-releases of 2.0
+        Interval { lower: Bound::Excluded(low), upper: Bound::Included(high) | Bound::Excluded(high) }
+            if high.post == Some(u64::MAX) =>
+        {
+            format!("the post-releases of {} after {}", Pep440Version { post: None, ..high }, low)
+        }
+        Interval { lower: Bound::Included(low), upper: Bound::Included(high) | Bound::Excluded(high) }
+            if high.post == Some(u64::MAX) =>
+        {
+            format!("the post-releases of {} from {}", Pep440Version { post: None, ..high }, low)
+        }
+        written => written.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn release(version: &str) -> Release {
+        Release {
+            version: Pep440Version::parse(version).unwrap(),
+            yanked: false,
+            files: Vec::new(),
+            uploaded: None,
+        }
+    }
+
+    #[test]
+    fn test_explain_gap() -> Result<()> {
+        let old = PythonPackage::parse("requests<2.0")?;
+        let new = PythonPackage::parse("requests>=3.0")?;
+        let releases = vec![release("1.9.2"), release("2.5"), release("3.0.1")];
+
+        let explanation = explain(&[&old, &new], Some(&releases));
+        assert!(explanation.intersection.is_empty());
+        assert_eq!(explanation.gap.as_ref().map(|g| as_written(g).to_string()), Some("[2.0.dev0, 3.0)".to_string()));
+        let (below, above) = explanation.nearest.clone().unwrap();
+        assert_eq!(below.map(|v| v.to_string()), Some("1.9.2".to_string()));
+        assert_eq!(above.map(|v| v.to_string()), Some("3.0.1".to_string()));
+
+        let text = explanation.to_string();
+        assert!(text.contains("requests <2.0   allows (-inf, 2.0)"), "{}", text);
+        assert!(text.contains("together they allow no version"), "{}", text);
+        let pinned = PythonPackage::parse("six===1.16")?;
+        let text = explain(&[&pinned, &PythonPackage::parse("six===1.16.0")?], None).to_string();
+        assert!(text.contains("===1.16 matches only that text"), "{}", text);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_explain_without_gap() -> Result<()> {
+        let pinned = PythonPackage::parse("six==1.16")?;
+        let excluded = PythonPackage::parse("six!=1.16")?;
+        let explanation = explain(&[&pinned, &excluded], None);
+        assert!(explanation.gap.is_none());
+        assert!(explanation.nearest.is_none());
+
+        // Overlapping ranges without a release in between
+        let a = PythonPackage::parse("six>1.15")?;
+        let b = PythonPackage::parse("six<1.16")?;
+        let releases = vec![release("1.15"), release("1.16")];
+        let explanation = explain(&[&a, &b], Some(&releases));
+        assert!(explanation.gap.is_none());
+        let (below, above) = explanation.nearest.unwrap();
+        assert_eq!(below.map(|v| v.to_string()), Some("1.15".to_string()));
+        assert_eq!(above.map(|v| v.to_string()), Some("1.16".to_string()));
+
+        // A `===` pin on a non-PEP 440 version only agrees with the same pin
+        let custom = PythonPackage::parse("six===1.16-custom")?;
+        let text = explain(&[&custom, &b], None).to_string();
+        assert!(text.contains("together they allow no version"), "{}", text);
+
+        Ok(())
+    }
+}
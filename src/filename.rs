@@ -0,0 +1,196 @@
+use anyhow::Result;
+use std::fmt;
+
+use crate::error::PyhelperError;
+use crate::package::{canonicalize_name, SDIST_EXTENSIONS};
+use crate::pep440::Pep440Version;
+
+/// One interpreter, ABI and platform combination a wheel can be installed
+/// on, e.g. `cp311-cp311-manylinux_2_17_x86_64`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Tag {
+    pub python: String,
+    pub abi: String,
+    pub platform: String,
+}
+
+impl fmt::Display for Tag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}-{}", self.python, self.abi, self.platform)
+    }
+}
+
+/// The parts of a wheel file name (PEP 427):
+/// `{name}-{version}(-{build})?-{python}-{abi}-{platform}.whl`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WheelName {
+    /// The project name as spelled in the file name, `-` replaced by `_`.
+    pub name: String,
+    pub version: Pep440Version,
+    /// The build tag, split into its leading number and the rest, e.g. `(1, "b")` for `1b`.
+    pub build: Option<(u64, String)>,
+    /// The tag sets, which `.` compresses, e.g. `["py2", "py3"]` for `py2.py3`.
+    pub python: Vec<String>,
+    pub abi: Vec<String>,
+    pub platform: Vec<String>,
+}
+
+impl WheelName {
+    pub fn parse(file: &str) -> Result<Self> {
+        let invalid = || PyhelperError::Invalid(format!("Invalid wheel file name: {}", file));
+        let stem = file.strip_suffix(".whl").ok_or_else(invalid)?;
+        let parts: Vec<&str> = stem.split('-').collect();
+        let (name, version, build, tags) = match parts.as_slice() {
+            [name, version, python, abi, platform] => (name, version, None, [python, abi, platform]),
+            [name, version, build, python, abi, platform] => (name, version, Some(build), [python, abi, platform]),
+            _ => return Err(invalid().into()),
+        };
+        let build = match build {
+            Some(build) => {
+                let digits = build.find(|c: char| !c.is_ascii_digit()).unwrap_or(build.len());
+                let number = build[..digits].parse().map_err(|_| invalid())?;
+                Some((number, build[digits..].to_string()))
+            }
+            None => None,
+        };
+        let [python, abi, platform] = tags.map(|set| set.split('.').map(str::to_string).collect::<Vec<_>>());
+        if name.is_empty() || [&python, &abi, &platform].iter().any(|set| set.iter().any(|tag| tag.is_empty())) {
+            return Err(invalid().into());
+        }
+        Ok(WheelName {
+            name: name.to_string(),
+            version: Pep440Version::parse(version).map_err(|_| invalid())?,
+            build,
+            python,
+            abi,
+            platform,
+        })
+    }
+
+    /// Every tag the wheel supports: each combination of its tag sets.
+    pub fn tags(&self) -> Vec<Tag> {
+        let mut tags = Vec::new();
+        for python in &self.python {
+            for abi in &self.abi {
+                for platform in &self.platform {
+                    tags.push(Tag {
+                        python: python.clone(),
+                        abi: abi.clone(),
+                        platform: platform.clone(),
+                    });
+                }
+            }
+        }
+        tags
+    }
+
+    /// Whether the wheel installs on any interpreter and platform, like `py3-none-any`.
+    pub fn is_pure(&self) -> bool {
+        self.abi == ["none"] && self.platform == ["any"]
+    }
+}
+
+/// The parts of a source distribution file name, `{name}-{version}.tar.gz`
+/// and the older archive formats pip still installs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SdistName {
+    /// The project name as spelled in the file name; older sdists keep `-` in it.
+    pub name: String,
+    pub version: Pep440Version,
+    /// The archive extension, e.g. `.tar.gz`.
+    pub extension: &'static str,
+}
+
+impl SdistName {
+    pub fn parse(file: &str) -> Result<Self> {
+        let invalid = || PyhelperError::Invalid(format!("Invalid source distribution file name: {}", file));
+        let (stem, extension) = SDIST_EXTENSIONS
+            .iter()
+            .find_map(|ext| file.strip_suffix(ext).map(|stem| (stem, *ext)))
+            .ok_or_else(invalid)?;
+        // The version is the last part that parses, as names may contain `-`
+        let (name, version) = stem.rsplit_once('-').ok_or_else(invalid)?;
+        if name.is_empty() {
+            return Err(invalid().into());
+        }
+        Ok(SdistName {
+            name: name.to_string(),
+            version: Pep440Version::parse(version).map_err(|_| invalid())?,
+            extension,
+        })
+    }
+}
+
+/// A file of a release on an index: a wheel or a source distribution.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DistributionName {
+    Wheel(WheelName),
+    Sdist(SdistName),
+}
+
+impl DistributionName {
+    /// Parses a wheel or source distribution file name. `None` for other files,
+    /// such as eggs or Windows installers, or names without a PEP 440 version.
+    pub fn parse(file: &str) -> Option<Self> {
+        match file.ends_with(".whl") {
+            true => WheelName::parse(file).ok().map(DistributionName::Wheel),
+            false => SdistName::parse(file).ok().map(DistributionName::Sdist),
+        }
+    }
+
+    /// The project name as spelled in the file name.
+    pub fn name(&self) -> &str {
+        match self {
+            DistributionName::Wheel(wheel) => &wheel.name,
+            DistributionName::Sdist(sdist) => &sdist.name,
+        }
+    }
+
+    pub fn canonical_name(&self) -> String {
+        canonicalize_name(self.name())
+    }
+
+    pub fn version(&self) -> &Pep440Version {
+        match self {
+            DistributionName::Wheel(wheel) => &wheel.version,
+            DistributionName::Sdist(sdist) => &sdist.version,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wheel_name() -> Result<()> {
+        let wheel = WheelName::parse("numpy-1.26.4-cp311-cp311-manylinux_2_17_x86_64.manylinux2014_x86_64.whl")?;
+        assert_eq!((wheel.name.as_str(), wheel.version.to_string().as_str()), ("numpy", "1.26.4"));
+        assert_eq!(wheel.build, None);
+        let tags: Vec<String> = wheel.tags().iter().map(|tag| tag.to_string()).collect();
+        assert_eq!(tags, vec!["cp311-cp311-manylinux_2_17_x86_64", "cp311-cp311-manylinux2014_x86_64"]);
+        assert!(!wheel.is_pure());
+
+        let wheel = WheelName::parse("typing_extensions-4.9.0-1b-py2.py3-none-any.whl")?;
+        assert_eq!(wheel.build, Some((1, "b".to_string())));
+        assert_eq!(wheel.python, vec!["py2", "py3"]);
+        assert!(wheel.is_pure());
+
+        assert!(WheelName::parse("pkg-1.0-py3-none.whl").is_err());
+        assert!(WheelName::parse("pkg-1.0-x1-py3-none-any.whl").is_err());
+        assert!(WheelName::parse("pkg-latest-py3-none-any.whl").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_distribution_name() {
+        let sdist = DistributionName::parse("zope.interface-6.1.tar.gz").unwrap();
+        assert_eq!((sdist.name(), sdist.version().to_string().as_str()), ("zope.interface", "6.1"));
+        let legacy = DistributionName::parse("python-dateutil-2.8.2.zip").unwrap();
+        assert_eq!((legacy.canonical_name().as_str(), legacy.version().to_string().as_str()), ("python-dateutil", "2.8.2"));
+        let wheel = DistributionName::parse("Django-5.0-py3-none-any.whl").unwrap();
+        assert_eq!(wheel.canonical_name(), "django");
+        assert!(DistributionName::parse("pkg-1.0-py3.9.egg").is_none());
+        assert!(DistributionName::parse("pkg-1.0.win32.exe").is_none());
+    }
+}
@@ -0,0 +1,336 @@
+use anyhow::Result;
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use crate::error::PyhelperError;
+use crate::local;
+use crate::merge;
+use crate::package::{canonicalize_name, PythonPackage};
+
+/// A requirements file rewritten in canonical form.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Formatted {
+    pub content: String,
+    /// Packages required more than once, whose requirements were merged.
+    pub merged: Vec<String>,
+    /// Merged packages whose requirements together allow no version.
+    pub unsatisfiable: Vec<String>,
+}
+
+/// One logical line of the file with the comments that belong to it.
+struct Entry {
+    line: usize,
+    text: String,
+    /// Comment lines directly above the entry.
+    above: Vec<String>,
+    /// A comment at the end of the entry.
+    trailing: Option<String>,
+}
+
+/// A requirement with its per-requirement options.
+struct Requirement {
+    package: PythonPackage,
+    hashes: Vec<String>,
+    options: Vec<String>,
+    above: Vec<String>,
+    trailing: Vec<String>,
+}
+
+/// Formats the content of the requirements file `path`: global options,
+/// includes, editables and local paths stay first, in their order, and
+/// requirements follow sorted by name, spelled canonically and one per
+/// package and marker, with each `--hash` on a continuation line of its own.
+/// A comment block at the top, set apart by a blank line, stays the header;
+/// other comments move with the line below them.
+pub fn format(content: &str, path: &Path) -> Result<Formatted> {
+    let (header, entries, footer) = entries(content);
+
+    let mut verbatim = Vec::new();
+    let mut groups: BTreeMap<(String, String), Vec<Requirement>> = BTreeMap::new();
+    for entry in entries {
+        let (requirement, options) = split_options(&entry.text);
+        if entry.text.starts_with('-') || local::is_path(requirement) {
+            verbatim.push(render(&entry.above, entry.text.split_whitespace().collect::<Vec<_>>().join(" "), &[], entry.trailing));
+            continue;
+        }
+        let package = PythonPackage::parse(requirement).map_err(|e| PyhelperError::parse(path, entry.line, None, e))?;
+        let (hashes, options) = hash_options(options);
+        let marker = package.marker.as_ref().map(|m| m.to_string()).unwrap_or_default();
+        groups.entry((canonicalize_name(&package.name), marker)).or_default().push(Requirement {
+            package,
+            hashes,
+            options,
+            above: entry.above,
+            trailing: entry.trailing.into_iter().collect(),
+        });
+    }
+
+    let mut merged = Vec::new();
+    let mut unsatisfiable = Vec::new();
+    let mut blocks = verbatim;
+    for ((name, _), requirements) in groups {
+        let packages: Vec<PythonPackage> = requirements.iter().map(|r| r.package.clone()).collect();
+        let mut package = match packages.as_slice() {
+            [only] => only.clone(),
+            _ => {
+                merged.push(name.clone());
+                let package = merge::intersect(&packages)?;
+                if package.specifiers.range().is_empty() {
+                    unsatisfiable.push(name.clone());
+                }
+                package
+            }
+        };
+        package.name = name;
+        package.extras = package.extras.iter().map(|extra| canonicalize_name(extra)).collect();
+        package.extras.sort();
+        package.extras.dedup();
+
+        let mut hashes: Vec<String> = Vec::new();
+        let mut options: Vec<String> = Vec::new();
+        let mut above = Vec::new();
+        let mut trailing = Vec::new();
+        for requirement in requirements {
+            for hash in requirement.hashes {
+                if !hashes.contains(&hash) {
+                    hashes.push(hash);
+                }
+            }
+            for option in requirement.options {
+                if !options.contains(&option) {
+                    options.push(option);
+                }
+            }
+            above.extend(requirement.above);
+            trailing.extend(requirement.trailing);
+        }
+        let mut text = merge::line(&package);
+        for option in options {
+            text.push(' ');
+            text.push_str(&option);
+        }
+        let trailing = (!trailing.is_empty()).then(|| trailing.join(" "));
+        blocks.push(render(&above, text, &hashes, trailing));
+    }
+
+    let mut out = String::new();
+    if !header.is_empty() {
+        out.push_str(&header.join("\n"));
+        out.push_str("\n\n");
+    }
+    out.push_str(&blocks.concat());
+    if !footer.is_empty() {
+        if !blocks.is_empty() {
+            out.push('\n');
+        }
+        out.push_str(&footer.join("\n"));
+        out.push('\n');
+    }
+    Ok(Formatted {
+        content: out,
+        merged,
+        unsatisfiable,
+    })
+}
+
+/// Splits the content into the header comments, the entries and the
+/// comments after the last entry, dropping blank lines.
+fn entries(content: &str) -> (Vec<String>, Vec<Entry>, Vec<String>) {
+    static COMMENT: OnceLock<Regex> = OnceLock::new();
+    let comment = COMMENT.get_or_init(|| Regex::new(r"(^|\s+)(#.*)$").unwrap());
+
+    let mut header = Vec::new();
+    let mut entries: Vec<Entry> = Vec::new();
+    let mut comments: Vec<String> = Vec::new();
+    let mut pending: Option<(usize, String)> = None;
+    for (index, raw) in content.lines().enumerate() {
+        let trimmed = raw.trim();
+        if pending.is_none() {
+            if trimmed.is_empty() {
+                // Only a block at the very top, set apart, is a header
+                if entries.is_empty() && header.is_empty() {
+                    header = std::mem::take(&mut comments);
+                }
+                continue;
+            }
+            if trimmed.starts_with('#') {
+                comments.push(trimmed.to_string());
+                continue;
+            }
+        }
+        let (start, mut text) = pending.take().unwrap_or((index + 1, String::new()));
+        if let Some(continued) = raw.strip_suffix('\\') {
+            text.push_str(continued);
+            text.push(' ');
+            pending = Some((start, text));
+            continue;
+        }
+        text.push_str(raw);
+        let trailing = comment.captures(&text).map(|c| c[2].to_string());
+        let text = comment.replace(&text, "").trim().to_string();
+        if text.is_empty() {
+            comments.extend(trailing);
+            continue;
+        }
+        entries.push(Entry {
+            line: start,
+            text,
+            above: std::mem::take(&mut comments),
+            trailing,
+        });
+    }
+    if let Some((line, text)) = pending {
+        let text = comment.replace(&text, "").trim().to_string();
+        if !text.is_empty() {
+            entries.push(Entry {
+                line,
+                text,
+                above: std::mem::take(&mut comments),
+                trailing: None,
+            });
+        }
+    }
+    (header, entries, comments)
+}
+
+/// Splits a requirement line into the requirement and its options, such
+/// as `--hash=sha256:...`.
+fn split_options(text: &str) -> (&str, &str) {
+    static OPTION: OnceLock<Regex> = OnceLock::new();
+    let option = OPTION.get_or_init(|| Regex::new(r"\s--?[a-zA-Z]").unwrap());
+    match option.find(text) {
+        Some(m) => (text[..m.start()].trim(), text[m.start()..].trim()),
+        None => (text, ""),
+    }
+}
+
+/// The `--hash` values of the options, and the other options each with its value.
+fn hash_options(options: &str) -> (Vec<String>, Vec<String>) {
+    let mut pieces: Vec<Vec<&str>> = Vec::new();
+    for token in options.split_whitespace() {
+        match pieces.last_mut() {
+            Some(piece) if !token.starts_with('-') => piece.push(token),
+            _ => pieces.push(vec![token]),
+        }
+    }
+    let mut hashes = Vec::new();
+    let mut others = Vec::new();
+    for piece in pieces {
+        match piece.as_slice() {
+            ["--hash", value, ..] => hashes.push(value.to_string()),
+            [option, ..] if option.starts_with("--hash=") => hashes.push(option["--hash=".len()..].to_string()),
+            _ => others.push(piece.join(" ")),
+        }
+    }
+    (hashes, others)
+}
+
+fn render(above: &[String], text: String, hashes: &[String], trailing: Option<String>) -> String {
+    let mut out = String::new();
+    for comment in above {
+        out.push_str(comment);
+        out.push('\n');
+    }
+    out.push_str(&text);
+    for hash in hashes {
+        out.push_str(&format!(" \\\n    --hash={}", hash));
+    }
+    if let Some(comment) = trailing {
+        out.push_str("  ");
+        out.push_str(&comment);
+    }
+    out.push('\n');
+    out
+}
+
+/// The lines of `before` missing from `after` (`false`) and the lines of
+/// `after` new to it (`true`), in file order.
+pub fn changes<'a>(before: &'a str, after: &'a str) -> Vec<(bool, &'a str)> {
+    let a: Vec<&str> = before.lines().collect();
+    let b: Vec<&str> = after.lines().collect();
+    // Longest common subsequence of lines, from the ends backwards
+    let mut common = vec![vec![0usize; b.len() + 1]; a.len() + 1];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            common[i][j] = match a[i] == b[j] {
+                true => common[i + 1][j + 1] + 1,
+                false => common[i + 1][j].max(common[i][j + 1]),
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut out = Vec::new();
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            i += 1;
+            j += 1;
+        } else if i < a.len() && (j == b.len() || common[i + 1][j] >= common[i][j + 1]) {
+            out.push((false, a[i]));
+            i += 1;
+        } else {
+            out.push((true, b[j]));
+            j += 1;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format() -> Result<()> {
+        let content = "\
+# Service dependencies
+
+--index-url https://pypi.org/simple
+Requests >= 2.28 ,<3 # http
+# the web framework
+DjangoRESTFramework==3.14.0 --hash sha256:bbb   --hash=sha256:aaa
+-r   base.txt
+requests!=2.30.0
+six==1.16.0 \\
+        --hash=sha256:ccc \\
+            --hash=sha256:ddd
+colorama ; sys_platform=='win32'
+";
+        let formatted = format(content, Path::new("requirements.txt"))?;
+        assert_eq!(
+            formatted.content,
+            "\
+# Service dependencies
+
+--index-url https://pypi.org/simple
+-r base.txt
+colorama ; sys_platform == \"win32\"
+# the web framework
+djangorestframework==3.14.0 \\
+    --hash=sha256:bbb \\
+    --hash=sha256:aaa
+requests>=2.28,<3,!=2.30.0  # http
+six==1.16.0 \\
+    --hash=sha256:ccc \\
+    --hash=sha256:ddd
+"
+        );
+        assert_eq!(formatted.merged, vec!["requests"]);
+        assert!(formatted.unsatisfiable.is_empty());
+
+        // Formatting is idempotent
+        assert_eq!(format(&formatted.content, Path::new("requirements.txt"))?, Formatted { merged: Vec::new(), ..formatted });
+
+        let conflicting = format("flask<2\nFlask>=3\n", Path::new("requirements.txt"))?;
+        assert_eq!(conflicting.unsatisfiable, vec!["flask"]);
+        assert!(format("ok\nflask>>2\n", Path::new("requirements.txt")).unwrap_err().to_string().contains("requirements.txt:2"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_changes() {
+        assert_eq!(changes("a\nb\nc\n", "a\nc\nd\n"), vec![(false, "b"), (true, "d")]);
+        assert!(changes("a\n", "a\n").is_empty());
+    }
+}
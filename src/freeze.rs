@@ -0,0 +1,166 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::pep440::Pep440Version;
+use crate::requirements::RequirementLine;
+use crate::specifier::Operator;
+
+/// How a package changed between two `pip freeze` outputs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChangeKind {
+    Major,
+    Downgrade,
+    Minor,
+    Patch,
+    /// The requirement changed but is not a plain `==` pin on both sides.
+    Changed,
+    Added,
+    Removed,
+}
+
+impl ChangeKind {
+    /// Whether the change is likely to break code or other constraints.
+    pub fn is_risky(self) -> bool {
+        matches!(self, ChangeKind::Major | ChangeKind::Downgrade)
+    }
+}
+
+impl fmt::Display for ChangeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            ChangeKind::Major => "major",
+            ChangeKind::Downgrade => "downgrade",
+            ChangeKind::Minor => "minor",
+            ChangeKind::Patch => "patch",
+            ChangeKind::Changed => "changed",
+            ChangeKind::Added => "added",
+            ChangeKind::Removed => "removed",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// One package that differs between the old and new freeze files.
+#[derive(Debug, Clone)]
+pub struct FreezeChange {
+    /// PEP 503 normalized package name.
+    pub name: String,
+    pub old: Option<RequirementLine>,
+    pub new: Option<RequirementLine>,
+    pub kind: ChangeKind,
+}
+
+/// The version a requirement pins with a single `==` or `===` clause, if it does.
+pub fn pinned_version(requirement: &RequirementLine) -> Option<&Pep440Version> {
+    match requirement.package.specifiers.specifiers.as_slice() {
+        [spec] if spec.op == Operator::Equal && !spec.wildcard => Some(&spec.version),
+        [spec] if spec.op == Operator::ArbitraryEqual && !spec.is_opaque() => Some(&spec.version),
+        _ => None,
+    }
+}
+
+/// Compares two freeze files package by package, ignoring unchanged pins.
+pub fn diff(old: &[RequirementLine], new: &[RequirementLine]) -> Vec<FreezeChange> {
+    let index = |lines: &[RequirementLine]| -> BTreeMap<String, RequirementLine> {
+        lines
+            .iter()
+            .map(|line| (line.package.canonical_name(), line.clone()))
+            .collect()
+    };
+    let mut old = index(old);
+    let new = index(new);
+
+    let mut changes = Vec::new();
+    for (name, after) in new {
+        let before = old.remove(&name);
+        let kind = match &before {
+            None => ChangeKind::Added,
+            Some(before) => match (pinned_version(before), pinned_version(&after)) {
+                (Some(a), Some(b)) if a == b => continue,
+                (Some(a), Some(b)) => classify(a, b),
+                _ if before.package.specifiers == after.package.specifiers => continue,
+                _ => ChangeKind::Changed,
+            },
+        };
+        changes.push(FreezeChange {
+            name,
+            old: before,
+            new: Some(after),
+            kind,
+        });
+    }
+    changes.extend(old.into_iter().map(|(name, before)| FreezeChange {
+        name,
+        old: Some(before),
+        new: None,
+        kind: ChangeKind::Removed,
+    }));
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+    changes
+}
+
+/// Classifies the move from `old` to `new` by the first release segment that changed.
+fn classify(old: &Pep440Version, new: &Pep440Version) -> ChangeKind {
+    if new < old {
+        return ChangeKind::Downgrade;
+    }
+    let segment = |v: &Pep440Version, i: usize| v.release.get(i).copied().unwrap_or(0);
+    if old.epoch != new.epoch || segment(old, 0) != segment(new, 0) {
+        ChangeKind::Major
+    } else if segment(old, 1) != segment(new, 1) {
+        ChangeKind::Minor
+    } else {
+        ChangeKind::Patch
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::PythonPackage;
+    use anyhow::Result;
+    use std::path::PathBuf;
+
+    fn lines(file: &str, requirements: &[&str]) -> Result<Vec<RequirementLine>> {
+        requirements
+            .iter()
+            .enumerate()
+            .map(|(i, r)| {
+                Ok(RequirementLine {
+                    package: PythonPackage::parse(r)?,
+                    file: PathBuf::from(file),
+                    line: i + 1,
+                    columns: None,
+                    hashes: Vec::new(),
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_diff() -> Result<()> {
+        let old = lines(
+            "old.txt",
+            &["Django==3.2.20", "requests==2.28.0", "urllib3==2.0.4", "six==1.16.0", "idna==3.4", "attrs==23.1.0"],
+        )?;
+        let new = lines(
+            "new.txt",
+            &["django==4.2.7", "requests==2.31.0", "urllib3==2.0.7", "rich==13.5.0", "idna==3.3", "attrs==23.1"],
+        )?;
+
+        let found: Vec<(String, ChangeKind)> = diff(&old, &new).into_iter().map(|c| (c.name, c.kind)).collect();
+        assert_eq!(
+            found,
+            vec![
+                ("django".to_string(), ChangeKind::Major),
+                ("idna".to_string(), ChangeKind::Downgrade),
+                ("requests".to_string(), ChangeKind::Minor),
+                ("rich".to_string(), ChangeKind::Added),
+                ("six".to_string(), ChangeKind::Removed),
+                ("urllib3".to_string(), ChangeKind::Patch),
+            ]
+        );
+
+        Ok(())
+    }
+}
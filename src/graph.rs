@@ -0,0 +1,267 @@
+#[cfg(feature = "network")]
+use anyhow::Result;
+use std::collections::BTreeMap;
+#[cfg(feature = "network")]
+use tracing::{debug, instrument};
+
+use crate::env::InstalledDistribution;
+use crate::marker::MarkerEnvironment;
+use crate::package::{canonicalize_name, PythonPackage};
+use crate::pep440::Pep440Version;
+#[cfg(feature = "network")]
+use crate::prerelease::PreReleasePolicy;
+#[cfg(feature = "network")]
+use crate::pypi::{self, PypiClient};
+
+/// A package in a dependency graph.
+#[derive(Debug, Clone)]
+pub struct Node {
+    /// The name as the package spells it.
+    pub name: String,
+    /// The installed or selected version, `None` when no version is available.
+    pub version: Option<Pep440Version>,
+}
+
+/// A dependency of one package on another, with the requirement that declares it.
+#[derive(Debug, Clone)]
+pub struct Edge {
+    /// PEP 503 normalized names of the dependent and the dependency.
+    pub from: String,
+    pub to: String,
+    pub requirement: PythonPackage,
+}
+
+/// Packages and the requirements between them, keyed by normalized name.
+#[derive(Debug, Clone, Default)]
+pub struct DependencyGraph {
+    pub nodes: BTreeMap<String, Node>,
+    pub edges: Vec<Edge>,
+    /// The top-level packages the graph was built from.
+    pub roots: Vec<String>,
+}
+
+impl DependencyGraph {
+    /// Builds the graph of an installed environment. Requirements that do not
+    /// apply to the environment are left out; without marker values only
+    /// unconditional requirements are followed. Every distribution nothing else
+    /// depends on is a root.
+    pub fn from_installed(distributions: &[InstalledDistribution], markers: Option<&MarkerEnvironment>) -> Self {
+        let mut graph = DependencyGraph::default();
+        for distribution in distributions {
+            graph.nodes.insert(
+                canonicalize_name(&distribution.name),
+                Node {
+                    name: distribution.name.clone(),
+                    version: Some(distribution.version.clone()),
+                },
+            );
+        }
+        for distribution in distributions {
+            for requirement in &distribution.requires {
+                let applies = match markers {
+                    Some(env) => requirement.applies_in(env, &[]),
+                    None => requirement.marker.is_none(),
+                };
+                if applies {
+                    graph.add_edge(&distribution.name, requirement);
+                }
+            }
+        }
+        graph.roots = graph
+            .nodes
+            .keys()
+            .filter(|name| graph.dependents(name).is_empty())
+            .cloned()
+            .collect();
+        graph
+    }
+
+    #[cfg(feature = "network")]
+    /// Builds the graph of `requirements` from the package index, selecting for
+    /// every package the release pip would pick for the first requirement that
+    /// reaches it. Dependencies are followed `depth` levels deep, or all the way.
+    /// The metadata of each level is fetched concurrently.
+    #[instrument(skip_all, fields(roots = requirements.len(), ?depth))]
+    pub fn from_index(
+        client: &PypiClient,
+        requirements: &[PythonPackage],
+        depth: Option<usize>,
+        prereleases: PreReleasePolicy,
+    ) -> Result<Self> {
+        let mut graph = DependencyGraph {
+            roots: requirements.iter().map(PythonPackage::canonical_name).collect(),
+            ..Default::default()
+        };
+        let mut level: Vec<PythonPackage> = requirements.to_vec();
+        let mut current = 0;
+
+        while !level.is_empty() {
+            let mut reached: Vec<PythonPackage> = Vec::new();
+            for requirement in level {
+                let name = requirement.canonical_name();
+                if !graph.nodes.contains_key(&name) && !reached.iter().any(|r| r.canonical_name() == name) {
+                    reached.push(requirement);
+                }
+            }
+            if let Some(progress) = client.progress() {
+                progress.step(&format!("Resolving dependencies: level {}, {} package(s)", current, reached.len()));
+            }
+            debug!(level = current, packages = reached.len(), "level");
+            let names: Vec<&str> = reached.iter().map(|r| r.name.as_str()).collect();
+            let mut selected = Vec::new();
+            for (requirement, releases) in reached.iter().zip(client.releases_all(&names)?) {
+                let version = pypi::best_match(&releases, &requirement.specifiers, prereleases);
+                graph.nodes.insert(
+                    requirement.canonical_name(),
+                    Node {
+                        name: requirement.name.clone(),
+                        version: version.clone(),
+                    },
+                );
+                if let Some(version) = version {
+                    selected.push((requirement, version));
+                }
+            }
+            if depth.is_some_and(|depth| current >= depth) {
+                break;
+            }
+
+            let releases: Vec<(&str, &Pep440Version)> =
+                selected.iter().map(|(r, version)| (r.name.as_str(), version)).collect();
+            let mut next = Vec::new();
+            for ((requirement, _), dependencies) in selected.iter().zip(client.requires_dist_all(&releases)?) {
+                for dependency in dependencies {
+                    if !dependency.may_apply(&requirement.extras) {
+                        continue;
+                    }
+                    graph.add_edge(&requirement.name, &dependency);
+                    next.push(dependency);
+                }
+            }
+            level = next;
+            current += 1;
+        }
+        Ok(graph)
+    }
+
+    fn add_edge(&mut self, from: &str, requirement: &PythonPackage) {
+        self.edges.push(Edge {
+            from: canonicalize_name(from),
+            to: requirement.canonical_name(),
+            requirement: requirement.clone(),
+        });
+    }
+
+    /// The node for a package name in any spelling.
+    pub fn node(&self, name: &str) -> Option<&Node> {
+        self.nodes.get(&canonicalize_name(name))
+    }
+
+    /// The requirements a package declares, in declaration order.
+    pub fn dependencies(&self, name: &str) -> Vec<&Edge> {
+        let name = canonicalize_name(name);
+        self.edges.iter().filter(|edge| edge.from == name).collect()
+    }
+
+    /// The requirements other packages declare on a package.
+    pub fn dependents(&self, name: &str) -> Vec<&Edge> {
+        let name = canonicalize_name(name);
+        self.edges.iter().filter(|edge| edge.to == name).collect()
+    }
+
+    /// Every chain of requirements leading from a root to `name`, cycles
+    /// excluded. A root that is the package itself contributes an empty chain.
+    pub fn paths_to(&self, name: &str) -> Vec<Vec<&Edge>> {
+        let target = canonicalize_name(name);
+        let mut paths = Vec::new();
+        for root in &self.roots {
+            self.collect_paths(root, &target, &mut Vec::new(), &mut paths);
+        }
+        paths
+    }
+
+    fn collect_paths<'a>(&'a self, name: &str, target: &str, path: &mut Vec<&'a Edge>, paths: &mut Vec<Vec<&'a Edge>>) {
+        if name == target {
+            paths.push(path.clone());
+            return;
+        }
+        for edge in self.dependencies(name) {
+            // Every package on the path so far is the dependent of one of its edges
+            if edge.to == edge.from || path.iter().any(|e| e.from == edge.to) {
+                continue;
+            }
+            path.push(edge);
+            self.collect_paths(&edge.to, target, path, paths);
+            path.pop();
+        }
+    }
+
+    /// Whether the version of the dependency satisfies the edge's requirement.
+    pub fn is_satisfied(&self, edge: &Edge) -> bool {
+        self.nodes
+            .get(&edge.to)
+            .and_then(|node| node.version.as_ref())
+            .is_some_and(|version| edge.requirement.specifiers.range().contains(version))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn dist(name: &str, version: &str, requires: &[&str]) -> InstalledDistribution {
+        InstalledDistribution {
+            name: name.to_string(),
+            version: Pep440Version::parse(version).unwrap(),
+            requires: requires.iter().map(|r| PythonPackage::parse(r).unwrap()).collect(),
+            path: PathBuf::new(),
+        }
+    }
+
+    #[test]
+    fn test_from_installed() {
+        let distributions = vec![
+            dist("requests", "2.31.0", &["urllib3<3,>=1.21.1", "idna<4,>=2.5", "PySocks; extra == \"socks\""]),
+            dist("urllib3", "3.0.0", &[]),
+            dist("Flask", "3.0.0", &["Werkzeug>=3.0", "colorama; sys_platform == \"win32\""]),
+            dist("werkzeug", "3.0.1", &[]),
+        ];
+        let graph = DependencyGraph::from_installed(&distributions, Some(&MarkerEnvironment::default()));
+        assert_eq!(graph.roots, vec!["flask", "requests"]);
+
+        let dependencies: Vec<&str> = graph.dependencies("requests").iter().map(|e| e.to.as_str()).collect();
+        assert_eq!(dependencies, vec!["urllib3", "idna"]);
+        let results: Vec<bool> = graph.dependencies("requests").iter().map(|e| graph.is_satisfied(e)).collect();
+        assert_eq!(results, vec![false, false]);
+
+        let dependents: Vec<&str> = graph.dependents("Werkzeug").iter().map(|e| e.from.as_str()).collect();
+        assert_eq!(dependents, vec!["flask"]);
+        assert!(graph.is_satisfied(graph.dependents("werkzeug")[0]));
+    }
+
+    #[test]
+    fn test_paths_to() {
+        let distributions = vec![
+            dist("boto3", "1.28.0", &["botocore<1.32.0,>=1.31.0"]),
+            dist("botocore", "1.31.0", &["urllib3<1.27,>=1.25.4"]),
+            dist("requests", "2.31.0", &["urllib3<3,>=1.21.1"]),
+            dist("urllib3", "1.26.18", &[]),
+            // A cycle reachable from a root
+            dist("a", "1.0", &["b"]),
+            dist("b", "1.0", &["a", "urllib3"]),
+        ];
+        let mut graph = DependencyGraph::from_installed(&distributions, None);
+        graph.roots.push("a".to_string());
+
+        let chains: Vec<Vec<&str>> = graph
+            .paths_to("URLLIB3")
+            .iter()
+            .map(|path| path.iter().map(|e| e.from.as_str()).collect())
+            .collect();
+        assert_eq!(chains, vec![vec!["boto3", "botocore"], vec!["requests"], vec!["a", "b"]]);
+        let own = graph.paths_to("boto3");
+        assert!(own.len() == 1 && own[0].is_empty());
+        assert!(graph.paths_to("missing").is_empty());
+    }
+}
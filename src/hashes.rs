@@ -0,0 +1,129 @@
+use anyhow::Result;
+use std::fmt;
+
+use crate::freeze::pinned_version;
+use crate::pep440::Pep440Version;
+use crate::pypi::{self, PypiClient};
+use crate::requirements::RequirementLine;
+
+/// Why a requirement would not pass `pip install --require-hashes`, or could
+/// install a file the index never published.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HashIssue {
+    /// The requirement is not pinned with `==`, so its files are unknown.
+    Unpinned,
+    /// The pinned requirement carries no hash.
+    Missing,
+    /// These hashes match none of the release's files on the index.
+    Mismatch(Vec<String>),
+    /// Only hashes of algorithms the index does not report were given.
+    Unverifiable(Vec<String>),
+}
+
+impl fmt::Display for HashIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            HashIssue::Unpinned => "unpinned",
+            HashIssue::Missing => "missing",
+            HashIssue::Mismatch(_) => "mismatch",
+            HashIssue::Unverifiable(_) => "unverifiable",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// The outcome for one requirement.
+#[derive(Debug, Clone)]
+pub struct HashCheck {
+    pub requirement: RequirementLine,
+    /// The pinned version, when there is one.
+    pub version: Option<Pep440Version>,
+    /// How many of the requirement's hashes match a file on the index.
+    pub matched: usize,
+    pub issue: Option<HashIssue>,
+}
+
+/// Checks the hashes of every requirement against the files the index lists
+/// for the pinned release.
+pub fn verify(client: &PypiClient, requirements: &[RequirementLine]) -> Result<Vec<HashCheck>> {
+    let pinned: Vec<(&RequirementLine, &Pep440Version)> = requirements
+        .iter()
+        .filter(|r| !r.hashes.is_empty())
+        .filter_map(|r| Some((r, pinned_version(r)?)))
+        .collect();
+    let releases: Vec<(&str, &Pep440Version)> =
+        pinned.iter().map(|(r, version)| (r.package.name.as_str(), *version)).collect();
+    let mut published = client.metadata_all(&releases, pypi::parse_hashes)?.into_iter();
+
+    let mut checks = Vec::new();
+    for requirement in requirements {
+        let version = pinned_version(requirement).cloned();
+        let (matched, issue) = match &version {
+            None => (0, Some(HashIssue::Unpinned)),
+            Some(_) if requirement.hashes.is_empty() => (0, Some(HashIssue::Missing)),
+            Some(_) => compare(&requirement.hashes, &published.next().unwrap_or_default()),
+        };
+        checks.push(HashCheck {
+            requirement: requirement.clone(),
+            version,
+            matched,
+            issue,
+        });
+    }
+    Ok(checks)
+}
+
+/// Compares declared hashes with the `sha256:` hashes of the published files,
+/// returning how many match and what is wrong. Algorithm names are matched
+/// case-insensitively, digests as given in lowercase hex.
+fn compare(declared: &[String], published: &[String]) -> (usize, Option<HashIssue>) {
+    let mut matched = 0;
+    let mut unknown = Vec::new();
+    let mut unverifiable = Vec::new();
+    for hash in declared {
+        let normalized = match hash.split_once(':') {
+            Some((algorithm, digest)) => format!("{}:{}", algorithm.to_ascii_lowercase(), digest.to_ascii_lowercase()),
+            None => hash.clone(),
+        };
+        if !normalized.starts_with("sha256:") {
+            unverifiable.push(hash.clone());
+        } else if published.contains(&normalized) {
+            matched += 1;
+        } else {
+            unknown.push(hash.clone());
+        }
+    }
+    let issue = if !unknown.is_empty() {
+        Some(HashIssue::Mismatch(unknown))
+    } else if matched == 0 {
+        Some(HashIssue::Unverifiable(unverifiable))
+    } else {
+        None
+    };
+    (matched, issue)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|v| v.to_string()).collect()
+    }
+
+    #[test]
+    fn test_compare() {
+        let published = strings(&["sha256:aa11", "sha256:bb22"]);
+        assert_eq!(compare(&strings(&["sha256:AA11", "sha256:bb22"]), &published), (2, None));
+        assert_eq!(
+            compare(&strings(&["sha256:aa11", "sha256:dead"]), &published),
+            (1, Some(HashIssue::Mismatch(strings(&["sha256:dead"]))))
+        );
+        // Other algorithms cannot be checked, but do not fail a verified requirement
+        assert_eq!(compare(&strings(&["sha256:aa11", "sha512:ff"]), &published), (1, None));
+        assert_eq!(
+            compare(&strings(&["sha512:ff"]), &published),
+            (0, Some(HashIssue::Unverifiable(strings(&["sha512:ff"]))))
+        );
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+
+use crate::conflict::ConflictReport;
+use crate::lock::{self, LockIssue, LockReport};
+use crate::prerelease::PreReleasePolicy;
+use crate::requirements;
+use crate::{conda, pipfile, pyproject, setup};
+
+/// What a file passed to the hook holds, by its name.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    /// `*.txt` and `*.in` files, such as requirements.txt or constraints.txt.
+    Requirements,
+    Pyproject,
+    Pipfile,
+    /// A conda environment.yml.
+    Conda,
+    /// A setuptools setup.cfg or setup.py.
+    Setup,
+    /// A poetry.lock, uv.lock or pdm.lock.
+    Lock,
+}
+
+impl FileKind {
+    /// The kind of `path`; `None` for files the hook has no check for.
+    pub fn detect(path: &Path) -> Option<FileKind> {
+        let name = path.file_name()?.to_str()?;
+        match name {
+            "pyproject.toml" => Some(FileKind::Pyproject),
+            "Pipfile" => Some(FileKind::Pipfile),
+            "environment.yml" | "environment.yaml" => Some(FileKind::Conda),
+            "setup.cfg" | "setup.py" => Some(FileKind::Setup),
+            _ if lock::LOCK_FILE_NAMES.contains(&name) => Some(FileKind::Lock),
+            _ if name.ends_with(".txt") || name.ends_with(".in") => Some(FileKind::Requirements),
+            _ => None,
+        }
+    }
+}
+
+/// One problem found by the hook, at the line of the file to fix.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Failure {
+    pub file: PathBuf,
+    pub line: usize,
+    pub column: Option<usize>,
+    pub message: String,
+}
+
+impl Failure {
+    /// Where to fix it, as `file:line:column`, or `file:line` without a column.
+    pub fn location(&self) -> String {
+        match self.column {
+            Some(column) => format!("{}:{}:{}", self.file.display(), self.line, column),
+            None => format!("{}:{}", self.file.display(), self.line),
+        }
+    }
+}
+
+/// Runs the checks fitting each file: every requirement source is checked for
+/// conflicts on its own, with the files it includes, and a pyproject.toml
+/// with a lock file next to it is checked against the lock, whichever of the
+/// two changed. Files that cannot be read are returned with their error
+/// instead of stopping the others.
+pub fn check(files: &[PathBuf], policy: PreReleasePolicy) -> (Vec<Failure>, Vec<(PathBuf, anyhow::Error)>) {
+    let mut failures = Vec::new();
+    let mut errors = Vec::new();
+    let mut locks: Vec<(PathBuf, PathBuf)> = Vec::new();
+    for file in files {
+        let declared = match FileKind::detect(file) {
+            Some(FileKind::Requirements) => requirements::parse_file(file),
+            Some(FileKind::Pyproject) => pyproject::parse_file(file),
+            Some(FileKind::Pipfile) => pipfile::parse_file(file),
+            Some(FileKind::Conda) => conda::parse_file(file),
+            Some(FileKind::Setup) => setup::parse_file(file),
+            Some(FileKind::Lock) => {
+                let manifest = file.with_file_name("pyproject.toml");
+                if manifest.is_file() {
+                    locks.push((manifest, file.clone()));
+                }
+                continue;
+            }
+            None => continue,
+        };
+        match declared {
+            Ok(declared) => failures.extend(conflicts(&ConflictReport::check_with(&declared, policy))),
+            Err(e) => errors.push((file.clone(), e)),
+        }
+        if FileKind::detect(file) == Some(FileKind::Pyproject) {
+            if let Some(lock_file) = lock::find_lock_file(file) {
+                locks.push((file.clone(), lock_file));
+            }
+        }
+    }
+
+    locks.sort();
+    locks.dedup();
+    for (manifest, lock_file) in locks {
+        match check_lock(&manifest, &lock_file) {
+            Ok(found) => failures.extend(found),
+            Err(e) => errors.push((lock_file, e)),
+        }
+    }
+    failures.sort();
+    failures.dedup();
+    (failures, errors)
+}
+
+/// A failure for every conflict, at the first requirement taking part in it.
+fn conflicts(report: &ConflictReport) -> Vec<Failure> {
+    report
+        .conflicts
+        .iter()
+        .filter_map(|conflict| {
+            let (first, others) = conflict.requirements.split_first()?;
+            let message = if others.is_empty() {
+                format!("no version of {} satisfies {}", conflict.name, first.package.version_spec())
+            } else {
+                let others: Vec<String> = others.iter().map(|other| format!("{} ({})", other.package, other.location())).collect();
+                format!("requirements on {} conflict: {} excludes {}", conflict.name, first.package, others.join(", "))
+            };
+            Some(Failure {
+                file: first.file.clone(),
+                line: first.line,
+                column: first.columns.as_ref().map(|columns| columns.start),
+                message,
+            })
+        })
+        .collect()
+}
+
+/// The requirements of `manifest` the lock file no longer satisfies.
+/// Conflicts among the requirements are reported by the manifest's own check.
+fn check_lock(manifest: &Path, lock_file: &Path) -> Result<Vec<Failure>> {
+    let report = LockReport::check(&pyproject::parse_file(manifest)?, &lock::parse_file(lock_file)?);
+    Ok(report
+        .issues
+        .iter()
+        .map(|issue| {
+            let requirement = issue.requirement();
+            let message = match issue {
+                LockIssue::Stale { locked, .. } => {
+                    let versions: Vec<String> = locked.iter().map(|pin| pin.version.to_string()).collect();
+                    format!("{} is locked at {} in {}", requirement.package, versions.join(", "), lock_file.display())
+                }
+                LockIssue::Missing { .. } => format!("{} is missing from {}", requirement.package, lock_file.display()),
+            };
+            Failure {
+                file: requirement.file.clone(),
+                line: requirement.line,
+                column: requirement.columns.as_ref().map(|columns| columns.start),
+                message,
+            }
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn test_check() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("pyhelper-hook-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        fs::write(dir.join("requirements.txt"), "flask\nsix<1\nsix>=2\n")?;
+        fs::write(dir.join("pyproject.toml"), "[project]\nname = \"demo\"\ndependencies = [\"attrs>=23\", \"rich\"]\n")?;
+        fs::write(
+            dir.join("uv.lock"),
+            "[[package]]\nname = \"attrs\"\nversion = \"22.2.0\"\n\n[[package]]\nname = \"demo\"\nversion = \"0.1.0\"\n",
+        )?;
+        fs::write(dir.join("broken.txt"), "six>>1\n")?;
+
+        let files = ["requirements.txt", "uv.lock", "pyproject.toml", "broken.txt", "README.md"].map(|name| dir.join(name));
+        let (failures, errors) = check(&files, PreReleasePolicy::default());
+        let found: Vec<(String, usize)> =
+            failures.iter().map(|f| (f.file.file_name().unwrap().to_string_lossy().into_owned(), f.line)).collect();
+        assert_eq!(found, vec![("pyproject.toml".to_string(), 3), ("pyproject.toml".to_string(), 3), ("requirements.txt".to_string(), 2)]);
+        assert!(failures[0].message.contains("attrs >=23 is locked at 22.2.0"), "{}", failures[0].message);
+        assert!(failures[1].message.contains("rich * is missing from"), "{}", failures[1].message);
+        assert!(failures[2].message.starts_with("requirements on six conflict: six <1 excludes six >=2"), "{}", failures[2].message);
+        assert!(failures[2].location().ends_with("requirements.txt:2:1"), "{}", failures[2].location());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, dir.join("broken.txt"));
+
+        assert_eq!(FileKind::detect(Path::new("docs/requirements-dev.in")), Some(FileKind::Requirements));
+        assert_eq!(FileKind::detect(Path::new("setup.py")), Some(FileKind::Setup));
+        assert_eq!(FileKind::detect(Path::new("tox.ini")), None);
+        fs::remove_dir_all(&dir)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,181 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Deserializer};
+use std::fmt;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use toml::value::Datetime;
+
+use crate::error::PyhelperError;
+use crate::output::sbom::timestamp;
+use crate::package::canonicalize_name;
+
+/// An acknowledged problem that should no longer fail a run: everything about
+/// a package (`setuptools`), or one rule of it (`requests==version-conflict`,
+/// `urllib3==GHSA-v845-jxx5-vc9f`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct IgnoreRule {
+    /// PEP 503 normalized package name.
+    pub package: String,
+    /// The check rule, advisory ID or alias acknowledged; `None` for the whole package.
+    pub rule: Option<String>,
+    /// The last day the rule applies.
+    pub expires: Option<Datetime>,
+    /// Why the problem is acceptable.
+    pub reason: Option<String>,
+}
+
+impl IgnoreRule {
+    /// Whether the rule is past its expiry date; `today` is `YYYY-MM-DD`.
+    pub fn is_expired(&self, today: &str) -> bool {
+        self.expires.as_ref().and_then(|expires| expires.date).is_some_and(|date| date.to_string().as_str() < today)
+    }
+
+    /// Whether the rule leaves `package` out entirely.
+    pub fn covers_package(&self, package: &str) -> bool {
+        self.rule.is_none() && self.package == canonicalize_name(package)
+    }
+
+    /// Whether the rule acknowledges a problem of `package` known by any of `ids`.
+    pub fn covers(&self, package: &str, ids: &[&str]) -> bool {
+        self.package == canonicalize_name(package)
+            && self.rule.as_ref().is_none_or(|rule| ids.iter().any(|id| id.eq_ignore_ascii_case(rule)))
+    }
+}
+
+impl FromStr for IgnoreRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (package, rule) = match s.split_once("==") {
+            Some((package, rule)) => (package.trim(), Some(rule.trim())),
+            None => (s.trim(), None),
+        };
+        if package.is_empty() || rule.is_some_and(str::is_empty) {
+            bail!(PyhelperError::Invalid(format!("Invalid ignore rule: {} (expected PACKAGE or PACKAGE==RULE)", s)));
+        }
+        Ok(IgnoreRule {
+            package: canonicalize_name(package),
+            rule: rule.map(str::to_string),
+            expires: None,
+            reason: None,
+        })
+    }
+}
+
+impl fmt::Display for IgnoreRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.rule {
+            Some(rule) => write!(f, "{}=={}", self.package, rule),
+            None => write!(f, "{}", self.package),
+        }
+    }
+}
+
+/// Rules are written as a string, or as a table adding an expiry date and a
+/// justification: `{ rule = "urllib3==GHSA-...", expires = 2025-06-30, reason = "..." }`.
+impl<'de> Deserialize<'de> for IgnoreRule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Entry {
+            Short(String),
+            Full {
+                rule: String,
+                expires: Option<Datetime>,
+                reason: Option<String>,
+            },
+        }
+
+        let (rule, expires, reason) = match Entry::deserialize(deserializer)? {
+            Entry::Short(rule) => (rule, None, None),
+            Entry::Full { rule, expires, reason } => (rule, expires, reason),
+        };
+        let mut parsed: IgnoreRule = rule.parse().map_err(serde::de::Error::custom)?;
+        parsed.expires = expires;
+        parsed.reason = reason;
+        Ok(parsed)
+    }
+}
+
+/// A problem left out of a report because a rule acknowledged it.
+#[derive(Debug, Clone)]
+pub struct Acknowledged {
+    pub package: String,
+    /// The rule or advisory ID of the problem.
+    pub id: String,
+    pub by: IgnoreRule,
+}
+
+/// Whether any of the rules leaves `package` out entirely.
+pub fn ignores_package(rules: &[IgnoreRule], package: &str) -> bool {
+    rules.iter().any(|rule| rule.covers_package(package))
+}
+
+/// The first rule acknowledging a problem of `package` known by any of `ids`.
+pub fn find<'a>(rules: &'a [IgnoreRule], package: &str, ids: &[&str]) -> Option<&'a IgnoreRule> {
+    rules.iter().find(|rule| rule.covers(package, ids))
+}
+
+/// Whether a rule acknowledges the problem of `package` known by any of
+/// `ids`, recording it in `acknowledged` when one does. The first ID is the
+/// one recorded.
+pub fn acknowledge(rules: &[IgnoreRule], package: &str, ids: &[&str], acknowledged: &mut Vec<Acknowledged>) -> bool {
+    let Some(rule) = find(rules, package, ids) else {
+        return false;
+    };
+    acknowledged.push(Acknowledged {
+        package: package.to_string(),
+        id: ids.first().map(|id| id.to_string()).unwrap_or_default(),
+        by: rule.clone(),
+    });
+    true
+}
+
+/// Today's date in UTC, as `YYYY-MM-DD`.
+pub fn today() -> String {
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+    timestamp(now)[..10].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rules() -> Result<()> {
+        let rule: IgnoreRule = "Urllib3==ghsa-v845-jxx5-vc9f".parse()?;
+        assert_eq!(rule.to_string(), "urllib3==ghsa-v845-jxx5-vc9f");
+        assert!(rule.covers("urllib3", &["PYSEC-2023-192", "GHSA-v845-jxx5-vc9f"]));
+        assert!(!rule.covers("urllib3", &["GHSA-g4mx-q9vg-27p4"]));
+        assert!(!rule.covers_package("urllib3"));
+
+        let package: IgnoreRule = "setup_tools".parse()?;
+        assert!(package.covers_package("Setup.Tools"));
+        assert!(package.covers("setup-tools", &["version-conflict"]));
+        assert!("==x".parse::<IgnoreRule>().is_err());
+        assert!("pkg==".parse::<IgnoreRule>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_deserialize() -> Result<()> {
+        #[derive(Deserialize)]
+        struct List {
+            ignore: Vec<IgnoreRule>,
+        }
+        let list: List = toml::from_str(
+            r#"ignore = [
+    "six",
+    { rule = "requests==version-conflict", expires = 2024-03-31, reason = "fixed by the next release" },
+]"#,
+        )?;
+        assert_eq!(list.ignore[0], "six".parse()?);
+        let rule = &list.ignore[1];
+        assert_eq!(rule.rule.as_deref(), Some("version-conflict"));
+        assert_eq!(rule.reason.as_deref(), Some("fixed by the next release"));
+        assert!(!rule.is_expired("2024-03-31"));
+        assert!(rule.is_expired("2024-04-01"));
+        assert!(!list.ignore[0].is_expired("2024-04-01"));
+        Ok(())
+    }
+}
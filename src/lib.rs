@@ -0,0 +1,85 @@
+pub mod audit;
+#[cfg(feature = "network")]
+pub mod auth;
+pub mod baseline;
+pub mod batch;
+#[cfg(feature = "network")]
+pub mod cache;
+pub mod conda;
+pub mod config;
+pub mod conflict;
+#[cfg(feature = "network")]
+pub mod confusion;
+pub mod convert;
+pub mod diff;
+pub mod drift;
+#[cfg(feature = "docker")]
+pub mod docker;
+pub mod env;
+pub mod error;
+pub mod explain;
+#[cfg(feature = "network")]
+pub mod fetch;
+pub mod filename;
+pub mod fmt;
+pub mod freeze;
+pub mod graph;
+#[cfg(feature = "network")]
+pub mod hashes;
+pub mod hook;
+pub mod ignore;
+pub mod license;
+pub mod lint;
+pub mod local;
+pub mod lock;
+pub mod lsp;
+#[cfg(feature = "network")]
+pub mod maintenance;
+pub mod marker;
+pub mod merge;
+#[cfg(feature = "network")]
+pub mod notify;
+#[cfg(feature = "network")]
+pub mod outdated;
+pub mod output;
+pub mod package;
+pub mod pep440;
+pub mod pipfile;
+pub mod plugin;
+pub mod poetry;
+pub mod prerelease;
+pub mod pypi;
+pub mod pyproject;
+pub mod range;
+pub mod requirements;
+#[cfg(feature = "network")]
+pub mod resolve;
+pub mod scan;
+pub mod selftest;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod setup;
+pub mod severity;
+pub mod simple;
+#[cfg(feature = "network")]
+pub mod simulate;
+pub mod specifier;
+pub mod suggest;
+pub mod sync;
+pub mod tags;
+pub mod timeline;
+pub mod typosquat;
+#[cfg(feature = "tui")]
+pub mod tui;
+#[cfg(feature = "watch")]
+pub mod watch;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod workspace;
+
+pub use conflict::{ConflictReport, PackageConflict};
+pub use error::PyhelperError;
+pub use package::{canonicalize_name, parse_requirement_lossy, LossyRequirement, PythonPackage};
+pub use pep440::Pep440Version;
+pub use requirements::RequirementLine;
+pub use specifier::{Operator, Specifier, SpecifierSet};
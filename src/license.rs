@@ -0,0 +1,424 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::error::PyhelperError;
+use crate::package::canonicalize_name;
+use crate::pep440::Pep440Version;
+use crate::pypi::LicenseInfo;
+#[cfg(feature = "network")]
+use crate::pypi::PypiClient;
+
+/// SPDX identifiers of the common `License ::` trove classifiers.
+const CLASSIFIERS: &[(&str, &str)] = &[
+    ("MIT License", "MIT"),
+    ("Apache Software License", "Apache-2.0"),
+    ("ISC License (ISCL)", "ISC"),
+    ("Mozilla Public License 2.0 (MPL 2.0)", "MPL-2.0"),
+    ("GNU General Public License v2 (GPLv2)", "GPL-2.0-only"),
+    ("GNU General Public License v2 or later (GPLv2+)", "GPL-2.0-or-later"),
+    ("GNU General Public License v3 (GPLv3)", "GPL-3.0-only"),
+    ("GNU General Public License v3 or later (GPLv3+)", "GPL-3.0-or-later"),
+    ("GNU Lesser General Public License v2 (LGPLv2)", "LGPL-2.0-only"),
+    ("GNU Lesser General Public License v2 or later (LGPLv2+)", "LGPL-2.0-or-later"),
+    ("GNU Lesser General Public License v3 (LGPLv3)", "LGPL-3.0-only"),
+    ("GNU Lesser General Public License v3 or later (LGPLv3+)", "LGPL-3.0-or-later"),
+    ("GNU Affero General Public License v3", "AGPL-3.0-only"),
+    ("GNU Affero General Public License v3 or later (AGPLv3+)", "AGPL-3.0-or-later"),
+    ("Python Software Foundation License", "PSF-2.0"),
+    ("The Unlicense (Unlicense)", "Unlicense"),
+    ("Eclipse Public License 2.0 (EPL-2.0)", "EPL-2.0"),
+    ("zlib/libpng License", "Zlib"),
+];
+
+/// SPDX identifiers of license names commonly written in the `License` field.
+const NAMES: &[(&str, &str)] = &[
+    ("mit license", "MIT"),
+    ("apache 2.0", "Apache-2.0"),
+    ("apache license 2.0", "Apache-2.0"),
+    ("apache license, version 2.0", "Apache-2.0"),
+    ("apache software license", "Apache-2.0"),
+    ("bsd license", "BSD"),
+    ("new bsd license", "BSD-3-Clause"),
+    ("3-clause bsd license", "BSD-3-Clause"),
+    ("psf", "PSF-2.0"),
+    ("mpl 2.0", "MPL-2.0"),
+];
+
+/// Longer values of the `License` field are taken to be the license text itself.
+const MAX_NAME_LENGTH: usize = 64;
+
+/// Where a license was read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LicenseSource {
+    Expression,
+    Classifier,
+    Field,
+}
+
+impl fmt::Display for LicenseSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            LicenseSource::Expression => "expression",
+            LicenseSource::Classifier => "classifier",
+            LicenseSource::Field => "license",
+        })
+    }
+}
+
+/// The license of a release as an SPDX expression where possible: the PEP 639
+/// expression, else its trove classifiers (alternatives joined with `OR`), else
+/// a short `License` field. `None` when the release declares none.
+pub fn identify(info: &LicenseInfo) -> Option<(String, LicenseSource)> {
+    if let Some(expression) = &info.expression {
+        return Some((expression.clone(), LicenseSource::Expression));
+    }
+    let classifiers: Vec<String> = info
+        .classifiers
+        .iter()
+        .filter_map(|classifier| {
+            let name = classifier.rsplit(" :: ").next()?;
+            match CLASSIFIERS.iter().find(|(known, _)| *known == name) {
+                Some((_, spdx)) => Some(spdx.to_string()),
+                // `License :: OSI Approved` alone says nothing about which one
+                None if name == "OSI Approved" || name == "License" => None,
+                None => Some(name.to_string()),
+            }
+        })
+        .collect();
+    if !classifiers.is_empty() {
+        return Some((classifiers.join(" OR "), LicenseSource::Classifier));
+    }
+    let field = info
+        .license
+        .as_deref()
+        .filter(|field| field.len() <= MAX_NAME_LENGTH && !field.contains('\n'))
+        .filter(|field| !field.eq_ignore_ascii_case("unknown"))?;
+    let spdx = NAMES
+        .iter()
+        .find(|(name, _)| field.eq_ignore_ascii_case(name))
+        .map_or(field, |(_, spdx)| spdx);
+    Some((spdx.to_string(), LicenseSource::Field))
+}
+
+/// The license of one package and how it fares against a policy.
+#[derive(Debug, Clone)]
+pub struct LicenseEntry {
+    pub name: String,
+    pub version: Pep440Version,
+    pub license: Option<(String, LicenseSource)>,
+    pub verdict: Verdict,
+    pub violation: bool,
+}
+
+#[cfg(feature = "network")]
+/// Fetches the license of every release and judges it against `policy`.
+pub fn inventory(
+    client: &PypiClient,
+    packages: &[(String, Pep440Version)],
+    policy: &LicensePolicy,
+) -> Result<Vec<LicenseEntry>> {
+    let releases: Vec<(&str, &Pep440Version)> = packages.iter().map(|(name, version)| (name.as_str(), version)).collect();
+    let infos = client.licenses_all(&releases)?;
+    Ok(packages
+        .iter()
+        .zip(infos)
+        .map(|((name, version), info)| {
+            let license = identify(&info);
+            let verdict = policy.check(name, license.as_ref().map(|(license, _)| license.as_str()));
+            LicenseEntry {
+                name: name.clone(),
+                version: version.clone(),
+                license,
+                verdict,
+                violation: verdict.is_violation(policy),
+            }
+        })
+        .collect())
+}
+
+/// A parsed SPDX license expression. `WITH` exceptions are kept as part of the
+/// license they modify.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expression {
+    License(String),
+    And(Vec<Expression>),
+    Or(Vec<Expression>),
+}
+
+impl Expression {
+    /// Parses an expression such as `MIT OR (Apache-2.0 AND BSD-2-Clause)`.
+    /// `AND` binds tighter than `OR`; a `/` between names is read as `OR`, as
+    /// in `MIT/Apache-2.0`.
+    pub fn parse(input: &str) -> Result<Self> {
+        let spaced = input.replace('(', " ( ").replace(')', " ) ").replace('/', " OR ");
+        let tokens: Vec<&str> = spaced.split_whitespace().collect();
+        let mut position = 0;
+        let expression = parse_or(&tokens, &mut position)?;
+        if position != tokens.len() {
+            bail!(PyhelperError::License(format!("Unexpected `{}` in license expression: {}", tokens[position], input)));
+        }
+        Ok(expression)
+    }
+
+    fn permits(&self, policy: &LicensePolicy) -> bool {
+        match self {
+            Expression::License(license) => policy.permits(license),
+            Expression::And(terms) => terms.iter().all(|term| term.permits(policy)),
+            Expression::Or(terms) => terms.iter().any(|term| term.permits(policy)),
+        }
+    }
+}
+
+fn parse_or(tokens: &[&str], position: &mut usize) -> Result<Expression> {
+    let mut terms = vec![parse_and(tokens, position)?];
+    while tokens.get(*position).is_some_and(|t| t.eq_ignore_ascii_case("OR")) {
+        *position += 1;
+        terms.push(parse_and(tokens, position)?);
+    }
+    Ok(if terms.len() == 1 { terms.remove(0) } else { Expression::Or(terms) })
+}
+
+fn parse_and(tokens: &[&str], position: &mut usize) -> Result<Expression> {
+    let mut terms = vec![parse_term(tokens, position)?];
+    while tokens.get(*position).is_some_and(|t| t.eq_ignore_ascii_case("AND")) {
+        *position += 1;
+        terms.push(parse_term(tokens, position)?);
+    }
+    Ok(if terms.len() == 1 { terms.remove(0) } else { Expression::And(terms) })
+}
+
+fn parse_term(tokens: &[&str], position: &mut usize) -> Result<Expression> {
+    let operator = |token: &str| ["AND", "OR", "WITH"].iter().any(|op| token.eq_ignore_ascii_case(op));
+    match tokens.get(*position) {
+        Some(&"(") => {
+            *position += 1;
+            let expression = parse_or(tokens, position)?;
+            if tokens.get(*position) != Some(&")") {
+                bail!(PyhelperError::License("Unclosed parenthesis in license expression".to_string()));
+            }
+            *position += 1;
+            Ok(expression)
+        }
+        Some(token) if *token != ")" && !operator(token) => {
+            // Names written with spaces, e.g. `BSD License`, form one license
+            let mut words = vec![*token];
+            *position += 1;
+            while let Some(token) = tokens.get(*position).filter(|t| !operator(t) && **t != "(" && **t != ")") {
+                words.push(token);
+                *position += 1;
+            }
+            let mut license = words.join(" ");
+            if tokens.get(*position).is_some_and(|t| t.eq_ignore_ascii_case("WITH")) {
+                let exception = tokens
+                    .get(*position + 1)
+                    .ok_or_else(|| PyhelperError::License("Missing exception after WITH".to_string()))?;
+                license = format!("{} WITH {}", license, exception);
+                *position += 2;
+            }
+            Ok(Expression::License(license))
+        }
+        Some(token) => Err(PyhelperError::License(format!("Unexpected `{}` in license expression", token)).into()),
+        None => Err(PyhelperError::License("Incomplete license expression".to_string()).into()),
+    }
+}
+
+/// What to do with packages whose license cannot be determined.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UnknownLicenses {
+    Allow,
+    #[default]
+    Warn,
+    Deny,
+}
+
+/// Licenses a project may depend on, read from a TOML policy file:
+///
+/// ```toml
+/// allow = ["MIT", "BSD-*", "Apache-2.0"]
+/// deny = ["GPL-3.0*", "AGPL-*"]
+/// ignore = ["internal-tools"]
+/// unknown = "deny"
+/// ```
+///
+/// Patterns match case-insensitively and may end in `*`. A license is
+/// permitted when it matches no `deny` pattern and, if `allow` is given, one of
+/// its patterns.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LicensePolicy {
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(default)]
+    pub deny: Vec<String>,
+    /// Packages exempt from the policy.
+    #[serde(default)]
+    pub ignore: Vec<String>,
+    #[serde(default)]
+    pub unknown: UnknownLicenses,
+}
+
+/// How a package's license fares against a policy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    Allowed,
+    Denied,
+    Unknown,
+    Ignored,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Verdict::Allowed => "allowed",
+            Verdict::Denied => "denied",
+            Verdict::Unknown => "unknown",
+            Verdict::Ignored => "ignored",
+        })
+    }
+}
+
+impl Verdict {
+    /// Whether the verdict fails the check under `policy`.
+    pub fn is_violation(&self, policy: &LicensePolicy) -> bool {
+        match self {
+            Verdict::Denied => true,
+            Verdict::Unknown => policy.unknown == UnknownLicenses::Deny,
+            Verdict::Allowed | Verdict::Ignored => false,
+        }
+    }
+}
+
+impl LicensePolicy {
+    pub fn parse(input: &str) -> Result<Self> {
+        Ok(toml::from_str(input)?)
+    }
+
+    pub fn parse_file(path: &Path) -> Result<Self> {
+        let content = fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+        Self::parse(&content).with_context(|| format!("Invalid license policy: {}", path.display()))
+    }
+
+    /// Judges a package's license, given as an SPDX expression. Expressions
+    /// that do not parse are judged as a single license.
+    pub fn check(&self, name: &str, license: Option<&str>) -> Verdict {
+        let name = canonicalize_name(name);
+        if self.ignore.iter().any(|ignored| canonicalize_name(ignored) == name) {
+            return Verdict::Ignored;
+        }
+        let Some(license) = license else {
+            return match self.unknown {
+                UnknownLicenses::Allow => Verdict::Allowed,
+                _ => Verdict::Unknown,
+            };
+        };
+        let expression = Expression::parse(license).unwrap_or_else(|_| Expression::License(license.to_string()));
+        if expression.permits(self) {
+            Verdict::Allowed
+        } else {
+            Verdict::Denied
+        }
+    }
+
+    fn permits(&self, license: &str) -> bool {
+        let matches = |patterns: &[String]| patterns.iter().any(|pattern| matches_pattern(pattern, license));
+        !matches(&self.deny) && (self.allow.is_empty() || matches(&self.allow))
+    }
+}
+
+fn matches_pattern(pattern: &str, license: &str) -> bool {
+    let pattern = pattern.to_ascii_lowercase();
+    let license = license.to_ascii_lowercase();
+    match pattern.strip_suffix('*') {
+        Some(prefix) => license.starts_with(prefix),
+        None => license == pattern,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_identify() {
+        let info = |expression: Option<&str>, license: Option<&str>, classifiers: &[&str]| LicenseInfo {
+            expression: expression.map(str::to_string),
+            license: license.map(str::to_string),
+            classifiers: classifiers.iter().map(|c| c.to_string()).collect(),
+        };
+        let spdx = |info: LicenseInfo| identify(&info).map(|(license, source)| (license, source.to_string()));
+
+        assert_eq!(
+            spdx(info(Some("MIT OR Apache-2.0"), Some("MIT"), &[])),
+            Some(("MIT OR Apache-2.0".into(), "expression".into()))
+        );
+        assert_eq!(
+            spdx(info(
+                None,
+                Some("Apache 2.0"),
+                &["License :: OSI Approved", "License :: OSI Approved :: Apache Software License"]
+            )),
+            Some(("Apache-2.0".into(), "classifier".into()))
+        );
+        assert_eq!(spdx(info(None, Some("Apache 2.0"), &[])), Some(("Apache-2.0".into(), "license".into())));
+        let text = "Copyright (c) 2024\n\nPermission is hereby granted, free of charge, ...";
+        assert_eq!(spdx(info(None, Some(text), &[])), None);
+        assert_eq!(spdx(info(None, Some("UNKNOWN"), &[])), None);
+    }
+
+    #[test]
+    fn test_expression() -> Result<()> {
+        let license = |name: &str| Expression::License(name.to_string());
+        assert_eq!(
+            Expression::parse("MIT OR (Apache-2.0 AND BSD-2-Clause)")?,
+            Expression::Or(vec![
+                license("MIT"),
+                Expression::And(vec![license("Apache-2.0"), license("BSD-2-Clause")])
+            ])
+        );
+        assert_eq!(
+            Expression::parse("GPL-2.0-or-later WITH Classpath-exception-2.0")?,
+            license("GPL-2.0-or-later WITH Classpath-exception-2.0")
+        );
+        assert_eq!(
+            Expression::parse("MIT/Apache-2.0")?,
+            Expression::Or(vec![license("MIT"), license("Apache-2.0")])
+        );
+        assert_eq!(Expression::parse("BSD License")?, license("BSD License"));
+        assert!(Expression::parse("(MIT").is_err());
+        assert!(Expression::parse("MIT AND").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_policy() -> Result<()> {
+        let policy = LicensePolicy::parse(
+            r#"
+            allow = ["MIT", "BSD-*", "Apache-2.0", "LGPL-*"]
+            deny = ["GPL-3.0*", "LGPL-3.0*"]
+            ignore = ["Internal_Tools"]
+            unknown = "deny"
+            "#,
+        )?;
+        assert_eq!(policy.check("requests", Some("Apache-2.0")), Verdict::Allowed);
+        assert_eq!(policy.check("foo", Some("bsd-3-clause")), Verdict::Allowed);
+        assert_eq!(policy.check("foo", Some("GPL-3.0-only")), Verdict::Denied);
+        assert_eq!(policy.check("foo", Some("GPL-3.0-only OR MIT")), Verdict::Allowed);
+        assert_eq!(policy.check("foo", Some("GPL-3.0-only AND MIT")), Verdict::Denied);
+        assert_eq!(policy.check("foo", Some("LGPL-3.0-or-later")), Verdict::Denied);
+        assert_eq!(policy.check("foo", Some("MPL-2.0")), Verdict::Denied);
+        assert_eq!(policy.check("internal-tools", Some("Proprietary")), Verdict::Ignored);
+
+        let unknown = policy.check("foo", None);
+        assert_eq!(unknown, Verdict::Unknown);
+        assert!(unknown.is_violation(&policy));
+        assert!(!unknown.is_violation(&LicensePolicy::default()));
+        assert_eq!(LicensePolicy::default().check("foo", Some("GPL-3.0-only")), Verdict::Allowed);
+
+        assert!(LicensePolicy::parse("alow = []").is_err());
+        Ok(())
+    }
+}
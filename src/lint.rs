@@ -0,0 +1,376 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::PyhelperError;
+use crate::merge;
+use crate::package::{matches_name, PythonPackage};
+use crate::pep440::Pep440Version;
+use crate::range::IntervalSet;
+use crate::requirements::RequirementLine;
+use crate::specifier::{Operator, Specifier};
+
+/// How the requirements on a package listed more than once relate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Every listing allows the same versions, so all but one can go.
+    Redundant,
+    /// The listings allow different versions, but some satisfy all of them.
+    Consistent,
+    /// No version satisfies all of them.
+    Conflicting,
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::Redundant => write!(f, "redundant"),
+            Verdict::Consistent => write!(f, "consistent"),
+            Verdict::Conflicting => write!(f, "conflicting"),
+        }
+    }
+}
+
+/// A package listed more than once under the same marker, across a file and
+/// its includes.
+#[derive(Debug, Clone)]
+pub struct Duplicate {
+    pub name: String,
+    /// Every listing, in the order pip reads them.
+    pub lines: Vec<RequirementLine>,
+    pub verdict: Verdict,
+    /// Pairs of indexes into `lines`: the first listing allows every version
+    /// the second allows, so the second decides and the first has no effect.
+    pub shadowed: Vec<(usize, usize)>,
+    /// The single requirement the listings amount to, unless they conflict.
+    pub combined: Option<PythonPackage>,
+}
+
+/// Finds every package required more than once under the same marker.
+/// Listings under different markers usually target different environments
+/// on purpose, and are left alone.
+pub fn duplicates(requirements: &[RequirementLine]) -> Vec<Duplicate> {
+    let mut groups: BTreeMap<(String, String), Vec<&RequirementLine>> = BTreeMap::new();
+    for requirement in requirements {
+        let marker = requirement.package.marker.as_ref().map(|m| m.to_string()).unwrap_or_default();
+        groups.entry((requirement.package.canonical_name(), marker)).or_default().push(requirement);
+    }
+
+    let mut found: Vec<Duplicate> = groups
+        .into_values()
+        .filter(|lines| lines.len() > 1)
+        .map(|lines| {
+            let ranges: Vec<IntervalSet<Pep440Version>> = lines.iter().map(|line| line.package.specifiers.range()).collect();
+            let combined = ranges.iter().skip(1).fold(ranges[0].clone(), |all, range| all.intersect(range));
+            let verdict = if combined.is_empty() {
+                Verdict::Conflicting
+            } else if ranges.iter().all(|range| covers(range, &ranges[0]) && covers(&ranges[0], range)) {
+                Verdict::Redundant
+            } else {
+                Verdict::Consistent
+            };
+
+            let mut shadowed = Vec::new();
+            if verdict == Verdict::Consistent {
+                for (i, wider) in ranges.iter().enumerate() {
+                    // Only name the first of several listings shadowing it
+                    let by = ranges.iter().enumerate().find(|(j, narrower)| *j != i && covers(wider, narrower) && !covers(narrower, wider));
+                    if let Some((j, _)) = by {
+                        shadowed.push((i, j));
+                    }
+                }
+            }
+            let packages: Vec<PythonPackage> = lines.iter().map(|line| line.package.clone()).collect();
+            Duplicate {
+                name: lines[0].package.name.clone(),
+                lines: lines.into_iter().cloned().collect(),
+                verdict,
+                shadowed,
+                combined: (verdict != Verdict::Conflicting).then(|| merge::intersect(&packages).ok()).flatten(),
+            }
+        })
+        .collect();
+    found.sort_by_key(|duplicate| (duplicate.lines[0].file.clone(), duplicate.lines[0].line));
+    found
+}
+
+/// How the requirements of a project must constrain their packages.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PinRule {
+    /// One exact version, as applications pin their dependencies.
+    Exact,
+    /// Anything but one exact version, as libraries leave room for the
+    /// other packages of an environment.
+    Range,
+    /// No rule.
+    Any,
+}
+
+impl FromStr for PinRule {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "exact" => Ok(PinRule::Exact),
+            "range" => Ok(PinRule::Range),
+            "any" => Ok(PinRule::Any),
+            _ => Err(PyhelperError::Invalid(format!("Invalid pin rule: {} (expected exact, range or any)", s)).into()),
+        }
+    }
+}
+
+impl fmt::Display for PinRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PinRule::Exact => write!(f, "exact"),
+            PinRule::Range => write!(f, "range"),
+            PinRule::Any => write!(f, "any"),
+        }
+    }
+}
+
+/// The pin rule of the packages matching a name pattern, written
+/// `PACKAGE=RULE`; the pattern may end in `*` to match a prefix.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PinOverride {
+    pub pattern: String,
+    pub rule: PinRule,
+}
+
+impl FromStr for PinOverride {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let (pattern, rule) = s
+            .split_once('=')
+            .filter(|(pattern, _)| !pattern.trim().is_empty())
+            .ok_or_else(|| PyhelperError::Invalid(format!("Invalid pin policy: {} (expected PACKAGE=RULE)", s)))?;
+        Ok(PinOverride {
+            pattern: pattern.trim().to_string(),
+            rule: rule.parse()?,
+        })
+    }
+}
+
+impl fmt::Display for PinOverride {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}={}", self.pattern, self.rule)
+    }
+}
+
+/// A requirement breaking the pin rule of its package.
+#[derive(Debug, Clone)]
+pub struct PinViolation {
+    pub line: RequirementLine,
+    pub rule: PinRule,
+    /// The pattern of the override the rule comes from, if any.
+    pub by: Option<String>,
+}
+
+impl fmt::Display for PinViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let requirement = merge::line(&self.line.package);
+        match self.rule {
+            PinRule::Exact => write!(f, "{} must be pinned to one version with ==", requirement)?,
+            _ => write!(f, "{} must allow a range of versions, not one", requirement)?,
+        }
+        if let Some(pattern) = &self.by {
+            write!(f, " (pin policy of {})", pattern)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether a requirement allows a single version: one `==` clause without
+/// a wildcard, or one `===` clause.
+pub fn is_pinned(package: &PythonPackage) -> bool {
+    match package.specifiers.specifiers.as_slice() {
+        [spec] => (spec.op == Operator::Equal && !spec.wildcard) || spec.op == Operator::ArbitraryEqual,
+        _ => false,
+    }
+}
+
+/// The requirements breaking their pin rule: that of the most specific
+/// override matching the package (a name before any prefix, a longer prefix
+/// before a shorter one), else `default`. Direct references are never
+/// checked, as they name one file rather than versions.
+pub fn pin_violations(requirements: &[RequirementLine], default: PinRule, overrides: &[PinOverride]) -> Vec<PinViolation> {
+    requirements
+        .iter()
+        .filter(|line| line.package.url.is_none())
+        .filter_map(|line| {
+            let chosen = overrides
+                .iter()
+                .filter(|o| matches_name(&o.pattern, &line.package.name))
+                .max_by_key(|o| o.pattern.strip_suffix('*').map_or(usize::MAX, str::len));
+            let rule = chosen.map_or(default, |o| o.rule);
+            let broken = match rule {
+                PinRule::Exact => !is_pinned(&line.package),
+                PinRule::Range => is_pinned(&line.package),
+                PinRule::Any => false,
+            };
+            broken.then(|| PinViolation {
+                line: line.clone(),
+                rule,
+                by: chosen.map(|o| o.pattern.clone()),
+            })
+        })
+        .collect()
+}
+
+/// A library requirement capping its package below the next major release,
+/// which leaves out releases that may well work before anyone has tried them.
+#[derive(Debug, Clone)]
+pub struct Cap {
+    pub line: RequirementLine,
+    /// The clause setting the cap, `<N` or an `~=X.Y` implying `<X+1`.
+    pub clause: Specifier,
+}
+
+impl fmt::Display for Cap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let version = &self.clause.version;
+        let major = match self.clause.op {
+            Operator::Compatible => version.release[0] + 1,
+            _ => version.release[0],
+        };
+        // Versions of another epoch are another numbering altogether, as in `<1!2`
+        let major = if version.epoch == 0 { major.to_string() } else { format!("{}!{}", version.epoch, major) };
+        write!(f, "{} caps {} below major version {}", merge::line(&self.line.package), self.line.package.name, major)
+    }
+}
+
+/// The clause of a requirement capping it below a major release: `<N`,
+/// `<N.0` and the like (also in the pre-release forms such as `<N.0.dev0`),
+/// or `~=X.Y`, which allows `X.*` only.
+pub fn major_cap(package: &PythonPackage) -> Option<&Specifier> {
+    package.specifiers.specifiers.iter().find(|spec| {
+        let release = &spec.version.release;
+        match spec.op {
+            Operator::Less => spec.literal.is_none() && release.iter().skip(1).all(|part| *part == 0) && release[0] > 0,
+            Operator::Compatible => release.len() == 2,
+            _ => false,
+        }
+    })
+}
+
+/// The requirements capping a package below its next major release, except
+/// those on the packages matching one of the `allowed` patterns, such as
+/// projects known to break their API without a major release.
+pub fn caps(requirements: &[RequirementLine], allowed: &[String]) -> Vec<Cap> {
+    requirements
+        .iter()
+        .filter(|line| !allowed.iter().any(|pattern| matches_name(pattern, &line.package.name)))
+        .filter_map(|line| {
+            Some(Cap {
+                clause: major_cap(&line.package)?.clone(),
+                line: line.clone(),
+            })
+        })
+        .collect()
+}
+
+/// Whether `wider` allows every version `narrower` allows.
+fn covers(wider: &IntervalSet<Pep440Version>, narrower: &IntervalSet<Pep440Version>) -> bool {
+    narrower.intersect(&wider.complement()).is_empty()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn line(input: &str, file: &str, line: usize) -> RequirementLine {
+        RequirementLine {
+            package: PythonPackage::parse(input).unwrap(),
+            file: PathBuf::from(file),
+            line,
+            columns: None,
+            hashes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let requirements = vec![
+            line("requests>=2.28", "requirements.txt", 1),
+            line("Django==4.2.7", "requirements.txt", 2),
+            line("flask<2", "requirements.txt", 3),
+            line("colorama; sys_platform == 'win32'", "requirements.txt", 4),
+            line("django == 4.2.7", "base.txt", 1),
+            line("requests>=2,<3", "base.txt", 2),
+            line("Flask>=2.1", "base.txt", 3),
+            line("colorama>=0.4", "base.txt", 4),
+        ];
+        let found = duplicates(&requirements);
+        let verdicts: Vec<(&str, Verdict)> = found.iter().map(|d| (d.name.as_str(), d.verdict)).collect();
+        assert_eq!(
+            verdicts,
+            vec![("requests", Verdict::Consistent), ("Django", Verdict::Redundant), ("flask", Verdict::Conflicting)]
+        );
+
+        let requests = &found[0];
+        assert_eq!(requests.shadowed, Vec::new());
+        assert_eq!(requests.combined.as_ref().map(merge::line).as_deref(), Some("requests>=2.28,<3"));
+        assert!(found[2].combined.is_none());
+
+        let shadowed = duplicates(&[line("attrs>=20", "a.txt", 1), line("attrs>=22,<24", "a.txt", 7)]);
+        assert_eq!(shadowed[0].shadowed, vec![(0, 1)]);
+    }
+
+    #[test]
+    fn test_pin_violations() -> Result<()> {
+        let requirements = vec![
+            line("requests==2.31.0", "requirements.txt", 1),
+            line("flask>=2", "requirements.txt", 2),
+            line("six==1.*", "requirements.txt", 3),
+            line("acme-core~=1.4", "requirements.txt", 4),
+            line("acme-utils>=1", "requirements.txt", 5),
+            line("pkg @ https://example.com/pkg-1.0.tar.gz", "requirements.txt", 6),
+        ];
+        let overrides: Vec<PinOverride> = ["acme-*=any", "Acme_Utils=exact", "requests=range"]
+            .iter()
+            .map(|o| o.parse())
+            .collect::<Result<_>>()?;
+        let violations = pin_violations(&requirements, PinRule::Exact, &overrides);
+        let found: Vec<(usize, Option<&str>)> = violations.iter().map(|v| (v.line.line, v.by.as_deref())).collect();
+        assert_eq!(found, vec![(1, Some("requests")), (2, None), (3, None), (5, Some("Acme_Utils"))]);
+        assert_eq!(violations[1].to_string(), "flask>=2 must be pinned to one version with ==");
+
+        let violations = pin_violations(&requirements, PinRule::Range, &[]);
+        assert_eq!(violations.iter().map(|v| v.line.line).collect::<Vec<_>>(), vec![1]);
+        assert!(pin_violations(&requirements, PinRule::Any, &[]).is_empty());
+        assert!("requests".parse::<PinOverride>().is_err() && "requests=loose".parse::<PinOverride>().is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_caps() {
+        let requirements = vec![
+            line("requests>=2.28,<3", "pyproject.toml", 10),
+            line("attrs~=22.1", "pyproject.toml", 11),
+            line("click~=8.1.3", "pyproject.toml", 12),
+            line("urllib3<2.0.0.dev0", "pyproject.toml", 13),
+            line("pydantic<2.5", "pyproject.toml", 14),
+            line("numpy<2", "pyproject.toml", 15),
+            line("flask!=3.0.0", "pyproject.toml", 16),
+            line("pytz<1!2", "pyproject.toml", 17),
+        ];
+        let caps = caps(&requirements, &["NumPy".to_string()]);
+        let found: Vec<(usize, String)> = caps.iter().map(|c| (c.line.line, c.clause.to_string())).collect();
+        assert_eq!(
+            found,
+            vec![
+                (10, "<3".to_string()),
+                (11, "~=22.1".to_string()),
+                (13, "<2.0.0.dev0".to_string()),
+                (17, "<1!2".to_string())
+            ]
+        );
+        assert_eq!(caps[1].to_string(), "attrs~=22.1 caps attrs below major version 23");
+        assert_eq!(caps[3].to_string(), "pytz<1!2 caps pytz below major version 1!2");
+    }
+}
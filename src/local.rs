@@ -0,0 +1,135 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::filename::DistributionName;
+use crate::package::{self, PythonPackage};
+use crate::pep440::Pep440Version;
+use crate::pyproject;
+use crate::setup;
+use crate::specifier::SpecifierSet;
+
+/// A project installed from a local directory or archive, as its metadata names it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LocalProject {
+    pub name: String,
+    /// `None` when the version is computed at build time.
+    pub version: Option<Pep440Version>,
+}
+
+/// Whether a line of a requirements file installs a local path rather than a
+/// project from the index, like `./libs/internal`, `../pkg[dev]` or
+/// `vendor/pkg-1.0.tar.gz`.
+pub fn is_path(target: &str) -> bool {
+    let target = target.split(';').next().unwrap_or(target).trim();
+    if target.starts_with(['.', '/', '\\']) || target.starts_with("file:") {
+        return true;
+    }
+    // `pkg @ https://...` names its project; a bare archive or nested path does not
+    !target.contains('@')
+        && (target.contains(['/', '\\'])
+            || target.ends_with(".whl")
+            || package::SDIST_EXTENSIONS.iter().any(|ext| target.ends_with(ext)))
+}
+
+/// The requirement a local path such as `./libs/internal[dev]` stands for,
+/// resolving relative paths against `base`. Directories are only read when
+/// there is a `base`; archives are named by their file name alone. `None`
+/// when the project cannot be named.
+pub fn requirement(target: &str, base: Option<&Path>) -> Option<PythonPackage> {
+    let (location, extras) = match target.trim().strip_suffix(']').and_then(|t| t.rsplit_once('[')) {
+        Some((location, extras)) => (location, extras.split(',').map(|e| e.trim().to_string()).filter(|e| !e.is_empty()).collect()),
+        None => (target.trim(), Vec::new()),
+    };
+    let path = resolve(location, base);
+    let project = match base {
+        Some(_) => project(&path),
+        None => archive(&path),
+    }?;
+    let specifiers = match &project.version {
+        Some(version) => SpecifierSet::parse(&format!("=={}", version)).ok()?,
+        None => SpecifierSet::default(),
+    };
+    Some(PythonPackage {
+        name: project.name,
+        extras,
+        specifiers,
+        marker: None,
+        url: Some(location.to_string()),
+    })
+}
+
+/// Reads the name and version of the project at `path`: from the file name
+/// of a wheel or source archive, or from the pyproject.toml, setup.cfg or
+/// PKG-INFO of a source directory, in that order.
+pub fn project(path: &Path) -> Option<LocalProject> {
+    if !path.is_dir() {
+        return archive(path);
+    }
+    let read = |name: &str| fs::read_to_string(path.join(name)).ok();
+    let (name, version) = read("pyproject.toml")
+        .and_then(|content| pyproject::project_metadata(&content).ok().flatten())
+        .or_else(|| read("setup.cfg").and_then(|content| setup::cfg_metadata(&content)))
+        .or_else(|| read("PKG-INFO").and_then(|content| pkg_info_metadata(&content)))?;
+    Some(LocalProject {
+        name,
+        version: version.and_then(|v| Pep440Version::parse(&v).ok()),
+    })
+}
+
+fn archive(path: &Path) -> Option<LocalProject> {
+    let dist = DistributionName::parse(path.file_name()?.to_str()?)?;
+    Some(LocalProject {
+        name: dist.name().to_string(),
+        version: Some(dist.version().clone()),
+    })
+}
+
+fn resolve(location: &str, base: Option<&Path>) -> PathBuf {
+    let location = location.strip_prefix("file://").unwrap_or(location);
+    let location = location.strip_prefix("file:").unwrap_or(location);
+    match base {
+        Some(base) if Path::new(location).is_relative() => base.join(location),
+        _ => PathBuf::from(location),
+    }
+}
+
+/// The `Name` and `Version` headers of core metadata.
+fn pkg_info_metadata(content: &str) -> Option<(String, Option<String>)> {
+    let headers = content.lines().take_while(|line| !line.trim().is_empty());
+    let field = |key: &str| {
+        headers.clone().find_map(|line| {
+            let (k, v) = line.split_once(':')?;
+            k.trim().eq_ignore_ascii_case(key).then(|| v.trim().to_string())
+        })
+    };
+    Some((field("Name")?, field("Version")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requirement() -> anyhow::Result<()> {
+        let base = std::env::temp_dir().join(format!("pyhelper-local-{}", std::process::id()));
+        fs::create_dir_all(base.join("libs/internal"))?;
+        fs::create_dir_all(base.join("libs/legacy"))?;
+        fs::write(base.join("libs/internal/pyproject.toml"), "[project]\nname = \"internal\"\nversion = \"1.2\"\n")?;
+        fs::write(base.join("libs/legacy/setup.cfg"), "[metadata]\nname = legacy\nversion = attr: legacy.VERSION\n")?;
+
+        let pkg = requirement("./libs/internal[dev]", Some(&base)).unwrap();
+        assert_eq!((pkg.name.as_str(), pkg.version_spec().as_str()), ("internal", "==1.2"));
+        assert_eq!(pkg.extras, vec!["dev"]);
+        assert_eq!(pkg.to_string(), "internal[dev] @ ./libs/internal");
+        let pkg = requirement("libs/legacy", Some(&base)).unwrap();
+        assert_eq!((pkg.name.as_str(), pkg.version_spec().as_str()), ("legacy", "*"));
+        let pkg = requirement("./vendor/pkg-1.0.tar.gz", None).unwrap();
+        assert_eq!((pkg.name.as_str(), pkg.version_spec().as_str()), ("pkg", "==1.0"));
+        assert!(requirement("./libs/missing", Some(&base)).is_none());
+
+        assert!(is_path("./libs/internal") && is_path("pkg-1.0-py3-none-any.whl") && is_path("file:///src/pkg"));
+        assert!(!is_path("requests>=2") && !is_path("pkg @ https://example.com/pkg-1.0.zip"));
+        fs::remove_dir_all(&base)?;
+        Ok(())
+    }
+}
@@ -0,0 +1,375 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml::Spanned;
+use tracing::{debug, instrument};
+
+use crate::conflict::ConflictReport;
+use crate::error::PyhelperError;
+use crate::package::canonicalize_name;
+use crate::pep440::Pep440Version;
+use crate::requirements::RequirementLine;
+
+/// Lock files looked for next to a pyproject.toml, in order of preference.
+pub const LOCK_FILE_NAMES: &[&str] = &["poetry.lock", "uv.lock", "pdm.lock"];
+
+/// A package version pinned by a lock file.
+#[derive(Debug, Clone)]
+pub struct LockedPackage {
+    pub name: String,
+    pub version: Pep440Version,
+    pub file: PathBuf,
+    pub line: usize,
+    /// The `<algorithm>:<digest>` hashes of the locked files.
+    pub hashes: Vec<String>,
+    /// PEP 503 normalized names of the packages the entry depends on,
+    /// optional and development ones included.
+    pub dependencies: Vec<String>,
+    /// Whether the entry is the locked project itself, as uv lists it, rather
+    /// than one of its dependencies.
+    pub local: bool,
+}
+
+/// A declared requirement the lock file does not honour.
+#[derive(Debug, Clone)]
+pub enum LockIssue {
+    /// No locked version of the package satisfies the requirement any more.
+    Stale {
+        requirement: RequirementLine,
+        locked: Vec<LockedPackage>,
+    },
+    /// The package is declared but missing from the lock file.
+    Missing { requirement: RequirementLine },
+}
+
+impl LockIssue {
+    pub fn requirement(&self) -> &RequirementLine {
+        match self {
+            LockIssue::Stale { requirement, .. } | LockIssue::Missing { requirement } => requirement,
+        }
+    }
+}
+
+/// The result of validating a lock file against the manifest it was locked from.
+#[derive(Debug, Default)]
+pub struct LockReport {
+    /// Number of declared requirements that were examined.
+    pub checked: usize,
+    pub issues: Vec<LockIssue>,
+    /// Conflicts between the declared requirements themselves.
+    pub conflicts: ConflictReport,
+}
+
+impl LockReport {
+    pub fn check(requirements: &[RequirementLine], locked: &[LockedPackage]) -> Self {
+        let mut by_name: BTreeMap<String, Vec<&LockedPackage>> = BTreeMap::new();
+        for package in locked {
+            by_name.entry(canonicalize_name(&package.name)).or_default().push(package);
+        }
+
+        let issues = requirements
+            .iter()
+            .filter_map(|requirement| {
+                let Some(pins) = by_name.get(&requirement.package.canonical_name()) else {
+                    return Some(LockIssue::Missing {
+                        requirement: requirement.clone(),
+                    });
+                };
+                // Lock files may hold several versions of a package for different environments
+                let range = requirement.package.specifiers.range();
+                let satisfied = pins.iter().any(|pin| range.contains(&pin.version));
+                (!satisfied).then(|| LockIssue::Stale {
+                    requirement: requirement.clone(),
+                    locked: pins.iter().map(|pin| (*pin).clone()).collect(),
+                })
+            })
+            .collect();
+
+        LockReport {
+            checked: requirements.len(),
+            issues,
+            conflicts: ConflictReport::check(requirements),
+        }
+    }
+
+    pub fn is_clean(&self) -> bool {
+        self.issues.is_empty() && !self.conflicts.has_conflicts()
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LockFile {
+    #[serde(default)]
+    package: Vec<LockEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockEntry {
+    name: Spanned<String>,
+    // uv leaves the version out for virtual workspace members
+    version: Option<Spanned<String>>,
+    // Poetry and PDM list every file with its hash
+    #[serde(default)]
+    files: Vec<LockedFile>,
+    // uv lists the sdist and the wheels separately
+    sdist: Option<LockedFile>,
+    #[serde(default)]
+    wheels: Vec<LockedFile>,
+    source: Option<BTreeMap<String, toml::Value>>,
+    #[serde(default)]
+    dependencies: LockDependencies,
+    // uv lists the extras and the dependency groups apart
+    #[serde(default, rename = "optional-dependencies")]
+    optional_dependencies: BTreeMap<String, Vec<LockDependency>>,
+    #[serde(default, rename = "dev-dependencies")]
+    dev_dependencies: BTreeMap<String, Vec<LockDependency>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum LockDependencies {
+    // Poetry maps every dependency to its constraint
+    Table(BTreeMap<String, toml::Value>),
+    List(Vec<LockDependency>),
+}
+
+impl Default for LockDependencies {
+    fn default() -> Self {
+        LockDependencies::List(Vec::new())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum LockDependency {
+    Named { name: String },
+    // PDM writes requirement strings
+    Requirement(String),
+}
+
+impl LockDependency {
+    fn name(&self) -> &str {
+        match self {
+            LockDependency::Named { name } => name,
+            LockDependency::Requirement(requirement) => requirement
+                .split(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.')))
+                .next()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LockedFile {
+    hash: Option<String>,
+}
+
+/// The first lock file found next to `manifest`.
+pub fn find_lock_file(manifest: &Path) -> Option<PathBuf> {
+    LOCK_FILE_NAMES
+        .iter()
+        .map(|name| manifest.with_file_name(name))
+        .find(|path| path.is_file())
+}
+
+/// Reads the `[[package]]` entries of a poetry.lock, uv.lock or pdm.lock file,
+/// which all pin one version per entry.
+#[instrument(skip_all, err, fields(file = %path.display()))]
+pub fn parse_file(path: &Path) -> Result<Vec<LockedPackage>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Cannot read lock file: {}", path.display()))?;
+    let lock: LockFile =
+        toml::from_str(&content).with_context(|| format!("Invalid lock file: {}", path.display()))?;
+
+    let line_of = |span: std::ops::Range<usize>| content[..span.start].matches('\n').count() + 1;
+    let packages: Vec<LockedPackage> = lock
+        .package
+        .into_iter()
+        .filter_map(|entry| {
+            let hashes = entry
+                .files
+                .into_iter()
+                .chain(entry.sdist)
+                .chain(entry.wheels)
+                .filter_map(|file| file.hash)
+                .collect();
+            let mut dependencies: Vec<String> = match &entry.dependencies {
+                LockDependencies::Table(table) => table.keys().map(|name| canonicalize_name(name)).collect(),
+                LockDependencies::List(list) => list.iter().map(|dependency| canonicalize_name(dependency.name())).collect(),
+            };
+            dependencies.extend(
+                entry
+                    .optional_dependencies
+                    .values()
+                    .chain(entry.dev_dependencies.values())
+                    .flatten()
+                    .map(|dependency| canonicalize_name(dependency.name())),
+            );
+            dependencies.sort();
+            dependencies.dedup();
+            let local = entry
+                .source
+                .is_some_and(|source| source.contains_key("editable") || source.contains_key("virtual"));
+            Some((entry.name, entry.version?, hashes, dependencies, local))
+        })
+        .map(|(name, version, hashes, dependencies, local)| {
+            let line = line_of(name.span());
+            let version = Pep440Version::parse(version.get_ref())
+                .map_err(|e| PyhelperError::parse(path, line_of(version.span()), None, e))?;
+            Ok(LockedPackage {
+                name: name.into_inner(),
+                version,
+                file: path.to_path_buf(),
+                line,
+                hashes,
+                dependencies,
+                local,
+            })
+        })
+        .collect::<Result<_>>()?;
+    debug!(packages = packages.len(), "parsed");
+    Ok(packages)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::package::PythonPackage;
+
+    #[test]
+    fn test_parse_file() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("pyhelper-lock-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("poetry.lock");
+        fs::write(
+            &path,
+            r#"# This file is automatically @generated by Poetry and should not be changed by hand.
+
+[[package]]
+name = "certifi"
+version = "2023.7.22"
+description = "Python package for providing Mozilla's CA Bundle."
+optional = false
+python-versions = ">=3.6"
+
+[[package]]
+name = "Requests"
+version = "2.31.0"
+files = [
+    {file = "requests-2.31.0-py3-none-any.whl", hash = "sha256:58cd2187c01e70e6e26505bca751777aa9f2ee0b7f4300988b709f44e013003f"},
+]
+
+[package.dependencies]
+certifi = ">=2017.4.17"
+PySocks = {version = ">=1.5.6,!=1.5.7", optional = true}
+
+[metadata]
+lock-version = "2.0"
+"#,
+        )?;
+
+        let locked = parse_file(&path)?;
+        let found: Vec<(String, String, usize)> = locked
+            .iter()
+            .map(|p| (p.name.clone(), p.version.to_string(), p.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("certifi".to_string(), "2023.7.22".to_string(), 4),
+                ("Requests".to_string(), "2.31.0".to_string(), 11),
+            ]
+        );
+        assert!(locked[0].hashes.is_empty());
+        assert_eq!(locked[1].hashes.len(), 1);
+        assert_eq!(locked[1].dependencies, vec!["certifi", "pysocks"]);
+
+        let uv = dir.join("uv.lock");
+        fs::write(
+            &uv,
+            r#"version = 1
+requires-python = ">=3.9"
+
+[[package]]
+name = "demo"
+version = "0.1.0"
+source = { editable = "." }
+dependencies = [{ name = "idna" }]
+
+[[package]]
+name = "idna"
+version = "3.6"
+source = { registry = "https://pypi.org/simple" }
+sdist = { url = "https://files.pythonhosted.org/idna-3.6.tar.gz", hash = "sha256:9ecdbbd0", size = 175426 }
+wheels = [{ url = "https://files.pythonhosted.org/idna-3.6-py3-none-any.whl", hash = "sha256:c05567e9", size = 61567 }]
+
+[[package]]
+name = "member"
+source = { virtual = "packages/member" }
+"#,
+        )?;
+        let uv_locked = parse_file(&uv)?;
+        let names: Vec<String> = uv_locked.iter().map(|p| p.name.clone()).collect();
+        assert_eq!(uv_locked[1].hashes, vec!["sha256:9ecdbbd0", "sha256:c05567e9"]);
+        assert_eq!(names, vec!["demo", "idna"]);
+        assert!(uv_locked[0].local && !uv_locked[1].local);
+        assert_eq!(uv_locked[0].dependencies, vec!["idna"]);
+
+        // poetry.lock is preferred when several lock files exist
+        let manifest = dir.join("pyproject.toml");
+        assert_eq!(find_lock_file(&manifest), Some(path.clone()));
+        fs::remove_file(&path)?;
+        assert_eq!(find_lock_file(&manifest), Some(uv));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_lock_report() -> Result<()> {
+        let declared = |line: usize, input: &str| -> Result<RequirementLine> {
+            Ok(RequirementLine {
+                package: PythonPackage::parse(input)?,
+                file: PathBuf::from("pyproject.toml"),
+                line,
+                columns: None,
+                hashes: Vec::new(),
+            })
+        };
+        let pin = |name: &str, version: &str| LockedPackage {
+            name: name.to_string(),
+            version: Pep440Version::parse(version).unwrap(),
+            file: PathBuf::from("poetry.lock"),
+            line: 1,
+            hashes: Vec::new(),
+            dependencies: Vec::new(),
+            local: false,
+        };
+
+        let requirements = vec![
+            declared(1, "requests>=2.32")?,
+            declared(2, "certifi")?,
+            declared(3, "rich>=13")?,
+            declared(4, "attrs>=22")?,
+        ];
+        let locked = vec![
+            pin("requests", "2.31.0"),
+            pin("certifi", "2023.7.22"),
+            pin("attrs", "21.4.0"),
+            pin("attrs", "23.1.0"),
+        ];
+
+        let report = LockReport::check(&requirements, &locked);
+        assert!(!report.is_clean());
+        assert_eq!(report.checked, 4);
+        let lines: Vec<(usize, bool)> = report
+            .issues
+            .iter()
+            .map(|issue| (issue.requirement().line, matches!(issue, LockIssue::Missing { .. })))
+            .collect();
+        assert_eq!(lines, vec![(1, false), (3, true)]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,311 @@
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use std::io::{BufRead, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::conflict::ConflictReport;
+use crate::error::PyhelperError;
+use crate::pyproject;
+use crate::requirements::{self, RequirementLine};
+
+/// LSP `DiagnosticSeverity.Error`.
+const SEVERITY_ERROR: u8 = 1;
+
+/// JSON-RPC error code for requests the server does not implement.
+const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The kinds of buffer diagnostics are computed for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Requirements,
+    Pyproject,
+}
+
+impl Format {
+    /// The format of a file by its name: `pyproject.toml`, or requirement
+    /// files such as `requirements.txt`, `dev-requirements.in` or `constraints.txt`.
+    fn of(path: &Path) -> Option<Format> {
+        let name = path.file_name()?.to_str()?;
+        if name == "pyproject.toml" {
+            Some(Format::Pyproject)
+        } else if name.ends_with(".txt") || name.ends_with(".in") {
+            Some(Format::Requirements)
+        } else {
+            None
+        }
+    }
+}
+
+/// Serves diagnostics over the Language Server Protocol until the client
+/// sends `exit`. Open buffers are checked on every change, and all of them
+/// again when one is saved, as it may be included by the others.
+pub fn run(mut input: impl BufRead, mut output: impl Write) -> Result<()> {
+    let mut documents: BTreeMap<String, String> = BTreeMap::new();
+    while let Some(message) = read_message(&mut input)? {
+        let method = message["method"].as_str().unwrap_or_default();
+        let params = &message["params"];
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or_default().to_string();
+        match method {
+            "initialize" => {
+                let result = json!({
+                    "capabilities": {
+                        "textDocumentSync": { "openClose": true, "change": 1, "save": { "includeText": false } },
+                    },
+                    "serverInfo": { "name": "pyhelper", "version": env!("CARGO_PKG_VERSION") },
+                });
+                write_message(&mut output, &json!({ "jsonrpc": "2.0", "id": message["id"], "result": result }))?;
+            }
+            "shutdown" => write_message(&mut output, &json!({ "jsonrpc": "2.0", "id": message["id"], "result": null }))?,
+            "exit" => return Ok(()),
+            "textDocument/didOpen" => {
+                let text = params["textDocument"]["text"].as_str().unwrap_or_default();
+                documents.insert(uri.clone(), text.to_string());
+                publish(&mut output, &uri, &documents[&uri])?;
+            }
+            "textDocument/didChange" => {
+                // Full synchronization: the last change holds the whole buffer
+                let changes = params["contentChanges"].as_array();
+                if let Some(text) = changes.and_then(|c| c.last()).and_then(|c| c["text"].as_str()) {
+                    documents.insert(uri.clone(), text.to_string());
+                    publish(&mut output, &uri, &documents[&uri])?;
+                }
+            }
+            "textDocument/didSave" => {
+                for (uri, text) in &documents {
+                    publish(&mut output, uri, text)?;
+                }
+            }
+            "textDocument/didClose" => {
+                documents.remove(&uri);
+                let params = json!({ "uri": uri, "diagnostics": [] });
+                write_message(&mut output, &json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": params }))?;
+            }
+            _ if !message["id"].is_null() => {
+                let error = json!({ "code": METHOD_NOT_FOUND, "message": format!("Unsupported method: {}", method) });
+                write_message(&mut output, &json!({ "jsonrpc": "2.0", "id": message["id"], "error": error }))?;
+            }
+            // Other notifications need no answer
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// The diagnostics of a buffer holding `content`, for the file at `path`:
+/// the requirement that does not parse, or every requirement taking part in
+/// a conflict. Requirements of included files are checked too, but only the
+/// lines of the buffer are reported.
+pub fn diagnostics(path: &Path, content: &str) -> Vec<Value> {
+    let parsed = match Format::of(path) {
+        Some(Format::Requirements) => requirements::parse_content(content, path),
+        Some(Format::Pyproject) => pyproject::parse_str(content, path),
+        None => return Vec::new(),
+    };
+    let requirements = match parsed {
+        Ok(requirements) => requirements,
+        Err(e) => {
+            let (line, column, message) = located(&e, path, content);
+            // Only the start of the error is known
+            let columns = column.map(|column| column..usize::MAX);
+            return vec![diagnostic(content, line, columns, message)];
+        }
+    };
+
+    let report = ConflictReport::check(&requirements);
+    let mut diagnostics = Vec::new();
+    for conflict in &report.conflicts {
+        for requirement in conflict.requirements.iter().filter(|r| r.file == path) {
+            let message = match conflict.requirements.as_slice() {
+                [_] => format!("No version of {} satisfies {}", conflict.name, requirement.package.version_spec()),
+                involved => {
+                    let others: Vec<String> = involved
+                        .iter()
+                        .filter(|other| other.file != requirement.file || other.line != requirement.line)
+                        .map(|other| format!("{} ({})", other.package, location(other, path)))
+                        .collect();
+                    format!("Requirements on {} conflict: {} excludes {}", conflict.name, requirement.package, others.join(", "))
+                }
+            };
+            diagnostics.push(diagnostic(content, requirement.line, requirement.columns.clone(), message));
+        }
+    }
+    diagnostics
+}
+
+fn publish(output: &mut impl Write, uri: &str, text: &str) -> Result<()> {
+    let diagnostics = diagnostics(&uri_path(uri), text);
+    let params = json!({ "uri": uri, "diagnostics": diagnostics });
+    write_message(output, &json!({ "jsonrpc": "2.0", "method": "textDocument/publishDiagnostics", "params": params }))
+}
+
+/// A diagnostic on line `line` (1-based) of `content`, spanning the 1-based
+/// `columns` of that line if given, else all of it.
+fn diagnostic(content: &str, line: usize, columns: Option<Range<usize>>, message: String) -> Value {
+    let text = content.lines().nth(line.saturating_sub(1)).unwrap_or_default();
+    // Positions count UTF-16 code units, columns count bytes
+    let character = |column: usize| text.get(..column.saturating_sub(1)).unwrap_or(text).encode_utf16().count();
+    let (start, end) = match columns {
+        Some(columns) => (character(columns.start), character(columns.end)),
+        None => (0, character(usize::MAX)),
+    };
+    json!({
+        "range": {
+            "start": { "line": line.saturating_sub(1), "character": start },
+            "end": { "line": line.saturating_sub(1), "character": end },
+        },
+        "severity": SEVERITY_ERROR,
+        "source": "pyhelper",
+        "message": message,
+    })
+}
+
+/// The line and column an error of the buffer's parser points at, and its
+/// message without the location. TOML syntax errors carry a span instead.
+fn located(error: &anyhow::Error, path: &Path, content: &str) -> (usize, Option<usize>, String) {
+    if let Some(toml) = error.downcast_ref::<toml::de::Error>() {
+        return match toml.span() {
+            Some(span) => {
+                let (line, columns) = requirements::span_columns(content, span);
+                (line, Some(columns.start), toml.message().to_string())
+            }
+            None => (1, None, toml.message().to_string()),
+        };
+    }
+    match error.downcast_ref::<PyhelperError>() {
+        Some(PyhelperError::Parse { file, line, column, message }) if file == path => (*line, *column, message.clone()),
+        _ => (1, None, format!("{:#}", error)),
+    }
+}
+
+/// Where a requirement is, relative to the buffer of `path`.
+fn location(requirement: &RequirementLine, path: &Path) -> String {
+    if requirement.file == path {
+        format!("line {}", requirement.line)
+    } else {
+        requirement.location()
+    }
+}
+
+/// The path of a `file://` URI; other URIs, such as unsaved buffers, are
+/// used as they are.
+fn uri_path(uri: &str) -> PathBuf {
+    let Some(path) = uri.strip_prefix("file://") else {
+        return PathBuf::from(uri);
+    };
+    let bytes = path.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let escaped = (bytes[i] == b'%').then(|| path.get(i + 1..i + 3)).flatten();
+        match escaped.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+            Some(byte) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            None => {
+                decoded.push(bytes[i]);
+                i += 1;
+            }
+        }
+    }
+    let decoded = String::from_utf8_lossy(&decoded).into_owned();
+    // `file:///C:/...` on Windows
+    match decoded.strip_prefix('/') {
+        Some(rest) if cfg!(windows) => PathBuf::from(rest),
+        _ => PathBuf::from(decoded),
+    }
+}
+
+/// Reads one message framed by a `Content-Length` header; `None` at the end of input.
+fn read_message(input: &mut impl BufRead) -> Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                length = Some(value.trim().parse::<usize>().context("Invalid Content-Length header")?);
+            }
+        }
+    }
+    let length = length.ok_or_else(|| PyhelperError::Invalid("Message without a Content-Length header".to_string()))?;
+    let mut body = vec![0; length];
+    input.read_exact(&mut body)?;
+    Ok(Some(serde_json::from_slice(&body).context("Invalid JSON-RPC message")?))
+}
+
+fn write_message(output: &mut impl Write, message: &Value) -> Result<()> {
+    let body = message.to_string();
+    write!(output, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    output.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diagnostics() {
+        let path = Path::new("/project/requirements.txt");
+        let found = diagnostics(path, "flask\nsix<1\n# pinned\nsix>=2\ndjango>=4,<3\n");
+        let lines: Vec<u64> = found.iter().filter_map(|d| d["range"]["start"]["line"].as_u64()).collect();
+        assert_eq!(lines, vec![4, 1, 3]);
+        assert_eq!(found[1]["message"], "Requirements on six conflict: six <1 excludes six >=2 (line 4)");
+        assert_eq!(found[0]["message"], "No version of django satisfies >=4,<3");
+        assert_eq!((&found[0]["range"]["start"]["character"], &found[0]["range"]["end"]["character"]), (&json!(0), &json!(12)));
+
+        let broken = diagnostics(path, "flask\nsix>>1\n");
+        assert_eq!(broken.len(), 1);
+        assert_eq!(broken[0]["range"]["start"]["line"], 1);
+        assert_eq!(broken[0]["range"]["end"]["character"], 6);
+        assert!(broken[0]["message"].as_str().is_some_and(|m| !m.contains("requirements.txt")), "{}", broken[0]);
+
+        let pyproject = diagnostics(Path::new("/project/pyproject.toml"), "[project]\ndependencies = [\n  \"six<1\",\n  \"six>=2\",\n]\n");
+        assert_eq!(pyproject.len(), 2);
+        assert_eq!((&pyproject[0]["range"]["start"]["character"], &pyproject[0]["range"]["end"]["character"]), (&json!(3), &json!(8)));
+        let invalid = diagnostics(Path::new("/project/pyproject.toml"), "[project]\ndependencies = [\n");
+        assert_eq!(invalid.len(), 1);
+        assert!(diagnostics(Path::new("/project/setup.py"), "six<1\nsix>=2\n").is_empty());
+    }
+
+    #[test]
+    fn test_session() -> Result<()> {
+        let frame = |message: Value| format!("Content-Length: {}\r\n\r\n{}", message.to_string().len(), message);
+        let uri = "file:///project/dev%20requirements.txt";
+        let input = [
+            frame(json!({ "jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {} })),
+            frame(json!({ "jsonrpc": "2.0", "method": "textDocument/didOpen", "params": { "textDocument": { "uri": uri, "text": "six<1\nsix>=2\n" } } })),
+            frame(json!({ "jsonrpc": "2.0", "method": "textDocument/didChange", "params": { "textDocument": { "uri": uri }, "contentChanges": [{ "text": "six<1\n" }] } })),
+            frame(json!({ "jsonrpc": "2.0", "id": 2, "method": "textDocument/hover", "params": {} })),
+            frame(json!({ "jsonrpc": "2.0", "id": 3, "method": "shutdown" })),
+            frame(json!({ "jsonrpc": "2.0", "method": "exit" })),
+        ]
+        .concat();
+        let mut output = Vec::new();
+        run(input.as_bytes(), &mut output)?;
+
+        let mut reader = output.as_slice();
+        let mut messages = Vec::new();
+        while let Some(message) = read_message(&mut reader)? {
+            messages.push(message);
+        }
+        assert_eq!(messages.len(), 5);
+        assert_eq!(messages[0]["result"]["capabilities"]["textDocumentSync"]["change"], 1);
+        assert_eq!(messages[1]["params"]["uri"], uri);
+        assert_eq!(messages[1]["params"]["diagnostics"].as_array().map(Vec::len), Some(2));
+        assert_eq!(messages[2]["params"]["diagnostics"].as_array().map(Vec::len), Some(0));
+        assert_eq!(messages[3]["error"]["code"], METHOD_NOT_FOUND);
+        assert_eq!(messages[4]["id"], 3);
+        assert_eq!(uri_path(uri), PathBuf::from("/project/dev requirements.txt"));
+        Ok(())
+    }
+}
@@ -1,190 +1,4303 @@
-use clap::Parser;
+use anyhow::{anyhow, Context, Result};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
 use colored::*;
-use regex::Regex;
-use semver::{Version, VersionReq};
-use std::str::FromStr;
-use anyhow::{Result, anyhow};
+use indicatif::{ProgressBar, ProgressStyle};
+use pyhelper::audit::{self, Audited, OsvClient, Severity};
+use pyhelper::baseline::Baseline;
+use pyhelper::batch;
+use pyhelper::cache::Cache;
+use pyhelper::config::{Config, Settings};
+use pyhelper::conflict::witness;
+use pyhelper::convert::{self, Format};
+use pyhelper::confusion;
+use pyhelper::diff::{self, ConstraintChange};
+use pyhelper::drift::{self, Drift};
+use pyhelper::docker;
+use pyhelper::env::{self, Environment};
+use pyhelper::explain::{explain, versions};
+use pyhelper::fetch::{NetworkOptions, Progress};
+use pyhelper::fmt;
+use pyhelper::freeze::{self, ChangeKind};
+use pyhelper::graph::{DependencyGraph, Edge};
+use pyhelper::hashes::{self, HashIssue};
+use pyhelper::hook;
+use pyhelper::ignore::{self, Acknowledged, IgnoreRule};
+use pyhelper::marker::MarkerTree;
+use pyhelper::merge;
+use pyhelper::notify::{self, Destination};
+use pyhelper::outdated::{self, Gap};
+use pyhelper::output::sbom::{self, Component};
+use pyhelper::output::document::Document;
+use pyhelper::output::{self, csv, diagram, github, json, junit, sarif, schema};
+use pyhelper::pep440::Pep440Version;
+use pyhelper::plugin::{self, AdvisorySource, Capability, Sink, SubprocessPlugin};
+use pyhelper::output::tree::{self, Charset, TreeOptions};
+use pyhelper::prerelease::PreReleasePolicy;
+use pyhelper::pypi::{self, PypiClient, Release, Verification};
+use pyhelper::range::IntervalSet;
+use pyhelper::resolve::{self, Resolution};
+use pyhelper::scan;
+use pyhelper::selftest;
+use pyhelper::serve::Server;
+use pyhelper::severity::{self, SeverityPolicy};
+use pyhelper::simulate;
+use pyhelper::suggest::suggest;
+use pyhelper::sync::{self, Alignment};
+use pyhelper::tags::Target;
+use pyhelper::timeline;
+use pyhelper::tui::{self, Explorer};
+use pyhelper::typosquat;
+use pyhelper::watch::{self, ConflictChange};
+use pyhelper::workspace;
+use pyhelper::license::{self, LicensePolicy, Verdict};
+use pyhelper::lint::{self, PinOverride, PinRule};
+use pyhelper::lock::{self, LockIssue, LockReport};
+use pyhelper::lsp;
+use pyhelper::maintenance::{self, Maintenance};
+use pyhelper::{canonicalize_name, conda, pipfile, pyproject, requirements, setup, ConflictReport, PythonPackage, RequirementLine};
+use std::collections::BTreeMap;
+use std::ffi::OsString;
+use std::io::{self, BufRead, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::filter::{filter_fn, Targets};
+use tracing_subscriber::layer::{self, SubscriberExt};
+use tracing_subscriber::registry::LookupSpan;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+/// Exit status when at least one conflict was found.
+const EXIT_CONFLICT: u8 = 1;
+/// Exit status for unreadable input; clap uses the same code for usage errors.
+const EXIT_ERROR: u8 = 2;
 
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
-struct Args {
+#[command(
+    author,
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
+    // Running without a subcommand behaves like `check`
+    #[command(flatten)]
+    check: CheckArgs,
+
+    /// Read settings from this file instead of the pyhelper.toml, or pyproject.toml
+    /// with a [tool.pyhelper] table, found in the current directory or a parent
+    #[arg(long, global = true, value_name = "FILE")]
+    config: Option<PathBuf>,
+
+    /// Apply a profile of the configuration file, e.g. "strict-ci", on top of its
+    /// top-level settings
+    #[arg(long, global = true, value_name = "NAME")]
+    profile: Option<String>,
+
+    /// Print without colors, as when NO_COLOR is set or the output is not a terminal
+    #[arg(long, global = true)]
+    no_color: bool,
+
+    /// Also look packages up on this index, after the one of --index-url; can be
+    /// repeated. Releases of every index are merged, as pip does
+    #[arg(long, global = true, value_name = "URL")]
+    extra_index_url: Vec<String>,
+
+    /// Send every request through this proxy instead of the one named by
+    /// HTTPS_PROXY, HTTP_PROXY and NO_PROXY
+    #[arg(long, global = true, value_name = "URL")]
+    proxy: Option<String>,
+
+    /// Ask the `keyring` command for the password of an index username that has none
+    #[arg(long, global = true)]
+    keyring: bool,
+
+    /// Log the requests, parsing and resolution of the run to standard error,
+    /// from this level up; without it, RUST_LOG directives such as
+    /// "pyhelper::fetch=debug" are used
+    #[arg(long, global = true, value_enum, value_name = "LEVEL")]
+    log_level: Option<LogLevel>,
+
+    /// Format of the log lines
+    #[arg(long, global = true, value_enum, default_value_t = LogFormat::Text)]
+    log_format: LogFormat,
+
+    /// Print to standard error how many files, requests and cache hits the
+    /// run had and how long each of its phases took
+    #[arg(long, global = true)]
+    stats: bool,
+}
+
+/// The extra indexes and network options of the command line, set once it is
+/// parsed, for every client the subcommands create.
+static NETWORK: OnceLock<(Vec<String>, NetworkOptions)> = OnceLock::new();
+
+/// The plugins of the configuration file, set up before the command runs.
+static PLUGINS: OnceLock<Vec<SubprocessPlugin>> = OnceLock::new();
+
+/// The levels the configuration file gives rules.
+static SEVERITY: OnceLock<SeverityPolicy> = OnceLock::new();
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Check version requirements for conflicts
+    Check(Box<CheckArgs>),
+    /// Find every dependency file of a project directory and check them for
+    /// conflicts with each other
+    Scan(ScanArgs),
+    /// Check the sub-projects of a monorepo against each other and propose one
+    /// set of constraints for all of them
+    Workspace(WorkspaceArgs),
+    /// Verify that every locked version (poetry.lock, uv.lock or pdm.lock) still
+    /// satisfies the pyproject.toml constraints
+    LockCheck(LockCheckArgs),
+    /// Compare every manifest of a directory with every lock file next to it,
+    /// whatever tool wrote them
+    Drift(DriftArgs),
+    /// Check the installed packages of a Python environment for broken requirements
+    EnvCheck(EnvCheckArgs),
+    /// Check the packages installed in a container image for broken requirements
+    /// and known vulnerabilities (OSV.dev), without running it
+    Docker(DockerArgs),
+    /// Compare two `pip freeze` outputs and classify every change
+    FreezeDiff(FreezeDiffArgs),
+    /// Print the dependency tree of a package from the index, or of an environment
+    Tree(TreeArgs),
+    /// Show every chain of dependencies that pulls in a package
+    Why(WhyArgs),
+    /// Report pinned or resolved versions affected by known vulnerabilities (OSV.dev)
+    Audit(AuditArgs),
+    /// List the license of every dependency and check it against a policy
+    Licenses(LicensesArgs),
+    /// Report pinned or locked versions that are behind the latest release
+    Outdated(OutdatedArgs),
+    /// Show the release history of a package, and how far a pinned version is behind
+    Info(InfoArgs),
+    /// Resolve loose requirements to exact `==` pins, like pip-compile
+    Pin(PinArgs),
+    /// Show which requirements break and which pins must also move if one pin
+    /// is upgraded, without changing any file
+    Simulate(SimulateArgs),
+    /// Find the co-downgrades that make an older release of a pinned package
+    /// fit, to roll back after a bad release
+    Downgrade(DowngradeArgs),
+    /// Check the `--hash` options of pinned requirements against the files on the index
+    VerifyHashes(VerifyHashesArgs),
+    /// Combine several requirement sources into one requirement per package
+    Merge(MergeArgs),
+    /// Translate a dependency file into another format, such as a requirements
+    /// file into a pyproject.toml
+    Convert(ConvertArgs),
+    /// Show how the constraints of two requirement files differ per package
+    Diff(DiffArgs),
+    /// Check that the dev requirements on packages shared with prod only allow
+    /// versions prod allows too
+    SyncCheck(SyncCheckArgs),
+    /// Rewrite requirements without redundant clauses, in canonical order
+    Simplify(SimplifyArgs),
+    /// Sort, deduplicate and normalize the entries of requirements files in place
+    Fmt(FmtArgs),
+    /// Flag packages listed more than once across a requirements file and its
+    /// includes, requirements breaking a pinning policy, and upper caps
+    Lint(LintArgs),
+    /// Combine requirements on one package into the single specifier set all of
+    /// them allow
+    Intersect(IntersectArgs),
+    /// Combine alternative requirements on one package into the loosest single
+    /// requirement allowing every one of them
+    Union(UnionArgs),
+    /// Check the version engine against the `packaging` library of a Python
+    /// interpreter, on shipped and generated versions and specifiers
+    Selftest(SelftestArgs),
+    /// Write a software bill of materials (CycloneDX or SPDX) for a dependency set
+    Sbom(SbomArgs),
+    /// Inspect, clear or fill the local cache of package index metadata
+    Cache(CacheArgs),
+    /// Record the conflicts and advisories a project already has, so that
+    /// `check --baseline` and `audit --baseline` only fail for new ones
+    Baseline(BaselineArgs),
+    /// Browse the dependency graph of a requirements file or an environment, and
+    /// try out other versions of its packages
+    Tui(TuiArgs),
+    /// Check requirement files for conflicts again every time one of them changes
+    Watch(WatchArgs),
+    /// Answer check, resolve and audit requests over HTTP, sharing one cache
+    Serve(ServeArgs),
+    /// Report conflicts in requirements.txt and pyproject.toml buffers to an
+    /// editor, as a Language Server Protocol server on standard input and output
+    Lsp,
+    /// Check the files a pre-commit hook passes, picking the checks by file name
+    Hook(HookArgs),
+    /// Print a shell completion script
+    Completions(CompletionsArgs),
+    /// Print the JSON Schema of the `--format json` reports
+    Schema,
+}
+
+#[derive(clap::Args, Debug)]
+struct SbomArgs {
+    /// A poetry.lock, uv.lock or pdm.lock, or a requirements.txt or pyproject.toml
+    /// whose dependencies are resolved from the index
+    file: PathBuf,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = SbomFormat::Cyclonedx)]
+    format: SbomFormat,
+
+    /// Maximum number of dependency levels to resolve below the requirements
+    #[arg(long)]
+    depth: Option<usize>,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum SbomFormat {
+    /// CycloneDX 1.5 JSON
+    Cyclonedx,
+    /// SPDX 2.3 JSON
+    Spdx,
+}
+
+#[derive(clap::Args, Debug)]
+struct LicensesArgs {
+    /// A requirements.txt, pyproject.toml, poetry.lock, uv.lock or pdm.lock; the
+    /// dependencies of requirements are resolved from the index
+    file: PathBuf,
+
+    /// A TOML file with `allow`, `deny` and `ignore` lists and an `unknown` setting
+    #[arg(long)]
+    policy: Option<PathBuf>,
+
+    /// Maximum number of dependency levels to resolve below the requirements
+    #[arg(long)]
+    depth: Option<usize>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = TableFormat::Text)]
+    format: TableFormat,
+
+    /// Always exit with status 0 after reporting, even when the policy is violated
+    #[arg(long)]
+    no_fail: bool,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct AuditArgs {
+    /// A requirements.txt, pyproject.toml, Pipfile.lock, poetry.lock, uv.lock or pdm.lock
+    file: PathBuf,
+
+    /// Only audit pinned versions instead of picking a version from the index
+    /// for every other requirement
+    #[arg(long, conflicts_with_all = ["index_url", "no_cache"])]
+    no_resolve: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = AuditFormat::Text)]
+    format: AuditFormat,
+
+    #[command(flatten)]
+    fail: FailArgs,
+
+    /// Only fail for advisories at least this severe (low, medium, high or critical);
+    /// advisories without a severity always fail
+    #[arg(long, value_name = "SEVERITY")]
+    fail_on: Option<Severity>,
+
+    /// Leave a package out of the audit, or with PACKAGE==ID one advisory by its
+    /// ID or alias (repeatable)
+    #[arg(long, value_name = "PACKAGE[==ID]")]
+    ignore: Vec<IgnoreRule>,
+
+    /// Accept the advisories recorded by `baseline create`, so only new ones fail
+    #[arg(long, value_name = "FILE")]
+    baseline: Option<PathBuf>,
+
+    /// Post a summary of the results to a Slack webhook (slack://hooks.slack.com/...)
+    /// or, with the JSON report, to an HTTP(S) URL (repeatable)
+    #[arg(long, value_name = "URL")]
+    notify: Vec<Destination>,
+
+    /// Also flag likely unmaintained packages: no release in two years, a
+    /// latest release for end-of-life Pythons only, or project URLs answering 404
+    #[arg(long)]
+    maintenance: bool,
+
+    /// Also warn about dependency names resembling popular projects, like
+    /// `python-requests` or `reqeusts` for `requests`
+    #[arg(long)]
+    typosquat: bool,
+
+    /// Names of internal packages, or prefixes like `acme-*` (repeatable); warn
+    /// when the public index has a package of the same name
+    #[arg(long, value_name = "PATTERN")]
+    internal: Vec<String>,
+
+    /// The public index internal names are looked up on
+    #[arg(long, default_value = pypi::DEFAULT_INDEX_URL)]
+    public_index_url: String,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    #[command(flatten)]
+    index: IndexArgs,
+
+    /// Base URL of the OSV API
+    #[arg(long, default_value = audit::DEFAULT_OSV_URL)]
+    osv_url: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct OutdatedArgs {
+    /// A requirements.txt, pyproject.toml, Pipfile.lock, poetry.lock, uv.lock or pdm.lock
+    file: PathBuf,
+
+    /// A constraints file whose requirements upgrade targets must satisfy (repeatable)
+    #[arg(short = 'c', long = "constraint", value_name = "FILE")]
+    constraints: Vec<PathBuf>,
+
+    /// Only report packages a major version behind
+    #[arg(long)]
+    major_only: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = TableFormat::Text)]
+    format: TableFormat,
+
+    #[command(flatten)]
+    fail: FailArgs,
+
+    /// Leave a package out of the report (repeatable)
+    #[arg(long, value_name = "PACKAGE")]
+    ignore: Vec<IgnoreRule>,
+
+    /// Post a summary of the results to a Slack webhook (slack://hooks.slack.com/...)
+    /// or, with the JSON report, to an HTTP(S) URL (repeatable)
+    #[arg(long, value_name = "URL")]
+    notify: Vec<Destination>,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct InfoArgs {
+    /// Package to look up (e.g., "numpy")
+    package: String,
+
+    /// The version you pin, to report how far it is behind
+    #[arg(long, value_name = "VERSION", conflicts_with = "file")]
+    pinned: Option<String>,
+
+    /// Take the pinned version from a requirements.txt, pyproject.toml, Pipfile.lock or lock file
+    #[arg(short = 'f', long, value_name = "FILE")]
+    file: Option<PathBuf>,
+
+    /// How many of the newest releases to list; 0 lists all of them
+    #[arg(long, default_value_t = 20)]
+    limit: usize,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct PinArgs {
+    /// A requirements.in, requirements.txt or pyproject.toml with the requirements to resolve
+    file: PathBuf,
+
+    /// Write the pinned requirements to this file instead of standard output
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Add the sha256 hash of every file of each pinned release, for `pip install --require-hashes`
+    #[arg(long)]
+    generate_hashes: bool,
+
+    /// Which allowed release to pin: the newest, or the oldest to test that the
+    /// declared lower bounds actually work
+    #[arg(long, value_enum, default_value_t = ResolutionMode::Highest)]
+    resolution: ResolutionMode,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct SimulateArgs {
+    /// A requirements.txt, pyproject.toml, Pipfile.lock, poetry.lock, uv.lock or pdm.lock
+    file: PathBuf,
+
+    /// The pin to try, e.g. "numpy==2.0.0"
+    #[arg(long, value_name = "REQUIREMENT")]
+    upgrade: String,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Always exit with status 0 after reporting, even when the upgrade needs other changes
+    #[arg(long)]
+    no_fail: bool,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct DowngradeArgs {
+    /// The package to roll back
+    package: String,
+
+    /// The older version to pin it to
+    #[arg(long, value_name = "VERSION")]
+    to: String,
+
+    /// A requirements.txt, pyproject.toml, Pipfile.lock, poetry.lock, uv.lock or pdm.lock
+    file: PathBuf,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Always exit with status 0 after reporting, even when no rollback fits
+    #[arg(long)]
+    no_fail: bool,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct VerifyHashesArgs {
+    /// A requirements.txt with `--hash` options, or a Pipfile.lock
+    file: PathBuf,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Always exit with status 0 after reporting, even when hashes do not match
+    #[arg(long)]
+    no_fail: bool,
+
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct MergeArgs {
+    /// Requirements files, pyproject.toml, Pipfiles or conda environment.yml files to merge
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// Write the merged requirements to this file instead of standard output
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct ConvertArgs {
+    /// The requirements file, pyproject.toml, Pipfile, setup.cfg, setup.py or environment.yml to convert
+    #[arg(long, value_name = "FILE")]
+    from: PathBuf,
+
+    /// The format to write
+    #[arg(long, value_enum)]
+    to: ConvertFormat,
+
+    /// The project name of a pyproject.toml or environment.yml [default: the directory of the source]
+    #[arg(long)]
+    name: Option<String>,
+
+    /// Write the converted file to this file instead of standard output
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ConvertFormat {
+    /// A requirements file, with the index options
+    #[value(alias = "requirements.txt")]
+    Requirements,
+    /// A PEP 621 pyproject.toml, with the indexes as uv reads them
+    #[value(alias = "pyproject.toml")]
+    Pyproject,
+    /// A Pipfile
+    Pipfile,
+    /// A conda environment.yml installing everything with pip
+    #[value(alias = "environment.yml")]
+    Conda,
+}
+
+#[derive(clap::Args, Debug)]
+struct DiffArgs {
+    /// The earlier requirements file, pyproject.toml, Pipfile or environment.yml
+    old: PathBuf,
+
+    /// The later one
+    new: PathBuf,
+
+    /// Only show changes that reject versions the earlier file allowed, and removals
+    #[arg(long)]
+    breaking_only: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Always exit with status 0 after reporting, even when a change is breaking
+    #[arg(long)]
+    no_fail: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct SyncCheckArgs {
+    /// The prod requirements file, pyproject.toml, Pipfile or environment.yml
+    prod: PathBuf,
+
+    /// The dev one, e.g. requirements-dev.txt
+    dev: PathBuf,
+
+    /// Also flag dev requirements allowing fewer versions than prod: every shared
+    /// package must allow the same versions on both sides
+    #[arg(long)]
+    strict: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Always exit with status 0 after reporting, even when a package is flagged
+    #[arg(long)]
+    no_fail: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct SimplifyArgs {
+    /// Requirements to simplify, e.g. "pkg>=1.2,<2.0,!=1.5,>=1.3"
+    #[arg(required_unless_present = "file", value_name = "REQUIREMENT")]
+    requirements: Vec<String>,
+
+    /// List the requirements of this dependency file that can be simplified instead
+    #[arg(short = 'r', long = "requirements", value_name = "FILE", conflicts_with = "requirements")]
+    file: Option<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct FmtArgs {
+    /// Requirements files to format
+    #[arg(required = true, value_name = "FILE")]
+    files: Vec<PathBuf>,
+
+    /// Only show what would change, and exit with status 1 if any file is not
+    /// formatted, for CI
+    #[arg(long)]
+    check: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct LintArgs {
+    /// The requirements file to lint, with the files it includes, or a
+    /// pyproject.toml
+    #[arg(value_name = "FILE")]
+    file: PathBuf,
+
+    /// Require every requirement to pin one version with ==, as applications do
+    #[arg(long, conflicts_with = "forbid_pins")]
+    require_pins: bool,
+
+    /// Forbid requirements pinning one version, as libraries should allow ranges
+    #[arg(long)]
+    forbid_pins: bool,
+
+    /// The pin rule of a package, or of the packages starting with a prefix
+    /// ending in `*`: exact, range or any (repeatable); overrides the rule of
+    /// --require-pins and --forbid-pins
+    #[arg(long, value_name = "PACKAGE=RULE")]
+    pin_policy: Vec<PinOverride>,
+
+    /// Flag upper caps below the next major release (`<3`, `~=2.1`), which a
+    /// library's dependencies should not speculatively set
+    #[arg(long)]
+    forbid_caps: bool,
+
+    /// Accept caps on this package, or on the packages starting with a prefix
+    /// ending in `*`, e.g. one known to break its API in minor releases (repeatable)
+    #[arg(long, value_name = "PACKAGE")]
+    allow_cap: Vec<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    #[command(flatten)]
+    fail: FailArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct IntersectArgs {
+    /// Requirements on one package, e.g. "django>=3.2" "django<5" "django!=4.0.*"
+    #[arg(required = true, value_name = "REQUIREMENT")]
+    requirements: Vec<String>,
+
+    /// Always exit with status 0, even when no version satisfies every requirement
+    #[arg(long)]
+    no_fail: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct UnionArgs {
+    /// Alternative requirements on one package, e.g. "django>=3.2,<4" "django~=4.2"
+    #[arg(required_unless_present = "files", value_name = "REQUIREMENT")]
+    requirements: Vec<String>,
+
+    /// Per-environment dependency files to cover, e.g. -r requirements/prod.txt
+    /// -r requirements/ci.txt; prints one requirement per package
+    #[arg(short = 'r', long = "requirements", value_name = "FILE", conflicts_with = "requirements")]
+    files: Vec<PathBuf>,
+}
+
+#[derive(clap::Args, Debug)]
+struct SelftestArgs {
+    /// Number of generated checks to ask the interpreter about
+    #[arg(long, default_value_t = 5000)]
+    cases: usize,
+
+    /// Seed of the generated checks, to reproduce a run
+    #[arg(long, default_value_t = 1)]
+    seed: u64,
+
+    /// Interpreter whose `packaging` answers [default: the active virtualenv, else python3]
+    #[arg(long)]
+    python: Option<PathBuf>,
+
+    /// Only check the answers shipped with pyhelper, without running Python
+    #[arg(long)]
+    offline: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+}
+
+#[derive(clap::Args, Debug)]
+struct TuiArgs {
+    /// A requirements.txt, pyproject.toml, Pipfile or environment.yml whose
+    /// dependencies are resolved from the index
+    #[arg(required_unless_present = "env")]
+    file: Option<PathBuf>,
+
+    /// Explore the packages installed in a Python environment instead
+    #[arg(long, conflicts_with = "file")]
+    env: bool,
+
+    /// Interpreter of the environment [default: the active virtualenv, else python3]
+    #[arg(long, requires = "env")]
+    python: Option<PathBuf>,
+
+    /// Read this site-packages directory instead of asking the interpreter
+    #[arg(long, requires = "env")]
+    site_packages: Vec<PathBuf>,
+
+    /// Maximum number of dependency levels to resolve below the requirements
+    #[arg(long, conflicts_with = "env")]
+    depth: Option<usize>,
+
+    /// Never ask the index for the dependencies of a version tried out
+    #[arg(long, requires = "env")]
+    offline: bool,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct WatchArgs {
+    /// requirements.txt, pyproject.toml, Pipfile or environment.yml files to check together
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// A constraints file whose bounds also apply to the requirements (repeatable)
+    #[arg(short = 'c', long = "constraint", value_name = "FILE")]
+    constraints: Vec<PathBuf>,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct ServeArgs {
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+
+    /// Address to listen on; use 0.0.0.0 to accept connections from other hosts
+    #[arg(long, default_value = "127.0.0.1")]
+    host: String,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    #[command(flatten)]
+    index: IndexArgs,
+
+    /// Base URL of the OSV API
+    #[arg(long, default_value = audit::DEFAULT_OSV_URL)]
+    osv_url: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct ScanArgs {
+    /// The project directory to search
+    #[arg(default_value = ".")]
+    path: PathBuf,
+
+    /// Do not search directories with this name, e.g. "vendor" (repeatable)
+    #[arg(long, value_name = "DIR")]
+    exclude: Vec<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    #[command(flatten)]
+    fail: FailArgs,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct WorkspaceArgs {
+    /// The root directory of the workspace
+    #[arg(default_value = ".")]
+    path: PathBuf,
+
+    /// Do not search directories with this name, e.g. "vendor" (repeatable)
+    #[arg(long, value_name = "DIR")]
+    exclude: Vec<String>,
+
+    /// Write the unified constraints to this file, for `pip install -c`
+    #[arg(short, long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Always exit with status 0 after reporting, even when conflicts are found
+    #[arg(long)]
+    no_fail: bool,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct HookArgs {
+    /// Changed files: requirement files (*.txt, *.in), pyproject.toml, Pipfile,
+    /// environment.yml, setup.cfg, setup.py and lock files; other files are skipped
+    files: Vec<PathBuf>,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct CompletionsArgs {
+    /// The shell to complete in
+    #[arg(value_enum)]
+    shell: Shell,
+}
+
+#[derive(clap::Args, Debug)]
+struct BaselineArgs {
+    #[command(subcommand)]
+    action: BaselineAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum BaselineAction {
+    /// Check dependency files together for conflicts, audit their pinned
+    /// versions and write what was found
+    Create(BaselineCreateArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct BaselineCreateArgs {
+    /// Dependency files: requirements files, pyproject.toml, Pipfile, conda
+    /// environment files, setup.cfg, setup.py or lock files
+    #[arg(required = true)]
+    files: Vec<PathBuf>,
+
+    /// The baseline file to write
+    #[arg(short, long, value_name = "FILE", default_value = "baseline.json")]
+    output: PathBuf,
+
+    /// Only record conflicts, without asking the vulnerability database
+    #[arg(long)]
+    no_audit: bool,
+
+    /// Base URL of the OSV API
+    #[arg(long, default_value = audit::DEFAULT_OSV_URL)]
+    osv_url: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct CacheArgs {
+    #[command(subcommand)]
+    action: CacheAction,
+}
+
+#[derive(Subcommand, Debug)]
+enum CacheAction {
+    /// Show where the cache is and what it holds
+    Status,
+    /// Remove every cached response
+    Clear,
+    /// Fetch the metadata of requirements and their dependencies ahead of time
+    Warm(WarmArgs),
+}
+
+#[derive(clap::Args, Debug)]
+struct WarmArgs {
+    /// A requirements.txt file whose packages to fetch
+    #[arg(short = 'r', long, required_unless_present = "pyproject")]
+    requirements: Vec<PathBuf>,
+
+    /// A pyproject.toml whose dependencies to fetch
+    #[arg(long)]
+    pyproject: Option<PathBuf>,
+
+    /// Maximum number of dependency levels to follow
+    #[arg(long)]
+    depth: Option<usize>,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    /// Base URL of a PyPI-compatible JSON API, or of a simple index ending in `/simple`
+    #[arg(long, default_value = pypi::DEFAULT_INDEX_URL)]
+    index_url: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct WhyArgs {
+    /// Package to trace (e.g., "urllib3")
+    package: String,
+
+    /// Resolve the dependencies of a requirements.txt file from the index
+    #[arg(short = 'r', long, required_unless_present = "env", conflicts_with = "env")]
+    requirements: Option<PathBuf>,
+
+    /// Trace the packages installed in a Python environment instead
+    #[arg(long, conflicts_with_all = ["index_url", "no_cache"])]
+    env: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = GraphFormat::Text)]
+    format: GraphFormat,
+
+    /// Interpreter of the environment [default: the active virtualenv, else python3]
+    #[arg(long, requires = "env")]
+    python: Option<PathBuf>,
+
+    /// Read this site-packages directory instead of asking the interpreter
+    #[arg(long, requires = "env")]
+    site_packages: Vec<PathBuf>,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct TreeArgs {
+    /// Package to show, optionally with a constraint (e.g., "requests>=2.31")
+    #[arg(required_unless_present = "env")]
+    package: Option<String>,
+
+    /// Read the packages installed in a Python environment instead of the index
+    #[arg(long, conflicts_with_all = ["index_url", "no_cache"])]
+    env: bool,
+
+    /// Interpreter of the environment [default: the active virtualenv, else python3]
+    #[arg(long, requires = "env")]
+    python: Option<PathBuf>,
+
+    /// Read this site-packages directory instead of asking the interpreter
+    #[arg(long, requires = "env")]
+    site_packages: Vec<PathBuf>,
+
+    /// Maximum number of dependency levels to show
+    #[arg(long)]
+    depth: Option<usize>,
+
+    /// Show the installed packages that depend on the package instead
+    #[arg(long, requires_all = ["env", "package"])]
+    invert: bool,
+
+    /// Draw the tree with ASCII characters
+    #[arg(long)]
+    ascii: bool,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = GraphFormat::Text)]
+    format: GraphFormat,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct FreezeDiffArgs {
+    /// The earlier freeze output
+    old: PathBuf,
+
+    /// The later freeze output
+    new: PathBuf,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Always exit with status 0 after reporting, even when the new file has conflicts
+    #[arg(long)]
+    no_fail: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct EnvCheckArgs {
+    /// Interpreter of the environment to inspect [default: the active virtualenv, else python3]
+    #[arg(long)]
+    python: Option<PathBuf>,
+
+    /// Inspect this site-packages directory instead of asking the interpreter
+    #[arg(long)]
+    site_packages: Vec<PathBuf>,
+
+    /// Ask this interpreter for the distributions `importlib.metadata` finds instead
+    /// of reading site-packages, for zipapps, pex files and Nix environments; a
+    /// command running one, like "nix develop --command python", is split on spaces
+    #[arg(long, value_name = "COMMAND", conflicts_with_all = ["python", "site_packages"])]
+    exec: Option<String>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Always exit with status 0 after reporting, even when problems are found
+    #[arg(long)]
+    no_fail: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct DockerArgs {
+    /// An image reference such as `python:3.12-slim` or `ghcr.io/acme/app:1.4`, or
+    /// the archive `docker save` or an OCI image layout writes
+    image: String,
+
+    /// The platform to read from a multi-platform image
+    #[arg(long, default_value = docker::DEFAULT_PLATFORM, value_name = "OS/ARCH[/VARIANT]")]
+    platform: String,
+
+    /// Only check the requirements of the installed packages, without auditing them
+    #[arg(long)]
+    no_audit: bool,
+
+    /// Only fail for advisories at least this severe (low, medium, high or critical);
+    /// advisories without a severity always fail
+    #[arg(long, value_name = "SEVERITY", conflicts_with = "no_audit")]
+    fail_on: Option<Severity>,
+
+    /// Leave a package out of the audit, or with PACKAGE==ID one advisory by its
+    /// ID or alias (repeatable)
+    #[arg(long, value_name = "PACKAGE[==ID]", conflicts_with = "no_audit")]
+    ignore: Vec<IgnoreRule>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Always exit with status 0 after reporting, even when problems are found
+    #[arg(long)]
+    no_fail: bool,
+
+    /// Base URL of the OSV API
+    #[arg(long, default_value = audit::DEFAULT_OSV_URL)]
+    osv_url: String,
+}
+
+#[derive(clap::Args, Debug)]
+struct LockCheckArgs {
+    /// The pyproject.toml declaring the constraints
+    #[arg(long, default_value = "pyproject.toml")]
+    pyproject: PathBuf,
+
+    /// The lock file to validate [default: poetry.lock, uv.lock or pdm.lock next to the pyproject.toml]
+    #[arg(long)]
+    lock: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Always exit with status 0 after reporting, even when problems are found
+    #[arg(long)]
+    no_fail: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct DriftArgs {
+    /// The directory holding the manifests and lock files
+    #[arg(default_value = ".")]
+    dir: PathBuf,
+
+    /// Compare only this manifest with the lock file given with --lock
+    #[arg(long, requires = "lock", conflicts_with = "dir")]
+    manifest: Option<PathBuf>,
+
+    /// The lock file to compare the --manifest with
+    #[arg(long, requires = "manifest")]
+    lock: Option<PathBuf>,
+
+    /// Output format
+    #[arg(long, value_enum, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Always exit with status 0 after reporting, even when problems are found
+    #[arg(long)]
+    no_fail: bool,
+}
+
+#[derive(clap::Args, Debug)]
+#[command(group(clap::ArgGroup::new("index").args(["index_url", "no_cache"]).multiple(true).requires("online")))]
+struct CheckArgs {
     /// First package with version constraint (e.g., "requests>=2.0.0")
-    #[arg(short = '1', long)]
-    pkg1: String,
+    #[arg(
+        short = '1',
+        long,
+        requires = "pkg2",
+        required_unless_present_any = ["requirements", "pyproject", "pipfile", "conda", "setup", "auto", "stdin"]
+    )]
+    pkg1: Option<String>,
+
+    /// Second package with version constraint (e.g., "requests<3.0.0")
+    #[arg(
+        short = '2',
+        long,
+        requires = "pkg1",
+        required_unless_present_any = ["requirements", "pyproject", "pipfile", "conda", "setup", "auto", "stdin"]
+    )]
+    pkg2: Option<String>,
+
+    /// Check every requirement in a requirements.txt file against each other
+    #[arg(short = 'r', long, conflicts_with_all = ["pkg1", "pkg2"])]
+    requirements: Option<PathBuf>,
+
+    /// Check the dependencies declared in a pyproject.toml (PEP 621 and Poetry tables)
+    #[arg(long, conflicts_with_all = ["pkg1", "pkg2"])]
+    pyproject: Option<PathBuf>,
+
+    /// Check the packages of a Pipfile, and the pins of the Pipfile.lock next to it
+    #[arg(long, conflicts_with_all = ["pkg1", "pkg2"])]
+    pipfile: Option<PathBuf>,
+
+    /// Check the conda and pip dependencies of a conda environment.yml
+    #[arg(long, conflicts_with_all = ["pkg1", "pkg2"])]
+    conda: Option<PathBuf>,
+
+    /// Check the install_requires and extras_require of a setup.cfg, or of a
+    /// setup.py declaring them as literals
+    #[arg(long, conflicts_with_all = ["pkg1", "pkg2"])]
+    setup: Option<PathBuf>,
+
+    /// Check every dependency file of the current directory together:
+    /// requirements.txt, pyproject.toml, Pipfile, environment.yml, setup.cfg and setup.py
+    #[arg(long, conflicts_with_all = ["pkg1", "pkg2", "requirements", "pyproject", "pipfile", "conda", "setup"])]
+    auto: bool,
+
+    /// A pip constraints file whose bounds also apply to the requirements being checked
+    #[arg(short = 'c', long, conflicts_with_all = ["pkg1", "pkg2"])]
+    constraints: Vec<PathBuf>,
+
+    /// Only check the requirements whose markers can hold under this Python
+    /// version; with --platform, the CPython version to install for
+    #[arg(long, visible_alias = "python", value_name = "VERSION")]
+    python_version: Option<Pep440Version>,
+
+    /// With --online, only count the releases publishing a wheel for this
+    /// platform tag and --python-version, or a source distribution, e.g.
+    /// manylinux2014_x86_64, macosx_14_0_arm64 or win_amd64
+    #[arg(long, value_name = "TAG", requires = "online")]
+    platform: Option<String>,
+
+    /// Leave the requirements on a package out of the check, or with PACKAGE==RULE
+    /// accept one kind of conflict: version-conflict or no-matching-release (repeatable)
+    #[arg(long, value_name = "PACKAGE[==RULE]", conflicts_with_all = ["pkg1", "pkg2"])]
+    ignore: Vec<IgnoreRule>,
+
+    /// Accept the conflicts recorded by `baseline create`, so only new ones fail
+    #[arg(long, value_name = "FILE", conflicts_with_all = ["pkg1", "pkg2"])]
+    baseline: Option<PathBuf>,
+
+    /// Read one set of requirements per line from standard input, separated by
+    /// whitespace or `|`, and report a result for every line
+    #[arg(
+        long,
+        conflicts_with_all = ["pkg1", "pkg2", "requirements", "pyproject", "pipfile", "conda", "setup", "auto", "constraints", "online", "explain", "suggest"]
+    )]
+    stdin: bool,
+
+    /// Output format; `jsonl` prints every JSON document on a single line
+    #[arg(long, value_enum, default_value_t = CheckFormat::Text)]
+    format: CheckFormat,
+
+    /// Exit with status 1 when a conflict is found (the default)
+    #[arg(long, overrides_with = "no_fail")]
+    fail_on_conflict: bool,
+
+    #[command(flatten)]
+    fail: FailArgs,
+
+    /// Also check the requirements against the versions actually published on the index
+    #[arg(long)]
+    online: bool,
+
+    #[command(flatten)]
+    index: IndexArgs,
+
+    /// Explain each conflict: the versions every requirement allows and, with
+    /// --online, the nearest published releases
+    #[arg(long)]
+    explain: bool,
+
+    /// Propose the smallest change to a requirement that resolves each conflict,
+    /// targeting real releases with --online
+    #[arg(long)]
+    suggest: bool,
+
+    /// Only print the verdict
+    #[arg(short, long, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Show on standard error what every requirement allows and what they allow
+    /// together; repeat (-vv) to also show every clause and marker
+    #[arg(short, long, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    #[command(flatten)]
+    prereleases: PreReleaseArgs,
+}
+
+#[derive(clap::Args, Debug)]
+struct IndexArgs {
+    /// Base URL of a PyPI-compatible JSON API, or of a simple index ending in `/simple`
+    #[arg(long, default_value = pypi::DEFAULT_INDEX_URL)]
+    index_url: String,
+
+    /// Always ask the index instead of reading or writing the local cache
+    #[arg(long)]
+    no_cache: bool,
+}
+
+#[derive(clap::Args, Debug)]
+struct FailArgs {
+    /// Always exit with status 0 after reporting, even when findings are above --max-severity
+    #[arg(long)]
+    no_fail: bool,
+
+    /// Tolerate findings up to this level: info, warning (the default) or error;
+    /// rules get their level from the `severity` table of the configuration
+    #[arg(long, value_name = "LEVEL", default_value_t = severity::Level::Warning)]
+    max_severity: severity::Level,
+}
+
+#[derive(clap::Args, Debug)]
+struct PreReleaseArgs {
+    /// Let pre-releases and development releases satisfy every requirement
+    #[arg(long, overrides_with = "no_pre")]
+    pre: bool,
+
+    /// Never let pre-releases satisfy a requirement, even one naming a pre-release
+    /// [default: only requirements naming a pre-release, e.g. ">=2.0rc1", accept them]
+    #[arg(long, overrides_with = "pre")]
+    no_pre: bool,
+}
+
+impl CheckArgs {
+    /// The interpreter and platform of --platform, which needs --python-version.
+    fn target(&self) -> Result<Option<Target>> {
+        match (&self.platform, &self.python_version) {
+            (Some(platform), Some(python)) => Target::new(python, platform).map(Some),
+            (Some(_), None) => Err(anyhow!("--platform needs the Python version to install for, e.g. --python-version 3.11")),
+            (None, _) => Ok(None),
+        }
+    }
+
+    /// ` for Python 3.11 on win_amd64`, for the messages about the releases
+    /// counted with --platform.
+    fn for_target(&self) -> String {
+        match self.target() {
+            Ok(Some(target)) => format!(" for {}", target),
+            _ => String::new(),
+        }
+    }
+}
+
+impl IndexArgs {
+    /// A client for the index and the extra indexes, answering from the
+    /// user's cache unless --no-cache is given.
+    fn client(&self) -> Result<PypiClient> {
+        let (extra_index_urls, options) = network();
+        let client = PypiClient::with_options(&self.index_url, extra_index_urls, options)?;
+        Ok(match Cache::from_env().filter(|_| !self.no_cache) {
+            Some(cache) => client.with_cache(cache),
+            None => client,
+        })
+    }
+}
+
+impl PreReleaseArgs {
+    fn policy(&self) -> PreReleasePolicy {
+        match (self.pre, self.no_pre) {
+            (true, _) => PreReleasePolicy::Allow,
+            (_, true) => PreReleasePolicy::Deny,
+            _ => PreReleasePolicy::Auto,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum ResolutionMode {
+    /// The newest release allowed, as pip picks
+    Highest,
+    /// The oldest release allowed
+    Lowest,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    /// Human-readable, colored report
+    Text,
+    /// A single JSON document for CI pipelines
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum CheckFormat {
+    /// Human-readable, colored report
+    Text,
+    /// A single JSON document for CI pipelines
+    Json,
+    /// One compact JSON document per line, e.g. per `--stdin` input line
+    Jsonl,
+    /// A SARIF 2.1.0 log, for code scanning annotations on the offending lines
+    Sarif,
+    /// GitHub Actions `::error` commands, annotating the offending lines of a pull request
+    Github,
+    /// A JUnit XML report with a test case per package, for Jenkins and GitLab test reports
+    Junit,
+    /// A Markdown report with a summary table and graphs, to paste into a pull request
+    Markdown,
+    /// A standalone HTML page of the Markdown report, to publish as a CI artifact
+    Html,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum AuditFormat {
+    /// Human-readable, colored report
+    Text,
+    /// A single JSON document for CI pipelines
+    Json,
+    /// GitHub Actions `::error` commands, annotating the vulnerable requirements of a pull request
+    Github,
+    /// Comma separated values with a row per advisory, for spreadsheets
+    Csv,
+    /// Tab separated values with a row per advisory
+    Tsv,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum TableFormat {
+    /// Human-readable, colored report
+    Text,
+    /// A single JSON document for CI pipelines
+    Json,
+    /// Comma separated values with a row per package, for spreadsheets
+    Csv,
+    /// Tab separated values with a row per package
+    Tsv,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum GraphFormat {
+    /// Human-readable tree or list of chains
+    Text,
+    /// A Graphviz digraph, for `dot -Tsvg`
+    Dot,
+    /// A Mermaid flowchart, for Markdown documentation and CI summaries
+    Mermaid,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum LogLevel {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum LogFormat {
+    /// One human-readable line per event, with the spans it happened in
+    Text,
+    /// One JSON object per event, for log collectors
+    Json,
+}
+
+fn main() -> ExitCode {
+    let started = Instant::now();
+    let settings = match configured_settings() {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    let plugins = settings.plugins.iter().flatten().map(|config| SubprocessPlugin::new(config.clone())).collect();
+    match plugins {
+        Ok(plugins) => PLUGINS.get_or_init(|| plugins),
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    SEVERITY.get_or_init(|| settings.severity.clone().unwrap_or_default());
+    let matches = with_defaults(Cli::command(), &settings).get_matches();
+    let mut cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    add_ignore_rules(&mut cli, settings.ignore.as_deref().unwrap_or_default());
+    if cli.no_color {
+        colored::control::set_override(false);
+    }
+    let stats = cli.stats.then(|| Arc::new(Mutex::new(Stats::default())));
+    let logging = match init_tracing(cli.log_level, cli.log_format, stats.clone()) {
+        Ok(logging) => logging,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            return ExitCode::from(EXIT_ERROR);
+        }
+    };
+    // Progress goes to standard error, and would only get in the way of output
+    // redirected to a file, of a verdict asked for alone or of log lines
+    let quiet = match &cli.command {
+        Some(Command::Check(args)) => args.quiet,
+        Some(_) => false,
+        None => cli.check.quiet,
+    };
+    let drawn = io::stdout().is_terminal() && io::stderr().is_terminal() && !quiet && !logging;
+    let options = NetworkOptions {
+        proxy: cli.proxy.clone(),
+        keyring: cli.keyring,
+        progress: drawn.then(|| Arc::new(ProgressBars::default()) as Arc<dyn Progress>),
+    };
+    NETWORK.get_or_init(|| (cli.extra_index_url.clone(), options));
+    let (outcome, no_fail) = match &cli.command {
+        Some(Command::Check(args)) => (run_check(args), args.fail.no_fail),
+        Some(Command::LockCheck(args)) => (run_lock_check(args), args.no_fail),
+        Some(Command::Drift(args)) => (run_drift(args), args.no_fail),
+        Some(Command::EnvCheck(args)) => (run_env_check(args), args.no_fail),
+        Some(Command::Docker(args)) => (run_docker(args), args.no_fail),
+        Some(Command::FreezeDiff(args)) => (run_freeze_diff(args), args.no_fail),
+        Some(Command::Tree(args)) => (run_tree(args), false),
+        Some(Command::Why(args)) => (run_why(args), false),
+        Some(Command::Audit(args)) => (run_audit(args), args.fail.no_fail),
+        Some(Command::Licenses(args)) => (run_licenses(args), args.no_fail),
+        Some(Command::Outdated(args)) => (run_outdated(args), args.fail.no_fail),
+        Some(Command::Info(args)) => (run_info(args), false),
+        Some(Command::Pin(args)) => (run_pin(args), false),
+        Some(Command::Simulate(args)) => (run_simulate(args), args.no_fail),
+        Some(Command::Downgrade(args)) => (run_downgrade(args), args.no_fail),
+        Some(Command::VerifyHashes(args)) => (run_verify_hashes(args), args.no_fail),
+        Some(Command::Scan(args)) => (run_scan(args), args.fail.no_fail),
+        Some(Command::Workspace(args)) => (run_workspace(args), args.no_fail),
+        Some(Command::Merge(args)) => (run_merge(args), false),
+        Some(Command::Convert(args)) => (run_convert(args), false),
+        Some(Command::Diff(args)) => (run_diff(args), args.no_fail),
+        Some(Command::SyncCheck(args)) => (run_sync_check(args), args.no_fail),
+        Some(Command::Simplify(args)) => (run_simplify(args), false),
+        Some(Command::Fmt(args)) => (run_fmt(args), false),
+        Some(Command::Lint(args)) => (run_lint(args), args.fail.no_fail),
+        Some(Command::Intersect(args)) => (run_intersect(args), args.no_fail),
+        Some(Command::Union(args)) => (run_union(args), false),
+        Some(Command::Selftest(args)) => (run_selftest(args), false),
+        Some(Command::Sbom(args)) => (run_sbom(args), false),
+        Some(Command::Cache(args)) => (run_cache(&args.action), false),
+        Some(Command::Baseline(args)) => (run_baseline(&args.action), false),
+        Some(Command::Tui(args)) => (run_tui(args), false),
+        Some(Command::Watch(args)) => (run_watch(args), false),
+        Some(Command::Serve(args)) => (run_serve(args), false),
+        Some(Command::Hook(args)) => (run_hook(args), false),
+        Some(Command::Lsp) => (lsp::run(io::stdin().lock(), io::stdout().lock()).map(|_| false), false),
+        Some(Command::Completions(args)) => (run_completions(args), false),
+        Some(Command::Schema) => (run_schema(), false),
+        None => (run_check(&cli.check), cli.check.fail.no_fail),
+    };
+    if let Some(stats) = stats {
+        stats.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).print(started.elapsed());
+    }
+
+    match outcome {
+        Ok(true) if !no_fail => ExitCode::from(EXIT_CONFLICT),
+        Ok(_) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("Error: {:?}", e);
+            ExitCode::from(EXIT_ERROR)
+        }
+    }
+}
+
+/// The settings of the configuration file named by `--config`, or found from
+/// the current directory, refined by `--profile`. They are read before the
+/// command line is parsed, as they provide its defaults.
+fn configured_settings() -> Result<Settings> {
+    let args: Vec<String> = std::env::args().skip(1).take_while(|arg| arg != "--").collect();
+    // The last occurrence wins, as it does for clap
+    let option = |name: &str| {
+        let flag = format!("--{}", name);
+        args.iter().enumerate().rev().find_map(|(i, arg)| match arg.strip_prefix(&flag) {
+            Some("") => args.get(i + 1).cloned(),
+            Some(value) => value.strip_prefix('=').map(str::to_string),
+            None => None,
+        })
+    };
+    let config = match option("config") {
+        Some(path) => Config::parse_file(Path::new(&path))?,
+        None => Config::discover(&std::env::current_dir()?)?.map(|(_, config)| config).unwrap_or_default(),
+    };
+    config.settings(option("profile").as_deref())
+}
+
+/// Makes the configured settings the defaults of the options they apply to,
+/// in every subcommand, so the command line still overrides them. A format
+/// is only applied to the commands supporting it.
+fn with_defaults(mut cmd: clap::Command, settings: &Settings) -> clap::Command {
+    let find = |cmd: &clap::Command, id: &str| cmd.get_arguments().find(|arg| arg.get_id() == id).cloned();
+    if let (Some(url), Some(_)) = (&settings.index_url, find(&cmd, "index_url")) {
+        cmd = cmd.mut_arg("index_url", |arg| arg.default_value(url.clone()));
+    }
+    if let (Some(urls), Some(_)) = (&settings.extra_index_url, find(&cmd, "extra_index_url")) {
+        cmd = cmd.mut_arg("extra_index_url", |arg| arg.default_values(urls.clone()));
+    }
+    if let (Some(proxy), Some(_)) = (&settings.proxy, find(&cmd, "proxy")) {
+        cmd = cmd.mut_arg("proxy", |arg| arg.default_value(proxy.clone()));
+    }
+    if let (Some(format), Some(arg)) = (&settings.format, find(&cmd, "format")) {
+        if arg.get_possible_values().iter().any(|value| value.matches(format, false)) {
+            cmd = cmd.mut_arg("format", |arg| arg.default_value(format.clone()));
+        }
+    }
+    if let (Some(version), Some(_)) = (&settings.python_version, find(&cmd, "python_version")) {
+        cmd = cmd.mut_arg("python_version", |arg| arg.default_value(version.to_string()));
+    }
+    if let (Some(platform), Some(_)) = (&settings.platform, find(&cmd, "platform")) {
+        cmd = cmd.mut_arg("platform", |arg| arg.default_value(platform.clone()));
+    }
+    if let (Some(patterns), Some(_)) = (&settings.internal, find(&cmd, "internal")) {
+        cmd = cmd.mut_arg("internal", |arg| arg.default_values(patterns.clone()));
+    }
+    if let (Some(policy), Some(_)) = (&settings.pin_policy, find(&cmd, "pin_policy")) {
+        let overrides: Vec<String> = policy.iter().map(|(pattern, rule)| format!("{}={}", pattern, rule)).collect();
+        cmd = cmd.mut_arg("pin_policy", |arg| arg.default_values(overrides));
+    }
+    if let (Some(patterns), Some(_)) = (&settings.allow_caps, find(&cmd, "allow_cap")) {
+        cmd = cmd.mut_arg("allow_cap", |arg| arg.default_values(patterns.clone()));
+    }
+    if let (Some(severity), Some(_)) = (settings.fail_on, find(&cmd, "fail_on")) {
+        cmd = cmd.mut_arg("fail_on", |arg| arg.default_value(severity.to_string()));
+    }
+    if let (Some(level), Some(_)) = (settings.max_severity, find(&cmd, "max_severity")) {
+        cmd = cmd.mut_arg("max_severity", |arg| arg.default_value(level.to_string()));
+    }
+    let subcommands: Vec<String> = cmd.get_subcommands().map(|sub| sub.get_name().to_string()).collect();
+    for name in subcommands {
+        cmd = cmd.mut_subcommand(name, |sub| with_defaults(sub, settings));
+    }
+    cmd
+}
+
+/// Adds the ignore rules of the configuration to those of the command line,
+/// dropping the expired ones with a warning.
+fn add_ignore_rules(cli: &mut Cli, rules: &[IgnoreRule]) {
+    let target = match &mut cli.command {
+        Some(Command::Check(args)) => &mut args.ignore,
+        Some(Command::Audit(args)) => &mut args.ignore,
+        Some(Command::Outdated(args)) => &mut args.ignore,
+        None => &mut cli.check.ignore,
+        _ => return,
+    };
+    let today = ignore::today();
+    for rule in rules {
+        match &rule.expires {
+            Some(expires) if rule.is_expired(&today) => {
+                eprintln!("{} the ignore rule {} expired on {} and no longer applies", "warning:".yellow().bold(), rule, expires);
+            }
+            _ => target.push(rule.clone()),
+        }
+    }
+}
+
+/// Prints the problems that ignore rules acknowledged, with their justification.
+fn print_acknowledged(acknowledged: &[Acknowledged]) {
+    for entry in acknowledged {
+        let mut notes = Vec::new();
+        notes.extend(entry.by.reason.clone());
+        notes.extend(entry.by.expires.as_ref().map(|expires| format!("until {}", expires)));
+        let notes = if notes.is_empty() { String::new() } else { format!("  ({})", notes.join("; ")) };
+        println!("{} {} {}{}", "IGNORED:".yellow().bold(), entry.package, entry.id, notes);
+    }
+}
+
+/// A marker that holds exactly under the given Python version: `3.11` covers
+/// every 3.11 release, `3.11.4` only that one.
+fn python_marker(version: &Pep440Version) -> Result<MarkerTree> {
+    let short: Vec<String> = version.release.iter().take(2).map(|n| n.to_string()).collect();
+    let mut marker = format!("python_version == '{}'", short.join("."));
+    if version.release.len() > 2 {
+        marker.push_str(&format!(" and python_full_version == '{}'", version));
+    }
+    MarkerTree::parse(&marker)
+}
+
+/// Runs the check and returns whether any conflict was found.
+fn run_check(args: &CheckArgs) -> Result<bool> {
+    let target = args.target()?;
+    let client = args
+        .online
+        .then(|| args.index.client())
+        .transpose()?
+        .map(|client| match &target {
+            Some(target) => client.with_target(target.clone()),
+            None => client,
+        });
+
+    if let (Some(pkg1), Some(pkg2)) = (&args.pkg1, &args.pkg2) {
+        return check_pair(pkg1, pkg2, args, client.as_ref());
+    }
+    if args.stdin {
+        return check_batch(args);
+    }
+
+    let manifests = Manifests::of(args)?;
+    let mut requirements = Vec::new();
+    let mut sources = Vec::new();
+    if let Some(path) = &manifests.requirements {
+        requirements.extend(requirements::parse_file(path)?);
+        sources.push(path.display().to_string());
+    }
+    if let Some(path) = &manifests.pyproject {
+        requirements.extend(pyproject::parse_file(path)?);
+        sources.push(path.display().to_string());
+    }
+    if let Some(path) = &manifests.pipfile {
+        requirements.extend(pipfile::parse_file(path)?);
+        sources.push(path.display().to_string());
+        let lock = path.with_file_name("Pipfile.lock");
+        if lock.is_file() {
+            requirements.extend(pipfile::parse_lock(&lock)?);
+            sources.push(lock.display().to_string());
+        }
+    }
+    if let Some(path) = &manifests.conda {
+        requirements.extend(conda::parse_file(path)?);
+        sources.push(path.display().to_string());
+    }
+    for path in &manifests.setup {
+        requirements.extend(setup::parse_file(path)?);
+        sources.push(path.display().to_string());
+    }
+    for path in &args.constraints {
+        requirements.extend(requirements::parse_file(path)?);
+        sources.push(path.display().to_string());
+    }
+    requirements.retain(|r| !ignore::ignores_package(&args.ignore, &r.package.name));
+    if let Some(version) = &args.python_version {
+        let python = python_marker(version)?;
+        requirements.retain(|r| {
+            let applies = r.package.marker.as_ref().is_none_or(|marker| marker.overlaps(&python));
+            if !applies && args.verbose > 0 {
+                eprintln!("Skipping {}  {} (not for Python {})", r.location(), r.package, version);
+            }
+            applies
+        });
+    }
+    check_requirements(&sources, &requirements, args, client.as_ref())
+}
+
+/// The dependency files a check reads, given as options or found by --auto.
+#[derive(Debug, Default)]
+struct Manifests {
+    requirements: Option<PathBuf>,
+    pyproject: Option<PathBuf>,
+    pipfile: Option<PathBuf>,
+    conda: Option<PathBuf>,
+    /// A setup.cfg and a setup.py may both declare dependencies.
+    setup: Vec<PathBuf>,
+}
+
+impl Manifests {
+    fn of(args: &CheckArgs) -> Result<Self> {
+        if !args.auto {
+            return Ok(Manifests {
+                requirements: args.requirements.clone(),
+                pyproject: args.pyproject.clone(),
+                pipfile: args.pipfile.clone(),
+                conda: args.conda.clone(),
+                setup: args.setup.iter().cloned().collect(),
+            });
+        }
+        let existing = |name: &&str| Path::new(name).is_file().then(|| PathBuf::from(name));
+        let manifests = Manifests {
+            requirements: ["requirements.txt"].iter().find_map(existing),
+            pyproject: ["pyproject.toml"].iter().find_map(existing),
+            pipfile: ["Pipfile"].iter().find_map(existing),
+            conda: ["environment.yml", "environment.yaml"].iter().find_map(existing),
+            setup: ["setup.cfg", "setup.py"].iter().filter_map(existing).collect(),
+        };
+        let found = [&manifests.requirements, &manifests.pyproject, &manifests.pipfile, &manifests.conda];
+        if found.iter().all(|path| path.is_none()) && manifests.setup.is_empty() {
+            return Err(anyhow!("No dependency files found in the current directory"));
+        }
+        Ok(manifests)
+    }
+}
+
+fn check_pair(
+    pkg1: &str,
+    pkg2: &str,
+    args: &CheckArgs,
+    client: Option<&PypiClient>,
+) -> Result<bool> {
+    let pkg1 = PythonPackage::parse(pkg1)?;
+    let pkg2 = PythonPackage::parse(pkg2)?;
+    let policy = args.prereleases.policy();
+    let same_package = pkg1.canonical_name() == pkg2.canonical_name();
+    let conflict = same_package && pkg1.conflicts_under(&pkg2, policy);
+
+    // Releases are needed to verify a satisfiable pair, or to diagnose a conflict
+    let releases = match client {
+        Some(client) if same_package && (!conflict || args.explain || args.suggest) => {
+            Some(client.releases(&pkg1.name)?)
+        }
+        _ => None,
+    };
+    let released: Option<Vec<_>> = releases.as_deref().filter(|_| !conflict).map(|releases| {
+        // A `===` pin only matches the release written the same way
+        let range = pkg1.specifiers.range().intersect(&pkg2.specifiers.range());
+        pypi::satisfying(releases, &range, policy.allows(&[&pkg1.specifiers, &pkg2.specifiers]))
+            .into_iter()
+            .filter(|v| pkg1.specifiers.matches_literal(v) && pkg2.specifiers.matches_literal(v))
+            .collect()
+    });
+    let failing = match (conflict, released.as_deref()) {
+        (true, _) => fails(sarif::CONFLICT_RULE, &pkg1.name, args.fail.max_severity),
+        (false, Some([])) => fails(sarif::UNRELEASED_RULE, &pkg1.name, args.fail.max_severity),
+        _ => false,
+    };
+    let why = || {
+        if !args.quiet {
+            print!("{}", diagnose(&[&pkg1, &pkg2], releases.as_deref(), args));
+        }
+    };
+    // Each package is traced on its own, as only requirements on the same one are compared
+    for package in if same_package { vec![vec![&pkg1, &pkg2]] } else { vec![vec![&pkg1], vec![&pkg2]] } {
+        let requirements: Vec<(String, &PythonPackage)> = package.into_iter().map(|p| (String::new(), p)).collect();
+        trace(&requirements[0].1.canonical_name(), &requirements, args.verbose);
+    }
+
+    if args.format != CheckFormat::Text {
+        let value = json::pair(&pkg1, &pkg2, released.as_deref(), policy);
+        let conflict = value["conflict"] == true;
+        if args.format == CheckFormat::Sarif {
+            print_json(&sarif::pair(&pkg1, &pkg2, conflict))?;
+        } else if args.format == CheckFormat::Github {
+            print_report(&github::pair(&pkg1, &pkg2, conflict))?;
+        } else if args.format == CheckFormat::Junit {
+            print_report(&junit::pair(&pkg1, &pkg2, conflict))?;
+        } else if args.format == CheckFormat::Markdown {
+            print_report(&Document::pair(&pkg1, &pkg2, conflict).markdown())?;
+        } else if args.format == CheckFormat::Html {
+            print_report(&Document::pair(&pkg1, &pkg2, conflict).html())?;
+        } else if args.format == CheckFormat::Jsonl {
+            print_report(&format!("{}\n", value))?;
+        } else {
+            print_json(&value)?;
+        }
+        return Ok(failing);
+    }
+
+    // The verdict is the first line of every outcome; --quiet prints nothing else
+    let detail = |line: String| {
+        if !args.quiet {
+            println!("{}", line);
+        }
+    };
+    detail(format!("\nAnalyzing potential conflicts between:\n  Package 1: {}\n  Package 2: {}\n", pkg1, pkg2));
+
+    if !same_package {
+        println!("{}", "No conflict: Different packages".green());
+        return Ok(false);
+    }
+
+    if conflict {
+        println!("{}", "CONFLICT DETECTED!".red().bold());
+        let exclusive = pkg1.specifiers.excludes_literal(&pkg2.specifiers)
+            || pkg1.specifiers.range().intersect(&pkg2.specifiers.range()).is_empty();
+        if exclusive {
+            detail("The version requirements are mutually exclusive.".to_string());
+        } else {
+            detail("Only pre-releases satisfy both requirements; pass --pre to allow them.".to_string());
+        }
+        why();
+        return Ok(failing);
+    }
+
+    match released.as_deref() {
+        Some([]) => {
+            println!("{}", "CONFLICT DETECTED!".red().bold());
+            detail(format!("No released version of {}{} satisfies both requirements.", pkg1.name, args.for_target()));
+            why();
+            Ok(failing)
+        }
+        Some(versions) => {
+            println!("{}", "No conflict detected".green());
+            detail(format!(
+                "{} released version(s){} satisfy both requirements (oldest: {}, latest: {}).",
+                versions.len(),
+                args.for_target(),
+                versions[0],
+                versions[versions.len() - 1]
+            ));
+            Ok(false)
+        }
+        None => {
+            println!("{}", "No conflict detected".green());
+            detail("The version requirements are compatible.".to_string());
+            // Without release data, show what both allow and a version inside it
+            match pkg1.specifiers.literal().or(pkg2.specifiers.literal()) {
+                // Only the text of a `===` pin matches it
+                Some(pinned) => detail(format!("Both allow only {}.", pinned)),
+                None => {
+                    let range = pkg1.specifiers.range().intersect(&pkg2.specifiers.range());
+                    let prereleases = policy.allows(&[&pkg1.specifiers, &pkg2.specifiers]);
+                    let example = witness(&range, prereleases).map(|v| format!(", e.g. {}", v)).unwrap_or_default();
+                    detail(format!("Both allow {}{}.", versions(&range), example));
+                }
+            }
+            Ok(false)
+        }
+    }
+}
+
+fn check_requirements(
+    sources: &[String],
+    requirements: &[RequirementLine],
+    args: &CheckArgs,
+    client: Option<&PypiClient>,
+) -> Result<bool> {
+    let mut report = ConflictReport::check_with(requirements, args.prereleases.policy());
+    let mut verified = client.map(|client| client.verify(&report)).transpose()?;
+    let baseline = args.baseline.as_deref().map(Baseline::parse_file).transpose()?;
+    let mut acknowledged = Vec::new();
+    report.conflicts.retain(|conflict| {
+        !ignore::acknowledge(&args.ignore, &conflict.name, &[sarif::CONFLICT_RULE], &mut acknowledged)
+            && !baseline.as_ref().is_some_and(|baseline| baseline.acknowledge_conflict(conflict, &mut acknowledged))
+    });
+    if let Some(verified) = &mut verified {
+        verified.retain(|v| {
+            !v.matching.is_empty() || !ignore::acknowledge(&args.ignore, &v.name, &[sarif::UNRELEASED_RULE], &mut acknowledged)
+        });
+    }
+
+    let unreleased: Vec<&Verification> = verified
+        .iter()
+        .flatten()
+        .filter(|v| v.matching.is_empty())
+        .collect();
+    let failing = report.conflicts.iter().any(|c| fails(sarif::CONFLICT_RULE, &c.name, args.fail.max_severity))
+        || unreleased.iter().any(|v| fails(sarif::UNRELEASED_RULE, &v.name, args.fail.max_severity));
+
+    if args.verbose > 0 {
+        let mut files: Vec<&Path> = requirements.iter().map(|r| r.file.as_path()).collect();
+        files.dedup();
+        for file in files {
+            let count = requirements.iter().filter(|r| r.file == file).count();
+            eprintln!("Read {} requirement(s) from {}", count, file.display());
+        }
+        for summary in &report.packages {
+            let requirements: Vec<(String, &PythonPackage)> = summary
+                .requirements
+                .iter()
+                .map(|r| (format!("{}  ", r.location()), &r.package))
+                .collect();
+            trace(&summary.name, &requirements, args.verbose);
+        }
+    }
+
+    if args.format != CheckFormat::Text {
+        let mut value = json::report(sources, &report, verified.as_deref());
+        value["ignored"] = json::acknowledged(&acknowledged);
+        if args.format == CheckFormat::Sarif {
+            print_json(&sarif::report(&report, verified.as_deref()))?;
+        } else if args.format == CheckFormat::Github {
+            print_report(&github::report(&report, verified.as_deref()))?;
+        } else if args.format == CheckFormat::Junit {
+            print_report(&junit::report(&report, verified.as_deref()))?;
+        } else if args.format == CheckFormat::Markdown {
+            print_report(&Document::report(sources, &report, verified.as_deref()).markdown())?;
+        } else if args.format == CheckFormat::Html {
+            print_report(&Document::report(sources, &report, verified.as_deref()).html())?;
+        } else if args.format == CheckFormat::Jsonl {
+            print_report(&format!("{}\n", value))?;
+        } else {
+            print_json(&value)?;
+        }
+        return Ok(failing);
+    }
+
+    let conflicting = report.conflicts.len() + unreleased.len();
+    if args.quiet {
+        match conflicting {
+            0 => println!("{}", "No conflicts detected".green()),
+            n => println!("{}", format!("{} package(s) have mutually exclusive requirements.", n).red().bold()),
+        }
+        return Ok(failing);
+    }
+
+    println!(
+        "\nAnalyzing {} requirements from {}\n",
+        requirements.len(),
+        sources.join(", ")
+    );
+    print_acknowledged(&acknowledged);
+    if !report.has_conflicts() && unreleased.is_empty() {
+        println!("{}", "No conflicts detected".green());
+        match verified {
+            Some(_) => println!("Every package has a released version satisfying its requirements."),
+            None => println!("All version requirements are compatible."),
+        }
+        return Ok(false);
+    }
+
+    for conflict in &report.conflicts {
+        println!("{} {}", "CONFLICT DETECTED:".red().bold(), conflict.name);
+        for line in &conflict.requirements {
+            println!("  {}  {}", line.location(), line.package);
+        }
+        if args.explain || args.suggest {
+            let releases = client.map(|client| client.releases(&conflict.name)).transpose()?;
+            let packages: Vec<&PythonPackage> = conflict.requirements.iter().map(|r| &r.package).collect();
+            print!("{}", indent(&diagnose(&packages, releases.as_deref(), args)));
+        }
+    }
+    for verification in &unreleased {
+        println!(
+            "{} {} (no released version{} satisfies the requirements)",
+            "CONFLICT DETECTED:".red().bold(),
+            verification.name,
+            args.for_target()
+        );
+        let summary = report.packages.iter().find(|p| p.name == verification.name);
+        let lines = summary.map(|p| p.requirements.as_slice()).unwrap_or_default();
+        for line in lines {
+            println!("  {}  {}", line.location(), line.package);
+        }
+        let packages: Vec<&PythonPackage> = lines.iter().map(|r| &r.package).collect();
+        print!("{}", indent(&diagnose(&packages, Some(&verification.releases), args)));
+    }
+    println!("\n{} package(s) have mutually exclusive requirements.", conflicting);
+
+    Ok(failing)
+}
+
+/// With `-v`, prints to standard error the versions each requirement on a
+/// package allows and what they allow together; `-vv` adds every clause,
+/// extra and marker. Each requirement comes with its location, if any.
+fn trace(name: &str, requirements: &[(String, &PythonPackage)], verbose: u8) {
+    if verbose == 0 {
+        return;
+    }
+    let allowed = requirements
+        .iter()
+        .fold(IntervalSet::full(), |acc, (_, package)| acc.intersect(&package.specifiers.range()));
+    eprintln!("{}: together allow {}", name.bold(), versions(&allowed));
+    for (location, package) in requirements {
+        eprintln!("  {}{}  allows {}", location, package, versions(&package.specifiers.range()));
+        if verbose < 2 {
+            continue;
+        }
+        for clause in &package.specifiers.specifiers {
+            eprintln!("    {}  allows {}", clause, versions(&clause.range()));
+        }
+        if !package.extras.is_empty() {
+            eprintln!("    extras: {}", package.extras.join(", "));
+        }
+        if let Some(marker) = &package.marker {
+            eprintln!("    only where {}", marker);
+        }
+    }
+}
+
+/// Checks every line of standard input on its own and returns whether any
+/// line has a conflict. Lines that cannot be parsed are reported and make the
+/// run fail once all lines are done.
+fn check_batch(args: &CheckArgs) -> Result<bool> {
+    let located = match args.format {
+        CheckFormat::Sarif => Some("SARIF"),
+        CheckFormat::Github => Some("GitHub annotation"),
+        _ => None,
+    };
+    if let Some(name) = located {
+        return Err(anyhow!("{} output needs file locations and is not available with --stdin", name));
+    }
+    if matches!(args.format, CheckFormat::Markdown | CheckFormat::Html) {
+        return Err(anyhow!("Reports are of a single set of requirements and not available with --stdin"));
+    }
+    let policy = args.prereleases.policy();
+    let mut out = io::stdout().lock();
+    let mut documents = Vec::new();
+    let mut cases = Vec::new();
+    let (mut conflicts, mut errors) = (0, 0);
+    for (i, input) in io::stdin().lock().lines().enumerate() {
+        let input = input.context("Cannot read standard input")?;
+        if batch::is_blank(&input) {
+            continue;
+        }
+        let line = i + 1;
+        let (value, text) = match batch::parse_line(&input, line) {
+            Ok(requirements) => {
+                let report = ConflictReport::check_with(&requirements, policy);
+                let mut value = json::report(&[batch::STDIN_NAME.to_string()], &report, None);
+                value["line"] = line.into();
+                value["input"] = input.as_str().into();
+                let text = if report.has_conflicts() {
+                    if report.conflicts.iter().any(|c| fails(sarif::CONFLICT_RULE, &c.name, args.fail.max_severity)) {
+                        conflicts += 1;
+                    }
+                    let packages: Vec<String> = report
+                        .conflicts
+                        .iter()
+                        .map(|conflict| {
+                            let specs: Vec<String> =
+                                conflict.requirements.iter().map(|r| r.package.version_spec()).collect();
+                            format!("{} ({})", conflict.name, specs.join(" vs "))
+                        })
+                        .collect();
+                    format!("{}: {} {}", line, "conflict:".red().bold(), packages.join(", "))
+                } else {
+                    format!("{}: {}", line, "ok".green())
+                };
+                if args.format == CheckFormat::Junit {
+                    cases.push((line, input.clone(), Ok(report)));
+                }
+                (value, text)
+            }
+            Err(e) => {
+                errors += 1;
+                if args.format == CheckFormat::Junit {
+                    cases.push((line, input.clone(), Err(e.to_string())));
+                }
+                let value = serde_json::json!({
+                    "schema_version": json::SCHEMA_VERSION,
+                    "line": line,
+                    "input": input,
+                    "error": e.to_string(),
+                });
+                (value, format!("{}: {} {}", line, "error:".red().bold(), e))
+            }
+        };
+        match args.format {
+            // Each line is written as soon as it is checked
+            CheckFormat::Text => output::write_report(&mut out, &format!("{}\n", text))?,
+            CheckFormat::Jsonl => output::write_report(&mut out, &format!("{}\n", value))?,
+            CheckFormat::Json => documents.push(value),
+            CheckFormat::Sarif | CheckFormat::Github | CheckFormat::Junit | CheckFormat::Markdown | CheckFormat::Html => {}
+        }
+    }
+    drop(out);
+    if args.format == CheckFormat::Json {
+        print_json(&documents)?;
+    } else if args.format == CheckFormat::Junit {
+        print_report(&junit::batch(&cases))?;
+    }
+
+    if errors > 0 {
+        return Err(anyhow!("{} input line(s) could not be checked", errors));
+    }
+    Ok(conflicts > 0)
+}
+
+/// Writes a report to standard output. A reader closing the pipe early, as
+/// `| head` does, ends the output without an error.
+fn print_report(report: &str) -> Result<()> {
+    Ok(output::write_report(&mut io::stdout().lock(), report)?)
+}
+
+/// Writes a JSON report to standard output, indented.
+fn print_json(value: &impl serde::Serialize) -> Result<()> {
+    print_report(&format!("{}\n", serde_json::to_string_pretty(value)?))
+}
+
+/// Runs `lock-check` and returns whether any problem was found.
+fn run_lock_check(args: &LockCheckArgs) -> Result<bool> {
+    let lock_path = match &args.lock {
+        Some(path) => path.clone(),
+        None => lock::find_lock_file(&args.pyproject).ok_or_else(|| {
+            anyhow!(
+                "No lock file found next to {} (looked for {})",
+                args.pyproject.display(),
+                lock::LOCK_FILE_NAMES.join(", ")
+            )
+        })?,
+    };
+    let requirements = pyproject::parse_file(&args.pyproject)?;
+    let locked = lock::parse_file(&lock_path)?;
+    let report = LockReport::check(&requirements, &locked);
+
+    if args.format == OutputFormat::Json {
+        let value = json::lock_report(&args.pyproject, &lock_path, &report);
+        print_json(&value)?;
+        return Ok(!report.is_clean());
+    }
+
+    println!(
+        "\nChecking {} locked packages in {} against {}\n",
+        locked.len(),
+        lock_path.display(),
+        args.pyproject.display()
+    );
+    if report.is_clean() {
+        println!("{}", "Lock file is up to date".green());
+        println!("All {} requirements are satisfied by the locked versions.", report.checked);
+        return Ok(false);
+    }
+
+    print_lock_issues(&report.issues);
+    for conflict in &report.conflicts.conflicts {
+        println!("{} {}", "CONFLICT DETECTED:".red().bold(), conflict.name);
+        for line in &conflict.requirements {
+            println!("  {}  {}", line.location(), line.package);
+        }
+    }
+    println!(
+        "\n{} requirement(s) are not satisfied by the lock file and {} package(s) have conflicting constraints.",
+        report.issues.len(),
+        report.conflicts.conflicts.len()
+    );
+
+    Ok(true)
+}
+
+/// Prints every declared requirement a lock file does not honour.
+fn print_lock_issues(issues: &[LockIssue]) {
+    for issue in issues {
+        let requirement = issue.requirement();
+        match issue {
+            LockIssue::Stale { locked, .. } => {
+                println!("{} {}", "STALE PIN:".red().bold(), requirement.package.name);
+                println!("  {}  {}", requirement.location(), requirement.package);
+                for pin in locked {
+                    println!("  {}:{}  locked at {}", pin.file.display(), pin.line, pin.version);
+                }
+            }
+            LockIssue::Missing { .. } => {
+                println!("{} {}", "MISSING FROM LOCK:".red().bold(), requirement.package.name);
+                println!("  {}  {}", requirement.location(), requirement.package);
+            }
+        }
+    }
+}
+
+/// Runs `drift` and returns whether any lock file drifted from a manifest.
+fn run_drift(args: &DriftArgs) -> Result<bool> {
+    let pairs = match (&args.manifest, &args.lock) {
+        (Some(manifest), Some(lock)) => vec![(manifest.clone(), lock.clone())],
+        _ => drift::find_pairs(&args.dir)?,
+    };
+    if pairs.is_empty() {
+        return Err(anyhow!("No manifest with a lock file found in {}", args.dir.display()));
+    }
+    let drifts: Vec<Drift> = pairs.iter().map(|(manifest, lock)| drift::check(manifest, lock)).collect::<Result<_>>()?;
+    let drifted = drifts.iter().filter(|drift| drift.has_drift()).count();
+
+    if args.format == OutputFormat::Json {
+        let value = json::drift_report(&drifts);
+        print_json(&value)?;
+        return Ok(drifted > 0);
+    }
+
+    for drift in &drifts {
+        println!("\nComparing {} with {}\n", drift.lock.display(), drift.manifest.display());
+        if !drift.has_drift() {
+            println!("{}", "No drift".green());
+            continue;
+        }
+        print_lock_issues(&drift.issues);
+        for package in drift.undeclared.iter().flatten() {
+            println!("{} {}", "NOT DECLARED:".red().bold(), package.name);
+            println!("  {}:{}  locked at {}", package.file.display(), package.line, package.version);
+        }
+    }
+    println!("\n{} of {} lock file and manifest pair(s) drifted.", drifted, drifts.len());
+    Ok(drifted > 0)
+}
+
+/// Runs `env-check` and returns whether any installed requirement is broken.
+fn run_env_check(args: &EnvCheckArgs) -> Result<bool> {
+    let (environment, distributions) = match &args.exec {
+        Some(command) => Environment::from_importlib(&command.split_whitespace().map(OsString::from).collect::<Vec<_>>())?,
+        None => {
+            let environment = load_environment(args.python.as_deref(), &args.site_packages)?;
+            let distributions = environment.distributions()?;
+            (environment, distributions)
+        }
+    };
+    let issues = env::check(&distributions, environment.markers.as_ref());
+
+    if args.format == OutputFormat::Json {
+        let value = json::env_report(&environment, &distributions, &issues);
+        print_json(&value)?;
+        return Ok(!issues.is_empty());
+    }
+
+    let locations: Vec<String> = environment
+        .site_packages
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+    println!(
+        "\nChecking {} installed distributions in {}\n",
+        distributions.len(),
+        locations.join(", ")
+    );
+    print_env_issues(&issues);
+    Ok(!issues.is_empty())
+}
+
+/// Runs `docker`, reading the image from an archive when a file of that name
+/// exists or it ends in `.tar`, and pulling it otherwise. Returns whether a
+/// requirement is broken or an advisory fails the audit.
+fn run_docker(args: &DockerArgs) -> Result<bool> {
+    let path = Path::new(&args.image);
+    let image = if path.is_file() || args.image.ends_with(".tar") {
+        docker::read_archive(path, &args.platform)?
+    } else {
+        let reference: docker::Reference = args.image.parse()?;
+        docker::Registry::with_options(&network().1)?.pull(&reference, &args.platform)?
+    };
+    let issues = env::check(&image.distributions, image.environment.markers.as_ref());
+    let mut packages = image.audited();
+    packages.retain(|package| !ignore::ignores_package(&args.ignore, &package.name));
+    let mut findings = if args.no_audit {
+        Vec::new()
+    } else {
+        OsvClient::with_options(&args.osv_url, &network().1)?.audit(&packages)?
+    };
+    let mut acknowledged = Vec::new();
+    for finding in &mut findings {
+        let name = &finding.package.name;
+        finding.advisories.retain(|advisory| {
+            let ids: Vec<&str> = std::iter::once(&advisory.id).chain(&advisory.aliases).map(String::as_str).collect();
+            !ignore::acknowledge(&args.ignore, name, &ids, &mut acknowledged)
+        });
+    }
+    findings.retain(|finding| !finding.advisories.is_empty());
+    let vulnerable = findings.iter().flat_map(|f| &f.advisories).any(|advisory| match (args.fail_on, advisory.severity) {
+        (Some(threshold), Some(severity)) => severity >= threshold,
+        _ => true,
+    });
+
+    if args.format == OutputFormat::Json {
+        let audit = (!args.no_audit).then(|| {
+            let mut value = json::audit_report(Path::new(&image.source), packages.len(), &findings, &[]);
+            value["ignored"] = json::acknowledged(&acknowledged);
+            value
+        });
+        print_json(&json::image_report(&image, &issues, audit))?;
+        return Ok(!issues.is_empty() || vulnerable);
+    }
+
+    let locations: Vec<String> = image.environment.site_packages.iter().map(|p| p.display().to_string()).collect();
+    println!(
+        "\nChecking {} installed distributions of {} ({}) in {}\n",
+        image.distributions.len(),
+        image.source,
+        image.platform,
+        if locations.is_empty() { "no site-packages directory".to_string() } else { locations.join(", ") }
+    );
+    print_env_issues(&issues);
+    if args.no_audit {
+        return Ok(!issues.is_empty());
+    }
+    println!();
+    print_findings(&findings);
+    print_acknowledged(&acknowledged);
+    if findings.is_empty() {
+        println!("{}", "No known vulnerabilities found".green());
+    } else {
+        let count: usize = findings.iter().map(|f| f.advisories.len()).sum();
+        println!("\n{} known vulnerabilit(ies) in {} of {} packages.", count, findings.len(), packages.len());
+    }
+    if !findings.is_empty() && !vulnerable {
+        println!("None of them is at least {} severity.", args.fail_on.map(|s| s.to_string()).unwrap_or_default());
+    }
+    Ok(!issues.is_empty() || vulnerable)
+}
+
+/// Prints every broken requirement of an environment, or that there is none.
+fn print_env_issues(issues: &[env::EnvIssue]) {
+    if issues.is_empty() {
+        println!("{}", "No broken requirements found".green());
+        return;
+    }
+    for issue in issues {
+        let requirement = &issue.requirement;
+        match &issue.installed {
+            Some(version) => println!(
+                "{} {} {} requires {} {}, but {} is installed",
+                "BROKEN REQUIREMENT:".red().bold(),
+                issue.dependent,
+                issue.dependent_version,
+                requirement.name,
+                requirement.version_spec(),
+                version
+            ),
+            None => println!(
+                "{} {} {} requires {} {}, which is not installed",
+                "MISSING DEPENDENCY:".red().bold(),
+                issue.dependent,
+                issue.dependent_version,
+                requirement.name,
+                requirement.version_spec()
+            ),
+        }
+    }
+    println!("\n{} requirement(s) of installed packages are not satisfied.", issues.len());
+}
+
+/// Prints every package with known vulnerabilities and its advisories.
+fn print_findings(findings: &[audit::Finding]) {
+    for finding in findings {
+        let package = &finding.package;
+        let origin = if package.resolved { " (latest matching release)" } else { "" };
+        println!(
+            "{} {} {}  {}:{}{}",
+            "VULNERABLE:".red().bold(),
+            package.name,
+            package.version,
+            package.file.display(),
+            package.line,
+            origin
+        );
+        for advisory in &finding.advisories {
+            let mut title = advisory.id.clone();
+            if !advisory.aliases.is_empty() {
+                title = format!("{} ({})", title, advisory.aliases.join(", "));
+            }
+            let severity = match (advisory.severity, advisory.score) {
+                (Some(severity), Some(score)) => format!("{} {:.1}", severity, score),
+                (Some(severity), None) => severity.to_string(),
+                (None, _) => "UNKNOWN".to_string(),
+            };
+            println!("  {}  {}", title, severity.yellow());
+            if let Some(summary) = &advisory.summary {
+                println!("    {}", summary);
+            }
+            match advisory.fixed_after(&package.version) {
+                Some(fixed) => println!("    fixed in {}", fixed),
+                None => println!("    no fixed version published"),
+            }
+        }
+    }
+}
+
+/// Runs `freeze-diff` and returns whether the new file has conflicting requirements.
+fn run_freeze_diff(args: &FreezeDiffArgs) -> Result<bool> {
+    let old = requirements::parse_file(&args.old)?;
+    let new = requirements::parse_file(&args.new)?;
+    let changes = freeze::diff(&old, &new);
+    let report = ConflictReport::check(&new);
+
+    if args.format == OutputFormat::Json {
+        let value = json::freeze_diff(&args.old, &args.new, &changes, &report);
+        print_json(&value)?;
+        return Ok(report.has_conflicts());
+    }
+
+    println!("\nComparing {} -> {}\n", args.old.display(), args.new.display());
+    if changes.is_empty() {
+        println!("{}", "No changes".green());
+    }
+    for change in &changes {
+        let version = |line: &Option<RequirementLine>| {
+            line.as_ref().map_or(String::new(), |l| match freeze::pinned_version(l) {
+                Some(version) => version.to_string(),
+                None => l.package.version_spec(),
+            })
+        };
+        let label = format!("{:<10}", change.kind.to_string().to_uppercase());
+        let label = if change.kind.is_risky() {
+            label.red().bold()
+        } else {
+            label.normal()
+        };
+        let detail = match change.kind {
+            ChangeKind::Added => version(&change.new),
+            ChangeKind::Removed => version(&change.old),
+            _ => format!("{} -> {}", version(&change.old), version(&change.new)),
+        };
+        println!("  {} {} {}", label, change.name, detail);
+    }
+
+    let count = |kind: ChangeKind| changes.iter().filter(|c| c.kind == kind).count();
+    println!(
+        "\n{} major, {} minor, {} patch, {} downgrade(s), {} added, {} removed",
+        count(ChangeKind::Major),
+        count(ChangeKind::Minor),
+        count(ChangeKind::Patch),
+        count(ChangeKind::Downgrade),
+        count(ChangeKind::Added),
+        count(ChangeKind::Removed)
+    );
+
+    // Pins that no longer agree with other requirements listed in the new file
+    for conflict in &report.conflicts {
+        println!("{} {}", "CONFLICT DETECTED:".red().bold(), conflict.name);
+        for line in &conflict.requirements {
+            println!("  {}  {}", line.location(), line.package);
+        }
+    }
+
+    Ok(report.has_conflicts())
+}
+
+/// Runs `tree`; it only prints, so it never reports a failure.
+fn run_tree(args: &TreeArgs) -> Result<bool> {
+    let package = args.package.as_deref().map(PythonPackage::parse).transpose()?;
+    let graph = if args.env {
+        let environment = load_environment(args.python.as_deref(), &args.site_packages)?;
+        DependencyGraph::from_installed(&environment.distributions()?, environment.markers.as_ref())
+    } else {
+        let client = args.index.client()?;
+        DependencyGraph::from_index(&client, package.as_slice(), args.depth, args.prereleases.policy())?
+    };
+
+    let roots = match &package {
+        Some(package) if graph.node(&package.name).is_none() => {
+            return Err(anyhow!("Package not installed: {}", package.name));
+        }
+        Some(package) => vec![package.canonical_name()],
+        None => graph.roots.clone(),
+    };
+    let options = TreeOptions {
+        depth: args.depth,
+        invert: args.invert,
+        charset: if args.ascii { Charset::Ascii } else { Charset::Utf8 },
+    };
+    match args.format {
+        GraphFormat::Text => print_report(&tree::render(&graph, &roots, &options))?,
+        format => {
+            let edges = diagram::tree_edges(&graph, &roots, &options);
+            print_report(&render_diagram(format, &graph, &roots, &edges))?;
+        }
+    }
+
+    Ok(false)
+}
+
+/// Runs `why`; like `tree` it only prints.
+fn run_why(args: &WhyArgs) -> Result<bool> {
+    let mut declared = Vec::new();
+    let graph = match &args.requirements {
+        Some(path) => {
+            declared = requirements::parse_file(path)?;
+            let packages: Vec<PythonPackage> = declared.iter().map(|line| line.package.clone()).collect();
+            let client = args.index.client()?;
+            DependencyGraph::from_index(&client, &packages, None, args.prereleases.policy())?
+        }
+        None => {
+            let environment = load_environment(args.python.as_deref(), &args.site_packages)?;
+            DependencyGraph::from_installed(&environment.distributions()?, environment.markers.as_ref())
+        }
+    };
+
+    let chains = graph.paths_to(&args.package);
+    if args.format != GraphFormat::Text {
+        let mut roots: Vec<String> = Vec::new();
+        let mut edges: Vec<&Edge> = Vec::new();
+        for chain in &chains {
+            let root = chain.first().map_or_else(|| canonicalize_name(&args.package), |edge| edge.from.clone());
+            if !roots.contains(&root) {
+                roots.push(root);
+            }
+            for edge in chain {
+                if !edges.iter().any(|e| std::ptr::eq(*e, *edge)) {
+                    edges.push(edge);
+                }
+            }
+        }
+        print_report(&render_diagram(args.format, &graph, &roots, &edges))?;
+        return Ok(false);
+    }
+    let Some(node) = graph.node(&args.package).filter(|_| !chains.is_empty()) else {
+        println!("\nNothing depends on {}", args.package);
+        return Ok(false);
+    };
+    let unsatisfied: Vec<_> = graph
+        .dependents(&args.package)
+        .into_iter()
+        .filter(|edge| !graph.is_satisfied(edge))
+        .collect();
+
+    println!(
+        "\n{} is pulled in by {} dependency chain(s):\n",
+        node.name,
+        chains.len()
+    );
+    for chain in &chains {
+        let root = chain.first().map_or_else(|| canonicalize_name(&args.package), |edge| edge.from.clone());
+        // Chains from a requirements file start at the line declaring the root
+        let location = declared
+            .iter()
+            .find(|line| line.package.canonical_name() == root)
+            .map_or(String::new(), |line| format!("{}  ", line.location()));
+        println!("  {}{}", location, tree::render_chain(&graph, &root, chain));
+    }
+    if !unsatisfied.is_empty() {
+        println!(
+            "\n{} {} requirement(s) on {} are not satisfied by {}.",
+            "CONFLICT DETECTED:".red().bold(),
+            unsatisfied.len(),
+            node.name,
+            node.version.as_ref().map_or("any version".to_string(), |v| v.to_string())
+        );
+    }
+
+    Ok(false)
+}
+
+/// Runs `audit` and returns whether any vulnerable version was found.
+/// The pinned versions of a requirements file, `pyproject.toml`,
+/// `Pipfile.lock` or lock file, and the requirements that are not pinned.
+fn pinned_packages(path: &Path) -> Result<(Vec<Audited>, Vec<RequirementLine>)> {
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    Ok(if lock::LOCK_FILE_NAMES.contains(&file_name) {
+        let locked = lock::parse_file(path)?;
+        (locked.iter().map(Audited::from).collect(), Vec::new())
+    } else if file_name == "Pipfile.lock" {
+        audit::pinned(&pipfile::parse_lock(path)?)
+    } else if file_name == "pyproject.toml" {
+        audit::pinned(&pyproject::parse_file(path)?)
+    } else {
+        audit::pinned(&requirements::parse_file(path)?)
+    })
+}
+
+fn run_audit(args: &AuditArgs) -> Result<bool> {
+    let (mut packages, mut skipped) = pinned_packages(&args.file)?;
+    packages.retain(|package| !ignore::ignores_package(&args.ignore, &package.name));
+    skipped.retain(|line| !ignore::ignores_package(&args.ignore, &line.package.name));
+    if !args.no_resolve && !skipped.is_empty() {
+        let client = args.index.client()?;
+        let (resolved, unresolved) = audit::resolve(&client, &skipped, args.prereleases.policy())?;
+        packages.extend(resolved);
+        skipped = unresolved;
+    }
+    let mut findings = OsvClient::with_options(&args.osv_url, &network().1)?.audit(&packages)?;
+    for source in plugins(Capability::Advisories) {
+        plugin::merge_findings(&mut findings, source.advisories(&packages)?);
+    }
+    let baseline = args.baseline.as_deref().map(Baseline::parse_file).transpose()?;
+    let mut acknowledged = Vec::new();
+    for finding in &mut findings {
+        let name = &finding.package.name;
+        finding.advisories.retain(|advisory| {
+            let ids: Vec<&str> = std::iter::once(&advisory.id).chain(&advisory.aliases).map(String::as_str).collect();
+            !ignore::acknowledge(&args.ignore, name, &ids, &mut acknowledged)
+                && !baseline.as_ref().is_some_and(|baseline| baseline.acknowledge_advisory(name, &ids, &mut acknowledged))
+        });
+    }
+    findings.retain(|finding| !finding.advisories.is_empty());
+    // Heuristics, whose rules are warnings unless configured otherwise
+    let mut typosquats: Vec<(PathBuf, usize, typosquat::Suspect)> = Vec::new();
+    if args.typosquat {
+        let declared = packages.iter().map(|p| (&p.name, &p.file, p.line));
+        for (name, file, line) in declared.chain(skipped.iter().map(|l| (&l.package.name, &l.file, l.line))) {
+            if let Some(suspect) = typosquat::check(name) {
+                typosquats.push((file.clone(), line, suspect));
+            }
+        }
+    }
+    let lookalikes = if args.internal.is_empty() {
+        Vec::new()
+    } else {
+        // Asked fresh, as a lookalike may have been published since the last run
+        let public = PypiClient::with_options(&args.public_index_url, &[], &network().1)?;
+        let pinned = packages.iter().map(|p| (p.name.as_str(), (!p.resolved).then_some(&p.version)));
+        let declared: Vec<(&str, Option<&Pep440Version>)> = pinned
+            .chain(skipped.iter().map(|line| (line.package.name.as_str(), None)))
+            .filter(|(name, _)| confusion::is_internal(name, &args.internal))
+            .collect();
+        confusion::check(&public, &declared)?
+    };
+    let unmaintained: Vec<Maintenance> = if args.maintenance {
+        let client = args.index.client()?;
+        let names: Vec<&str> = packages.iter().map(|package| package.name.as_str()).collect();
+        let today = timeline::days(&ignore::today()).unwrap_or_default();
+        let mut reports = maintenance::assess(&client, &names, today, true)?;
+        reports.retain(Maintenance::is_likely_unmaintained);
+        reports
+    } else {
+        Vec::new()
+    };
+    let max = args.fail.max_severity;
+    let failing = findings.iter().any(|finding| {
+        finding.advisories.iter().any(|advisory| match (args.fail_on, advisory.severity) {
+            (Some(threshold), Some(severity)) if severity < threshold => false,
+            _ => fails(severity::VULNERABILITY_RULE, &finding.package.name, max),
+        })
+    }) || typosquats.iter().any(|(_, _, suspect)| fails(severity::TYPOSQUAT_RULE, &suspect.name, max))
+        || lookalikes.iter().any(|lookalike| {
+            let rule = if lookalike.outranks() { severity::CONFUSION_RULE } else { severity::LOOKALIKE_RULE };
+            fails(rule, &lookalike.name, max)
+        })
+        || unmaintained.iter().any(|report| fails(severity::UNMAINTAINED_RULE, &report.name, max));
+
+    let report = || {
+        let mut value = json::audit_report(&args.file, packages.len(), &findings, &skipped);
+        value["ignored"] = json::acknowledged(&acknowledged);
+        if args.maintenance {
+            value["unmaintained"] = unmaintained.iter().map(json::maintenance).collect();
+        }
+        if args.typosquat {
+            value["typosquats"] = json::typosquats(&typosquats);
+        }
+        if !args.internal.is_empty() {
+            value["public_lookalikes"] = json::lookalikes(&lookalikes);
+        }
+        value
+    };
+    send_report("audit", &args.notify, report)?;
+
+    if args.format == AuditFormat::Github {
+        print_report(&github::audit(&findings, args.fail_on))?;
+        return Ok(failing);
+    }
+    if args.format == AuditFormat::Csv {
+        print_report(&csv::audit(&findings).csv())?;
+        return Ok(failing);
+    }
+    if args.format == AuditFormat::Tsv {
+        print_report(&csv::audit(&findings).tsv())?;
+        return Ok(failing);
+    }
+    if args.format == AuditFormat::Json {
+        print_json(&report())?;
+        return Ok(failing);
+    }
+
+    println!("\nAuditing {} packages from {}\n", packages.len(), args.file.display());
+    print_findings(&findings);
+    for lookalike in &lookalikes {
+        let label = if lookalike.outranks() { "DEPENDENCY CONFUSION:".red().bold() } else { "PUBLIC LOOKALIKE:".yellow().bold() };
+        println!("{} {}", label, lookalike);
+    }
+    for (file, line, suspect) in &typosquats {
+        println!("{} {}:{}  {}", "POSSIBLE TYPOSQUAT:".yellow().bold(), file.display(), line, suspect);
+    }
+    for report in &unmaintained {
+        let latest = report.latest.as_ref().map(|v| format!(" (latest {})", v)).unwrap_or_default();
+        println!("{} {}{}", "LIKELY UNMAINTAINED:".yellow().bold(), report.name, latest);
+        for signal in &report.signals {
+            println!("  {}", signal);
+        }
+    }
+    for line in &skipped {
+        println!("{} {}:{}  {}", "NOT AUDITED:".yellow().bold(), line.file.display(), line.line, line.package);
+    }
+    print_acknowledged(&acknowledged);
+
+    if findings.is_empty() {
+        println!("{}", "No known vulnerabilities found".green());
+    } else {
+        let count: usize = findings.iter().map(|f| f.advisories.len()).sum();
+        println!(
+            "\n{} known vulnerabilit(ies) in {} of {} packages.",
+            count,
+            findings.len(),
+            packages.len()
+        );
+    }
+    if !skipped.is_empty() {
+        let hint = if args.no_resolve { "pin them to audit them" } else { "no release satisfies them" };
+        println!("{} requirement(s) were not audited: {}.", skipped.len(), hint);
+    }
+    if !lookalikes.is_empty() {
+        println!(
+            "{} internal package name(s) exist on {}: pin them with hashes, or install them from the internal index only.",
+            lookalikes.len(),
+            args.public_index_url
+        );
+    }
+    if !typosquats.is_empty() {
+        println!("{} name(s) resemble a popular project: check they are the packages you mean.", typosquats.len());
+    }
+    if !unmaintained.is_empty() {
+        println!("{} package(s) look unmaintained.", unmaintained.len());
+    }
+    if !findings.is_empty() && !failing {
+        println!("None of them is at least {} severity.", args.fail_on.map(|s| s.to_string()).unwrap_or_default());
+    }
+    Ok(failing)
+}
+
+/// Runs `licenses` and returns whether the policy was violated.
+fn run_licenses(args: &LicensesArgs) -> Result<bool> {
+    let policy = match &args.policy {
+        Some(path) => LicensePolicy::parse_file(path)?,
+        None => LicensePolicy::default(),
+    };
+    let client = args.index.client()?;
+    let file_name = args.file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let packages: Vec<(String, Pep440Version)> = if lock::LOCK_FILE_NAMES.contains(&file_name) {
+        lock::parse_file(&args.file)?
+            .into_iter()
+            .map(|locked| (locked.name, locked.version))
+            .collect()
+    } else {
+        let declared = if file_name == "pyproject.toml" {
+            pyproject::parse_file(&args.file)?
+        } else {
+            requirements::parse_file(&args.file)?
+        };
+        let roots: Vec<PythonPackage> = declared.into_iter().map(|line| line.package).collect();
+        let graph = DependencyGraph::from_index(&client, &roots, args.depth, args.prereleases.policy())?;
+        graph
+            .nodes
+            .into_values()
+            .filter_map(|node| Some((node.name, node.version?)))
+            .collect()
+    };
+    let entries = license::inventory(&client, &packages, &policy)?;
+    let violations = entries.iter().filter(|entry| entry.violation).count();
+
+    match args.format {
+        TableFormat::Json => {
+            let value = json::license_report(&args.file, args.policy.as_deref(), &entries);
+            print_json(&value)?;
+            return Ok(violations > 0);
+        }
+        TableFormat::Csv => {
+            print_report(&csv::licenses(&entries).csv())?;
+            return Ok(violations > 0);
+        }
+        TableFormat::Tsv => {
+            print_report(&csv::licenses(&entries).tsv())?;
+            return Ok(violations > 0);
+        }
+        TableFormat::Text => {}
+    }
+
+    println!("\nLicenses of {} packages from {}\n", entries.len(), args.file.display());
+    let license = |entry: &license::LicenseEntry| {
+        entry.license.as_ref().map_or("UNKNOWN".to_string(), |(license, _)| license.clone())
+    };
+    let package = |entry: &license::LicenseEntry| format!("{} {}", entry.name, entry.version);
+    let package_width = entries.iter().map(|entry| package(entry).len()).max().unwrap_or_default();
+    let license_width = entries.iter().map(|entry| license(entry).len()).max().unwrap_or_default();
+    for entry in &entries {
+        let verdict = match entry.verdict {
+            _ if entry.violation => format!("  {}", entry.verdict.to_string().to_uppercase().red().bold()),
+            Verdict::Unknown => format!("  {}", "unknown".yellow()),
+            Verdict::Ignored => "  ignored".to_string(),
+            Verdict::Allowed | Verdict::Denied => String::new(),
+        };
+        let line = format!(
+            "  {:package_width$}  {:license_width$}{}",
+            package(entry),
+            license(entry),
+            verdict,
+            package_width = package_width,
+            license_width = license_width
+        );
+        println!("{}", line.trim_end());
+    }
+
+    println!();
+    if violations > 0 {
+        println!(
+            "{} {} package(s) violate the license policy.",
+            "LICENSE POLICY VIOLATED:".red().bold(),
+            violations
+        );
+    } else if args.policy.is_some() {
+        println!("{}", "All licenses comply with the policy".green());
+    }
+    Ok(violations > 0)
+}
+
+fn run_outdated(args: &OutdatedArgs) -> Result<bool> {
+    let (mut packages, mut skipped) = pinned_packages(&args.file)?;
+    packages.retain(|package| !ignore::ignores_package(&args.ignore, &package.name));
+    skipped.retain(|line| !ignore::ignores_package(&args.ignore, &line.package.name));
+    let mut constraints = Vec::new();
+    for path in &args.constraints {
+        constraints.extend(requirements::parse_file(path)?);
+    }
+    let client = args.index.client()?;
+    let mut outdated = outdated::check(&client, &packages, &constraints, args.prereleases.policy())?;
+    if args.major_only {
+        outdated.retain(|entry| entry.gap == Gap::Major);
+    }
+
+    let failing = outdated.iter().any(|entry| {
+        let rule = match entry.gap {
+            Gap::Major => severity::OUTDATED_MAJOR_RULE,
+            Gap::Minor => severity::OUTDATED_MINOR_RULE,
+            Gap::Patch => severity::OUTDATED_PATCH_RULE,
+        };
+        fails(rule, &entry.package.name, args.fail.max_severity)
+    });
+    let report = || json::outdated_report(&args.file, packages.len(), &outdated, &skipped);
+    send_report("outdated", &args.notify, report)?;
+
+    match args.format {
+        TableFormat::Json => {
+            print_json(&report())?;
+            return Ok(failing);
+        }
+        TableFormat::Csv => {
+            print_report(&csv::outdated(&outdated).csv())?;
+            return Ok(failing);
+        }
+        TableFormat::Tsv => {
+            print_report(&csv::outdated(&outdated).tsv())?;
+            return Ok(failing);
+        }
+        TableFormat::Text => {}
+    }
+
+    println!("\nChecking {} pinned packages from {}\n", packages.len(), args.file.display());
+    let name_width = outdated.iter().map(|entry| entry.package.name.len()).max().unwrap_or_default();
+    let version_width = outdated.iter().map(|entry| entry.package.version.to_string().len()).max().unwrap_or_default();
+    let latest_width = outdated.iter().map(|entry| entry.latest.to_string().len()).max().unwrap_or_default();
+    for entry in &outdated {
+        let gap = match entry.gap {
+            Gap::Major => entry.gap.to_string().red().bold(),
+            Gap::Minor => entry.gap.to_string().yellow(),
+            Gap::Patch => entry.gap.to_string().normal(),
+        };
+        println!(
+            "  {:name_width$}  {:version_width$} -> {:latest_width$}  {}  {}:{}",
+            entry.package.name,
+            entry.package.version.to_string(),
+            entry.latest.to_string(),
+            gap,
+            entry.package.file.display(),
+            entry.package.line,
+            name_width = name_width,
+            version_width = version_width,
+            latest_width = latest_width
+        );
+        if !entry.held_back_by.is_empty() {
+            match &entry.target {
+                Some(target) => println!("    {} {}, held back by:", "upgrade to".green(), target),
+                None => println!("    {}, held back by:", "no upgrade possible".yellow()),
+            }
+            for source in &entry.held_back_by {
+                println!("      {}", source);
+            }
+        }
+    }
+    for line in &skipped {
+        println!("{} {}:{}  {}", "NOT CHECKED:".yellow().bold(), line.file.display(), line.line, line.package);
+    }
+
+    if outdated.is_empty() {
+        let scope = if args.major_only { "a major version " } else { "" };
+        println!("{}", format!("No pinned package is {}behind the latest release", scope).green());
+    } else {
+        println!("\n{} of {} pinned packages are outdated.", outdated.len(), packages.len());
+    }
+    if !skipped.is_empty() {
+        println!("{} requirement(s) were not checked: pin them to compare them.", skipped.len());
+    }
+    Ok(failing)
+}
+
+/// Runs `info`; a stale pin is reported, not a failure.
+fn run_info(args: &InfoArgs) -> Result<bool> {
+    let pinned = match (&args.pinned, &args.file) {
+        (Some(version), _) => Some(Pep440Version::parse(version)?),
+        (None, Some(path)) => {
+            let (packages, _) = pinned_packages(path)?;
+            let name = canonicalize_name(&args.package);
+            let package = packages.into_iter().find(|package| canonicalize_name(&package.name) == name);
+            Some(package.ok_or_else(|| anyhow!("{} is not pinned in {}", args.package, path.display()))?.version)
+        }
+        (None, None) => None,
+    };
+    let client = args.index.client()?;
+    let releases = client.releases(&args.package)?;
+    if let Some(pinned) = pinned.as_ref().filter(|pinned| !releases.iter().any(|r| r.version == **pinned)) {
+        return Err(anyhow!("Release not found on index: {} {}", args.package, pinned));
+    }
+    let stale = pinned.as_ref().and_then(|pinned| timeline::staleness(&releases, pinned));
+    let today = timeline::days(&ignore::today());
+    let upkeep = maintenance::assess(&client, &[args.package.as_str()], today.unwrap_or_default(), true)?.remove(0);
+
+    if args.format == OutputFormat::Json {
+        let mut value = json::release_timeline(&args.package, &releases, pinned.as_ref(), stale.as_ref());
+        value["maintenance"] = json::maintenance(&upkeep);
+        print_json(&value)?;
+        return Ok(false);
+    }
+
+    let date = |release: &Release| release.uploaded.as_deref().and_then(|time| time.get(..10)).unwrap_or("-").to_string();
+    let newest = releases.iter().rev().find(|r| !r.yanked && !r.version.is_prerelease()).or(releases.last());
+    match newest {
+        Some(newest) => println!("\n{}: {} releases, latest {} ({})\n", args.package, releases.len(), newest.version, date(newest)),
+        None => println!("\n{}: no releases\n", args.package),
+    }
+    let shown = if args.limit == 0 { releases.len() } else { args.limit };
+    let version_width = releases.iter().map(|r| r.version.to_string().len()).max().unwrap_or_default();
+    let print_release = |release: &Release| {
+        let mut notes = Vec::new();
+        if release.yanked {
+            notes.push("yanked".red().to_string());
+        } else if release.version.is_prerelease() {
+            notes.push("pre-release".to_string());
+        }
+        if pinned.as_ref() == Some(&release.version) {
+            let ago = match (today, release.uploaded.as_deref().and_then(timeline::days)) {
+                (Some(today), Some(uploaded)) => format!(", released {} ago", timeline::age(today - uploaded)),
+                _ => String::new(),
+            };
+            notes.push(format!("<- pinned{}", ago).bold().to_string());
+        }
+        let line = format!("  {:width$}  {:10}  {}", release.version.to_string(), date(release), notes.join(", "), width = version_width);
+        println!("{}", line.trim_end());
+    };
+    let listed: Vec<&Release> = releases.iter().rev().take(shown).collect();
+    listed.iter().for_each(|release| print_release(release));
+    let mut hidden = releases.len() - listed.len();
+    if let Some(release) = releases.iter().find(|r| pinned.as_ref() == Some(&r.version) && !listed.contains(r)) {
+        println!("  ...");
+        print_release(release);
+        hidden -= 1;
+    }
+    if hidden > 0 {
+        println!("  ({} more releases; --limit 0 lists all of them)", hidden);
+    }
+
+    if let Some(stale) = &stale {
+        let verdict = format!("Your pin {} is {}.", stale.pinned, stale);
+        match stale.days_behind {
+            _ if stale.is_latest() => println!("\n{}", verdict.green()),
+            Some(days) if days >= 365 => println!("\n{}", verdict.red().bold()),
+            _ => println!("\n{}", verdict.yellow()),
+        }
+    }
+    if upkeep.is_likely_unmaintained() {
+        println!("\n{} {}", "LIKELY UNMAINTAINED:".yellow().bold(), upkeep.name);
+        for signal in &upkeep.signals {
+            println!("  {}", signal);
+        }
+    }
+    Ok(false)
+}
+
+/// Runs `pin`; resolution failures are errors rather than conflicts.
+fn run_pin(args: &PinArgs) -> Result<bool> {
+    let file_name = args.file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let declared = if file_name == "pyproject.toml" {
+        pyproject::parse_file(&args.file)?
+    } else {
+        requirements::parse_file(&args.file)?
+    };
+    let client = args.index.client()?;
+    let resolution = match args.resolution {
+        ResolutionMode::Highest => Resolution::Highest,
+        ResolutionMode::Lowest => Resolution::Lowest,
+    };
+    let pins = resolve::resolve(&client, &declared, args.prereleases.policy(), resolution)?;
+    let hashes = if args.generate_hashes {
+        let releases: Vec<(&str, &Pep440Version)> = pins.iter().map(|pin| (pin.name.as_str(), &pin.version)).collect();
+        client.metadata_all(&releases, pypi::parse_hashes)?
+    } else {
+        Vec::new()
+    };
+
+    let mut command = format!("pyhelper pin {}", args.file.display());
+    if let Some(output) = &args.output {
+        command.push_str(&format!(" -o {}", output.display()));
+    }
+    if args.generate_hashes {
+        command.push_str(" --generate-hashes");
+    }
+    if args.resolution == ResolutionMode::Lowest {
+        command.push_str(" --resolution lowest");
+    }
+    let rendered = resolve::requirements_txt(&pins, &hashes, &command);
+    match &args.output {
+        Some(output) => {
+            std::fs::write(output, rendered).with_context(|| format!("Cannot write {}", output.display()))?;
+            println!("{}", format!("Pinned {} packages in {}", pins.len(), output.display()).green());
+        }
+        None => print_report(&rendered)?,
+    }
+    Ok(false)
+}
+
+fn run_simulate(args: &SimulateArgs) -> Result<bool> {
+    let upgrade = PythonPackage::parse(&args.upgrade)?;
+    // Requirements that are not pinned still constrain the new versions
+    let (packages, skipped) = pinned_packages(&args.file)?;
+    let client = args.index.client()?;
+    let sim = simulate::simulate(&client, &packages, &skipped, &upgrade, args.prereleases.policy())?;
+
+    if args.format == OutputFormat::Json {
+        let value = json::simulation(&args.file, &sim);
+        print_json(&value)?;
+        return Ok(!sim.is_clean());
+    }
+
+    let from = sim.from.as_ref().map_or("unpinned".to_string(), |v| v.to_string());
+    println!("\nSimulating {} {} -> {} in {}\n", sim.name, from, sim.to, args.file.display());
+    print_simulation(&sim);
+    Ok(!sim.is_clean())
+}
+
+/// Runs `downgrade`; needing co-downgrades is the answer, not a failure, so
+/// only a rollback with no fitting release counts as a conflict.
+fn run_downgrade(args: &DowngradeArgs) -> Result<bool> {
+    let to = Pep440Version::parse(&args.to)?;
+    let (packages, skipped) = pinned_packages(&args.file)?;
+    let client = args.index.client()?;
+    let sim = simulate::downgrade(&client, &packages, &skipped, &args.package, &to, args.prereleases.policy())?;
+    let stuck = sim.moves.iter().any(|moved| moved.to.is_none());
+
+    if args.format == OutputFormat::Json {
+        let value = json::simulation(&args.file, &sim);
+        print_json(&value)?;
+        return Ok(stuck);
+    }
+
+    let from = sim.from.as_ref().map(|v| v.to_string()).unwrap_or_default();
+    println!("\nRolling {} back {} -> {} in {}\n", sim.name, from, sim.to, args.file.display());
+    print_simulation(&sim);
+    if !stuck {
+        println!("\nPins for the rollback:");
+        println!("  {}=={}", sim.name, sim.to);
+        for moved in &sim.moves {
+            if let Some(to) = &moved.to {
+                println!("  {}=={}", moved.name, to);
+            }
+        }
+    }
+    Ok(stuck)
+}
+
+/// Prints what a simulated version change breaks, moves and adds, with a
+/// one-line verdict.
+fn print_simulation(sim: &simulate::Simulation) {
+    if !sim.broken.is_empty() {
+        println!("Requirements that break:");
+        for broken in &sim.broken {
+            println!("  {}  requires {}, excluding {}", broken.source, broken.requirement, broken.excludes);
+        }
+    }
+    if !sim.moves.is_empty() {
+        println!("Packages that must also move:");
+        let name_width = sim.moves.iter().map(|moved| moved.name.len()).max().unwrap_or_default();
+        for moved in &sim.moves {
+            let to = match &moved.to {
+                Some(to) => to.to_string().green(),
+                None => "no release fits".red(),
+            };
+            println!("  {:name_width$}  {} -> {}", moved.name, moved.from, to, name_width = name_width);
+        }
+    }
+    if !sim.added.is_empty() {
+        println!("New dependencies:");
+        for (by, requirement) in &sim.added {
+            println!("  {}  required by {}", requirement, by);
+        }
+    }
+
+    if sim.is_clean() {
+        println!("{}", format!("{} {} fits without other changes", sim.name, sim.to).green());
+    } else {
+        let stuck = sim.moves.iter().filter(|moved| moved.to.is_none()).count();
+        println!(
+            "\n{} requirement(s) break; {} other package(s) must move{}.",
+            sim.broken.len(),
+            sim.moves.len(),
+            if stuck > 0 { format!(", {} with no release that fits", stuck) } else { String::new() }
+        );
+    }
+}
+
+fn run_verify_hashes(args: &VerifyHashesArgs) -> Result<bool> {
+    let file_name = args.file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let requirements = if file_name == "Pipfile.lock" {
+        pipfile::parse_lock(&args.file)?
+    } else {
+        requirements::parse_file(&args.file)?
+    };
+    let client = args.index.client()?;
+    let checks = hashes::verify(&client, &requirements)?;
+    let failed: Vec<&hashes::HashCheck> = checks.iter().filter(|check| check.issue.is_some()).collect();
+
+    if args.format == OutputFormat::Json {
+        let value = json::hash_report(&args.file, &checks);
+        print_json(&value)?;
+        return Ok(!failed.is_empty());
+    }
 
-    /// Second package with version constraint (e.g., "requests<3.0.0")
-    #[arg(short = '2', long)]
-    pkg2: String,
-}
-
-#[derive(Debug)]
-struct PythonPackage {
-    name: String,
-    version_req: VersionReq,
-}
-
-impl PythonPackage {
-    fn parse(input: &str) -> Result<Self> {
-        let re = Regex::new(r"^([a-zA-Z0-9_-]+)(.*?)$").unwrap();
-        let captures = re.captures(input)
-            .ok_or_else(|| anyhow!("Invalid package format: {}", input))?;
-        
-        let name = captures[1].to_string();
-        let version_str = captures.get(2).map_or("", |m| m.as_str());
-        
-        // Convert Python-style version specifiers to semver format
-        let version_req = if version_str.is_empty() {
-            VersionReq::STAR
-        } else {
-            let cleaned = version_str
-                .replace(">=", ">=")
-                .replace("<=", "<=")
-                .replace("==", "=")
-                .replace("~=", "~")
-                .replace("!=", "!");
-            
-            VersionReq::from_str(&cleaned)
-                .map_err(|_| anyhow!("Invalid version requirement: {}", version_str))?
-        };
-
-        Ok(PythonPackage { name, version_req })
-    }
-
-    fn conflicts_with(&self, other: &PythonPackage) -> bool {
-        if self.name != other.name {
-            return false;
-        }
-
-        // Check some common versions to detect conflicts
-        let test_versions = [
-            "0.1.0", "1.0.0", "2.0.0", "3.0.0", "4.0.0", "5.0.0", "6.0.0", "7.0.0",
-            "1.2.3", "2.3.4", "3.4.5", "4.5.6", "5.6.7",
-            "1.0.1", "2.0.1", "3.0.1", "4.0.1", "5.0.1"
-        ];
-
-        for version_str in test_versions.iter() {
-            if let Ok(version) = Version::parse(version_str) {
-                let matches_self = self.version_req.matches(&version);
-                let matches_other = other.version_req.matches(&version);
-                
-                if matches_self && matches_other {
-                    return false; // Found a version that satisfies both requirements
+    println!("\nVerifying hashes of {} requirements from {}\n", checks.len(), args.file.display());
+    for check in &failed {
+        let requirement = &check.requirement;
+        let location = requirement.location();
+        match check.issue.as_ref().expect("only failed checks are listed") {
+            HashIssue::Mismatch(hashes) => {
+                println!("{} {}  {}", "HASH MISMATCH:".red().bold(), location, requirement.package);
+                let version = check.version.as_ref().map(|v| v.to_string()).unwrap_or_default();
+                for hash in hashes {
+                    println!("  {} matches no file of {} {} on the index", hash, requirement.package.name, version);
                 }
             }
+            HashIssue::Missing => println!("{} {}  {}", "MISSING HASH:".red().bold(), location, requirement.package),
+            HashIssue::Unpinned => println!("{} {}  {}", "NOT PINNED:".red().bold(), location, requirement.package),
+            HashIssue::Unverifiable(hashes) => {
+                println!("{} {}  {}", "UNVERIFIABLE:".yellow().bold(), location, requirement.package);
+                println!("  the index only reports sha256 hashes, not {}", hashes.join(", "));
+            }
         }
+    }
 
-        true // No common version found
+    if failed.is_empty() {
+        println!("{}", "All hashes match files published on the index".green());
+    } else {
+        println!("\n{} of {} requirements failed hash verification.", failed.len(), checks.len());
     }
+    Ok(!failed.is_empty())
 }
 
-fn main() -> Result<()> {
-    let args = Args::parse();
+/// Runs `scan`: every dependency file found is checked against the others.
+fn run_scan(args: &ScanArgs) -> Result<bool> {
+    let files = scan::discover(&args.path, &args.exclude)?;
+    if files.is_empty() {
+        return Err(anyhow!("No dependency files found under {}", args.path.display()));
+    }
+    let (requirements, errors) = scan::load(&files);
+    for (_, error) in &errors {
+        eprintln!("{} {:#}", "Error:".red().bold(), error);
+    }
+    let report = ConflictReport::check_with(&requirements, args.prereleases.policy());
 
-    let pkg1 = PythonPackage::parse(&args.pkg1)?;
-    let pkg2 = PythonPackage::parse(&args.pkg2)?;
+    if args.format == OutputFormat::Json {
+        let sources: Vec<String> = files.iter().map(|file| file.display().to_string()).collect();
+        print_json(&json::report(&sources, &report, None))?;
+    } else {
+        println!("\nFound {} dependency file(s) under {}", files.len(), args.path.display());
+        for file in &files {
+            let count = requirements.iter().filter(|r| r.file == *file).count();
+            println!("  {} ({} requirement(s))", file.display(), count);
+        }
+        println!();
+        if report.has_conflicts() {
+            for conflict in &report.conflicts {
+                println!("{} {}", "CONFLICT DETECTED:".red().bold(), conflict.name);
+                for line in &conflict.requirements {
+                    println!("  {}  {}", line.location(), line.package);
+                }
+            }
+            println!("\n{} package(s) have mutually exclusive requirements.", report.conflicts.len());
+        } else {
+            println!("{}", "No conflicts detected".green());
+            println!("All version requirements are compatible across the files.");
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow!("{} file(s) could not be read", errors.len()));
+    }
+    Ok(report.conflicts.iter().any(|c| fails(sarif::CONFLICT_RULE, &c.name, args.fail.max_severity)))
+}
 
-    println!("\nAnalyzing potential conflicts between:");
-    println!("  Package 1: {} {}", pkg1.name, pkg1.version_req);
-    println!("  Package 2: {} {}\n", pkg2.name, pkg2.version_req);
+/// Runs `workspace`: conflicts between the members' third-party requirements
+/// fail, and the requirements they agree on become the unified constraints.
+fn run_workspace(args: &WorkspaceArgs) -> Result<bool> {
+    let (members, errors) = workspace::discover(&args.path, &args.exclude)?;
+    if members.is_empty() {
+        return Err(anyhow!("No projects found under {}", args.path.display()));
+    }
+    for (_, error) in &errors {
+        eprintln!("{} {:#}", "Error:".red().bold(), error);
+    }
+    let report = workspace::check(members, args.prereleases.policy());
+    let constraints = workspace::constraints_txt(&report, &args.path);
+    if let Some(output) = &args.output {
+        std::fs::write(output, &constraints).with_context(|| format!("Cannot write {}", output.display()))?;
+    }
 
-    if pkg1.name != pkg2.name {
-        println!("{}", "No conflict: Different packages".green());
-        return Ok(());
+    if args.format == OutputFormat::Json {
+        print_json(&json::workspace_report(&args.path, &report))?;
+    } else {
+        println!("\nWorkspace of {} project(s) under {}", report.members.len(), args.path.display());
+        for member in &report.members {
+            let dir = if member.dir.as_os_str().is_empty() { Path::new(".") } else { &member.dir };
+            println!("  {} ({}): {} requirement(s)", member.name.bold(), dir.display(), member.requirements.len());
+        }
+        println!();
+        for shared in &report.conflicts {
+            let between = match shared.members.as_slice() {
+                [member] => format!("within {}", member),
+                members => format!("between {}", members.join(", ")),
+            };
+            println!("{} {} ({})", "CONFLICT DETECTED:".red().bold(), shared.conflict.name, between);
+            for line in &shared.conflict.requirements {
+                let member = report.member_of(line).map(|member| member.name.as_str()).unwrap_or_default();
+                println!("  {}  {}  [{}]", line.location(), line.package, member);
+            }
+            let packages: Vec<&PythonPackage> = shared.conflict.requirements.iter().map(|r| &r.package).collect();
+            let suggestions = suggest(&packages, None);
+            if !suggestions.is_empty() {
+                println!("  Suggestions:");
+            }
+            for suggestion in suggestions {
+                println!("    - {}", suggestion);
+            }
+        }
+        if report.conflicts.is_empty() {
+            println!("{}", "No conflicts between the projects".green());
+        } else {
+            println!("\n{} package(s) have mutually exclusive requirements.", report.conflicts.len());
+        }
+        match &args.output {
+            Some(output) => println!(
+                "\nWrote {} unified constraint(s) to {}",
+                report.unified.len(),
+                output.display()
+            ),
+            None => print!("\nUnified constraints:\n{}", constraints),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(anyhow!("{} file(s) could not be read", errors.len()));
     }
+    Ok(!report.conflicts.is_empty())
+}
 
-    if pkg1.conflicts_with(&pkg2) {
-        println!("{}", "CONFLICT DETECTED!".red().bold());
-        println!("The version requirements are mutually exclusive.");
+/// Runs `merge`; conflicting requirements are reported and nothing is written.
+fn run_merge(args: &MergeArgs) -> Result<bool> {
+    let mut requirements = Vec::new();
+    for path in &args.files {
+        requirements.extend(scan::parse_file(path)?);
+    }
+    let sources: Vec<String> = args.files.iter().map(|path| path.display().to_string()).collect();
+
+    let report = ConflictReport::check_with(&requirements, args.prereleases.policy());
+    if report.has_conflicts() {
+        for conflict in &report.conflicts {
+            println!("{} {}", "CONFLICT DETECTED:".red().bold(), conflict.name);
+            for line in &conflict.requirements {
+                println!("  {}  {}", line.location(), line.package);
+            }
+            let packages: Vec<&PythonPackage> = conflict.requirements.iter().map(|r| &r.package).collect();
+            print!("{}", indent(&format!("\nWhy:\n{}", explain(&packages, None))));
+        }
+        println!(
+            "\n{} package(s) have no version satisfying every source; nothing was merged.",
+            report.conflicts.len()
+        );
+        return Ok(true);
+    }
+
+    let merged = merge::merge(&requirements);
+    let rendered = merge::requirements_txt(&merged, &sources);
+    match &args.output {
+        Some(output) => {
+            std::fs::write(output, rendered).with_context(|| format!("Cannot write {}", output.display()))?;
+            println!(
+                "{}",
+                format!("Merged {} requirements into {} in {}", requirements.len(), merged.len(), output.display()).green()
+            );
+        }
+        None => print_report(&rendered)?,
+    }
+    Ok(false)
+}
+
+/// Runs `convert`, which only fails on unreadable or inexpressible input.
+fn run_convert(args: &ConvertArgs) -> Result<bool> {
+    let manifest = convert::read(&args.from)?;
+    let format = match args.to {
+        ConvertFormat::Requirements => Format::Requirements,
+        ConvertFormat::Pyproject => Format::Pyproject,
+        ConvertFormat::Pipfile => Format::Pipfile,
+        ConvertFormat::Conda => Format::Conda,
+    };
+    let name = match &args.name {
+        Some(name) => name.clone(),
+        None => {
+            let dir = std::fs::canonicalize(&args.from)?;
+            let dir = dir.parent().and_then(|dir| dir.file_name());
+            dir.map_or("project".to_string(), |dir| dir.to_string_lossy().into_owned())
+        }
+    };
+    let source = args.from.file_name().map_or(args.from.display().to_string(), |name| name.to_string_lossy().into_owned());
+    let converted = convert::render(&manifest, format, &name, &source)?;
+    for dropped in &converted.dropped {
+        eprintln!("{} left out {}", "warning:".yellow().bold(), dropped);
+    }
+    match &args.output {
+        Some(output) => {
+            std::fs::write(output, &converted.content).with_context(|| format!("Cannot write {}", output.display()))?;
+            println!(
+                "{}",
+                format!("Converted {} requirements from {} into {}", manifest.requirements.len(), args.from.display(), output.display()).green()
+            );
+        }
+        None => print_report(&converted.content)?,
+    }
+    Ok(false)
+}
+
+fn run_simplify(args: &SimplifyArgs) -> Result<bool> {
+    let simplified = |package: &PythonPackage| PythonPackage {
+        specifiers: package.specifiers.simplified(),
+        ..package.clone()
+    };
+
+    let Some(path) = &args.file else {
+        for requirement in &args.requirements {
+            let package = PythonPackage::parse(requirement)?;
+            println!("{}", merge::line(&simplified(&package)));
+            if package.specifiers.range().is_empty() {
+                eprintln!("{} {} matches no version", "warning:".yellow().bold(), package.name);
+            }
+        }
+        return Ok(false);
+    };
+
+    let mut count = 0;
+    for line in scan::parse_file(path)? {
+        let after = simplified(&line.package);
+        if after.specifiers.to_string() == line.package.specifiers.to_string() {
+            continue;
+        }
+        count += 1;
+        println!(
+            "{}:{}  {} -> {}",
+            line.file.display(),
+            line.line,
+            merge::line(&line.package),
+            merge::line(&after).green()
+        );
+    }
+    match count {
+        0 => println!("{}", "Nothing to simplify".green()),
+        _ => println!("\n{} requirement(s) can be simplified", count),
+    }
+    Ok(false)
+}
+
+fn run_fmt(args: &FmtArgs) -> Result<bool> {
+    let mut unformatted = 0;
+    for path in &args.files {
+        let content = std::fs::read_to_string(path).with_context(|| format!("Cannot read requirements file: {}", path.display()))?;
+        let formatted = fmt::format(&content, path)?;
+        for name in &formatted.unsatisfiable {
+            eprintln!(
+                "{} the requirements on {} in {} together allow no version",
+                "warning:".yellow().bold(),
+                name,
+                path.display()
+            );
+        }
+        if formatted.content == content {
+            continue;
+        }
+        unformatted += 1;
+        if !args.check {
+            std::fs::write(path, &formatted.content).with_context(|| format!("Cannot write {}", path.display()))?;
+            println!("Formatted {}", path.display());
+            continue;
+        }
+        println!("{} is not formatted:", path.display().to_string().bold());
+        for (added, line) in fmt::changes(&content, &formatted.content) {
+            match added {
+                true => println!("  {}", format!("+{}", line).green()),
+                false => println!("  {}", format!("-{}", line).red()),
+            }
+        }
+        if !formatted.merged.is_empty() {
+            println!("  merges the requirements on {}", formatted.merged.join(", "));
+        }
+    }
+
+    match (unformatted, args.check) {
+        (0, _) => println!("{}", "Nothing to format".green()),
+        (n, true) => println!("\n{} of {} file(s) would be reformatted", n, args.files.len()),
+        (n, false) => println!("{}", format!("Formatted {} of {} file(s)", n, args.files.len()).green()),
+    }
+    Ok(args.check && unformatted > 0)
+}
+
+fn run_lint(args: &LintArgs) -> Result<bool> {
+    let requirements = scan::parse_file(&args.file)?;
+    let duplicates = lint::duplicates(&requirements);
+    let default = match (args.require_pins, args.forbid_pins) {
+        (true, _) => PinRule::Exact,
+        (_, true) => PinRule::Range,
+        _ => PinRule::Any,
+    };
+    let violations = lint::pin_violations(&requirements, default, &args.pin_policy);
+    let caps = match args.forbid_caps {
+        true => lint::caps(&requirements, &args.allow_cap),
+        false => Vec::new(),
+    };
+    let failed = duplicates.iter().any(|d| fails(severity::DUPLICATE_RULE, &d.name, args.fail.max_severity))
+        || violations.iter().any(|v| fails(severity::PIN_RULE, &v.line.package.name, args.fail.max_severity))
+        || caps.iter().any(|c| fails(severity::CAP_RULE, &c.line.package.name, args.fail.max_severity));
+    if args.format == OutputFormat::Json {
+        print_json(&json::lint_report(&args.file, &duplicates, &violations, &caps))?;
+        return Ok(failed);
+    }
+
+    for duplicate in &duplicates {
+        let label = match duplicate.verdict {
+            lint::Verdict::Conflicting => "DUPLICATE REQUIREMENT:".red().bold(),
+            _ => "DUPLICATE REQUIREMENT:".yellow().bold(),
+        };
+        println!(
+            "{} {} is listed {} times ({})",
+            label,
+            duplicate.name,
+            duplicate.lines.len(),
+            duplicate.verdict
+        );
+        for line in &duplicate.lines {
+            println!("  {}  {}", line.location(), merge::line(&line.package));
+        }
+        match (duplicate.verdict, &duplicate.combined) {
+            (lint::Verdict::Conflicting, _) => println!("  no version satisfies all of them"),
+            (lint::Verdict::Redundant, _) => println!(
+                "  every listing allows the same versions; keep {} and remove the others",
+                duplicate.lines[0].location()
+            ),
+            (lint::Verdict::Consistent, Some(combined)) => println!("  together they allow {}", merge::line(combined).green()),
+            (lint::Verdict::Consistent, None) => {}
+        }
+        for (wider, narrower) in &duplicate.shadowed {
+            println!(
+                "  {} has no effect: {} is stricter",
+                duplicate.lines[*wider].location(),
+                duplicate.lines[*narrower].location()
+            );
+        }
+        println!();
+    }
+
+    for violation in &violations {
+        println!("{} {}  {}", "PIN POLICY:".yellow().bold(), violation.line.location(), violation);
+    }
+    if !violations.is_empty() {
+        println!();
+    }
+    for cap in &caps {
+        println!("{} {}  {}", "UPPER CAP:".yellow().bold(), cap.line.location(), cap);
+    }
+    if !caps.is_empty() {
+        println!();
+    }
+
+    match duplicates.len() {
+        0 => println!("{}", "No duplicate requirements".green()),
+        n => println!("{} package(s) listed more than once", n),
+    }
+    if default != PinRule::Any || !args.pin_policy.is_empty() {
+        match violations.len() {
+            0 => println!("{}", "Every requirement follows the pin policy".green()),
+            n => println!("{} requirement(s) break the pin policy", n),
+        }
+    }
+    if args.forbid_caps {
+        match caps.len() {
+            0 => println!("{}", "No upper caps".green()),
+            n => println!("{} requirement(s) cap a major version", n),
+        }
+    }
+    Ok(failed)
+}
+
+fn run_intersect(args: &IntersectArgs) -> Result<bool> {
+    let packages = args.requirements.iter().map(|r| PythonPackage::parse(r)).collect::<Result<Vec<_>>>()?;
+    let combined = merge::intersect(&packages)?;
+    if combined.specifiers.range().is_empty() {
+        println!("{} {}", "CONFLICT DETECTED:".red().bold(), combined.name);
+        let packages: Vec<&PythonPackage> = packages.iter().collect();
+        print!("{}", indent(&format!("\nWhy:\n{}", explain(&packages, None))));
+        return Ok(true);
+    }
+    println!("{}", merge::line(&combined));
+    Ok(false)
+}
+
+fn run_union(args: &UnionArgs) -> Result<bool> {
+    let report = |union: &merge::Union| {
+        let line = merge::line(&union.package);
+        if !union.widened.is_empty() {
+            eprintln!("{} {} also allows {}, which none of the alternatives allows", "note:".bold(), line, versions(&union.widened));
+        }
+        if let Some((low, high)) = &union.majors {
+            let high = high.as_ref().map_or_else(|| "later".to_string(), |high| high.to_string());
+            eprintln!(
+                "{} {} spans major versions {} to {}; environments may get different major versions",
+                "warning:".yellow().bold(),
+                line,
+                low,
+                high
+            );
+        }
+        line
+    };
+
+    if args.files.is_empty() {
+        let packages = args.requirements.iter().map(|r| PythonPackage::parse(r)).collect::<Result<Vec<_>>>()?;
+        println!("{}", report(&merge::union(&packages)?));
+        return Ok(false);
+    }
+
+    // Within one file the requirements on a package all apply, across files any one does
+    let mut alternatives: BTreeMap<(String, String), Vec<PythonPackage>> = BTreeMap::new();
+    for path in &args.files {
+        let mut groups: BTreeMap<(String, String), Vec<PythonPackage>> = BTreeMap::new();
+        for line in scan::parse_file(path)? {
+            let marker = line.package.marker.as_ref().map(|m| m.to_string()).unwrap_or_default();
+            groups.entry((line.package.canonical_name(), marker)).or_default().push(line.package);
+        }
+        for (key, packages) in groups {
+            alternatives.entry(key).or_default().push(merge::intersect(&packages)?);
+        }
+    }
+    let sources: Vec<String> = args.files.iter().map(|path| path.display().to_string()).collect();
+    println!("# Union by pyhelper of {}", sources.join(", "));
+    for packages in alternatives.values() {
+        println!("{}", report(&merge::union(packages)?));
+    }
+    Ok(false)
+}
+
+
+/// Compares the version engine with the shipped answers of `packaging`, then
+/// with the interpreter's own on generated checks. Without an interpreter to
+/// ask, only the shipped answers are compared.
+fn run_selftest(args: &SelftestArgs) -> Result<bool> {
+    let (checks, answers) = selftest::table()?;
+    let mut outcome = selftest::Outcome { packaging: None, checked: checks.len(), mismatches: selftest::compare(&checks, &answers)? };
+    if !args.offline {
+        let python = args.python.clone().unwrap_or_else(default_python);
+        let generated = selftest::generate(args.cases, args.seed);
+        match selftest::ask_python(&python, &generated) {
+            Ok((version, answers)) => {
+                outcome.mismatches.extend(selftest::compare(&generated, &answers)?);
+                outcome.checked += generated.len();
+                outcome.packaging = Some(version);
+            }
+            Err(e) => eprintln!("{} only checking the shipped answers: {:#}", "warning:".yellow().bold(), e),
+        }
+    }
+
+    if args.format == OutputFormat::Json {
+        print_json(&json::selftest(&outcome))?;
+        return Ok(outcome.failed());
+    }
+
+    let mut known: BTreeMap<&str, usize> = BTreeMap::new();
+    for mismatch in &outcome.mismatches {
+        match mismatch.known {
+            Some(reason) => *known.entry(reason).or_default() += 1,
+            None => println!(
+                "{} {}: packaging says {}, pyhelper says {}",
+                "MISMATCH:".red().bold(),
+                mismatch.check,
+                mismatch.expected,
+                mismatch.actual
+            ),
+        }
+    }
+    for (reason, count) in &known {
+        println!("{} {} check(s): {}", "KNOWN:".yellow().bold(), count, reason);
+    }
+    let against = outcome.packaging.as_ref().map_or_else(|| "the shipped answers".to_string(), |version| format!("packaging {}", version));
+    println!(
+        "\n{} of {} check(s) disagree with {}, {} of them known difference(s).",
+        outcome.mismatches.len(),
+        outcome.checked,
+        against,
+        known.values().sum::<usize>()
+    );
+    Ok(outcome.failed())
+}
+
+fn run_diff(args: &DiffArgs) -> Result<bool> {
+    let old = scan::parse_file(&args.old)?;
+    let new = scan::parse_file(&args.new)?;
+    let mut diffs = diff::diff(&old, &new);
+    if args.breaking_only {
+        diffs.retain(|diff| diff.kind.is_breaking());
+    }
+    let breaking = diffs.iter().any(|diff| diff.kind.is_breaking());
+
+    if args.format == OutputFormat::Json {
+        let value = json::constraint_diff(&args.old, &args.new, &diffs);
+        print_json(&value)?;
+        return Ok(breaking);
+    }
+
+    println!("\nComparing {} -> {}\n", args.old.display(), args.new.display());
+    if diffs.is_empty() {
+        println!("{}", "No constraint changes".green());
+    }
+    let specifiers = |lines: &[RequirementLine]| {
+        let specs: Vec<String> = lines.iter().map(|line| line.package.version_spec()).collect();
+        specs.join(" and ")
+    };
+    let width = diffs.iter().map(|diff| diff.name.len()).max().unwrap_or_default();
+    for diff in &diffs {
+        let label = format!("{:<10}", diff.kind.to_string().to_uppercase());
+        let label = if diff.kind.is_breaking() { label.red().bold() } else { label.normal() };
+        let detail = match diff.kind {
+            ConstraintChange::Added => specifiers(&diff.new),
+            ConstraintChange::Removed => specifiers(&diff.old),
+            _ => format!("{} -> {}", specifiers(&diff.old), specifiers(&diff.new)),
+        };
+        println!("  {} {:width$}  {}", label, diff.name, detail, width = width);
+    }
+
+    let count = |kind: ConstraintChange| diffs.iter().filter(|diff| diff.kind == kind).count();
+    println!(
+        "\n{} tightened, {} loosened, {} shifted, {} added, {} removed, {} changed",
+        count(ConstraintChange::Tightened),
+        count(ConstraintChange::Loosened),
+        count(ConstraintChange::Shifted),
+        count(ConstraintChange::Added),
+        count(ConstraintChange::Removed),
+        count(ConstraintChange::Changed)
+    );
+    Ok(breaking)
+}
+
+/// Runs `sync-check` and returns whether a shared package is flagged.
+fn run_sync_check(args: &SyncCheckArgs) -> Result<bool> {
+    let prod = scan::parse_file(&args.prod)?;
+    let dev = scan::parse_file(&args.dev)?;
+    let shared = sync::check(&prod, &dev);
+    let flagged = shared.iter().any(|package| package.alignment.is_flagged(args.strict));
+
+    if args.format == OutputFormat::Json {
+        let value = json::sync_report(&args.prod, &args.dev, &shared, args.strict);
+        print_json(&value)?;
+        return Ok(flagged);
+    }
+
+    println!("\nComparing dev {} against prod {}\n", args.dev.display(), args.prod.display());
+    let locations = |lines: &[RequirementLine]| {
+        let lines: Vec<String> = lines.iter().map(|line| format!("{}  {}", line.location(), line.package)).collect();
+        lines.join(", ")
+    };
+    for package in shared.iter().filter(|package| package.alignment.is_flagged(args.strict)) {
+        let explanation = match package.alignment {
+            Alignment::Conflicting => "no version satisfies both".to_string(),
+            Alignment::Drifted => format!("dev also allows {}", versions(&package.outside_prod())),
+            _ => format!("dev only allows {}", versions(&package.dev_allowed())),
+        };
+        let label = format!("{}:", package.alignment.to_string().to_uppercase());
+        println!("{} {}: {}", label.red().bold(), package.name, explanation);
+        println!("  prod  {}", locations(&package.prod));
+        println!("  dev   {}", locations(&package.dev));
+    }
+    if !flagged {
+        let message = if args.strict { "Every shared package allows the same versions" } else { "No drift from prod" };
+        println!("{}", message.green());
+    }
+    let count = |alignment: Alignment| shared.iter().filter(|package| package.alignment == alignment).count();
+    println!(
+        "\n{} shared package(s): {} identical, {} narrower, {} drifted, {} conflicting",
+        shared.len(),
+        count(Alignment::Identical),
+        count(Alignment::Narrower),
+        count(Alignment::Drifted),
+        count(Alignment::Conflicting)
+    );
+    Ok(flagged)
+}
+
+/// Runs `sbom`, which only prints the document.
+fn run_sbom(args: &SbomArgs) -> Result<bool> {
+    let client = args.index.client()?;
+    let file_name = args.file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    let mut components: Vec<Component> = Vec::new();
+    if lock::LOCK_FILE_NAMES.contains(&file_name) {
+        for locked in lock::parse_file(&args.file)? {
+            let duplicate = components
+                .iter()
+                .any(|c| canonicalize_name(&c.name) == canonicalize_name(&locked.name) && c.version == locked.version);
+            if !duplicate {
+                components.push(Component {
+                    name: locked.name,
+                    version: locked.version,
+                    hashes: locked.hashes,
+                    license: None,
+                    dependencies: Vec::new(),
+                });
+            }
+        }
     } else {
-        println!("{}", "No conflict detected".green());
-        println!("The version requirements are compatible.");
+        let declared = if file_name == "pyproject.toml" {
+            pyproject::parse_file(&args.file)?
+        } else {
+            requirements::parse_file(&args.file)?
+        };
+        let roots: Vec<PythonPackage> = declared.into_iter().map(|line| line.package).collect();
+        let graph = DependencyGraph::from_index(&client, &roots, args.depth, args.prereleases.policy())?;
+        let names: Vec<&String> = graph.nodes.iter().filter(|(_, node)| node.version.is_some()).map(|(k, _)| k).collect();
+        for name in &names {
+            let node = &graph.nodes[*name];
+            let dependencies = graph
+                .dependencies(name)
+                .iter()
+                .filter_map(|edge| names.iter().position(|n| **n == edge.to))
+                .collect();
+            components.push(Component {
+                name: node.name.clone(),
+                version: node.version.clone().expect("only resolved packages are listed"),
+                hashes: Vec::new(),
+                license: None,
+                dependencies,
+            });
+        }
+    }
+
+    let releases: Vec<(&str, &Pep440Version)> = components.iter().map(|c| (c.name.as_str(), &c.version)).collect();
+    let metadata = client.metadata_all(&releases, |body| Ok((pypi::parse_license_info(body)?, pypi::parse_hashes(body)?)))?;
+    for (component, (info, hashes)) in components.iter_mut().zip(metadata) {
+        component.license = license::identify(&info).map(|(license, _)| license);
+        // Lock files record the hashes of the files they install; the index lists every file
+        if component.hashes.is_empty() {
+            component.hashes = hashes;
+        }
+    }
+
+    let name = args.file.display().to_string();
+    let value = match args.format {
+        SbomFormat::Cyclonedx => sbom::cyclonedx(&name, &components, creation_time()),
+        SbomFormat::Spdx => sbom::spdx(&name, &components, creation_time()),
+    };
+    print_json(&value)?;
+    Ok(false)
+}
+
+/// The time a generated document is stamped with: `$SOURCE_DATE_EPOCH` for
+/// reproducible builds, else now.
+fn creation_time() -> u64 {
+    std::env::var("SOURCE_DATE_EPOCH")
+        .ok()
+        .and_then(|epoch| epoch.parse().ok())
+        .unwrap_or_else(|| {
+            std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs())
+        })
+}
+
+/// Runs the interactive explorer; it only reports on screen.
+fn run_tui(args: &TuiArgs) -> Result<bool> {
+    let client = (!args.offline).then(|| args.index.client()).transpose()?;
+    let explorer = match (&args.file, &client) {
+        (Some(path), Some(client)) => {
+            let declared = scan::parse_file(path)?;
+            let packages: Vec<PythonPackage> = declared.iter().map(|line| line.package.clone()).collect();
+            let graph = DependencyGraph::from_index(client, &packages, args.depth, args.prereleases.policy())?;
+            Explorer::new(graph, declared)
+        }
+        _ => {
+            let environment = load_environment(args.python.as_deref(), &args.site_packages)?;
+            let graph = DependencyGraph::from_installed(&environment.distributions()?, environment.markers.as_ref());
+            Explorer::new(graph, Vec::new())
+        }
+    };
+    tui::run(explorer, client.as_ref())?;
+    Ok(false)
+}
+
+/// Runs `watch`, checking the files again whenever one changes. After the
+/// first run, only the conflicts that appeared, changed or went away are
+/// printed. It only stops when interrupted.
+fn run_watch(args: &WatchArgs) -> Result<bool> {
+    let mut previous = None;
+    let inputs: Vec<String> = args.files.iter().chain(&args.constraints).map(|p| p.display().to_string()).collect();
+    println!("Watching {} for changes; press Ctrl-C to stop", inputs.join(", "));
+    watch::watch(|changed| {
+        let mut files: Vec<PathBuf> = args.files.iter().chain(&args.constraints).cloned().collect();
+        if !changed.is_empty() {
+            let cwd = std::env::current_dir().unwrap_or_default();
+            let names: Vec<String> = changed.iter().map(|p| p.strip_prefix(&cwd).unwrap_or(p).display().to_string()).collect();
+            println!("\n{} changed", names.join(", "));
+        }
+        let loaded = args
+            .files
+            .iter()
+            .map(|path| scan::parse_file(path))
+            .chain(args.constraints.iter().map(|path| requirements::parse_file(path)))
+            .collect::<Result<Vec<_>>>();
+        let requirements: Vec<RequirementLine> = match loaded {
+            Ok(loaded) => loaded.into_iter().flatten().collect(),
+            Err(e) => {
+                println!("{} {:#}", "Error:".red().bold(), e);
+                return files;
+            }
+        };
+        for line in &requirements {
+            if !files.contains(&line.file) {
+                files.push(line.file.clone());
+            }
+        }
+
+        let report = ConflictReport::check_with(&requirements, args.prereleases.policy());
+        let current = watch::snapshot(&report);
+        let print_lines = |name: &str| {
+            for line in &current[name] {
+                println!("  {}", line);
+            }
+        };
+        match &previous {
+            None => {
+                for name in current.keys() {
+                    println!("{} {}", "CONFLICT DETECTED:".red().bold(), name);
+                    print_lines(name);
+                }
+            }
+            Some(before) => {
+                let changes = watch::compare(before, &current);
+                if changes.is_empty() {
+                    println!("No change in conflicts");
+                }
+                for change in changes {
+                    match change {
+                        ConflictChange::Found(name) => {
+                            println!("{} {}", "NEW CONFLICT:".red().bold(), name);
+                            print_lines(&name);
+                        }
+                        ConflictChange::Changed(name) => {
+                            println!("{} {}", "CONFLICT CHANGED:".red().bold(), name);
+                            print_lines(&name);
+                        }
+                        ConflictChange::Resolved(name) => println!("{} {}", "RESOLVED:".green().bold(), name),
+                    }
+                }
+            }
+        }
+        match current.len() {
+            0 => println!("{}", "No conflicts detected".green()),
+            n => println!("{} package(s) have mutually exclusive requirements.", n),
+        }
+        previous = Some(current);
+        files
+    })?;
+    Ok(false)
+}
+
+/// Prints the completion script for a shell. It completes every subcommand,
+/// option and value choice, such as the formats, from the definitions above.
+fn run_serve(args: &ServeArgs) -> Result<bool> {
+    let client = args.index.client()?;
+    let server = Server::new(client, OsvClient::with_options(&args.osv_url, &network().1)?, args.prereleases.policy());
+    let address = format!("{}:{}", args.host, args.port);
+    println!("Listening on http://{} (POST /check, /resolve, /audit); press Ctrl-C to stop", address);
+    server.run(&address)?;
+    Ok(false)
+}
+
+/// Runs `hook`, printing one `file:line:column: message` line per failure and nothing
+/// when every file passes.
+fn run_hook(args: &HookArgs) -> Result<bool> {
+    let (failures, errors) = hook::check(&args.files, args.prereleases.policy());
+    for failure in &failures {
+        println!("{}: {}", failure.location(), failure.message);
+    }
+    for (_, error) in &errors {
+        eprintln!("{:#}", error);
+    }
+    if !errors.is_empty() {
+        return Err(anyhow!("{} file(s) could not be checked", errors.len()));
+    }
+    Ok(!failures.is_empty())
+}
+
+fn run_completions(args: &CompletionsArgs) -> Result<bool> {
+    let mut cmd = Cli::command();
+    let name = cmd.get_name().to_string();
+    clap_complete::generate(args.shell, &mut cmd, name, &mut io::stdout());
+    Ok(false)
+}
+
+fn run_schema() -> Result<bool> {
+    print_json(&schema::document())?;
+    Ok(false)
+}
+
+/// Runs a `cache` action; none of them reports conflicts.
+fn run_baseline(action: &BaselineAction) -> Result<bool> {
+    let BaselineAction::Create(args) = action;
+    let mut requirements = Vec::new();
+    let mut packages = Vec::new();
+    for file in &args.files {
+        let file_name = file.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+        // The pins of a lock file are audited, but were resolved not to conflict
+        if lock::LOCK_FILE_NAMES.contains(&file_name) {
+            packages.extend(lock::parse_file(file)?.iter().map(Audited::from));
+            continue;
+        }
+        let declared = if file_name == "Pipfile.lock" { pipfile::parse_lock(file)? } else { scan::parse_file(file)? };
+        packages.extend(audit::pinned(&declared).0);
+        requirements.extend(declared);
+    }
+    let conflicts = ConflictReport::check(&requirements).conflicts;
+    let mut findings = Vec::new();
+    if !args.no_audit {
+        findings = OsvClient::with_options(&args.osv_url, &network().1)?.audit(&packages)?;
+        for source in plugins(Capability::Advisories) {
+            plugin::merge_findings(&mut findings, source.advisories(&packages)?);
+        }
+    }
+    let baseline = Baseline::record(&conflicts, &findings);
+    let content = format!("{}\n", serde_json::to_string_pretty(&json::baseline(&baseline))?);
+    std::fs::write(&args.output, content).with_context(|| format!("Cannot write baseline: {}", args.output.display()))?;
+    println!(
+        "Recorded {} conflict(s) and {} advisory(ies) in {}",
+        baseline.conflicts.len(),
+        baseline.advisories.len(),
+        args.output.display()
+    );
+    Ok(false)
+}
+
+fn run_cache(action: &CacheAction) -> Result<bool> {
+    let cache = Cache::from_env().ok_or_else(|| anyhow!("Cannot locate a cache directory; set PYHELPER_CACHE_DIR"))?;
+    match action {
+        CacheAction::Status => {
+            let status = cache.status()?;
+            println!("Cache directory: {}", cache.dir().display());
+            println!("  {} release list(s), {} release metadata file(s)", status.release_lists, status.metadata);
+            println!("  {} stale entry(ies), {:.1} KiB", status.stale, status.bytes as f64 / 1024.0);
+        }
+        CacheAction::Clear => {
+            let removed = cache.clear()?;
+            println!("Removed {} cached response(s) from {}", removed, cache.dir().display());
+        }
+        CacheAction::Warm(args) => {
+            let mut packages = Vec::new();
+            for path in &args.requirements {
+                packages.extend(requirements::parse_file(path)?);
+            }
+            if let Some(path) = &args.pyproject {
+                packages.extend(pyproject::parse_file(path)?);
+            }
+            let packages: Vec<PythonPackage> = packages.into_iter().map(|line| line.package).collect();
+            let (extra_index_urls, options) = network();
+            let client = PypiClient::with_options(&args.index_url, extra_index_urls, options)?.with_cache(cache.clone());
+            let graph = DependencyGraph::from_index(&client, &packages, args.depth, args.prereleases.policy())?;
+            let message = format!("Cached the metadata of {} package(s) in {}", graph.nodes.len(), cache.dir().display());
+            println!("{}", message.green());
+        }
     }
+    Ok(false)
+}
 
+/// The extra indexes and network options given on the command line.
+fn network() -> &'static (Vec<String>, NetworkOptions) {
+    NETWORK.get_or_init(Default::default)
+}
+
+/// Whether a finding of `rule` on `package` is more severe than `max`
+/// tolerates, with the levels of the configuration.
+fn fails(rule: &str, package: &str, max: severity::Level) -> bool {
+    SEVERITY.get_or_init(Default::default).fails(rule, package, max)
+}
+
+/// The configured plugins providing `capability`.
+fn plugins(capability: Capability) -> impl Iterator<Item = &'static SubprocessPlugin> {
+    PLUGINS.get_or_init(Vec::new).iter().filter(move |plugin| plugin.provides(capability))
+}
+
+/// Hands the JSON report of `command` to the plugins receiving reports and
+/// posts its summary to the notification destinations. It is only built
+/// when there are any.
+fn send_report(command: &str, destinations: &[Destination], report: impl Fn() -> serde_json::Value) -> Result<()> {
+    let mut sinks = plugins(Capability::Reports).peekable();
+    if sinks.peek().is_none() && destinations.is_empty() {
+        return Ok(());
+    }
+    let report = report();
+    for sink in sinks {
+        sink.send(command, &report)?;
+    }
+    if !destinations.is_empty() {
+        notify::send(destinations, command, &report, &network().1)?;
+    }
     Ok(())
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+/// Sends the events of the library to standard error, filtered by `level` or
+/// else by RUST_LOG, and to `stats` when given. Returns whether anything is
+/// logged: without either filter, nothing is.
+fn init_tracing(level: Option<LogLevel>, format: LogFormat, stats: Option<Arc<Mutex<Stats>>>) -> Result<bool> {
+    let filter = match level {
+        Some(level) => Some(EnvFilter::new(match level {
+            LogLevel::Error => "error",
+            LogLevel::Warn => "warn",
+            LogLevel::Info => "info",
+            LogLevel::Debug => "debug",
+            LogLevel::Trace => "trace",
+        })),
+        None if std::env::var_os(EnvFilter::DEFAULT_ENV).is_some() => {
+            Some(EnvFilter::try_from_default_env().context("Invalid RUST_LOG directives")?)
+        }
+        None => None,
+    };
+    let logging = filter.is_some();
+    let log: Option<Box<dyn Layer<Registry> + Send + Sync>> = filter.map(|filter| {
+        let layer = tracing_subscriber::fmt::layer().with_writer(io::stderr);
+        match format {
+            LogFormat::Text => {
+                let colored = io::stderr().is_terminal() && colored::control::SHOULD_COLORIZE.should_colorize();
+                layer.with_ansi(colored).with_filter(filter).boxed()
+            }
+            LogFormat::Json => layer.json().with_current_span(true).with_span_list(true).with_filter(filter).boxed(),
+        }
+    });
+    // The statistics are read from the debug events, whatever is logged
+    let stats = stats.map(|stats| StatsLayer(stats).with_filter(Targets::new().with_target("pyhelper", Level::DEBUG)));
+    // Without a log, the warnings about the input still need to be seen; errors
+    // recorded by spans are printed as the error the command fails with
+    let warnings = log.is_none().then(|| {
+        WarningLayer.with_filter(filter_fn(|meta| {
+            let input = ["pyhelper::requirements", "pyhelper::pyproject"];
+            input.iter().any(|module| meta.target().starts_with(module)) && *meta.level() == Level::WARN
+        }))
+    });
+    tracing_subscriber::registry()
+        .with(log)
+        .with(stats)
+        .with(warnings)
+        .try_init()
+        .map_err(|e| anyhow!("Cannot set up logging: {}", e))?;
+    Ok(logging)
+}
+
+/// What a run parsed and fetched, and how long its phases took, for `--stats`.
+#[derive(Debug, Default)]
+struct Stats {
+    files: u64,
+    parsed: u64,
+    parse_time: Duration,
+    requests: u64,
+    retries: u64,
+    cache_hits: u64,
+    cache_misses: u64,
+    fetch_time: Duration,
+    rounds: u64,
+    revised: u64,
+    resolve_time: Duration,
+    levels: u64,
+    graph_time: Duration,
+}
+
+impl Stats {
+    fn print(&self, total: Duration) {
+        eprintln!("{}", "Statistics:".bold());
+        eprintln!(
+            "  parsed      {} requirement(s) from {} file(s) in {:.2?}",
+            self.parsed, self.files, self.parse_time
+        );
+        eprintln!("  requests    {} ({} retried) in {:.2?}", self.requests, self.retries, self.fetch_time);
+        eprintln!("  cache       {} hit(s), {} miss(es)", self.cache_hits, self.cache_misses);
+        if self.rounds > 0 {
+            // The resolver never backtracks: it revises the picks of the
+            // previous round instead, which is what a backtrack would undo
+            eprintln!(
+                "  resolution  {} round(s), {} pick(s) revised in {:.2?}",
+                self.rounds, self.revised, self.resolve_time
+            );
+        }
+        if self.levels > 0 {
+            eprintln!("  graph       {} level(s) in {:.2?}", self.levels, self.graph_time);
+        }
+        eprintln!("  total       {:.2?}", total);
+    }
+}
+
+/// Gathers the `Stats` of a run from the spans and events of the library.
+struct StatsLayer(Arc<Mutex<Stats>>);
+
+/// Prints the warnings the library gives about its input, such as a local
+/// project it left out, as `warning:` lines.
+struct WarningLayer;
+
+impl<S: Subscriber> Layer<S> for WarningLayer {
+    fn on_event(&self, event: &Event<'_>, _: layer::Context<'_, S>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        eprintln!("{} {}", "warning:".yellow().bold(), fields.message);
+    }
+}
+
+/// When a span was created, kept in its extensions.
+struct Started(Instant);
+
+/// The message and integer fields of an event.
+#[derive(Default)]
+struct Fields {
+    message: String,
+    values: BTreeMap<&'static str, u64>,
+}
 
-    #[test]
-    fn test_package_parsing() -> Result<()> {
-        // Test basic package name
-        let pkg = PythonPackage::parse("requests")?;
-        assert_eq!(pkg.name, "requests");
-        assert_eq!(pkg.version_req.to_string(), "*");
+impl Fields {
+    fn get(&self, name: &str) -> u64 {
+        self.values.get(name).copied().unwrap_or_default()
+    }
+}
 
-        // Test with version constraints
-        let test_cases = vec![
-            ("requests>=2.0.0", "requests", ">=2.0.0"),
-            ("django==3.2.0", "django", "=3.2.0"),
-            ("flask<1.0", "flask", "<1.0"),
-            ("numpy~=1.20", "numpy", "~1.20"),
-        ];
+impl Visit for Fields {
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.values.insert(field.name(), value);
+    }
 
-        for (input, expected_name, expected_version) in test_cases {
-            let pkg = PythonPackage::parse(input)?;
-            assert_eq!(pkg.name, expected_name);
-            assert_eq!(pkg.version_req.to_string(), expected_version);
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
         }
+    }
+}
 
-        Ok(())
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for StatsLayer {
+    fn on_new_span(&self, _: &Attributes<'_>, id: &Id, ctx: layer::Context<'_, S>) {
+        if let Some(span) = ctx.span(id) {
+            span.extensions_mut().insert(Started(Instant::now()));
+        }
     }
 
-    #[test]
-    fn test_invalid_package_format() {
-        // Test invalid package names
-        assert!(PythonPackage::parse("").is_err());
-        assert!(PythonPackage::parse("@invalid").is_err());
-        assert!(PythonPackage::parse("invalid@1.0").is_err());
+    fn on_event(&self, event: &Event<'_>, ctx: layer::Context<'_, S>) {
+        let mut fields = Fields::default();
+        event.record(&mut fields);
+        // A file parsed while parsing another is already in the counts of the outer one
+        let nested = ctx
+            .event_scope(event)
+            .is_some_and(|scope| scope.filter(|span| span.name() == "parse_file").count() > 1);
+        let mut stats = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        match fields.message.as_str() {
+            "parsed" if !nested => {
+                stats.files += fields.values.get("files").copied().unwrap_or(1);
+                stats.parsed += fields.get("requirements") + fields.get("packages");
+            }
+            "fetching" => {
+                stats.cache_hits += fields.get("cached");
+                stats.cache_misses += fields.get("requests") - fields.get("cached");
+            }
+            "retrying" => stats.retries += 1,
+            "round" => {
+                stats.rounds += 1;
+                stats.revised += fields.get("revised");
+            }
+            "level" => stats.levels += 1,
+            _ => {}
+        }
     }
 
-    #[test]
-    fn test_package_conflicts() -> Result<()> {
-        // Test compatible versions
-        let pkg1 = PythonPackage::parse("requests>=2.0.0")?;
-        let pkg2 = PythonPackage::parse("requests<3.0.0")?;
-        assert!(!pkg1.conflicts_with(&pkg2));
+    fn on_close(&self, id: Id, ctx: layer::Context<'_, S>) {
+        let Some(span) = ctx.span(&id) else { return };
+        let Some(elapsed) = span.extensions().get::<Started>().map(|started| started.0.elapsed()) else {
+            return;
+        };
+        let within = |name: &str| span.scope().skip(1).any(|parent| parent.name() == name);
+        let mut stats = self.0.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        // Time is counted once per phase, not again for the phases nested in one of the same kind
+        match span.name() {
+            "parse_file" if !within("parse_file") => stats.parse_time += elapsed,
+            "fetch" => stats.fetch_time += elapsed,
+            "request" => {
+                stats.requests += 1;
+                if !within("fetch") {
+                    stats.fetch_time += elapsed;
+                }
+            }
+            "resolve" if !within("resolve") => stats.resolve_time += elapsed,
+            "from_index" if !within("from_index") => stats.graph_time += elapsed,
+            _ => {}
+        }
+    }
+}
 
-        // Test conflicting versions
-        let pkg1 = PythonPackage::parse("django>=4.0.0")?;
-        let pkg2 = PythonPackage::parse("django<3.0.0")?;
-        assert!(pkg1.conflicts_with(&pkg2));
+/// Draws a progress bar on standard error while a batch of requests runs,
+/// labeled with the step it belongs to, such as a round of a resolution.
+/// The bar is cleared once the batch is done, so that none is left over the
+/// report.
+#[derive(Debug, Default)]
+struct ProgressBars {
+    bar: Mutex<Option<ProgressBar>>,
+    step: Mutex<Option<String>>,
+}
 
-        // Test different packages (should never conflict)
-        let pkg1 = PythonPackage::parse("requests>=2.0.0")?;
-        let pkg2 = PythonPackage::parse("flask>=2.0.0")?;
-        assert!(!pkg1.conflicts_with(&pkg2));
+impl Progress for ProgressBars {
+    fn start(&self, total: usize) {
+        let style = ProgressStyle::with_template("{prefix} [{bar:30}] {pos}/{len} {wide_msg}")
+            .unwrap_or_else(|_| ProgressStyle::default_bar())
+            .progress_chars("=> ");
+        let step = self.step.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).clone();
+        let bar = ProgressBar::new(total as u64)
+            .with_style(style)
+            .with_prefix(step.unwrap_or_else(|| "Fetching".to_string()));
+        *self.bar.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(bar);
+    }
 
-        // Test exact version requirements
-        let pkg1 = PythonPackage::parse("pytest==6.0.0")?;
-        let pkg2 = PythonPackage::parse("pytest==6.0.0")?;
-        assert!(!pkg1.conflicts_with(&pkg2));
+    fn advance(&self, url: &str) {
+        if let Some(bar) = &*self.bar.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) {
+            bar.set_message(url.split_once("://").map_or(url, |(_, rest)| rest).to_string());
+            bar.inc(1);
+        }
+    }
 
-        let pkg1 = PythonPackage::parse("pytest==7.0.0")?;
-        let pkg2 = PythonPackage::parse("pytest==6.0.0")?;
-        assert!(pkg1.conflicts_with(&pkg2));
+    fn finish(&self) {
+        if let Some(bar) = self.bar.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take() {
+            bar.finish_and_clear();
+        }
+        self.step.lock().unwrap_or_else(|poisoned| poisoned.into_inner()).take();
+    }
 
-        Ok(())
+    fn step(&self, message: &str) {
+        *self.step.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(message.to_string());
     }
+}
 
-    #[test]
-    fn test_version_ranges() -> Result<()> {
-        // Test overlapping ranges
-        let pkg1 = PythonPackage::parse("django>=2.0.0")?;
-        let pkg2 = PythonPackage::parse("django<5.0.0")?;
-        assert!(!pkg1.conflicts_with(&pkg2));
 
-        // Test non-overlapping ranges
-        let pkg1 = PythonPackage::parse("django>=5.0.0")?;
-        let pkg2 = PythonPackage::parse("django<4.0.0")?;
-        assert!(pkg1.conflicts_with(&pkg2));
+fn render_diagram(format: GraphFormat, graph: &DependencyGraph, roots: &[String], edges: &[&Edge]) -> String {
+    match format {
+        GraphFormat::Dot => diagram::dot(graph, roots, edges),
+        GraphFormat::Mermaid => diagram::mermaid(graph, roots, edges),
+        GraphFormat::Text => unreachable!("text output is rendered by the subcommand"),
+    }
+}
+
+/// The environment of an interpreter, or the given site-packages directories
+/// with the marker values of `python` when one is given.
+fn load_environment(python: Option<&Path>, site_packages: &[PathBuf]) -> Result<Environment> {
+    if site_packages.is_empty() {
+        let python = python.map_or_else(default_python, Path::to_path_buf);
+        return Environment::from_interpreter(&python);
+    }
+    let interpreter = python.map(Environment::from_interpreter).transpose()?;
+    Ok(Environment {
+        site_packages: site_packages.to_vec(),
+        markers: interpreter.and_then(|i| i.markers),
+    })
+}
 
-        // Test boundary cases
-        let pkg1 = PythonPackage::parse("django==3.0.0")?;
-        let pkg2 = PythonPackage::parse("django>=3.0.0")?;
-        assert!(!pkg1.conflicts_with(&pkg2));
+/// The interpreter of the active virtualenv, falling back to `python3` on the PATH.
+fn default_python() -> PathBuf {
+    match std::env::var_os("VIRTUAL_ENV") {
+        Some(venv) if cfg!(windows) => PathBuf::from(venv).join("Scripts").join("python.exe"),
+        Some(venv) => PathBuf::from(venv).join("bin").join("python"),
+        None => PathBuf::from("python3"),
+    }
+}
 
-        Ok(())
+/// The `--explain` and `--suggest` sections for one conflicting package.
+fn diagnose(packages: &[&PythonPackage], releases: Option<&[Release]>, args: &CheckArgs) -> String {
+    let mut text = String::new();
+    if args.explain {
+        text.push_str(&format!("\nWhy:\n{}", explain(packages, releases)));
+    }
+    if args.suggest {
+        let suggestions = suggest(packages, releases);
+        text.push_str("\nSuggestions:\n");
+        if suggestions.is_empty() {
+            text.push_str("  no single-clause change resolves this conflict\n");
+        }
+        for suggestion in suggestions {
+            text.push_str(&format!("  - {}\n", suggestion));
+        }
     }
+    text
+}
+
+fn indent(text: &str) -> String {
+    text.lines()
+        .map(|line| match line {
+            "" => "\n".to_string(),
+            line => format!("  {}\n", line),
+        })
+        .collect()
 }
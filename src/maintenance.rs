@@ -0,0 +1,186 @@
+use anyhow::Result;
+use std::fmt;
+
+use crate::pep440::Pep440Version;
+use crate::pypi::{self, ProjectInfo, PypiClient, Release};
+use crate::specifier::SpecifierSet;
+use crate::timeline;
+
+/// How long a project may go without a release before it looks abandoned.
+pub const STALE_DAYS: i64 = 2 * 365;
+
+/// The day each Python version stopped getting security fixes, or will.
+const PYTHON_EOL: &[((u64, u64), &str)] = &[
+    ((2, 6), "2013-10-29"),
+    ((2, 7), "2020-01-01"),
+    ((3, 0), "2009-06-27"),
+    ((3, 1), "2012-04-09"),
+    ((3, 2), "2016-02-20"),
+    ((3, 3), "2017-09-29"),
+    ((3, 4), "2019-03-18"),
+    ((3, 5), "2020-09-30"),
+    ((3, 6), "2021-12-23"),
+    ((3, 7), "2023-06-27"),
+    ((3, 8), "2024-10-07"),
+    ((3, 9), "2025-10-31"),
+    ((3, 10), "2026-10-31"),
+    ((3, 11), "2027-10-31"),
+    ((3, 12), "2028-10-31"),
+    ((3, 13), "2029-10-31"),
+    ((3, 14), "2030-10-31"),
+];
+
+/// A hint that a project is no longer maintained.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Signal {
+    /// Nothing was released for more than [`STALE_DAYS`]; holds when the
+    /// last release was uploaded.
+    NoRecentRelease(String),
+    /// Every Python version the latest release supports is past its end of
+    /// life; holds the classifiers' versions or the `Requires-Python`.
+    EolPythonOnly(String),
+    /// A project URL of the latest release answers 404.
+    DeadUrl { label: String, url: String },
+}
+
+impl fmt::Display for Signal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Signal::NoRecentRelease(uploaded) => write!(f, "no release since {}", uploaded.get(..10).unwrap_or(uploaded)),
+            Signal::EolPythonOnly(supported) => write!(f, "the latest release only supports end-of-life Pythons ({})", supported),
+            Signal::DeadUrl { label, url } => write!(f, "the {} URL answers 404: {}", label, url),
+        }
+    }
+}
+
+/// The maintenance signals of one package.
+#[derive(Debug, Clone)]
+pub struct Maintenance {
+    pub name: String,
+    /// The newest final release that is not yanked, else the newest release.
+    pub latest: Option<Pep440Version>,
+    /// When the last release that is not yanked was uploaded, if the index says.
+    pub last_upload: Option<String>,
+    pub signals: Vec<Signal>,
+}
+
+impl Maintenance {
+    pub fn is_likely_unmaintained(&self) -> bool {
+        !self.signals.is_empty()
+    }
+}
+
+/// Looks for signs of abandonment in the latest release of every package:
+/// its age on `today` (days since the Unix epoch), the Python versions it
+/// supports, and with `check_urls` its project URLs.
+pub fn assess(client: &PypiClient, names: &[&str], today: i64, check_urls: bool) -> Result<Vec<Maintenance>> {
+    let all_releases = client.releases_all(names)?;
+    let mut reports: Vec<Maintenance> = names
+        .iter()
+        .zip(&all_releases)
+        .map(|(name, releases)| {
+            let listed: Vec<&Release> = releases.iter().filter(|r| !r.yanked).collect();
+            let latest = listed.iter().rev().find(|r| !r.version.is_prerelease()).or(listed.last());
+            let last_upload = listed.iter().filter_map(|r| r.uploaded.clone()).max();
+            let signals = last_upload
+                .iter()
+                .filter(|uploaded| timeline::days(uploaded).is_some_and(|day| today - day > STALE_DAYS))
+                .map(|uploaded| Signal::NoRecentRelease(uploaded.clone()))
+                .collect();
+            Maintenance {
+                name: name.to_string(),
+                latest: latest.map(|r| r.version.clone()),
+                last_upload,
+                signals,
+            }
+        })
+        .collect();
+
+    let latest: Vec<Option<Pep440Version>> = reports.iter().map(|report| report.latest.clone()).collect();
+    let asked: Vec<(usize, (&str, &Pep440Version))> = latest
+        .iter()
+        .enumerate()
+        .filter_map(|(i, version)| Some((i, (names[i], version.as_ref()?))))
+        .collect();
+    let releases: Vec<(&str, &Pep440Version)> = asked.iter().map(|(_, release)| *release).collect();
+    let infos = client.metadata_all(&releases, pypi::parse_project_info)?;
+    let mut urls: Vec<(usize, String, String)> = Vec::new();
+    for ((i, _), info) in asked.iter().zip(infos) {
+        if let Some(supported) = eol_only(&info, today) {
+            reports[*i].signals.push(Signal::EolPythonOnly(supported));
+        }
+        if check_urls {
+            urls.extend(info.urls.into_iter().map(|(label, url)| (*i, label, url)));
+        }
+    }
+    let pages: Vec<String> = urls.iter().map(|(_, _, url)| url.clone()).collect();
+    for ((i, label, url), missing) in urls.into_iter().zip(client.missing_pages(&pages)) {
+        if missing {
+            reports[i].signals.push(Signal::DeadUrl { label, url });
+        }
+    }
+    Ok(reports)
+}
+
+/// The Python versions a release supports when every one of them is past
+/// its end of life on `today`: those of its `Programming Language :: Python
+/// :: X.Y` classifiers, else its `Requires-Python`.
+pub fn eol_only(info: &ProjectInfo, today: i64) -> Option<String> {
+    let ended = |version: (u64, u64)| {
+        PYTHON_EOL
+            .iter()
+            .find(|(known, _)| *known == version)
+            .is_some_and(|(_, eol)| timeline::days(eol).is_some_and(|eol| eol <= today))
+    };
+    let classified: Vec<(u64, u64)> = info.classifiers.iter().filter_map(|classifier| classifier_version(classifier)).collect();
+    if !classified.is_empty() {
+        let shown: Vec<String> = classified.iter().map(|(major, minor)| format!("{}.{}", major, minor)).collect();
+        return classified.iter().all(|version| ended(*version)).then(|| format!("Python {}", shown.join(", ")));
+    }
+
+    // The versions `Requires-Python` allows any patch release of
+    let requires = info.requires_python.as_deref()?;
+    let range = SpecifierSet::parse(requires).ok()?.range();
+    let allows = |(major, minor): (u64, u64)| {
+        ["", ".999"]
+            .iter()
+            .filter_map(|patch| Pep440Version::parse(&format!("{}.{}{}", major, minor, patch)).ok())
+            .any(|version| range.contains(&version))
+    };
+    let allowed: Vec<(u64, u64)> = PYTHON_EOL.iter().map(|(version, _)| *version).filter(|version| allows(*version)).collect();
+    (!allowed.is_empty() && allowed.iter().all(|version| ended(*version))).then(|| format!("Requires-Python {}", requires))
+}
+
+/// The `X.Y` of a classifier like `Programming Language :: Python :: 3.7`.
+fn classifier_version(classifier: &str) -> Option<(u64, u64)> {
+    let version = classifier.strip_prefix("Programming Language :: Python :: ")?;
+    let (major, minor) = version.split_once('.')?;
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_eol_only() {
+        let today = timeline::days("2026-10-14").unwrap();
+        let classified = |versions: &[&str]| ProjectInfo {
+            classifiers: versions.iter().map(|v| format!("Programming Language :: Python :: {}", v)).collect(),
+            ..ProjectInfo::default()
+        };
+        assert_eq!(eol_only(&classified(&["2.7", "3", "3.6", "3.7"]), today).as_deref(), Some("Python 2.7, 3.6, 3.7"));
+        assert_eq!(eol_only(&classified(&["3.9", "3.10"]), today), None);
+        assert_eq!(eol_only(&classified(&["3 :: Only"]), today), None);
+
+        let requiring = |spec: &str| ProjectInfo {
+            requires_python: Some(spec.to_string()),
+            ..ProjectInfo::default()
+        };
+        assert_eq!(eol_only(&requiring(">=2.7, <3.8"), today).as_deref(), Some("Requires-Python >=2.7, <3.8"));
+        assert_eq!(eol_only(&requiring(">=3.6"), today), None);
+        // 3.10 is still supported on that day
+        assert_eq!(eol_only(&requiring("<3.11"), today), None);
+        assert_eq!(eol_only(&ProjectInfo::default(), today), None);
+    }
+}
@@ -0,0 +1,513 @@
+use anyhow::{bail, Result};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use crate::error::PyhelperError;
+use crate::pep440::Pep440Version;
+use crate::range::IntervalSet;
+use crate::specifier::Specifier;
+
+/// Marker variables whose values are compared as PEP 440 versions.
+const VERSION_VARIABLES: &[&str] = &["python_version", "python_full_version", "implementation_version"];
+
+/// Beyond this many alternatives the satisfiability check gives up and assumes overlap.
+const MAX_CONJUNCTIONS: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MarkerOperator {
+    Equal,
+    NotEqual,
+    Less,
+    LessEq,
+    Greater,
+    GreaterEq,
+    Compatible,
+    ArbitraryEqual,
+    In,
+    NotIn,
+}
+
+impl MarkerOperator {
+    /// The operator that gives the same result with its operands swapped.
+    fn flipped(self) -> Option<Self> {
+        match self {
+            MarkerOperator::Less => Some(MarkerOperator::Greater),
+            MarkerOperator::LessEq => Some(MarkerOperator::GreaterEq),
+            MarkerOperator::Greater => Some(MarkerOperator::Less),
+            MarkerOperator::GreaterEq => Some(MarkerOperator::LessEq),
+            MarkerOperator::Equal | MarkerOperator::NotEqual | MarkerOperator::ArbitraryEqual => Some(self),
+            MarkerOperator::Compatible | MarkerOperator::In | MarkerOperator::NotIn => None,
+        }
+    }
+
+    fn is_version_comparison(self) -> bool {
+        !matches!(
+            self,
+            MarkerOperator::ArbitraryEqual | MarkerOperator::In | MarkerOperator::NotIn
+        )
+    }
+}
+
+impl fmt::Display for MarkerOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            MarkerOperator::Equal => "==",
+            MarkerOperator::NotEqual => "!=",
+            MarkerOperator::Less => "<",
+            MarkerOperator::LessEq => "<=",
+            MarkerOperator::Greater => ">",
+            MarkerOperator::GreaterEq => ">=",
+            MarkerOperator::Compatible => "~=",
+            MarkerOperator::ArbitraryEqual => "===",
+            MarkerOperator::In => "in",
+            MarkerOperator::NotIn => "not in",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkerValue {
+    Variable(String),
+    Literal(String),
+}
+
+impl fmt::Display for MarkerValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkerValue::Variable(name) => write!(f, "{}", name),
+            MarkerValue::Literal(value) if value.contains('"') => write!(f, "'{}'", value),
+            MarkerValue::Literal(value) => write!(f, "\"{}\"", value),
+        }
+    }
+}
+
+/// A single comparison such as `python_version < "3.9"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkerExpression {
+    pub lhs: MarkerValue,
+    pub op: MarkerOperator,
+    pub rhs: MarkerValue,
+}
+
+/// A PEP 508 environment marker, e.g. `sys_platform == "win32" and python_version >= "3.8"`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MarkerTree {
+    Expression(MarkerExpression),
+    And(Vec<MarkerTree>),
+    Or(Vec<MarkerTree>),
+}
+
+/// Values of the marker variables for one target environment. Unset variables read as "".
+#[derive(Debug, Clone, Default)]
+pub struct MarkerEnvironment {
+    values: HashMap<String, String>,
+}
+
+impl MarkerEnvironment {
+    pub fn new(values: HashMap<String, String>) -> Self {
+        MarkerEnvironment { values }
+    }
+
+    pub fn get(&self, variable: &str) -> &str {
+        self.values.get(variable).map_or("", |v| v.as_str())
+    }
+
+    pub fn set(&mut self, variable: &str, value: &str) {
+        self.values.insert(variable.to_string(), value.to_string());
+    }
+}
+
+impl MarkerExpression {
+    pub fn evaluate(&self, env: &MarkerEnvironment) -> bool {
+        let resolve = |value: &MarkerValue| match value {
+            MarkerValue::Variable(name) => env.get(name).to_string(),
+            MarkerValue::Literal(value) => value.clone(),
+        };
+        let lhs = resolve(&self.lhs);
+        let rhs = resolve(&self.rhs);
+
+        match self.op {
+            MarkerOperator::In => return rhs.contains(&lhs),
+            MarkerOperator::NotIn => return !rhs.contains(&lhs),
+            MarkerOperator::ArbitraryEqual => return lhs == rhs,
+            _ => {}
+        }
+
+        // Values that both parse as versions are compared as versions, anything else as strings
+        let spec = Specifier::parse(&format!("{}{}", self.op, rhs));
+        if let (Ok(version), Ok(spec)) = (Pep440Version::parse(&lhs), spec) {
+            return spec.range().contains(&version);
+        }
+        match self.op {
+            MarkerOperator::Equal => lhs == rhs,
+            MarkerOperator::NotEqual => lhs != rhs,
+            MarkerOperator::Less => lhs < rhs,
+            MarkerOperator::LessEq => lhs <= rhs,
+            MarkerOperator::Greater => lhs > rhs,
+            MarkerOperator::GreaterEq => lhs >= rhs,
+            _ => false,
+        }
+    }
+}
+
+impl MarkerTree {
+    pub fn parse(input: &str) -> Result<Self> {
+        let tokens = tokenize(input)?;
+        let mut parser = Parser { tokens, pos: 0, depth: 0 };
+        let tree = parser.parse_or()?;
+        if parser.pos != parser.tokens.len() {
+            bail!(PyhelperError::Marker(format!("Unexpected trailing input in marker: {}", input)));
+        }
+        Ok(tree)
+    }
+
+    pub fn evaluate(&self, env: &MarkerEnvironment) -> bool {
+        match self {
+            MarkerTree::Expression(expression) => expression.evaluate(env),
+            MarkerTree::And(trees) => trees.iter().all(|t| t.evaluate(env)),
+            MarkerTree::Or(trees) => trees.iter().any(|t| t.evaluate(env)),
+        }
+    }
+
+    /// Whether some environment could make this marker true. Comparisons that
+    /// cannot be reasoned about are assumed satisfiable, so this never reports
+    /// `false` for a marker that might hold.
+    pub fn is_satisfiable(&self) -> bool {
+        match self.conjunctions() {
+            Some(conjunctions) => conjunctions.iter().any(|c| conjunction_satisfiable(c)),
+            None => true,
+        }
+    }
+
+    /// Whether both markers could hold in the same environment.
+    pub fn overlaps(&self, other: &MarkerTree) -> bool {
+        MarkerTree::And(vec![self.clone(), other.clone()]).is_satisfiable()
+    }
+
+    /// Disjunctive normal form: a list of alternatives, each a list of comparisons
+    /// that must all hold. `None` when the expansion grows too large.
+    fn conjunctions(&self) -> Option<Vec<Vec<&MarkerExpression>>> {
+        match self {
+            MarkerTree::Expression(expr) => Some(vec![vec![expr]]),
+            MarkerTree::Or(children) => {
+                let mut all = Vec::new();
+                for child in children {
+                    all.extend(child.conjunctions()?);
+                    if all.len() > MAX_CONJUNCTIONS {
+                        return None;
+                    }
+                }
+                Some(all)
+            }
+            MarkerTree::And(children) => {
+                let mut product: Vec<Vec<&MarkerExpression>> = vec![Vec::new()];
+                for child in children {
+                    let alternatives = child.conjunctions()?;
+                    if product.len() * alternatives.len() > MAX_CONJUNCTIONS {
+                        return None;
+                    }
+                    product = product
+                        .iter()
+                        .flat_map(|prefix| {
+                            alternatives.iter().map(move |alt| {
+                                prefix.iter().chain(alt.iter()).copied().collect()
+                            })
+                        })
+                        .collect();
+                }
+                Some(product)
+            }
+        }
+    }
+}
+
+fn conjunction_satisfiable(expressions: &[&MarkerExpression]) -> bool {
+    let mut versions: HashMap<&str, IntervalSet<Pep440Version>> = HashMap::new();
+    let mut equal: HashMap<&str, &str> = HashMap::new();
+    let mut not_equal: HashMap<&str, HashSet<&str>> = HashMap::new();
+
+    for expr in expressions {
+        // Put the variable on the left so each comparison reads `variable op value`
+        let (variable, op, value) = match (&expr.lhs, &expr.rhs) {
+            (MarkerValue::Literal(_), MarkerValue::Literal(_)) => {
+                if !expr.evaluate(&MarkerEnvironment::default()) {
+                    return false;
+                }
+                continue;
+            }
+            (MarkerValue::Variable(var), MarkerValue::Literal(value)) => (var.as_str(), expr.op, value.as_str()),
+            (MarkerValue::Literal(value), MarkerValue::Variable(var)) => match expr.op.flipped() {
+                Some(op) => (var.as_str(), op, value.as_str()),
+                None => continue,
+            },
+            (MarkerValue::Variable(_), MarkerValue::Variable(_)) => continue,
+        };
+
+        if VERSION_VARIABLES.contains(&variable) && op.is_version_comparison() {
+            if let Ok(spec) = Specifier::parse(&format!("{}{}", op, value)) {
+                let allowed = versions.entry(variable).or_insert_with(IntervalSet::full);
+                *allowed = allowed.intersect(&spec.range());
+                if allowed.is_empty() {
+                    return false;
+                }
+                continue;
+            }
+        }
+
+        match op {
+            MarkerOperator::Equal | MarkerOperator::ArbitraryEqual => {
+                if equal.get(variable).is_some_and(|&v| v != value)
+                    || not_equal.get(variable).is_some_and(|set| set.contains(value))
+                {
+                    return false;
+                }
+                equal.insert(variable, value);
+            }
+            MarkerOperator::NotEqual => {
+                if equal.get(variable) == Some(&value) {
+                    return false;
+                }
+                not_equal.entry(variable).or_default().insert(value);
+            }
+            _ => {}
+        }
+    }
+
+    true
+}
+
+impl fmt::Display for MarkerExpression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.lhs, self.op, self.rhs)
+    }
+}
+
+impl fmt::Display for MarkerTree {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarkerTree::Expression(expr) => write!(f, "{}", expr),
+            MarkerTree::And(children) => {
+                let parts: Vec<String> = children
+                    .iter()
+                    .map(|c| match c {
+                        MarkerTree::Or(_) => format!("({})", c),
+                        _ => c.to_string(),
+                    })
+                    .collect();
+                write!(f, "{}", parts.join(" and "))
+            }
+            MarkerTree::Or(children) => {
+                let parts: Vec<String> = children.iter().map(|c| c.to_string()).collect();
+                write!(f, "{}", parts.join(" or "))
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Identifier(String),
+    Literal(String),
+    Operator(MarkerOperator),
+    And,
+    Or,
+    LeftParen,
+    RightParen,
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' || c == ')' {
+            tokens.push(if c == '(' { Token::LeftParen } else { Token::RightParen });
+            i += 1;
+        } else if c == '"' || c == '\'' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|&q| q == c)
+                .ok_or_else(|| PyhelperError::Marker(format!("Unterminated string in marker: {}", input)))?;
+            tokens.push(Token::Literal(chars[i + 1..i + 1 + end].iter().collect()));
+            i += end + 2;
+        } else if "<>=!~".contains(c) {
+            let op: String = chars[i..].iter().take_while(|c| "<>=!~".contains(**c)).collect();
+            let op = match op.as_str() {
+                "==" => MarkerOperator::Equal,
+                "!=" => MarkerOperator::NotEqual,
+                "<" => MarkerOperator::Less,
+                "<=" => MarkerOperator::LessEq,
+                ">" => MarkerOperator::Greater,
+                ">=" => MarkerOperator::GreaterEq,
+                "~=" => MarkerOperator::Compatible,
+                "===" => MarkerOperator::ArbitraryEqual,
+                other => bail!(PyhelperError::Marker(format!("Invalid marker operator: {}", other))),
+            };
+            i += op.to_string().len();
+            tokens.push(Token::Operator(op));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let word: String = chars[i..]
+                .iter()
+                .take_while(|c| c.is_ascii_alphanumeric() || **c == '_' || **c == '.')
+                .collect();
+            i += word.len();
+            tokens.push(match word.as_str() {
+                "and" => Token::And,
+                "or" => Token::Or,
+                "in" => Token::Operator(MarkerOperator::In),
+                "not" => {
+                    // `not` only appears as part of `not in`
+                    let skipped = chars[i..].iter().take_while(|c| c.is_whitespace()).count();
+                    let rest = &chars[i + skipped..];
+                    let followed_by_in = rest.starts_with(&['i', 'n'])
+                        && rest.get(2).is_none_or(|c| !c.is_ascii_alphanumeric() && *c != '_');
+                    if skipped == 0 || !followed_by_in {
+                        bail!(PyhelperError::Marker(format!("Expected 'in' after 'not' in marker: {}", input)));
+                    }
+                    i += skipped + 2;
+                    Token::Operator(MarkerOperator::NotIn)
+                }
+                _ => Token::Identifier(word),
+            });
+        } else {
+            bail!(PyhelperError::Marker(format!("Unexpected character '{}' in marker: {}", c, input)));
+        }
+    }
+
+    Ok(tokens)
+}
+
+/// The most parentheses a marker may nest, so a malformed one cannot exhaust
+/// the stack of the recursive parser.
+const MAX_DEPTH: usize = 64;
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    /// The parentheses open at `pos`.
+    depth: usize,
+}
+
+impl Parser {
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<MarkerTree> {
+        let mut children = vec![self.parse_and()?];
+        while self.tokens.get(self.pos) == Some(&Token::Or) {
+            self.pos += 1;
+            children.push(self.parse_and()?);
+        }
+        Ok(if children.len() == 1 { children.remove(0) } else { MarkerTree::Or(children) })
+    }
+
+    fn parse_and(&mut self) -> Result<MarkerTree> {
+        let mut children = vec![self.parse_atom()?];
+        while self.tokens.get(self.pos) == Some(&Token::And) {
+            self.pos += 1;
+            children.push(self.parse_atom()?);
+        }
+        Ok(if children.len() == 1 { children.remove(0) } else { MarkerTree::And(children) })
+    }
+
+    fn parse_atom(&mut self) -> Result<MarkerTree> {
+        if self.tokens.get(self.pos) == Some(&Token::LeftParen) {
+            if self.depth == MAX_DEPTH {
+                bail!(PyhelperError::Marker(format!("Marker nested more than {} parentheses deep", MAX_DEPTH)));
+            }
+            self.pos += 1;
+            self.depth += 1;
+            let inner = self.parse_or()?;
+            self.depth -= 1;
+            return match self.next() {
+                Some(Token::RightParen) => Ok(inner),
+                _ => Err(PyhelperError::Marker("Expected ')' in marker".to_string()).into()),
+            };
+        }
+
+        let lhs = self.parse_value()?;
+        let op = match self.next() {
+            Some(Token::Operator(op)) => op,
+            _ => bail!(PyhelperError::Marker(format!("Expected a comparison operator after {} in marker", lhs))),
+        };
+        let rhs = self.parse_value()?;
+        Ok(MarkerTree::Expression(MarkerExpression { lhs, op, rhs }))
+    }
+
+    fn parse_value(&mut self) -> Result<MarkerValue> {
+        match self.next() {
+            Some(Token::Identifier(name)) => Ok(MarkerValue::Variable(name)),
+            Some(Token::Literal(value)) => Ok(MarkerValue::Literal(value)),
+            _ => Err(PyhelperError::Marker("Expected a marker variable or quoted string".to_string()).into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn marker(s: &str) -> MarkerTree {
+        MarkerTree::parse(s).unwrap()
+    }
+
+    fn expression(s: &str) -> MarkerExpression {
+        match marker(s) {
+            MarkerTree::Expression(expr) => expr,
+            other => panic!("not a single comparison: {}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        let m = marker("sys_platform=='win32' and (python_version<'3.9' or os_name not in \"nt posix\")");
+        assert_eq!(
+            m.to_string(),
+            "sys_platform == \"win32\" and (python_version < \"3.9\" or os_name not in \"nt posix\")"
+        );
+
+        assert!(MarkerTree::parse("python_version").is_err());
+        assert!(MarkerTree::parse("python_version >> '3'").is_err());
+        assert!(MarkerTree::parse("(os_name == 'nt'").is_err());
+        assert!(MarkerTree::parse("os_name == 'nt' extra").is_err());
+        let deep = format!("{}os_name == 'nt'{}", "(".repeat(10_000), ")".repeat(10_000));
+        assert!(MarkerTree::parse(&deep).is_err());
+    }
+
+    #[test]
+    fn test_evaluate() {
+        let mut env = MarkerEnvironment::default();
+        env.set("python_version", "3.10");
+        env.set("sys_platform", "linux");
+
+        // Version comparison, not string comparison: "3.10" > "3.9"
+        assert!(expression("python_version > '3.9'").evaluate(&env));
+        assert!(expression("'linux' in sys_platform").evaluate(&env));
+        assert!(!expression("python_version < '3.8'").evaluate(&env));
+        assert!(expression("extra == ''").evaluate(&env));
+
+        assert!(marker("sys_platform == 'win32' or python_version >= '3.10'").evaluate(&env));
+        assert!(!marker("sys_platform == 'linux' and extra == 'socks'").evaluate(&env));
+    }
+
+    #[test]
+    fn test_satisfiability() {
+        assert!(marker("sys_platform == 'win32'").overlaps(&marker("python_version >= '3.8'")));
+        assert!(!marker("sys_platform == 'win32'").overlaps(&marker("sys_platform == 'linux'")));
+        assert!(!marker("sys_platform == 'win32'").overlaps(&marker("sys_platform != 'win32'")));
+        assert!(!marker("python_version < '3.9'").overlaps(&marker("'3.9' <= python_version")));
+        assert!(marker("python_version < '3.9' or os_name == 'nt'").overlaps(&marker("python_version >= '3.9'")));
+        assert!(!marker("'a' == 'b'").is_satisfiable());
+
+        // Comparisons that cannot be analysed are assumed to overlap
+        assert!(marker("platform_release in '5.10'").overlaps(&marker("platform_release not in '5.10'")));
+    }
+}
@@ -0,0 +1,314 @@
+use anyhow::{bail, Result};
+use std::collections::BTreeMap;
+use std::ops::Bound;
+
+use crate::error::PyhelperError;
+use crate::package::PythonPackage;
+use crate::pep440::Pep440Version;
+use crate::range::{Interval, IntervalSet};
+use crate::requirements::RequirementLine;
+use crate::specifier::{as_written, Operator, Specifier, SpecifierSet};
+
+/// The requirements on one package from every source, combined into one.
+#[derive(Debug, Clone)]
+pub struct MergedRequirement {
+    pub package: PythonPackage,
+    pub sources: Vec<RequirementLine>,
+}
+
+/// Combines the requirements on each package into the tightest single
+/// requirement: the union of their clauses, without the clauses the others
+/// already imply (see [`SpecifierSet::reduced`]). Requirements under different markers stay separate, and
+/// extras are merged. The requirements are expected to be free of conflicts.
+pub fn merge(requirements: &[RequirementLine]) -> Vec<MergedRequirement> {
+    let mut groups: BTreeMap<(String, String), Vec<&RequirementLine>> = BTreeMap::new();
+    for requirement in requirements {
+        let marker = requirement.package.marker.as_ref().map(|m| m.to_string()).unwrap_or_default();
+        groups.entry((requirement.package.canonical_name(), marker)).or_default().push(requirement);
+    }
+
+    groups
+        .into_values()
+        .map(|lines| {
+            let first = &lines[0].package;
+            let mut extras: Vec<String> = lines.iter().flat_map(|r| r.package.extras.clone()).collect();
+            extras.sort();
+            extras.dedup();
+            let clauses = lines.iter().flat_map(|r| r.package.specifiers.specifiers.clone()).collect();
+            MergedRequirement {
+                package: PythonPackage {
+                    name: first.name.clone(),
+                    extras,
+                    specifiers: SpecifierSet { specifiers: clauses }.reduced(),
+                    marker: first.marker.clone(),
+                    url: lines.iter().find_map(|r| r.package.url.clone()),
+                },
+                sources: lines.into_iter().cloned().collect(),
+            }
+        })
+        .collect()
+}
+
+/// The single requirement accepting exactly the versions every one of
+/// `packages` accepts: all their clauses, simplified. The requirements must
+/// name the same package under the same marker; their extras are combined.
+pub fn intersect(packages: &[PythonPackage]) -> Result<PythonPackage> {
+    let first = same_package(packages, "intersect")?;
+    let clauses = packages.iter().flat_map(|p| p.specifiers.specifiers.clone()).collect();
+    Ok(combined(first, packages, SpecifierSet { specifiers: clauses }.simplified()))
+}
+
+/// The loosest single requirement covering several alternatives, such as the
+/// requirements on one package in per-environment requirements files.
+#[derive(Debug, Clone)]
+pub struct Union {
+    pub package: PythonPackage,
+    /// The versions the requirement allows although none of the alternatives
+    /// does, between the ranges they allow.
+    pub widened: IntervalSet<Pep440Version>,
+    /// The lowest and highest major version allowed, such as `3` or `1!2`
+    /// with an epoch, `None` when unbounded, when the alternatives together
+    /// span more major versions than any of them does alone.
+    pub majors: Option<(Pep440Version, Option<Pep440Version>)>,
+}
+
+/// Combines alternative requirements on one package into the single
+/// requirement allowing every version any of them allows: bounds around all of
+/// them, and the exclusions every one of them makes. The requirements must
+/// name the same package under the same marker; their extras are combined.
+pub fn union(packages: &[PythonPackage]) -> Result<Union> {
+    let first = same_package(packages, "unite")?;
+    let covered = packages
+        .iter()
+        .fold(IntervalSet { intervals: Vec::new() }, |acc, p| acc.union(&p.specifiers.range()));
+    let Some(hull) = covered.hull() else {
+        bail!(PyhelperError::Resolution(format!("None of the requirements on {} allows any version", first.name)));
+    };
+
+    let clauses: Vec<&Specifier> = packages.iter().flat_map(|p| p.specifiers.specifiers.iter()).collect();
+    // Bounds computed for `~=` and `.*` end at a `.dev0`, which is not how anyone writes them
+    let written = |version: Pep440Version| {
+        let computed = version.dev == Some(0) && version.pre.is_none() && version.post.is_none();
+        if computed && !clauses.iter().any(|c| c.version == version) {
+            Pep440Version { dev: None, ..version }
+        } else {
+            version
+        }
+    };
+    let clause = |op, version| Specifier {
+        op,
+        version,
+        wildcard: false,
+        literal: None,
+    };
+    let mut specifiers = Vec::new();
+    let hull = as_written(&hull);
+    // Bounds cannot name a local label, so one on a local version covers all of that version
+    let public = |v: &Pep440Version| Pep440Version { local: Vec::new(), ..v.clone() };
+    match &hull.lower {
+        Bound::Included(v) | Bound::Excluded(v) if !v.local.is_empty() => specifiers.push(clause(Operator::GreaterEq, public(v))),
+        Bound::Included(v) => specifiers.push(clause(Operator::GreaterEq, written(v.clone()))),
+        Bound::Excluded(v) => specifiers.push(clause(Operator::Greater, v.clone())),
+        Bound::Unbounded => {}
+    }
+    match &hull.upper {
+        Bound::Included(v) | Bound::Excluded(v) if !v.local.is_empty() => specifiers.push(clause(Operator::LessEq, public(v))),
+        Bound::Included(v) => specifiers.push(clause(Operator::LessEq, v.clone())),
+        Bound::Excluded(v) => specifiers.push(clause(Operator::Less, written(v.clone()))),
+        Bound::Unbounded => {}
+    }
+    // An exclusion every alternative makes can stay
+    specifiers.extend(
+        clauses
+            .iter()
+            .filter(|c| c.op == Operator::NotEqual && covered.intersect(&c.range().complement()).is_empty())
+            .map(|c| (*c).clone()),
+    );
+    let specifiers = SpecifierSet { specifiers }.simplified();
+
+    let span = |range: &IntervalSet<Pep440Version>| range.hull().map(|hull| majors(&hull));
+    // Spanning two epochs is wider than any number of major versions
+    let width = |span: &Option<(Pep440Version, Option<Pep440Version>)>| match span {
+        None => 0,
+        Some((low, Some(high))) if low.epoch == high.epoch => high.release[0].saturating_sub(low.release[0]),
+        Some(_) => u64::MAX,
+    };
+    let widest = packages.iter().map(|p| width(&span(&p.specifiers.range()))).max().unwrap_or(0);
+    let union_span = span(&covered);
+    Ok(Union {
+        widened: specifiers.range().intersect(&IntervalSet::from(hull)).intersect(&covered.complement()),
+        majors: union_span.clone().filter(|_| width(&union_span) > widest),
+        package: combined(first, packages, specifiers),
+    })
+}
+
+/// The lowest and highest major version in `interval`, within their epochs,
+/// not counting the pre-releases below an upper bound like `<4`.
+fn majors(interval: &Interval<Pep440Version>) -> (Pep440Version, Option<Pep440Version>) {
+    let low = match &interval.lower {
+        Bound::Included(v) | Bound::Excluded(v) => major(v.epoch, v.release[0]),
+        Bound::Unbounded => major(0, 0),
+    };
+    let high = match &interval.upper {
+        Bound::Included(v) => Some(major(v.epoch, v.release[0])),
+        Bound::Excluded(v) if v.release[1..].iter().all(|&s| s == 0) && v.post.is_none() => {
+            Some(major(v.epoch, v.release[0].saturating_sub(1)))
+        }
+        Bound::Excluded(v) => Some(major(v.epoch, v.release[0])),
+        Bound::Unbounded => None,
+    };
+    (low, high)
+}
+
+fn major(epoch: u64, number: u64) -> Pep440Version {
+    Pep440Version {
+        epoch,
+        release: vec![number],
+        pre: None,
+        post: None,
+        dev: None,
+        local: Vec::new(),
+    }
+}
+
+/// The first of `packages`, after checking that all of them name the same
+/// package under the same marker.
+fn same_package<'a>(packages: &'a [PythonPackage], verb: &str) -> Result<&'a PythonPackage> {
+    let Some(first) = packages.first() else {
+        bail!(PyhelperError::Invalid(format!("No requirements to {}", verb)));
+    };
+    let marker = |package: &PythonPackage| package.marker.as_ref().map(|m| m.to_string());
+    for package in &packages[1..] {
+        if package.canonical_name() != first.canonical_name() {
+            let message = format!("Cannot {} requirements on different packages: {} and {}", verb, first.name, package.name);
+            bail!(PyhelperError::Invalid(message));
+        }
+        if marker(package) != marker(first) {
+            let message = format!("Cannot {} requirements under different markers: {} and {}", verb, first, package);
+            bail!(PyhelperError::Invalid(message));
+        }
+    }
+    Ok(first)
+}
+
+/// A requirement like `first` with the extras of all `packages` and `specifiers`.
+fn combined(first: &PythonPackage, packages: &[PythonPackage], specifiers: SpecifierSet) -> PythonPackage {
+    let mut extras: Vec<String> = packages.iter().flat_map(|p| p.extras.clone()).collect();
+    extras.sort();
+    extras.dedup();
+    PythonPackage {
+        name: first.name.clone(),
+        extras,
+        specifiers,
+        marker: first.marker.clone(),
+        url: packages.iter().find_map(|p| p.url.clone()),
+    }
+}
+
+/// Renders merged requirements as a requirements file.
+pub fn requirements_txt(merged: &[MergedRequirement], sources: &[String]) -> String {
+    format!("# Merged by pyhelper from {}\n{}", sources.join(", "), lines(merged))
+}
+
+/// One requirements-file line per merged requirement.
+pub fn lines(merged: &[MergedRequirement]) -> String {
+    let mut out = String::new();
+    for requirement in merged {
+        out.push_str(&line(&requirement.package));
+        out.push('\n');
+    }
+    out
+}
+
+/// `package` spelled as in a requirements file, e.g. `requests[socks]>=2.31,<3`.
+pub fn line(package: &PythonPackage) -> String {
+    let mut out = package.name.clone();
+    if !package.extras.is_empty() {
+        out.push_str(&format!("[{}]", package.extras.join(",")));
+    }
+    match &package.url {
+        Some(url) => out.push_str(&format!(" @ {}", url)),
+        None => out.push_str(&package.specifiers.to_string()),
+    }
+    if let Some(marker) = &package.marker {
+        out.push_str(&format!(" ; {}", marker));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::explain::versions;
+    use std::path::PathBuf;
+
+    fn line(input: &str) -> RequirementLine {
+        RequirementLine {
+            package: PythonPackage::parse(input).unwrap(),
+            file: PathBuf::from("requirements.txt"),
+            line: 1,
+            columns: None,
+            hashes: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_merge() {
+        let requirements = vec![
+            line("requests>=2.0,<3"),
+            line("Django>=3.2"),
+            line("requests[socks]>=2.31"),
+            line("django~=4.2"),
+            line("requests!=2.32.0,<3"),
+            line("colorama; sys_platform == 'win32'"),
+            line("colorama>=0.4"),
+        ];
+        let merged = requirements_txt(&merge(&requirements), &["a.txt".to_string(), "b.txt".to_string()]);
+        assert_eq!(
+            merged,
+            "# Merged by pyhelper from a.txt, b.txt\ncolorama>=0.4\ncolorama ; sys_platform == \"win32\"\nDjango~=4.2\nrequests[socks]<3,>=2.31,!=2.32.0\n"
+        );
+    }
+
+    #[test]
+    fn test_intersect() -> Result<()> {
+        let packages: Vec<PythonPackage> = ["django>=3.2", "Django<5", "django!=4.0.*", "django>=2"]
+            .iter()
+            .map(|p| PythonPackage::parse(p))
+            .collect::<Result<_>>()?;
+        assert_eq!(intersect(&packages)?.to_string(), "django >=3.2,<5,!=4.0.*");
+
+        let a = PythonPackage::parse("django<3")?;
+        let b = PythonPackage::parse("flask<3")?;
+        assert!(intersect(&[a.clone(), b]).is_err());
+        let b = PythonPackage::parse("django>=4; python_version >= '3.10'")?;
+        assert!(intersect(&[a, b]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_union() -> Result<()> {
+        let union_of = |inputs: &[&str]| -> Result<Union> {
+            let packages = inputs.iter().map(|p| PythonPackage::parse(p)).collect::<Result<Vec<_>>>()?;
+            union(&packages)
+        };
+        let both = union_of(&["django>=3.2,<4,!=3.2.5", "django~=4.1", "django==4.1.2"])?;
+        assert_eq!(both.package.to_string(), "django >=3.2,<5,!=3.2.5");
+        assert_eq!(versions(&both.widened), "[4.dev0, 4.1)");
+        assert_eq!(both.majors, Some((major(0, 3), Some(major(0, 4)))));
+
+        let joined = union_of(&["attrs>=21,<22", "attrs>=21.3,<=22.1"])?;
+        assert_eq!(joined.package.to_string(), "attrs >=21,<=22.1");
+        assert!(joined.widened.is_empty());
+        assert_eq!(union_of(&["six>=1.15", "six>=1.16,<2"])?.majors, None);
+        // A project that moved from calendar versions to an epoch
+        let reset = union_of(&["pytz>=2020.1,<2021", "pytz>=1!1.0,<1!2"])?;
+        assert_eq!(reset.majors, Some((major(0, 2020), Some(major(1, 1)))));
+        assert!(union_of(&["six<1", "six>2,<1"]).is_ok());
+        assert!(union_of(&["six>2,<1"]).is_err());
+        // Bounds drop the local labels they cannot name
+        let local = union_of(&["torch==2.0+cpu", "torch==2.1+cpu"])?;
+        assert_eq!(local.package.to_string(), "torch >=2.0,<=2.1");
+        Ok(())
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::Result;
+use std::fmt;
+
+use crate::audit::Audited;
+use crate::package::canonicalize_name;
+use crate::pep440::Pep440Version;
+use crate::prerelease::PreReleasePolicy;
+use crate::pypi::{self, PypiClient};
+use crate::requirements::RequirementLine;
+use crate::specifier::SpecifierSet;
+
+/// How far a pinned version is behind a newer release, by the first release
+/// segment that changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Gap {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl Gap {
+    /// The gap from `current` up to `newer`; `None` unless `newer` is newer.
+    pub fn between(current: &Pep440Version, newer: &Pep440Version) -> Option<Gap> {
+        if newer <= current {
+            return None;
+        }
+        let segment = |version: &Pep440Version, i: usize| version.release.get(i).copied().unwrap_or_default();
+        if newer.epoch != current.epoch || segment(newer, 0) != segment(current, 0) {
+            Some(Gap::Major)
+        } else if segment(newer, 1) != segment(current, 1) {
+            Some(Gap::Minor)
+        } else {
+            Some(Gap::Patch)
+        }
+    }
+}
+
+impl fmt::Display for Gap {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Gap::Patch => "patch",
+            Gap::Minor => "minor",
+            Gap::Major => "major",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// A pinned package with a newer release on the index.
+#[derive(Debug, Clone)]
+pub struct OutdatedPackage {
+    pub package: Audited,
+    /// The release pip would pick for the bare package name.
+    pub latest: Pep440Version,
+    pub gap: Gap,
+    /// The newest release the constraints on the package allow, when it is
+    /// newer than the pinned version.
+    pub target: Option<Pep440Version>,
+    /// The constraints excluding `latest`, e.g. `lib 2.0 requires util<2`.
+    pub held_back_by: Vec<String>,
+}
+
+/// Compares every package against the index. Upgrade targets respect the
+/// requirements the other packages declare at their pinned versions, and the
+/// given constraints.
+pub fn check(
+    client: &PypiClient,
+    packages: &[Audited],
+    constraints: &[RequirementLine],
+    policy: PreReleasePolicy,
+) -> Result<Vec<OutdatedPackage>> {
+    let names: Vec<&str> = packages.iter().map(|p| p.name.as_str()).collect();
+    let all_releases = client.releases_all(&names)?;
+    let pins: Vec<(&str, &Pep440Version)> = packages.iter().map(|p| (p.name.as_str(), &p.version)).collect();
+    let mut declared: Vec<(String, SpecifierSet, String)> = Vec::new();
+    for (package, dependencies) in packages.iter().zip(client.requires_dist_all(&pins)?) {
+        for dependency in dependencies.into_iter().filter(|d| d.may_apply(&[])) {
+            let source = format!("{} {} requires {}", package.name, package.version, dependency);
+            declared.push((dependency.canonical_name(), dependency.specifiers, source));
+        }
+    }
+    for constraint in constraints {
+        let source = format!("{} {}", constraint.location(), constraint.package);
+        declared.push((constraint.package.canonical_name(), constraint.package.specifiers.clone(), source));
+    }
+
+    let mut outdated = Vec::new();
+    for (package, releases) in packages.iter().zip(all_releases) {
+        let Some(latest) = pypi::best_match(&releases, &SpecifierSet::default(), policy) else {
+            continue;
+        };
+        let Some(gap) = Gap::between(&package.version, &latest) else {
+            continue;
+        };
+        let name = canonicalize_name(&package.name);
+        let applying: Vec<&(String, SpecifierSet, String)> =
+            declared.iter().filter(|(constrained, _, _)| *constrained == name).collect();
+        let combined = SpecifierSet {
+            specifiers: applying.iter().flat_map(|(_, specifiers, _)| specifiers.specifiers.clone()).collect(),
+        };
+        let target = pypi::best_match(&releases, &combined, policy).filter(|target| *target > package.version);
+        let held_back_by = applying
+            .iter()
+            .filter(|(_, specifiers, _)| !specifiers.range().contains(&latest))
+            .map(|(_, _, source)| source.clone())
+            .collect();
+        outdated.push(OutdatedPackage {
+            package: package.clone(),
+            latest,
+            gap,
+            target,
+            held_back_by,
+        });
+    }
+    Ok(outdated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gap(current: &str, newer: &str) -> Option<Gap> {
+        Gap::between(&Pep440Version::parse(current).unwrap(), &Pep440Version::parse(newer).unwrap())
+    }
+
+    #[test]
+    fn test_gap() {
+        assert_eq!(gap("1.2.3", "1.2.4"), Some(Gap::Patch));
+        assert_eq!(gap("1.2.3", "1.2.3.post1"), Some(Gap::Patch));
+        assert_eq!(gap("1.2", "1.3.0"), Some(Gap::Minor));
+        assert_eq!(gap("1.9.9", "2.0"), Some(Gap::Major));
+        assert_eq!(gap("2023.1", "1!1.0"), Some(Gap::Major));
+        assert_eq!(gap("1.2.3", "1.2.3"), None);
+        assert_eq!(gap("2.0", "1.9"), None);
+    }
+}
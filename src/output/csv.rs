@@ -0,0 +1,162 @@
+use crate::audit::Finding;
+use crate::license::LicenseEntry;
+#[cfg(feature = "network")]
+use crate::outdated::OutdatedPackage;
+
+/// Rows under a header, for spreadsheets. Lists within a cell, such as the
+/// aliases of an advisory, are joined with `; `.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Table {
+    pub header: Vec<&'static str>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Renders the table as RFC 4180 CSV, quoting the fields that need it.
+    pub fn csv(&self) -> String {
+        self.render(",", |field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                format!("\"{}\"", field.replace('"', "\"\""))
+            } else {
+                field.to_string()
+            }
+        })
+    }
+
+    /// Renders the table as tab separated values, which have no quoting:
+    /// tabs and line breaks within a field become spaces.
+    pub fn tsv(&self) -> String {
+        self.render("\t", |field| field.replace(['\t', '\n', '\r'], " "))
+    }
+
+    fn render(&self, separator: &str, field: impl Fn(&str) -> String) -> String {
+        let header = self.header.iter().map(|name| name.to_string());
+        std::iter::once(header.collect::<Vec<String>>())
+            .chain(self.rows.iter().cloned())
+            .map(|row| {
+                let fields: Vec<String> = row.iter().map(|value| field(value)).collect();
+                format!("{}\r\n", fields.join(separator))
+            })
+            .collect()
+    }
+}
+
+/// A row per advisory affecting a package.
+pub fn audit(findings: &[Finding]) -> Table {
+    let rows = findings
+        .iter()
+        .flat_map(|finding| {
+            let package = &finding.package;
+            finding.advisories.iter().map(move |advisory| {
+                vec![
+                    package.name.clone(),
+                    package.version.to_string(),
+                    package.file.display().to_string(),
+                    package.line.to_string(),
+                    package.resolved.to_string(),
+                    advisory.id.clone(),
+                    advisory.aliases.join("; "),
+                    advisory.severity.map(|s| s.to_string()).unwrap_or_default(),
+                    advisory.score.map(|s| format!("{:.1}", s)).unwrap_or_default(),
+                    advisory.fixed_after(&package.version).map(|v| v.to_string()).unwrap_or_default(),
+                    advisory.summary.clone().unwrap_or_default(),
+                ]
+            })
+        })
+        .collect();
+    Table {
+        header: vec!["name", "version", "file", "line", "resolved", "id", "aliases", "severity", "score", "fixed_in", "summary"],
+        rows,
+    }
+}
+
+/// A row per package, with its license and how the policy judges it.
+pub fn licenses(entries: &[LicenseEntry]) -> Table {
+    let rows = entries
+        .iter()
+        .map(|entry| {
+            let (license, source) = match &entry.license {
+                Some((license, source)) => (license.clone(), source.to_string()),
+                None => (String::new(), String::new()),
+            };
+            vec![
+                entry.name.clone(),
+                entry.version.to_string(),
+                license,
+                source,
+                entry.verdict.to_string(),
+                entry.violation.to_string(),
+            ]
+        })
+        .collect();
+    Table {
+        header: vec!["name", "version", "license", "source", "verdict", "violation"],
+        rows,
+    }
+}
+
+#[cfg(feature = "network")]
+/// A row per package behind its latest release.
+pub fn outdated(outdated: &[OutdatedPackage]) -> Table {
+    let rows = outdated
+        .iter()
+        .map(|entry| {
+            vec![
+                entry.package.name.clone(),
+                entry.package.version.to_string(),
+                entry.latest.to_string(),
+                entry.gap.to_string(),
+                entry.target.as_ref().map(|v| v.to_string()).unwrap_or_default(),
+                entry.held_back_by.join("; "),
+                entry.package.file.display().to_string(),
+                entry.package.line.to_string(),
+            ]
+        })
+        .collect();
+    Table {
+        header: vec!["name", "version", "latest", "gap", "target", "held_back_by", "file", "line"],
+        rows,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audit::{Advisory, Audited, Severity};
+    use crate::pep440::Pep440Version;
+    use anyhow::Result;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_audit() -> Result<()> {
+        let findings = vec![Finding {
+            package: Audited {
+                name: "django".to_string(),
+                version: Pep440Version::parse("3.2.0")?,
+                file: PathBuf::from("requirements.txt"),
+                line: 3,
+                columns: None,
+                resolved: false,
+            },
+            advisories: vec![Advisory {
+                id: "GHSA-jh3w-4vvf-mjgr".to_string(),
+                aliases: vec!["CVE-2023-24580".to_string(), "PYSEC-2023-13".to_string()],
+                summary: Some("Denial of service in \"multipart\" uploads,\tfixed".to_string()),
+                severity: Some(Severity::High),
+                score: Some(7.5),
+                fixed: vec![Pep440Version::parse("3.2.18")?],
+            }],
+        }];
+        let table = audit(&findings);
+        let csv = table.csv();
+        let lines: Vec<&str> = csv.split("\r\n").collect();
+        assert_eq!(lines[0], "name,version,file,line,resolved,id,aliases,severity,score,fixed_in,summary");
+        assert_eq!(
+            lines[1],
+            "django,3.2.0,requirements.txt,3,false,GHSA-jh3w-4vvf-mjgr,CVE-2023-24580; PYSEC-2023-13,HIGH,7.5,3.2.18,\
+             \"Denial of service in \"\"multipart\"\" uploads,\tfixed\""
+        );
+        assert!(table.tsv().ends_with("\t3.2.18\tDenial of service in \"multipart\" uploads, fixed\r\n"));
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+use std::collections::HashSet;
+
+use crate::graph::{DependencyGraph, Edge};
+use crate::output::tree::{edge_label, is_unsatisfied, label, TreeOptions};
+
+/// The edges `tree` would print for these roots and options, each once.
+pub fn tree_edges<'a>(graph: &'a DependencyGraph, roots: &[String], options: &TreeOptions) -> Vec<&'a Edge> {
+    let mut edges = Vec::new();
+    let mut expanded = HashSet::new();
+    let mut level: Vec<String> = roots.to_vec();
+    let mut depth = 0;
+    while !level.is_empty() && options.depth.is_none_or(|max| depth < max) {
+        let mut next = Vec::new();
+        for name in level {
+            if !expanded.insert(name.clone()) {
+                continue;
+            }
+            let children = if options.invert {
+                graph.dependents(&name)
+            } else {
+                graph.dependencies(&name)
+            };
+            for edge in children {
+                next.push(if options.invert { edge.from.clone() } else { edge.to.clone() });
+                edges.push(edge);
+            }
+        }
+        level = next;
+        depth += 1;
+    }
+    edges
+}
+
+/// Renders the edges as a Graphviz digraph. Roots are drawn bold, missing
+/// packages dashed and unsatisfied requirements in red.
+pub fn dot(graph: &DependencyGraph, roots: &[String], edges: &[&Edge]) -> String {
+    let mut text = String::from("digraph dependencies {\n    node [shape=box];\n");
+    for name in nodes(roots, edges) {
+        let mut attributes = vec![format!("label={}", dot_string(&label(graph, &name)))];
+        let mut styles = Vec::new();
+        if roots.contains(&name) {
+            styles.push("bold");
+        }
+        if graph.node(&name).is_none_or(|node| node.version.is_none()) {
+            styles.push("dashed");
+        }
+        if !styles.is_empty() {
+            attributes.push(format!("style={}", dot_string(&styles.join(","))));
+        }
+        text.push_str(&format!("    {} [{}];\n", dot_string(&name), attributes.join(", ")));
+    }
+    for edge in edges {
+        let mut attributes = Vec::new();
+        let constraint = edge_label(edge);
+        if !constraint.is_empty() {
+            attributes.push(format!("label={}", dot_string(&constraint)));
+        }
+        if is_unsatisfied(graph, edge) {
+            attributes.push("color=red, fontcolor=red".to_string());
+        }
+        text.push_str(&format!("    {} -> {}", dot_string(&edge.from), dot_string(&edge.to)));
+        if !attributes.is_empty() {
+            text.push_str(&format!(" [{}]", attributes.join(", ")));
+        }
+        text.push_str(";\n");
+    }
+    text.push_str("}\n");
+    text
+}
+
+/// Renders the edges as a Mermaid flowchart, with unsatisfied requirements in red.
+pub fn mermaid(graph: &DependencyGraph, roots: &[String], edges: &[&Edge]) -> String {
+    let nodes = nodes(roots, edges);
+    let id = |name: &str| format!("n{}", nodes.iter().position(|n| n == name).unwrap_or_default());
+    let mut text = String::from("graph TD\n");
+    for name in &nodes {
+        text.push_str(&format!("    {}[\"{}\"]\n", id(name), mermaid_text(&label(graph, name))));
+    }
+    let mut conflicts = Vec::new();
+    for (i, edge) in edges.iter().enumerate() {
+        match edge_label(edge) {
+            constraint if constraint.is_empty() => {
+                text.push_str(&format!("    {} --> {}\n", id(&edge.from), id(&edge.to)));
+            }
+            constraint => text.push_str(&format!(
+                "    {} -->|\"{}\"| {}\n",
+                id(&edge.from),
+                mermaid_text(&constraint),
+                id(&edge.to)
+            )),
+        }
+        if is_unsatisfied(graph, edge) {
+            conflicts.push(i.to_string());
+        }
+    }
+    if !conflicts.is_empty() {
+        text.push_str(&format!("    linkStyle {} stroke:red,color:red\n", conflicts.join(",")));
+    }
+    text
+}
+
+/// The roots and every endpoint of the edges, in order of appearance.
+fn nodes(roots: &[String], edges: &[&Edge]) -> Vec<String> {
+    let mut nodes: Vec<String> = Vec::new();
+    let endpoints = edges.iter().flat_map(|edge| [&edge.from, &edge.to]);
+    for name in roots.iter().chain(endpoints) {
+        if !nodes.contains(name) {
+            nodes.push(name.clone());
+        }
+    }
+    nodes
+}
+
+fn dot_string(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Mermaid reads labels as HTML, so quotes and angle brackets become entity codes.
+pub(crate) fn mermaid_text(text: &str) -> String {
+    text.replace('"', "#quot;").replace('<', "#lt;").replace('>', "#gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::InstalledDistribution;
+    use crate::output::tree::Charset;
+    use crate::package::PythonPackage;
+    use crate::pep440::Pep440Version;
+    use std::path::PathBuf;
+
+    fn graph() -> DependencyGraph {
+        let dist = |name: &str, version: &str, requires: &[&str]| InstalledDistribution {
+            name: name.to_string(),
+            version: Pep440Version::parse(version).unwrap(),
+            requires: requires.iter().map(|r| PythonPackage::parse(r).unwrap()).collect(),
+            path: PathBuf::new(),
+        };
+        DependencyGraph::from_installed(
+            &[
+                dist("requests", "2.31.0", &["urllib3<3,>=1.21.1", "idna", "certifi"]),
+                dist("urllib3", "3.0.0", &[]),
+                dist("idna", "3.6", &[]),
+            ],
+            None,
+        )
+    }
+
+    #[test]
+    fn test_dot() {
+        let graph = graph();
+        let options = TreeOptions {
+            depth: None,
+            invert: false,
+            charset: Charset::Utf8,
+        };
+        let edges = tree_edges(&graph, &graph.roots, &options);
+        assert_eq!(
+            dot(&graph, &graph.roots, &edges),
+            r#"digraph dependencies {
+    node [shape=box];
+    "requests" [label="requests 2.31.0", style="bold"];
+    "urllib3" [label="urllib3 3.0.0"];
+    "idna" [label="idna 3.6"];
+    "certifi" [label="certifi (missing)", style="dashed"];
+    "requests" -> "urllib3" [label="<3,>=1.21.1", color=red, fontcolor=red];
+    "requests" -> "idna";
+    "requests" -> "certifi";
+}
+"#
+        );
+    }
+
+    #[test]
+    fn test_mermaid() {
+        let graph = graph();
+        let options = TreeOptions {
+            depth: Some(1),
+            invert: true,
+            charset: Charset::Utf8,
+        };
+        let roots = vec!["urllib3".to_string()];
+        let edges = tree_edges(&graph, &roots, &options);
+        assert_eq!(
+            mermaid(&graph, &roots, &edges),
+            r##"graph TD
+    n0["urllib3 3.0.0"]
+    n1["requests 2.31.0"]
+    n1 -->|"#lt;3,#gt;=1.21.1"| n0
+    linkStyle 0 stroke:red,color:red
+"##
+        );
+    }
+}
@@ -0,0 +1,341 @@
+use crate::conflict::ConflictReport;
+use crate::explain::versions;
+use crate::package::PythonPackage;
+use crate::pypi::Verification;
+use crate::range::IntervalSet;
+use crate::requirements::RequirementLine;
+
+use super::diagram::mermaid_text;
+
+const TITLE: &str = "pyhelper check";
+
+/// A check result laid out for people rather than tools: a summary, then a
+/// row per package, with a graph of the requirements behind each failure.
+/// It renders as Markdown for pull request descriptions, or as a standalone
+/// HTML page to publish as a CI artifact.
+#[derive(Debug, Clone)]
+pub struct Document {
+    sources: Vec<String>,
+    checked: usize,
+    /// Whether releases were verified, which adds a count to the summary.
+    verified: bool,
+    rows: Vec<Row>,
+}
+
+#[derive(Debug, Clone)]
+struct Row {
+    name: String,
+    status: Status,
+    requirements: Vec<Requirement>,
+    /// The versions every requirement allows together.
+    allowed: String,
+}
+
+#[derive(Debug, Clone)]
+struct Requirement {
+    package: PythonPackage,
+    /// `file:line:column`, or `None` for requirements given on the command line.
+    location: Option<String>,
+    file: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Conflict,
+    /// The requirements overlap, but not on any released version.
+    Unreleased,
+}
+
+impl Status {
+    fn label(self) -> &'static str {
+        match self {
+            Status::Ok => "ok",
+            Status::Conflict => "conflict",
+            Status::Unreleased => "no matching release",
+        }
+    }
+}
+
+impl Document {
+    /// Lays out a report, with every package's status and, when `verified`
+    /// is given, the packages without a released version in common.
+    pub fn report(sources: &[String], report: &ConflictReport, verified: Option<&[Verification]>) -> Self {
+        let rows = report
+            .packages
+            .iter()
+            .map(|summary| {
+                let unreleased = verified
+                    .into_iter()
+                    .flatten()
+                    .any(|v| v.name == summary.name && v.matching.is_empty());
+                let status = match report.conflict_for(&summary.name) {
+                    Some(_) => Status::Conflict,
+                    None if unreleased => Status::Unreleased,
+                    None => Status::Ok,
+                };
+                Row {
+                    name: summary.name.clone(),
+                    status,
+                    requirements: summary.requirements.iter().map(located).collect(),
+                    allowed: versions(&summary.allowed),
+                }
+            })
+            .collect();
+        Document {
+            sources: sources.to_vec(),
+            checked: report.checked,
+            verified: verified.is_some(),
+            rows,
+        }
+    }
+
+    /// Lays out the comparison of two requirements given on the command
+    /// line: a row for their package, or one for each if they differ.
+    pub fn pair(pkg1: &PythonPackage, pkg2: &PythonPackage, conflict: bool) -> Self {
+        let unlocated = |package: &PythonPackage| Requirement {
+            package: package.clone(),
+            location: None,
+            file: None,
+        };
+        let row = |packages: &[&PythonPackage], status| Row {
+            name: packages[0].canonical_name(),
+            status,
+            requirements: packages.iter().map(|p| unlocated(p)).collect(),
+            allowed: versions(&packages.iter().fold(IntervalSet::full(), |acc, p| acc.intersect(&p.specifiers.range()))),
+        };
+        let rows = if pkg1.canonical_name() == pkg2.canonical_name() {
+            vec![row(&[pkg1, pkg2], if conflict { Status::Conflict } else { Status::Ok })]
+        } else {
+            vec![row(&[pkg1], Status::Ok), row(&[pkg2], Status::Ok)]
+        };
+        Document {
+            sources: vec!["--pkg1".to_string(), "--pkg2".to_string()],
+            checked: 2,
+            verified: false,
+            rows,
+        }
+    }
+
+    fn failing(&self) -> impl Iterator<Item = &Row> {
+        self.rows.iter().filter(|row| row.status != Status::Ok)
+    }
+
+    fn count(&self, status: Status) -> usize {
+        self.rows.iter().filter(|row| row.status == status).count()
+    }
+
+    /// The summary as label and count pairs.
+    fn summary(&self) -> Vec<(&'static str, usize)> {
+        let mut summary = vec![
+            ("Requirements checked", self.checked),
+            ("Packages", self.rows.len()),
+            ("Conflicts", self.count(Status::Conflict)),
+        ];
+        if self.verified {
+            summary.push(("Without a matching release", self.count(Status::Unreleased)));
+        }
+        summary
+    }
+
+    fn verdict(&self) -> &'static str {
+        match self.failing().count() {
+            0 => "No conflicts detected",
+            _ => "Conflicts detected",
+        }
+    }
+
+    /// Renders the document as GitHub flavored Markdown, with a Mermaid
+    /// graph per failing package.
+    pub fn markdown(&self) -> String {
+        let sources: Vec<String> = self.sources.iter().map(|s| format!("`{}`", s)).collect();
+        let mut text = format!("# {}\n\n**{}** in {}.\n\n", TITLE, self.verdict(), sources.join(", "));
+        text.push_str("## Summary\n\n| | Count |\n| --- | ---: |\n");
+        for (label, count) in self.summary() {
+            text.push_str(&format!("| {} | {} |\n", label, count));
+        }
+        text.push_str("\n## Packages\n\n| Package | Status | Requirements | Allowed versions |\n| --- | --- | --- | --- |\n");
+        for row in &self.rows {
+            let requirements: Vec<String> = row
+                .requirements
+                .iter()
+                .map(|r| match &r.location {
+                    Some(location) => format!("`{}` ({})", r.package, location),
+                    None => format!("`{}`", r.package),
+                })
+                .collect();
+            let status = match row.status {
+                Status::Ok => row.status.label().to_string(),
+                status => format!("**{}**", status.label()),
+            };
+            text.push_str(&format!(
+                "| {} | {} | {} | {} |\n",
+                cell(&row.name),
+                status,
+                cell(&requirements.join("<br>")),
+                cell(&row.allowed)
+            ));
+        }
+        for row in self.failing() {
+            text.push_str(&format!("\n## {} ({})\n\n", row.name, row.status.label()));
+            for requirement in &row.requirements {
+                match &requirement.location {
+                    Some(location) => text.push_str(&format!("- `{}` at `{}`\n", requirement.package, location)),
+                    None => text.push_str(&format!("- `{}`\n", requirement.package)),
+                }
+            }
+            text.push_str(&format!("\n```mermaid\n{}```\n", graph(row)));
+        }
+        text
+    }
+
+    /// Renders the document as a standalone HTML page. Graphs are drawn by
+    /// Mermaid when the page can load it, and show as text otherwise.
+    pub fn html(&self) -> String {
+        let sources: Vec<String> = self.sources.iter().map(|s| format!("<code>{}</code>", escape(s))).collect();
+        let mut text = format!(
+            "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n<style>{}</style>\n</head>\n<body>\n",
+            TITLE, STYLE
+        );
+        text.push_str(&format!("<h1>{}</h1>\n<p><strong>{}</strong> in {}.</p>\n", TITLE, self.verdict(), sources.join(", ")));
+        text.push_str("<h2>Summary</h2>\n<table>\n");
+        for (label, count) in self.summary() {
+            text.push_str(&format!("<tr><th>{}</th><td class=\"count\">{}</td></tr>\n", label, count));
+        }
+        text.push_str("</table>\n<h2>Packages</h2>\n<table>\n");
+        text.push_str("<tr><th>Package</th><th>Status</th><th>Requirements</th><th>Allowed versions</th></tr>\n");
+        for row in &self.rows {
+            let requirements: Vec<String> = row
+                .requirements
+                .iter()
+                .map(|r| match &r.location {
+                    Some(location) => format!("<code>{}</code> ({})", escape(&r.package.to_string()), escape(location)),
+                    None => format!("<code>{}</code>", escape(&r.package.to_string())),
+                })
+                .collect();
+            text.push_str(&format!(
+                "<tr class=\"{}\"><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+                if row.status == Status::Ok { "ok" } else { "failing" },
+                escape(&row.name),
+                row.status.label(),
+                requirements.join("<br>"),
+                escape(&row.allowed)
+            ));
+        }
+        text.push_str("</table>\n");
+        for row in self.failing() {
+            text.push_str(&format!("<h2>{} ({})</h2>\n<ul>\n", escape(&row.name), row.status.label()));
+            for requirement in &row.requirements {
+                let package = escape(&requirement.package.to_string());
+                match &requirement.location {
+                    Some(location) => text.push_str(&format!("<li><code>{}</code> at <code>{}</code></li>\n", package, escape(location))),
+                    None => text.push_str(&format!("<li><code>{}</code></li>\n", package)),
+                }
+            }
+            text.push_str(&format!("</ul>\n<pre class=\"mermaid\">\n{}</pre>\n", escape(&graph(row))));
+        }
+        if self.failing().next().is_some() {
+            text.push_str(MERMAID);
+        }
+        text.push_str("</body>\n</html>\n");
+        text
+    }
+}
+
+const STYLE: &str = "body{font-family:sans-serif;margin:2em;}table{border-collapse:collapse;}\
+th,td{border:1px solid #ccc;padding:.3em .6em;text-align:left;vertical-align:top;}\
+td.count{text-align:right;}tr.failing td{background:#fdecea;}";
+
+const MERMAID: &str = "<script type=\"module\">\
+import mermaid from \"https://cdn.jsdelivr.net/npm/mermaid@11/dist/mermaid.esm.min.mjs\";\
+mermaid.initialize({startOnLoad: true});</script>\n";
+
+fn located(requirement: &RequirementLine) -> Requirement {
+    Requirement {
+        package: requirement.package.clone(),
+        location: Some(requirement.location()),
+        file: Some(requirement.file.display().to_string()),
+    }
+}
+
+/// A Mermaid flowchart from each file to the package, labeled with what the
+/// file requires, every edge in red as none of them can be met together.
+fn graph(row: &Row) -> String {
+    let mut files: Vec<&str> = Vec::new();
+    for requirement in &row.requirements {
+        let file = requirement.file.as_deref().unwrap_or("command line");
+        if !files.contains(&file) {
+            files.push(file);
+        }
+    }
+    let mut text = String::from("graph LR\n");
+    text.push_str(&format!("    p[\"{}\"]\n", mermaid_text(&row.name)));
+    for (i, file) in files.iter().enumerate() {
+        text.push_str(&format!("    f{}[\"{}\"]\n", i, mermaid_text(file)));
+    }
+    for requirement in &row.requirements {
+        let file = requirement.file.as_deref().unwrap_or("command line");
+        let i = files.iter().position(|f| *f == file).unwrap_or_default();
+        text.push_str(&format!("    f{} -->|\"{}\"| p\n", i, mermaid_text(&requirement.package.version_spec())));
+    }
+    let edges: Vec<String> = (0..row.requirements.len()).map(|i| i.to_string()).collect();
+    text.push_str(&format!("    linkStyle {} stroke:red,color:red\n", edges.join(",")));
+    text
+}
+
+/// Table cells end at a `|`, and at a line break.
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_document() -> Result<()> {
+        let lines = [("requirements.txt", 2, "django>=4.0"), ("requirements.txt", 3, "requests"), ("constraints.txt", 5, "Django<3.0")];
+        let requirements: Vec<RequirementLine> = lines
+            .iter()
+            .map(|(file, line, req)| {
+                Ok(RequirementLine {
+                    package: PythonPackage::parse(req)?,
+                    file: PathBuf::from(file),
+                    line: *line,
+                    columns: None,
+                    hashes: Vec::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        let sources = vec!["requirements.txt".to_string(), "constraints.txt".to_string()];
+        let document = Document::report(&sources, &ConflictReport::check(&requirements), None);
+
+        let markdown = document.markdown();
+        assert!(markdown.starts_with("# pyhelper check\n\n**Conflicts detected** in `requirements.txt`, `constraints.txt`.\n"));
+        assert!(markdown.contains("| Conflicts | 1 |\n"));
+        assert!(markdown.contains(
+            "| django | **conflict** | `django >=4.0` (requirements.txt:2)<br>`Django <3.0` (constraints.txt:5) | no version |\n"
+        ));
+        assert!(markdown.contains("| requests | ok | `requests *` (requirements.txt:3) | (-inf, +inf) |\n"));
+        assert!(markdown.contains(
+            "```mermaid\ngraph LR\n    p[\"django\"]\n    f0[\"requirements.txt\"]\n    f1[\"constraints.txt\"]\n    \
+             f0 -->|\"#gt;=4.0\"| p\n    f1 -->|\"#lt;3.0\"| p\n    linkStyle 0,1 stroke:red,color:red\n```\n"
+        ));
+
+        let html = document.html();
+        assert!(html.contains("<tr class=\"failing\"><td>django</td><td>conflict</td><td><code>django &gt;=4.0</code> (requirements.txt:2)"));
+        assert!(html.contains("<pre class=\"mermaid\">"));
+
+        let pkg = PythonPackage::parse("flask<2")?;
+        let pair = Document::pair(&pkg, &PythonPackage::parse("six")?, false).markdown();
+        assert!(pair.contains("**No conflicts detected** in `--pkg1`, `--pkg2`."));
+        assert!(!pair.contains("```mermaid"));
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use std::ops::Range;
+
+use crate::audit::{Finding, Severity};
+use crate::conflict::ConflictReport;
+use crate::package::PythonPackage;
+use crate::pypi::Verification;
+use crate::requirements::RequirementLine;
+
+use super::sarif::{CONFLICT_RULE, UNRELEASED_RULE};
+
+/// Renders a report as GitHub Actions workflow commands, one `::error` per
+/// requirement involved in a conflict, so that the run annotates the
+/// offending lines of a pull request.
+pub fn report(report: &ConflictReport, verified: Option<&[Verification]>) -> String {
+    let mut commands = Vec::new();
+    for conflict in &report.conflicts {
+        commands.extend(line_commands(CONFLICT_RULE, &conflict.name, &conflict.requirements, "conflicts with"));
+    }
+    for verification in verified.into_iter().flatten().filter(|v| v.matching.is_empty()) {
+        let Some(summary) = report.packages.iter().find(|p| p.name == verification.name) else {
+            continue;
+        };
+        commands.extend(line_commands(
+            UNRELEASED_RULE,
+            &verification.name,
+            &summary.requirements,
+            "has no released version in common with",
+        ));
+    }
+    lines(commands)
+}
+
+/// Renders the result of comparing two requirements given on the command
+/// line, as an annotation of the run rather than of a file.
+pub fn pair(pkg1: &PythonPackage, pkg2: &PythonPackage, conflict: bool) -> String {
+    if !conflict {
+        return String::new();
+    }
+    lines(vec![command("error", &[("title", CONFLICT_RULE)], &format!("{} conflicts with {}", pkg1, pkg2))])
+}
+
+/// Renders every advisory affecting a package at the line declaring it.
+/// Advisories below `fail_on` are warnings; those without a severity always
+/// fail, like in the text report.
+pub fn audit(findings: &[Finding], fail_on: Option<Severity>) -> String {
+    let mut commands = Vec::new();
+    for finding in findings {
+        let package = &finding.package;
+        let (file, line) = (path(&package.file.display().to_string()), package.line.to_string());
+        for advisory in &finding.advisories {
+            let level = match (fail_on, advisory.severity) {
+                (Some(threshold), Some(severity)) if severity < threshold => "warning",
+                _ => "error",
+            };
+            let mut message = format!("{} {} is affected by {}", package.name, package.version, advisory.id);
+            if !advisory.aliases.is_empty() {
+                message.push_str(&format!(" ({})", advisory.aliases.join(", ")));
+            }
+            if let Some(severity) = advisory.severity {
+                message.push_str(&format!(", severity {}", severity));
+            }
+            if let Some(summary) = &advisory.summary {
+                message.push_str(&format!(": {}", summary));
+            }
+            match advisory.fixed_after(&package.version) {
+                Some(fixed) => message.push_str(&format!(". Fixed in {}", fixed)),
+                None => message.push_str(". No fixed version published"),
+            }
+            let mut properties = vec![("file", file.clone()), ("line", line.clone())];
+            properties.extend(columns(package.columns.as_ref()));
+            properties.push(("title", advisory.id.clone()));
+            let properties: Vec<(&str, &str)> = properties.iter().map(|(key, value)| (*key, value.as_str())).collect();
+            commands.push(command(level, &properties, &message));
+        }
+    }
+    lines(commands)
+}
+
+fn line_commands(rule: &str, name: &str, requirements: &[RequirementLine], relation: &str) -> Vec<String> {
+    requirements
+        .iter()
+        .enumerate()
+        .map(|(i, requirement)| {
+            let others: Vec<String> = requirements
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, other)| {
+                    let file = path(&other.file.display().to_string());
+                    match &other.columns {
+                        Some(columns) => format!("{} ({}:{}:{})", other.package, file, other.line, columns.start),
+                        None => format!("{} ({}:{})", other.package, file, other.line),
+                    }
+                })
+                .collect();
+            let message = if others.is_empty() {
+                format!("No version of {} satisfies {}", name, requirement.package)
+            } else {
+                format!("{} {} {}", requirement.package, relation, others.join(", "))
+            };
+            let (file, line) = (path(&requirement.file.display().to_string()), requirement.line.to_string());
+            let mut properties = vec![("file", file), ("line", line)];
+            properties.extend(columns(requirement.columns.as_ref()));
+            properties.push(("title", rule.to_string()));
+            let properties: Vec<(&str, &str)> = properties.iter().map(|(key, value)| (*key, value.as_str())).collect();
+            command("error", &properties, &message)
+        })
+        .collect()
+}
+
+/// The `col` and `endColumn` properties of an annotation, when the columns are known.
+fn columns(columns: Option<&Range<usize>>) -> Vec<(&'static str, String)> {
+    columns.map_or(Vec::new(), |columns| vec![("col", columns.start.to_string()), ("endColumn", columns.end.to_string())])
+}
+
+/// A workflow command such as `::error file=requirements.txt,line=3::message`.
+fn command(level: &str, properties: &[(&str, &str)], message: &str) -> String {
+    let properties: Vec<String> = properties.iter().map(|(key, value)| format!("{}={}", key, escape_property(value))).collect();
+    format!("::{} {}::{}", level, properties.join(","), escape_data(message))
+}
+
+fn lines(commands: Vec<String>) -> String {
+    commands.iter().map(|command| format!("{}\n", command)).collect()
+}
+
+/// Annotations resolve paths against the checkout, with forward slashes.
+fn path(file: &str) -> String {
+    let file = file.replace('\\', "/");
+    file.strip_prefix("./").map(str::to_string).unwrap_or(file)
+}
+
+fn escape_data(value: &str) -> String {
+    value.replace('%', "%25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+fn escape_property(value: &str) -> String {
+    escape_data(value).replace(':', "%3A").replace(',', "%2C")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_report() -> Result<()> {
+        let lines = [("./requirements.txt", 2, "django>=4.0"), ("constraints, old.txt", 5, "Django<3.0")];
+        let requirements: Vec<RequirementLine> = lines
+            .iter()
+            .map(|(file, line, req)| {
+                Ok(RequirementLine {
+                    package: PythonPackage::parse(req)?,
+                    file: PathBuf::from(file),
+                    line: *line,
+                    columns: None,
+                    hashes: Vec::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let rendered = report(&ConflictReport::check(&requirements), None);
+        let commands: Vec<&str> = rendered.lines().collect();
+        assert_eq!(
+            commands,
+            vec![
+                "::error file=requirements.txt,line=2,title=version-conflict::django >=4.0 conflicts with Django <3.0 (constraints, old.txt:5)",
+                "::error file=constraints%2C old.txt,line=5,title=version-conflict::Django <3.0 conflicts with django >=4.0 (requirements.txt:2)",
+            ]
+        );
+
+        let pkg = PythonPackage::parse("flask")?;
+        assert!(pair(&pkg, &pkg, false).is_empty());
+        assert_eq!(escape_data("100%\nsure"), "100%25%0Asure");
+        Ok(())
+    }
+}
@@ -0,0 +1,909 @@
+use serde_json::{json, Value};
+use std::ops::{Bound, Range};
+use std::path::{Path, PathBuf};
+
+use crate::audit::Finding;
+use crate::baseline::Baseline;
+use crate::conflict::{example, ConflictReport};
+#[cfg(feature = "network")]
+use crate::confusion::Lookalike;
+use crate::diff::ConstraintDiff;
+use crate::drift::Drift;
+#[cfg(feature = "docker")]
+use crate::docker::Image;
+use crate::env::{EnvIssue, Environment, InstalledDistribution};
+use crate::freeze::{pinned_version, FreezeChange};
+#[cfg(feature = "network")]
+use crate::hashes::{HashCheck, HashIssue};
+use crate::ignore::Acknowledged;
+use crate::license::LicenseEntry;
+use crate::lint::{Cap, Duplicate, PinViolation};
+use crate::lock::{LockIssue, LockReport};
+#[cfg(feature = "network")]
+use crate::maintenance::{Maintenance, Signal};
+use crate::merge;
+#[cfg(feature = "network")]
+use crate::outdated::OutdatedPackage;
+use crate::package::PythonPackage;
+use crate::pep440::Pep440Version;
+use crate::prerelease::PreReleasePolicy;
+use crate::pypi::{Release, Verification};
+use crate::range::{Interval, IntervalSet};
+#[cfg(feature = "network")]
+use crate::resolve::Pin;
+use crate::selftest::Outcome;
+#[cfg(feature = "network")]
+use crate::simulate::Simulation;
+use crate::requirements::RequirementLine;
+use crate::specifier::{as_written, SpecifierSet};
+use crate::sync::SharedPackage;
+use crate::timeline::Staleness;
+use crate::typosquat::{Resemblance, Suspect};
+use crate::workspace::{self, WorkspaceReport};
+
+/// The version of the JSON report format, in the `schema_version` field of
+/// every report. Fields may be added to a version; removing, renaming or
+/// retyping one needs a new version.
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Renders the result of comparing two requirements. `released` holds the
+/// published versions satisfying both when they were checked against an index;
+/// finding none counts as a conflict.
+pub fn pair(
+    pkg1: &PythonPackage,
+    pkg2: &PythonPackage,
+    released: Option<&[Pep440Version]>,
+    policy: PreReleasePolicy,
+) -> Value {
+    let same_package = pkg1.canonical_name() == pkg2.canonical_name();
+    let intersection = pkg1.specifiers.range().intersect(&pkg2.specifiers.range());
+    let unreleased = released.is_some_and(|versions| versions.is_empty());
+    let mut value = json!({
+        "schema_version": SCHEMA_VERSION,
+        "packages": [package(pkg1), package(pkg2)],
+        "same_package": same_package,
+        "conflict": same_package && (pkg1.conflicts_under(pkg2, policy) || unreleased),
+        "prereleases": policy.allows(&[&pkg1.specifiers, &pkg2.specifiers]),
+        "intersection": range(&intersection),
+        "witness": example(&[&pkg1.specifiers, &pkg2.specifiers], policy.allows(&[&pkg1.specifiers, &pkg2.specifiers])),
+    });
+    if let Some(versions) = released {
+        value["released"] = versions_list(versions);
+    }
+    value
+}
+
+/// Renders a report over the requirements gathered from one or more files,
+/// with the index verification of each conflict-free package when available.
+pub fn report(sources: &[String], report: &ConflictReport, verified: Option<&[Verification]>) -> Value {
+    let verification = |name: &str| verified?.iter().find(|v| v.name == name);
+    let packages: Vec<Value> = report
+        .packages
+        .iter()
+        .map(|summary| {
+            let requirements: Vec<Value> = summary
+                .requirements
+                .iter()
+                .map(|r| {
+                    let mut entry = package(&r.package);
+                    entry["file"] = json!(r.file.display().to_string());
+                    entry["line"] = json!(r.line);
+                    entry["column"] = column(&r.columns);
+                    entry["end_column"] = end_column(&r.columns);
+                    entry
+                })
+                .collect();
+            let specifiers: Vec<&SpecifierSet> = summary.requirements.iter().map(|r| &r.package.specifiers).collect();
+            let released = verification(&summary.name);
+            let unreleased = released.is_some_and(|v| v.matching.is_empty());
+            let mut value = json!({
+                "name": summary.name,
+                "conflict": report.conflict_for(&summary.name).is_some() || unreleased,
+                "requirements": requirements,
+                "prereleases": summary.prereleases,
+                "intersection": range(&summary.allowed),
+                "witness": example(&specifiers, summary.prereleases),
+            });
+            if verified.is_some() {
+                value["released"] = released.map_or(Value::Null, |v| versions_list(&v.matching));
+            }
+            value
+        })
+        .collect();
+
+    let conflict = packages.iter().any(|p| p["conflict"] == true);
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "sources": sources,
+        "checked": report.checked,
+        "conflict": conflict,
+        "packages": packages,
+    })
+}
+
+/// Renders the validation of a lock file against its manifest.
+pub fn lock_report(manifest: &Path, lock: &Path, report: &LockReport) -> Value {
+    let issues = lock_issues(&report.issues);
+    let conflicts: Vec<&str> = report.conflicts.conflicts.iter().map(|c| c.name.as_str()).collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "manifest": manifest.display().to_string(),
+        "lock": lock.display().to_string(),
+        "checked": report.checked,
+        "clean": report.is_clean(),
+        "issues": issues,
+        "conflicts": conflicts,
+    })
+}
+
+/// Renders the declared requirements a lock file does not honour.
+fn lock_issues(issues: &[LockIssue]) -> Vec<Value> {
+    issues
+        .iter()
+        .map(|issue| {
+            let requirement = issue.requirement();
+            let mut value = package(&requirement.package);
+            value["file"] = json!(requirement.file.display().to_string());
+            value["line"] = json!(requirement.line);
+            value["column"] = column(&requirement.columns);
+            value["end_column"] = end_column(&requirement.columns);
+            match issue {
+                LockIssue::Stale { locked, .. } => {
+                    value["issue"] = json!("stale");
+                    value["locked"] = locked
+                        .iter()
+                        .map(|pin| {
+                            json!({
+                                "version": pin.version.to_string(),
+                                "file": pin.file.display().to_string(),
+                                "line": pin.line,
+                            })
+                        })
+                        .collect();
+                }
+                LockIssue::Missing { .. } => value["issue"] = json!("missing"),
+            }
+            value
+        })
+        .collect()
+}
+
+/// Renders a baseline, the file `--baseline` reads back.
+pub fn baseline(baseline: &Baseline) -> Value {
+    let conflicts: Vec<Value> = baseline
+        .conflicts
+        .iter()
+        .map(|conflict| json!({"package": conflict.package, "requirements": conflict.requirements}))
+        .collect();
+    let advisories: Vec<Value> = baseline
+        .advisories
+        .iter()
+        .map(|advisory| json!({"package": advisory.package, "id": advisory.id, "aliases": advisory.aliases}))
+        .collect();
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "conflicts": conflicts,
+        "advisories": advisories,
+    })
+}
+
+/// Renders the result of `selftest`, known differences included.
+pub fn selftest(outcome: &Outcome) -> Value {
+    let mismatches: Vec<Value> = outcome
+        .mismatches
+        .iter()
+        .map(|mismatch| {
+            json!({
+                "check": mismatch.check.to_string(),
+                "expected": mismatch.expected.to_string(),
+                "actual": mismatch.actual.to_string(),
+                "known": mismatch.known,
+            })
+        })
+        .collect();
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "packaging": outcome.packaging,
+        "checked": outcome.checked,
+        "mismatches": mismatches,
+    })
+}
+
+/// Renders how every lock file drifted from the manifest it was paired with.
+pub fn drift_report(drifts: &[Drift]) -> Value {
+    let pairs: Vec<Value> = drifts
+        .iter()
+        .map(|drift| {
+            let undeclared = drift.undeclared.as_ref().map(|undeclared| {
+                undeclared
+                    .iter()
+                    .map(|package| {
+                        json!({
+                            "name": package.name,
+                            "version": package.version.to_string(),
+                            "file": package.file.display().to_string(),
+                            "line": package.line,
+                        })
+                    })
+                    .collect::<Vec<Value>>()
+            });
+            json!({
+                "manifest": drift.manifest.display().to_string(),
+                "lock": drift.lock.display().to_string(),
+                "checked": drift.checked,
+                "drifted": drift.has_drift(),
+                "issues": lock_issues(&drift.issues),
+                "undeclared": undeclared,
+            })
+        })
+        .collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "drifted": drifts.iter().any(Drift::has_drift),
+        "pairs": pairs,
+    })
+}
+
+/// Renders the broken requirements found in an installed environment.
+pub fn env_report(environment: &Environment, distributions: &[InstalledDistribution], issues: &[EnvIssue]) -> Value {
+    let site_packages: Vec<String> = environment
+        .site_packages
+        .iter()
+        .map(|p| p.display().to_string())
+        .collect();
+    let issues: Vec<Value> = issues
+        .iter()
+        .map(|issue| {
+            json!({
+                "dependent": issue.dependent,
+                "dependent_version": issue.dependent_version.to_string(),
+                "requirement": package(&issue.requirement),
+                "installed": issue.installed.as_ref().map(|v| v.to_string()),
+            })
+        })
+        .collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "site_packages": site_packages,
+        "checked": distributions.len(),
+        "broken": !issues.is_empty(),
+        "issues": issues,
+    })
+}
+
+#[cfg(feature = "docker")]
+/// Renders the check of a container image: the `env-check` report of its
+/// distributions, and the `audit` report of them unless it was skipped.
+pub fn image_report(image: &Image, issues: &[EnvIssue], audit: Option<Value>) -> Value {
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "image": image.source,
+        "platform": image.platform,
+        "environment": env_report(&image.environment, &image.distributions, issues),
+        "audit": audit,
+    })
+}
+
+/// Renders the differences between two freeze files.
+pub fn freeze_diff(old: &Path, new: &Path, changes: &[FreezeChange], report: &ConflictReport) -> Value {
+    let side = |line: &Option<RequirementLine>| match line {
+        Some(line) => json!({
+            "requirement": line.package.to_string(),
+            "version": pinned_version(line).map(|v| v.to_string()),
+            "file": line.file.display().to_string(),
+            "line": line.line,
+            "column": column(&line.columns),
+            "end_column": end_column(&line.columns),
+        }),
+        None => Value::Null,
+    };
+    let changes: Vec<Value> = changes
+        .iter()
+        .map(|change| {
+            json!({
+                "name": change.name,
+                "kind": change.kind.to_string(),
+                "risky": change.kind.is_risky(),
+                "old": side(&change.old),
+                "new": side(&change.new),
+            })
+        })
+        .collect();
+    let conflicts: Vec<&str> = report.conflicts.iter().map(|c| c.name.as_str()).collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "old": old.display().to_string(),
+        "new": new.display().to_string(),
+        "changes": changes,
+        "conflicts": conflicts,
+    })
+}
+
+/// Renders the problems ignore rules acknowledged, with their justification.
+pub fn acknowledged(acknowledged: &[Acknowledged]) -> Value {
+    let entries: Vec<Value> = acknowledged
+        .iter()
+        .map(|entry| {
+            json!({
+                "package": entry.package,
+                "id": entry.id,
+                "rule": entry.by.to_string(),
+                "reason": entry.by.reason,
+                "expires": entry.by.expires.as_ref().map(|expires| expires.to_string()),
+            })
+        })
+        .collect();
+    json!(entries)
+}
+
+/// Renders a vulnerability audit: the affected packages with their advisories,
+/// and the requirements no version could be picked for.
+pub fn audit_report(source: &Path, audited: usize, findings: &[Finding], skipped: &[RequirementLine]) -> Value {
+    let packages: Vec<Value> = findings
+        .iter()
+        .map(|finding| {
+            let package = &finding.package;
+            let vulnerabilities: Vec<Value> = finding
+                .advisories
+                .iter()
+                .map(|advisory| {
+                    json!({
+                        "id": advisory.id,
+                        "aliases": advisory.aliases,
+                        "summary": advisory.summary,
+                        "severity": advisory.severity.map(|s| s.to_string()),
+                        "score": advisory.score,
+                        "fixed": versions_list(&advisory.fixed),
+                        "fixed_in": advisory.fixed_after(&package.version).map(|v| v.to_string()),
+                    })
+                })
+                .collect();
+            json!({
+                "name": package.name,
+                "version": package.version.to_string(),
+                "file": package.file.display().to_string(),
+                "line": package.line,
+                "column": column(&package.columns),
+                "end_column": end_column(&package.columns),
+                "resolved": package.resolved,
+                "vulnerabilities": vulnerabilities,
+            })
+        })
+        .collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "source": source.display().to_string(),
+        "audited": audited,
+        "vulnerable": !findings.is_empty(),
+        "packages": packages,
+        "skipped": requirement_lines(skipped),
+    })
+}
+
+#[cfg(feature = "network")]
+/// Renders the packages with newer releases on the index.
+pub fn outdated_report(source: &Path, checked: usize, outdated: &[OutdatedPackage], skipped: &[RequirementLine]) -> Value {
+    let packages: Vec<Value> = outdated
+        .iter()
+        .map(|entry| {
+            let package = &entry.package;
+            json!({
+                "name": package.name,
+                "version": package.version.to_string(),
+                "file": package.file.display().to_string(),
+                "line": package.line,
+                "latest": entry.latest.to_string(),
+                "gap": entry.gap.to_string(),
+                "target": entry.target.as_ref().map(|v| v.to_string()),
+                "held_back_by": entry.held_back_by,
+            })
+        })
+        .collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "source": source.display().to_string(),
+        "checked": checked,
+        "outdated": packages,
+        "skipped": requirement_lines(skipped),
+    })
+}
+
+#[cfg(feature = "network")]
+/// Renders what upgrading one pin breaks and moves.
+pub fn simulation(source: &Path, simulation: &Simulation) -> Value {
+    let broken: Vec<Value> = simulation
+        .broken
+        .iter()
+        .map(|broken| {
+            json!({
+                "source": broken.source,
+                "requirement": broken.requirement.to_string(),
+                "excludes": broken.excludes.to_string(),
+            })
+        })
+        .collect();
+    let moves: Vec<Value> = simulation
+        .moves
+        .iter()
+        .map(|moved| {
+            json!({
+                "name": moved.name,
+                "from": moved.from.to_string(),
+                "to": moved.to.as_ref().map(|v| v.to_string()),
+            })
+        })
+        .collect();
+    let added: Vec<Value> = simulation
+        .added
+        .iter()
+        .map(|(by, requirement)| json!({ "requirement": requirement.to_string(), "required_by": by }))
+        .collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "source": source.display().to_string(),
+        "name": simulation.name,
+        "from": simulation.from.as_ref().map(|v| v.to_string()),
+        "to": simulation.to.to_string(),
+        "clean": simulation.is_clean(),
+        "broken": broken,
+        "moves": moves,
+        "added": added,
+    })
+}
+
+#[cfg(feature = "network")]
+/// Renders the maintenance signals of a package.
+pub fn maintenance(report: &Maintenance) -> Value {
+    let signals: Vec<Value> = report
+        .signals
+        .iter()
+        .map(|signal| {
+            let kind = match signal {
+                Signal::NoRecentRelease(_) => "no-recent-release",
+                Signal::EolPythonOnly(_) => "eol-python-only",
+                Signal::DeadUrl { .. } => "dead-url",
+            };
+            json!({ "kind": kind, "message": signal.to_string() })
+        })
+        .collect();
+    json!({
+        "name": report.name,
+        "latest": report.latest.as_ref().map(|v| v.to_string()),
+        "last_upload": report.last_upload,
+        "likely_unmaintained": report.is_likely_unmaintained(),
+        "signals": signals,
+    })
+}
+
+#[cfg(feature = "network")]
+/// Renders the internal packages whose names are taken on the public index.
+pub fn lookalikes(lookalikes: &[Lookalike]) -> Value {
+    lookalikes
+        .iter()
+        .map(|lookalike| {
+            json!({
+                "name": lookalike.name,
+                "pinned": lookalike.pinned.as_ref().map(|v| v.to_string()),
+                "public_latest": lookalike.public_latest.as_ref().map(|v| v.to_string()),
+                "outranks": lookalike.outranks(),
+            })
+        })
+        .collect()
+}
+
+/// Renders the dependency names resembling popular projects, with where
+/// they are declared.
+pub fn typosquats(suspects: &[(PathBuf, usize, Suspect)]) -> Value {
+    suspects
+        .iter()
+        .map(|(file, line, suspect)| {
+            let kind = match suspect.resemblance {
+                Resemblance::Typo(_) => "typo",
+                Resemblance::Affix(_) => "affix",
+                Resemblance::Separators => "separators",
+            };
+            json!({
+                "name": suspect.name,
+                "file": file.display().to_string(),
+                "line": line,
+                "resembles": suspect.resembles,
+                "kind": kind,
+                "message": suspect.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Renders the lint findings of a requirements file: the packages listed
+/// more than once, with every listing, the requirements breaking the pin
+/// policy and the upper caps.
+pub fn lint_report(source: &Path, duplicates: &[Duplicate], violations: &[PinViolation], caps: &[Cap]) -> Value {
+    let duplicates: Vec<Value> = duplicates
+        .iter()
+        .map(|duplicate| {
+            let shadowed: Vec<Value> = duplicate
+                .shadowed
+                .iter()
+                .map(|(wider, narrower)| {
+                    let (wider, narrower) = (&duplicate.lines[*wider], &duplicate.lines[*narrower]);
+                    json!({
+                        "file": wider.file.display().to_string(),
+                        "line": wider.line,
+                        "column": column(&wider.columns),
+                        "by_file": narrower.file.display().to_string(),
+                        "by_line": narrower.line,
+                        "by_column": column(&narrower.columns),
+                    })
+                })
+                .collect();
+            json!({
+                "name": duplicate.name,
+                "verdict": duplicate.verdict.to_string(),
+                "listings": requirement_lines(&duplicate.lines),
+                "shadowed": shadowed,
+                "combined": duplicate.combined.as_ref().map(package),
+            })
+        })
+        .collect();
+
+    let violations: Vec<Value> = violations
+        .iter()
+        .map(|violation| {
+            json!({
+                "name": violation.line.package.name,
+                "requirement": merge::line(&violation.line.package),
+                "file": violation.line.file.display().to_string(),
+                "line": violation.line.line,
+                "column": column(&violation.line.columns),
+                "end_column": end_column(&violation.line.columns),
+                "rule": violation.rule.to_string(),
+                "override": violation.by,
+                "message": violation.to_string(),
+            })
+        })
+        .collect();
+
+    let caps: Vec<Value> = caps
+        .iter()
+        .map(|cap| {
+            json!({
+                "name": cap.line.package.name,
+                "requirement": merge::line(&cap.line.package),
+                "file": cap.line.file.display().to_string(),
+                "line": cap.line.line,
+                "column": column(&cap.line.columns),
+                "end_column": end_column(&cap.line.columns),
+                "clause": cap.clause.to_string(),
+                "message": cap.to_string(),
+            })
+        })
+        .collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "source": source.display().to_string(),
+        "duplicates": duplicates,
+        "pin_violations": violations,
+        "upper_caps": caps,
+    })
+}
+
+/// Renders the release history of a package, newest first, with how far
+/// the pinned version is behind.
+pub fn release_timeline(name: &str, releases: &[Release], pinned: Option<&Pep440Version>, staleness: Option<&Staleness>) -> Value {
+    let history: Vec<Value> = releases
+        .iter()
+        .rev()
+        .map(|release| {
+            json!({
+                "version": release.version.to_string(),
+                "uploaded": release.uploaded,
+                "yanked": release.yanked,
+                "prerelease": release.version.is_prerelease(),
+            })
+        })
+        .collect();
+    let staleness = staleness.map(|stale| {
+        json!({
+            "latest": stale.latest.to_string(),
+            "releases_behind": stale.releases_behind,
+            "days_behind": stale.days_behind,
+        })
+    });
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "name": name,
+        "pinned": pinned.map(|v| v.to_string()),
+        "staleness": staleness,
+        "releases": history,
+    })
+}
+
+/// Renders the constraint changes between two requirement files.
+pub fn constraint_diff(old: &Path, new: &Path, diffs: &[ConstraintDiff]) -> Value {
+    let side = |lines: &[RequirementLine]| -> Value {
+        lines
+            .iter()
+            .map(|line| {
+                json!({
+                    "requirement": line.package.to_string(),
+                    "file": line.file.display().to_string(),
+                    "line": line.line,
+                    "column": column(&line.columns),
+                    "end_column": end_column(&line.columns),
+                })
+            })
+            .collect()
+    };
+    let changes: Vec<Value> = diffs
+        .iter()
+        .map(|diff| {
+            json!({
+                "name": diff.name,
+                "kind": diff.kind.to_string(),
+                "breaking": diff.kind.is_breaking(),
+                "old": side(&diff.old),
+                "new": side(&diff.new),
+                "old_allowed": (!diff.old.is_empty()).then(|| range(&diff.old_allowed())),
+                "new_allowed": (!diff.new.is_empty()).then(|| range(&diff.new_allowed())),
+            })
+        })
+        .collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "old": old.display().to_string(),
+        "new": new.display().to_string(),
+        "changes": changes,
+        "breaking": diffs.iter().any(|diff| diff.kind.is_breaking()),
+    })
+}
+
+/// Renders how the dev requirements on every shared package compare to prod.
+pub fn sync_report(prod: &Path, dev: &Path, shared: &[SharedPackage], strict: bool) -> Value {
+    let packages: Vec<Value> = shared
+        .iter()
+        .map(|package| {
+            json!({
+                "name": package.name,
+                "alignment": package.alignment.to_string(),
+                "flagged": package.alignment.is_flagged(strict),
+                "prod": requirement_lines(&package.prod),
+                "dev": requirement_lines(&package.dev),
+                "prod_allowed": range(&package.prod_allowed()),
+                "dev_allowed": range(&package.dev_allowed()),
+                "outside_prod": range(&package.outside_prod()),
+            })
+        })
+        .collect();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "prod": prod.display().to_string(),
+        "dev": dev.display().to_string(),
+        "strict": strict,
+        "flagged": shared.iter().any(|package| package.alignment.is_flagged(strict)),
+        "packages": packages,
+    })
+}
+
+#[cfg(feature = "network")]
+/// Renders resolved pins with what requires each of them.
+pub fn pins(pins: &[Pin]) -> Value {
+    pins.iter()
+        .map(|pin| json!({ "name": pin.name, "version": pin.version.to_string(), "via": pin.via }))
+        .collect()
+}
+
+#[cfg(feature = "network")]
+/// Renders the hash verification of every requirement.
+pub fn hash_report(source: &Path, checks: &[HashCheck]) -> Value {
+    let requirements: Vec<Value> = checks
+        .iter()
+        .map(|check| {
+            let requirement = &check.requirement;
+            let offending = match &check.issue {
+                Some(HashIssue::Mismatch(hashes) | HashIssue::Unverifiable(hashes)) => hashes.clone(),
+                _ => Vec::new(),
+            };
+            json!({
+                "name": requirement.package.name,
+                "version": check.version.as_ref().map(|v| v.to_string()),
+                "file": requirement.file.display().to_string(),
+                "line": requirement.line,
+                "column": column(&requirement.columns),
+                "end_column": end_column(&requirement.columns),
+                "hashes": requirement.hashes,
+                "matched": check.matched,
+                "issue": check.issue.as_ref().map(|issue| issue.to_string()),
+                "offending": offending,
+            })
+        })
+        .collect();
+    let issues = checks.iter().filter(|check| check.issue.is_some()).count();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "source": source.display().to_string(),
+        "requirements": requirements,
+        "issues": issues,
+        "verified": issues == 0,
+    })
+}
+
+/// Renders the license inventory of a dependency set and its policy verdicts.
+pub fn license_report(source: &Path, policy_file: Option<&Path>, entries: &[LicenseEntry]) -> Value {
+    let packages: Vec<Value> = entries
+        .iter()
+        .map(|entry| {
+            json!({
+                "name": entry.name,
+                "version": entry.version.to_string(),
+                "license": entry.license.as_ref().map(|(license, _)| license),
+                "source": entry.license.as_ref().map(|(_, source)| source.to_string()),
+                "verdict": entry.verdict.to_string(),
+                "violation": entry.violation,
+            })
+        })
+        .collect();
+    let violations = entries.iter().filter(|entry| entry.violation).count();
+
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "source": source.display().to_string(),
+        "policy": policy_file.map(|p| p.display().to_string()),
+        "packages": packages,
+        "violations": violations,
+        "compliant": violations == 0,
+    })
+}
+
+/// Renders a `workspace` report: the members found, the conflicts between
+/// their requirements and the constraints they agree on.
+pub fn workspace_report(root: &Path, report: &WorkspaceReport) -> Value {
+    let members: Vec<Value> = report
+        .members
+        .iter()
+        .map(|member| {
+            json!({
+                "name": member.name,
+                "dir": member.dir.display().to_string(),
+                "requirements": requirement_lines(&member.requirements),
+            })
+        })
+        .collect();
+    let conflicts: Vec<Value> = report
+        .conflicts
+        .iter()
+        .map(|shared| {
+            json!({
+                "name": shared.conflict.name,
+                "members": shared.members,
+                "cross_project": shared.is_cross_project(),
+                "requirements": requirement_lines(&shared.conflict.requirements),
+            })
+        })
+        .collect();
+    json!({
+        "schema_version": SCHEMA_VERSION,
+        "root": root.display().to_string(),
+        "conflict": !report.conflicts.is_empty(),
+        "members": members,
+        "conflicts": conflicts,
+        "unified": report.unified.iter().map(|merged| package(&merged.package)).collect::<Vec<Value>>(),
+        "constraints_txt": workspace::constraints_txt(report, root),
+    })
+}
+
+fn requirement_lines(lines: &[RequirementLine]) -> Value {
+    lines
+        .iter()
+        .map(|line| {
+            let mut value = self::package(&line.package);
+            value["file"] = json!(line.file.display().to_string());
+            value["line"] = json!(line.line);
+            value["column"] = column(&line.columns);
+            value["end_column"] = end_column(&line.columns);
+            value
+        })
+        .collect()
+}
+
+/// The 1-based column a requirement starts at, `null` when the source has none.
+fn column(columns: &Option<Range<usize>>) -> Value {
+    json!(columns.as_ref().map(|columns| columns.start))
+}
+
+/// The column after the last character of a requirement, `null` when the
+/// source has none.
+fn end_column(columns: &Option<Range<usize>>) -> Value {
+    json!(columns.as_ref().map(|columns| columns.end))
+}
+
+fn versions_list(versions: &[Pep440Version]) -> Value {
+    versions.iter().map(|v| v.to_string()).collect()
+}
+
+/// Renders a requirement with the versions its specifiers allow.
+pub fn package(package: &PythonPackage) -> Value {
+    json!({
+        "name": package.name,
+        "canonical_name": package.canonical_name(),
+        "requirement": package.to_string(),
+        "extras": package.extras,
+        "specifiers": package.specifiers.to_string(),
+        "marker": package.marker.as_ref().map(|m| m.to_string()),
+        "url": package.url,
+        "allowed": range(&package.specifiers.range()),
+    })
+}
+
+/// Renders a version range as its intervals. An empty list of intervals is
+/// the proof that no version satisfies the range.
+pub fn range(set: &IntervalSet<Pep440Version>) -> Value {
+    Value::Array(set.intervals.iter().map(interval).collect())
+}
+
+fn interval(interval: &Interval<Pep440Version>) -> Value {
+    let interval = as_written(interval);
+    json!({
+        "lower": bound(&interval.lower),
+        "upper": bound(&interval.upper),
+        "display": interval.to_string(),
+    })
+}
+
+fn bound(bound: &Bound<Pep440Version>) -> Value {
+    match bound {
+        Bound::Included(v) => json!({ "version": v.to_string(), "inclusive": true }),
+        Bound::Excluded(v) => json!({ "version": v.to_string(), "inclusive": false }),
+        Bound::Unbounded => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_pair() -> Result<()> {
+        let pkg1 = PythonPackage::parse("requests>=2.0")?;
+        let pkg2 = PythonPackage::parse("Requests<3,!=2.5")?;
+        let value = pair(&pkg1, &pkg2, None, PreReleasePolicy::Auto);
+        assert_eq!(value["conflict"], json!(false));
+        assert!(value.get("released").is_none());
+        assert_eq!(value["witness"], json!("2.0"));
+        assert_eq!(value["packages"][1]["canonical_name"], json!("requests"));
+        assert_eq!(value["intersection"][0]["display"], json!("[2.0, 2.5)"));
+        assert_eq!(value["intersection"][1]["lower"], json!({"version": "2.5", "inclusive": false}));
+
+        let value = pair(&pkg1, &pkg2, Some(&[]), PreReleasePolicy::Auto);
+        assert_eq!(value["conflict"], json!(true));
+        assert_eq!(value["released"], json!([]));
+
+        let pkg2 = PythonPackage::parse("requests<1.0")?;
+        let value = pair(&pkg1, &pkg2, None, PreReleasePolicy::Auto);
+        assert_eq!(value["conflict"], json!(true));
+        assert_eq!(value["intersection"], json!([]));
+        assert_eq!(value["witness"], Value::Null);
+
+        // Only pre-releases of 2.1 satisfy both
+        let pkg1 = PythonPackage::parse("requests==2.1.*")?;
+        let pkg2 = PythonPackage::parse("requests<2.1rc1")?;
+        assert_eq!(pair(&pkg1, &pkg2, None, PreReleasePolicy::Auto)["conflict"], json!(true));
+        let value = pair(&pkg1, &pkg2, None, PreReleasePolicy::Allow);
+        assert_eq!(value["conflict"], json!(false));
+        assert_eq!(value["prereleases"], json!(true));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,214 @@
+use crate::conflict::ConflictReport;
+use crate::package::PythonPackage;
+use crate::pypi::Verification;
+use crate::requirements::RequirementLine;
+
+use super::sarif::{CONFLICT_RULE, UNRELEASED_RULE};
+
+/// The name of the test suite every rendering reports its cases under.
+const SUITE: &str = "pyhelper check";
+
+/// One `<testcase>`: a package, a pair or an input line.
+struct Case {
+    classname: String,
+    name: String,
+    outcome: Outcome,
+}
+
+enum Outcome {
+    Passed,
+    /// A conflict, reported under the SARIF rule ID it breaks.
+    Failed { rule: &'static str, message: String, details: String },
+    /// An input that could not be checked at all.
+    Errored(String),
+}
+
+/// Renders a report as a JUnit XML document with a test case per package,
+/// named after the package and grouped by the file first requiring it, so
+/// that Jenkins and GitLab list conflicts in their test report. Packages
+/// whose requirements conflict, or overlap on no released version, fail.
+pub fn report(report: &ConflictReport, verified: Option<&[Verification]>) -> String {
+    let cases: Vec<Case> = report
+        .packages
+        .iter()
+        .map(|summary| {
+            let classname = summary
+                .requirements
+                .first()
+                .map_or_else(String::new, |r| r.file.display().to_string());
+            let conflict = report.conflicts.iter().find(|c| c.name == summary.name);
+            let unreleased = verified
+                .into_iter()
+                .flatten()
+                .any(|v| v.name == summary.name && v.matching.is_empty());
+            let outcome = match conflict {
+                Some(conflict) => failure(CONFLICT_RULE, "conflict", &conflict.name, &conflict.requirements),
+                None if unreleased => failure(UNRELEASED_RULE, "no released version", &summary.name, &summary.requirements),
+                None => Outcome::Passed,
+            };
+            Case {
+                classname,
+                name: summary.name.clone(),
+                outcome,
+            }
+        })
+        .collect();
+    suite(&cases)
+}
+
+/// Renders the result of comparing two requirements given on the command
+/// line as a single test case.
+pub fn pair(pkg1: &PythonPackage, pkg2: &PythonPackage, conflict: bool) -> String {
+    let outcome = if conflict {
+        Outcome::Failed {
+            rule: CONFLICT_RULE,
+            message: format!("{} conflicts with {}", pkg1, pkg2),
+            details: format!("{}\n{}\n", pkg1, pkg2),
+        }
+    } else {
+        Outcome::Passed
+    };
+    suite(&[Case {
+        classname: String::new(),
+        name: format!("{} vs {}", pkg1, pkg2),
+        outcome,
+    }])
+}
+
+/// Renders every line of a `--stdin` batch as a test case named after its
+/// line number: lines with a conflict fail, and lines that cannot be parsed
+/// are errors.
+pub fn batch(lines: &[(usize, String, Result<ConflictReport, String>)]) -> String {
+    let cases: Vec<Case> = lines
+        .iter()
+        .map(|(line, input, result)| {
+            let outcome = match result {
+                Ok(report) if report.has_conflicts() => {
+                    let conflicts: Vec<String> = report
+                        .conflicts
+                        .iter()
+                        .map(|conflict| {
+                            let specs: Vec<String> = conflict.requirements.iter().map(|r| r.package.version_spec()).collect();
+                            format!("{} ({})", conflict.name, specs.join(" vs "))
+                        })
+                        .collect();
+                    Outcome::Failed {
+                        rule: CONFLICT_RULE,
+                        message: format!("conflict: {}", conflicts.join(", ")),
+                        details: format!("{}\n", input),
+                    }
+                }
+                Ok(_) => Outcome::Passed,
+                Err(e) => Outcome::Errored(e.clone()),
+            };
+            Case {
+                classname: crate::batch::STDIN_NAME.to_string(),
+                name: format!("line {}", line),
+                outcome,
+            }
+        })
+        .collect();
+    suite(&cases)
+}
+
+/// A failure listing each requirement involved at its location.
+fn failure(rule: &'static str, relation: &str, name: &str, requirements: &[RequirementLine]) -> Outcome {
+    let specs: Vec<String> = requirements.iter().map(|r| r.package.to_string()).collect();
+    Outcome::Failed {
+        rule,
+        message: format!("{} {}: {}", name, relation, specs.join(" vs ")),
+        details: requirements
+            .iter()
+            .map(|r| format!("{}: {}\n", r.location(), r.package))
+            .collect(),
+    }
+}
+
+fn suite(cases: &[Case]) -> String {
+    let failures = cases.iter().filter(|c| matches!(c.outcome, Outcome::Failed { .. })).count();
+    let errors = cases.iter().filter(|c| matches!(c.outcome, Outcome::Errored(_))).count();
+    let counts = format!(r#"name="{}" tests="{}" failures="{}" errors="{}""#, SUITE, cases.len(), failures, errors);
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<testsuites {}>\n", counts));
+    xml.push_str(&format!("  <testsuite {}>\n", counts));
+    for case in cases {
+        let attributes = format!(r#"classname="{}" name="{}""#, escape(&case.classname), escape(&case.name));
+        match &case.outcome {
+            Outcome::Passed => xml.push_str(&format!("    <testcase {}/>\n", attributes)),
+            Outcome::Failed { rule, message, details } => {
+                xml.push_str(&format!("    <testcase {}>\n", attributes));
+                xml.push_str(&format!(
+                    "      <failure type=\"{}\" message=\"{}\">{}</failure>\n",
+                    rule,
+                    escape(message),
+                    escape(details)
+                ));
+                xml.push_str("    </testcase>\n");
+            }
+            Outcome::Errored(message) => {
+                xml.push_str(&format!("    <testcase {}>\n", attributes));
+                xml.push_str(&format!("      <error message=\"{}\"/>\n", escape(message)));
+                xml.push_str("    </testcase>\n");
+            }
+        }
+    }
+    xml.push_str("  </testsuite>\n</testsuites>\n");
+    xml
+}
+
+/// Escapes text for an attribute or element, dropping the control
+/// characters XML 1.0 cannot represent.
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_report() -> Result<()> {
+        let lines = [(2, "django>=4.0"), (3, "requests"), (5, "Django<3.0")];
+        let requirements: Vec<RequirementLine> = lines
+            .iter()
+            .map(|(line, req)| {
+                Ok(RequirementLine {
+                    package: PythonPackage::parse(req)?,
+                    file: PathBuf::from("requirements.txt"),
+                    line: *line,
+                    columns: None,
+                    hashes: Vec::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let rendered = report(&ConflictReport::check(&requirements), None);
+        assert!(rendered.contains(r#"<testsuite name="pyhelper check" tests="2" failures="1" errors="0">"#));
+        assert!(rendered.contains(r#"<testcase classname="requirements.txt" name="requests"/>"#));
+        assert!(rendered.contains(
+            "<failure type=\"version-conflict\" message=\"django conflict: django &gt;=4.0 vs Django &lt;3.0\">\
+             requirements.txt:2: django &gt;=4.0\nrequirements.txt:5: Django &lt;3.0\n</failure>"
+        ));
+
+        let lines = [(1, "six".to_string(), Ok(ConflictReport::default())), (2, "a>".to_string(), Err("bad \"a\u{1}\"".to_string()))];
+        let rendered = batch(&lines);
+        assert!(rendered.contains(r#"tests="2" failures="0" errors="1""#));
+        assert!(rendered.contains(r#"<error message="bad &quot;a&quot;"/>"#));
+        Ok(())
+    }
+}
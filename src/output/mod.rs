@@ -0,0 +1,43 @@
+//! Machine-readable renderings of check results (JSON, SARIF, GitHub Actions
+//! annotations and JUnit XML), Markdown and HTML reports of them, CSV tables of
+//! audits, licenses and outdated packages, software bills of materials, and
+//! text and diagram renderings of dependency graphs.
+
+pub mod csv;
+pub mod diagram;
+pub mod document;
+pub mod github;
+pub mod json;
+pub mod junit;
+pub mod sarif;
+pub mod sbom;
+pub mod schema;
+pub mod tree;
+
+use std::io::{self, Write};
+
+/// Writes `report` to `out` and flushes it. A reader closing the pipe early,
+/// as `| head` does, ends the report without an error.
+pub fn write_report(out: &mut impl Write, report: &str) -> io::Result<()> {
+    match out.write_all(report.as_bytes()).and_then(|_| out.flush()) {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => Ok(()),
+        written => written,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_report() -> io::Result<()> {
+        let mut out = Vec::new();
+        write_report(&mut out, "{}\n")?;
+        assert_eq!(out, b"{}\n");
+
+        let (reader, mut writer) = io::pipe()?;
+        drop(reader);
+        assert_eq!(writer.write_all(b"{}").unwrap_err().kind(), io::ErrorKind::BrokenPipe);
+        write_report(&mut writer, "{}\n")
+    }
+}
@@ -0,0 +1,187 @@
+use serde_json::{json, Value};
+
+use crate::conflict::ConflictReport;
+use crate::package::PythonPackage;
+use crate::pypi::Verification;
+use crate::requirements::RequirementLine;
+
+const SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Requirements on a package that no version satisfies together.
+pub const CONFLICT_RULE: &str = "version-conflict";
+/// Requirements that overlap, but not on any published release.
+pub const UNRELEASED_RULE: &str = "no-matching-release";
+
+/// Renders a report as a SARIF 2.1.0 log for code scanning. Every requirement
+/// involved in a conflict is a result at its file and line, related to the
+/// other requirements it conflicts with.
+pub fn report(report: &ConflictReport, verified: Option<&[Verification]>) -> Value {
+    let mut results = Vec::new();
+    for conflict in &report.conflicts {
+        results.extend(line_results(CONFLICT_RULE, &conflict.name, &conflict.requirements, "conflicts with"));
+    }
+    for verification in verified.into_iter().flatten().filter(|v| v.matching.is_empty()) {
+        let Some(summary) = report.packages.iter().find(|p| p.name == verification.name) else {
+            continue;
+        };
+        results.extend(line_results(
+            UNRELEASED_RULE,
+            &verification.name,
+            &summary.requirements,
+            "has no released version in common with",
+        ));
+    }
+    log(results)
+}
+
+/// Renders the result of comparing two requirements given on the command line,
+/// which have no location to point at.
+pub fn pair(pkg1: &PythonPackage, pkg2: &PythonPackage, conflict: bool) -> Value {
+    let mut results = Vec::new();
+    if conflict {
+        results.push(json!({
+            "ruleId": CONFLICT_RULE,
+            "level": "error",
+            "message": {"text": format!("`{}` conflicts with `{}`", pkg1, pkg2)},
+        }));
+    }
+    log(results)
+}
+
+fn line_results(rule: &str, name: &str, requirements: &[RequirementLine], relation: &str) -> Vec<Value> {
+    requirements
+        .iter()
+        .enumerate()
+        .map(|(i, requirement)| {
+            let others: Vec<(usize, &RequirementLine)> =
+                requirements.iter().enumerate().filter(|(j, _)| *j != i).collect();
+            let described: Vec<String> = others
+                .iter()
+                .map(|(_, other)| format!("`{}` ({})", other.package, position(other)))
+                .collect();
+            let text = if described.is_empty() {
+                format!("No version of {} satisfies `{}`", name, requirement.package)
+            } else {
+                format!("`{}` {} {}", requirement.package, relation, described.join(", "))
+            };
+            let related: Vec<Value> = others
+                .iter()
+                .map(|(j, other)| {
+                    let mut location = location(other);
+                    location["id"] = json!(j);
+                    location["message"] = json!({"text": other.package.to_string()});
+                    location
+                })
+                .collect();
+            json!({
+                "ruleId": rule,
+                "level": "error",
+                "message": {"text": text},
+                "locations": [location(requirement)],
+                "relatedLocations": related,
+                "partialFingerprints": {"package/v1": name},
+            })
+        })
+        .collect()
+}
+
+fn location(requirement: &RequirementLine) -> Value {
+    let mut region = json!({"startLine": requirement.line});
+    if let Some(columns) = &requirement.columns {
+        region["startColumn"] = json!(columns.start);
+        region["endColumn"] = json!(columns.end);
+    }
+    json!({
+        "physicalLocation": {
+            "artifactLocation": {"uri": uri(requirement)},
+            "region": region,
+        }
+    })
+}
+
+/// `uri:line:column`, or `uri:line` without columns.
+fn position(requirement: &RequirementLine) -> String {
+    match &requirement.columns {
+        Some(columns) => format!("{}:{}:{}", uri(requirement), requirement.line, columns.start),
+        None => format!("{}:{}", uri(requirement), requirement.line),
+    }
+}
+
+/// SARIF URIs use forward slashes; relative paths resolve against the checkout.
+fn uri(requirement: &RequirementLine) -> String {
+    let path = requirement.file.display().to_string().replace('\\', "/");
+    path.strip_prefix("./").map(str::to_string).unwrap_or(path)
+}
+
+fn log(results: Vec<Value>) -> Value {
+    json!({
+        "$schema": SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "pyhelper",
+                    "version": env!("CARGO_PKG_VERSION"),
+                    "rules": [
+                        {
+                            "id": CONFLICT_RULE,
+                            "shortDescription": {"text": "Conflicting version requirements"},
+                            "fullDescription": {"text": "No version of the package satisfies all of its requirements."},
+                            "defaultConfiguration": {"level": "error"},
+                        },
+                        {
+                            "id": UNRELEASED_RULE,
+                            "shortDescription": {"text": "No matching release"},
+                            "fullDescription": {"text": "No published, non-yanked release satisfies all of the package's requirements."},
+                            "defaultConfiguration": {"level": "error"},
+                        },
+                    ],
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use std::path::PathBuf;
+
+    #[test]
+    fn test_report() -> Result<()> {
+        let lines = [("./requirements.txt", 2, "django>=4.0"), ("constraints.txt", 5, "Django<3.0")];
+        let requirements: Vec<RequirementLine> = lines
+            .iter()
+            .map(|(file, line, req)| {
+                Ok(RequirementLine {
+                    package: PythonPackage::parse(req)?,
+                    file: PathBuf::from(file),
+                    line: *line,
+                    columns: None,
+                    hashes: Vec::new(),
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        let value = report(&ConflictReport::check(&requirements), None);
+        assert_eq!(value["version"], json!("2.1.0"));
+        let results = value["runs"][0]["results"].as_array().unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0]["ruleId"], json!(CONFLICT_RULE));
+        assert_eq!(
+            results[0]["message"]["text"],
+            json!("`django >=4.0` conflicts with `Django <3.0` (constraints.txt:5)")
+        );
+        let physical = &results[0]["locations"][0]["physicalLocation"];
+        assert_eq!(physical["artifactLocation"]["uri"], json!("requirements.txt"));
+        assert_eq!(physical["region"]["startLine"], json!(2));
+        assert_eq!(results[1]["relatedLocations"][0]["id"], json!(0));
+
+        let pkg = PythonPackage::parse("flask")?;
+        assert!(pair(&pkg, &pkg, false)["runs"][0]["results"].as_array().unwrap().is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,264 @@
+use serde_json::{json, Value};
+
+use crate::license::Expression;
+use crate::package::canonicalize_name;
+use crate::pep440::Pep440Version;
+
+/// A package in a software bill of materials.
+#[derive(Debug, Clone)]
+pub struct Component {
+    pub name: String,
+    pub version: Pep440Version,
+    /// `<algorithm>:<digest>` hashes of the package's files.
+    pub hashes: Vec<String>,
+    /// The license as an SPDX expression, or a license name when it is not one.
+    pub license: Option<String>,
+    /// Indexes of the components this one depends on.
+    pub dependencies: Vec<usize>,
+}
+
+impl Component {
+    /// The package URL, e.g. `pkg:pypi/django@4.2.1`.
+    pub fn purl(&self) -> String {
+        // Epochs and local versions need their `!` and `+` percent-encoded
+        let version = self.version.to_string().replace('!', "%21").replace('+', "%2B");
+        format!("pkg:pypi/{}@{}", canonicalize_name(&self.name), version)
+    }
+}
+
+/// Renders a CycloneDX 1.5 JSON document. `created` is a Unix timestamp.
+pub fn cyclonedx(name: &str, components: &[Component], created: u64) -> Value {
+    let entries: Vec<Value> = components
+        .iter()
+        .map(|component| {
+            let hashes: Vec<Value> = component
+                .hashes
+                .iter()
+                .filter_map(|hash| {
+                    let (algorithm, digest) = hash.split_once(':')?;
+                    let algorithm = match algorithm {
+                        "sha256" => "SHA-256",
+                        "sha384" => "SHA-384",
+                        "sha512" => "SHA-512",
+                        _ => return None,
+                    };
+                    Some(json!({"alg": algorithm, "content": digest}))
+                })
+                .collect();
+            let licenses = match &component.license {
+                Some(license) if is_spdx_expression(license) => json!([{"expression": license}]),
+                Some(license) => json!([{"license": {"name": license}}]),
+                None => json!([]),
+            };
+            json!({
+                "type": "library",
+                "bom-ref": component.purl(),
+                "name": component.name,
+                "version": component.version.to_string(),
+                "purl": component.purl(),
+                "hashes": hashes,
+                "licenses": licenses,
+            })
+        })
+        .collect();
+    let dependencies: Vec<Value> = components
+        .iter()
+        .map(|component| {
+            let depends_on: Vec<String> = component.dependencies.iter().map(|&i| components[i].purl()).collect();
+            json!({"ref": component.purl(), "dependsOn": depends_on})
+        })
+        .collect();
+
+    json!({
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "version": 1,
+        "metadata": {
+            "timestamp": timestamp(created),
+            "tools": {
+                "components": [{"type": "application", "name": "pyhelper", "version": env!("CARGO_PKG_VERSION")}],
+            },
+            "component": {"type": "application", "bom-ref": name, "name": name},
+        },
+        "components": entries,
+        "dependencies": dependencies,
+    })
+}
+
+/// Renders an SPDX 2.3 JSON document. `created` is a Unix timestamp.
+pub fn spdx(name: &str, components: &[Component], created: u64) -> Value {
+    let ids: Vec<String> = components
+        .iter()
+        .map(|component| spdx_id(&format!("Package-{}-{}", component.name, component.version)))
+        .collect();
+    let packages: Vec<Value> = components
+        .iter()
+        .zip(&ids)
+        .map(|(component, id)| {
+            let checksums: Vec<Value> = component
+                .hashes
+                .iter()
+                .filter_map(|hash| {
+                    let (algorithm, digest) = hash.split_once(':')?;
+                    let algorithm = match algorithm {
+                        "sha256" => "SHA256",
+                        "sha384" => "SHA384",
+                        "sha512" => "SHA512",
+                        _ => return None,
+                    };
+                    Some(json!({"algorithm": algorithm, "checksumValue": digest}))
+                })
+                .collect();
+            let declared = component
+                .license
+                .as_deref()
+                .filter(|license| is_spdx_expression(license))
+                .unwrap_or("NOASSERTION");
+            json!({
+                "SPDXID": id,
+                "name": component.name,
+                "versionInfo": component.version.to_string(),
+                "downloadLocation": "NOASSERTION",
+                "filesAnalyzed": false,
+                "licenseConcluded": "NOASSERTION",
+                "licenseDeclared": declared,
+                "checksums": checksums,
+                "externalRefs": [{
+                    "referenceCategory": "PACKAGE-MANAGER",
+                    "referenceType": "purl",
+                    "referenceLocator": component.purl(),
+                }],
+            })
+        })
+        .collect();
+    let mut relationships: Vec<Value> = ids
+        .iter()
+        .map(|id| json!({"spdxElementId": "SPDXRef-DOCUMENT", "relationshipType": "DESCRIBES", "relatedSpdxElement": id}))
+        .collect();
+    for (component, id) in components.iter().zip(&ids) {
+        for &dependency in &component.dependencies {
+            relationships.push(json!({
+                "spdxElementId": id,
+                "relationshipType": "DEPENDS_ON",
+                "relatedSpdxElement": ids[dependency],
+            }));
+        }
+    }
+
+    json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!("urn:pyhelper:spdx:{}:{}", sanitize(name), created),
+        "creationInfo": {
+            "created": timestamp(created),
+            "creators": [format!("Tool: pyhelper-{}", env!("CARGO_PKG_VERSION"))],
+        },
+        "packages": packages,
+        "relationships": relationships,
+    })
+}
+
+/// Whether a license parses as an expression of SPDX-style identifiers, as
+/// opposed to a free-form name such as `BSD License`.
+fn is_spdx_expression(license: &str) -> bool {
+    fn identifiers(expression: &Expression) -> bool {
+        match expression {
+            Expression::License(license) => license
+                .split(" WITH ")
+                .all(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || "-.+".contains(c))),
+            Expression::And(terms) | Expression::Or(terms) => terms.iter().all(identifiers),
+        }
+    }
+    Expression::parse(license).is_ok_and(|expression| identifiers(&expression))
+}
+
+fn spdx_id(name: &str) -> String {
+    format!("SPDXRef-{}", sanitize(name))
+}
+
+/// SPDX identifiers may only hold letters, digits, `.` and `-`.
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' })
+        .collect()
+}
+
+/// Formats a Unix timestamp as RFC 3339 in UTC, e.g. `2023-11-14T22:13:20Z`.
+pub(crate) fn timestamp(seconds: u64) -> String {
+    // Days since the epoch to a civil date, after Howard Hinnant's algorithm
+    let days = (seconds / 86_400) as i64 + 719_468;
+    let era = days.div_euclid(146_097);
+    let day_of_era = days.rem_euclid(146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let shifted_month = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * shifted_month + 2) / 5 + 1;
+    let month = if shifted_month < 10 { shifted_month + 3 } else { shifted_month - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    let time = seconds % 86_400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn components() -> Vec<Component> {
+        vec![
+            Component {
+                name: "Requests".to_string(),
+                version: Pep440Version::parse("2.31.0").unwrap(),
+                hashes: vec!["sha256:58cd".to_string(), "md5:0f".to_string()],
+                license: Some("Apache-2.0".to_string()),
+                dependencies: vec![1],
+            },
+            Component {
+                name: "zope.interface".to_string(),
+                version: Pep440Version::parse("1!6.0+local").unwrap(),
+                hashes: Vec::new(),
+                license: Some("BSD License".to_string()),
+                dependencies: Vec::new(),
+            },
+        ]
+    }
+
+    #[test]
+    fn test_cyclonedx() {
+        let components = components();
+        assert_eq!(components[1].purl(), "pkg:pypi/zope-interface@1%216.0%2Blocal");
+
+        let bom = cyclonedx("requirements.txt", &components, 1_700_000_000);
+        assert_eq!(bom["metadata"]["timestamp"], json!("2023-11-14T22:13:20Z"));
+        let requests = &bom["components"][0];
+        assert_eq!(requests["purl"], json!("pkg:pypi/requests@2.31.0"));
+        assert_eq!(requests["hashes"], json!([{"alg": "SHA-256", "content": "58cd"}]));
+        assert_eq!(requests["licenses"], json!([{"expression": "Apache-2.0"}]));
+        assert_eq!(bom["components"][1]["licenses"], json!([{"license": {"name": "BSD License"}}]));
+        assert_eq!(bom["dependencies"][0]["dependsOn"], json!(["pkg:pypi/zope-interface@1%216.0%2Blocal"]));
+    }
+
+    #[test]
+    fn test_spdx() {
+        let document = spdx("requirements.txt", &components(), 0);
+        assert_eq!(document["creationInfo"]["created"], json!("1970-01-01T00:00:00Z"));
+        assert_eq!(document["documentNamespace"], json!("urn:pyhelper:spdx:requirements.txt:0"));
+        let packages = document["packages"].as_array().unwrap();
+        assert_eq!(packages[0]["SPDXID"], json!("SPDXRef-Package-Requests-2.31.0"));
+        assert_eq!(packages[0]["checksums"], json!([{"algorithm": "SHA256", "checksumValue": "58cd"}]));
+        assert_eq!(packages[1]["SPDXID"], json!("SPDXRef-Package-zope.interface-1-6.0-local"));
+        assert_eq!(packages[1]["licenseDeclared"], json!("NOASSERTION"));
+        let relationships = document["relationships"].as_array().unwrap();
+        assert_eq!(relationships.len(), 3);
+        assert_eq!(relationships[2]["relationshipType"], json!("DEPENDS_ON"));
+    }
+}
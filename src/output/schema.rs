@@ -0,0 +1,694 @@
+use serde_json::{json, Map, Value};
+
+use crate::output::json::SCHEMA_VERSION;
+
+/// The JSON Schema (draft 2020-12) of the JSON reports of version
+/// [`SCHEMA_VERSION`]. Every report is one of the definitions, named after
+/// the command printing it. Objects may gain properties within a version, so
+/// none forbids additional properties.
+pub fn document() -> Value {
+    let mut defs = Map::new();
+    for (name, schema) in common().into_iter().chain(reports()) {
+        defs.insert(name.to_string(), schema);
+    }
+    let reports: Vec<Value> = reports().into_iter().map(|(name, _)| reference(name)).collect();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "pyhelper JSON reports",
+        "description": format!("The JSON output of pyhelper, schema version {}", SCHEMA_VERSION),
+        "anyOf": reports,
+        "$defs": defs,
+    })
+}
+
+/// The definitions shared by the reports.
+fn common() -> Vec<(&'static str, Value)> {
+    let location = [
+        ("file", kind("string")),
+        ("line", kind("integer")),
+        ("column", nullable("integer")),
+        ("end_column", nullable("integer")),
+    ];
+    let package = [
+        ("name", kind("string")),
+        ("canonical_name", kind("string")),
+        ("requirement", kind("string")),
+        ("extras", array(kind("string"))),
+        ("specifiers", kind("string")),
+        ("marker", nullable("string")),
+        ("url", nullable("string")),
+        ("allowed", reference("range")),
+    ];
+    vec![
+        ("bound", json!({
+            "description": "A bound of an interval, null when unbounded",
+            "anyOf": [object(&[("version", kind("string")), ("inclusive", kind("boolean"))], &[]), kind("null")],
+        })),
+        ("range", with_description(
+            array(object(&[("lower", reference("bound")), ("upper", reference("bound")), ("display", kind("string"))], &[])),
+            "The versions allowed, as intervals; none means no version",
+        )),
+        ("package", object(&package, &[])),
+        ("requirement_line", object(&[&package[..], &location[..]].concat(), &[])),
+        ("location", object(&location, &[])),
+        ("lock_issue", all_of(
+            reference("requirement_line"),
+            object(
+                &[("issue", json!({ "enum": ["stale", "missing"] }))],
+                &[("locked", array(object(
+                    &[("version", kind("string")), ("file", kind("string")), ("line", kind("integer"))],
+                    &[],
+                )))],
+            ),
+        )),
+        ("acknowledged", array(object(
+            &[
+                ("package", kind("string")),
+                ("id", kind("string")),
+                ("rule", kind("string")),
+                ("reason", nullable("string")),
+                ("expires", nullable("string")),
+            ],
+            &[],
+        ))),
+        ("maintenance", object(
+            &[
+                ("name", kind("string")),
+                ("latest", nullable("string")),
+                ("last_upload", nullable("string")),
+                ("likely_unmaintained", kind("boolean")),
+                ("signals", array(object(&[("kind", kind("string")), ("message", kind("string"))], &[]))),
+            ],
+            &[],
+        )),
+    ]
+}
+
+/// The reports, by the command printing them.
+fn reports() -> Vec<(&'static str, Value)> {
+    let sides = |side: Value| [("old", side.clone()), ("new", side)];
+    let pinned = |extra: &[(&'static str, Value)]| {
+        let mut properties = vec![
+            ("name", kind("string")),
+            ("version", kind("string")),
+            ("file", kind("string")),
+            ("line", kind("integer")),
+        ];
+        properties.extend(extra.iter().cloned());
+        object(&properties, &[])
+    };
+    vec![
+        ("check-pair", report(
+            &[
+                ("packages", array(reference("package"))),
+                ("same_package", kind("boolean")),
+                ("conflict", kind("boolean")),
+                ("prereleases", kind("boolean")),
+                ("intersection", reference("range")),
+                ("witness", nullable("string")),
+            ],
+            &[("released", array(kind("string")))],
+        )),
+        ("check", report(
+            &[
+                ("sources", array(kind("string"))),
+                ("checked", kind("integer")),
+                ("conflict", kind("boolean")),
+                ("packages", array(object(
+                    &[
+                        ("name", kind("string")),
+                        ("conflict", kind("boolean")),
+                        ("requirements", array(reference("requirement_line"))),
+                        ("prereleases", kind("boolean")),
+                        ("intersection", reference("range")),
+                        ("witness", nullable("string")),
+                    ],
+                    &[("released", json!({ "type": ["array", "null"], "items": kind("string") }))],
+                ))),
+            ],
+            &[("ignored", reference("acknowledged")), ("line", kind("integer")), ("input", kind("string"))],
+        )),
+        ("check-error", report(&[("line", kind("integer")), ("input", kind("string")), ("error", kind("string"))], &[])),
+        ("lock-check", report(
+            &[
+                ("manifest", kind("string")),
+                ("lock", kind("string")),
+                ("checked", kind("integer")),
+                ("clean", kind("boolean")),
+                ("issues", array(reference("lock_issue"))),
+                ("conflicts", array(kind("string"))),
+            ],
+            &[],
+        )),
+        ("drift", report(
+            &[
+                ("drifted", kind("boolean")),
+                ("pairs", array(object(
+                    &[
+                        ("manifest", kind("string")),
+                        ("lock", kind("string")),
+                        ("checked", kind("integer")),
+                        ("drifted", kind("boolean")),
+                        ("issues", array(reference("lock_issue"))),
+                        ("undeclared", json!({
+                            "description": "Locked packages nothing declared pulls in, null when the lock file does not record dependencies",
+                            "anyOf": [
+                                array(object(
+                                    &[
+                                        ("name", kind("string")),
+                                        ("version", kind("string")),
+                                        ("file", kind("string")),
+                                        ("line", kind("integer")),
+                                    ],
+                                    &[],
+                                )),
+                                kind("null"),
+                            ],
+                        })),
+                    ],
+                    &[],
+                ))),
+            ],
+            &[],
+        )),
+        ("env-check", report(
+            &[
+                ("site_packages", array(kind("string"))),
+                ("checked", kind("integer")),
+                ("broken", kind("boolean")),
+                ("issues", array(object(
+                    &[
+                        ("dependent", kind("string")),
+                        ("dependent_version", kind("string")),
+                        ("requirement", reference("package")),
+                        ("installed", nullable("string")),
+                    ],
+                    &[],
+                ))),
+            ],
+            &[],
+        )),
+        ("freeze-diff", report(
+            &[
+                ("old", kind("string")),
+                ("new", kind("string")),
+                ("changes", array(object(
+                    &[
+                        &[("name", kind("string")), ("kind", kind("string")), ("risky", kind("boolean"))][..],
+                        &sides(json!({
+                            "anyOf": [
+                                all_of(
+                                    reference("location"),
+                                    object(&[("requirement", kind("string")), ("version", nullable("string"))], &[]),
+                                ),
+                                kind("null"),
+                            ],
+                        })),
+                    ]
+                    .concat(),
+                    &[],
+                ))),
+                ("conflicts", array(kind("string"))),
+            ],
+            &[],
+        )),
+        ("audit", report(
+            &[
+                ("source", kind("string")),
+                ("audited", kind("integer")),
+                ("vulnerable", kind("boolean")),
+                ("packages", array(object(
+                    &[
+                        ("name", kind("string")),
+                        ("version", kind("string")),
+                        ("file", kind("string")),
+                        ("line", kind("integer")),
+                        ("column", nullable("integer")),
+                        ("end_column", nullable("integer")),
+                        ("resolved", kind("boolean")),
+                        ("vulnerabilities", array(object(
+                            &[
+                                ("id", kind("string")),
+                                ("aliases", array(kind("string"))),
+                                ("summary", nullable("string")),
+                                ("severity", json!({ "enum": ["LOW", "MEDIUM", "HIGH", "CRITICAL", null] })),
+                                ("score", nullable("number")),
+                                ("fixed", array(kind("string"))),
+                                ("fixed_in", nullable("string")),
+                            ],
+                            &[],
+                        ))),
+                    ],
+                    &[],
+                ))),
+                ("skipped", array(reference("requirement_line"))),
+            ],
+            &[
+                ("ignored", reference("acknowledged")),
+                ("unmaintained", array(reference("maintenance"))),
+                ("typosquats", array(object(
+                    &[
+                        ("name", kind("string")),
+                        ("file", kind("string")),
+                        ("line", kind("integer")),
+                        ("resembles", kind("string")),
+                        ("kind", json!({ "enum": ["typo", "affix", "separators"] })),
+                        ("message", kind("string")),
+                    ],
+                    &[],
+                ))),
+                ("public_lookalikes", array(object(
+                    &[
+                        ("name", kind("string")),
+                        ("pinned", nullable("string")),
+                        ("public_latest", nullable("string")),
+                        ("outranks", kind("boolean")),
+                    ],
+                    &[],
+                ))),
+            ],
+        )),
+        ("docker", report(
+            &[
+                ("image", kind("string")),
+                ("platform", kind("string")),
+                ("environment", reference("env-check")),
+                ("audit", json!({ "anyOf": [reference("audit"), kind("null")] })),
+            ],
+            &[],
+        )),
+        ("licenses", report(
+            &[
+                ("source", kind("string")),
+                ("policy", nullable("string")),
+                ("packages", array(object(
+                    &[
+                        ("name", kind("string")),
+                        ("version", kind("string")),
+                        ("license", nullable("string")),
+                        ("source", nullable("string")),
+                        ("verdict", kind("string")),
+                        ("violation", kind("boolean")),
+                    ],
+                    &[],
+                ))),
+                ("violations", kind("integer")),
+                ("compliant", kind("boolean")),
+            ],
+            &[],
+        )),
+        ("outdated", report(
+            &[
+                ("source", kind("string")),
+                ("checked", kind("integer")),
+                ("outdated", array(pinned(&[
+                    ("latest", kind("string")),
+                    ("gap", kind("string")),
+                    ("target", nullable("string")),
+                    ("held_back_by", array(kind("string"))),
+                ]))),
+                ("skipped", array(reference("requirement_line"))),
+            ],
+            &[],
+        )),
+        ("info", report(
+            &[
+                ("name", kind("string")),
+                ("pinned", nullable("string")),
+                ("staleness", json!({
+                    "anyOf": [
+                        object(
+                            &[
+                                ("latest", kind("string")),
+                                ("releases_behind", kind("integer")),
+                                ("days_behind", nullable("integer")),
+                            ],
+                            &[],
+                        ),
+                        kind("null"),
+                    ],
+                })),
+                ("releases", array(object(
+                    &[
+                        ("version", kind("string")),
+                        ("uploaded", nullable("string")),
+                        ("yanked", kind("boolean")),
+                        ("prerelease", kind("boolean")),
+                    ],
+                    &[],
+                ))),
+            ],
+            &[("maintenance", reference("maintenance"))],
+        )),
+        ("simulate", report(
+            &[
+                ("source", kind("string")),
+                ("name", kind("string")),
+                ("from", nullable("string")),
+                ("to", kind("string")),
+                ("clean", kind("boolean")),
+                ("broken", array(object(
+                    &[("source", kind("string")), ("requirement", kind("string")), ("excludes", kind("string"))],
+                    &[],
+                ))),
+                ("moves", array(object(
+                    &[("name", kind("string")), ("from", kind("string")), ("to", nullable("string"))],
+                    &[],
+                ))),
+                ("added", array(object(&[("requirement", kind("string")), ("required_by", kind("string"))], &[]))),
+            ],
+            &[],
+        )),
+        ("verify-hashes", report(
+            &[
+                ("source", kind("string")),
+                ("requirements", array(object(
+                    &[
+                        ("name", kind("string")),
+                        ("version", nullable("string")),
+                        ("file", kind("string")),
+                        ("line", kind("integer")),
+                        ("column", nullable("integer")),
+                        ("end_column", nullable("integer")),
+                        ("hashes", array(kind("string"))),
+                        ("matched", kind("integer")),
+                        ("issue", nullable("string")),
+                        ("offending", array(kind("string"))),
+                    ],
+                    &[],
+                ))),
+                ("issues", kind("integer")),
+                ("verified", kind("boolean")),
+            ],
+            &[],
+        )),
+        ("diff", report(
+            &[
+                &[("old", kind("string")), ("new", kind("string")), ("breaking", kind("boolean"))][..],
+                &[(
+                    "changes",
+                    array(object(
+                        &[
+                            &[
+                                ("name", kind("string")),
+                                ("kind", kind("string")),
+                                ("breaking", kind("boolean")),
+                                ("old_allowed", json!({ "anyOf": [reference("range"), kind("null")] })),
+                                ("new_allowed", json!({ "anyOf": [reference("range"), kind("null")] })),
+                            ][..],
+                            &sides(array(all_of(reference("location"), object(&[("requirement", kind("string"))], &[])))),
+                        ]
+                        .concat(),
+                        &[],
+                    )),
+                )][..],
+            ]
+            .concat(),
+            &[],
+        )),
+        ("sync-check", report(
+            &[
+                ("prod", kind("string")),
+                ("dev", kind("string")),
+                ("strict", kind("boolean")),
+                ("flagged", kind("boolean")),
+                ("packages", array(object(
+                    &[
+                        ("name", kind("string")),
+                        ("alignment", json!({ "enum": ["identical", "narrower", "drifted", "conflicting"] })),
+                        ("flagged", kind("boolean")),
+                        ("prod", array(reference("requirement_line"))),
+                        ("dev", array(reference("requirement_line"))),
+                        ("prod_allowed", reference("range")),
+                        ("dev_allowed", reference("range")),
+                        ("outside_prod", reference("range")),
+                    ],
+                    &[],
+                ))),
+            ],
+            &[],
+        )),
+        ("lint", report(
+            &[
+                ("source", kind("string")),
+                ("duplicates", array(object(
+                    &[
+                        ("name", kind("string")),
+                        ("verdict", json!({ "enum": ["redundant", "consistent", "conflicting"] })),
+                        ("listings", array(reference("requirement_line"))),
+                        ("shadowed", array(object(
+                            &[
+                                ("file", kind("string")),
+                                ("line", kind("integer")),
+                                ("column", nullable("integer")),
+                                ("by_file", kind("string")),
+                                ("by_line", kind("integer")),
+                                ("by_column", nullable("integer")),
+                            ],
+                            &[],
+                        ))),
+                        ("combined", json!({ "anyOf": [reference("package"), kind("null")] })),
+                    ],
+                    &[],
+                ))),
+                ("pin_violations", array(all_of(
+                    reference("location"),
+                    object(
+                        &[
+                            ("name", kind("string")),
+                            ("requirement", kind("string")),
+                            ("rule", json!({ "enum": ["exact", "range", "any"] })),
+                            ("override", nullable("string")),
+                            ("message", kind("string")),
+                        ],
+                        &[],
+                    ),
+                ))),
+                ("upper_caps", array(all_of(
+                    reference("location"),
+                    object(
+                        &[
+                            ("name", kind("string")),
+                            ("requirement", kind("string")),
+                            ("clause", kind("string")),
+                            ("message", kind("string")),
+                        ],
+                        &[],
+                    ),
+                ))),
+            ],
+            &[],
+        )),
+        ("workspace", report(
+            &[
+                ("root", kind("string")),
+                ("conflict", kind("boolean")),
+                ("members", array(object(
+                    &[
+                        ("name", kind("string")),
+                        ("dir", kind("string")),
+                        ("requirements", array(reference("requirement_line"))),
+                    ],
+                    &[],
+                ))),
+                ("conflicts", array(object(
+                    &[
+                        ("name", kind("string")),
+                        ("members", array(kind("string"))),
+                        ("cross_project", kind("boolean")),
+                        ("requirements", array(reference("requirement_line"))),
+                    ],
+                    &[],
+                ))),
+                ("unified", array(reference("package"))),
+                ("constraints_txt", kind("string")),
+            ],
+            &[],
+        )),
+        ("baseline", report(
+            &[
+                ("conflicts", array(object(
+                    &[("package", kind("string")), ("requirements", array(kind("string")))],
+                    &[],
+                ))),
+                ("advisories", array(object(
+                    &[("package", kind("string")), ("id", kind("string")), ("aliases", array(kind("string")))],
+                    &[],
+                ))),
+            ],
+            &[],
+        )),
+        ("selftest", report(
+            &[
+                ("packaging", nullable("string")),
+                ("checked", kind("integer")),
+                ("mismatches", array(object(
+                    &[
+                        ("check", kind("string")),
+                        ("expected", kind("string")),
+                        ("actual", kind("string")),
+                        ("known", nullable("string")),
+                    ],
+                    &[],
+                ))),
+            ],
+            &[],
+        )),
+        ("serve-resolve", report(
+            &[
+                ("pins", array(object(
+                    &[("name", kind("string")), ("version", kind("string")), ("via", array(kind("string")))],
+                    &[],
+                ))),
+                ("requirements_txt", kind("string")),
+            ],
+            &[],
+        )),
+    ]
+}
+
+/// A report object: `schema_version` and the `required` properties always
+/// appear, the `optional` ones depending on the options given.
+fn report(required: &[(&'static str, Value)], optional: &[(&'static str, Value)]) -> Value {
+    let version = ("schema_version", json!({ "const": SCHEMA_VERSION }));
+    object(&[&[version][..], required].concat(), optional)
+}
+
+fn object(required: &[(&'static str, Value)], optional: &[(&'static str, Value)]) -> Value {
+    let properties: Map<String, Value> =
+        required.iter().chain(optional).map(|(name, schema)| (name.to_string(), schema.clone())).collect();
+    let names: Vec<&str> = required.iter().map(|(name, _)| *name).collect();
+    json!({ "type": "object", "properties": properties, "required": names })
+}
+
+fn array(items: Value) -> Value {
+    json!({ "type": "array", "items": items })
+}
+
+fn kind(name: &str) -> Value {
+    json!({ "type": name })
+}
+
+fn nullable(name: &str) -> Value {
+    json!({ "type": [name, "null"] })
+}
+
+fn reference(name: &str) -> Value {
+    json!({ "$ref": format!("#/$defs/{}", name) })
+}
+
+fn all_of(a: Value, b: Value) -> Value {
+    json!({ "allOf": [a, b] })
+}
+
+fn with_description(mut schema: Value, description: &str) -> Value {
+    schema["description"] = json!(description);
+    schema
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::ConflictReport;
+    use crate::lint;
+    use crate::output::json;
+    use crate::package::PythonPackage;
+    use crate::prerelease::PreReleasePolicy;
+    use crate::requirements;
+    use std::path::Path;
+
+    /// Whether `value` is valid against `schema`, for the keywords the document uses.
+    fn conforms(value: &Value, schema: &Value, document: &Value) -> bool {
+        if let Some(Value::String(target)) = schema.get("$ref") {
+            let name = target.trim_start_matches("#/$defs/");
+            return conforms(value, &document["$defs"][name], document);
+        }
+        if let Some(Value::Array(options)) = schema.get("anyOf") {
+            return options.iter().any(|option| conforms(value, option, document));
+        }
+        if let Some(Value::Array(parts)) = schema.get("allOf") {
+            return parts.iter().all(|part| conforms(value, part, document));
+        }
+        if let Some(constant) = schema.get("const") {
+            return value == constant;
+        }
+        if let Some(Value::Array(values)) = schema.get("enum") {
+            return values.contains(value);
+        }
+        let types: Vec<&str> = match &schema["type"] {
+            Value::String(name) => vec![name.as_str()],
+            Value::Array(names) => names.iter().filter_map(Value::as_str).collect(),
+            _ => return true,
+        };
+        let matches = |name: &str| match name {
+            "object" => value.is_object(),
+            "array" => value.is_array(),
+            "string" => value.is_string(),
+            "integer" => value.is_u64() || value.is_i64(),
+            "number" => value.is_number(),
+            "boolean" => value.is_boolean(),
+            "null" => value.is_null(),
+            _ => false,
+        };
+        if !types.into_iter().any(matches) {
+            return false;
+        }
+        match value {
+            Value::Object(fields) => {
+                let required = schema["required"].as_array().cloned().unwrap_or_default();
+                let properties = schema["properties"].as_object().cloned().unwrap_or_default();
+                required.iter().filter_map(Value::as_str).all(|name| fields.contains_key(name))
+                    && fields
+                        .iter()
+                        .all(|(name, field)| properties.get(name).is_none_or(|schema| conforms(field, schema, document)))
+            }
+            Value::Array(items) => items.iter().all(|item| conforms(item, &schema["items"], document)),
+            _ => true,
+        }
+    }
+
+    #[test]
+    fn test_reports_conform() -> anyhow::Result<()> {
+        let document = document();
+        let check = |name: &str, value: &Value| {
+            let schema = &document["$defs"][name];
+            assert!(conforms(value, schema, &document), "{} does not match its schema: {:#}", name, value);
+            // Properties the schema does not know about would go unchecked
+            let known = schema["properties"].as_object().unwrap();
+            let unknown: Vec<&String> = value.as_object().unwrap().keys().filter(|k| !known.contains_key(*k)).collect();
+            assert!(unknown.is_empty(), "{}: {:?}", name, unknown);
+        };
+
+        let path = Path::new("requirements.txt");
+        let requirements = requirements::parse_str("django>=4\nDjango<3\nsix==1.16.0\nsix\n", path)?;
+        let report = ConflictReport::check(&requirements);
+        check("check", &json::report(&["requirements.txt".to_string()], &report, None));
+        let pair = json::pair(&PythonPackage::parse("six>1")?, &PythonPackage::parse("six<2")?, Some(&[]), PreReleasePolicy::Auto);
+        check("check-pair", &pair);
+        let duplicates = lint::duplicates(&requirements);
+        let caps = lint::caps(&requirements, &[]);
+        check("lint", &json::lint_report(path, &duplicates, &[], &caps));
+        let old = requirements::parse_str("six==1.15.0\n", path)?;
+        check("diff", &json::constraint_diff(path, path, &crate::diff::diff(&old, &requirements)));
+        check("freeze-diff", &json::freeze_diff(path, path, &crate::freeze::diff(&old, &requirements), &report));
+        check("sync-check", &json::sync_report(path, path, &crate::sync::check(&old, &requirements), true));
+        let drift = crate::drift::Drift {
+            manifest: path.to_path_buf(),
+            lock: path.to_path_buf(),
+            checked: requirements.len(),
+            issues: crate::lock::LockReport::check(&requirements, &[]).issues,
+            undeclared: Some(Vec::new()),
+        };
+        check("drift", &json::drift_report(&[drift]));
+        let conflicts = crate::conflict::ConflictReport::check(&requirements::parse_str("six>2\nsix<2\n", path)?).conflicts;
+        check("baseline", &json::baseline(&crate::baseline::Baseline::record(&conflicts, &[])));
+        let (checks, answers) = crate::selftest::table()?;
+        let mismatch = crate::selftest::Mismatch { check: checks[0].clone(), expected: answers[0], actual: answers[0], known: None };
+        let outcome = crate::selftest::Outcome { packaging: Some("24.0".to_string()), checked: 1, mismatches: vec![mismatch] };
+        check("selftest", &json::selftest(&outcome));
+
+        assert_eq!(json::report(&[], &report, None)["schema_version"], json!(1));
+        assert!(!conforms(&json!({ "schema_version": 2 }), &document["$defs"]["check-error"], &document));
+        Ok(())
+    }
+}
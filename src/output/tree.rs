@@ -0,0 +1,232 @@
+use std::collections::HashSet;
+
+use crate::graph::{DependencyGraph, Edge};
+
+/// Box-drawing characters used to connect the lines of a tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Charset {
+    Utf8,
+    Ascii,
+}
+
+impl Charset {
+    /// The branch, last branch, continuation and blank prefixes.
+    fn pieces(self) -> [&'static str; 4] {
+        match self {
+            Charset::Utf8 => ["├── ", "└── ", "│   ", "    "],
+            Charset::Ascii => ["|-- ", "`-- ", "|   ", "    "],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct TreeOptions {
+    /// Levels of dependencies to print below each root, or all of them.
+    pub depth: Option<usize>,
+    /// Print the packages depending on each root instead of its dependencies.
+    pub invert: bool,
+    pub charset: Charset,
+}
+
+/// Renders one tree per root, like `cargo tree`. Every edge shows the
+/// requirement the dependent declares; packages already printed in full are
+/// marked `(*)` instead of being expanded again.
+pub fn render(graph: &DependencyGraph, roots: &[String], options: &TreeOptions) -> String {
+    let mut text = String::new();
+    let mut expanded = HashSet::new();
+    for (i, root) in roots.iter().enumerate() {
+        if i > 0 {
+            text.push('\n');
+        }
+        let mut line = label(graph, root);
+        render_children(graph, root, options, 0, "", &mut expanded, &mut line);
+        text.push_str(&line);
+    }
+    text
+}
+
+/// Appends the children of `name` to `text`, whose last line is `name` itself.
+fn render_children(
+    graph: &DependencyGraph,
+    name: &str,
+    options: &TreeOptions,
+    level: usize,
+    prefix: &str,
+    expanded: &mut HashSet<String>,
+    text: &mut String,
+) {
+    let children = if options.invert {
+        graph.dependents(name)
+    } else {
+        graph.dependencies(name)
+    };
+    if children.is_empty() || options.depth.is_some_and(|depth| level >= depth) {
+        text.push('\n');
+        return;
+    }
+    if !expanded.insert(name.to_string()) {
+        text.push_str(" (*)\n");
+        return;
+    }
+    text.push('\n');
+
+    let [branch, last, continuation, blank] = options.charset.pieces();
+    for (i, edge) in children.iter().enumerate() {
+        let is_last = i + 1 == children.len();
+        let child = if options.invert { &edge.from } else { &edge.to };
+        text.push_str(prefix);
+        text.push_str(if is_last { last } else { branch });
+        text.push_str(&label(graph, child));
+        text.push_str(&constraint(graph, edge));
+        let prefix = format!("{}{}", prefix, if is_last { blank } else { continuation });
+        render_children(graph, child, options, level + 1, &prefix, expanded, text);
+    }
+}
+
+/// Renders a chain of requirements on one line, e.g.
+/// `boto3 1.28.0 -> botocore 1.31.0 (<1.32.0,>=1.31.0) -> urllib3 1.26.18 (<1.27,>=1.25.4)`.
+pub fn render_chain(graph: &DependencyGraph, root: &str, chain: &[&Edge]) -> String {
+    let mut text = label(graph, root);
+    for edge in chain {
+        text.push_str(" -> ");
+        text.push_str(&label(graph, &edge.to));
+        text.push_str(&constraint(graph, edge));
+    }
+    text
+}
+
+/// A package with its version, e.g. `urllib3 2.2.3`.
+pub(crate) fn label(graph: &DependencyGraph, name: &str) -> String {
+    match graph.nodes.get(name) {
+        Some(node) => match &node.version {
+            Some(version) => format!("{} {}", node.name, version),
+            None => format!("{} (missing)", node.name),
+        },
+        None => format!("{} (missing)", name),
+    }
+}
+
+/// The requirement on an edge if it restricts anything,
+/// e.g. ` (<3,>=1.21.1; python_version < "3.10")`.
+fn constraint(graph: &DependencyGraph, edge: &Edge) -> String {
+    let mut text = match edge_label(edge) {
+        label if label.is_empty() => label,
+        label => format!(" ({})", label),
+    };
+    if is_unsatisfied(graph, edge) {
+        text.push_str(" [unsatisfied]");
+    }
+    text
+}
+
+/// The specifiers and marker of an edge's requirement, empty when it accepts
+/// any version everywhere.
+pub(crate) fn edge_label(edge: &Edge) -> String {
+    let requirement = &edge.requirement;
+    let mut clauses = Vec::new();
+    if !requirement.specifiers.is_empty() {
+        clauses.push(requirement.specifiers.to_string());
+    }
+    if let Some(marker) = &requirement.marker {
+        clauses.push(marker.to_string());
+    }
+    clauses.join("; ")
+}
+
+/// Whether the dependency has a version and it breaks the edge's requirement.
+pub(crate) fn is_unsatisfied(graph: &DependencyGraph, edge: &Edge) -> bool {
+    !graph.is_satisfied(edge) && graph.node(&edge.to).is_some_and(|node| node.version.is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::env::InstalledDistribution;
+    use crate::package::PythonPackage;
+    use crate::pep440::Pep440Version;
+    use std::path::PathBuf;
+
+    fn graph() -> DependencyGraph {
+        let dist = |name: &str, version: &str, requires: &[&str]| InstalledDistribution {
+            name: name.to_string(),
+            version: Pep440Version::parse(version).unwrap(),
+            requires: requires.iter().map(|r| PythonPackage::parse(r).unwrap()).collect(),
+            path: PathBuf::new(),
+        };
+        DependencyGraph::from_installed(
+            &[
+                dist("requests", "2.31.0", &["urllib3<3,>=1.21.1", "idna<4,>=2.5", "certifi>=2017.4.17"]),
+                dist("urllib3", "3.0.0", &[]),
+                dist("idna", "3.6", &[]),
+                dist("httpx", "0.27.0", &["anyio", "sniffio"]),
+                dist("anyio", "4.0", &["idna", "sniffio"]),
+                dist("sniffio", "1.3.0", &["idna"]),
+            ],
+            None,
+        )
+    }
+
+    #[test]
+    fn test_render() {
+        let graph = graph();
+        let options = TreeOptions {
+            depth: None,
+            invert: false,
+            charset: Charset::Utf8,
+        };
+        assert_eq!(
+            render(&graph, &graph.roots, &options),
+            "\
+httpx 0.27.0
+├── anyio 4.0
+│   ├── idna 3.6
+│   └── sniffio 1.3.0
+│       └── idna 3.6
+└── sniffio 1.3.0 (*)
+
+requests 2.31.0
+├── urllib3 3.0.0 (<3,>=1.21.1) [unsatisfied]
+├── idna 3.6 (<4,>=2.5)
+└── certifi (missing) (>=2017.4.17)
+"
+        );
+    }
+
+    #[test]
+    fn test_render_inverted() {
+        let graph = graph();
+        let options = TreeOptions {
+            depth: Some(1),
+            invert: true,
+            charset: Charset::Ascii,
+        };
+        assert_eq!(
+            render(&graph, &["idna".to_string()], &options),
+            "\
+idna 3.6
+|-- requests 2.31.0 (<4,>=2.5)
+|-- anyio 4.0
+`-- sniffio 1.3.0
+"
+        );
+    }
+
+    #[test]
+    fn test_render_chain() {
+        let graph = graph();
+        let chains: Vec<String> = graph
+            .paths_to("idna")
+            .iter()
+            .map(|chain| render_chain(&graph, &chain[0].from, chain))
+            .collect();
+        assert_eq!(
+            chains,
+            vec![
+                "httpx 0.27.0 -> anyio 4.0 -> idna 3.6",
+                "httpx 0.27.0 -> anyio 4.0 -> sniffio 1.3.0 -> idna 3.6",
+                "httpx 0.27.0 -> sniffio 1.3.0 -> idna 3.6",
+                "requests 2.31.0 -> idna 3.6 (<4,>=2.5)",
+            ]
+        );
+    }
+}
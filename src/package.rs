@@ -0,0 +1,630 @@
+use anyhow::Result;
+use regex::Regex;
+use std::fmt;
+use std::sync::OnceLock;
+
+use crate::error::PyhelperError;
+use crate::filename::DistributionName;
+use crate::marker::{MarkerEnvironment, MarkerTree};
+use crate::pep440::Pep440Version;
+use crate::prerelease::{self, PreReleasePolicy};
+use crate::specifier::{Specifier, SpecifierSet};
+
+/// A single requirement such as `requests[socks]>=2.20; python_version >= "3.8"`.
+#[derive(Debug, Clone)]
+pub struct PythonPackage {
+    pub name: String,
+    /// Optional features requested in brackets, e.g. `security` in `requests[security]`.
+    pub extras: Vec<String>,
+    pub specifiers: SpecifierSet,
+    /// Environment marker after `;`; the requirement only applies where it holds.
+    pub marker: Option<MarkerTree>,
+    /// The URL of a PEP 508 direct reference such as `pkg @ https://.../pkg-1.0.whl`.
+    /// It pins the version its file name or VCS tag names, if any.
+    pub url: Option<String>,
+}
+
+impl PythonPackage {
+    pub fn parse(input: &str) -> Result<Self> {
+        if let Some(package) = parse_direct_reference(input)? {
+            return Ok(package);
+        }
+        let (input, marker) = match input.split_once(';') {
+            Some((requirement, marker)) => (requirement.trim(), Some(MarkerTree::parse(marker)?)),
+            None => (input, None),
+        };
+
+        static RE: OnceLock<Regex> = OnceLock::new();
+        let re = RE.get_or_init(|| Regex::new(r"^([a-zA-Z0-9](?:[a-zA-Z0-9._-]*[a-zA-Z0-9])?)\s*(?:\[([^\]]*)\])?(.*?)$").unwrap());
+        let captures = re.captures(input)
+            .ok_or_else(|| PyhelperError::Specifier(format!("Invalid package format: {}", input)))?;
+        
+        let name = captures[1].to_string();
+        let extras = match captures.get(2) {
+            Some(m) => parse_extras(m.as_str())
+                .ok_or_else(|| PyhelperError::Specifier(format!("Invalid extras: [{}]", m.as_str())))?,
+            None => Vec::new(),
+        };
+        let version_str = captures.get(3).map_or("", |m| m.as_str());
+        // PEP 508 also allows the specifiers in parentheses, as in `requests (>=2.0)`
+        let version_str = version_str
+            .trim()
+            .strip_prefix('(')
+            .and_then(|v| v.strip_suffix(')'))
+            .unwrap_or(version_str);
+
+        let specifiers = SpecifierSet::parse(version_str)
+            .map_err(|_| PyhelperError::Specifier(format!("Invalid version requirement: {}", version_str)))?;
+
+        Ok(PythonPackage {
+            name,
+            extras,
+            specifiers,
+            marker,
+            url: None,
+        })
+    }
+
+    /// Specifiers in PEP 440 notation, or `*` when any version is accepted.
+    pub fn version_spec(&self) -> String {
+        if self.specifiers.is_empty() {
+            return "*".to_string();
+        }
+        self.specifiers.to_string()
+    }
+
+    /// The PEP 503 normalized name used to decide whether two packages are the same.
+    pub fn canonical_name(&self) -> String {
+        canonicalize_name(&self.name)
+    }
+
+    /// Whether both requirements can apply to the same environment.
+    pub fn markers_overlap(&self, other: &PythonPackage) -> bool {
+        match (&self.marker, &other.marker) {
+            (Some(a), Some(b)) => a.overlaps(b),
+            (Some(m), None) | (None, Some(m)) => m.is_satisfiable(),
+            (None, None) => true,
+        }
+    }
+
+    /// Whether this dependency applies in `env` when the depending package was
+    /// installed with the given `extras`.
+    pub fn applies_in(&self, env: &MarkerEnvironment, extras: &[String]) -> bool {
+        let Some(marker) = &self.marker else {
+            return true;
+        };
+        let mut env = env.clone();
+        std::iter::once("").chain(extras.iter().map(|e| e.as_str())).any(|extra| {
+            env.set("extra", extra);
+            marker.evaluate(&env)
+        })
+    }
+
+    /// Like [`applies_in`](Self::applies_in) for an unknown environment: only
+    /// dependencies whose marker cannot hold for the given `extras` are excluded.
+    pub fn may_apply(&self, extras: &[String]) -> bool {
+        let Some(marker) = &self.marker else {
+            return true;
+        };
+        std::iter::once("").chain(extras.iter().map(|e| e.as_str())).any(|extra| {
+            let extra = MarkerTree::parse(&format!("extra == '{}'", extra));
+            extra.is_ok_and(|extra| marker.overlaps(&extra))
+        })
+    }
+
+    pub fn conflicts_with(&self, other: &PythonPackage) -> bool {
+        self.conflicts_under(other, PreReleasePolicy::default())
+    }
+
+    /// Like [`conflicts_with`](Self::conflicts_with), counting pre-releases as
+    /// `policy` says: requirements only overlapping in pre-releases conflict
+    /// unless pre-releases are allowed.
+    pub fn conflicts_under(&self, other: &PythonPackage, policy: PreReleasePolicy) -> bool {
+        if self.canonical_name() != other.canonical_name() || !self.markers_overlap(other) {
+            return false;
+        }
+
+        if self.specifiers.excludes_literal(&other.specifiers) {
+            return true;
+        }
+        let range = self.specifiers.range().intersect(&other.specifiers.range());
+        !prerelease::is_satisfiable(&range, policy.allows(&[&self.specifiers, &other.specifiers]))
+    }
+}
+
+impl fmt::Display for PythonPackage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)?;
+        if !self.extras.is_empty() {
+            write!(f, "[{}]", self.extras.join(","))?;
+        }
+        match &self.url {
+            Some(url) => write!(f, " @ {}", url)?,
+            None => write!(f, " {}", self.version_spec())?,
+        }
+        if let Some(marker) = &self.marker {
+            write!(f, "; {}", marker)?;
+        }
+        Ok(())
+    }
+}
+
+/// A requirement read as far as it is valid.
+#[derive(Debug, Clone)]
+pub struct LossyRequirement {
+    /// The requirement without its invalid parts, or `None` when not even a
+    /// name could be read.
+    pub package: Option<PythonPackage>,
+    /// Why each dropped part is invalid; empty when nothing was dropped.
+    pub errors: Vec<String>,
+}
+
+/// Parses a requirement that may be malformed, such as a line of a
+/// hand-edited requirements file, without failing or panicking on any input.
+/// Invalid extras, clauses, URLs and markers are dropped and reported in
+/// `errors`, so `pkg[ok,b@d]>=1.0,<<2; os_name =` still reads as `pkg[ok]>=1.0`.
+pub fn parse_requirement_lossy(input: &str) -> LossyRequirement {
+    let error = match PythonPackage::parse(input) {
+        Ok(package) => return LossyRequirement { package: Some(package), errors: Vec::new() },
+        Err(e) => e,
+    };
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"^\s*([a-zA-Z0-9](?:[a-zA-Z0-9._-]*[a-zA-Z0-9])?)\s*(?:\[([^\]]*)\])?(.*)$").unwrap());
+    let Some(captures) = re.captures(input) else {
+        return LossyRequirement { package: None, errors: vec![error.to_string()] };
+    };
+    let mut errors = Vec::new();
+    let mut package = PythonPackage {
+        name: captures[1].to_string(),
+        extras: Vec::new(),
+        specifiers: SpecifierSet::default(),
+        marker: None,
+        url: None,
+    };
+    for extra in captures.get(2).map_or("", |m| m.as_str()).split(',') {
+        match parse_extras(extra) {
+            Some(valid) => package.extras.extend(valid),
+            None => errors.push(format!("Invalid extra: {}", extra.trim())),
+        }
+    }
+
+    let rest = captures[3].trim();
+    let marker = match rest.strip_prefix('@') {
+        Some(reference) => {
+            // As in a valid direct reference, the marker follows the URL after whitespace
+            let reference = reference.trim_start();
+            let (url, after) = reference.split_once(char::is_whitespace).unwrap_or((reference, ""));
+            static SCHEME: OnceLock<Regex> = OnceLock::new();
+            let scheme = SCHEME.get_or_init(|| Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*:\S+$").unwrap());
+            if scheme.is_match(url) {
+                if let Some(version) = url_version(url) {
+                    package.specifiers.specifiers.extend(Specifier::parse(&format!("=={}", version)));
+                }
+                package.url = Some(url.to_string());
+            } else {
+                errors.push(format!("Invalid URL: {}", url));
+            }
+            let (junk, marker) = match after.split_once(';') {
+                Some((junk, marker)) => (junk.trim(), Some(marker)),
+                None => (after.trim(), None),
+            };
+            if !junk.is_empty() {
+                errors.push(format!("Unexpected text after URL: {}", junk));
+            }
+            marker
+        }
+        None => {
+            let (clauses, marker) = match rest.split_once(';') {
+                Some((clauses, marker)) => (clauses.trim(), Some(marker)),
+                None => (rest, None),
+            };
+            let clauses = clauses.strip_prefix('(').and_then(|c| c.strip_suffix(')')).unwrap_or(clauses);
+            for clause in clauses.split(',').filter(|_| !clauses.trim().is_empty()) {
+                match Specifier::parse(clause) {
+                    Ok(specifier) => package.specifiers.specifiers.push(specifier),
+                    Err(_) => errors.push(format!("Invalid version requirement: {}", clause.trim())),
+                }
+            }
+            marker
+        }
+    };
+    if let Some(marker) = marker {
+        match MarkerTree::parse(marker) {
+            Ok(marker) => package.marker = Some(marker),
+            Err(e) => errors.push(e.to_string()),
+        }
+    }
+    if errors.is_empty() {
+        errors.push(error.to_string());
+    }
+    LossyRequirement { package: Some(package), errors }
+}
+
+/// Parses `name[extras] @ url ; marker`, or returns `None` for a requirement
+/// that is not a direct reference, i.e. has no URL with a scheme after `@`. As the URL may contain `;`, a marker must
+/// follow it after whitespace.
+fn parse_direct_reference(input: &str) -> Result<Option<PythonPackage>> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"^\s*([a-zA-Z0-9](?:[a-zA-Z0-9._-]*[a-zA-Z0-9])?)\s*(?:\[([^\]]*)\])?\s*@\s*([a-zA-Z][a-zA-Z0-9+.-]*:\S+)(?:\s+;(.*))?\s*$").unwrap());
+    let Some(captures) = re.captures(input) else {
+        return Ok(None);
+    };
+    let extras = match captures.get(2) {
+        Some(m) => {
+            parse_extras(m.as_str()).ok_or_else(|| PyhelperError::Specifier(format!("Invalid extras: [{}]", m.as_str())))?
+        }
+        None => Vec::new(),
+    };
+    let marker = captures.get(4).map(|m| MarkerTree::parse(m.as_str())).transpose()?;
+    let url = captures[3].to_string();
+    let specifiers = match url_version(&url) {
+        Some(version) => SpecifierSet::parse(&format!("=={}", version))?,
+        None => SpecifierSet::default(),
+    };
+    Ok(Some(PythonPackage {
+        name: captures[1].to_string(),
+        extras,
+        specifiers,
+        marker,
+        url: Some(url),
+    }))
+}
+
+/// The version a direct reference installs: the version in the file name of
+/// a wheel or source archive, or a `@v1.0` tag at the end of a VCS URL.
+/// Branches and commits name no version.
+fn url_version(url: &str) -> Option<Pep440Version> {
+    let url = url.split(['#', '?']).next().unwrap_or(url);
+    let file = percent_decode(url.trim_end_matches('/').rsplit('/').next()?);
+    if url.split(':').next().is_some_and(|scheme| scheme.contains('+')) {
+        let tag = file.rsplit_once('@')?.1;
+        return Pep440Version::parse(tag.strip_prefix('v').unwrap_or(tag)).ok();
+    }
+    DistributionName::parse(&file).map(|dist| dist.version().clone())
+}
+
+/// Decodes the `%XX` escapes of a URL part, such as the `%2B` of a local
+/// version in a wheel URL.
+pub(crate) fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(std::str::from_utf8(hex).ok()?, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                out.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// The file name extensions of source archives.
+pub(crate) const SDIST_EXTENSIONS: [&str; 4] = [".tar.gz", ".tar.bz2", ".tgz", ".zip"];
+
+/// Splits the comma-separated contents of `[...]`, returning `None` if any extra is not a valid name.
+fn parse_extras(list: &str) -> Option<Vec<String>> {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"^[a-zA-Z0-9](?:[a-zA-Z0-9._-]*[a-zA-Z0-9])?$").unwrap());
+    if list.trim().is_empty() {
+        return Some(Vec::new());
+    }
+    list.split(',')
+        .map(|extra| {
+            let extra = extra.trim();
+            re.is_match(extra).then(|| extra.to_string())
+        })
+        .collect()
+}
+
+/// Lowercases a project name and collapses runs of `-`, `_` and `.` into a single `-`,
+/// so `Python_Dateutil` and `python.dateutil` both become `python-dateutil`.
+pub fn canonicalize_name(name: &str) -> String {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    let re = RE.get_or_init(|| Regex::new(r"[-_.]+").unwrap());
+    re.replace_all(name, "-").to_lowercase()
+}
+
+/// Whether a project name matches a name pattern: a name, or a prefix ending
+/// in `*` such as `acme-*`, compared after canonicalization.
+pub fn matches_name(pattern: &str, name: &str) -> bool {
+    let name = canonicalize_name(name);
+    match pattern.strip_suffix('*') {
+        // Normalizing would trim the separator a prefix ends in
+        Some(prefix) => name.starts_with(&prefix.to_ascii_lowercase().replace(['_', '.'], "-")),
+        None => name == canonicalize_name(pattern),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_package_parsing() -> Result<()> {
+        // Test basic package name
+        let pkg = PythonPackage::parse("requests")?;
+        assert_eq!(pkg.name, "requests");
+        assert_eq!(pkg.version_spec(), "*");
+
+        // Test with version constraints
+        let test_cases = vec![
+            ("requests>=2.0.0", "requests", ">=2.0.0"),
+            ("django==3.2.0", "django", "==3.2.0"),
+            ("flask<1.0", "flask", "<1.0"),
+            ("numpy~=1.20", "numpy", "~=1.20"),
+            ("scipy>=1.0b2", "scipy", ">=1.0b2"),
+            ("twisted==2!21.7.0.post1", "twisted", "==2!21.7.0.post1"),
+            ("urllib3 (<3,>=1.21.1)", "urllib3", "<3,>=1.21.1"),
+        ];
+
+        for (input, expected_name, expected_version) in test_cases {
+            let pkg = PythonPackage::parse(input)?;
+            assert_eq!(pkg.name, expected_name);
+            assert_eq!(pkg.version_spec(), expected_version);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_extras_parsing() -> Result<()> {
+        let pkg = PythonPackage::parse("requests[security, socks]>=2.20")?;
+        assert_eq!(pkg.name, "requests");
+        assert_eq!(pkg.extras, vec!["security", "socks"]);
+        assert_eq!(pkg.version_spec(), ">=2.20");
+        assert_eq!(pkg.to_string(), "requests[security,socks] >=2.20");
+
+        let pkg = PythonPackage::parse("uvicorn [standard]; python_version >= '3.8'")?;
+        assert_eq!(pkg.extras, vec!["standard"]);
+        assert!(pkg.marker.is_some());
+
+        assert!(PythonPackage::parse("requests[]")?.extras.is_empty());
+        assert!(PythonPackage::parse("requests[sec urity]").is_err());
+        assert!(PythonPackage::parse("requests[security").is_err());
+
+        // Extras do not change which versions are acceptable
+        let pkg1 = PythonPackage::parse("requests[socks]>=2.20")?;
+        let pkg2 = PythonPackage::parse("requests<2.0")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_direct_reference() -> Result<()> {
+        let cases = [
+            ("pkg @ https://example.com/pkg-1.0-py3-none-any.whl", "==1.0"),
+            ("pkg@https://example.com/pkg-2.1.post1.tar.gz#sha256=abc", "==2.1.post1"),
+            ("pkg @ git+https://github.com/org/pkg@v1.0", "==1.0"),
+            ("pkg @ git+https://github.com/org/pkg.git@main", "*"),
+            ("pkg @ https://download.pytorch.org/whl/cu118/pkg-2.1.0%2Bcu118-cp311-cp311-linux_x86_64.whl", "==2.1.0+cu118"),
+            ("pkg @ file:///src/pkg", "*"),
+        ];
+        for (input, expected) in cases {
+            let pkg = PythonPackage::parse(input)?;
+            assert_eq!(pkg.name, "pkg");
+            assert_eq!(pkg.version_spec(), expected, "{}", input);
+        }
+
+        let pkg = PythonPackage::parse("pkg[cli] @ https://example.com/x;y/pkg-1.0.zip ; python_version >= '3.8'")?;
+        assert_eq!(pkg.url.as_deref(), Some("https://example.com/x;y/pkg-1.0.zip"));
+        assert_eq!(pkg.extras, vec!["cli"]);
+        assert!(pkg.marker.is_some());
+        assert_eq!(pkg.to_string(), "pkg[cli] @ https://example.com/x;y/pkg-1.0.zip; python_version >= \"3.8\"");
+
+        assert!(pkg.conflicts_with(&PythonPackage::parse("pkg>=2")?));
+        assert!(!pkg.conflicts_with(&PythonPackage::parse("pkg<2")?));
+        Ok(())
+    }
+
+    #[test]
+    fn test_name_normalization() -> Result<()> {
+        assert_eq!(canonicalize_name("Django"), "django");
+        assert_eq!(canonicalize_name("python_dateutil"), "python-dateutil");
+        assert_eq!(canonicalize_name("Python.__Dateutil"), "python-dateutil");
+
+        let pkg = PythonPackage::parse("zope.interface>=5")?;
+        assert_eq!(pkg.name, "zope.interface");
+
+        // Differently spelled names still refer to the same project
+        let pkg1 = PythonPackage::parse("DJANGO>=4.0")?;
+        let pkg2 = PythonPackage::parse("django<3.0")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        let pkg1 = PythonPackage::parse("python.dateutil==2.8.2")?;
+        let pkg2 = PythonPackage::parse("Python_Dateutil!=2.8.2")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_package_format() {
+        // Test invalid package names
+        assert!(PythonPackage::parse("").is_err());
+        assert!(PythonPackage::parse("@invalid").is_err());
+        assert!(PythonPackage::parse("invalid@1.0").is_err());
+        assert!(PythonPackage::parse("-leading>=1.0").is_err());
+    }
+
+    #[test]
+    fn test_lossy_parsing() {
+        let lossy = parse_requirement_lossy("pkg[ok,b@d]>=1.0,<<2; os_name =");
+        assert_eq!(lossy.package.map(|p| p.to_string()).as_deref(), Some("pkg[ok] >=1.0"));
+        assert_eq!(lossy.errors.len(), 3);
+        assert_eq!(lossy.errors[..2], ["Invalid extra: b@d", "Invalid version requirement: <<2"]);
+
+        let lossy = parse_requirement_lossy("pkg @ https://example.com/pkg-1.0.tar.gz trailing ; os_name == 'nt'");
+        let package = lossy.package.expect("a package");
+        assert_eq!(package.url.as_deref(), Some("https://example.com/pkg-1.0.tar.gz"));
+        assert_eq!(package.to_string(), "pkg @ https://example.com/pkg-1.0.tar.gz; os_name == \"nt\"");
+        assert_eq!(lossy.errors, ["Unexpected text after URL: trailing"]);
+
+        assert!(parse_requirement_lossy("requests>=2.0").errors.is_empty());
+        assert!(parse_requirement_lossy("-leading>=1.0").package.is_none());
+        assert!(parse_requirement_lossy("pkg==1.0.post99999999999999999999").package.is_some_and(|p| p.specifiers.is_empty()));
+    }
+
+    #[test]
+    fn test_package_conflicts() -> Result<()> {
+        // Test compatible versions
+        let pkg1 = PythonPackage::parse("requests>=2.0.0")?;
+        let pkg2 = PythonPackage::parse("requests<3.0.0")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        // Test conflicting versions
+        let pkg1 = PythonPackage::parse("django>=4.0.0")?;
+        let pkg2 = PythonPackage::parse("django<3.0.0")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        // Test different packages (should never conflict)
+        let pkg1 = PythonPackage::parse("requests>=2.0.0")?;
+        let pkg2 = PythonPackage::parse("flask>=2.0.0")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        // Test exact version requirements
+        let pkg1 = PythonPackage::parse("pytest==6.0.0")?;
+        let pkg2 = PythonPackage::parse("pytest==6.0.0")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        let pkg1 = PythonPackage::parse("pytest==7.0.0")?;
+        let pkg2 = PythonPackage::parse("pytest==6.0.0")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        // `===` pins compare as text, even on PEP 440 versions
+        let pkg1 = PythonPackage::parse("a===1.0")?;
+        assert!(pkg1.conflicts_with(&PythonPackage::parse("a===1.0.0")?));
+        assert!(!pkg1.conflicts_with(&PythonPackage::parse("a>=0.5")?));
+        let pkg1 = PythonPackage::parse("a===1.0-custom")?;
+        assert!(pkg1.conflicts_with(&PythonPackage::parse("a===2.0-custom")?));
+        assert!(pkg1.conflicts_with(&PythonPackage::parse("a<1")?));
+        assert!(!pkg1.conflicts_with(&PythonPackage::parse("a===1.0-Custom")?));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_marker_guarded_conflicts() -> Result<()> {
+        let pkg = PythonPackage::parse("pywin32>=300; sys_platform == \"win32\"")?;
+        assert_eq!(pkg.name, "pywin32");
+        assert_eq!(pkg.to_string(), "pywin32 >=300; sys_platform == \"win32\"");
+        assert!(PythonPackage::parse("pywin32>=300; sys_platform ==").is_err());
+
+        // Requirements for disjoint environments never conflict
+        let pkg1 = PythonPackage::parse("numpy<1.25; python_version < '3.9'")?;
+        let pkg2 = PythonPackage::parse("numpy>=1.26; python_version >= '3.9'")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        let pkg2 = PythonPackage::parse("numpy>=1.26; sys_platform == 'linux'")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        let pkg2 = PythonPackage::parse("numpy>=1.26")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_version_ranges() -> Result<()> {
+        // Test overlapping ranges
+        let pkg1 = PythonPackage::parse("django>=2.0.0")?;
+        let pkg2 = PythonPackage::parse("django<5.0.0")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        // Test non-overlapping ranges
+        let pkg1 = PythonPackage::parse("django>=5.0.0")?;
+        let pkg2 = PythonPackage::parse("django<4.0.0")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        // Test boundary cases
+        let pkg1 = PythonPackage::parse("django==3.0.0")?;
+        let pkg2 = PythonPackage::parse("django>=3.0.0")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        // Narrow ranges that fall between common release numbers
+        let pkg1 = PythonPackage::parse("django>=7.1.0")?;
+        let pkg2 = PythonPackage::parse("django<7.2.0")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        let pkg1 = PythonPackage::parse("django>3.0.0")?;
+        let pkg2 = PythonPackage::parse("django<=3.0.0")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        // Shorter release numbers are zero-padded rather than treated as prefixes
+        let pkg1 = PythonPackage::parse("django<=3.1")?;
+        let pkg2 = PythonPackage::parse("django>=3.1.9")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        let pkg1 = PythonPackage::parse("django==3.1")?;
+        let pkg2 = PythonPackage::parse("django>=3.1.0")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        // Comma-separated specifier sets apply every clause
+        let pkg1 = PythonPackage::parse("requests>=2.0,<3.0,!=2.5.0")?;
+        let pkg2 = PythonPackage::parse("requests==2.5.0")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        let pkg2 = PythonPackage::parse("requests>=2.5.0,<=2.5.1")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        // Compatible release pins every segment but the last
+        let pkg1 = PythonPackage::parse("numpy~=1.4")?;
+        let pkg2 = PythonPackage::parse("numpy>=1.9")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        let pkg1 = PythonPackage::parse("numpy~=1.4.2")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        let pkg1 = PythonPackage::parse("numpy~=2.2")?;
+        let pkg2 = PythonPackage::parse("numpy==2.9")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        // Wildcard equality matches the whole release prefix
+        let pkg1 = PythonPackage::parse("django==4.2.*")?;
+        let pkg2 = PythonPackage::parse("django>=4.2.7")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        let pkg2 = PythonPackage::parse("django>=3.2,!=4.2.*,<5.0")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+
+        // Pre-releases sort before the final release, but only count when
+        // both requirements opt in to them
+        let pkg1 = PythonPackage::parse("django==4.0.*")?;
+        let pkg2 = PythonPackage::parse("django<4.0rc2")?;
+        assert!(pkg1.conflicts_with(&pkg2));
+        assert!(!pkg1.conflicts_under(&pkg2, PreReleasePolicy::Allow));
+        // `<4.0` leaves out the pre-releases of 4.0 whatever the policy
+        let pkg1 = PythonPackage::parse("django<4.0")?;
+        let pkg2 = PythonPackage::parse("django>=4.0rc1")?;
+        assert!(pkg1.conflicts_under(&pkg2, PreReleasePolicy::Allow));
+        let pkg1 = PythonPackage::parse("django<=4.0rc2")?;
+        assert!(!pkg1.conflicts_with(&pkg2));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_dependency_applies() -> Result<()> {
+        let socks = PythonPackage::parse("PySocks>=1.5.6; extra == \"socks\"")?;
+        let windows = PythonPackage::parse("colorama; sys_platform == \"win32\"")?;
+        let mut env = MarkerEnvironment::default();
+        env.set("sys_platform", "linux");
+
+        assert!(!socks.applies_in(&env, &[]));
+        assert!(socks.applies_in(&env, &["socks".to_string()]));
+        assert!(!windows.applies_in(&env, &[]));
+
+        assert!(!socks.may_apply(&[]));
+        assert!(socks.may_apply(&["socks".to_string()]));
+        assert!(windows.may_apply(&[]));
+
+        Ok(())
+    }
+}
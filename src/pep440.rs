@@ -0,0 +1,402 @@
+use anyhow::Result;
+use regex::Regex;
+use std::cmp::Ordering;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use crate::error::PyhelperError;
+
+/// Pre-release phase, ordered alpha < beta < release candidate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum PreKind {
+    Alpha,
+    Beta,
+    Rc,
+}
+
+impl fmt::Display for PreKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PreKind::Alpha => write!(f, "a"),
+            PreKind::Beta => write!(f, "b"),
+            PreKind::Rc => write!(f, "rc"),
+        }
+    }
+}
+
+/// One dot-separated piece of a local version label such as `cu118` or `2`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum LocalSegment {
+    Number(u64),
+    Text(String),
+}
+
+impl Ord for LocalSegment {
+    // Numeric segments sort after alphanumeric ones, per PEP 440
+    fn cmp(&self, other: &Self) -> Ordering {
+        match (self, other) {
+            (LocalSegment::Number(a), LocalSegment::Number(b)) => a.cmp(b),
+            (LocalSegment::Text(a), LocalSegment::Text(b)) => a.cmp(b),
+            (LocalSegment::Number(_), LocalSegment::Text(_)) => Ordering::Greater,
+            (LocalSegment::Text(_), LocalSegment::Number(_)) => Ordering::Less,
+        }
+    }
+}
+
+impl PartialOrd for LocalSegment {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for LocalSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LocalSegment::Number(n) => write!(f, "{}", n),
+            LocalSegment::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// A Python package version as defined by PEP 440, e.g. `2!1.0.3rc1.post2.dev0+ubuntu.1`.
+#[derive(Debug, Clone)]
+pub struct Pep440Version {
+    pub epoch: u64,
+    pub release: Vec<u64>,
+    pub pre: Option<(PreKind, u64)>,
+    pub post: Option<u64>,
+    pub dev: Option<u64>,
+    pub local: Vec<LocalSegment>,
+}
+
+fn version_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    // Adapted from the reference pattern in PEP 440, Appendix B
+    RE.get_or_init(|| {
+        Regex::new(
+            r"(?xi)
+            ^\s*v?
+            (?:(?P<epoch>[0-9]+)!)?
+            (?P<release>[0-9]+(?:\.[0-9]+)*)
+            (?P<pre>[-_.]?(?P<pre_l>alpha|a|beta|b|preview|pre|c|rc)[-_.]?(?P<pre_n>[0-9]+)?)?
+            (?P<post>(?:-(?P<post_n1>[0-9]+))|(?:[-_.]?(?:post|rev|r)[-_.]?(?P<post_n2>[0-9]+)?))?
+            (?P<dev>[-_.]?dev[-_.]?(?P<dev_n>[0-9]+)?)?
+            (?:\+(?P<local>[a-z0-9]+(?:[-_.][a-z0-9]+)*))?
+            \s*$",
+        )
+        .unwrap()
+    })
+}
+
+/// The largest version segment accepted, leaving room above for the next
+/// release of a prefix and for bounds such as [`after_posts`](Pep440Version::after_posts).
+const MAX_SEGMENT: u64 = u64::MAX >> 1;
+
+fn parse_number(s: &str) -> Result<u64> {
+    s.parse::<u64>()
+        .ok()
+        .filter(|n| *n <= MAX_SEGMENT)
+        .ok_or_else(|| PyhelperError::Specifier(format!("Version segment out of range: {}", s)).into())
+}
+
+impl Pep440Version {
+    pub fn parse(input: &str) -> Result<Self> {
+        let captures = version_regex()
+            .captures(input)
+            .ok_or_else(|| PyhelperError::Specifier(format!("Invalid version: {}", input)))?;
+        let number = |name: &str| -> Result<Option<u64>> {
+            captures.name(name).map(|m| parse_number(m.as_str())).transpose()
+        };
+
+        let epoch = number("epoch")?.unwrap_or(0);
+        let release = captures["release"]
+            .split('.')
+            .map(parse_number)
+            .collect::<Result<Vec<_>>>()?;
+
+        let pre = match captures.name("pre_l") {
+            Some(label) => {
+                let kind = match label.as_str().to_lowercase().as_str() {
+                    "a" | "alpha" => PreKind::Alpha,
+                    "b" | "beta" => PreKind::Beta,
+                    _ => PreKind::Rc,
+                };
+                Some((kind, number("pre_n")?.unwrap_or(0)))
+            }
+            None => None,
+        };
+
+        let post = match captures.name("post") {
+            Some(_) => Some(number("post_n1")?.or(number("post_n2")?).unwrap_or(0)),
+            None => None,
+        };
+
+        let dev = match captures.name("dev") {
+            Some(_) => Some(number("dev_n")?.unwrap_or(0)),
+            None => None,
+        };
+
+        let local = captures
+            .name("local")
+            .map(|m| {
+                m.as_str()
+                    .split(['.', '-', '_'])
+                    .map(|segment| match segment.parse::<u64>() {
+                        Ok(n) => LocalSegment::Number(n),
+                        Err(_) => LocalSegment::Text(segment.to_lowercase()),
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        Ok(Pep440Version {
+            epoch,
+            release,
+            pre,
+            post,
+            dev,
+            local,
+        })
+    }
+
+    /// Whether this is a pre-release or development release.
+    pub fn is_prerelease(&self) -> bool {
+        self.pre.is_some() || self.dev.is_some()
+    }
+
+    /// The smallest version that no longer shares the first `len` release segments,
+    /// e.g. `1.5.dev0` for `1.4.2` with a prefix length of two.
+    pub fn next_prefix(&self, len: usize) -> Self {
+        let mut release: Vec<u64> = self.release.iter().copied().take(len).collect();
+        release.resize(len.max(1), 0);
+        if let Some(last) = release.last_mut() {
+            *last += 1;
+        }
+        Pep440Version {
+            epoch: self.epoch,
+            release,
+            pre: None,
+            post: None,
+            dev: Some(0),
+            local: Vec::new(),
+        }
+    }
+
+    /// The smallest version after every local version of this one, e.g.
+    /// `1.0.post0.dev0` for `1.0`, which `1.0+cu118` and `1.0+ubuntu.1` precede.
+    pub fn after_locals(&self) -> Self {
+        let (post, dev) = match (self.post, self.dev) {
+            (post, Some(dev)) => (post, Some(dev + 1)),
+            (Some(post), None) => (Some(post + 1), Some(0)),
+            (None, None) => (Some(0), Some(0)),
+        };
+        Pep440Version {
+            epoch: self.epoch,
+            release: self.release.clone(),
+            pre: self.pre,
+            post,
+            dev,
+            local: Vec::new(),
+        }
+    }
+
+    /// A bound above every post-release of this release and their local
+    /// versions, `1.0.post18446744073709551615` for `1.0`, which no release
+    /// with more segments such as `1.0.1` reaches.
+    pub fn after_posts(&self) -> Self {
+        Pep440Version {
+            epoch: self.epoch,
+            release: self.release.clone(),
+            pre: None,
+            post: Some(u64::MAX),
+            dev: None,
+            local: Vec::new(),
+        }
+    }
+
+    /// The version `after_locals` was computed from, for a final or post
+    /// release: `1.0` for `1.0.post0.dev0`. `None` for other versions.
+    pub fn before_locals(&self) -> Option<Self> {
+        if self.dev != Some(0) || !self.local.is_empty() {
+            return None;
+        }
+        let post = match self.post? {
+            0 => None,
+            post => Some(post - 1),
+        };
+        Some(Pep440Version { post, dev: None, ..self.clone() })
+    }
+
+    /// Release segments with trailing zeros removed, so `1.0.0` and `1` compare equal.
+    fn trimmed_release(&self) -> &[u64] {
+        let len = self
+            .release
+            .iter()
+            .rposition(|&segment| segment != 0)
+            .map_or(0, |i| i + 1);
+        &self.release[..len]
+    }
+
+    fn cmp_pre(&self, other: &Self) -> Ordering {
+        // A dev release of a final version sorts before any of its pre-releases,
+        // and a final version sorts after all of them.
+        let key = |v: &Self| match (v.pre, v.post, v.dev) {
+            (None, None, Some(_)) => (0, None),
+            (None, _, _) => (2, None),
+            (Some(pre), _, _) => (1, Some(pre)),
+        };
+        key(self).cmp(&key(other))
+    }
+}
+
+impl Ord for Pep440Version {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.epoch
+            .cmp(&other.epoch)
+            .then_with(|| self.trimmed_release().cmp(other.trimmed_release()))
+            .then_with(|| self.cmp_pre(other))
+            // No post segment sorts before any post release
+            .then_with(|| self.post.cmp(&other.post))
+            // No dev segment sorts after any dev release
+            .then_with(|| match (self.dev, other.dev) {
+                (None, None) => Ordering::Equal,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(_), None) => Ordering::Less,
+                (Some(a), Some(b)) => a.cmp(&b),
+            })
+            .then_with(|| self.local.cmp(&other.local))
+    }
+}
+
+impl PartialOrd for Pep440Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Pep440Version {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Pep440Version {}
+
+impl Hash for Pep440Version {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.epoch.hash(state);
+        self.trimmed_release().hash(state);
+        self.pre.hash(state);
+        self.post.hash(state);
+        self.dev.hash(state);
+        self.local.hash(state);
+    }
+}
+
+impl FromStr for Pep440Version {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Pep440Version::parse(s)
+    }
+}
+
+impl fmt::Display for Pep440Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.epoch != 0 {
+            write!(f, "{}!", self.epoch)?;
+        }
+        let release: Vec<String> = self.release.iter().map(|n| n.to_string()).collect();
+        write!(f, "{}", release.join("."))?;
+        if let Some((kind, n)) = self.pre {
+            write!(f, "{}{}", kind, n)?;
+        }
+        if let Some(n) = self.post {
+            write!(f, ".post{}", n)?;
+        }
+        if let Some(n) = self.dev {
+            write!(f, ".dev{}", n)?;
+        }
+        if !self.local.is_empty() {
+            let local: Vec<String> = self.local.iter().map(|s| s.to_string()).collect();
+            write!(f, "+{}", local.join("."))?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Pep440Version {
+        Pep440Version::parse(s).unwrap()
+    }
+
+    #[test]
+    fn test_parse_components() {
+        let version = v("2!1.0.3rc1.post2.dev0+ubuntu.1");
+        assert_eq!(version.epoch, 2);
+        assert_eq!(version.release, vec![1, 0, 3]);
+        assert_eq!(version.pre, Some((PreKind::Rc, 1)));
+        assert_eq!(version.post, Some(2));
+        assert_eq!(version.dev, Some(0));
+        assert_eq!(
+            version.local,
+            vec![LocalSegment::Text("ubuntu".into()), LocalSegment::Number(1)]
+        );
+
+        assert!(Pep440Version::parse("").is_err());
+        assert!(Pep440Version::parse("1..0").is_err());
+        assert!(Pep440Version::parse("latest").is_err());
+        // Too large to take the successor of
+        assert!(Pep440Version::parse("1.0.post18446744073709551615").is_err());
+        assert!(Pep440Version::parse("1.0.dev9223372036854775807").is_ok_and(|v| v.after_locals().dev == Some(9223372036854775808)));
+    }
+
+    #[test]
+    fn test_normalized_display() {
+        let cases = vec![
+            ("1.0", "1.0"),
+            ("v1.0", "1.0"),
+            ("1.0-ALPHA.2", "1.0a2"),
+            ("1.0c1", "1.0rc1"),
+            ("1.0b", "1.0b0"),
+            ("1.0-1", "1.0.post1"),
+            ("1.0.rev3", "1.0.post3"),
+            ("1.0-dev", "1.0.dev0"),
+            ("0!1.0+Local-Build_7", "1.0+local.build.7"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(v(input).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn test_ordering() {
+        let ordered = [
+            "1.0.dev1", "1.0a1.dev1", "1.0a1", "1.0a2", "1.0b1", "1.0rc1", "1.0", "1.0+abc",
+            "1.0+5", "1.0.post1.dev1", "1.0.post1", "1.0.1", "1.1", "1!0.1",
+        ];
+        for pair in ordered.windows(2) {
+            assert!(v(pair[0]) < v(pair[1]), "{} < {}", pair[0], pair[1]);
+        }
+
+        assert_eq!(v("1.4.2").next_prefix(2), v("1.5.dev0"));
+        assert_eq!(v("1!2.0rc1").next_prefix(1), v("1!3.dev0"));
+        for version in ["1.0", "1.0rc1", "1.0.post2", "1.0.dev3", "1.0.post1.dev1"] {
+            let next = v(version).after_locals();
+            assert!(v(&format!("{}+cu118.9", version)) < next, "{} < {}", version, next);
+        }
+        assert_eq!(v("1.0").after_locals(), v("1.0.post0.dev0"));
+        assert_eq!(v("1.0.post0.dev0").before_locals(), Some(v("1.0")));
+        assert_eq!(v("1.0.post3.dev0").before_locals(), Some(v("1.0.post2")));
+        assert_eq!(v("1.5.dev0").before_locals(), None);
+        assert!(v("1.0.post99+local") < v("1.0").after_posts() && v("1.0").after_posts() < v("1.0.0.1.dev0"));
+
+        assert_eq!(v("1.0"), v("1.0.0"));
+        assert_eq!(v("1"), v("1.0.0.0"));
+    }
+}
@@ -0,0 +1,332 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use toml::Spanned;
+use tracing::{debug, instrument};
+
+use crate::convert::Index;
+use crate::error::PyhelperError;
+use crate::marker::MarkerTree;
+use crate::package::PythonPackage;
+use crate::requirements::{self, RequirementLine};
+use crate::specifier::SpecifierSet;
+
+/// Pipenv lets a dependency table carry individual marker variables as keys,
+/// e.g. `pywin32 = { version = "*", sys_platform = "== 'win32'" }`.
+const MARKER_KEYS: &[&str] = &[
+    "os_name",
+    "sys_platform",
+    "platform_machine",
+    "platform_python_implementation",
+    "platform_release",
+    "platform_system",
+    "platform_version",
+    "python_version",
+    "python_full_version",
+    "implementation_name",
+    "implementation_version",
+];
+
+#[derive(Debug, Default, Deserialize)]
+struct Pipfile {
+    #[serde(default)]
+    packages: BTreeMap<String, Spanned<PipfileDependency>>,
+    #[serde(default, rename = "dev-packages")]
+    dev_packages: BTreeMap<String, Spanned<PipfileDependency>>,
+    #[serde(default)]
+    source: Vec<PipfileSource>,
+    #[serde(default)]
+    requires: PipfileRequires,
+}
+
+#[derive(Debug, Deserialize)]
+struct PipfileSource {
+    name: Option<String>,
+    url: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PipfileRequires {
+    python_version: Option<String>,
+    python_full_version: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PipfileDependency {
+    Constraint(String),
+    Detailed(PipfileDetail),
+}
+
+#[derive(Debug, Deserialize)]
+struct PipfileDetail {
+    version: Option<String>,
+    markers: Option<String>,
+    #[serde(default)]
+    extras: Vec<String>,
+    #[serde(flatten)]
+    other: BTreeMap<String, toml::Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PipfileLock {
+    #[serde(default)]
+    default: BTreeMap<String, LockedEntry>,
+    #[serde(default)]
+    develop: BTreeMap<String, LockedEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+struct LockedEntry {
+    version: Option<String>,
+    markers: Option<String>,
+    #[serde(default)]
+    extras: Vec<String>,
+    #[serde(default)]
+    hashes: Vec<String>,
+}
+
+/// Reads the `[packages]` and `[dev-packages]` tables of a Pipfile.
+#[instrument(skip_all, err, fields(file = %path.display()))]
+pub fn parse_file(path: &Path) -> Result<Vec<RequirementLine>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Cannot read Pipfile: {}", path.display()))?;
+    let pipfile: Pipfile =
+        toml::from_str(&content).with_context(|| format!("Invalid Pipfile: {}", path.display()))?;
+
+    let mut requirements = Vec::new();
+    for (name, dependency) in pipfile.packages.iter().chain(&pipfile.dev_packages) {
+        // The entry spans from its key to the end of its value
+        let (line, value) = requirements::span_columns(&content, dependency.span());
+        let columns = requirements::columns_of(&content, line, name).map(|key| key.start..value.end.max(key.end));
+        let column = columns.as_ref().map_or(1, |c| c.start);
+        let located = |e: anyhow::Error| PyhelperError::parse(path, line, Some(column), e);
+        let package = match dependency.get_ref() {
+            PipfileDependency::Constraint(c) => PythonPackage {
+                name: name.clone(),
+                extras: Vec::new(),
+                specifiers: pipfile_specifiers(c).map_err(located)?,
+                marker: None,
+                url: None,
+            },
+            PipfileDependency::Detailed(detail) => PythonPackage {
+                name: name.clone(),
+                extras: detail.extras.clone(),
+                specifiers: match &detail.version {
+                    Some(c) => pipfile_specifiers(c).map_err(located)?,
+                    None => SpecifierSet::default(),
+                },
+                marker: detail_marker(detail).map_err(located)?,
+                url: None,
+            },
+        };
+        requirements.push(RequirementLine {
+            package,
+            file: path.to_path_buf(),
+            line,
+            columns,
+            hashes: Vec::new(),
+        });
+    }
+
+    debug!(requirements = requirements.len(), "parsed");
+    Ok(requirements)
+}
+
+/// The `[[source]]` indexes of a Pipfile. pipenv installs from the first one
+/// and falls back to the others.
+pub fn sources(content: &str) -> Result<Vec<Index>> {
+    let pipfile: Pipfile = toml::from_str(content)?;
+    Ok(pipfile
+        .source
+        .into_iter()
+        .enumerate()
+        .map(|(i, source)| Index { name: source.name, url: source.url, primary: i == 0 })
+        .collect())
+}
+
+/// The Python version a Pipfile requires: `python_full_version` exactly, or
+/// any release of `python_version`.
+pub fn requires_python(content: &str) -> Result<Option<SpecifierSet>> {
+    let pipfile: Pipfile = toml::from_str(content)?;
+    let requires = pipfile.requires;
+    let spec = match (requires.python_full_version, requires.python_version) {
+        (Some(full), _) => format!("=={}", full),
+        (None, Some(version)) => format!("=={}.*", version),
+        (None, None) => return Ok(None),
+    };
+    Ok(Some(SpecifierSet::parse(&spec)?))
+}
+
+/// Reads the pinned versions of a Pipfile.lock as `==` requirements, so they
+/// can be checked against the constraints they were locked from.
+pub fn parse_lock(path: &Path) -> Result<Vec<RequirementLine>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Cannot read Pipfile.lock: {}", path.display()))?;
+    let lock: PipfileLock = serde_json::from_str(&content)
+        .with_context(|| format!("Invalid Pipfile.lock: {}", path.display()))?;
+
+    let mut requirements = Vec::new();
+    for (section, entries) in [("default", &lock.default), ("develop", &lock.develop)] {
+        for (name, entry) in entries {
+            // VCS and path dependencies are locked by reference, not by version
+            let Some(version) = &entry.version else {
+                continue;
+            };
+            let line = json_line(&content, section, name);
+            let located = |e: anyhow::Error| PyhelperError::parse(path, line, None, e);
+            let marker = entry
+                .markers
+                .as_deref()
+                .map(MarkerTree::parse)
+                .transpose()
+                .map_err(located)?;
+            requirements.push(RequirementLine {
+                package: PythonPackage {
+                    name: name.clone(),
+                    extras: entry.extras.clone(),
+                    specifiers: SpecifierSet::parse(version).map_err(located)?,
+                    marker,
+                    url: None,
+                },
+                file: path.to_path_buf(),
+                line,
+                columns: requirements::columns_of(&content, line, &format!("\"{}\"", name)),
+                hashes: entry.hashes.clone(),
+            });
+        }
+    }
+
+    Ok(requirements)
+}
+
+fn pipfile_specifiers(constraint: &str) -> Result<SpecifierSet> {
+    match constraint.trim() {
+        "*" => Ok(SpecifierSet::default()),
+        constraint => SpecifierSet::parse(constraint),
+    }
+}
+
+/// Combines the `markers` string with any per-variable marker keys.
+fn detail_marker(detail: &PipfileDetail) -> Result<Option<MarkerTree>> {
+    let mut clauses: Vec<String> = detail.markers.iter().map(|m| format!("({})", m)).collect();
+    for key in MARKER_KEYS {
+        if let Some(toml::Value::String(condition)) = detail.other.get(*key) {
+            clauses.push(format!("{} {}", key, condition));
+        }
+    }
+    if clauses.is_empty() {
+        return Ok(None);
+    }
+    MarkerTree::parse(&clauses.join(" and ")).map(Some)
+}
+
+/// The line of the `"name": {` key inside a top-level section of the lock file.
+fn json_line(content: &str, section: &str, name: &str) -> usize {
+    let start = content.find(&format!("\"{}\"", section)).unwrap_or(0);
+    let offset = content[start..]
+        .find(&format!("\"{}\":", name))
+        .map_or(start, |i| start + i);
+    content[..offset].matches('\n').count() + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, content: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("pyhelper-pipfile-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_parse_pipfile() -> Result<()> {
+        let path = write_temp(
+            "Pipfile",
+            r#"[[source]]
+url = "https://pypi.org/simple"
+name = "pypi"
+
+[packages]
+requests = "*"
+django = { version = ">=4.2,<5", extras = ["bcrypt"] }
+pywin32 = { version = ">=300", sys_platform = "== 'win32'" }
+internal = { git = "https://example.com/internal.git", editable = true }
+
+[dev-packages]
+pytest = ">=7"
+
+[requires]
+python_version = "3.11"
+"#,
+        );
+
+        let requirements = parse_file(&path)?;
+        let found: Vec<(String, String, usize)> = requirements
+            .iter()
+            .map(|r| (r.package.name.clone(), r.package.version_spec(), r.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("django".to_string(), ">=4.2,<5".to_string(), 7),
+                ("internal".to_string(), "*".to_string(), 9),
+                ("pywin32".to_string(), ">=300".to_string(), 8),
+                ("requests".to_string(), "*".to_string(), 6),
+                ("pytest".to_string(), ">=7".to_string(), 12),
+            ]
+        );
+        assert_eq!(requirements[0].package.extras, vec!["bcrypt"]);
+        let marker = requirements[2].package.marker.as_ref().map(|m| m.to_string());
+        assert_eq!(marker.as_deref(), Some("sys_platform == \"win32\""));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_lock() -> Result<()> {
+        let path = write_temp(
+            "Pipfile.lock",
+            r#"{
+    "_meta": {"hash": {"sha256": "abc"}},
+    "default": {
+        "requests": {
+            "hashes": ["sha256:abc"],
+            "version": "==2.31.0"
+        },
+        "internal": {
+            "git": "https://example.com/internal.git"
+        }
+    },
+    "develop": {
+        "pytest": {
+            "markers": "python_version >= '3.8'",
+            "version": "==7.4.0"
+        }
+    }
+}"#,
+        );
+
+        let requirements = parse_lock(&path)?;
+        let found: Vec<(String, String, usize)> = requirements
+            .iter()
+            .map(|r| (r.package.name.clone(), r.package.version_spec(), r.line))
+            .collect();
+        assert_eq!(
+            found,
+            vec![
+                ("requests".to_string(), "==2.31.0".to_string(), 4),
+                ("pytest".to_string(), "==7.4.0".to_string(), 13),
+            ]
+        );
+        assert!(requirements[1].package.marker.is_some());
+        assert_eq!(requirements[0].hashes, vec!["sha256:abc"]);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,259 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::{ErrorKind, Write};
+use std::process::{Command, Stdio};
+
+use crate::audit::{Advisory, Audited, Finding, Severity};
+use crate::error::PyhelperError;
+use crate::package::canonicalize_name;
+use crate::pep440::Pep440Version;
+
+/// The version of the protocol spoken with plugins, sent with every request.
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// What a plugin takes part in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Capability {
+    /// Answers `advisories` requests, adding to the findings of `audit`.
+    Advisories,
+    /// Receives the reports of `audit` and `outdated` with `report` requests.
+    Reports,
+}
+
+/// A plugin of the configuration file.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginConfig {
+    /// The name errors and warnings refer to the plugin by.
+    pub name: String,
+    /// The program to run and its arguments.
+    pub command: Vec<String>,
+    pub provides: Vec<Capability>,
+}
+
+/// A source of advisories besides OSV.dev, such as an internal vulnerability
+/// database.
+pub trait AdvisorySource {
+    fn name(&self) -> &str;
+
+    /// The advisories affecting the given package versions, as findings of
+    /// those packages.
+    fn advisories(&self, packages: &[Audited]) -> Result<Vec<Finding>>;
+}
+
+/// A destination of reports besides standard output, such as a chat channel
+/// or a dashboard.
+pub trait Sink {
+    fn name(&self) -> &str;
+
+    /// Hands over the JSON report of `command`, whatever format was printed.
+    fn send(&self, command: &str, report: &Value) -> Result<()>;
+}
+
+/// A plugin run as a separate program for every request. The request is
+/// written to its standard input as a single JSON object,
+/// `{"protocol": 1, "method": ..., "params": ...}`, and the program answers on
+/// its standard output with `{"result": ...}` or `{"error": "message"}`. Its
+/// standard error is shown as is.
+#[derive(Debug, Clone)]
+pub struct SubprocessPlugin {
+    config: PluginConfig,
+}
+
+#[derive(Debug, Deserialize)]
+struct Response {
+    result: Option<Value>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AdvisoriesResult {
+    advisories: Vec<PluginAdvisory>,
+}
+
+/// An advisory as plugins send it, with the package version it affects.
+#[derive(Debug, Deserialize)]
+struct PluginAdvisory {
+    name: String,
+    version: String,
+    id: String,
+    #[serde(default)]
+    aliases: Vec<String>,
+    summary: Option<String>,
+    severity: Option<String>,
+    score: Option<f64>,
+    #[serde(default)]
+    fixed: Vec<String>,
+}
+
+impl SubprocessPlugin {
+    pub fn new(config: PluginConfig) -> Result<Self> {
+        if config.command.is_empty() {
+            return Err(PyhelperError::Invalid(format!("Plugin {} has no command", config.name)).into());
+        }
+        Ok(SubprocessPlugin { config })
+    }
+
+    pub fn provides(&self, capability: Capability) -> bool {
+        self.config.provides.contains(&capability)
+    }
+
+    /// Sends one request and returns the result the plugin answered with.
+    fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let name = &self.config.name;
+        let mut child = Command::new(&self.config.command[0])
+            .args(&self.config.command[1..])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Cannot run plugin {}: {}", name, self.config.command.join(" ")))?;
+        let request = json!({"protocol": PROTOCOL_VERSION, "method": method, "params": params}).to_string();
+        let mut stdin = child.stdin.take().expect("standard input is piped");
+        // Written from another thread, so a plugin answering before it read
+        // everything cannot block on a full pipe
+        let writer = std::thread::spawn(move || match stdin.write_all(request.as_bytes()) {
+            Err(e) if e.kind() != ErrorKind::BrokenPipe => Err(e),
+            _ => Ok(()),
+        });
+        let output = child.wait_with_output().with_context(|| format!("Cannot run plugin {}", name))?;
+        writer.join().expect("the writer does not panic").with_context(|| format!("Cannot write to plugin {}", name))?;
+        if !output.status.success() {
+            return Err(PyhelperError::Invalid(format!("Plugin {} failed: {}", name, output.status)).into());
+        }
+        let response: Response = serde_json::from_slice(&output.stdout)
+            .with_context(|| format!("Invalid response of plugin {} to {}", name, method))?;
+        match response {
+            Response { error: Some(message), .. } => {
+                Err(PyhelperError::Invalid(format!("Plugin {} failed: {}", name, message)).into())
+            }
+            Response { result, .. } => Ok(result.unwrap_or(Value::Null)),
+        }
+    }
+}
+
+impl AdvisorySource for SubprocessPlugin {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// Asks with `{"packages": [{"name": ..., "version": ...}]}` and expects
+    /// `{"advisories": [...]}`, each advisory naming the package and version
+    /// it affects besides the fields of `audit --format json`. Advisories of
+    /// versions that were not asked about are left out.
+    fn advisories(&self, packages: &[Audited]) -> Result<Vec<Finding>> {
+        let queries: Vec<Value> =
+            packages.iter().map(|p| json!({"name": p.name, "version": p.version.to_string()})).collect();
+        let result = self.call("advisories", json!({"packages": queries}))?;
+        let invalid = || format!("Invalid advisories from plugin {}", self.config.name);
+        let result: AdvisoriesResult = serde_json::from_value(result).with_context(invalid)?;
+
+        let mut findings: Vec<Finding> = Vec::new();
+        for advisory in result.advisories {
+            let version = Pep440Version::parse(&advisory.version).with_context(invalid)?;
+            let name = canonicalize_name(&advisory.name);
+            let Some(package) = packages.iter().find(|p| canonicalize_name(&p.name) == name && p.version == version) else {
+                continue;
+            };
+            let parsed = Advisory {
+                id: advisory.id,
+                aliases: advisory.aliases,
+                summary: advisory.summary,
+                severity: advisory.severity.as_deref().map(str::parse::<Severity>).transpose().with_context(invalid)?,
+                score: advisory.score,
+                fixed: advisory.fixed.iter().map(|v| Pep440Version::parse(v)).collect::<Result<_>>().with_context(invalid)?,
+            };
+            match findings.iter_mut().find(|f| f.package.name == package.name && f.package.version == package.version) {
+                Some(finding) => finding.advisories.push(parsed),
+                None => findings.push(Finding { package: package.clone(), advisories: vec![parsed] }),
+            }
+        }
+        Ok(findings)
+    }
+}
+
+impl Sink for SubprocessPlugin {
+    fn name(&self) -> &str {
+        &self.config.name
+    }
+
+    /// Sends `{"command": ..., "report": ...}` and ignores the result.
+    fn send(&self, command: &str, report: &Value) -> Result<()> {
+        self.call("report", json!({"command": command, "report": report})).map(|_| ())
+    }
+}
+
+/// Adds the findings of another source to `findings`. An advisory already
+/// reported for the package, under its id or one of its aliases, is not
+/// added again.
+pub fn merge_findings(findings: &mut Vec<Finding>, extra: Vec<Finding>) {
+    for finding in extra {
+        let same = |f: &&mut Finding| f.package.name == finding.package.name && f.package.version == finding.package.version;
+        let Some(existing) = findings.iter_mut().find(same) else {
+            findings.push(finding);
+            continue;
+        };
+        for advisory in finding.advisories {
+            let known = existing.advisories.iter().any(|known| {
+                let ids = || std::iter::once(&known.id).chain(&known.aliases);
+                std::iter::once(&advisory.id).chain(&advisory.aliases).any(|id| ids().any(|k| k == id))
+            });
+            if !known {
+                existing.advisories.push(advisory);
+            }
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn plugin(script: &str) -> SubprocessPlugin {
+        let command = vec!["sh".to_string(), "-c".to_string(), script.to_string()];
+        SubprocessPlugin::new(PluginConfig { name: "internal".to_string(), command, provides: vec![Capability::Advisories] })
+            .unwrap()
+    }
+
+    #[test]
+    fn test_advisories() -> Result<()> {
+        let audited = |name: &str, version: &str| Audited {
+            name: name.to_string(),
+            version: Pep440Version::parse(version).unwrap(),
+            file: PathBuf::from("requirements.txt"),
+            line: 1,
+            columns: None,
+            resolved: false,
+        };
+        let packages = vec![audited("Django", "4.2.0"), audited("requests", "2.31.0")];
+        let source = plugin(
+            r#"cat > /dev/null; echo '{"result": {"advisories": [
+                {"name": "django", "version": "4.2.0", "id": "INTERNAL-1", "aliases": ["CVE-2024-1"], "severity": "high", "fixed": ["4.2.1"]},
+                {"name": "django", "version": "3.2.0", "id": "INTERNAL-2"}
+            ]}}'"#,
+        );
+        let extra = source.advisories(&packages)?;
+        assert_eq!(extra.len(), 1);
+        assert_eq!(extra[0].package.name, "Django");
+        assert_eq!(extra[0].advisories[0].severity, Some(Severity::High));
+
+        let known = Advisory {
+            id: "GHSA-xxxx".to_string(),
+            aliases: vec!["CVE-2024-1".to_string()],
+            summary: None,
+            severity: None,
+            score: None,
+            fixed: Vec::new(),
+        };
+        let mut findings = vec![Finding { package: packages[0].clone(), advisories: vec![known] }];
+        merge_findings(&mut findings, extra);
+        assert_eq!(findings[0].advisories.len(), 1);
+
+        let err = plugin(r#"echo '{"error": "database unavailable"}'"#).advisories(&packages).unwrap_err();
+        assert_eq!(err.to_string(), "Plugin internal failed: database unavailable");
+        assert!(plugin("exit 3").advisories(&packages).is_err());
+        Ok(())
+    }
+}
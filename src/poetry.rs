@@ -0,0 +1,211 @@
+use anyhow::{bail, Result};
+
+use crate::error::PyhelperError;
+use crate::marker::MarkerTree;
+use crate::merge;
+use crate::package::PythonPackage;
+use crate::pep440::Pep440Version;
+use crate::prerelease;
+use crate::specifier::SpecifierSet;
+
+/// Translates a Poetry version constraint into PEP 440 specifiers:
+///
+/// - `^1.2.3` allows changes keeping the leftmost non-zero segment, so it
+///   means `>=1.2.3,<2`, and `^0.2.3` means `>=0.2.3,<0.3`.
+/// - `~1.2.3` allows patch-level changes (`>=1.2.3,<1.3`), and `~1` minor
+///   ones (`>=1,<2`). `~=` keeps its PEP 440 meaning.
+/// - A bare version such as `1.4` or `1.4.*`, or one after `=`, is exact.
+/// - Constraints separated by commas or spaces must all hold.
+/// - Alternatives separated by `||` become the range covering all of them.
+///   Alternatives with final releases between them, such as `^1.0 || ^3.0`,
+///   are a [`PyhelperError::Resolution`], as no set of PEP 440 clauses allows
+///   just what they allow.
+pub fn specifiers(constraint: &str) -> Result<SpecifierSet> {
+    let mut alternatives = alternatives(constraint)?;
+    if alternatives.len() == 1 {
+        return Ok(alternatives.remove(0));
+    }
+    let packages: Vec<PythonPackage> = alternatives
+        .into_iter()
+        .map(|specifiers| PythonPackage { name: String::new(), extras: Vec::new(), specifiers, marker: None, url: None })
+        .collect();
+    let union = merge::union(&packages)?;
+    // Only the pre-releases of `2.0` separate `^1.2 || ^2.0`, and they are left out anyway
+    if prerelease::is_satisfiable(&union.widened, false) {
+        let written: Vec<&str> = constraint.split('|').map(str::trim).filter(|a| !a.is_empty()).collect();
+        bail!(PyhelperError::Resolution(format!(
+            "Poetry constraint {} cannot be checked: no one range allows {} without the versions between them",
+            constraint.trim(),
+            written.join(" or ")
+        )));
+    }
+    Ok(union.package.specifiers)
+}
+
+/// The marker a dependency's `python` constraint stands for, such as
+/// `python_version >= "3.8" and python_version < "4"` for `^3.8`, or `None`
+/// when it allows any version. Versions of three segments compare against
+/// `python_full_version`.
+pub fn python_marker(constraint: &str) -> Result<Option<MarkerTree>> {
+    let alternatives: Vec<String> = alternatives(constraint)?
+        .iter()
+        .filter(|specifiers| !specifiers.is_empty())
+        .map(|specifiers| {
+            let clauses: Vec<String> = specifiers
+                .specifiers
+                .iter()
+                .map(|spec| {
+                    let text = spec.to_string();
+                    let version = text.trim_start_matches(|c: char| "=!<>~".contains(c));
+                    let variable = if !spec.wildcard && spec.version.release.len() > 2 { "python_full_version" } else { "python_version" };
+                    format!("{} {} \"{}\"", variable, &text[..text.len() - version.len()], version)
+                })
+                .collect();
+            clauses.join(" and ")
+        })
+        .collect();
+    // An alternative allowing any version makes the whole constraint do so
+    if alternatives.is_empty() || alternatives.len() < constraint.split('|').filter(|a| !a.trim().is_empty()).count() {
+        return Ok(None);
+    }
+    let marker = match alternatives.as_slice() {
+        [single] => single.clone(),
+        _ => alternatives.iter().map(|a| format!("({})", a)).collect::<Vec<_>>().join(" or "),
+    };
+    Ok(Some(MarkerTree::parse(&marker)?))
+}
+
+/// The specifiers of every `||` alternative of `constraint`.
+fn alternatives(constraint: &str) -> Result<Vec<SpecifierSet>> {
+    let invalid = || PyhelperError::Specifier(format!("Invalid Poetry constraint: {}", constraint.trim()));
+    // A single `|` separates alternatives too
+    let alternatives: Vec<&str> = constraint.split('|').filter(|a| !a.trim().is_empty()).collect();
+    if alternatives.is_empty() {
+        return Ok(vec![SpecifierSet::default()]);
+    }
+    alternatives
+        .into_iter()
+        .map(|alternative| {
+            let clauses = clauses(alternative).into_iter().map(|clause| translate(&clause)).collect::<Result<Vec<_>>>();
+            let clauses = clauses.map_err(|_| invalid())?;
+            let joined: Vec<String> = clauses.into_iter().flatten().collect();
+            SpecifierSet::parse(&joined.join(",")).map_err(|_| invalid().into())
+        })
+        .collect()
+}
+
+/// The clauses of an alternative, separated by commas or whitespace. An
+/// operator followed by a space, as in `>= 1.2`, stays with its version.
+fn clauses(alternative: &str) -> Vec<String> {
+    let mut clauses: Vec<String> = Vec::new();
+    let mut operator = String::new();
+    for token in alternative.split(|c: char| c == ',' || c.is_whitespace()).filter(|t| !t.is_empty()) {
+        if token.chars().all(|c| "^~=!<>".contains(c)) {
+            operator.push_str(token);
+        } else {
+            clauses.push(format!("{}{}", std::mem::take(&mut operator), token));
+        }
+    }
+    if !operator.is_empty() {
+        clauses.push(operator);
+    }
+    clauses
+}
+
+/// One Poetry clause as PEP 440 clauses, none for `*`.
+fn translate(clause: &str) -> Result<Vec<String>> {
+    if clause == "*" {
+        return Ok(Vec::new());
+    }
+    if let Some(version) = clause.strip_prefix('^') {
+        let version = Pep440Version::parse(version)?;
+        // Allow changes that keep the leftmost non-zero segment
+        let index = version
+            .release
+            .iter()
+            .position(|&segment| segment != 0)
+            .unwrap_or(version.release.len() - 1);
+        return Ok(vec![format!(">={}", version), format!("<{}", bumped(&version, index))]);
+    }
+    if let Some(version) = clause.strip_prefix('~').filter(|v| !v.starts_with('=')) {
+        let version = Pep440Version::parse(version)?;
+        // Allow patch-level changes, or minor-level when only a major is given
+        let index = if version.release.len() >= 2 { 1 } else { 0 };
+        return Ok(vec![format!(">={}", version), format!("<{}", bumped(&version, index))]);
+    }
+    if clause.starts_with(|c: char| c.is_ascii_digit()) {
+        return Ok(vec![format!("=={}", clause)]);
+    }
+    match clause.strip_prefix('=').filter(|v| v.starts_with(|c: char| c.is_ascii_digit())) {
+        Some(version) => Ok(vec![format!("=={}", version)]),
+        None => Ok(vec![clause.to_string()]),
+    }
+}
+
+/// The release number with the segment at `index` incremented and later ones dropped.
+fn bumped(version: &Pep440Version, index: usize) -> String {
+    let mut release = version.release[..=index].to_vec();
+    release[index] += 1;
+    let segments: Vec<String> = release.iter().map(|n| n.to_string()).collect();
+    match version.epoch {
+        0 => segments.join("."),
+        epoch => format!("{}!{}", epoch, segments.join(".")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_specifiers() -> Result<()> {
+        let cases = vec![
+            ("^1.2.3", ">=1.2.3,<2"),
+            ("^0.2.3", ">=0.2.3,<0.3"),
+            ("^0.0.3", ">=0.0.3,<0.0.4"),
+            ("^0.0", ">=0.0,<0.1"),
+            ("~1.2.3", ">=1.2.3,<1.3"),
+            ("~1", ">=1,<2"),
+            ("~=1.2", "~=1.2"),
+            ("1.4", "==1.4"),
+            ("=1.4", "==1.4"),
+            ("1.4.*", "==1.4.*"),
+            ("*", ""),
+            ("", ""),
+            (">=2.0,<3.0", ">=2.0,<3.0"),
+            (">= 2.0 < 3.0", ">=2.0,<3.0"),
+            ("^1.2, !=1.2.5", ">=1.2,<2,!=1.2.5"),
+            ("^1.2 || ^2.0", ">=1.2,<3"),
+            ("~1.2 | ~1.3", ">=1.2,<1.4"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(specifiers(input)?.to_string(), expected, "{}", input);
+        }
+        assert!(specifiers("^banana").is_err());
+        assert!(specifiers(">=").is_err());
+        // Nothing but the alternatives themselves may be allowed
+        let err = specifiers("^1.0 || ^3.0").unwrap_err();
+        assert!(matches!(err.downcast_ref::<PyhelperError>(), Some(PyhelperError::Resolution(_))));
+        assert_eq!(
+            err.to_string(),
+            "Poetry constraint ^1.0 || ^3.0 cannot be checked: no one range allows ^1.0 or ^3.0 without the versions between them"
+        );
+        assert!(specifiers("~1.2 | ~1.5").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_python_marker() -> Result<()> {
+        let marker = |constraint: &str| -> Result<Option<String>> { Ok(python_marker(constraint)?.map(|m| m.to_string())) };
+        assert_eq!(marker("^3.8")?.as_deref(), Some("python_version >= \"3.8\" and python_version < \"4\""));
+        assert_eq!(marker(">=3.8.1")?.as_deref(), Some("python_full_version >= \"3.8.1\""));
+        assert_eq!(marker("3.9.*")?.as_deref(), Some("python_version == \"3.9.*\""));
+        assert_eq!(
+            marker("~2.7 || >=3.6")?.as_deref(),
+            Some("python_version >= \"2.7\" and python_version < \"2.8\" or python_version >= \"3.6\"")
+        );
+        assert_eq!(marker("*")?, None);
+        assert_eq!(marker("^3.8 || *")?, None);
+        Ok(())
+    }
+}
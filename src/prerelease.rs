@@ -0,0 +1,133 @@
+use std::ops::Bound;
+
+use crate::pep440::Pep440Version;
+use crate::range::{Interval, IntervalSet};
+use crate::specifier::SpecifierSet;
+
+/// Whether pre-releases may satisfy requirements. PEP 440 excludes them unless
+/// they are explicitly requested.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PreReleasePolicy {
+    /// Allow pre-releases of a package only when every requirement on it names
+    /// one, like pip.
+    #[default]
+    Auto,
+    /// Always allow pre-releases, like `pip install --pre`.
+    Allow,
+    /// Never allow pre-releases, even when a requirement names one.
+    Deny,
+}
+
+impl PreReleasePolicy {
+    /// Whether pre-releases can satisfy all of the given specifier sets together.
+    pub fn allows(self, specifiers: &[&SpecifierSet]) -> bool {
+        match self {
+            PreReleasePolicy::Auto => specifiers.iter().all(|s| s.mentions_prerelease()),
+            PreReleasePolicy::Allow => true,
+            PreReleasePolicy::Deny => false,
+        }
+    }
+}
+
+/// Whether the range holds a version that is acceptable when pre-releases are
+/// (or are not) allowed.
+pub fn is_satisfiable(range: &IntervalSet<Pep440Version>, prereleases: bool) -> bool {
+    if prereleases {
+        return !range.is_empty();
+    }
+    // The range is convex per interval, so checking its smallest candidate suffices
+    range
+        .intervals
+        .iter()
+        .any(|interval| interval.contains(&first_final(interval)))
+}
+
+/// The smallest version that is not a pre-release or development release and
+/// satisfies the lower bound of `interval`, e.g. `2.0` for `>=2.0rc1` and
+/// `2.0.post1` for `>2.0.post0`. When no smallest one exists, as past the
+/// post-releases of `>2.0`, it is one that is below the upper bound if any is.
+pub(crate) fn first_final(interval: &Interval<Pep440Version>) -> Pep440Version {
+    let (version, excluded) = match &interval.lower {
+        Bound::Included(v) => (v, false),
+        Bound::Excluded(v) => (v, true),
+        Bound::Unbounded => {
+            return Pep440Version {
+                epoch: 0,
+                release: vec![0],
+                pre: None,
+                post: None,
+                dev: None,
+                local: Vec::new(),
+            }
+        }
+    };
+    if version.is_prerelease() {
+        // `1.0rc1` and `1.0.dev0` precede `1.0`; `1.0.post1.dev0` precedes `1.0.post1`
+        return Pep440Version {
+            epoch: version.epoch,
+            release: version.release.clone(),
+            pre: None,
+            post: version.post.filter(|_| version.pre.is_none()),
+            dev: None,
+            local: Vec::new(),
+        };
+    }
+    let mut candidate = version.clone();
+    if excluded && version.post == Some(u64::MAX) {
+        // Past every post-release of `2.0`, as for `>2.0`, come `2.0.1`,
+        // `2.0.0.1`, and so on without end; `<2.0.0.0.1` still admits `2.0.0.0.0.1`
+        let segments = match &interval.upper {
+            Bound::Included(upper) | Bound::Excluded(upper) => upper.release.len(),
+            Bound::Unbounded => 0,
+        };
+        candidate.release.resize(candidate.release.len().max(segments), 0);
+        candidate.release.push(1);
+        candidate.post = None;
+    } else if excluded {
+        candidate.post = Some(candidate.post.map_or(0, |post| post + 1));
+        candidate.local.clear();
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_policy() -> Result<()> {
+        let opted_in = SpecifierSet::parse(">=2.0.0rc1")?;
+        let excluded = SpecifierSet::parse("!=2.0rc1")?;
+        let plain = SpecifierSet::parse("<3")?;
+        assert!(PreReleasePolicy::Auto.allows(&[&opted_in]));
+        assert!(!PreReleasePolicy::Auto.allows(&[&excluded]));
+        assert!(!PreReleasePolicy::Auto.allows(&[&opted_in, &plain]));
+        assert!(PreReleasePolicy::Allow.allows(&[&plain]));
+        assert!(!PreReleasePolicy::Deny.allows(&[&opted_in]));
+        // A dev release counts as a pre-release, so naming one opts in too
+        assert!(PreReleasePolicy::Auto.allows(&[&SpecifierSet::parse(">=2.0.dev3")?]));
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_satisfiable() -> Result<()> {
+        let finals = |spec: &str| -> Result<bool> { Ok(is_satisfiable(&SpecifierSet::parse(spec)?.range(), false)) };
+        assert!(!finals(">=2.0rc1,<2.0")?);
+        assert!(!finals("==2.0b1")?);
+        assert!(!finals(">=1.0.post1.dev0,<1.0.post1")?);
+        assert!(finals(">=1.0.post1.dev0,<=1.0.post1")?);
+        assert!(finals(">=2.0rc1,<=2.0")?);
+        assert!(finals(">2.0,<2.0.1")?);
+        assert!(finals(">1.0,<1.0.0.0.1")?);
+        assert!(!finals(">1.0,<1.0.post5")?);
+        assert!(finals("<1")?);
+        assert!(!finals("<0")?);
+        assert!(!finals("==2.0.*,<2.0rc3")?);
+        assert!(is_satisfiable(&SpecifierSet::parse("==2.0.*,<2.0rc3")?.range(), true));
+        // `<2.0` excludes the pre-releases of 2.0, even when they are allowed
+        assert!(!is_satisfiable(&SpecifierSet::parse(">=2.0rc1,<2.0")?.range(), true));
+        assert!(is_satisfiable(&SpecifierSet::parse(">=2.0rc1,<2.0rc3")?.range(), true));
+        Ok(())
+    }
+}
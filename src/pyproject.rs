@@ -0,0 +1,359 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use toml::Spanned;
+use tracing::{debug, instrument, warn};
+
+use crate::convert::Index;
+use crate::error::PyhelperError;
+use crate::marker::MarkerTree;
+use crate::poetry;
+use crate::requirements::{self, RequirementLine};
+use crate::specifier::SpecifierSet;
+use crate::package::PythonPackage;
+
+#[derive(Debug, Default, Deserialize)]
+struct PyProject {
+    #[serde(default)]
+    project: Project,
+    #[serde(default)]
+    tool: Tool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Project {
+    name: Option<String>,
+    version: Option<String>,
+    #[serde(default)]
+    dependencies: Vec<Spanned<String>>,
+    #[serde(default, rename = "optional-dependencies")]
+    optional_dependencies: BTreeMap<String, Vec<Spanned<String>>>,
+    #[serde(rename = "requires-python")]
+    requires_python: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Tool {
+    #[serde(default)]
+    poetry: Poetry,
+    #[serde(default)]
+    uv: Uv,
+    #[serde(default)]
+    pdm: Pdm,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct Uv {
+    #[serde(default)]
+    index: Vec<UvIndex>,
+    index_url: Option<String>,
+    #[serde(default)]
+    extra_index_url: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct UvIndex {
+    name: Option<String>,
+    url: String,
+    #[serde(default)]
+    default: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Pdm {
+    #[serde(default)]
+    source: Vec<Source>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Source {
+    name: Option<String>,
+    url: String,
+    priority: Option<String>,
+    // Before Poetry 1.5 a boolean marked the source replacing PyPI
+    #[serde(default)]
+    default: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Poetry {
+    name: Option<String>,
+    version: Option<String>,
+    #[serde(default)]
+    dependencies: BTreeMap<String, Spanned<PoetryDependency>>,
+    #[serde(default, rename = "dev-dependencies")]
+    dev_dependencies: BTreeMap<String, Spanned<PoetryDependency>>,
+    #[serde(default)]
+    group: BTreeMap<String, PoetryGroup>,
+    #[serde(default)]
+    source: Vec<Source>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PoetryGroup {
+    #[serde(default)]
+    dependencies: BTreeMap<String, Spanned<PoetryDependency>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum PoetryDependency {
+    Constraint(String),
+    Detailed(PoetryDetail),
+    Multiple(Vec<PoetryDetail>),
+}
+
+#[derive(Debug, Deserialize)]
+struct PoetryDetail {
+    version: Option<String>,
+    markers: Option<String>,
+    // The Python versions the dependency is needed on
+    python: Option<String>,
+    #[serde(default)]
+    extras: Vec<String>,
+}
+
+/// Reads every dependency declared in a pyproject.toml, covering PEP 621
+/// `[project]` tables as well as Poetry's `[tool.poetry]` dependency tables.
+#[instrument(skip_all, err, fields(file = %path.display()))]
+pub fn parse_file(path: &Path) -> Result<Vec<RequirementLine>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Cannot read pyproject file: {}", path.display()))?;
+    let requirements = parse_str(&content, path)?;
+    debug!(requirements = requirements.len(), "parsed");
+    Ok(requirements)
+}
+
+/// Like [`parse_file`], for the content of the pyproject.toml at `path`.
+pub fn parse_str(content: &str, path: &Path) -> Result<Vec<RequirementLine>> {
+    let pyproject: PyProject = toml::from_str(content)
+        .with_context(|| format!("Invalid pyproject file: {}", path.display()))?;
+
+    // A requirement string without its quotes; a Poetry entry from its key
+    let string_columns = |span: std::ops::Range<usize>| {
+        let (line, columns) = requirements::span_columns(content, span.start + 1..span.end.saturating_sub(1));
+        (line, Some(columns))
+    };
+    let entry_columns = |name: &str, span: std::ops::Range<usize>| {
+        let (line, value) = requirements::span_columns(content, span);
+        (line, requirements::columns_of(content, line, name).map(|key| key.start..value.end.max(key.end)))
+    };
+    let mut requirements = Vec::new();
+
+    let project = &pyproject.project;
+    let pep621 = project
+        .dependencies
+        .iter()
+        .chain(project.optional_dependencies.values().flatten());
+    for dependency in pep621 {
+        let (line, columns) = string_columns(dependency.span());
+        let column = columns.as_ref().map_or(1, |c| c.start);
+        let package = PythonPackage::parse(dependency.get_ref())
+            .map_err(|e| PyhelperError::parse(path, line, Some(column), e))?;
+        requirements.push(RequirementLine {
+            package,
+            file: path.to_path_buf(),
+            line,
+            columns,
+            hashes: Vec::new(),
+        });
+    }
+
+    let poetry = &pyproject.tool.poetry;
+    let poetry_tables = std::iter::once(&poetry.dependencies)
+        .chain(std::iter::once(&poetry.dev_dependencies))
+        .chain(poetry.group.values().map(|group| &group.dependencies));
+    for (name, dependency) in poetry_tables.flatten() {
+        // The interpreter constraint is not a package
+        if name == "python" {
+            continue;
+        }
+        let (line, columns) = entry_columns(name, dependency.span());
+        let column = columns.as_ref().map_or(1, |c| c.start);
+        let located = |e: anyhow::Error| PyhelperError::parse(path, line, Some(column), e);
+        // Alternatives no range can hold leave out the dependency, not the whole file
+        let constraint = |c: &str| match poetry::specifiers(c) {
+            Err(e) if matches!(e.downcast_ref::<PyhelperError>(), Some(PyhelperError::Resolution(_))) => {
+                warn!("{}:{}: skipped {}, as {}", path.display(), line, name, e);
+                Ok(None)
+            }
+            specifiers => specifiers.map(Some).map_err(located),
+        };
+        let details: Vec<&PoetryDetail> = match dependency.get_ref() {
+            PoetryDependency::Constraint(c) => {
+                let Some(specifiers) = constraint(c)? else {
+                    continue;
+                };
+                requirements.push(RequirementLine {
+                    package: PythonPackage {
+                        name: name.clone(),
+                        extras: Vec::new(),
+                        specifiers,
+                        marker: None,
+                        url: None,
+                    },
+                    file: path.to_path_buf(),
+                    line,
+                    columns: columns.clone(),
+                    hashes: Vec::new(),
+                });
+                continue;
+            }
+            PoetryDependency::Detailed(detail) => vec![detail],
+            PoetryDependency::Multiple(details) => details.iter().collect(),
+        };
+        for detail in details {
+            let specifiers = match detail.version.as_deref().map(constraint).transpose()? {
+                Some(Some(specifiers)) => specifiers,
+                Some(None) => continue,
+                None => SpecifierSet::default(),
+            };
+            let markers = detail.markers.as_deref().map(MarkerTree::parse).transpose().map_err(located)?;
+            let python = match &detail.python {
+                Some(python) => poetry::python_marker(python).map_err(located)?,
+                None => None,
+            };
+            let marker = match (markers, python) {
+                (Some(markers), Some(python)) => Some(MarkerTree::parse(&format!("({}) and ({})", markers, python)).map_err(located)?),
+                (markers, python) => markers.or(python),
+            };
+            requirements.push(RequirementLine {
+                package: PythonPackage {
+                    name: name.clone(),
+                    extras: detail.extras.clone(),
+                    specifiers,
+                    marker,
+                    url: None,
+                },
+                file: path.to_path_buf(),
+                line,
+                columns: columns.clone(),
+                hashes: Vec::new(),
+            });
+        }
+    }
+
+    Ok(requirements)
+}
+
+/// The name and version a pyproject.toml gives its own project, from
+/// `[project]` or else `[tool.poetry]`. The version is `None` when it is
+/// computed at build time.
+pub fn project_metadata(content: &str) -> Result<Option<(String, Option<String>)>> {
+    let pyproject: PyProject = toml::from_str(content)?;
+    let (project, poetry) = (pyproject.project, pyproject.tool.poetry);
+    Ok(match (project.name, poetry.name) {
+        (Some(name), _) => Some((name, project.version)),
+        (None, Some(name)) => Some((name, poetry.version)),
+        (None, None) => None,
+    })
+}
+
+/// The Python versions a pyproject.toml supports: `requires-python`, or else
+/// the `python` entry of Poetry's dependencies.
+pub fn requires_python(content: &str) -> Result<Option<SpecifierSet>> {
+    let pyproject: PyProject = toml::from_str(content)?;
+    if let Some(requires) = &pyproject.project.requires_python {
+        return Ok(Some(SpecifierSet::parse(requires)?));
+    }
+    let constraint = match pyproject.tool.poetry.dependencies.get("python").map(|dependency| dependency.get_ref()) {
+        Some(PoetryDependency::Constraint(c)) => c,
+        Some(PoetryDependency::Detailed(PoetryDetail { version: Some(c), .. })) => c,
+        _ => return Ok(None),
+    };
+    Ok(Some(poetry::specifiers(constraint)?))
+}
+
+/// The package indexes a pyproject.toml installs from: uv's `[[tool.uv.index]]`
+/// and `index-url` settings, Poetry's `[[tool.poetry.source]]` and PDM's
+/// `[[tool.pdm.source]]`. An index is primary when it replaces PyPI: uv's
+/// default index, a Poetry source of `primary` priority, or a PDM source
+/// named `pypi`.
+pub fn indexes(content: &str) -> Result<Vec<Index>> {
+    let pyproject: PyProject = toml::from_str(content)?;
+    let Tool { poetry, uv, pdm } = pyproject.tool;
+    let mut indexes: Vec<Index> = uv
+        .index
+        .into_iter()
+        .map(|index| Index { name: index.name, url: index.url, primary: index.default })
+        .collect();
+    indexes.extend(uv.index_url.map(|url| Index { name: None, url, primary: true }));
+    indexes.extend(uv.extra_index_url.into_iter().map(|url| Index { name: None, url, primary: false }));
+    indexes.extend(poetry.source.into_iter().map(|source| Index {
+        primary: source.default || matches!(source.priority.as_deref(), Some("primary" | "default")),
+        name: source.name,
+        url: source.url,
+    }));
+    indexes.extend(pdm.source.into_iter().map(|source| Index {
+        primary: source.name.as_deref() == Some("pypi"),
+        name: source.name,
+        url: source.url,
+    }));
+    Ok(indexes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_file() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("pyhelper-pyproject-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("pyproject.toml");
+        fs::write(
+            &path,
+            r#"[project]
+name = "demo"
+dependencies = [
+    "requests>=2.28",
+]
+
+[project.optional-dependencies]
+test = ["pytest>=7"]
+
+[tool.poetry.dependencies]
+python = "^3.8"
+django = { version = "^4.2", optional = true, extras = ["bcrypt"] }
+internal = { git = "https://example.com/internal.git" }
+pywin32 = [
+    { version = ">=300", markers = "sys_platform == 'win32'", python = ">=3.9 <3.13" },
+]
+
+[tool.poetry.group.dev.dependencies]
+black = "~23.1"
+attrs = "^1.0 || ^3.0"
+"#,
+        )?;
+
+        let requirements = parse_file(&path)?;
+        let found: Vec<(String, String, usize)> = requirements
+            .iter()
+            .map(|r| (r.package.name.clone(), r.package.version_spec(), r.line))
+            .collect();
+        assert_eq!(
+            requirements[4].package.marker.as_ref().map(|m| m.to_string()).as_deref(),
+            Some("sys_platform == \"win32\" and python_version >= \"3.9\" and python_version < \"3.13\"")
+        );
+        assert_eq!(requires_python(&fs::read_to_string(&path)?)?.map(|s| s.to_string()).as_deref(), Some(">=3.8,<4"));
+        assert_eq!(requirements[2].package.extras, vec!["bcrypt"]);
+        assert_eq!(
+            found,
+            vec![
+                ("requests".to_string(), ">=2.28".to_string(), 4),
+                ("pytest".to_string(), ">=7".to_string(), 8),
+                ("django".to_string(), ">=4.2,<5".to_string(), 12),
+                ("internal".to_string(), "*".to_string(), 13),
+                ("pywin32".to_string(), ">=300".to_string(), 14),
+                ("black".to_string(), ">=23.1,<23.2".to_string(), 19),
+            ]
+        );
+        // attrs allows more than one range can, so it is skipped with a warning
+        assert!(requirements.iter().all(|r| r.package.name != "attrs"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+use std::cmp::Ordering;
+use std::fmt;
+use std::ops::Bound;
+
+/// A contiguous range of versions with independent lower and upper bounds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Interval<V> {
+    pub lower: Bound<V>,
+    pub upper: Bound<V>,
+}
+
+impl<V: Ord + Clone> Interval<V> {
+    pub fn new(lower: Bound<V>, upper: Bound<V>) -> Self {
+        Interval { lower, upper }
+    }
+
+    /// The interval containing every version.
+    pub fn full() -> Self {
+        Interval::new(Bound::Unbounded, Bound::Unbounded)
+    }
+
+    pub fn exact(version: V) -> Self {
+        Interval::new(Bound::Included(version.clone()), Bound::Included(version))
+    }
+
+    pub fn at_least(version: V) -> Self {
+        Interval::new(Bound::Included(version), Bound::Unbounded)
+    }
+
+    pub fn greater_than(version: V) -> Self {
+        Interval::new(Bound::Excluded(version), Bound::Unbounded)
+    }
+
+    pub fn at_most(version: V) -> Self {
+        Interval::new(Bound::Unbounded, Bound::Included(version))
+    }
+
+    pub fn less_than(version: V) -> Self {
+        Interval::new(Bound::Unbounded, Bound::Excluded(version))
+    }
+
+    /// Half-open interval `[lower, upper)`.
+    pub fn between(lower: V, upper: V) -> Self {
+        Interval::new(Bound::Included(lower), Bound::Excluded(upper))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        match (&self.lower, &self.upper) {
+            (Bound::Included(lo), Bound::Included(hi)) => lo > hi,
+            (Bound::Included(lo), Bound::Excluded(hi))
+            | (Bound::Excluded(lo), Bound::Included(hi))
+            | (Bound::Excluded(lo), Bound::Excluded(hi)) => lo >= hi,
+            _ => false,
+        }
+    }
+
+    pub fn contains(&self, version: &V) -> bool {
+        let above_lower = match &self.lower {
+            Bound::Included(lo) => version >= lo,
+            Bound::Excluded(lo) => version > lo,
+            Bound::Unbounded => true,
+        };
+        let below_upper = match &self.upper {
+            Bound::Included(hi) => version <= hi,
+            Bound::Excluded(hi) => version < hi,
+            Bound::Unbounded => true,
+        };
+        above_lower && below_upper
+    }
+
+    /// Every version below the interval, or `None` when it has no lower bound.
+    pub fn below(&self) -> Option<Interval<V>> {
+        match &self.lower {
+            Bound::Unbounded => None,
+            lower => Some(Interval::new(Bound::Unbounded, flip(lower))),
+        }
+    }
+
+    /// Every version above the interval, or `None` when it has no upper bound.
+    pub fn above(&self) -> Option<Interval<V>> {
+        match &self.upper {
+            Bound::Unbounded => None,
+            upper => Some(Interval::new(flip(upper), Bound::Unbounded)),
+        }
+    }
+
+    pub fn intersect(&self, other: &Interval<V>) -> Interval<V> {
+        let lower = match cmp_lower(&self.lower, &other.lower) {
+            Ordering::Less => other.lower.clone(),
+            _ => self.lower.clone(),
+        };
+        let upper = match cmp_upper(&self.upper, &other.upper) {
+            Ordering::Greater => other.upper.clone(),
+            _ => self.upper.clone(),
+        };
+        Interval { lower, upper }
+    }
+}
+
+/// A union of disjoint intervals, kept sorted by lower bound.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IntervalSet<V> {
+    pub intervals: Vec<Interval<V>>,
+}
+
+impl<V: Ord + Clone> IntervalSet<V> {
+    pub fn full() -> Self {
+        IntervalSet::from(Interval::full())
+    }
+
+    /// Every version except `version` itself.
+    pub fn excluding(version: V) -> Self {
+        IntervalSet {
+            intervals: vec![Interval::less_than(version.clone()), Interval::greater_than(version)],
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    pub fn contains(&self, version: &V) -> bool {
+        self.intervals.iter().any(|i| i.contains(version))
+    }
+
+    /// The smallest single interval covering the whole set.
+    pub fn hull(&self) -> Option<Interval<V>> {
+        let first = self.intervals.first()?;
+        let last = self.intervals.last()?;
+        Some(Interval::new(first.lower.clone(), last.upper.clone()))
+    }
+
+    /// Every version not contained in this set.
+    pub fn complement(&self) -> IntervalSet<V> {
+        let mut intervals = Vec::new();
+        let mut lower = Bound::Unbounded;
+        for interval in &self.intervals {
+            if !matches!(interval.lower, Bound::Unbounded) {
+                let gap = Interval::new(lower, flip(&interval.lower));
+                if !gap.is_empty() {
+                    intervals.push(gap);
+                }
+            }
+            lower = match flip(&interval.upper) {
+                Bound::Unbounded => return IntervalSet { intervals },
+                bound => bound,
+            };
+        }
+        intervals.push(Interval::new(lower, Bound::Unbounded));
+        IntervalSet { intervals }
+    }
+
+    pub fn intersect(&self, other: &IntervalSet<V>) -> IntervalSet<V> {
+        let mut intervals: Vec<Interval<V>> = self
+            .intervals
+            .iter()
+            .flat_map(|a| other.intervals.iter().map(move |b| a.intersect(b)))
+            .filter(|i| !i.is_empty())
+            .collect();
+        intervals.sort_by(|a, b| cmp_lower(&a.lower, &b.lower));
+        IntervalSet { intervals }
+    }
+
+    /// Every version contained in either set, with touching intervals joined.
+    pub fn union(&self, other: &IntervalSet<V>) -> IntervalSet<V> {
+        self.complement().intersect(&other.complement()).complement()
+    }
+}
+
+impl<V: Ord + Clone> From<Interval<V>> for IntervalSet<V> {
+    fn from(interval: Interval<V>) -> Self {
+        let intervals = if interval.is_empty() { Vec::new() } else { vec![interval] };
+        IntervalSet { intervals }
+    }
+}
+
+/// The bound on the other side of the same point, e.g. `[v` becomes `v)`.
+fn flip<V: Clone>(bound: &Bound<V>) -> Bound<V> {
+    match bound {
+        Bound::Included(v) => Bound::Excluded(v.clone()),
+        Bound::Excluded(v) => Bound::Included(v.clone()),
+        Bound::Unbounded => Bound::Unbounded,
+    }
+}
+
+/// Orders two lower bounds so that the more restrictive one compares greater.
+pub(crate) fn cmp_lower<V: Ord>(a: &Bound<V>, b: &Bound<V>) -> Ordering {
+    match (a, b) {
+        (Bound::Unbounded, Bound::Unbounded) => Ordering::Equal,
+        (Bound::Unbounded, _) => Ordering::Less,
+        (_, Bound::Unbounded) => Ordering::Greater,
+        (Bound::Included(x), Bound::Included(y)) | (Bound::Excluded(x), Bound::Excluded(y)) => {
+            x.cmp(y)
+        }
+        (Bound::Included(x), Bound::Excluded(y)) => x.cmp(y).then(Ordering::Less),
+        (Bound::Excluded(x), Bound::Included(y)) => x.cmp(y).then(Ordering::Greater),
+    }
+}
+
+/// Orders two upper bounds so that the more restrictive one compares less.
+pub(crate) fn cmp_upper<V: Ord>(a: &Bound<V>, b: &Bound<V>) -> Ordering {
+    match (a, b) {
+        (Bound::Unbounded, Bound::Unbounded) => Ordering::Equal,
+        (Bound::Unbounded, _) => Ordering::Greater,
+        (_, Bound::Unbounded) => Ordering::Less,
+        (Bound::Included(x), Bound::Included(y)) | (Bound::Excluded(x), Bound::Excluded(y)) => {
+            x.cmp(y)
+        }
+        (Bound::Included(x), Bound::Excluded(y)) => x.cmp(y).then(Ordering::Greater),
+        (Bound::Excluded(x), Bound::Included(y)) => x.cmp(y).then(Ordering::Less),
+    }
+}
+
+impl<V: fmt::Display> fmt::Display for Interval<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.lower {
+            Bound::Included(v) => write!(f, "[{}", v)?,
+            Bound::Excluded(v) => write!(f, "({}", v)?,
+            Bound::Unbounded => write!(f, "(-inf")?,
+        }
+        write!(f, ", ")?;
+        match &self.upper {
+            Bound::Included(v) => write!(f, "{}]", v),
+            Bound::Excluded(v) => write!(f, "{})", v),
+            Bound::Unbounded => write!(f, "+inf)"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_intersection_bounds() {
+        let a = Interval::at_least(3);
+        let b = Interval::less_than(5);
+        let both = a.intersect(&b);
+        assert_eq!(both, Interval::between(3, 5));
+        assert!(both.contains(&3));
+        assert!(!both.contains(&5));
+
+        // Touching bounds only overlap when both are inclusive
+        assert!(!Interval::exact(4).intersect(&Interval::at_most(4)).is_empty());
+        assert!(Interval::exact(4).intersect(&Interval::less_than(4)).is_empty());
+        assert!(Interval::greater_than(4).intersect(&Interval::at_most(4)).is_empty());
+    }
+
+    #[test]
+    fn test_tighter_bound_wins() {
+        let a = Interval::greater_than(2).intersect(&Interval::at_least(2));
+        assert_eq!(a.lower, Bound::Excluded(2));
+
+        let b = Interval::at_most(7).intersect(&Interval::less_than(7));
+        assert_eq!(b.upper, Bound::Excluded(7));
+
+        assert_eq!(Interval::<i32>::full().intersect(&Interval::exact(1)), Interval::exact(1));
+    }
+
+    #[test]
+    fn test_interval_set_holes() {
+        let around = IntervalSet::excluding(5).intersect(&IntervalSet::from(Interval::between(3, 8)));
+        assert_eq!(
+            around.intervals,
+            vec![
+                Interval::new(Bound::Included(3), Bound::Excluded(5)),
+                Interval::new(Bound::Excluded(5), Bound::Excluded(8)),
+            ]
+        );
+
+        // Excluding the only allowed version leaves nothing
+        let pinned = IntervalSet::from(Interval::exact(5));
+        assert!(pinned.intersect(&IntervalSet::excluding(5)).is_empty());
+        assert!(!pinned.intersect(&IntervalSet::excluding(6)).is_empty());
+    }
+
+    #[test]
+    fn test_complement() {
+        let window = IntervalSet::from(Interval::between(3, 5));
+        assert_eq!(
+            window.complement().intervals,
+            vec![Interval::less_than(3), Interval::at_least(5)]
+        );
+        assert_eq!(window.complement().complement(), window);
+
+        assert!(IntervalSet::<i32>::full().complement().is_empty());
+        assert_eq!(IntervalSet::excluding(4).complement().intervals, vec![Interval::exact(4)]);
+
+        let joined = window.union(&IntervalSet::from(Interval::between(5, 7)));
+        assert_eq!(joined.intervals, vec![Interval::between(3, 7)]);
+        assert_eq!(IntervalSet::excluding(4).union(&IntervalSet::from(Interval::exact(4))), IntervalSet::full());
+    }
+
+    #[test]
+    fn test_below_above_hull() {
+        let window = Interval::between(3, 5);
+        assert_eq!(window.below(), Some(Interval::less_than(3)));
+        assert_eq!(window.above(), Some(Interval::at_least(5)));
+        assert_eq!(Interval::at_least(3).above(), None);
+        assert_eq!(Interval::at_most(3).below(), None);
+
+        assert_eq!(IntervalSet::excluding(4).hull(), Some(Interval::full()));
+        assert_eq!(IntervalSet::<i32>::from(Interval::exact(2).intersect(&Interval::exact(3))).hull(), None);
+    }
+}
@@ -0,0 +1,391 @@
+use anyhow::{Context, Result};
+use regex::Regex;
+use std::collections::HashSet;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tracing::{debug, instrument, warn};
+
+use crate::error::PyhelperError;
+use crate::local;
+use crate::package::PythonPackage;
+
+/// A requirement together with the file and line it was declared on.
+#[derive(Debug, Clone)]
+pub struct RequirementLine {
+    pub package: PythonPackage,
+    pub file: PathBuf,
+    pub line: usize,
+    /// The 1-based columns the requirement spans on `line`, end exclusive,
+    /// when the source has them; requirements read from lock files or
+    /// generated output only have a line.
+    pub columns: Option<Range<usize>>,
+    /// `<algorithm>:<digest>` hashes the installed file must match, from
+    /// `--hash` options or a lock file.
+    pub hashes: Vec<String>,
+}
+
+impl RequirementLine {
+    /// Where the requirement is declared, as `file:line:column`, or
+    /// `file:line` without columns.
+    pub fn location(&self) -> String {
+        match &self.columns {
+            Some(columns) => format!("{}:{}:{}", self.file.display(), self.line, columns.start),
+            None => format!("{}:{}", self.file.display(), self.line),
+        }
+    }
+}
+
+/// The line a byte span of `content` starts on, and the 1-based columns it
+/// spans there, end exclusive, up to the end of that line.
+pub fn span_columns(content: &str, span: Range<usize>) -> (usize, Range<usize>) {
+    let line = content[..span.start].matches('\n').count() + 1;
+    let line_start = content[..span.start].rfind('\n').map_or(0, |i| i + 1);
+    let line_end = content[span.start..].find('\n').map_or(content.len(), |i| span.start + i);
+    (line, span.start - line_start + 1..span.end.min(line_end).max(span.start) - line_start + 1)
+}
+
+/// The 1-based columns `text` spans on line `line` of `content`, end
+/// exclusive, at its first occurrence on that line.
+pub fn columns_of(content: &str, line: usize, text: &str) -> Option<Range<usize>> {
+    let physical = content.lines().nth(line.checked_sub(1)?)?;
+    let start = physical.find(text)? + 1;
+    Some(start..start + text.len())
+}
+
+/// Parses a requirements file, following `-r` includes and `-c` constraints files
+/// relative to the including file. Local paths and editables are named by the
+/// metadata of the project they point to, also relative to the including file.
+#[instrument(skip_all, err, fields(file = %path.display()))]
+pub fn parse_file(path: &Path) -> Result<Vec<RequirementLine>> {
+    let mut visited = HashSet::new();
+    let mut requirements = Vec::new();
+    collect(path, &mut visited, &mut requirements)?;
+    debug!(files = visited.len(), requirements = requirements.len(), "parsed");
+    Ok(requirements)
+}
+
+/// Parses the content of a requirements file named `path`, for callers without
+/// a filesystem. Includes cannot be followed, so `-r` and `-c` lines are an
+/// error, and only local archives are named, by their file name.
+pub fn parse_str(content: &str, path: &Path) -> Result<Vec<RequirementLine>> {
+    let mut requirements = Vec::new();
+    parse_lines(path, None, content, &mut requirements, &mut |target, _| {
+        Err(PyhelperError::Invalid(format!("Cannot follow include of {} here", target.display())).into())
+    })?;
+    Ok(requirements)
+}
+
+/// Parses the unsaved content of the requirements file at `path`, for editors,
+/// following its includes on disk.
+pub fn parse_content(content: &str, path: &Path) -> Result<Vec<RequirementLine>> {
+    let mut visited = HashSet::new();
+    if let Ok(canonical) = fs::canonicalize(path) {
+        visited.insert(canonical);
+    }
+    let mut requirements = Vec::new();
+    let base = path.parent().unwrap_or(Path::new("."));
+    parse_lines(path, Some(base), content, &mut requirements, &mut |target, out| {
+        collect(&base.join(target), &mut visited, out)
+    })?;
+    Ok(requirements)
+}
+
+/// The indexes `content` points pip at: the `--index-url` (or `-i`), if any,
+/// and every `--extra-index-url`. Included files are not followed.
+pub fn index_urls(content: &str) -> (Option<String>, Vec<String>) {
+    let mut index_url = None;
+    let mut extra_index_urls = Vec::new();
+    for logical in logical_lines(content) {
+        if let Some(url) = option_value(&logical.text, &["--index-url", "-i"]) {
+            index_url = Some(url.to_string());
+        } else if let Some(url) = option_value(&logical.text, &["--extra-index-url"]) {
+            extra_index_urls.push(url.to_string());
+        }
+    }
+    (index_url, extra_index_urls)
+}
+
+fn collect(path: &Path, visited: &mut HashSet<PathBuf>, out: &mut Vec<RequirementLine>) -> Result<()> {
+    let canonical = fs::canonicalize(path)
+        .with_context(|| format!("Cannot read requirements file: {}", path.display()))?;
+    // Files included more than once (or in a cycle) only contribute their lines once
+    if !visited.insert(canonical) {
+        debug!(file = %path.display(), "already included");
+        return Ok(());
+    }
+
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Cannot read requirements file: {}", path.display()))?;
+    let base = path.parent().unwrap_or(Path::new("."));
+    parse_lines(path, Some(base), &content, out, &mut |target, out| collect(&base.join(target), visited, out))
+}
+
+/// Adds the requirements of `content` to `out`, passing the target of every
+/// include to `include`. Local projects are looked up in `base`, if any.
+fn parse_lines(
+    path: &Path,
+    base: Option<&Path>,
+    content: &str,
+    out: &mut Vec<RequirementLine>,
+    include: &mut dyn FnMut(&Path, &mut Vec<RequirementLine>) -> Result<()>,
+) -> Result<()> {
+    for logical in logical_lines(content) {
+        let (line, text) = (logical.line, logical.text.as_str());
+        let located = |e: anyhow::Error| PyhelperError::parse(path, line, Some(logical.column), e);
+        match classify(text) {
+            Line::Requirement(req) => {
+                let package = PythonPackage::parse(req).map_err(located)?;
+                out.push(RequirementLine {
+                    package,
+                    file: path.to_path_buf(),
+                    line,
+                    columns: logical.columns(req),
+                    hashes: hash_options(text),
+                });
+            }
+            Line::Include(target) => include(Path::new(target), out).map_err(located)?,
+            Line::Editable(target) => {
+                // Editables are named via `#egg=` or by the project they point to
+                let package = match egg_name(target) {
+                    Some(egg) => Some(PythonPackage::parse(egg).map_err(located)?),
+                    None => local::requirement(target, base),
+                };
+                match package {
+                    Some(package) => out.push(RequirementLine {
+                        package,
+                        file: path.to_path_buf(),
+                        line,
+                        columns: logical.columns(target),
+                        hashes: Vec::new(),
+                    }),
+                    None => unnamed(path, line, target),
+                }
+            }
+            Line::Local(target) => {
+                // Paths to projects without readable metadata cannot be checked
+                match local::requirement(target, base) {
+                    Some(package) => out.push(RequirementLine {
+                        package,
+                        file: path.to_path_buf(),
+                        line,
+                        columns: logical.columns(target),
+                        hashes: hash_options(text),
+                    }),
+                    None => unnamed(path, line, target),
+                }
+            }
+            Line::Ignored => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Reports a local project left out of the requirements because it could
+/// not be named, so that its absence from the checks is not silent.
+fn unnamed(path: &Path, line: usize, target: &str) {
+    warn!(
+        "{}:{}: skipped {}, as no project with readable metadata is there",
+        path.display(),
+        line,
+        target.trim()
+    );
+}
+
+enum Line<'a> {
+    Requirement(&'a str),
+    Include(&'a str),
+    Editable(&'a str),
+    Local(&'a str),
+    Ignored,
+}
+
+/// A logical line of a requirements file: its physical lines joined, without
+/// the comment.
+#[derive(Debug, PartialEq, Eq)]
+struct LogicalLine {
+    /// The physical line it starts on.
+    line: usize,
+    /// The column of that line `text` starts at.
+    column: usize,
+    /// The column that line ends at, before a `\` continuation.
+    end: usize,
+    text: String,
+}
+
+impl LogicalLine {
+    /// The columns a slice of `text` spans on the first physical line, as
+    /// far as it reaches there.
+    fn columns(&self, part: &str) -> Option<Range<usize>> {
+        let offset = (part.as_ptr() as usize).checked_sub(self.text.as_ptr() as usize)?;
+        let start = self.column + offset;
+        (start < self.end).then(|| start..(start + part.len()).min(self.end))
+    }
+}
+
+/// Joins backslash continuations and strips comments, yielding each logical line
+/// with where it starts.
+fn logical_lines(content: &str) -> Vec<LogicalLine> {
+    static COMMENT: OnceLock<Regex> = OnceLock::new();
+    let comment = COMMENT.get_or_init(|| Regex::new(r"(^|\s+)#.*$").unwrap());
+
+    let mut lines = Vec::new();
+    let mut pending: Option<(usize, usize, usize, String)> = None;
+    let mut finish = |(line, column, end, text): (usize, usize, usize, String)| {
+        let stripped = comment.replace(&text, "").trim().to_string();
+        if !stripped.is_empty() {
+            lines.push(LogicalLine { line, column, end, text: stripped });
+        }
+    };
+    for (index, raw) in content.lines().enumerate() {
+        let is_comment = raw.trim_start().starts_with('#');
+        let continued = raw.strip_suffix('\\').filter(|_| !is_comment);
+        let (line, column, end, mut text) = pending.take().unwrap_or_else(|| {
+            let indent = raw.len() - raw.trim_start().len();
+            (index + 1, indent + 1, continued.unwrap_or(raw).len() + 1, String::new())
+        });
+        match continued {
+            Some(continued) => {
+                text.push_str(continued);
+                pending = Some((line, column, end, text));
+            }
+            None => {
+                text.push_str(raw);
+                finish((line, column, end, text));
+            }
+        }
+    }
+    if let Some(pending) = pending {
+        finish(pending);
+    }
+    lines
+}
+
+fn classify(text: &str) -> Line<'_> {
+    // Constraints bound the same packages, so they take part in the analysis too
+    if let Some(target) = option_value(text, &["-r", "--requirement", "-c", "--constraint"]) {
+        return Line::Include(target);
+    }
+    if let Some(target) = option_value(text, &["-e", "--editable"]) {
+        return Line::Editable(target);
+    }
+    if text.starts_with('-') {
+        return Line::Ignored;
+    }
+
+    // Per-requirement options such as `--hash=sha256:...` follow the requirement itself
+    static OPTION: OnceLock<Regex> = OnceLock::new();
+    let option = OPTION.get_or_init(|| Regex::new(r"\s--?[a-zA-Z]").unwrap());
+    let requirement = match option.find(text) {
+        Some(m) => text[..m.start()].trim(),
+        None => text,
+    };
+    if local::is_path(requirement) {
+        return Line::Local(requirement);
+    }
+    Line::Requirement(requirement)
+}
+
+/// The values of the `--hash` options of a requirement line.
+fn hash_options(text: &str) -> Vec<String> {
+    static HASH: OnceLock<Regex> = OnceLock::new();
+    let hash = HASH.get_or_init(|| Regex::new(r"\s--hash(?:=|\s+)(\S+)").unwrap());
+    hash.captures_iter(text).map(|c| c[1].to_string()).collect()
+}
+
+/// Extracts the value of `-x value`, `-xvalue`, `--long value` or `--long=value`.
+fn option_value<'a>(text: &'a str, names: &[&str]) -> Option<&'a str> {
+    for name in names {
+        if let Some(rest) = text.strip_prefix(name) {
+            let value = if name.starts_with("--") {
+                match rest.strip_prefix('=') {
+                    Some(value) => value,
+                    None if rest.starts_with(char::is_whitespace) => rest,
+                    None => continue,
+                }
+            } else {
+                rest
+            };
+            return Some(value.trim());
+        }
+    }
+    None
+}
+
+fn egg_name(target: &str) -> Option<&str> {
+    let (_, fragment) = target.split_once("#egg=")?;
+    let name = fragment.split('&').next().unwrap_or(fragment).trim();
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_temp(name: &str, content: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pyhelper-req-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    #[test]
+    fn test_logical_lines() {
+        let content = "# header\nrequests>=2.0 # inline\n\ndjango>=3.2,\\\n    <5.0\n  flask\\\n";
+        let lines: Vec<(usize, usize, usize, String)> =
+            logical_lines(content).into_iter().map(|l| (l.line, l.column, l.end, l.text)).collect();
+        assert_eq!(
+            lines,
+            vec![
+                (2, 1, 23, "requests>=2.0".to_string()),
+                (4, 1, 13, "django>=3.2,    <5.0".to_string()),
+                (6, 3, 8, "flask".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_file_with_includes() -> Result<()> {
+        write_temp("base.txt", "six==1.16.0\n-r extra.txt\n");
+        write_temp("extra.txt", "-r base.txt\nattrs>=20 --hash=sha256:abc\n-c constraints.txt\n");
+        write_temp("constraints.txt", "attrs<23\n");
+        let local = write_temp("local.toml", "[project]\nname = \"local-pkg\"\nversion = \"0.3\"\n");
+        fs::create_dir_all(local.with_file_name("local")).unwrap();
+        fs::rename(&local, local.with_file_name("local").join("pyproject.toml")).unwrap();
+        let main = write_temp(
+            "main.txt",
+            "--index-url https://pypi.org/simple\n-r base.txt\n-e git+https://github.com/org/pkg#egg=pkg\n-e ./local\n",
+        );
+
+        let requirements = parse_file(&main)?;
+        let names: Vec<&str> = requirements.iter().map(|r| r.package.name.as_str()).collect();
+        assert_eq!(names, vec!["six", "attrs", "attrs", "pkg", "local-pkg"]);
+        assert_eq!(requirements[4].package.to_string(), "local-pkg @ ./local");
+        assert_eq!(requirements[4].package.version_spec(), "==0.3");
+        assert_eq!(requirements[1].line, 2);
+        assert!(requirements[1].file.ends_with("extra.txt"));
+        assert_eq!(requirements[1].hashes, vec!["sha256:abc"]);
+        assert_eq!(requirements[1].columns, Some(1..10));
+        assert_eq!(requirements[3].columns, Some(4..42));
+        assert!(requirements[1].location().ends_with("extra.txt:2:1"));
+        assert!(requirements[0].hashes.is_empty());
+
+        let broken = write_temp("broken.txt", "requests\nflask>>2\n");
+        let err = parse_file(&broken).unwrap_err().to_string();
+        assert!(err.contains("broken.txt:2"), "{}", err);
+
+        let inline = parse_str(
+            "six\n-e git+https://github.com/org/pkg#egg=pkg\n./vendor/attrs-22.1.0-py3-none-any.whl\n-e ./local\n",
+            Path::new("requirements.txt"),
+        )?;
+        let names: Vec<&str> = inline.iter().map(|r| r.package.name.as_str()).collect();
+        assert_eq!(names, vec!["six", "pkg", "attrs"]);
+        let err = parse_str("six\n-r base.txt\n", Path::new("requirements.txt")).unwrap_err().to_string();
+        assert!(err.contains("requirements.txt:2"), "{}", err);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,386 @@
+use anyhow::Result;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fmt;
+use tracing::{debug, info, instrument};
+
+use crate::error::PyhelperError;
+use crate::explain::versions;
+use crate::package::PythonPackage;
+use crate::pep440::Pep440Version;
+use crate::prerelease::PreReleasePolicy;
+use crate::pypi::{self, PypiClient, Release};
+use crate::requirements::RequirementLine;
+use crate::specifier::SpecifierSet;
+
+/// How many times the selection is revised before giving up.
+const MAX_ROUNDS: usize = 50;
+
+/// Which release [`resolve`] picks among those allowed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Resolution {
+    /// The newest, as pip does.
+    #[default]
+    Highest,
+    /// The oldest, to test that declared lower bounds actually work.
+    Lowest,
+}
+
+impl Resolution {
+    /// How the narration of a failure describes a pick.
+    fn picked(self) -> &'static str {
+        match self {
+            Resolution::Highest => "newest",
+            Resolution::Lowest => "oldest",
+        }
+    }
+}
+
+/// A package version picked by [`resolve`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pin {
+    /// PEP 503 normalized package name.
+    pub name: String,
+    pub version: Pep440Version,
+    /// What requires the package: `-r <file>` for the input requirements, else
+    /// the names of the depending packages.
+    pub via: Vec<String>,
+}
+
+/// A requirement and what declares it: `-r <file>` for the input requirements,
+/// else the name of the picked package depending on it.
+pub type Demand = (String, PythonPackage);
+
+/// Resolution failed: no release satisfies the requirements on a package
+/// together. Displays as a narration of how the requirements came about,
+/// from the input files down to the conflict.
+#[derive(Debug, Clone)]
+pub struct NoSolution {
+    /// PEP 503 normalized name of the package in conflict.
+    pub name: String,
+    /// The requirements on it.
+    pub demands: Vec<Demand>,
+    /// The packages picked when the conflict came up, with the requirements
+    /// they were picked for.
+    pub picks: BTreeMap<String, (Pep440Version, Vec<Demand>)>,
+    pub resolution: Resolution,
+}
+
+impl NoSolution {
+    /// Who declares a demand, as the narration names them.
+    fn declarer(&self, via: &str) -> String {
+        match (via.strip_prefix("-r "), self.picks.get(via)) {
+            (Some(file), _) => file.to_string(),
+            (None, Some((version, _))) => format!("{} {}", via, version),
+            (None, None) => via.to_string(),
+        }
+    }
+
+    fn declares(&self, (via, requirement): &Demand) -> String {
+        let verb = if via.starts_with("-r ") { "requires" } else { "depends on" };
+        format!("{} {} {}", self.declarer(via), verb, requirement_spec(requirement))
+    }
+
+    /// Explains the pick of `name` and, before it, the picks it came from.
+    fn explain_pick(&self, name: &str, seen: &mut BTreeSet<String>, lines: &mut Vec<String>) {
+        let Some((version, reasons)) = self.picks.get(name) else {
+            return;
+        };
+        if !seen.insert(name.to_string()) {
+            return;
+        }
+        for (via, _) in reasons {
+            self.explain_pick(via, seen, lines);
+        }
+        let reasons: Vec<String> = reasons.iter().map(|demand| self.declares(demand)).collect();
+        lines.push(format!(
+            "Because {}, {} {} is picked, the {} release allowed.",
+            and_list(&reasons),
+            name,
+            version,
+            self.resolution.picked()
+        ));
+    }
+}
+
+impl fmt::Display for NoSolution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "No release of {} satisfies every requirement on it.", self.name)?;
+        let mut lines = Vec::new();
+        let mut seen = BTreeSet::new();
+        for (via, _) in &self.demands {
+            self.explain_pick(via, &mut seen, &mut lines);
+        }
+
+        let mut reasons: Vec<String> = self.demands.iter().map(|demand| self.declares(demand)).collect();
+        let mut parties: Vec<String> = self.demands.iter().map(|(via, _)| self.declarer(via)).collect();
+        parties.dedup();
+        let allowed = self
+            .demands
+            .iter()
+            .fold(crate::range::IntervalSet::full(), |acc, (_, requirement)| acc.intersect(&requirement.specifiers.range()));
+        // Requirements that exclude each other need no more reason
+        if !allowed.is_empty() {
+            reasons.push(format!("no release of {} is in {}", self.name, versions(&allowed)));
+        }
+        let outcome = match parties.as_slice() {
+            [only] => format!("{} cannot be satisfied", only),
+            [first, second] => format!("{} and {} are incompatible", first, second),
+            _ => format!("{} cannot be installed together", and_list(&parties)),
+        };
+        lines.push(format!("Because {}, {}.", and_list(&reasons), outcome));
+        for line in lines {
+            write!(f, "\n  {}", line)?;
+        }
+
+        let involved: Vec<&str> = seen.iter().map(String::as_str).collect();
+        if !involved.is_empty() {
+            let (releases, release) = match self.resolution {
+                Resolution::Highest => ("older releases", "an older"),
+                Resolution::Lowest => ("newer releases", "a newer"),
+            };
+            write!(
+                f,
+                "\n  hint: {} are not tried; requiring {} {} may avoid the conflict.",
+                releases,
+                release,
+                or_list(&involved)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for NoSolution {}
+
+/// `name[extras]>=1,<2`, as a narration quotes a requirement.
+fn requirement_spec(requirement: &PythonPackage) -> String {
+    let mut spec = requirement.name.clone();
+    if !requirement.extras.is_empty() {
+        spec.push_str(&format!("[{}]", requirement.extras.join(",")));
+    }
+    match requirement.specifiers.specifiers.is_empty() {
+        true => spec.push_str(" (any version)"),
+        false => spec.push_str(&requirement.specifiers.to_string()),
+    }
+    spec
+}
+
+fn and_list<T: AsRef<str>>(items: &[T]) -> String {
+    joined(items, "and")
+}
+
+fn or_list<T: AsRef<str>>(items: &[T]) -> String {
+    joined(items, "or")
+}
+
+/// `a`, `a and b`, `a, b and c`.
+fn joined<T: AsRef<str>>(items: &[T], conjunction: &str) -> String {
+    let items: Vec<&str> = items.iter().map(AsRef::as_ref).collect();
+    match items.split_last() {
+        Some((last, [])) => last.to_string(),
+        Some((last, rest)) => format!("{} {} {}", rest.join(", "), conjunction, last),
+        None => String::new(),
+    }
+}
+
+/// Picks one version of every package the requirements reach, so that each
+/// version satisfies all requirements on its package at once.
+///
+/// Every round picks the newest release matching the combined requirements,
+/// or the oldest with [`Resolution::Lowest`], then collects the requirements
+/// of the picked versions again, until the selection no longer changes. There
+/// is no backtracking: when the requirements on a package exclude every
+/// release, resolution fails with a [`NoSolution`] narrating how they came
+/// about. Markers are not evaluated; dependencies are only left out when they
+/// apply to extras nobody asked for.
+#[instrument(skip_all, fields(requirements = requirements.len(), ?resolution))]
+pub fn resolve(
+    client: &PypiClient,
+    requirements: &[RequirementLine],
+    policy: PreReleasePolicy,
+    resolution: Resolution,
+) -> Result<Vec<Pin>> {
+    let mut releases: HashMap<String, Vec<Release>> = HashMap::new();
+    let mut dependencies: HashMap<(String, Pep440Version), Vec<PythonPackage>> = HashMap::new();
+    let mut selected: BTreeMap<String, Pep440Version> = BTreeMap::new();
+    let mut extras: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+    // The requirements each selected version was picked for
+    let mut reasons: BTreeMap<String, Vec<Demand>> = BTreeMap::new();
+
+    for round in 1..=MAX_ROUNDS {
+        if let Some(progress) = client.progress() {
+            progress.step(&format!("Resolving: round {}, {} package(s) picked", round, selected.len()));
+        }
+        let mut demands: BTreeMap<String, Vec<(String, &PythonPackage)>> = BTreeMap::new();
+        for line in requirements {
+            let via = format!("-r {}", line.file.display());
+            demands.entry(line.package.canonical_name()).or_default().push((via, &line.package));
+        }
+        for (name, version) in &selected {
+            let requested: Vec<String> = extras.get(name).into_iter().flatten().cloned().collect();
+            for dependency in &dependencies[&(name.clone(), version.clone())] {
+                if dependency.may_apply(&requested) {
+                    demands.entry(dependency.canonical_name()).or_default().push((name.clone(), dependency));
+                }
+            }
+        }
+
+        let unknown: Vec<&str> = demands.keys().filter(|name| !releases.contains_key(*name)).map(|name| name.as_str()).collect();
+        for (name, found) in unknown.iter().zip(client.releases_all(&unknown)?) {
+            releases.insert(name.to_string(), found);
+        }
+
+        let mut next = BTreeMap::new();
+        for (name, demanded) in &demands {
+            let combined = SpecifierSet {
+                specifiers: demanded.iter().flat_map(|(_, r)| r.specifiers.specifiers.clone()).collect(),
+            };
+            let matching = match resolution {
+                Resolution::Highest => pypi::best_match(&releases[name], &combined, policy),
+                Resolution::Lowest => pypi::lowest_match(&releases[name], &combined, policy),
+            };
+            let version = matching.ok_or_else(|| NoSolution {
+                name: name.clone(),
+                demands: owned(demanded),
+                picks: selected
+                    .iter()
+                    .map(|(name, version)| (name.clone(), (version.clone(), reasons.get(name).cloned().unwrap_or_default())))
+                    .collect(),
+                resolution,
+            })?;
+            next.insert(name.clone(), version);
+        }
+        // Picks changed since the last round, where a backtracking resolver would backtrack
+        let revised = next.iter().filter(|(name, version)| selected.get(*name).is_some_and(|v| v != *version)).count();
+        debug!(round, picked = next.len(), revised, "round");
+        let next_extras: BTreeMap<String, BTreeSet<String>> = demands
+            .iter()
+            .map(|(name, demanded)| (name.clone(), demanded.iter().flat_map(|(_, r)| r.extras.clone()).collect()))
+            .collect();
+
+        if next == selected && next_extras == extras {
+            info!(rounds = round, pins = selected.len(), "resolved");
+            return Ok(selected
+                .into_iter()
+                .map(|(name, version)| {
+                    let mut via: Vec<String> = demands[&name].iter().map(|(via, _)| via.clone()).collect();
+                    via.dedup();
+                    Pin { name, version, via }
+                })
+                .collect());
+        }
+
+        reasons = demands.iter().map(|(name, demanded)| (name.clone(), owned(demanded))).collect();
+        let missing: Vec<(&str, &Pep440Version)> = next
+            .iter()
+            .filter(|(name, version)| !dependencies.contains_key(&((*name).clone(), (*version).clone())))
+            .map(|(name, version)| (name.as_str(), version))
+            .collect();
+        for ((name, version), found) in missing.iter().zip(client.requires_dist_all(&missing)?) {
+            dependencies.insert((name.to_string(), (*version).clone()), found);
+        }
+        selected = next;
+        extras = next_extras;
+    }
+    Err(PyhelperError::Resolution(format!("Resolution did not settle after {} rounds", MAX_ROUNDS)).into())
+}
+
+fn owned(demanded: &[(String, &PythonPackage)]) -> Vec<Demand> {
+    demanded.iter().map(|(via, requirement)| (via.clone(), (*requirement).clone())).collect()
+}
+
+/// Renders pins as a requirements file in the style of pip-compile. `hashes`
+/// holds the hashes of each pin's files, or nothing to leave them out;
+/// `command` is the command line recorded in the header.
+pub fn requirements_txt(pins: &[Pin], hashes: &[Vec<String>], command: &str) -> String {
+    let mut out = format!("#\n# This file is autogenerated by pyhelper with:\n#\n#    {}\n#\n", command);
+    for (i, pin) in pins.iter().enumerate() {
+        out.push_str(&format!("{}=={}", pin.name, pin.version));
+        let pin_hashes = hashes.get(i).map_or(&[][..], |h| h.as_slice());
+        for hash in pin_hashes {
+            out.push_str(&format!(" \\\n    --hash={}", hash));
+        }
+        out.push('\n');
+        match pin.via.as_slice() {
+            [only] => out.push_str(&format!("    # via {}\n", only)),
+            via => {
+                out.push_str("    # via\n");
+                for source in via {
+                    out.push_str(&format!("    #   {}\n", source));
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requirements_txt() {
+        let pins = vec![
+            Pin {
+                name: "certifi".to_string(),
+                version: Pep440Version::parse("2024.2.2").unwrap(),
+                via: vec!["requests".to_string()],
+            },
+            Pin {
+                name: "urllib3".to_string(),
+                version: Pep440Version::parse("2.2.1").unwrap(),
+                via: vec!["-r requirements.in".to_string(), "requests".to_string()],
+            },
+        ];
+        let hashes = vec![vec!["sha256:aa".to_string(), "sha256:bb".to_string()], Vec::new()];
+        let expected = "\
+#
+# This file is autogenerated by pyhelper with:
+#
+#    pyhelper pin requirements.in
+#
+certifi==2024.2.2 \\
+    --hash=sha256:aa \\
+    --hash=sha256:bb
+    # via requests
+urllib3==2.2.1
+    # via
+    #   -r requirements.in
+    #   requests
+";
+        assert_eq!(requirements_txt(&pins, &hashes, "pyhelper pin requirements.in"), expected);
+    }
+
+    #[test]
+    fn test_no_solution() -> Result<()> {
+        let demand = |via: &str, requirement: &str| -> Result<Demand> { Ok((via.to_string(), PythonPackage::parse(requirement)?)) };
+        let version = |v: &str| Pep440Version::parse(v);
+        let failure = NoSolution {
+            name: "lib".to_string(),
+            demands: vec![demand("-r requirements.in", "lib>=2.0")?, demand("mid", "lib<1.5")?],
+            picks: BTreeMap::from([
+                ("app".to_string(), (version("2.0")?, vec![demand("-r requirements.in", "app>=1")?])),
+                ("mid".to_string(), (version("3.1")?, vec![demand("app", "mid>=3")?])),
+            ]),
+            resolution: Resolution::Highest,
+        };
+        let expected = "\
+No release of lib satisfies every requirement on it.
+  Because requirements.in requires app>=1, app 2.0 is picked, the newest release allowed.
+  Because app 2.0 depends on mid>=3, mid 3.1 is picked, the newest release allowed.
+  Because requirements.in requires lib>=2.0 and mid 3.1 depends on lib<1.5, requirements.in and mid 3.1 are incompatible.
+  hint: older releases are not tried; requiring an older app or mid may avoid the conflict.";
+        assert_eq!(failure.to_string(), expected);
+
+        let unreleased = NoSolution {
+            name: "lib".to_string(),
+            demands: vec![demand("-r requirements.in", "lib>=99")?],
+            picks: BTreeMap::new(),
+            resolution: Resolution::Lowest,
+        };
+        let expected = "Because requirements.in requires lib>=99 and no release of lib is in [99, +inf), requirements.in cannot be satisfied.";
+        assert!(unreleased.to_string().ends_with(expected), "{}", unreleased);
+        assert!(!unreleased.to_string().contains("hint:"));
+        Ok(())
+    }
+}
@@ -0,0 +1,142 @@
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::requirements::{self, RequirementLine};
+use crate::{conda, pipfile, pyproject, setup};
+
+/// Files that could not be read, each with its error.
+pub type Unreadable = Vec<(PathBuf, anyhow::Error)>;
+
+/// Directories never holding a project's own dependency files.
+const SKIPPED_DIRS: [&str; 3] = ["node_modules", "__pycache__", "site-packages"];
+
+/// Whether `path` declares dependencies, by its name: requirements and
+/// constraints files (`requirements*.txt`, `constraints*.in`, or any `*.txt`
+/// in a `requirements/` directory), pyproject.toml, Pipfile, setup.cfg,
+/// setup.py and conda environment files.
+pub fn is_dependency_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+        return false;
+    };
+    let listed = name.ends_with(".txt") || name.ends_with(".in");
+    let in_requirements_dir = path.parent().and_then(|dir| dir.file_name()).is_some_and(|dir| dir == "requirements");
+    match name {
+        "pyproject.toml" | "Pipfile" | "setup.cfg" | "setup.py" | "environment.yml" | "environment.yaml" => true,
+        _ => listed && (name.starts_with("requirements") || name.starts_with("constraints") || in_requirements_dir),
+    }
+}
+
+/// Finds every dependency file below `root`, sorted. Hidden directories,
+/// virtual environments and directories named in `exclude` are not entered.
+pub fn discover(root: &Path, exclude: &[String]) -> Result<Vec<PathBuf>> {
+    let mut found = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = fs::read_dir(&dir).with_context(|| format!("Cannot read directory: {}", dir.display()))?;
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            // Entries of `.` are shown without the `./` prefix
+            let path = if dir == Path::new(".") { PathBuf::from(&name) } else { dir.join(&name) };
+            if path.is_dir() {
+                let skipped = name.starts_with('.') || SKIPPED_DIRS.contains(&name.as_str()) || exclude.contains(&name);
+                if !skipped && !path.join("pyvenv.cfg").is_file() {
+                    pending.push(path);
+                }
+            } else if is_dependency_file(&path) {
+                found.push(path);
+            }
+        }
+    }
+    found.sort();
+    Ok(found)
+}
+
+/// The requirements declared by a requirements file, pyproject.toml,
+/// Pipfile, conda environment file, setup.cfg or setup.py, told apart by file name.
+pub fn parse_file(path: &Path) -> Result<Vec<RequirementLine>> {
+    let file_name = path.file_name().and_then(|name| name.to_str()).unwrap_or_default();
+    match file_name {
+        "pyproject.toml" => pyproject::parse_file(path),
+        "Pipfile" => pipfile::parse_file(path),
+        "environment.yml" | "environment.yaml" => conda::parse_file(path),
+        "setup.cfg" | "setup.py" => setup::parse_file(path),
+        _ => requirements::parse_file(path),
+    }
+}
+
+/// Reads the requirements of every file. A line reached from several files,
+/// such as a requirements file that others include, is only counted once,
+/// under the path it was found at. Files that cannot be read are returned
+/// with their error instead of stopping the others.
+pub fn load(files: &[PathBuf]) -> (Vec<RequirementLine>, Unreadable) {
+    // Several requirements can share a line, as in a pyproject.toml array
+    let key = |requirement: &RequirementLine| {
+        let file = fs::canonicalize(&requirement.file).unwrap_or_else(|_| requirement.file.clone());
+        (file, requirement.line, requirement.package.to_string())
+    };
+    let mut seen: HashMap<(PathBuf, usize, String), usize> = HashMap::new();
+    let mut requirements: Vec<RequirementLine> = Vec::new();
+    let mut errors = Vec::new();
+    for file in files {
+        let declared = match parse_file(file) {
+            Ok(declared) => declared,
+            Err(e) => {
+                errors.push((file.clone(), e));
+                continue;
+            }
+        };
+        for requirement in declared {
+            let key = key(&requirement);
+            match seen.get(&key) {
+                // Included before, e.g. as `../requirements.txt`
+                Some(&index) if requirement.file == *file => requirements[index] = requirement,
+                Some(_) => {}
+                None => {
+                    seen.insert(key, requirements.len());
+                    requirements.push(requirement);
+                }
+            }
+        }
+    }
+    (requirements, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::conflict::ConflictReport;
+
+    #[test]
+    fn test_scan() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("pyhelper-scan-{}", std::process::id()));
+        for dir in ["services/api", "requirements", ".venv/lib", "env"] {
+            fs::create_dir_all(root.join(dir))?;
+        }
+        fs::write(root.join("requirements.txt"), "requests>=2.31\n")?;
+        fs::write(root.join("requirements/dev.txt"), "-r ../requirements.txt\npytest\n")?;
+        fs::write(root.join("services/api/pyproject.toml"), "[project]\nname = \"api\"\ndependencies = [\"requests<2\", \"six\"]\n")?;
+        fs::write(root.join("services/api/LICENSE.txt"), "MIT\n")?;
+        fs::write(root.join(".venv/lib/requirements.txt"), "six\n")?;
+        fs::write(root.join("env/pyvenv.cfg"), "home = /usr/bin\n")?;
+        fs::write(root.join("env/requirements.txt"), "six\n")?;
+
+        let files = discover(&root, &[])?;
+        let relative: Vec<PathBuf> = files.iter().map(|f| f.strip_prefix(&root).unwrap().to_path_buf()).collect();
+        let expected = ["requirements/dev.txt", "requirements.txt", "services/api/pyproject.toml"];
+        assert_eq!(relative, expected.map(PathBuf::from));
+
+        let (requirements, errors) = load(&files);
+        assert!(errors.is_empty());
+        assert_eq!(requirements.len(), 4);
+        assert_eq!(requirements[0].file, root.join("requirements.txt"));
+        let report = ConflictReport::check(&requirements);
+        assert_eq!(report.conflicts.len(), 1);
+        assert!(report.conflicts[0].requirements[1].file.ends_with("pyproject.toml"));
+
+        assert_eq!(discover(&root, &["services".to_string()])?.len(), 2);
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+}
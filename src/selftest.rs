@@ -0,0 +1,396 @@
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use serde_json::json;
+use std::cmp::Ordering;
+use std::fmt;
+use std::io::{ErrorKind, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use crate::error::PyhelperError;
+use crate::pep440::{LocalSegment, Pep440Version, PreKind};
+use crate::specifier::{Operator, Specifier, SpecifierSet, MAX_SPLIT_POST};
+
+/// The answers of `packaging` to hand-picked checks, one
+/// `contains<TAB>specifier<TAB>version<TAB>true|false` or
+/// `compare<TAB>left<TAB>right<TAB>-1|0|1` per line.
+const ORACLE_TABLE: &str = include_str!("selftest_oracle.tsv");
+
+/// Answers the checks given on standard input with `packaging`, or the copy
+/// pip vendors when it is not installed on its own.
+const ORACLE_SCRIPT: &str = r#"
+import json, sys
+try:
+    import packaging
+    from packaging.specifiers import Specifier
+    from packaging.version import Version
+except ImportError:
+    from pip._vendor import packaging
+    from pip._vendor.packaging.specifiers import Specifier
+    from pip._vendor.packaging.version import Version
+
+answers = []
+for kind, left, right in json.load(sys.stdin):
+    if kind == "contains":
+        answers.append(Specifier(left).contains(right, prereleases=True))
+    else:
+        a, b = Version(left), Version(right)
+        answers.append((a > b) - (a < b))
+print(json.dumps({"version": packaging.__version__, "answers": answers}))
+"#;
+
+/// A question about versions that pyhelper and `packaging` must answer alike.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Check {
+    /// Whether `version` satisfies `specifier`, pre-releases allowed, as the
+    /// ranges of every command see it.
+    Contains { specifier: String, version: String },
+    /// How `left` sorts against `right`.
+    Compare { left: String, right: String },
+}
+
+/// The answer to a check.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    Contains(bool),
+    Compare(Ordering),
+}
+
+impl fmt::Display for Check {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Check::Contains { specifier, version } => write!(f, "{} contains {}", specifier, version),
+            Check::Compare { left, right } => write!(f, "{} vs {}", left, right),
+        }
+    }
+}
+
+impl fmt::Display for Answer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Answer::Contains(contains) => write!(f, "{}", contains),
+            Answer::Compare(Ordering::Less) => write!(f, "<"),
+            Answer::Compare(Ordering::Equal) => write!(f, "=="),
+            Answer::Compare(Ordering::Greater) => write!(f, ">"),
+        }
+    }
+}
+
+impl Check {
+    /// pyhelper's answer.
+    pub fn answer(&self) -> Result<Answer> {
+        match self {
+            Check::Contains { specifier, version } => {
+                let version = Pep440Version::parse(version)?;
+                let set = SpecifierSet { specifiers: vec![Specifier::parse(specifier)?] };
+                Ok(Answer::Contains(set.range().contains(&version) && set.matches_literal(&version)))
+            }
+            Check::Compare { left, right } => {
+                Ok(Answer::Compare(Pep440Version::parse(left)?.cmp(&Pep440Version::parse(right)?)))
+            }
+        }
+    }
+
+    /// Why pyhelper may answer differently: the few sets of versions
+    /// `packaging` accepts that no union of ranges can hold, and the prefix
+    /// matching of older `packaging` releases.
+    pub fn known_difference(&self) -> Option<&'static str> {
+        let Check::Contains { specifier, version } = self else {
+            return None;
+        };
+        let (spec, version) = (Specifier::parse(specifier).ok()?, Pep440Version::parse(version).ok()?);
+        let prefix = match spec.op {
+            Operator::Compatible => spec.version.release.len() - 1,
+            _ if spec.wildcard => spec.version.release.len(),
+            _ => 0,
+        };
+        // Compared as text, `1!2rc0` is not split into `1!2` and `rc0`
+        let glued = version.epoch != 0 && version.release.len() == 1 && version.pre.is_some();
+        if prefix > 0 && (version.release.len() < prefix || glued) {
+            return Some("older packaging releases match prefixes as text, without padding a shorter release or splitting off its epoch");
+        }
+        if final_release(&spec.version) != final_release(&version) {
+            return None;
+        }
+        let split = spec.version.post.is_some_and(|post| post > MAX_SPLIT_POST);
+        if spec.op == Operator::Less && split && !spec.version.is_prerelease() && version.post.is_some() && version.dev.is_some() {
+            return Some("`<` on a post-release past .post100 keeps the dev releases of the post-releases below it");
+        }
+        if spec.op != Operator::Greater {
+            return None;
+        }
+        if spec.version.post.is_none() && spec.version.is_prerelease() && !version.local.is_empty() {
+            return Some("`>` on a pre-release rejects every local version of the same release in packaging");
+        }
+        if spec.version.post.is_none() && spec.version.is_prerelease() && version.pre.is_some() && version.post.is_some() {
+            return Some("`>` on a pre-release rejects the post-releases of the pre-releases of the same release in packaging");
+        }
+        if spec.version.post.is_some() && !version.local.is_empty() {
+            return Some("`>` on a post-release rejects the local versions of later post-releases in packaging");
+        }
+        None
+    }
+}
+
+/// The final release `version` is a pre-, post- or dev release or local version of.
+fn final_release(version: &Pep440Version) -> Pep440Version {
+    Pep440Version { epoch: version.epoch, release: version.release.clone(), pre: None, post: None, dev: None, local: Vec::new() }
+}
+
+/// A check pyhelper answered differently from the oracle.
+#[derive(Debug, Clone)]
+pub struct Mismatch {
+    pub check: Check,
+    pub expected: Answer,
+    pub actual: Answer,
+    /// Set when the difference is a known one.
+    pub known: Option<&'static str>,
+}
+
+/// The result of a self-test.
+#[derive(Debug, Clone, Default)]
+pub struct Outcome {
+    /// The version of `packaging` that answered the generated checks, if any did.
+    pub packaging: Option<String>,
+    pub checked: usize,
+    pub mismatches: Vec<Mismatch>,
+}
+
+impl Outcome {
+    /// Whether any mismatch is not a known difference.
+    pub fn failed(&self) -> bool {
+        self.mismatches.iter().any(|mismatch| mismatch.known.is_none())
+    }
+}
+
+/// Answers every check and compares with the oracle's `expected` answers,
+/// returning where they differ.
+pub fn compare(checks: &[Check], expected: &[Answer]) -> Result<Vec<Mismatch>> {
+    let mut mismatches = Vec::new();
+    for (check, expected) in checks.iter().zip(expected) {
+        let actual = check.answer().with_context(|| format!("Cannot answer {}", check))?;
+        if actual != *expected {
+            mismatches.push(Mismatch { check: check.clone(), expected: *expected, actual, known: check.known_difference() });
+        }
+    }
+    Ok(mismatches)
+}
+
+/// The checks shipped with pyhelper and the answers `packaging` gave them.
+pub fn table() -> Result<(Vec<Check>, Vec<Answer>)> {
+    let mut checks = Vec::new();
+    let mut answers = Vec::new();
+    for (number, line) in ORACLE_TABLE.lines().enumerate() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let invalid = || PyhelperError::Invalid(format!("Invalid oracle table line {}: {}", number + 1, line));
+        let [kind, left, right, answer] = line.split('\t').collect::<Vec<_>>()[..] else {
+            return Err(invalid().into());
+        };
+        let (left, right) = (left.to_string(), right.to_string());
+        let (check, answer) = match (kind, answer) {
+            ("contains", "true" | "false") => (Check::Contains { specifier: left, version: right }, Answer::Contains(answer == "true")),
+            ("compare", "-1") => (Check::Compare { left, right }, Answer::Compare(Ordering::Less)),
+            ("compare", "0") => (Check::Compare { left, right }, Answer::Compare(Ordering::Equal)),
+            ("compare", "1") => (Check::Compare { left, right }, Answer::Compare(Ordering::Greater)),
+            _ => return Err(invalid().into()),
+        };
+        checks.push(check);
+        answers.push(answer);
+    }
+    Ok((checks, answers))
+}
+
+/// A small xorshift generator, so a seed reproduces a run on any platform.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn chance(&mut self, percent: u64) -> bool {
+        self.below(100) < percent
+    }
+}
+
+/// `count` checks of versions and specifiers around common releases, with
+/// epochs, pre-, post- and dev releases and local labels, where the rules
+/// have their corner cases.
+pub fn generate(count: usize, seed: u64) -> Vec<Check> {
+    // Zero would stay zero
+    let mut rng = Rng(seed.max(1));
+    (0..count)
+        .map(|_| {
+            let anchor: Vec<u64> = (0..1 + rng.below(3)).map(|_| rng.below(3)).collect();
+            if rng.chance(20) {
+                let left = near(&mut rng, &anchor, true).to_string();
+                let right = near(&mut rng, &anchor, true).to_string();
+                return Check::Compare { left, right };
+            }
+            let version = near(&mut rng, &anchor, true).to_string();
+            Check::Contains { specifier: specifier(&mut rng, &anchor), version }
+        })
+        .collect()
+}
+
+/// A version sharing most of its release with `anchor`.
+fn near(rng: &mut Rng, anchor: &[u64], local: bool) -> Pep440Version {
+    let mut release = anchor.to_vec();
+    match rng.below(6) {
+        0 => release.push(rng.below(2)),
+        1 if release.len() > 1 => {
+            release.pop();
+        }
+        2 => {
+            let last = release.last_mut().expect("the anchor has a segment");
+            *last = (*last + rng.below(3)).saturating_sub(1);
+        }
+        _ => {}
+    }
+    let pre = rng.chance(25).then(|| ([PreKind::Alpha, PreKind::Beta, PreKind::Rc][rng.below(3) as usize], rng.below(3)));
+    let local = match local && rng.chance(12) {
+        true => match rng.below(3) {
+            0 => vec![LocalSegment::Text("cu118".to_string())],
+            1 => vec![LocalSegment::Number(1)],
+            _ => vec![LocalSegment::Text("ubuntu".to_string()), LocalSegment::Number(2)],
+        },
+        false => Vec::new(),
+    };
+    Pep440Version {
+        epoch: u64::from(rng.chance(4)),
+        release,
+        pre,
+        // Now and then past MAX_SPLIT_POST, where `<V.postN` stops carving
+        post: rng.chance(20).then(|| if rng.chance(15) { MAX_SPLIT_POST + 50 * (1 + rng.below(2)) } else { rng.below(3) }),
+        dev: rng.chance(20).then(|| rng.below(3)),
+        local,
+    }
+}
+
+/// A clause on a version near `anchor`, of any operator.
+fn specifier(rng: &mut Rng, anchor: &[u64]) -> String {
+    let ops = ["==", "!=", "<", "<=", ">", ">=", "~=", "==*", "!=*", "==="];
+    let op = ops[rng.below(ops.len() as u64) as usize];
+    // Local labels are only allowed with `==`, `!=` and `===`
+    let mut version = near(rng, anchor, matches!(op, "==" | "!=" | "==="));
+    match op {
+        "==*" | "!=*" => {
+            let release: Vec<String> = version.release.iter().map(|n| n.to_string()).collect();
+            let epoch = if version.epoch == 0 { String::new() } else { format!("{}!", version.epoch) };
+            format!("{}{}{}.*", &op[..2], epoch, release.join("."))
+        }
+        "~=" => {
+            if version.release.len() < 2 {
+                version.release.push(rng.below(3));
+            }
+            format!("~={}", version)
+        }
+        _ => format!("{}{}", op, version),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct OracleOutput {
+    version: String,
+    answers: Vec<serde_json::Value>,
+}
+
+/// Asks the `packaging` of the `python` interpreter, returning its version
+/// and its answers.
+pub fn ask_python(python: &Path, checks: &[Check]) -> Result<(String, Vec<Answer>)> {
+    let shown = python.display().to_string();
+    let mut child = Command::new(python)
+        .args(["-c", ORACLE_SCRIPT])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Cannot run Python interpreter: {}", shown))?;
+    let request: Vec<[&str; 3]> = checks
+        .iter()
+        .map(|check| match check {
+            Check::Contains { specifier, version } => ["contains", specifier, version],
+            Check::Compare { left, right } => ["compare", left, right],
+        })
+        .collect();
+    let request = json!(request).to_string();
+    let mut stdin = child.stdin.take().expect("standard input is piped");
+    // Written from another thread, so a large request cannot block on a full pipe
+    let writer = std::thread::spawn(move || match stdin.write_all(request.as_bytes()) {
+        Err(e) if e.kind() != ErrorKind::BrokenPipe => Err(e),
+        _ => Ok(()),
+    });
+    let output = child.wait_with_output().with_context(|| format!("Cannot run Python interpreter: {}", shown))?;
+    writer.join().expect("the writer does not panic").with_context(|| format!("Cannot write to {}", shown))?;
+    if !output.status.success() {
+        return Err(anyhow!("Python interpreter {} failed: {}", shown, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    let oracle: OracleOutput =
+        serde_json::from_slice(&output.stdout).with_context(|| format!("Unexpected output from {}", shown))?;
+    let answers = oracle
+        .answers
+        .iter()
+        .map(|answer| match (answer.as_bool(), answer.as_i64()) {
+            (Some(contains), _) => Ok(Answer::Contains(contains)),
+            (_, Some(order)) => Ok(Answer::Compare(order.cmp(&0))),
+            _ => Err(anyhow!("Unexpected answer from {}: {}", shown, answer)),
+        })
+        .collect::<Result<Vec<_>>>()?;
+    if answers.len() != checks.len() {
+        return Err(anyhow!("{} answered {} of {} checks", shown, answers.len(), checks.len()));
+    }
+    Ok((oracle.version, answers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_table() -> Result<()> {
+        let (checks, answers) = table()?;
+        assert!(checks.len() > 100);
+        let mismatches = compare(&checks, &answers)?;
+        let unexpected: Vec<String> =
+            mismatches.iter().filter(|m| m.known.is_none()).map(|m| format!("{}: {} vs {}", m.check, m.expected, m.actual)).collect();
+        assert!(unexpected.is_empty(), "{:#?}", unexpected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_generate() -> Result<()> {
+        let checks = generate(500, 7);
+        assert_eq!(checks, generate(500, 7));
+        assert_ne!(checks, generate(500, 8));
+        // Everything generated is valid for both sides
+        for check in &checks {
+            check.answer()?;
+        }
+        Ok(())
+    }
+
+    /// Thousands of generated checks against the `packaging` of `python3`,
+    /// run with `cargo test --features differential`.
+    #[cfg(feature = "differential")]
+    #[test]
+    fn test_differential() -> Result<()> {
+        let checks = generate(20_000, 1);
+        let (_, answers) = ask_python(Path::new("python3"), &checks)?;
+        let unexpected: Vec<String> = compare(&checks, &answers)?
+            .iter()
+            .filter(|m| m.known.is_none())
+            .map(|m| format!("{}: {} vs {}", m.check, m.expected, m.actual))
+            .collect();
+        assert!(unexpected.is_empty(), "{:#?}", unexpected);
+        Ok(())
+    }
+}
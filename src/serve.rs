@@ -0,0 +1,181 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::io::Read;
+use std::path::Path;
+
+use crate::audit::{self, OsvClient};
+use crate::conflict::ConflictReport;
+use crate::error::PyhelperError;
+use crate::output::json;
+use crate::prerelease::PreReleasePolicy;
+use crate::pypi::PypiClient;
+use crate::requirements::{self, RequirementLine};
+use crate::resolve::{self, Resolution};
+
+/// The file name reported for the requirements of a request.
+const SOURCE: &str = "requirements.txt";
+
+/// The largest request body accepted, in bytes.
+const MAX_BODY: u64 = 1024 * 1024;
+
+/// The body of every request: the content of a requirements file, or a list
+/// of requirements, e.g. `{"requirements": ["django>=4.2", "celery<6"]}`.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Request {
+    requirements: Requirements,
+    /// For `/check`, also check against the versions published on the index.
+    #[serde(default)]
+    online: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Requirements {
+    Content(String),
+    List(Vec<String>),
+}
+
+/// A JSON response with its HTTP status.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: Value,
+}
+
+impl Response {
+    fn ok(body: Value) -> Self {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, error: impl std::fmt::Display) -> Self {
+        Response {
+            status,
+            body: json!({ "error": format!("{:#}", error) }),
+        }
+    }
+}
+
+/// The REST API of `pyhelper serve`. One server shares its index client, and
+/// so its cache, between all requests, which it answers one at a time.
+pub struct Server {
+    client: PypiClient,
+    osv: OsvClient,
+    policy: PreReleasePolicy,
+}
+
+impl Server {
+    pub fn new(client: PypiClient, osv: OsvClient, policy: PreReleasePolicy) -> Self {
+        Server { client, osv, policy }
+    }
+
+    /// Answers requests on `address` (`host:port`) until the process is interrupted.
+    pub fn run(&self, address: &str) -> Result<()> {
+        let server = tiny_http::Server::http(address).map_err(|e| anyhow!("Cannot listen on {}: {}", address, e))?;
+        let content_type = tiny_http::Header::from_bytes("Content-Type", "application/json").expect("valid header");
+        for mut request in server.incoming_requests() {
+            let mut body = String::new();
+            let response = match request.as_reader().take(MAX_BODY + 1).read_to_string(&mut body) {
+                Ok(read) if read as u64 > MAX_BODY => Response::error(413, format!("Request body exceeds {} bytes", MAX_BODY)),
+                Ok(_) => self.handle(request.method().as_str(), request.url(), &body),
+                Err(e) => Response::error(400, format!("Cannot read request body: {}", e)),
+            };
+            let reply = tiny_http::Response::from_string(response.body.to_string())
+                .with_status_code(response.status)
+                .with_header(content_type.clone());
+            // The client may be gone already
+            let _ = request.respond(reply);
+        }
+        Ok(())
+    }
+
+    /// Routes one request, e.g. `handle("POST", "/check", body)`.
+    pub fn handle(&self, method: &str, url: &str, body: &str) -> Response {
+        let path = url.split('?').next().unwrap_or(url);
+        let endpoint = match path {
+            "/check" => Server::check,
+            "/resolve" => Server::resolve,
+            "/audit" => Server::audit,
+            _ => return Response::error(404, format!("Not found: {}", path)),
+        };
+        if method != "POST" {
+            return Response::error(405, format!("Method not allowed: {} {} (expected POST)", method, path));
+        }
+        let request = match parse_request(body) {
+            Ok(request) => request,
+            Err(e) => return Response::error(400, e),
+        };
+        match endpoint(self, request) {
+            Ok(value) => Response::ok(value),
+            Err(e) => Response::error(500, e),
+        }
+    }
+
+    /// Like `check -r FILE --format json`.
+    fn check(&self, (requirements, online): (Vec<RequirementLine>, bool)) -> Result<Value> {
+        let report = ConflictReport::check_with(&requirements, self.policy);
+        let verified = if online { Some(self.client.verify(&report)?) } else { None };
+        Ok(json::report(&[SOURCE.to_string()], &report, verified.as_deref()))
+    }
+
+    /// Like `pin`, with the pins both as a list and as a requirements file.
+    fn resolve(&self, (requirements, _): (Vec<RequirementLine>, bool)) -> Result<Value> {
+        let pins = resolve::resolve(&self.client, &requirements, self.policy, Resolution::Highest)?;
+        Ok(json!({
+            "schema_version": json::SCHEMA_VERSION,
+            "pins": json::pins(&pins),
+            "requirements_txt": resolve::requirements_txt(&pins, &[], "pyhelper serve"),
+        }))
+    }
+
+    /// Like `audit --format json`, picking a release for every unpinned requirement.
+    fn audit(&self, (requirements, _): (Vec<RequirementLine>, bool)) -> Result<Value> {
+        let (mut packages, mut skipped) = audit::pinned(&requirements);
+        if !skipped.is_empty() {
+            let (resolved, unresolved) = audit::resolve(&self.client, &skipped, self.policy)?;
+            packages.extend(resolved);
+            skipped = unresolved;
+        }
+        let findings = self.osv.audit(&packages)?;
+        Ok(json::audit_report(Path::new(SOURCE), packages.len(), &findings, &skipped))
+    }
+}
+
+fn parse_request(body: &str) -> Result<(Vec<RequirementLine>, bool)> {
+    let request: Request =
+        serde_json::from_str(body).map_err(|e| PyhelperError::Invalid(format!("Invalid request body: {}", e)))?;
+    let content = match request.requirements {
+        Requirements::Content(content) => content,
+        Requirements::List(lines) => lines.join("\n"),
+    };
+    Ok((requirements::parse_str(&content, Path::new(SOURCE))?, request.online))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_handle() -> Result<()> {
+        // Nothing listens on the discard port, so no test reaches a network
+        let server = Server::new(
+            PypiClient::new("http://127.0.0.1:9")?,
+            OsvClient::new("http://127.0.0.1:9")?,
+            PreReleasePolicy::default(),
+        );
+        let response = server.handle("POST", "/check", r#"{"requirements": "six<1\nsix>=2\nflask\n"}"#);
+        assert_eq!(response.status, 200);
+        assert_eq!(response.body["conflict"], true);
+        let response = server.handle("POST", "/check?pretty", r#"{"requirements": ["flask>=2", "flask<3"]}"#);
+        assert_eq!(response.body["conflict"], false);
+
+        assert_eq!(server.handle("GET", "/check", "").status, 405);
+        assert_eq!(server.handle("POST", "/lock", "").status, 404);
+        assert_eq!(server.handle("POST", "/check", "requests").status, 400);
+        let response = server.handle("POST", "/resolve", r#"{"requirements": "flask>>2"}"#);
+        assert_eq!(response.status, 400);
+        assert!(response.body["error"].as_str().is_some_and(|e| e.contains("requirements.txt:1")), "{}", response.body);
+        Ok(())
+    }
+}
@@ -0,0 +1,408 @@
+use anyhow::{bail, Context, Result};
+use std::fs;
+use std::path::Path;
+use tracing::{debug, instrument};
+
+use crate::error::PyhelperError;
+use crate::package::PythonPackage;
+use crate::requirements::{self, RequirementLine};
+
+/// Reads the `install_requires` and `extras_require` of a setuptools project,
+/// from a setup.cfg or, for files ending in `.py`, from a setup.py.
+#[instrument(skip_all, err, fields(file = %path.display()))]
+pub fn parse_file(path: &Path) -> Result<Vec<RequirementLine>> {
+    let content = fs::read_to_string(path)
+        .with_context(|| format!("Cannot read setup file: {}", path.display()))?;
+    let requirements = if path.extension().is_some_and(|ext| ext == "py") {
+        parse_py(&content, path)?
+    } else {
+        parse_cfg(&content, path)?
+    };
+    debug!(requirements = requirements.len(), "parsed");
+    Ok(requirements)
+}
+
+/// One `key = value` option of an INI file, with the line of each value line.
+/// A value on the line of its key comes first; indented lines continue it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IniOption {
+    section: String,
+    key: String,
+    values: Vec<(usize, String)>,
+}
+
+/// Parses INI content the way setuptools' configparser does: `[section]`
+/// headers, `key = value` or `key: value` options, indented continuation
+/// lines and full-line `#` or `;` comments.
+fn parse_ini(content: &str, path: &Path) -> Result<Vec<IniOption>> {
+    let mut options: Vec<IniOption> = Vec::new();
+    let mut section = String::new();
+    for (index, raw) in content.lines().enumerate() {
+        let line = index + 1;
+        let text = raw.trim();
+        if text.is_empty() || text.starts_with(['#', ';']) {
+            continue;
+        }
+        if raw.starts_with(char::is_whitespace) {
+            match options.last_mut() {
+                Some(option) if option.section == section => option.values.push((line, text.to_string())),
+                _ => bail!(PyhelperError::parse(path, line, None, "Continuation line without an option")),
+            }
+        } else if let Some(header) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            section = header.trim().to_string();
+        } else {
+            let (key, value) = text
+                .split_once(['=', ':'])
+                .ok_or_else(|| PyhelperError::parse(path, line, None, format!("Invalid line: {}", text)))?;
+            let value = value.trim();
+            options.push(IniOption {
+                section: section.clone(),
+                key: key.trim().to_string(),
+                values: if value.is_empty() { Vec::new() } else { vec![(line, value.to_string())] },
+            });
+        }
+    }
+    Ok(options)
+}
+
+/// Like [`parse_file`], for the content of the setup.cfg at `path`. As in
+/// setuptools, a single-line list is separated by `;`, and `file:` reads
+/// requirements files relative to the setup.cfg.
+pub fn parse_cfg(content: &str, path: &Path) -> Result<Vec<RequirementLine>> {
+    let base = path.parent().unwrap_or(Path::new("."));
+    let mut requirements = Vec::new();
+    let wanted = parse_ini(content, path)?.into_iter().filter(|option| {
+        (option.section == "options" && option.key == "install_requires") || option.section == "options.extras_require"
+    });
+    for option in wanted {
+        if let [(line, value)] = option.values.as_slice() {
+            if let Some(files) = value.strip_prefix("file:") {
+                for file in files.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+                    requirements.extend(
+                        requirements::parse_file(&base.join(file)).map_err(|e| PyhelperError::parse(path, *line, None, e))?,
+                    );
+                }
+                continue;
+            }
+        }
+        let single_line = option.values.len() == 1;
+        for (line, value) in &option.values {
+            let items: Vec<&str> = if single_line { value.split(';').collect() } else { vec![value] };
+            for item in items.into_iter().map(str::trim).filter(|item| !item.is_empty()) {
+                let columns = requirements::columns_of(content, *line, item);
+                let column = columns.as_ref().map_or(1, |c| c.start);
+                let package =
+                    PythonPackage::parse(item).map_err(|e| PyhelperError::parse(path, *line, Some(column), e))?;
+                requirements.push(RequirementLine {
+                    package,
+                    file: path.to_path_buf(),
+                    line: *line,
+                    columns,
+                    hashes: Vec::new(),
+                });
+            }
+        }
+    }
+    Ok(requirements)
+}
+
+/// The `name` and `version` a setup.cfg gives its project in `[metadata]`.
+/// A version read from a file or attribute (`attr: pkg.__version__`) is `None`.
+pub fn cfg_metadata(content: &str) -> Option<(String, Option<String>)> {
+    let options = parse_ini(content, Path::new("setup.cfg")).ok()?;
+    let value = |key: &str| {
+        options
+            .iter()
+            .find(|option| option.section == "metadata" && option.key == key)
+            .and_then(|option| option.values.first())
+            .map(|(_, value)| value.clone())
+    };
+    Some((value("name")?, value("version").filter(|version| !version.contains(':'))))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Name(String),
+    /// A string literal, without its quotes. Escapes are kept as written.
+    Str(String),
+    /// A single punctuation character; `==` and other operators are `Other`.
+    Punct(char),
+    Other,
+}
+
+/// Splits Python source into the few tokens the extractor looks at, with
+/// their line numbers. f-strings and byte strings become `Other`.
+fn tokenize(source: &str) -> Vec<(usize, Token)> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let (mut i, mut line) = (0, 1);
+    while i < chars.len() {
+        let c = chars[i];
+        if c == '\n' {
+            line += 1;
+            i += 1;
+        } else if c.is_whitespace() || c == '\\' {
+            i += 1;
+        } else if c == '#' {
+            while i < chars.len() && chars[i] != '\n' {
+                i += 1;
+            }
+        } else if c.is_alphabetic() || c == '_' {
+            let start = i;
+            while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                i += 1;
+            }
+            let word: String = chars[start..i].iter().collect();
+            let quoted = i < chars.len() && (chars[i] == '\'' || chars[i] == '"');
+            if quoted && word.len() <= 2 && word.chars().all(|p| "rRuUbBfF".contains(p)) {
+                let literal = !word.contains(['b', 'B', 'f', 'F']);
+                let (value, end, newlines) = string_literal(&chars, i);
+                tokens.push((line, if literal { Token::Str(value) } else { Token::Other }));
+                line += newlines;
+                i = end;
+            } else {
+                tokens.push((line, Token::Name(word)));
+            }
+        } else if c == '\'' || c == '"' {
+            let (value, end, newlines) = string_literal(&chars, i);
+            tokens.push((line, Token::Str(value)));
+            line += newlines;
+            i = end;
+        } else {
+            let operator = c == '=' && chars.get(i + 1) == Some(&'=');
+            let compared = matches!(c, '!' | '<' | '>') && chars.get(i + 1) == Some(&'=');
+            if operator || compared {
+                tokens.push((line, Token::Other));
+                i += 2;
+            } else {
+                tokens.push((line, Token::Punct(c)));
+                i += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// The string literal starting at the quote `chars[start]`: its content, the
+/// index after it and the number of newlines it spans.
+fn string_literal(chars: &[char], start: usize) -> (String, usize, usize) {
+    let quote = chars[start];
+    let triple = chars.get(start + 1) == Some(&quote) && chars.get(start + 2) == Some(&quote);
+    let delimiter = if triple { 3 } else { 1 };
+    let mut i = start + delimiter;
+    let mut value = String::new();
+    while i < chars.len() {
+        if chars[i] == '\\' && i + 1 < chars.len() {
+            value.push(chars[i]);
+            value.push(chars[i + 1]);
+            i += 2;
+            continue;
+        }
+        let closes = chars[i] == quote && (!triple || (chars.get(i + 1) == Some(&quote) && chars.get(i + 2) == Some(&quote)));
+        if closes || (!triple && chars[i] == '\n') {
+            i += delimiter;
+            break;
+        }
+        value.push(chars[i]);
+        i += 1;
+    }
+    let newlines = value.matches('\n').count();
+    (value, i.min(chars.len()), newlines)
+}
+
+/// A list or tuple of string literals at `tokens[i]`, or a name bound to
+/// one, together with each requirement's line. `None` when the value is
+/// computed.
+fn string_list(tokens: &[(usize, Token)], i: usize) -> Option<Vec<(usize, String)>> {
+    match &tokens.get(i)?.1 {
+        Token::Name(name) => string_list(tokens, assignment(tokens, name)?),
+        Token::Punct('[') | Token::Punct('(') => {
+            let mut items = Vec::new();
+            let mut j = i + 1;
+            loop {
+                match &tokens.get(j)?.1 {
+                    Token::Punct(']') | Token::Punct(')') => return Some(items),
+                    Token::Str(value) => {
+                        let line = tokens[j].0;
+                        // Adjacent literals are concatenated
+                        let mut value = value.clone();
+                        while let Some((_, Token::Str(next))) = tokens.get(j + 1) {
+                            value.push_str(next);
+                            j += 1;
+                        }
+                        items.push((line, value));
+                        j += 1;
+                        match &tokens.get(j)?.1 {
+                            Token::Punct(',') => j += 1,
+                            Token::Punct(']') | Token::Punct(')') => {}
+                            _ => return None,
+                        }
+                    }
+                    _ => return None,
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+/// A dict literal mapping string keys to string lists, or a name bound to one.
+fn string_dict(tokens: &[(usize, Token)], i: usize) -> Option<Vec<(usize, String)>> {
+    match &tokens.get(i)?.1 {
+        Token::Name(name) => string_dict(tokens, assignment(tokens, name)?),
+        Token::Punct('{') => {
+            let mut items = Vec::new();
+            let mut j = i + 1;
+            loop {
+                match &tokens.get(j)?.1 {
+                    Token::Punct('}') => return Some(items),
+                    Token::Str(_) if tokens.get(j + 1)?.1 == Token::Punct(':') => {
+                        items.extend(string_list(tokens, j + 2)?);
+                        j = closing(tokens, j + 2)? + 1;
+                        match &tokens.get(j)?.1 {
+                            Token::Punct(',') => j += 1,
+                            Token::Punct('}') => {}
+                            _ => return None,
+                        }
+                    }
+                    _ => return None,
+                }
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The index of the value assigned to `name` at the top of the module.
+fn assignment(tokens: &[(usize, Token)], name: &str) -> Option<usize> {
+    (0..tokens.len().saturating_sub(1)).find_map(|i| {
+        let at_statement = i == 0 || tokens[i - 1].0 < tokens[i].0;
+        (at_statement && tokens[i].1 == Token::Name(name.to_string()) && tokens[i + 1].1 == Token::Punct('=')).then_some(i + 2)
+    })
+}
+
+/// The index of the bracket closing the one at `tokens[i]`, or `i` itself for
+/// a name.
+fn closing(tokens: &[(usize, Token)], i: usize) -> Option<usize> {
+    if matches!(tokens.get(i)?.1, Token::Name(_)) {
+        return Some(i);
+    }
+    let mut depth = 0;
+    for (j, (_, token)) in tokens.iter().enumerate().skip(i) {
+        match token {
+            Token::Punct('[' | '(' | '{') => depth += 1,
+            Token::Punct(']' | ')' | '}') => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(j);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Like [`parse_file`], for the content of the setup.py at `path`. Without
+/// running it, only literal lists and dicts are understood, given in the
+/// `setup()` call or bound to a module-level name; other values are an error.
+pub fn parse_py(content: &str, path: &Path) -> Result<Vec<RequirementLine>> {
+    let tokens = tokenize(content);
+    let mut items: Vec<(usize, String)> = Vec::new();
+    for (i, (line, token)) in tokens.iter().enumerate() {
+        let Token::Name(keyword) = token else {
+            continue;
+        };
+        if tokens.get(i + 1).map(|(_, t)| t) != Some(&Token::Punct('=')) {
+            continue;
+        }
+        let found = match keyword.as_str() {
+            "install_requires" => string_list(&tokens, i + 2),
+            "extras_require" => string_dict(&tokens, i + 2),
+            _ => continue,
+        };
+        let found = found.ok_or_else(|| {
+            let message = format!("{} is computed and cannot be read without running setup.py", keyword);
+            PyhelperError::parse(path, *line, None, message)
+        })?;
+        // `install_requires = [...]` followed by `install_requires=install_requires` names one list twice
+        for item in found {
+            if !items.contains(&item) {
+                items.push(item);
+            }
+        }
+    }
+
+    let mut requirements = Vec::new();
+    for (line, item) in items {
+        let columns = requirements::columns_of(content, line, item.trim());
+        let column = columns.as_ref().map_or(1, |c| c.start);
+        let package =
+            PythonPackage::parse(item.trim()).map_err(|e| PyhelperError::parse(path, line, Some(column), e))?;
+        requirements.push(RequirementLine {
+            package,
+            file: path.to_path_buf(),
+            line,
+            columns,
+            hashes: Vec::new(),
+        });
+    }
+    Ok(requirements)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn found(requirements: &[RequirementLine]) -> Vec<(String, usize)> {
+        requirements.iter().map(|r| (r.package.to_string(), r.line)).collect()
+    }
+
+    #[test]
+    fn test_parse_cfg() -> Result<()> {
+        let content = "[metadata]\nname = demo\nversion = attr: demo.__version__\n\n[options]\n# runtime\ninstall_requires =\n    requests>=2.31\n    importlib-metadata; python_version < \"3.8\"\npython_requires = >=3.7\n\n[options.extras_require]\ncli = click>=8; rich\n";
+        let requirements = parse_cfg(content, Path::new("setup.cfg"))?;
+        let expected = vec![
+            ("requests >=2.31".to_string(), 8),
+            ("importlib-metadata *; python_version < \"3.8\"".to_string(), 9),
+            ("click >=8".to_string(), 13),
+            ("rich *".to_string(), 13),
+        ];
+        assert_eq!(found(&requirements), expected);
+        assert_eq!(cfg_metadata(content), Some(("demo".to_string(), None)));
+        assert!(parse_cfg("[options]\ninstall_requires\n", Path::new("setup.cfg")).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_py() -> Result<()> {
+        let content = r#"from setuptools import setup
+
+REQUIRES = [
+    "requests>=2.31",  # http
+    'six' ">=1.16",
+]
+
+setup(
+    name="demo",
+    install_requires=REQUIRES,
+    extras_require={
+        "cli": ["click>=8"],
+        'docs': ("sphinx<8",),
+    },
+)
+"#;
+        let requirements = parse_py(content, Path::new("setup.py"))?;
+        let expected = vec![
+            ("requests >=2.31".to_string(), 4),
+            ("six >=1.16".to_string(), 5),
+            ("click >=8".to_string(), 12),
+            ("sphinx <8".to_string(), 13),
+        ];
+        assert_eq!(found(&requirements), expected);
+
+        let err = parse_py("setup(\n    install_requires=open('req.txt').read().split(),\n)\n", Path::new("setup.py"));
+        assert!(err.unwrap_err().to_string().starts_with("setup.py:2: install_requires is computed"));
+        Ok(())
+    }
+}
@@ -0,0 +1,170 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::error::PyhelperError;
+use crate::package::matches_name;
+use crate::output::sarif::{CONFLICT_RULE, UNRELEASED_RULE};
+
+pub const DUPLICATE_RULE: &str = "duplicate-requirement";
+pub const PIN_RULE: &str = "pin-policy";
+pub const CAP_RULE: &str = "upper-cap";
+pub const VULNERABILITY_RULE: &str = "vulnerability";
+pub const TYPOSQUAT_RULE: &str = "typosquat";
+pub const CONFUSION_RULE: &str = "dependency-confusion";
+pub const LOOKALIKE_RULE: &str = "public-lookalike";
+pub const UNMAINTAINED_RULE: &str = "unmaintained";
+pub const OUTDATED_MAJOR_RULE: &str = "outdated-major";
+pub const OUTDATED_MINOR_RULE: &str = "outdated-minor";
+pub const OUTDATED_PATCH_RULE: &str = "outdated-patch";
+
+/// Every rule findings are reported under, with its level when the
+/// configuration does not set one. The heuristics of `audit` are warnings,
+/// so they are reported without failing the run.
+pub const RULES: &[(&str, Level)] = &[
+    (CONFLICT_RULE, Level::Error),
+    (UNRELEASED_RULE, Level::Error),
+    (DUPLICATE_RULE, Level::Error),
+    (PIN_RULE, Level::Error),
+    (CAP_RULE, Level::Error),
+    (VULNERABILITY_RULE, Level::Error),
+    (TYPOSQUAT_RULE, Level::Warning),
+    (CONFUSION_RULE, Level::Warning),
+    (LOOKALIKE_RULE, Level::Info),
+    (UNMAINTAINED_RULE, Level::Warning),
+    (OUTDATED_MAJOR_RULE, Level::Error),
+    (OUTDATED_MINOR_RULE, Level::Error),
+    (OUTDATED_PATCH_RULE, Level::Error),
+];
+
+/// How much a finding matters. A run fails for findings above the level
+/// `--max-severity` tolerates, `warning` unless given.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Info,
+    Warning,
+    Error,
+}
+
+impl FromStr for Level {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        match s.to_ascii_lowercase().as_str() {
+            "info" => Ok(Level::Info),
+            "warning" => Ok(Level::Warning),
+            "error" => Ok(Level::Error),
+            _ => bail!(PyhelperError::Invalid(format!("Unknown severity level: {} (expected info, warning or error)", s))),
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Level::Info => "info",
+            Level::Warning => "warning",
+            Level::Error => "error",
+        })
+    }
+}
+
+/// The level of a rule, everywhere or for the packages matching a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Override {
+    /// A package name, or a prefix ending in `*` such as `acme-*`.
+    package: Option<String>,
+    rule: String,
+    level: Level,
+}
+
+/// The levels the configuration gives rules, as a `[severity]` table whose
+/// keys are a rule (`unmaintained = "error"`) or a package pattern and a
+/// rule (`"acme-*==outdated-major" = "info"`). A level set for a package
+/// takes precedence over the level of the rule.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SeverityPolicy {
+    overrides: Vec<Override>,
+}
+
+impl SeverityPolicy {
+    /// Adds the level of `rule`, for the packages matching `package` if given.
+    pub fn set(&mut self, package: Option<&str>, rule: &str, level: Level) -> Result<()> {
+        if !RULES.iter().any(|(known, _)| *known == rule) {
+            let known: Vec<&str> = RULES.iter().map(|(rule, _)| *rule).collect();
+            bail!(PyhelperError::Invalid(format!("Unknown rule: {} (expected one of {})", rule, known.join(", "))));
+        }
+        self.overrides.push(Override { package: package.map(str::to_string), rule: rule.to_string(), level });
+        Ok(())
+    }
+
+    /// The level of a finding of `rule` on `package`.
+    pub fn level(&self, rule: &str, package: &str) -> Level {
+        let of_rule = self.overrides.iter().filter(|o| o.rule == rule);
+        let for_package = of_rule.clone().find(|o| o.package.as_deref().is_some_and(|pattern| matches_name(pattern, package)));
+        let everywhere = of_rule.clone().find(|o| o.package.is_none());
+        match for_package.or(everywhere) {
+            Some(found) => found.level,
+            None => RULES.iter().find(|(known, _)| *known == rule).map_or(Level::Error, |(_, level)| *level),
+        }
+    }
+
+    /// Whether a finding of `rule` on `package` is more severe than `max`
+    /// tolerates, and so fails the run.
+    pub fn fails(&self, rule: &str, package: &str, max: Level) -> bool {
+        self.level(rule, package) > max
+    }
+}
+
+impl<'de> Deserialize<'de> for SeverityPolicy {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let table = BTreeMap::<String, String>::deserialize(deserializer)?;
+        let mut policy = SeverityPolicy::default();
+        for (key, level) in table {
+            let level: Level = level.parse().map_err(serde::de::Error::custom)?;
+            let (package, rule) = match key.split_once("==") {
+                Some((package, rule)) => (Some(package.trim()), rule.trim()),
+                None => (None, key.trim()),
+            };
+            policy.set(package, rule, level).map_err(serde::de::Error::custom)?;
+        }
+        Ok(policy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_levels() -> Result<()> {
+        #[derive(Deserialize)]
+        struct Config {
+            severity: SeverityPolicy,
+        }
+        let config: Config = toml::from_str(
+            r#"
+[severity]
+outdated-patch = "info"
+upper-cap = "warning"
+"acme-*==upper-cap" = "error"
+"Django==vulnerability" = "warning"
+"#,
+        )?;
+        let policy = config.severity;
+        assert_eq!(policy.level(OUTDATED_PATCH_RULE, "requests"), Level::Info);
+        assert_eq!(policy.level(CAP_RULE, "numpy"), Level::Warning);
+        assert_eq!(policy.level(CAP_RULE, "acme-billing"), Level::Error);
+        assert_eq!(policy.level(VULNERABILITY_RULE, "django"), Level::Warning);
+        assert_eq!(policy.level(UNMAINTAINED_RULE, "six"), Level::Warning);
+
+        assert!(!policy.fails(CAP_RULE, "numpy", Level::Warning));
+        assert!(policy.fails(CAP_RULE, "numpy", Level::Info));
+        assert!(!policy.fails(CONFLICT_RULE, "six", Level::Error));
+        assert!(toml::from_str::<Config>("[severity]\nunknown-rule = \"info\"").is_err());
+        assert!(toml::from_str::<Config>("[severity]\nupper-cap = \"fatal\"").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,324 @@
+use anyhow::{bail, Result};
+use regex::Regex;
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use crate::error::PyhelperError;
+use crate::filename::DistributionName;
+use crate::pep440::Pep440Version;
+use crate::pypi::Release;
+
+/// The `Accept` header asking a simple index for its PEP 691 JSON pages,
+/// and for the PEP 503 HTML pages of indexes without them.
+pub const ACCEPT: &str = "application/vnd.pypi.simple.v1+json, application/vnd.pypi.simple.v1+html;q=0.2, text/html;q=0.01";
+
+/// Whether `index_url` names a simple repository, like `https://pypi.org/simple`
+/// or devpi's `.../+simple`, rather than a JSON API.
+pub fn is_simple_index(index_url: &str) -> bool {
+    let url = index_url.trim_end_matches('/');
+    url.ends_with("/simple") || url.ends_with("/+simple")
+}
+
+/// One file listed on the project page of a simple index.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimpleFile {
+    pub filename: String,
+    /// The absolute URL of the file.
+    pub url: String,
+    pub sha256: Option<String>,
+    pub yanked: bool,
+    /// The upload time of PEP 700 JSON pages; HTML pages carry none.
+    pub upload_time: Option<String>,
+    /// Whether the index serves the file's core metadata at `<url>.metadata` (PEP 658).
+    pub core_metadata: bool,
+}
+
+impl SimpleFile {
+    /// The version in the file name, `None` for files that are not wheels or
+    /// source archives, or whose version is not PEP 440.
+    pub fn version(&self) -> Option<Pep440Version> {
+        DistributionName::parse(&self.filename).map(|dist| dist.version().clone())
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct JsonPage {
+    #[serde(default)]
+    files: Vec<JsonFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct JsonFile {
+    filename: String,
+    url: String,
+    #[serde(default)]
+    hashes: BTreeMap<String, String>,
+    /// `true`, or the reason the file was yanked.
+    #[serde(default)]
+    yanked: serde_json::Value,
+    #[serde(default)]
+    core_metadata: serde_json::Value,
+    #[serde(default)]
+    upload_time: Option<String>,
+    /// The name `core-metadata` had before PEP 714.
+    #[serde(default)]
+    dist_info_metadata: serde_json::Value,
+}
+
+/// Reads the files of a project page, JSON (PEP 691) or HTML (PEP 503).
+/// Relative file URLs are resolved against `page_url`.
+pub fn parse_page(body: &str, page_url: &str) -> Result<Vec<SimpleFile>> {
+    let flag = |value: &serde_json::Value| !matches!(value, serde_json::Value::Null | serde_json::Value::Bool(false));
+    if body.trim_start().starts_with('{') {
+        let page: JsonPage = serde_json::from_str(body)?;
+        return Ok(page
+            .files
+            .into_iter()
+            .map(|file| SimpleFile {
+                url: join_url(page_url, &file.url),
+                sha256: file.hashes.get("sha256").cloned(),
+                yanked: flag(&file.yanked),
+                upload_time: file.upload_time,
+                core_metadata: flag(&file.core_metadata) || flag(&file.dist_info_metadata),
+                filename: file.filename,
+            })
+            .collect());
+    }
+
+    static ANCHOR: OnceLock<Regex> = OnceLock::new();
+    static ATTRIBUTE: OnceLock<Regex> = OnceLock::new();
+    let anchor = ANCHOR.get_or_init(|| Regex::new(r"(?is)<a\s([^>]*)>(.*?)</a\s*>").unwrap());
+    let attribute = ATTRIBUTE
+        .get_or_init(|| Regex::new(r#"([a-zA-Z][a-zA-Z0-9-]*)(?:\s*=\s*(?:"([^"]*)"|'([^']*)'|([^\s"'>]+)))?"#).unwrap());
+    if !body.contains("<a") && !body.to_ascii_lowercase().contains("<html") {
+        bail!(PyhelperError::network(page_url, "Not a simple index page".to_string(), None));
+    }
+    let mut files = Vec::new();
+    for captures in anchor.captures_iter(body) {
+        let attributes: BTreeMap<String, Option<String>> = attribute
+            .captures_iter(&captures[1])
+            .map(|a| {
+                let value = a.get(2).or(a.get(3)).or(a.get(4)).map(|v| unescape(v.as_str()));
+                (a[1].to_ascii_lowercase(), value)
+            })
+            .collect();
+        let Some(Some(href)) = attributes.get("href") else {
+            continue;
+        };
+        let (href, fragment) = href.split_once('#').unwrap_or((href, ""));
+        let sha256 = fragment.strip_prefix("sha256=").map(str::to_string);
+        let filename = unescape(captures[2].trim());
+        files.push(SimpleFile {
+            url: join_url(page_url, href),
+            sha256,
+            yanked: attributes.contains_key("data-yanked"),
+            upload_time: None,
+            core_metadata: ["data-core-metadata", "data-dist-info-metadata"]
+                .iter()
+                .any(|key| attributes.get(*key).is_some_and(|value| value.as_deref() != Some("false"))),
+            filename,
+        });
+    }
+    Ok(files)
+}
+
+/// The releases listed by the files of a project page, oldest first. A
+/// release is yanked when all of its files are.
+pub fn releases(files: &[SimpleFile]) -> Vec<Release> {
+    let mut releases: Vec<Release> = Vec::new();
+    for file in files {
+        let Some(version) = file.version() else {
+            continue;
+        };
+        match releases.iter_mut().find(|r| r.version == version) {
+            Some(release) => {
+                release.yanked &= file.yanked;
+                release.files.push(file.filename.clone());
+                release.uploaded = release.uploaded.take().into_iter().chain(file.upload_time.clone()).min();
+            }
+            None => releases.push(Release {
+                version,
+                yanked: file.yanked,
+                files: vec![file.filename.clone()],
+                uploaded: file.upload_time.clone(),
+            }),
+        }
+    }
+    releases.sort_by(|a, b| a.version.cmp(&b.version));
+    releases
+}
+
+/// The files of one release, wheels first, as they are the ones indexes
+/// most often serve core metadata for.
+pub fn release_files<'a>(files: &'a [SimpleFile], version: &Pep440Version) -> Vec<&'a SimpleFile> {
+    let mut found: Vec<&SimpleFile> = files.iter().filter(|file| file.version().as_ref() == Some(version)).collect();
+    found.sort_by_key(|file| !file.filename.ends_with(".whl"));
+    found
+}
+
+/// Describes a release the way PyPI's JSON API does, so simple indexes can
+/// answer the same questions: its file hashes, and with its core metadata
+/// the `info` fields. Without core metadata `info` is `null`.
+pub fn release_document(files: &[&SimpleFile], core_metadata: Option<&str>) -> String {
+    let urls: Vec<_> = files
+        .iter()
+        .map(|file| {
+            let digests: BTreeMap<&str, &str> = file.sha256.iter().map(|sha| ("sha256", sha.as_str())).collect();
+            json!({"filename": file.filename, "url": file.url, "digests": digests})
+        })
+        .collect();
+    let info = core_metadata.map(|metadata| {
+        let headers = metadata_headers(metadata);
+        let all = |key: &str| -> Vec<&str> {
+            headers.iter().filter(|(k, _)| k.eq_ignore_ascii_case(key)).map(|(_, v)| v.as_str()).collect()
+        };
+        json!({
+            "name": all("Name").first(),
+            "version": all("Version").first(),
+            "requires_dist": all("Requires-Dist"),
+            "license": all("License").first(),
+            "license_expression": all("License-Expression").first(),
+            "classifiers": all("Classifier"),
+            "requires_python": all("Requires-Python").first(),
+            "home_page": all("Home-page").first(),
+            "project_urls": all("Project-URL")
+                .iter()
+                .filter_map(|entry| entry.split_once(','))
+                .map(|(label, url)| (label.trim(), url.trim()))
+                .collect::<BTreeMap<_, _>>(),
+        })
+    });
+    json!({"info": info, "urls": urls}).to_string()
+}
+
+/// The headers of a core metadata file, with continuation lines joined.
+fn metadata_headers(metadata: &str) -> Vec<(String, String)> {
+    let mut headers: Vec<(String, String)> = Vec::new();
+    for line in metadata.lines() {
+        if line.trim().is_empty() {
+            // The description follows the headers
+            break;
+        }
+        match (line.starts_with([' ', '\t']), headers.last_mut()) {
+            (true, Some((_, value))) => {
+                value.push('\n');
+                value.push_str(line.trim());
+            }
+            _ => {
+                if let Some((key, value)) = line.split_once(':') {
+                    headers.push((key.trim().to_string(), value.trim().to_string()));
+                }
+            }
+        }
+    }
+    headers
+}
+
+/// Resolves `href` against the URL of the page it was found on.
+pub fn join_url(base: &str, href: &str) -> String {
+    if href.contains("://") {
+        return href.to_string();
+    }
+    let (scheme, rest) = base.split_once("://").unwrap_or(("https", base));
+    let authority_end = rest.find('/').unwrap_or(rest.len());
+    let (authority, path) = rest.split_at(authority_end);
+    if let Some(absolute) = href.strip_prefix("//") {
+        return format!("{}://{}", scheme, absolute);
+    }
+    let mut segments: Vec<&str> = if href.starts_with('/') {
+        Vec::new()
+    } else {
+        // The last segment of the page's path is the page itself
+        let mut segments: Vec<&str> = path.split('/').filter(|s| !s.is_empty()).collect();
+        if !path.ends_with('/') {
+            segments.pop();
+        }
+        segments
+    };
+    for segment in href.split('/').filter(|s| !s.is_empty()) {
+        match segment {
+            "." => {}
+            ".." => {
+                segments.pop();
+            }
+            segment => segments.push(segment),
+        }
+    }
+    let trailing = if href.ends_with('/') { "/" } else { "" };
+    format!("{}://{}/{}{}", scheme, authority, segments.join("/"), trailing)
+}
+
+fn unescape(text: &str) -> String {
+    text.replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_page() -> Result<()> {
+        let page = "https://nexus.example.com/repository/pypi/simple/demo/";
+        let html = r#"<!DOCTYPE html><html><body>
+            <a href="../../packages/demo-1.0.tar.gz#sha256=aa11" data-requires-python="&gt;=3.8">demo-1.0.tar.gz</a>
+            <a href="/packages/demo-1.0-py3-none-any.whl#sha256=bb22" data-dist-info-metadata="sha256=cc">demo-1.0-py3-none-any.whl</a>
+            <a href='https://files.example.com/demo-1.1.tar.gz' data-yanked="broken">demo-1.1.tar.gz</a>
+            <a href="demo-legacy.exe">demo-legacy.exe</a>
+        </body></html>"#;
+        let files = parse_page(html, page)?;
+        assert_eq!(files.len(), 4);
+        assert_eq!(files[0].url, "https://nexus.example.com/repository/pypi/packages/demo-1.0.tar.gz");
+        assert_eq!(files[1].url, "https://nexus.example.com/packages/demo-1.0-py3-none-any.whl");
+        assert_eq!(files[0].sha256.as_deref(), Some("aa11"));
+        assert!(files[1].core_metadata && !files[0].core_metadata);
+        assert!(files[2].yanked);
+
+        let listed = releases(&files);
+        let versions: Vec<(String, bool)> = listed.iter().map(|r| (r.version.to_string(), r.yanked)).collect();
+        assert_eq!(versions, vec![("1.0".to_string(), false), ("1.1".to_string(), true)]);
+
+        let json = r#"{"meta": {"api-version": "1.1"}, "name": "demo", "files": [
+            {"filename": "demo-2.0-py3-none-any.whl", "url": "demo-2.0-py3-none-any.whl", "hashes": {"sha256": "dd44"},
+             "core-metadata": {"sha256": "ee"}, "yanked": false, "upload-time": "2024-01-05T10:00:00.123456Z"}
+        ]}"#;
+        let files = parse_page(json, page)?;
+        assert_eq!(files[0].url, format!("{}demo-2.0-py3-none-any.whl", page));
+        assert!(files[0].core_metadata && !files[0].yanked);
+        assert_eq!(releases(&files)[0].uploaded.as_deref(), Some("2024-01-05T10:00:00.123456Z"));
+        assert!(parse_page("Service unavailable", page).is_err());
+        assert!(is_simple_index("https://pypi.org/simple/") && is_simple_index("https://devpi.example.com/root/pypi/+simple"));
+        assert!(!is_simple_index("https://pypi.org/pypi"));
+        Ok(())
+    }
+
+    #[test]
+    fn test_release_document() -> Result<()> {
+        let files = parse_page(
+            r#"<a href="demo-1.0-py3-none-any.whl#sha256=bb22">demo-1.0-py3-none-any.whl</a>"#,
+            "https://example.com/simple/demo/",
+        )?;
+        let version = Pep440Version::parse("1.0")?;
+        let metadata = "Metadata-Version: 2.1\nName: demo\nVersion: 1.0\nLicense: MIT\nRequires-Dist: requests>=2\nRequires-Dist: pytest; extra == \"test\"\nRequires-Python: >=3.8\nProject-URL: Source, https://github.com/example/demo\n\nThe description: not a header\n";
+        let document = release_document(&release_files(&files, &version), Some(metadata));
+        let requires = crate::pypi::parse_requires_dist(&document)?;
+        assert_eq!(requires.len(), 2);
+        assert_eq!(crate::pypi::parse_license_info(&document)?.license.as_deref(), Some("MIT"));
+        assert_eq!(crate::pypi::parse_hashes(&document)?, vec!["sha256:bb22"]);
+        let project = crate::pypi::parse_project_info(&document)?;
+        assert_eq!(project.requires_python.as_deref(), Some(">=3.8"));
+        assert_eq!(project.urls, vec![("Source".to_string(), "https://github.com/example/demo".to_string())]);
+
+        let without = release_document(&release_files(&files, &version), None);
+        assert!(crate::pypi::parse_requires_dist(&without).is_err());
+        assert_eq!(crate::pypi::parse_hashes(&without)?, vec!["sha256:bb22"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,360 @@
+use anyhow::{bail, Result};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::audit::Audited;
+use crate::error::PyhelperError;
+use crate::package::{canonicalize_name, PythonPackage};
+use crate::pep440::Pep440Version;
+use crate::prerelease::PreReleasePolicy;
+use crate::pypi::{self, PypiClient, Release};
+use crate::range::{Interval, IntervalSet};
+use crate::requirements::RequirementLine;
+use crate::specifier::{Operator, SpecifierSet};
+
+/// How many releases of a dependent, newest first, are searched for one that
+/// accepts the new versions.
+const MAX_CANDIDATES: usize = 10;
+
+/// A requirement the simulated versions no longer satisfy.
+#[derive(Debug, Clone)]
+pub struct Break {
+    /// The package declaring it with the version that does, like `pandas 1.5.3`,
+    /// or the file and line of a constraint.
+    pub source: String,
+    /// PEP 503 normalized name of the declaring package; `None` for a constraint.
+    pub dependent: Option<String>,
+    pub requirement: PythonPackage,
+    /// The version of the required package it excludes.
+    pub excludes: Pep440Version,
+}
+
+/// A pinned package that has to change version along with the upgrade.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Move {
+    pub name: String,
+    pub from: Pep440Version,
+    /// `None` when no release fits; only the newest releases on the side of
+    /// the move are searched for dependents.
+    pub to: Option<Pep440Version>,
+}
+
+/// What bumping one pin does to the other pins of a file.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// The upgraded package as the file or the upgrade spells it.
+    pub name: String,
+    /// The version the file pins, if it pins one.
+    pub from: Option<Pep440Version>,
+    pub to: Pep440Version,
+    pub broken: Vec<Break>,
+    pub moves: Vec<Move>,
+    /// Dependencies of the new versions the file does not list, with the
+    /// release requiring them.
+    pub added: Vec<(String, PythonPackage)>,
+}
+
+impl Simulation {
+    /// Whether the upgrade fits without touching anything else.
+    pub fn is_clean(&self) -> bool {
+        self.broken.is_empty() && self.moves.is_empty()
+    }
+
+    /// Whether the simulated version is older than the pinned one.
+    pub fn is_downgrade(&self) -> bool {
+        self.from.as_ref().is_some_and(|from| self.to < *from)
+    }
+}
+
+/// Simulates pinning `upgrade`, an `==` requirement, among the pinned
+/// `packages`, which the `constraints` of the file also bind.
+///
+/// The requirements of every pinned release are checked against the new
+/// version, and the requirements of the new version against the pins. A
+/// dependent excluding the new version moves to the newest release that
+/// accepts it, among those after its pin for an upgrade and those before it
+/// for a downgrade; a dependency excluded by the new version moves to the release
+/// pip would pick for its requirements. Each move is checked the same way in
+/// turn, and a package only moves once. Markers are not evaluated.
+pub fn simulate(
+    client: &PypiClient,
+    packages: &[Audited],
+    constraints: &[RequirementLine],
+    upgrade: &PythonPackage,
+    policy: PreReleasePolicy,
+) -> Result<Simulation> {
+    let to = upgrade_version(upgrade)?;
+    let target = upgrade.canonical_name();
+    let mut versions: BTreeMap<String, (String, Pep440Version)> = packages
+        .iter()
+        .map(|package| (canonicalize_name(&package.name), (package.name.clone(), package.version.clone())))
+        .collect();
+    let from = versions.get(&target).map(|(_, version)| version.clone());
+    let name = versions.get(&target).map_or(upgrade.name.clone(), |(name, _)| name.clone());
+    let released = client.releases(&upgrade.name)?;
+    if !released.iter().any(|release| release.version == to) {
+        bail!(PyhelperError::NotFound(format!("Release not found on index: {} {}", upgrade.name, to)));
+    }
+    versions.insert(target.clone(), (name.clone(), to.clone()));
+    let older = from.as_ref().is_some_and(|from| to < *from);
+
+    let pins: Vec<(&str, &Pep440Version)> = versions.values().map(|(name, version)| (name.as_str(), version)).collect();
+    let mut dependencies: BTreeMap<String, Vec<PythonPackage>> = versions
+        .keys()
+        .cloned()
+        .zip(client.requires_dist_all(&pins)?.into_iter().map(applying))
+        .collect();
+    let declared: Vec<&RequirementLine> = constraints.iter().filter(|line| line.package.url.is_none()).collect();
+
+    let mut simulation = Simulation {
+        name,
+        from,
+        to,
+        broken: Vec::new(),
+        moves: Vec::new(),
+        added: Vec::new(),
+    };
+    let mut moved: BTreeSet<String> = BTreeSet::from([target.clone()]);
+    let mut pending = vec![target];
+    while let Some(changed) = pending.pop() {
+        for broken in breaks(&versions, &dependencies, &declared, &changed) {
+            let required = broken.requirement.canonical_name();
+            // The side that did not just change has to move; constraints cannot
+            let moving = if required == changed { broken.dependent.clone() } else { Some(required.clone()) };
+            simulation.broken.push(broken);
+            let Some(moving) = moving.filter(|moving| !moved.contains(moving)) else {
+                continue;
+            };
+            moved.insert(moving.clone());
+            let (spelled, pinned) = versions[&moving].clone();
+            let releases = client.releases(&spelled)?;
+            let to = if moving == required {
+                pypi::best_match(&releases, &demands(&dependencies, &declared, &moving), policy)
+            } else {
+                accepting(client, &spelled, &candidates(&releases, &pinned, older, policy), &versions, &moved)?
+            };
+            simulation.moves.push(Move {
+                name: spelled.clone(),
+                from: pinned,
+                to: to.clone(),
+            });
+            if let Some(to) = to {
+                let fetched = client.requires_dist(&spelled, &to)?;
+                dependencies.insert(moving.clone(), applying(fetched));
+                versions.insert(moving.clone(), (spelled, to));
+                pending.push(moving);
+            }
+        }
+    }
+
+    for name in &moved {
+        let (spelled, version) = &versions[name];
+        for dependency in &dependencies[name] {
+            let required = dependency.canonical_name();
+            let listed = versions.contains_key(&required) || declared.iter().any(|line| line.package.canonical_name() == required);
+            if !listed && !simulation.added.iter().any(|(_, added)| added.canonical_name() == required) {
+                simulation.added.push((format!("{} {}", spelled, version), dependency.clone()));
+            }
+        }
+    }
+    Ok(simulation)
+}
+
+/// The version an `==` upgrade pins.
+fn upgrade_version(upgrade: &PythonPackage) -> Result<Pep440Version> {
+    match upgrade.specifiers.specifiers.as_slice() {
+        [spec] if spec.op == Operator::Equal && !spec.wildcard => Ok(spec.version.clone()),
+        _ => {
+            let message = format!("The upgrade must pin one version, e.g. {}==2.0.0: {}", upgrade.name, upgrade);
+            Err(PyhelperError::Invalid(message).into())
+        }
+    }
+}
+
+/// The dependencies that apply without extras.
+fn applying(dependencies: Vec<PythonPackage>) -> Vec<PythonPackage> {
+    dependencies.into_iter().filter(|dependency| dependency.may_apply(&[])).collect()
+}
+
+/// The requirements broken by the version of `changed`: those of the other
+/// packages and of the constraints that exclude it, and those of `changed`
+/// that exclude the version of another package.
+fn breaks(
+    versions: &BTreeMap<String, (String, Pep440Version)>,
+    dependencies: &BTreeMap<String, Vec<PythonPackage>>,
+    declared: &[&RequirementLine],
+    changed: &str,
+) -> Vec<Break> {
+    let label = |name: &str| {
+        let (spelled, version) = &versions[name];
+        format!("{} {}", spelled, version)
+    };
+    let mut broken = Vec::new();
+    let (_, version) = &versions[changed];
+    for (name, requirements) in dependencies.iter().filter(|(name, _)| *name != changed) {
+        for requirement in requirements.iter().filter(|r| r.canonical_name() == changed) {
+            if !requirement.specifiers.range().contains(version) {
+                broken.push(Break {
+                    source: label(name),
+                    dependent: Some(name.clone()),
+                    requirement: requirement.clone(),
+                    excludes: version.clone(),
+                });
+            }
+        }
+    }
+    for line in declared.iter().filter(|line| line.package.canonical_name() == changed) {
+        if !line.package.specifiers.range().contains(version) {
+            broken.push(Break {
+                source: line.location(),
+                dependent: None,
+                requirement: line.package.clone(),
+                excludes: version.clone(),
+            });
+        }
+    }
+    for requirement in &dependencies[changed] {
+        let required = requirement.canonical_name();
+        if let Some((_, pinned)) = versions.get(&required).filter(|(_, pinned)| !requirement.specifiers.range().contains(pinned)) {
+            broken.push(Break {
+                source: label(changed),
+                dependent: Some(changed.to_string()),
+                requirement: requirement.clone(),
+                excludes: pinned.clone(),
+            });
+        }
+    }
+    broken
+}
+
+/// Every requirement on `name`, combined.
+fn demands(dependencies: &BTreeMap<String, Vec<PythonPackage>>, declared: &[&RequirementLine], name: &str) -> SpecifierSet {
+    let from_packages = dependencies.values().flatten().filter(|r| r.canonical_name() == name);
+    let from_files = declared.iter().map(|line| &line.package).filter(|r| r.canonical_name() == name);
+    SpecifierSet {
+        specifiers: from_packages.chain(from_files).flat_map(|r| r.specifiers.specifiers.clone()).collect(),
+    }
+}
+
+/// Simulates pinning `name` back to the older release `to`, and finds the
+/// co-downgrades that make it fit among the pinned `packages`.
+pub fn downgrade(
+    client: &PypiClient,
+    packages: &[Audited],
+    constraints: &[RequirementLine],
+    name: &str,
+    to: &Pep440Version,
+    policy: PreReleasePolicy,
+) -> Result<Simulation> {
+    let pinned = packages
+        .iter()
+        .find(|package| canonicalize_name(&package.name) == canonicalize_name(name))
+        .ok_or_else(|| PyhelperError::Invalid(format!("{} is not pinned in the file", name)))?;
+    if *to >= pinned.version {
+        let message = format!("{} is pinned at {}, so a downgrade needs an older version, not {}", pinned.name, pinned.version, to);
+        bail!(PyhelperError::Invalid(message));
+    }
+    let rollback = PythonPackage::parse(&format!("{}=={}", pinned.name, to))?;
+    simulate(client, packages, constraints, &rollback, policy)
+}
+
+/// The newest releases a dependent pinned at `pinned` may move to, newest
+/// first: those after it, or those before it when moving `older`.
+fn candidates(releases: &[Release], pinned: &Pep440Version, older: bool, policy: PreReleasePolicy) -> Vec<Pep440Version> {
+    let range = IntervalSet::from(match older {
+        true => Interval::less_than(pinned.clone()),
+        false => Interval::greater_than(pinned.clone()),
+    });
+    let mut candidates = pypi::satisfying(releases, &range, policy == PreReleasePolicy::Allow);
+    candidates.reverse();
+    candidates.truncate(MAX_CANDIDATES);
+    candidates
+}
+
+/// The first of the `candidates` releases of a dependent whose requirements
+/// accept the versions of every moved package.
+fn accepting(
+    client: &PypiClient,
+    name: &str,
+    candidates: &[Pep440Version],
+    versions: &BTreeMap<String, (String, Pep440Version)>,
+    moved: &BTreeSet<String>,
+) -> Result<Option<Pep440Version>> {
+    let asked: Vec<(&str, &Pep440Version)> = candidates.iter().map(|version| (name, version)).collect();
+    for (version, requirements) in candidates.iter().zip(client.requires_dist_all(&asked)?) {
+        let accepts = applying(requirements).iter().all(|requirement| {
+            let required = requirement.canonical_name();
+            !moved.contains(&required) || requirement.specifiers.range().contains(&versions[&required].1)
+        });
+        if accepts {
+            return Ok(Some(version.clone()));
+        }
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_breaks() -> Result<()> {
+        let version = |v: &str| Pep440Version::parse(v);
+        let versions = BTreeMap::from([
+            ("numpy".to_string(), ("numpy".to_string(), version("2.0.0")?)),
+            ("pandas".to_string(), ("pandas".to_string(), version("1.5.3")?)),
+            ("scipy".to_string(), ("scipy".to_string(), version("1.13.0")?)),
+        ]);
+        let dependencies = BTreeMap::from([
+            ("numpy".to_string(), Vec::new()),
+            ("pandas".to_string(), vec![PythonPackage::parse("numpy>=1.21,<2")?]),
+            ("scipy".to_string(), vec![PythonPackage::parse("numpy>=1.22.4")?]),
+        ]);
+        let constraint = RequirementLine {
+            package: PythonPackage::parse("numpy!=2.0.0")?,
+            file: "constraints.txt".into(),
+            line: 3,
+            columns: None,
+            hashes: Vec::new(),
+        };
+        let broken = breaks(&versions, &dependencies, &[&constraint], "numpy");
+        let sources: Vec<&str> = broken.iter().map(|b| b.source.as_str()).collect();
+        assert_eq!(sources, vec!["pandas 1.5.3", "constraints.txt:3"]);
+        assert_eq!(broken[0].excludes, version("2.0.0")?);
+
+        // A dependency of the changed package excluding a pin
+        let dependencies = BTreeMap::from([
+            ("numpy".to_string(), Vec::new()),
+            ("pandas".to_string(), vec![PythonPackage::parse("scipy<1.10")?]),
+            ("scipy".to_string(), Vec::new()),
+        ]);
+        let broken = breaks(&versions, &dependencies, &[], "pandas");
+        assert_eq!(broken.len(), 1);
+        assert_eq!((broken[0].source.as_str(), broken[0].excludes.to_string()), ("pandas 1.5.3", "1.13.0".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_candidates() -> Result<()> {
+        let release = |v: &str, yanked: bool| -> Result<Release> {
+            Ok(Release {
+                version: Pep440Version::parse(v)?,
+                yanked,
+                files: Vec::new(),
+                uploaded: None,
+            })
+        };
+        let releases = vec![
+            release("1.0", false)?,
+            release("1.1", true)?,
+            release("1.2", false)?,
+            release("2.0", false)?,
+            release("2.1rc1", false)?,
+            release("2.1", false)?,
+        ];
+        let pinned = Pep440Version::parse("2.0")?;
+        let shown = |versions: Vec<Pep440Version>| versions.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+        assert_eq!(shown(candidates(&releases, &pinned, false, PreReleasePolicy::Deny)), vec!["2.1"]);
+        assert_eq!(shown(candidates(&releases, &pinned, true, PreReleasePolicy::Deny)), vec!["1.2", "1.0"]);
+        Ok(())
+    }
+}
@@ -0,0 +1,585 @@
+use anyhow::{bail, Result};
+use regex::Regex;
+use std::fmt;
+use std::ops::Bound;
+use std::sync::OnceLock;
+
+use crate::error::PyhelperError;
+use crate::pep440::Pep440Version;
+use crate::range::{Interval, IntervalSet};
+
+/// Comparison operator of a single version specifier clause.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operator {
+    Equal,
+    NotEqual,
+    LessEq,
+    GreaterEq,
+    Less,
+    Greater,
+    Compatible,
+    /// `===`, comparing the version string as text.
+    ArbitraryEqual,
+}
+
+impl fmt::Display for Operator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Operator::Equal => "==",
+            Operator::NotEqual => "!=",
+            Operator::LessEq => "<=",
+            Operator::GreaterEq => ">=",
+            Operator::Less => "<",
+            Operator::Greater => ">",
+            Operator::Compatible => "~=",
+            Operator::ArbitraryEqual => "===",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+/// The highest post-release of `<1.0.post3` whose dev releases are carved out
+/// one by one; above it, as in date-like `.post20240101`, they are kept.
+pub(crate) const MAX_SPLIT_POST: u64 = 100;
+
+/// A single clause such as `>=2.0`, `~=1.4.2` or `==2.8.*`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Specifier {
+    pub op: Operator,
+    pub version: Pep440Version,
+    /// Set for `==V.*` and `!=V.*`, which match on the release prefix only.
+    pub wildcard: bool,
+    /// The version string of an `===` clause, compared as text. When it is
+    /// not a PEP 440 version, such as `1.0-custom`, it cannot be ordered
+    /// against other versions, so the range is unconstrained; `version` is
+    /// then `0`.
+    pub literal: Option<String>,
+}
+
+fn specifier_regex() -> &'static Regex {
+    static RE: OnceLock<Regex> = OnceLock::new();
+    RE.get_or_init(|| Regex::new(r"^\s*(~=|===|==|!=|<=|>=|<|>)\s*(\S+)\s*$").unwrap())
+}
+
+impl Specifier {
+    pub fn parse(input: &str) -> Result<Self> {
+        let captures = specifier_regex()
+            .captures(input)
+            .ok_or_else(|| PyhelperError::Specifier(format!("Invalid version specifier: {}", input)))?;
+
+        let op = match &captures[1] {
+            "~=" => Operator::Compatible,
+            "===" => Operator::ArbitraryEqual,
+            "==" => Operator::Equal,
+            "!=" => Operator::NotEqual,
+            "<=" => Operator::LessEq,
+            ">=" => Operator::GreaterEq,
+            "<" => Operator::Less,
+            _ => Operator::Greater,
+        };
+        if op == Operator::ArbitraryEqual {
+            let version = Pep440Version::parse(&captures[2]).or_else(|_| Pep440Version::parse("0"))?;
+            return Ok(Specifier {
+                op,
+                version,
+                wildcard: false,
+                literal: Some(captures[2].to_string()),
+            });
+        }
+        let (version_str, wildcard) = match captures[2].strip_suffix(".*") {
+            Some(prefix) => (prefix, true),
+            None => (&captures[2], false),
+        };
+        let version = Pep440Version::parse(version_str)?;
+        if wildcard {
+            if !matches!(op, Operator::Equal | Operator::NotEqual) {
+                bail!(PyhelperError::Specifier(format!("Wildcards are only allowed with == and !=: {}", input)));
+            }
+            if version.pre.is_some()
+                || version.post.is_some()
+                || version.dev.is_some()
+                || !version.local.is_empty()
+            {
+                bail!(PyhelperError::Specifier(format!("Wildcard prefix must be a release number: {}", input)));
+            }
+        }
+        // Only a match names a local label; there is no ordering against one
+        if !version.local.is_empty() && !matches!(op, Operator::Equal | Operator::NotEqual) {
+            bail!(PyhelperError::Specifier(format!("Local versions are only allowed with == and !=: {}", input)));
+        }
+        if op == Operator::Compatible && version.release.len() < 2 {
+            bail!(PyhelperError::Specifier(format!(
+                "Compatible release requires at least two release segments: {}",
+                input
+            )));
+        }
+
+        Ok(Specifier {
+            op,
+            version,
+            wildcard,
+            literal: None,
+        })
+    }
+
+    /// The versions this clause accepts.
+    pub fn range(&self) -> IntervalSet<Pep440Version> {
+        let version = self.version.clone();
+        if self.wildcard {
+            // The prefix covers everything from its first dev release up to the next prefix
+            let floor = Pep440Version {
+                dev: Some(0),
+                ..version
+            };
+            let prefix = IntervalSet::from(Interval::between(
+                floor,
+                self.version.next_prefix(self.version.release.len()),
+            ));
+            return match self.op {
+                Operator::NotEqual => prefix.complement(),
+                _ => prefix,
+            };
+        }
+        // A clause without a local label ignores the label of the candidate,
+        // so `==2.1.0` and `<=2.1.0` admit `2.1.0+cu118` while `>2.1.0` does not
+        let public = self.version.local.is_empty();
+        let interval = match self.op {
+            Operator::ArbitraryEqual if self.is_opaque() => return IntervalSet::full(),
+            Operator::NotEqual if public => {
+                return IntervalSet::from(Interval::between(version.clone(), version.after_locals())).complement()
+            }
+            Operator::NotEqual => return IntervalSet::excluding(version),
+            Operator::Equal if public => Interval::between(version.clone(), version.after_locals()),
+            Operator::Equal | Operator::ArbitraryEqual => Interval::exact(version),
+            Operator::LessEq if public => Interval::less_than(version.after_locals()),
+            Operator::LessEq => Interval::at_most(version),
+            Operator::GreaterEq => Interval::at_least(version),
+            // `<2.0` leaves out `2.0rc1` and `2.0.dev0` too, unless it names a
+            // pre-release itself; pip takes it to mean "before the 2.0 series"
+            Operator::Less if public && !version.is_prerelease() && version.post.is_none() => {
+                Interval::less_than(Pep440Version { dev: Some(0), ..version })
+            }
+            // Likewise `<2.0.post2` leaves out `2.0rc1` and `2.0.post1.dev0`
+            Operator::Less if public && !version.is_prerelease() => {
+                let post = version.post.unwrap_or_default();
+                let release = Pep440Version { post: None, ..version.clone() };
+                let mut range = IntervalSet::from(Interval::less_than(Pep440Version { dev: Some(0), ..release.clone() }));
+                if post > MAX_SPLIT_POST {
+                    return range.union(&IntervalSet::from(Interval::between(release, version)));
+                }
+                let mut from = release.clone();
+                for next in 0..=post {
+                    let to = Pep440Version { post: Some(next), dev: Some(0), ..release.clone() };
+                    range = range.union(&IntervalSet::from(Interval::between(from, to)));
+                    from = Pep440Version { post: Some(next), ..release.clone() };
+                }
+                return range;
+            }
+            Operator::Less => Interval::less_than(version),
+            // `>2.0` leaves out `2.0.post1` as well, unless it names a post-release
+            Operator::Greater if public && !version.is_prerelease() && version.post.is_none() => {
+                Interval::greater_than(version.after_posts())
+            }
+            // `>2.0rc1` and `>2.0.dev0` leave them out too, from `2.0.post0.dev0` on
+            Operator::Greater if public && version.post.is_none() => {
+                let posts = Interval::new(
+                    Bound::Included(Pep440Version { post: Some(0), dev: Some(0), ..version.after_posts() }),
+                    Bound::Included(version.after_posts()),
+                );
+                return IntervalSet::from(Interval::at_least(version.after_locals()))
+                    .intersect(&IntervalSet::from(posts).complement());
+            }
+            Operator::Greater if public =>Interval::at_least(version.after_locals()),
+            Operator::Greater => Interval::greater_than(version),
+            Operator::Compatible => {
+                // `~=1.4.2` means `>=1.4.2, ==1.4.*`
+                let upper = self.version.next_prefix(self.version.release.len() - 1);
+                Interval::between(version, upper)
+            }
+        };
+        IntervalSet::from(interval)
+    }
+
+    /// Whether this is an `===` clause on a string that is not a PEP 440
+    /// version, which has no place in the version order.
+    pub fn is_opaque(&self) -> bool {
+        self.literal.as_deref().is_some_and(|text| Pep440Version::parse(text).is_err())
+    }
+}
+
+impl fmt::Display for Specifier {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.literal {
+            Some(literal) => write!(f, "{}{}", self.op, literal)?,
+            None => write!(f, "{}{}", self.op, self.version)?,
+        }
+        if self.wildcard {
+            write!(f, ".*")?;
+        }
+        Ok(())
+    }
+}
+
+/// The same versions as `interval`, with the bounds a clause would write: a
+/// bound computed past the local versions of `2.0`, `2.0.post0.dev0`, is
+/// shown as a bound on `2.0` itself, one ending before its first dev
+/// release, `2.0.dev0`, as `<2.0`, and one past its post-releases as `(2.0`
+/// or `2.0]`, the way `>2.0` leaves them out. An interval starting among
+/// those post-releases keeps its computed end, as `2.0]` would come before
+/// its start.
+pub fn as_written(interval: &Interval<Pep440Version>) -> Interval<Pep440Version> {
+    let series = |v: &Pep440Version| v.dev == Some(0) && v.pre.is_none() && v.post.is_none();
+    let lower = match &interval.lower {
+        Bound::Excluded(v) if v.post == Some(u64::MAX) => Bound::Excluded(Pep440Version { post: None, ..v.clone() }),
+        Bound::Included(v) => v.before_locals().map_or_else(|| Bound::Included(v.clone()), Bound::Excluded),
+        bound => bound.clone(),
+    };
+    let at_or_below = |release: &Pep440Version| match &lower {
+        Bound::Included(v) | Bound::Excluded(v) => v <= release,
+        Bound::Unbounded => true,
+    };
+    let upper = match &interval.upper {
+        Bound::Included(v) | Bound::Excluded(v) if v.post == Some(u64::MAX) => {
+            let release = Pep440Version { post: None, ..v.clone() };
+            if at_or_below(&release) {
+                Bound::Included(release)
+            } else {
+                interval.upper.clone()
+            }
+        }
+        // `<2.0` and `~=1.9` end at `2.0.dev0`, which is how neither is written
+        Bound::Excluded(v) if series(v) => Bound::Excluded(Pep440Version { dev: None, ..v.clone() }),
+        Bound::Excluded(v) => v.before_locals().map_or_else(|| Bound::Excluded(v.clone()), Bound::Included),
+        bound => bound.clone(),
+    };
+    Interval::new(lower, upper)
+}
+
+/// A comma-separated list of clauses that must all hold, e.g. `>=2.0,<3.0,!=2.5.0`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SpecifierSet {
+    pub specifiers: Vec<Specifier>,
+}
+
+impl SpecifierSet {
+    pub fn parse(input: &str) -> Result<Self> {
+        if input.trim().is_empty() {
+            return Ok(SpecifierSet::default());
+        }
+        let specifiers = input
+            .split(',')
+            .map(Specifier::parse)
+            .collect::<Result<Vec<_>>>()?;
+        Ok(SpecifierSet { specifiers })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.specifiers.is_empty()
+    }
+
+    /// Whether a clause names a pre-release, which opts the set in to
+    /// pre-releases as in `>=2.0.0rc1`. Exclusions such as `!=2.0rc1` do not.
+    pub fn mentions_prerelease(&self) -> bool {
+        self.specifiers
+            .iter()
+            .any(|s| s.op != Operator::NotEqual && s.version.is_prerelease())
+    }
+
+    /// The text pinned by a `===` clause, such as `1.0-custom` or `1.0`.
+    /// When it is not a PEP 440 version it has no place in the version
+    /// order, so [`range`](Self::range) does not constrain it.
+    pub fn literal(&self) -> Option<&str> {
+        self.specifiers.iter().find_map(|s| s.literal.as_deref())
+    }
+
+    /// Whether a `===` pin in either set rules out every version the other
+    /// allows. The pinned text satisfies other `===` clauses only when it is
+    /// the same, ignoring case, as packaging compares them, and the ordered
+    /// clauses only when it is a PEP 440 version they contain.
+    pub fn excludes_literal(&self, other: &SpecifierSet) -> bool {
+        let Some(pinned) = self.literal().or_else(|| other.literal()) else {
+            return false;
+        };
+        let version = Pep440Version::parse(pinned).ok();
+        !self.specifiers.iter().chain(&other.specifiers).all(|s| match (&s.literal, &version) {
+            (Some(text), _) => text.eq_ignore_ascii_case(pinned),
+            (None, Some(version)) => s.range().contains(version),
+            (None, None) => false,
+        })
+    }
+
+    /// Whether `version`, written the way packaging normalizes it, is the
+    /// text of every `===` clause; the others are left to [`range`](Self::range).
+    pub fn matches_literal(&self, version: &Pep440Version) -> bool {
+        let text = version.to_string();
+        self.specifiers.iter().all(|s| s.literal.as_deref().is_none_or(|pinned| pinned.eq_ignore_ascii_case(&text)))
+    }
+
+    /// The versions accepted by every clause of the set.
+    pub fn range(&self) -> IntervalSet<Pep440Version> {
+        self.specifiers
+            .iter()
+            .fold(IntervalSet::full(), |acc, s| acc.intersect(&s.range()))
+    }
+
+    /// The set without the clauses the remaining ones already imply, keeping
+    /// the first spelling of duplicates. A clause naming a pre-release is only
+    /// dropped when another kept clause names one too, so pre-releases stay
+    /// allowed, and `===` clauses, which compare text, are always kept.
+    pub fn reduced(&self) -> SpecifierSet {
+        let mut kept: Vec<Specifier> = Vec::new();
+        for clause in &self.specifiers {
+            if !kept.iter().any(|k| k.to_string() == clause.to_string()) {
+                kept.push(clause.clone());
+            }
+        }
+
+        let mut i = 0;
+        while i < kept.len() {
+            let others: Vec<&Specifier> = kept.iter().enumerate().filter(|(j, _)| *j != i).map(|(_, s)| s).collect();
+            let implied = others
+                .iter()
+                .fold(IntervalSet::full(), |acc, s| acc.intersect(&s.range()))
+                .intersect(&kept[i].range().complement())
+                .is_empty();
+            let opts_in = |s: &Specifier| SpecifierSet { specifiers: vec![s.clone()] }.mentions_prerelease();
+            let literal = kept[i].literal.is_some();
+            if implied && !literal && (!opts_in(&kept[i]) || others.iter().any(|s| opts_in(s))) {
+                kept.remove(i);
+            } else {
+                i += 1;
+            }
+        }
+        SpecifierSet { specifiers: kept }
+    }
+
+    /// The reduced set in canonical order: pins, compatible releases, lower
+    /// bounds, upper bounds and exclusions, each by version.
+    pub fn simplified(&self) -> SpecifierSet {
+        let rank = |s: &Specifier| match s.op {
+            Operator::Equal | Operator::ArbitraryEqual => 0,
+            Operator::Compatible => 1,
+            Operator::Greater | Operator::GreaterEq => 2,
+            Operator::Less | Operator::LessEq => 3,
+            Operator::NotEqual => 4,
+        };
+        let mut specifiers = self.reduced().specifiers;
+        specifiers.sort_by(|a, b| rank(a).cmp(&rank(b)).then_with(|| a.version.cmp(&b.version)));
+        SpecifierSet { specifiers }
+    }
+}
+
+impl fmt::Display for SpecifierSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let clauses: Vec<String> = self.specifiers.iter().map(|s| s.to_string()).collect();
+        write!(f, "{}", clauses.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_specifier() -> Result<()> {
+        let spec = Specifier::parse(">= 2.0")?;
+        assert_eq!(spec.op, Operator::GreaterEq);
+        assert_eq!(spec.to_string(), ">=2.0");
+
+        assert_eq!(Specifier::parse("==1.0b2")?.to_string(), "==1.0b2");
+        assert!(Specifier::parse("2.0").is_err());
+        assert!(Specifier::parse("=>2.0").is_err());
+        assert!(Specifier::parse(">=two").is_err());
+        assert!(Specifier::parse("~=1").is_err());
+        assert!(Specifier::parse(">=2.8.*").is_err());
+        assert!(Specifier::parse("==2.8rc1.*").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_compatible_release() -> Result<()> {
+        let matches = |spec: &str, version: &str| -> Result<bool> {
+            let version = Pep440Version::parse(version)?;
+            let range = Specifier::parse(spec)?.range();
+            Ok(!range.intersect(&IntervalSet::from(Interval::exact(version))).is_empty())
+        };
+
+        assert!(matches("~=1.4.2", "1.4.2")?);
+        assert!(matches("~=1.4.2", "1.4.99")?);
+        assert!(!matches("~=1.4.2", "1.5.0")?);
+        assert!(!matches("~=1.4.2", "1.5.dev0")?);
+        assert!(!matches("~=1.4.2", "1.4.1")?);
+
+        assert!(matches("~=2.2", "2.9")?);
+        assert!(!matches("~=2.2", "3.0")?);
+        assert!(matches("~=2.2.post3", "2.2.post3")?);
+        assert!(!matches("~=2.2.post3", "2.2")?);
+
+        // Every version of a later epoch sorts after every version of an earlier one
+        assert!(matches("~=1!2.1", "1!2.9")?);
+        assert!(!matches("~=1!2.1", "2.9")?);
+        assert!(!matches("~=1!2.1", "1!3.0")?);
+        assert!(!matches("<2021", "1!1.0")?);
+        assert!(matches(">=2021", "1!1.0")?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wildcard_equality() -> Result<()> {
+        let spec = Specifier::parse("==2.8.*")?;
+        assert!(spec.wildcard);
+        assert_eq!(spec.to_string(), "==2.8.*");
+
+        let range = spec.range();
+        let contains = |version: &str| -> Result<bool> {
+            let version = IntervalSet::from(Interval::exact(Pep440Version::parse(version)?));
+            Ok(!range.intersect(&version).is_empty())
+        };
+        assert!(contains("2.8")?);
+        assert!(contains("2.8.5.post1")?);
+        assert!(contains("2.8.0rc1")?);
+        assert!(!contains("2.9.0")?);
+        assert!(!contains("2.7.9")?);
+        assert!(!contains("1!2.8")?);
+
+        let excluded = Specifier::parse("!=2.8.*")?.range();
+        assert!(excluded.intersect(&range).is_empty());
+        assert_eq!(excluded.intervals.len(), 2);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_local_versions() -> Result<()> {
+        let matches = |spec: &str, version: &str| -> Result<bool> {
+            Ok(Specifier::parse(spec)?.range().contains(&Pep440Version::parse(version)?))
+        };
+        assert!(matches("==2.1.0", "2.1.0+cu118")?);
+        assert!(matches("==2.1", "2.1.0+cu118")?);
+        assert!(!matches("==2.1.0", "2.1.0.post1")?);
+        assert!(matches("==2.1.0+cu118", "2.1.0+cu118")?);
+        assert!(!matches("==2.1.0+cu118", "2.1.0+cu121")?);
+        assert!(!matches("==2.1.0+cu118", "2.1.0")?);
+        assert!(!matches("!=2.1.0", "2.1.0+cpu")?);
+        assert!(matches("<=2.1.0", "2.1.0+cpu")?);
+        assert!(!matches(">2.1.0", "2.1.0+cpu")?);
+        assert!(!matches(">2.1.0", "2.1.0.post1")?);
+        assert!(matches(">2.1.0.post0", "2.1.0.post1")?);
+        assert!(matches(">2.1.0", "2.1.0.1")?);
+        assert!(!matches("<2.1.0", "2.1.0+cpu")?);
+        // Only matches may name a local label
+        for spec in [">=1.0+local", ">1.0+local", "<=1.0+local", "<1.0+local", "~=1.0+local"] {
+            assert!(Specifier::parse(spec).is_err(), "{}", spec);
+        }
+        assert!(matches("!=2.1.0+cpu", "2.1.0+cu118")?);
+        assert!(matches("===2.1.0+cpu", "2.1.0+cpu")?);
+
+        let set = SpecifierSet::parse(">=2.0,<=2.1.0")?;
+        assert_eq!(crate::explain::versions(&set.range()), "[2.0, 2.1.0]");
+        Ok(())
+    }
+
+    #[test]
+    fn test_prerelease_exclusions() -> Result<()> {
+        let matches = |spec: &str, version: &str| -> Result<bool> {
+            Ok(Specifier::parse(spec)?.range().contains(&Pep440Version::parse(version)?))
+        };
+        assert!(matches("<2.0", "1.9.9")?);
+        assert!(matches("<2.0", "1.9.9.post1.dev2")?);
+        assert!(!matches("<2.0", "2.0rc1")?);
+        assert!(!matches("<2.0", "2.0.dev1")?);
+        assert!(!matches("<2", "2.0a1")?);
+        assert!(matches("<2.0rc1", "2.0b1")?);
+        assert!(matches("<2.0rc1", "2.0.dev3")?);
+        assert!(matches("<=2.0", "2.0rc1")?);
+        assert!(matches("<2.0.post1", "2.0")?);
+        assert!(matches("<2.0.post2", "2.0.post1+local")?);
+        assert!(!matches("<2.0.post2", "2.0rc1")?);
+        assert!(!matches("<2.0.post2", "2.0.post1.dev3")?);
+        assert!(matches(">=2.0.dev0", "2.0.dev0")?);
+        assert!(!matches(">=2.0", "2.0.dev0")?);
+        // `>2.0` leaves out the post-releases of 2.0 unless it names one
+        assert!(!matches(">2.0", "2.0.post1")?);
+        assert!(!matches(">2", "2.0.post3+local")?);
+        assert!(matches(">2.0", "2.0.1")?);
+        assert!(matches(">2.0", "2.0.0.0.1")?);
+        assert!(matches(">2.0.post1", "2.0.post2")?);
+        assert!(!matches(">2.0.post1", "2.0.post1+local")?);
+        // As pip has it, naming a pre-release or dev release does not let them in
+        assert!(!matches(">2.0rc1", "2.0.post1")?);
+        assert!(!matches(">2.0.dev0", "2.0.post1")?);
+        assert!(!matches(">2.0rc1", "2.0.post2.dev1")?);
+        assert!(matches(">2.0rc1", "2.0rc2")?);
+        assert!(matches(">2.0rc1", "2.0")?);
+        assert!(matches(">2.0.dev0", "2.0.1")?);
+        assert!(matches(">2.0rc1.post1", "2.0.post1")?);
+
+        let versions = |spec: &str| -> Result<String> { Ok(crate::explain::versions(&SpecifierSet::parse(spec)?.range())) };
+        assert_eq!(versions(">=1.0,<2.0")?, "[1.0, 2.0)");
+        assert_eq!(versions(">2.0")?, "(2.0, +inf)");
+        // The post-releases of 2.0 are written as ending at 2.0, not at a computed bound
+        let complement = SpecifierSet::parse(">2.0")?.range().complement();
+        assert_eq!(crate::explain::versions(&complement), "(-inf, 2.0]");
+        let posts = complement.intersect(&SpecifierSet::parse("<=2.0")?.range().complement());
+        assert_eq!(crate::explain::versions(&posts), "the post-releases of 2.0");
+        // but not before a start among them
+        let later = complement.intersect(&SpecifierSet::parse("<=2.0.post5")?.range().complement());
+        assert_eq!(crate::explain::versions(&later), "the post-releases of 2.0 after 2.0.post5");
+        assert_eq!(crate::conflict::witness(&later, false).map(|v| v.to_string()), Some("2.0.post6".to_string()));
+        Ok(())
+    }
+
+    #[test]
+    fn test_arbitrary_equality() -> Result<()> {
+        let spec = Specifier::parse("===1.0")?;
+        assert_eq!(spec.op, Operator::ArbitraryEqual);
+        // Compared as text, so unlike `==1.0` it leaves out `1.0+local`
+        assert_eq!(spec.range(), IntervalSet::from(Interval::exact(Pep440Version::parse("1.0")?)));
+        assert_eq!(spec.literal.as_deref(), Some("1.0"));
+        // and as text `1.0.0` is another version, though it sorts the same
+        let set = SpecifierSet::parse("===1.0")?;
+        assert!(set.excludes_literal(&SpecifierSet::parse("===1.0.0")?));
+        assert!(!set.excludes_literal(&SpecifierSet::parse("===1.0,>=0.9")?));
+        assert!(set.excludes_literal(&SpecifierSet::parse("!=1.0")?));
+        assert!(!set.matches_literal(&Pep440Version::parse("1.0.0")?));
+        assert!(SpecifierSet::parse("===1.0RC1")?.matches_literal(&Pep440Version::parse("1.0rc1")?));
+
+        let spec = Specifier::parse("=== 1.0-custom")?;
+        assert_eq!(spec.literal.as_deref(), Some("1.0-custom"));
+        assert_eq!(spec.to_string(), "===1.0-custom");
+        assert_eq!(spec.range(), IntervalSet::full());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_specifier_set() -> Result<()> {
+        let set = SpecifierSet::parse(">=2.0, <3.0,!=2.5.0")?;
+        assert_eq!(set.specifiers.len(), 3);
+        assert_eq!(set.to_string(), ">=2.0,<3.0,!=2.5.0");
+        assert_eq!(set.range().intervals.len(), 2);
+
+        assert!(SpecifierSet::parse("")?.is_empty());
+        assert!(SpecifierSet::parse(">=2.0,").is_err());
+        assert!(SpecifierSet::parse("==1.0,!=1.0")?.range().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_simplified() -> Result<()> {
+        let simplified = |input: &str| SpecifierSet::parse(input).map(|set| set.simplified().to_string());
+        assert_eq!(simplified(">=1.2,<2.0,!=1.5,>=1.3")?, ">=1.3,<2.0,!=1.5");
+        assert_eq!(simplified("!=2.5,<3,!=1.0,>= 2.0,!=3.1")?, ">=2.0,<3,!=2.5");
+        assert_eq!(simplified(">1.0,>=1.0,==1.4")?, "==1.4");
+        assert_eq!(simplified("<3,===1.0-custom")?, "===1.0-custom,<3");
+
+        // Pre-releases stay allowed by the clause opting in to them
+        assert_eq!(simplified(">=1.0,>=2.0b1,<3")?, ">=2.0b1,<3");
+        assert_eq!(simplified(">=2.0b1,>=2.1")?, ">=2.0b1,>=2.1");
+
+        Ok(())
+    }
+}
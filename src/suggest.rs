@@ -0,0 +1,232 @@
+use std::fmt;
+use std::ops::Bound;
+
+use crate::conflict::witness;
+use crate::package::PythonPackage;
+use crate::pep440::Pep440Version;
+use crate::pypi::Release;
+use crate::range::IntervalSet;
+use crate::specifier::{as_written, Operator, Specifier, SpecifierSet};
+
+/// A single-clause change to one requirement that resolves a conflict.
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    /// The requirement to change, as written.
+    pub requirement: String,
+    pub original: Specifier,
+    /// The clause to use instead, or `None` when it should simply be removed.
+    pub replacement: Option<Specifier>,
+    /// A version the relaxed requirements would all accept.
+    pub target: Option<Pep440Version>,
+    /// Whether `target` is a published release rather than a computed bound.
+    pub released: bool,
+}
+
+/// Proposes, for each requirement, the smallest change to one of its clauses
+/// that lets it agree with all the other requirements. With release data the
+/// target is the latest release the relaxed requirements accept; otherwise it
+/// is the version closest to the clause being relaxed.
+pub fn suggest(packages: &[&PythonPackage], releases: Option<&[Release]>) -> Vec<Suggestion> {
+    let mut suggestions = Vec::new();
+    for (index, package) in packages.iter().enumerate() {
+        let others = packages
+            .iter()
+            .enumerate()
+            .filter(|(other, _)| *other != index)
+            .fold(IntervalSet::full(), |acc, (_, p)| acc.intersect(&p.specifiers.range()));
+
+        for (clause, original) in package.specifiers.specifiers.iter().enumerate() {
+            let rest = SpecifierSet {
+                specifiers: package
+                    .specifiers
+                    .specifiers
+                    .iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != clause)
+                    .map(|(_, s)| s.clone())
+                    .collect(),
+            };
+            let allowed = rest.range().intersect(&others);
+            let (target, released) = match releases {
+                Some(releases) => {
+                    let latest = releases
+                        .iter()
+                        .filter(|r| !r.yanked && allowed.contains(&r.version))
+                        .map(|r| &r.version)
+                        .max();
+                    match latest {
+                        Some(latest) => (Some(latest.clone()), true),
+                        None => continue,
+                    }
+                }
+                None if allowed.is_empty() => continue,
+                None => (closest(original, &allowed), false),
+            };
+
+            // Fall back to dropping the clause when no relaxed form admits the target
+            let replacement = target.as_ref().and_then(|t| relaxed(original, t)).filter(|r| {
+                let range = r.range().intersect(&allowed);
+                target.as_ref().map_or(!range.is_empty(), |t| range.contains(t))
+            });
+            suggestions.push(Suggestion {
+                requirement: package.to_string(),
+                original: original.clone(),
+                replacement,
+                target,
+                released,
+            });
+        }
+    }
+    suggestions
+}
+
+/// The version nearest to the clause being relaxed: the lowest allowed version
+/// when raising an upper bound, the highest one when lowering a lower bound.
+/// Without release data there is no sensible new pin for `==`.
+fn closest(original: &Specifier, allowed: &IntervalSet<Pep440Version>) -> Option<Pep440Version> {
+    match original.op {
+        Operator::Equal | Operator::ArbitraryEqual if !original.wildcard => None,
+        Operator::Greater | Operator::GreaterEq => match as_written(&allowed.hull()?).upper {
+            Bound::Included(v) => Some(v),
+            _ => None,
+        },
+        // A final release when there is one, as pip prefers
+        _ => witness(allowed, false).or_else(|| witness(allowed, true)),
+    }
+}
+
+/// The same kind of clause, moved just far enough to admit `target`.
+fn relaxed(original: &Specifier, target: &Pep440Version) -> Option<Specifier> {
+    let version = match original.op {
+        Operator::NotEqual | Operator::ArbitraryEqual => return None,
+        Operator::Less | Operator::LessEq => {
+            // Keep the granularity of the original bound, e.g. `<2.0` becomes `<3.0`
+            let segments = original.version.release.len();
+            let significant = original
+                .version
+                .release
+                .iter()
+                .rposition(|&segment| segment != 0)
+                .unwrap_or(0);
+            let mut release = padded(target, segments.max(significant + 1));
+            release[significant] += 1;
+            release[significant + 1..].iter_mut().for_each(|segment| *segment = 0);
+            release_version(target.epoch, release)
+        }
+        Operator::Compatible => {
+            release_version(target.epoch, padded(target, original.version.release.len()))
+        }
+        Operator::Equal if original.wildcard => {
+            release_version(target.epoch, padded(target, original.version.release.len()))
+        }
+        Operator::Equal | Operator::Greater | Operator::GreaterEq => target.clone(),
+    };
+    let op = match original.op {
+        Operator::Greater => Operator::GreaterEq,
+        op => op,
+    };
+    Some(Specifier {
+        op,
+        version,
+        wildcard: original.wildcard,
+        literal: None,
+    })
+}
+
+fn padded(version: &Pep440Version, len: usize) -> Vec<u64> {
+    let mut release: Vec<u64> = version.release.iter().copied().take(len).collect();
+    release.resize(len, 0);
+    release
+}
+
+fn release_version(epoch: u64, release: Vec<u64>) -> Pep440Version {
+    Pep440Version {
+        epoch,
+        release,
+        pre: None,
+        post: None,
+        dev: None,
+        local: Vec::new(),
+    }
+}
+
+impl fmt::Display for Suggestion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.replacement {
+            Some(replacement) => write!(
+                f,
+                "change `{}` to `{}` in `{}`",
+                self.original, replacement, self.requirement
+            )?,
+            None => write!(f, "remove `{}` from `{}`", self.original, self.requirement)?,
+        }
+        match (&self.target, self.released) {
+            (Some(target), true) => write!(
+                f,
+                " to allow {}, the latest release satisfying the other requirements",
+                target
+            ),
+            (Some(target), false) => write!(f, " to allow {}", target),
+            (None, _) => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    fn suggestions(a: &str, b: &str, releases: Option<&[Release]>) -> Result<Vec<String>> {
+        let a = PythonPackage::parse(a)?;
+        let b = PythonPackage::parse(b)?;
+        Ok(suggest(&[&a, &b], releases).iter().map(|s| s.to_string()).collect())
+    }
+
+    #[test]
+    fn test_suggest_offline() -> Result<()> {
+        assert_eq!(
+            suggestions("requests<2.0", "requests>=2.31", None)?,
+            vec![
+                "change `<2.0` to `<3.0` in `requests <2.0` to allow 2.31",
+                "remove `>=2.31` from `requests >=2.31`",
+            ]
+        );
+        assert_eq!(
+            suggestions("six==1.16", "six!=1.16", None)?,
+            vec![
+                "remove `==1.16` from `six ==1.16`",
+                "remove `!=1.16` from `six !=1.16` to allow 1.16",
+            ]
+        );
+        assert_eq!(
+            suggestions("attrs>=23", "attrs<=22.2", None)?[0],
+            "change `>=23` to `>=22.2` in `attrs >=23` to allow 22.2"
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_suggest_with_releases() -> Result<()> {
+        let releases: Vec<Release> = ["1.9", "2.31.0", "2.32.3"]
+            .iter()
+            .map(|v| Release {
+                version: Pep440Version::parse(v).unwrap(),
+                yanked: v == &"2.32.3",
+                files: Vec::new(),
+                uploaded: None,
+            })
+            .collect();
+        let found = suggestions("requests<2.0", "requests>=2.31", Some(&releases))?;
+        assert_eq!(
+            found,
+            vec![
+                "change `<2.0` to `<3.0` in `requests <2.0` to allow 2.31.0, the latest release satisfying the other requirements",
+                "change `>=2.31` to `>=1.9` in `requests >=2.31` to allow 1.9, the latest release satisfying the other requirements",
+            ]
+        );
+
+        Ok(())
+    }
+}
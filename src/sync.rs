@@ -0,0 +1,130 @@
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::diff::{allowed, is_subset};
+use crate::pep440::Pep440Version;
+use crate::range::IntervalSet;
+use crate::requirements::RequirementLine;
+
+/// How the dev requirements on a package compare to the prod ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Alignment {
+    /// Both allow the same versions.
+    Identical,
+    /// Dev only allows versions prod allows, but not all of them.
+    Narrower,
+    /// Dev allows versions prod rejects, so tests may pass on a version prod
+    /// never installs.
+    Drifted,
+    /// No version satisfies both.
+    Conflicting,
+}
+
+impl Alignment {
+    /// Whether the package is flagged: drifted or conflicting, and with
+    /// `strict` anything but identical.
+    pub fn is_flagged(self, strict: bool) -> bool {
+        match self {
+            Alignment::Identical => false,
+            Alignment::Narrower => strict,
+            Alignment::Drifted | Alignment::Conflicting => true,
+        }
+    }
+}
+
+impl fmt::Display for Alignment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kind = match self {
+            Alignment::Identical => "identical",
+            Alignment::Narrower => "narrower",
+            Alignment::Drifted => "drifted",
+            Alignment::Conflicting => "conflicting",
+        };
+        write!(f, "{}", kind)
+    }
+}
+
+/// A package required by both the prod and the dev requirements.
+#[derive(Debug, Clone)]
+pub struct SharedPackage {
+    /// PEP 503 normalized package name.
+    pub name: String,
+    pub prod: Vec<RequirementLine>,
+    pub dev: Vec<RequirementLine>,
+    pub alignment: Alignment,
+}
+
+impl SharedPackage {
+    /// The versions the prod requirements allow together, ignoring markers.
+    pub fn prod_allowed(&self) -> IntervalSet<Pep440Version> {
+        allowed(&self.prod)
+    }
+
+    /// The versions the dev requirements allow together, ignoring markers.
+    pub fn dev_allowed(&self) -> IntervalSet<Pep440Version> {
+        allowed(&self.dev)
+    }
+
+    /// The versions dev allows and prod rejects.
+    pub fn outside_prod(&self) -> IntervalSet<Pep440Version> {
+        self.dev_allowed().intersect(&self.prod_allowed().complement())
+    }
+}
+
+/// Compares the requirements on every package found in both the prod and
+/// the dev requirements, by the versions each side allows. Packages only one
+/// side requires, such as test tools, are left out. A dev file including the
+/// prod one with `-r` has the prod requirements on its side too.
+pub fn check(prod: &[RequirementLine], dev: &[RequirementLine]) -> Vec<SharedPackage> {
+    let group = |lines: &[RequirementLine]| {
+        let mut by_name: BTreeMap<String, Vec<RequirementLine>> = BTreeMap::new();
+        for line in lines {
+            by_name.entry(line.package.canonical_name()).or_default().push(line.clone());
+        }
+        by_name
+    };
+    let mut prod = group(prod);
+    group(dev)
+        .into_iter()
+        .filter_map(|(name, dev)| {
+            let prod = prod.remove(&name)?;
+            let (prod_allowed, dev_allowed) = (allowed(&prod), allowed(&dev));
+            let alignment = if prod_allowed.intersect(&dev_allowed).is_empty() {
+                Alignment::Conflicting
+            } else {
+                match (is_subset(&dev_allowed, &prod_allowed), is_subset(&prod_allowed, &dev_allowed)) {
+                    (true, true) => Alignment::Identical,
+                    (true, false) => Alignment::Narrower,
+                    (false, _) => Alignment::Drifted,
+                }
+            };
+            Some(SharedPackage { name, prod, dev, alignment })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::requirements;
+    use anyhow::Result;
+    use std::path::Path;
+
+    #[test]
+    fn test_check() -> Result<()> {
+        let prod = requirements::parse_str("django>=3.2,<4\nrequests==2.28.0\nsix\nattrs>=22\ngunicorn\n", Path::new("requirements.txt"))?;
+        let dev = requirements::parse_str(
+            "Django>=3.2\nrequests==2.31.0\nsix\nattrs>=23\npytest\n",
+            Path::new("requirements-dev.txt"),
+        )?;
+        let shared = check(&prod, &dev);
+        let found: Vec<(&str, Alignment)> = shared.iter().map(|p| (p.name.as_str(), p.alignment)).collect();
+        assert_eq!(
+            found,
+            [("attrs", Alignment::Narrower), ("django", Alignment::Drifted), ("requests", Alignment::Conflicting), ("six", Alignment::Identical)]
+        );
+        assert_eq!(crate::explain::versions(&shared[1].outside_prod()), "[4.dev0, +inf)");
+        assert!(Alignment::Narrower.is_flagged(true) && !Alignment::Narrower.is_flagged(false));
+        Ok(())
+    }
+}
@@ -0,0 +1,185 @@
+use anyhow::{bail, Result};
+use std::fmt;
+
+use crate::error::PyhelperError;
+use crate::filename::{DistributionName, Tag};
+use crate::pep440::Pep440Version;
+
+/// A CPython version and platform to install on, like pip's `--python-version`
+/// and `--platform`, e.g. 3.11 on `manylinux2014_x86_64`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    /// The major and minor version of the interpreter.
+    pub python: (u64, u64),
+    /// A platform tag such as `manylinux_2_28_aarch64`, `macosx_14_0_arm64` or `win_amd64`.
+    pub platform: String,
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Python {}.{} on {}", self.python.0, self.python.1, self.platform)
+    }
+}
+
+impl Target {
+    pub fn new(python: &Pep440Version, platform: &str) -> Result<Self> {
+        let (major, minor) = match python.release.as_slice() {
+            [major, minor, ..] => (*major, *minor),
+            _ => {
+                let message = format!("The target Python version needs a minor version, e.g. 3.11: {}", python);
+                bail!(PyhelperError::Invalid(message))
+            }
+        };
+        if platform.is_empty() || platform.contains(['-', ' ']) {
+            bail!(PyhelperError::Invalid(format!("Invalid platform tag: {}", platform)));
+        }
+        Ok(Target {
+            python: (major, minor),
+            platform: platform.to_string(),
+        })
+    }
+
+    /// Whether pip would install a file on the target: a source distribution,
+    /// or a wheel with a supported tag.
+    pub fn installable(&self, file: &str) -> bool {
+        match DistributionName::parse(file) {
+            Some(DistributionName::Wheel(wheel)) => wheel.tags().iter().any(|tag| self.supports(tag)),
+            Some(DistributionName::Sdist(_)) => true,
+            None => false,
+        }
+    }
+
+    /// Whether a wheel tag installs on the target, following the tags
+    /// `packaging` lists for CPython.
+    pub fn supports(&self, tag: &Tag) -> bool {
+        self.supports_interpreter(&tag.python, &tag.abi) && self.supports_platform(&tag.platform)
+    }
+
+    fn supports_interpreter(&self, python: &str, abi: &str) -> bool {
+        let (major, minor) = self.python;
+        let cpython = format!("cp{}{}", major, minor);
+        match abi {
+            // Pure Python, or extensions without a Python ABI
+            "none" => {
+                python == cpython
+                    || tag_version(python, "py").is_some_and(|(m, n)| m == major && n.is_none_or(|n| n <= minor))
+            }
+            // The stable ABI of one CPython 3 release and every later one
+            "abi3" => tag_version(python, "cp").is_some_and(|(m, n)| m == 3 && major == 3 && n.is_some_and(|n| n <= minor)),
+            // Before 3.8 the ABI of release builds carried an `m`
+            abi => python == cpython && (abi == cpython || (minor <= 7 && abi.strip_suffix('m') == Some(&cpython))),
+        }
+    }
+
+    fn supports_platform(&self, platform: &str) -> bool {
+        if platform == "any" || platform == self.platform {
+            return true;
+        }
+        match (Platform::parse(&self.platform), Platform::parse(platform)) {
+            (Some(target), Some(wheel)) => target.covers(&wheel),
+            _ => false,
+        }
+    }
+}
+
+/// The major and optional minor version of a `py3` or `cp311` tag.
+fn tag_version(tag: &str, prefix: &str) -> Option<(u64, Option<u64>)> {
+    let digits = tag.strip_prefix(prefix)?;
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let (major, minor) = digits.split_at(1);
+    Some((major.parse().ok()?, minor.parse().ok()))
+}
+
+/// The platform tags with a version below which they stay compatible.
+#[derive(Debug, PartialEq, Eq)]
+enum Platform<'a> {
+    /// `manylinux_2_17_x86_64`, with the glibc version; the legacy
+    /// `manylinux1`, `manylinux2010` and `manylinux2014` are aliases.
+    Manylinux((u64, u64), &'a str),
+    /// `musllinux_1_2_x86_64`, with the musl version.
+    Musllinux((u64, u64), &'a str),
+    /// `macosx_11_0_arm64`, with the oldest macOS version supported.
+    Macos((u64, u64), &'a str),
+    /// `linux_x86_64`, a build for the machine it was made on.
+    Linux(&'a str),
+}
+
+impl<'a> Platform<'a> {
+    fn parse(tag: &'a str) -> Option<Self> {
+        let versioned = |rest: &'a str| -> Option<((u64, u64), &'a str)> {
+            let mut parts = rest.splitn(3, '_');
+            let major = parts.next()?.parse().ok()?;
+            let minor = parts.next()?.parse().ok()?;
+            Some(((major, minor), parts.next()?))
+        };
+        for (alias, glibc) in [("manylinux1_", (2, 5)), ("manylinux2010_", (2, 12)), ("manylinux2014_", (2, 17))] {
+            if let Some(arch) = tag.strip_prefix(alias) {
+                return Some(Platform::Manylinux(glibc, arch));
+            }
+        }
+        if let Some(rest) = tag.strip_prefix("manylinux_") {
+            return versioned(rest).map(|(version, arch)| Platform::Manylinux(version, arch));
+        }
+        if let Some(rest) = tag.strip_prefix("musllinux_") {
+            return versioned(rest).map(|(version, arch)| Platform::Musllinux(version, arch));
+        }
+        if let Some(rest) = tag.strip_prefix("macosx_") {
+            return versioned(rest).map(|(version, arch)| Platform::Macos(version, arch));
+        }
+        tag.strip_prefix("linux_").map(Platform::Linux)
+    }
+
+    /// Whether wheels for `wheel` run on this platform.
+    fn covers(&self, wheel: &Platform) -> bool {
+        match (self, wheel) {
+            (Platform::Manylinux(glibc, arch), Platform::Manylinux(needed, wheel_arch))
+            | (Platform::Musllinux(glibc, arch), Platform::Musllinux(needed, wheel_arch)) => arch == wheel_arch && needed <= glibc,
+            (Platform::Manylinux(_, arch) | Platform::Musllinux(_, arch), Platform::Linux(wheel_arch)) => arch == wheel_arch,
+            (Platform::Macos(version, arch), Platform::Macos(needed, binary)) => needed <= version && macos_binary(arch, binary),
+            _ => false,
+        }
+    }
+}
+
+/// Whether a macOS wheel built as `binary` runs on `arch`; universal builds
+/// carry several architectures.
+fn macos_binary(arch: &str, binary: &str) -> bool {
+    match (arch, binary) {
+        _ if arch == binary => true,
+        ("arm64" | "x86_64", "universal2") => true,
+        ("x86_64", "intel" | "fat64" | "fat32" | "universal") => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_installable() -> Result<()> {
+        let linux = Target::new(&Pep440Version::parse("3.11")?, "manylinux2014_x86_64")?;
+        assert!(linux.installable("numpy-1.26.4-cp311-cp311-manylinux_2_17_x86_64.manylinux2014_x86_64.whl"));
+        assert!(linux.installable("psycopg2_binary-2.9.9-cp37-abi3-manylinux_2_5_x86_64.whl"));
+        assert!(linux.installable("six-1.16.0-py2.py3-none-any.whl") && linux.installable("six-1.16.0.tar.gz"));
+        assert!(!linux.installable("numpy-1.26.4-cp312-cp312-manylinux_2_17_x86_64.whl"));
+        assert!(!linux.installable("numpy-1.26.4-cp311-cp311-manylinux_2_28_x86_64.whl"));
+        assert!(!linux.installable("numpy-1.26.4-cp311-cp311-manylinux2014_aarch64.whl"));
+        assert!(!linux.installable("numpy-1.26.4-cp311-cp311-musllinux_1_1_x86_64.whl"));
+        assert!(!linux.installable("tool-1.0-py3.11.egg"));
+
+        let mac = Target::new(&Pep440Version::parse("3.12")?, "macosx_14_0_arm64")?;
+        assert!(mac.installable("orjson-3.9.10-cp312-cp312-macosx_10_15_x86_64.macosx_11_0_arm64.macosx_10_15_universal2.whl"));
+        assert!(mac.installable("lxml-5.1.0-cp312-cp312-macosx_10_9_universal2.whl"));
+        assert!(!mac.installable("lxml-5.1.0-cp312-cp312-macosx_10_9_x86_64.whl"));
+        assert!(!mac.installable("lxml-5.1.0-cp312-cp312-macosx_15_0_arm64.whl"));
+
+        let old = Target::new(&Pep440Version::parse("3.7")?, "win_amd64")?;
+        assert!(old.installable("pyyaml-6.0-cp37-cp37m-win_amd64.whl"));
+        assert!(!old.installable("pyyaml-6.0-cp37-cp37m-win32.whl"));
+        assert!(Target::new(&Pep440Version::parse("3")?, "win_amd64").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,133 @@
+use std::fmt;
+
+use crate::pep440::Pep440Version;
+use crate::pypi::Release;
+
+/// How far a pinned release is behind the latest one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Staleness {
+    pub pinned: Pep440Version,
+    /// The newest final release that is not yanked.
+    pub latest: Pep440Version,
+    /// The final releases after the pin that are not yanked.
+    pub releases_behind: usize,
+    /// Days between the uploads of the pinned and the latest release, when
+    /// the index dates both.
+    pub days_behind: Option<i64>,
+}
+
+impl Staleness {
+    pub fn is_latest(&self) -> bool {
+        self.releases_behind == 0
+    }
+}
+
+impl fmt::Display for Staleness {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_latest() {
+            return write!(f, "the latest release");
+        }
+        let releases = match self.releases_behind {
+            1 => "1 release".to_string(),
+            n => format!("{} releases", n),
+        };
+        match self.days_behind.filter(|days| *days > 0) {
+            Some(days) => write!(f, "{} and {} behind {}", age(days), releases, self.latest),
+            None => write!(f, "{} behind {}", releases, self.latest),
+        }
+    }
+}
+
+/// How far `pinned` is behind the newest final release of `releases`, sorted
+/// oldest first. `None` when nothing newer than a pre-release was published.
+pub fn staleness(releases: &[Release], pinned: &Pep440Version) -> Option<Staleness> {
+    let finals: Vec<&Release> = releases.iter().filter(|r| !r.yanked && !r.version.is_prerelease()).collect();
+    let latest = finals.last()?;
+    let uploaded = |version: &Pep440Version| releases.iter().find(|r| r.version == *version)?.uploaded.as_deref().and_then(days);
+    let days_behind = match (uploaded(pinned), uploaded(&latest.version)) {
+        (Some(pinned), Some(latest)) => Some(latest - pinned),
+        _ => None,
+    };
+    Some(Staleness {
+        pinned: pinned.clone(),
+        latest: latest.version.clone(),
+        releases_behind: finals.iter().filter(|r| r.version > *pinned).count(),
+        days_behind,
+    })
+}
+
+/// Days since the Unix epoch of the date part of an ISO 8601 time, such as
+/// `2021-06-22` or `2021-06-22T13:48:22Z`.
+pub fn days(time: &str) -> Option<i64> {
+    let date = time.get(..10)?;
+    let mut parts = date.splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: i64 = parts.next()?.parse().ok()?;
+    let day: i64 = parts.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+    // The inverse of `output::sbom::timestamp`, after Howard Hinnant's algorithm
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year.rem_euclid(400);
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    Some(era * 146_097 + day_of_era - 719_468)
+}
+
+/// A span of days in the largest whole unit, e.g. `3 years` or `5 months`.
+pub fn age(days: i64) -> String {
+    let (count, unit) = match days {
+        d if d >= 365 => (d / 365, "year"),
+        d if d >= 30 => (d / 30, "month"),
+        d => (d.max(0), "day"),
+    };
+    match count {
+        1 => format!("1 {}", unit),
+        n => format!("{} {}s", n, unit),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    #[test]
+    fn test_days() {
+        assert_eq!(days("1970-01-01"), Some(0));
+        assert_eq!(days("2000-03-01T00:00:00Z"), Some(11_017));
+        assert_eq!(days("2024-02-29T13:48:22.175000Z").map(|d| d - days("2023-02-28").unwrap()), Some(366));
+        assert_eq!(days("2024-13-01"), None);
+        assert_eq!(days("yesterday"), None);
+        assert_eq!((age(1200), age(45), age(1)), ("3 years".to_string(), "1 month".to_string(), "1 day".to_string()));
+    }
+
+    #[test]
+    fn test_staleness() -> Result<()> {
+        let release = |version: &str, uploaded: Option<&str>, yanked: bool| -> Result<Release> {
+            Ok(Release {
+                version: Pep440Version::parse(version)?,
+                yanked,
+                files: Vec::new(),
+                uploaded: uploaded.map(str::to_string),
+            })
+        };
+        let releases = vec![
+            release("1.21.0", Some("2021-06-22T13:48:22"), false)?,
+            release("1.22.0", Some("2021-12-31T10:00:00"), false)?,
+            release("1.23.0", None, true)?,
+            release("2.0.0rc1", Some("2024-04-01T00:00:00"), false)?,
+            release("2.0.0", Some("2024-06-16T18:00:00"), false)?,
+        ];
+        let stale = staleness(&releases, &Pep440Version::parse("1.21.0")?).unwrap();
+        assert_eq!((stale.releases_behind, stale.latest.to_string()), (2, "2.0.0".to_string()));
+        assert_eq!(stale.to_string(), "2 years and 2 releases behind 2.0.0");
+
+        let latest = staleness(&releases, &Pep440Version::parse("2.0.0")?).unwrap();
+        assert!(latest.is_latest());
+        assert_eq!(staleness(&releases[3..4], &Pep440Version::parse("2.0.0rc1")?), None);
+        Ok(())
+    }
+}
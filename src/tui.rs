@@ -0,0 +1,398 @@
+use anyhow::Result;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph};
+use ratatui::{DefaultTerminal, Frame};
+use std::collections::BTreeMap;
+
+use crate::graph::{DependencyGraph, Edge, Node};
+use crate::package::{canonicalize_name, PythonPackage};
+use crate::pep440::Pep440Version;
+use crate::pypi::PypiClient;
+use crate::requirements::RequirementLine;
+
+/// A dependency graph to browse, with the requirements it was built from and
+/// the versions changed to try out a scenario.
+pub struct Explorer {
+    original: DependencyGraph,
+    graph: DependencyGraph,
+    declared: Vec<RequirementLine>,
+    /// Normalized names of the packages, in display order.
+    names: Vec<String>,
+    selected: usize,
+    /// The versions tried out instead of the original ones, by normalized name.
+    pub bumped: BTreeMap<String, Pep440Version>,
+}
+
+/// A requirement the version of a package does not satisfy.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    /// The dependent package and its version, or the file and line declaring it.
+    pub source: String,
+    pub requirement: PythonPackage,
+}
+
+impl Explorer {
+    pub fn new(graph: DependencyGraph, declared: Vec<RequirementLine>) -> Self {
+        Explorer {
+            names: graph.nodes.keys().cloned().collect(),
+            original: graph.clone(),
+            graph,
+            declared,
+            selected: 0,
+            bumped: BTreeMap::new(),
+        }
+    }
+
+    /// The graph with the tried out versions.
+    pub fn graph(&self) -> &DependencyGraph {
+        &self.graph
+    }
+
+    /// The normalized name of the selected package.
+    pub fn selected(&self) -> Option<&str> {
+        self.names.get(self.selected).map(String::as_str)
+    }
+
+    pub fn select_next(&mut self) {
+        if self.selected + 1 < self.names.len() {
+            self.selected += 1;
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Tries out another version of a package. Its dependencies are replaced
+    /// by `dependencies` when they are known, and kept otherwise; packages new
+    /// to the graph are added without a version.
+    pub fn bump(&mut self, name: &str, version: Pep440Version, dependencies: Option<Vec<PythonPackage>>) {
+        let name = canonicalize_name(name);
+        if let Some(node) = self.graph.nodes.get_mut(&name) {
+            node.version = Some(version.clone());
+        }
+        if let Some(dependencies) = dependencies {
+            self.graph.edges.retain(|edge| edge.from != name);
+            for requirement in dependencies {
+                let to = requirement.canonical_name();
+                self.graph.nodes.entry(to.clone()).or_insert_with(|| Node {
+                    name: requirement.name.clone(),
+                    version: None,
+                });
+                self.graph.edges.push(Edge {
+                    from: name.clone(),
+                    to,
+                    requirement,
+                });
+            }
+        }
+        self.bumped.insert(name, version);
+        self.refresh_names();
+    }
+
+    /// Goes back to the versions the graph was built with.
+    pub fn reset(&mut self) {
+        self.graph = self.original.clone();
+        self.bumped.clear();
+        self.refresh_names();
+    }
+
+    /// Lists the packages again, keeping the selection on the same one.
+    fn refresh_names(&mut self) {
+        let selected = self.selected().map(str::to_string);
+        self.names = self.graph.nodes.keys().cloned().collect();
+        self.selected = selected
+            .and_then(|name| self.names.iter().position(|n| *n == name))
+            .unwrap_or_default();
+    }
+
+    /// The requirements on a package, from its dependents and the declared
+    /// requirements, that its version does not satisfy.
+    pub fn problems(&self, name: &str) -> Vec<Problem> {
+        let version = self.graph.node(name).and_then(|node| node.version.as_ref());
+        let satisfied = |requirement: &PythonPackage| version.is_some_and(|v| requirement.specifiers.range().contains(v));
+        let mut problems: Vec<Problem> = self
+            .graph
+            .dependents(name)
+            .into_iter()
+            .filter(|edge| !satisfied(&edge.requirement))
+            .map(|edge| Problem {
+                source: self.label(&edge.from),
+                requirement: edge.requirement.clone(),
+            })
+            .collect();
+        let name = canonicalize_name(name);
+        problems.extend(
+            self.declared
+                .iter()
+                .filter(|line| line.package.canonical_name() == name && !satisfied(&line.package))
+                .map(|line| Problem {
+                    source: line.location(),
+                    requirement: line.package.clone(),
+                }),
+        );
+        problems
+    }
+
+    /// How many requirements are unsatisfied across the graph.
+    pub fn problem_count(&self) -> usize {
+        self.names.iter().map(|name| self.problems(name).len()).sum()
+    }
+
+    /// A package as `name version`, or `name` without a version.
+    fn label(&self, name: &str) -> String {
+        match self.graph.node(name) {
+            Some(Node { name, version: Some(version) }) => format!("{} {}", name, version),
+            Some(node) => node.name.clone(),
+            None => name.to_string(),
+        }
+    }
+}
+
+enum Mode {
+    Browse,
+    /// Typing the version to try for the selected package.
+    Version(String),
+}
+
+/// Runs the explorer in the terminal until it is quit. With a client, trying
+/// out a version fetches that release's dependencies from the index.
+pub fn run(mut explorer: Explorer, client: Option<&PypiClient>) -> Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = event_loop(&mut terminal, &mut explorer, client);
+    ratatui::restore();
+    result
+}
+
+fn event_loop(terminal: &mut DefaultTerminal, explorer: &mut Explorer, client: Option<&PypiClient>) -> Result<()> {
+    let mut mode = Mode::Browse;
+    let mut status = String::new();
+    loop {
+        terminal.draw(|frame| draw(frame, explorer, &mode, &status))?;
+        let Event::Key(key) = event::read()? else {
+            continue;
+        };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match &mut mode {
+            Mode::Browse => match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Down | KeyCode::Char('j') => explorer.select_next(),
+                KeyCode::Up | KeyCode::Char('k') => explorer.select_previous(),
+                KeyCode::Char('b') | KeyCode::Enter => mode = Mode::Version(String::new()),
+                KeyCode::Char('r') => {
+                    explorer.reset();
+                    status = "Back to the original versions".to_string();
+                }
+                _ => {}
+            },
+            Mode::Version(text) => match key.code {
+                KeyCode::Esc => mode = Mode::Browse,
+                KeyCode::Backspace => {
+                    text.pop();
+                }
+                KeyCode::Char(c) => text.push(c),
+                KeyCode::Enter => {
+                    status = try_version(explorer, client, text.trim());
+                    mode = Mode::Browse;
+                }
+                _ => {}
+            },
+        }
+    }
+}
+
+/// Tries out a version for the selected package and describes the outcome.
+fn try_version(explorer: &mut Explorer, client: Option<&PypiClient>, text: &str) -> String {
+    let Some(name) = explorer.selected().map(str::to_string) else {
+        return String::new();
+    };
+    let version = match Pep440Version::parse(text) {
+        Ok(version) => version,
+        Err(e) => return format!("{:#}", e),
+    };
+    let spelled = explorer.graph().node(&name).map_or(name.clone(), |node| node.name.clone());
+    let mut note = String::new();
+    let dependencies = match client.map(|client| client.requires_dist_all(&[(spelled.as_str(), &version)])) {
+        Some(Ok(mut fetched)) => {
+            let dependencies = fetched.pop().unwrap_or_default();
+            Some(dependencies.into_iter().filter(|d| d.may_apply(&[])).collect())
+        }
+        Some(Err(e)) => {
+            note = format!(" (kept the current dependencies: {:#})", e);
+            None
+        }
+        None => {
+            note = " (dependencies not fetched offline)".to_string();
+            None
+        }
+    };
+    explorer.bump(&name, version.clone(), dependencies);
+    format!(
+        "What if {} {}: {} unsatisfied requirement(s){}",
+        spelled,
+        version,
+        explorer.problem_count(),
+        note
+    )
+}
+
+fn draw(frame: &mut Frame, explorer: &Explorer, mode: &Mode, status: &str) {
+    let [main, footer] = Layout::vertical([Constraint::Min(1), Constraint::Length(2)]).areas(frame.area());
+    let [left, right] = Layout::horizontal([Constraint::Percentage(35), Constraint::Percentage(65)]).areas(main);
+
+    let graph = explorer.graph();
+    let items: Vec<ListItem> = explorer
+        .names
+        .iter()
+        .map(|name| {
+            let mut style = Style::default();
+            if !explorer.problems(name).is_empty() {
+                style = style.fg(Color::Red);
+            }
+            let mut label = explorer.label(name);
+            if explorer.bumped.contains_key(name) {
+                label.push_str(" *");
+                style = style.add_modifier(Modifier::BOLD);
+            }
+            ListItem::new(label).style(style)
+        })
+        .collect();
+    let list = List::new(items)
+        .block(Block::bordered().title(format!(" Packages ({}) ", explorer.names.len())))
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    let mut state = ListState::default().with_selected(Some(explorer.selected));
+    frame.render_stateful_widget(list, left, &mut state);
+
+    let mut lines = Vec::new();
+    let mut title = String::new();
+    if let Some(name) = explorer.selected() {
+        title = format!(" {} ", explorer.label(name));
+        if let Some(original) = explorer.bumped.get(name).and(explorer.original.node(name)) {
+            let was = original.version.as_ref().map_or("no version".to_string(), |v| v.to_string());
+            lines.push(Line::from(format!("Trying another version (was {})", was).yellow()));
+            lines.push(Line::default());
+        }
+        let problems = explorer.problems(name);
+        let mark = |ok: bool| if ok { Span::from("ok").green() } else { Span::from("CONFLICT").red().bold() };
+
+        lines.push(Line::from("Required by".bold()));
+        let declared = explorer.declared.iter().filter(|line| line.package.canonical_name() == name);
+        let mut required: Vec<(String, &PythonPackage)> = declared
+            .map(|line| (line.location(), &line.package))
+            .collect();
+        required.extend(graph.dependents(name).into_iter().map(|edge| (explorer.label(&edge.from), &edge.requirement)));
+        if required.is_empty() {
+            lines.push(Line::from("  nothing".dim()));
+        }
+        for (source, requirement) in required {
+            let ok = !problems.iter().any(|p| p.source == source && p.requirement.to_string() == requirement.to_string());
+            lines.push(Line::from(vec![Span::from(format!("  {}  {}  ", source, requirement)), mark(ok)]));
+        }
+
+        lines.push(Line::default());
+        lines.push(Line::from("Depends on".bold()));
+        let dependencies = graph.dependencies(name);
+        if dependencies.is_empty() {
+            lines.push(Line::from("  nothing".dim()));
+        }
+        for edge in dependencies {
+            let target = graph.nodes.get(&edge.to).and_then(|node| node.version.as_ref());
+            let target = target.map_or("no version".to_string(), |v| v.to_string());
+            lines.push(Line::from(vec![
+                Span::from(format!("  {}  -> {}  ", edge.requirement, target)),
+                mark(graph.is_satisfied(edge)),
+            ]));
+        }
+    }
+    frame.render_widget(Paragraph::new(lines).block(Block::bordered().title(title)), right);
+
+    let help = match mode {
+        Mode::Browse => Line::from("up/down select   b try a version   r reset   q quit"),
+        Mode::Version(text) => Line::from(format!("Version to try (Enter to apply, Esc to cancel): {}_", text)),
+    };
+    let summary = match explorer.problem_count() {
+        0 => Line::from("All requirements are satisfied".green()),
+        n => Line::from(format!("{} unsatisfied requirement(s)", n).red()),
+    };
+    let status = if status.is_empty() { summary } else { Line::from(status.to_string()) };
+    frame.render_widget(Paragraph::new(vec![status, help]), footer);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+    use ratatui::Terminal;
+    use std::path::PathBuf;
+
+    fn explorer() -> Explorer {
+        let node = |name: &str, version: &str| {
+            let version = Pep440Version::parse(version).unwrap();
+            (name.to_string(), Node { name: name.to_string(), version: Some(version) })
+        };
+        let edge = |from: &str, requirement: &str| Edge {
+            from: from.to_string(),
+            to: PythonPackage::parse(requirement).unwrap().canonical_name(),
+            requirement: PythonPackage::parse(requirement).unwrap(),
+        };
+        let graph = DependencyGraph {
+            nodes: [node("requests", "2.31.0"), node("urllib3", "2.0.7")].into_iter().collect(),
+            edges: vec![edge("requests", "urllib3<3,>=1.21.1")],
+            roots: vec!["requests".to_string()],
+        };
+        let declared = vec![RequirementLine {
+            package: PythonPackage::parse("requests>=2.30").unwrap(),
+            file: PathBuf::from("requirements.txt"),
+            line: 1,
+            columns: None,
+            hashes: Vec::new(),
+        }];
+        Explorer::new(graph, declared)
+    }
+
+    #[test]
+    fn test_what_if() -> Result<()> {
+        let mut explorer = explorer();
+        assert_eq!(explorer.problem_count(), 0);
+
+        explorer.bump("urllib3", Pep440Version::parse("3.0")?, None);
+        assert_eq!(explorer.problems("urllib3").len(), 1);
+        assert_eq!(explorer.problems("urllib3")[0].source, "requests 2.31.0");
+
+        // The dependencies of the version tried out replace the old ones
+        let dependencies = vec![PythonPackage::parse("urllib3>=3")?, PythonPackage::parse("idna")?];
+        explorer.bump("requests", Pep440Version::parse("2.29")?, Some(dependencies));
+        assert!(explorer.problems("urllib3").is_empty());
+        let problems = explorer.problems("requests");
+        assert_eq!(problems.len(), 1);
+        assert_eq!(problems[0].source, "requirements.txt:1");
+        assert_eq!(explorer.problems("idna").len(), 1);
+        assert_eq!(explorer.selected(), Some("requests"));
+
+        explorer.reset();
+        assert_eq!(explorer.problem_count(), 0);
+        assert!(explorer.bumped.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_draw() -> Result<()> {
+        let mut explorer = explorer();
+        explorer.bump("urllib3", Pep440Version::parse("3.0")?, None);
+        explorer.select_next();
+        let mut terminal = Terminal::new(TestBackend::new(100, 12))?;
+        terminal.draw(|frame| draw(frame, &explorer, &Mode::Browse, ""))?;
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("urllib3 3.0 *"), "{}", screen);
+        assert!(screen.contains("Trying another version (was 2.0.7)"), "{}", screen);
+        assert!(screen.contains("requests 2.31.0  urllib3 <3,>=1.21.1  CONFLICT"), "{}", screen);
+        assert!(screen.contains("1 unsatisfied requirement(s)"), "{}", screen);
+        Ok(())
+    }
+}
@@ -0,0 +1,162 @@
+use std::fmt;
+
+use crate::package::canonicalize_name;
+
+/// Some of the most downloaded projects on PyPI, which squatters imitate.
+/// Names are PEP 503 normalized.
+pub const POPULAR: &[&str] = &[
+    "aiobotocore", "aiohttp", "aiosignal", "alembic", "annotated-types", "anyio", "argcomplete", "asgiref",
+    "async-timeout", "attrs", "azure-core", "azure-identity", "azure-storage-blob", "babel", "bcrypt",
+    "beautifulsoup4", "black", "bleach", "boto3", "botocore", "cachetools", "celery", "certifi", "cffi",
+    "chardet", "charset-normalizer", "click", "cloudpickle", "colorama", "coverage", "cryptography", "cycler",
+    "cython", "dask", "decorator", "defusedxml", "deprecated", "dill", "distlib", "distro", "django",
+    "djangorestframework", "dnspython", "docker", "docutils", "email-validator", "et-xmlfile", "exceptiongroup",
+    "fastapi", "filelock", "flake8", "flask", "fonttools", "frozenlist", "fsspec", "gitpython",
+    "google-api-core", "google-auth", "google-cloud-storage", "googleapis-common-protos", "greenlet", "grpcio",
+    "gunicorn", "h11", "httpcore", "httplib2", "httpx", "huggingface-hub", "idna", "importlib-metadata",
+    "iniconfig", "ipykernel", "ipython", "isodate", "isort", "itsdangerous", "jellyfish", "jinja2", "jmespath",
+    "joblib", "jsonschema", "jupyter", "jupyterlab", "keras", "kiwisolver", "lxml", "markdown",
+    "markdown-it-py", "markupsafe", "matplotlib", "mccabe", "mock", "more-itertools", "msgpack", "multidict",
+    "mypy", "mypy-extensions", "networkx", "nltk", "nose", "notebook", "nox", "numpy", "oauthlib", "openai",
+    "opencv-python", "openpyxl", "packaging", "pandas", "paramiko", "pathspec", "pillow", "pip", "platformdirs",
+    "pluggy", "poetry", "pre-commit", "prompt-toolkit", "protobuf", "psutil", "psycopg2", "psycopg2-binary",
+    "pyarrow", "pyasn1", "pycodestyle", "pycparser", "pycryptodome", "pydantic", "pydantic-core", "pyflakes",
+    "pygments", "pyjwt", "pylint", "pymongo", "pymysql", "pynacl", "pyopenssl", "pyparsing", "pyserial",
+    "pytest", "pytest-cov", "pytest-mock", "python-dateutil", "python-dotenv", "pytz", "pyyaml", "pyzmq",
+    "redis", "regex", "requests", "requests-oauthlib", "requests-toolbelt", "rich", "rsa", "ruamel-yaml",
+    "ruff", "s3transfer", "scapy", "scikit-learn", "scipy", "seaborn", "selenium", "setuptools", "simplejson",
+    "six", "sniffio", "soupsieve", "sphinx", "sqlalchemy", "starlette", "sympy", "tabulate", "tenacity",
+    "tensorflow", "toml", "tomli", "tomlkit", "toolz", "torch", "tornado", "tox", "tqdm", "transformers",
+    "typing-extensions", "tzdata", "ujson", "urllib3", "uvicorn", "virtualenv", "websocket-client",
+    "websockets", "werkzeug", "wheel", "wrapt", "xlrd", "xmltodict", "yarl", "zipp",
+];
+
+/// Words squatters add around a popular name, as in `python-requests`.
+const AFFIXES: &[&str] = &["python3-", "python-", "py3-", "py-", "-python3", "-python", "-py3", "-py"];
+
+/// How a name resembles a popular project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Resemblance {
+    /// A few characters apart: this many insertions, deletions,
+    /// substitutions or swaps of adjacent characters.
+    Typo(usize),
+    /// The popular name with a word like `python-` around it.
+    Affix(&'static str),
+    /// The popular name with its separators dropped or moved.
+    Separators,
+}
+
+/// A dependency whose name looks like a mistyped or imitated popular project.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Suspect {
+    pub name: String,
+    pub resembles: &'static str,
+    pub resemblance: Resemblance,
+}
+
+impl fmt::Display for Suspect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} looks like {}: ", self.name, self.resembles)?;
+        match &self.resemblance {
+            Resemblance::Typo(1) => write!(f, "one character apart"),
+            Resemblance::Typo(n) => write!(f, "{} characters apart", n),
+            Resemblance::Affix(affix) if affix.starts_with('-') => write!(f, "with the suffix {}", affix),
+            Resemblance::Affix(affix) => write!(f, "with the prefix {}", affix),
+            Resemblance::Separators => write!(f, "the same letters with other separators"),
+        }
+    }
+}
+
+/// Whether `name` imitates one of the [`POPULAR`] projects, which are never
+/// suspects themselves.
+pub fn check(name: &str) -> Option<Suspect> {
+    let canonical = canonicalize_name(name);
+    if POPULAR.contains(&canonical.as_str()) {
+        return None;
+    }
+    let suspect = |resembles: &'static str, resemblance| Suspect {
+        name: name.to_string(),
+        resembles,
+        resemblance,
+    };
+    for affix in AFFIXES {
+        let stripped = match affix.starts_with('-') {
+            true => canonical.strip_suffix(affix),
+            false => canonical.strip_prefix(affix),
+        };
+        if let Some(popular) = stripped.and_then(|stripped| POPULAR.iter().find(|popular| **popular == stripped)) {
+            return Some(suspect(popular, Resemblance::Affix(affix)));
+        }
+    }
+    let letters = |name: &str| name.replace('-', "");
+    if let Some(popular) = POPULAR.iter().find(|popular| letters(popular) == letters(&canonical)) {
+        return Some(suspect(popular, Resemblance::Separators));
+    }
+    POPULAR
+        .iter()
+        .filter_map(|popular| {
+            // Short names are a character apart from many legitimate projects
+            let allowed = match popular.len() {
+                0..=4 => 0,
+                5..=9 => 1,
+                _ => 2,
+            };
+            let distance = edit_distance(&canonical, popular);
+            (distance <= allowed).then_some((distance, *popular))
+        })
+        .min()
+        .map(|(distance, popular)| suspect(popular, Resemblance::Typo(distance)))
+}
+
+/// The optimal string alignment distance: the fewest insertions,
+/// deletions, substitutions and swaps of adjacent characters turning `a`
+/// into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    rows[0] = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = best;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        assert_eq!(edit_distance("reqeusts", "requests"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+
+        let found = |name: &str| check(name).map(|suspect| (suspect.resembles, suspect.resemblance));
+        assert_eq!(found("python-requests"), Some(("requests", Resemblance::Affix("python-"))));
+        assert_eq!(found("Requests_Py"), Some(("requests", Resemblance::Affix("-py"))));
+        assert_eq!(found("reqeusts"), Some(("requests", Resemblance::Typo(1))));
+        assert_eq!(found("urlib3"), Some(("urllib3", Resemblance::Typo(1))));
+        assert_eq!(found("scikitlearn"), Some(("scikit-learn", Resemblance::Separators)));
+        assert_eq!(found("colourama"), Some(("colorama", Resemblance::Typo(1))));
+
+        // Popular names, short names and unrelated names are left alone
+        assert_eq!(found("Python_DateUtil"), None);
+        assert_eq!(found("sax"), None);
+        assert_eq!(found("my-internal-tool"), None);
+        assert_eq!(found("toml"), None);
+        assert_eq!(
+            check("python-requests").unwrap().to_string(),
+            "python-requests looks like requests: with the prefix python-"
+        );
+    }
+}
@@ -0,0 +1,94 @@
+use anyhow::Result;
+use serde_json::{json, Value};
+use std::path::Path;
+use wasm_bindgen::prelude::*;
+
+use crate::conflict::{example, ConflictReport};
+use crate::output::json;
+use crate::package::PythonPackage;
+use crate::prerelease::PreReleasePolicy;
+use crate::requirements;
+use crate::specifier::SpecifierSet;
+
+/// The file name reported for requirements passed in as text.
+const SOURCE: &str = "requirements.txt";
+
+/// Parses a PEP 508 requirement, returning it as JSON in the shape of the
+/// `packages` entries of `pyhelper check --format json`.
+#[wasm_bindgen(js_name = parseRequirement)]
+pub fn parse_requirement(input: &str) -> Result<String, JsError> {
+    exported(parsed_requirement(input))
+}
+
+/// Intersects two specifier sets such as `>=2.0` and `<3,!=2.5.*`, returning
+/// the versions both allow and a version in that range as JSON.
+#[wasm_bindgen]
+pub fn intersect(a: &str, b: &str) -> Result<String, JsError> {
+    exported(intersection(a, b))
+}
+
+/// Compares two requirements like `pyhelper check REQ1 REQ2 --format json`.
+#[wasm_bindgen(js_name = checkPair)]
+pub fn check_pair(a: &str, b: &str) -> Result<String, JsError> {
+    exported(pair(a, b))
+}
+
+/// Checks the content of a requirements file for conflicts, returning the
+/// report of `pyhelper check -r FILE --format json`. Includes (`-r`, `-c`)
+/// cannot be followed and are an error.
+#[wasm_bindgen(js_name = checkConflicts)]
+pub fn check_conflicts(content: &str) -> Result<String, JsError> {
+    exported(conflicts(content))
+}
+
+fn parsed_requirement(input: &str) -> Result<Value> {
+    Ok(json::package(&PythonPackage::parse(input)?))
+}
+
+fn intersection(a: &str, b: &str) -> Result<Value> {
+    let (a, b) = (SpecifierSet::parse(a)?, SpecifierSet::parse(b)?);
+    let range = a.range().intersect(&b.range());
+    Ok(json!({
+        "intersection": json::range(&range),
+        "empty": range.is_empty(),
+        "witness": example(&[&a, &b], PreReleasePolicy::default().allows(&[&a, &b])),
+    }))
+}
+
+fn pair(a: &str, b: &str) -> Result<Value> {
+    let (a, b) = (PythonPackage::parse(a)?, PythonPackage::parse(b)?);
+    Ok(json::pair(&a, &b, None, Default::default()))
+}
+
+fn conflicts(content: &str) -> Result<Value> {
+    let requirements = requirements::parse_str(content, Path::new(SOURCE))?;
+    let report = ConflictReport::check(&requirements);
+    Ok(json::report(&[SOURCE.to_string()], &report, None))
+}
+
+fn exported(result: Result<Value>) -> Result<String, JsError> {
+    result.map(|value| value.to_string()).map_err(|e| JsError::new(&format!("{:#}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_exports() -> Result<()> {
+        let parsed = parsed_requirement("Requests[socks]>=2.0")?;
+        assert_eq!(parsed["canonical_name"], "requests");
+
+        let both = intersection(">=2.0", "<3,!=2.5")?;
+        assert_eq!(both["empty"], false);
+        assert_eq!(both["witness"], "2.0");
+        assert_eq!(intersection(">=3", "<2")?["empty"], true);
+
+        assert_eq!(pair("django>=4", "django<3")?["conflict"], true);
+        let report = conflicts("six<1\nsix>=2\nflask\n")?;
+        assert_eq!(report["conflict"], true);
+        assert_eq!(report["packages"][1]["conflict"], true);
+        assert!(conflicts("-r base.txt\n").is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use notify::{Event, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::time::Duration;
+
+use crate::conflict::ConflictReport;
+
+/// How long to wait for more events after a change, so that an editor saving
+/// through a temporary file triggers a single run.
+const SETTLE_TIME: Duration = Duration::from_millis(200);
+
+/// How the conflicts of one run differ from those of the run before.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConflictChange {
+    /// A package that had no conflict has one now.
+    Found(String),
+    /// A package whose conflict is gone.
+    Resolved(String),
+    /// A package still in conflict, over different requirements.
+    Changed(String),
+}
+
+/// The conflicts of a run, comparable with those of another run: every
+/// package's conflicting requirements with their locations.
+pub fn snapshot(report: &ConflictReport) -> BTreeMap<String, Vec<String>> {
+    report
+        .conflicts
+        .iter()
+        .map(|conflict| {
+            let requirements = conflict
+                .requirements
+                .iter()
+                .map(|line| format!("{}  {}", line.location(), line.package))
+                .collect();
+            (conflict.name.clone(), requirements)
+        })
+        .collect()
+}
+
+/// The changes from the conflicts of one run to those of the next, by package name.
+pub fn compare(before: &BTreeMap<String, Vec<String>>, after: &BTreeMap<String, Vec<String>>) -> Vec<ConflictChange> {
+    let names: BTreeSet<&String> = before.keys().chain(after.keys()).collect();
+    names
+        .into_iter()
+        .filter_map(|name| match (before.get(name), after.get(name)) {
+            (None, Some(_)) => Some(ConflictChange::Found(name.clone())),
+            (Some(_), None) => Some(ConflictChange::Resolved(name.clone())),
+            (Some(old), Some(new)) if old != new => Some(ConflictChange::Changed(name.clone())),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Calls `run` once, then again every time one of the files it returns
+/// changes, until the process is interrupted. `run` returns the files to
+/// watch next, so files included by a run are watched too.
+///
+/// The directories of the files are watched rather than the files, as many
+/// editors replace a file when saving it.
+pub fn watch(mut run: impl FnMut(&[PathBuf]) -> Vec<PathBuf>) -> Result<()> {
+    let (sender, events) = mpsc::channel::<notify::Result<Event>>();
+    let mut watcher = notify::recommended_watcher(sender)?;
+    let mut directories: BTreeSet<PathBuf> = BTreeSet::new();
+    let mut changed: Vec<PathBuf> = Vec::new();
+
+    loop {
+        let files: BTreeSet<PathBuf> = run(&changed).iter().map(|file| absolute(file)).collect();
+        let wanted: BTreeSet<PathBuf> = files.iter().filter_map(|file| file.parent().map(Path::to_path_buf)).collect();
+        for directory in directories.difference(&wanted) {
+            // The directory may be gone already
+            let _ = watcher.unwatch(directory);
+        }
+        for directory in wanted.difference(&directories) {
+            watcher
+                .watch(directory, RecursiveMode::NonRecursive)
+                .with_context(|| format!("Cannot watch {}", directory.display()))?;
+        }
+        directories = wanted;
+
+        changed.clear();
+        while changed.is_empty() {
+            let event = events.recv()?;
+            collect(event, &files, &mut changed);
+            while let Ok(event) = events.recv_timeout(SETTLE_TIME) {
+                collect(event, &files, &mut changed);
+            }
+        }
+    }
+}
+
+/// Adds the watched files an event modified to `changed`.
+fn collect(event: notify::Result<Event>, files: &BTreeSet<PathBuf>, changed: &mut Vec<PathBuf>) {
+    let Ok(event) = event else {
+        return;
+    };
+    if event.kind.is_access() {
+        return;
+    }
+    for path in event.paths {
+        let path = absolute(&path);
+        if files.contains(&path) && !changed.contains(&path) {
+            changed.push(path);
+        }
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conflicts(entries: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        entries
+            .iter()
+            .map(|(name, lines)| (name.to_string(), lines.iter().map(|l| l.to_string()).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_compare() {
+        let before = conflicts(&[("django", &["a.txt:1  django >=4", "a.txt:2  django <3"]), ("six", &["a.txt:3  six <1", "a.txt:4  six >=2"])]);
+        let after = conflicts(&[("django", &["a.txt:1  django >=4", "a.txt:2  django <3.2"]), ("flask", &["a.txt:5  flask <2", "a.txt:6  flask >=3"])]);
+        assert_eq!(
+            compare(&before, &after),
+            vec![
+                ConflictChange::Changed("django".to_string()),
+                ConflictChange::Found("flask".to_string()),
+                ConflictChange::Resolved("six".to_string()),
+            ]
+        );
+        assert!(compare(&after, &after).is_empty());
+    }
+}
@@ -0,0 +1,210 @@
+use anyhow::Result;
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::conflict::{ConflictReport, PackageConflict};
+use crate::local;
+use crate::merge::{self, MergedRequirement};
+use crate::package::canonicalize_name;
+use crate::prerelease::PreReleasePolicy;
+use crate::requirements::RequirementLine;
+use crate::scan::{self, Unreadable};
+
+/// The file names marking the directory of a project.
+const PROJECT_FILES: [&str; 3] = ["pyproject.toml", "setup.cfg", "setup.py"];
+
+/// One project of a workspace, with the requirements of the dependency files
+/// in its directory and in subdirectories belonging to no other project.
+#[derive(Debug, Clone)]
+pub struct Member {
+    /// The project name from its metadata, or else the name of its directory.
+    pub name: String,
+    pub dir: PathBuf,
+    pub requirements: Vec<RequirementLine>,
+}
+
+/// Requirements on a third-party package that no version satisfies together,
+/// and the members declaring them.
+#[derive(Debug, Clone)]
+pub struct SharedConflict {
+    pub conflict: PackageConflict,
+    /// Names of the members taking part, in the order of their requirements.
+    pub members: Vec<String>,
+}
+
+impl SharedConflict {
+    /// Whether the requirements come from more than one member.
+    pub fn is_cross_project(&self) -> bool {
+        self.members.len() > 1
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WorkspaceReport {
+    pub members: Vec<Member>,
+    pub conflicts: Vec<SharedConflict>,
+    /// One requirement per third-party package every member can agree on;
+    /// packages in conflict are left out.
+    pub unified: Vec<MergedRequirement>,
+}
+
+impl WorkspaceReport {
+    /// The member declaring `requirement`.
+    pub fn member_of(&self, requirement: &RequirementLine) -> Option<&Member> {
+        member_of(&self.members, &requirement.file)
+    }
+}
+
+/// Finds the projects below `root`: every directory with a pyproject.toml,
+/// setup.cfg or setup.py, and `root` itself for the dependency files outside
+/// of them. Files that cannot be read are returned with their error.
+pub fn discover(root: &Path, exclude: &[String]) -> Result<(Vec<Member>, Unreadable)> {
+    let files = scan::discover(root, exclude)?;
+    let mut dirs: BTreeSet<PathBuf> = files
+        .iter()
+        .filter(|file| file.file_name().and_then(|name| name.to_str()).is_some_and(|name| PROJECT_FILES.contains(&name)))
+        .map(|file| file.parent().unwrap_or(Path::new("")).to_path_buf())
+        .collect();
+    dirs.insert(root_dir(root));
+
+    let mut members: Vec<Member> = dirs
+        .into_iter()
+        .map(|dir| Member {
+            name: member_name(&dir),
+            dir,
+            requirements: Vec::new(),
+        })
+        .collect();
+    let (requirements, errors) = scan::load(&files);
+    for requirement in requirements {
+        let dir = member_of(&members, &requirement.file).map(|member| member.dir.clone());
+        if let Some(member) = members.iter_mut().find(|member| Some(&member.dir) == dir.as_ref()) {
+            member.requirements.push(requirement);
+        }
+    }
+    members.retain(|member| !member.requirements.is_empty() || member.dir != root_dir(root));
+    Ok((members, errors))
+}
+
+/// Checks the third-party requirements of all members against each other.
+/// Requirements on the members themselves are internal and left out.
+pub fn check(members: Vec<Member>, policy: PreReleasePolicy) -> WorkspaceReport {
+    let internal: BTreeSet<String> = members.iter().map(|member| canonicalize_name(&member.name)).collect();
+    let third_party: Vec<RequirementLine> = members
+        .iter()
+        .flat_map(|member| member.requirements.iter())
+        .filter(|requirement| !internal.contains(&requirement.package.canonical_name()))
+        .cloned()
+        .collect();
+
+    let report = ConflictReport::check_with(&third_party, policy);
+    let conflicts: Vec<SharedConflict> = report
+        .conflicts
+        .iter()
+        .map(|conflict| {
+            let mut names: Vec<String> = Vec::new();
+            for requirement in &conflict.requirements {
+                let name = member_of(&members, &requirement.file).map(|member| member.name.clone()).unwrap_or_default();
+                if !names.contains(&name) {
+                    names.push(name);
+                }
+            }
+            SharedConflict {
+                conflict: conflict.clone(),
+                members: names,
+            }
+        })
+        .collect();
+    let agreed: Vec<RequirementLine> = third_party
+        .into_iter()
+        .filter(|requirement| report.conflict_for(&requirement.package.canonical_name()).is_none())
+        .collect();
+    WorkspaceReport {
+        unified: merge::merge(&agreed),
+        members,
+        conflicts,
+    }
+}
+
+/// The workspace constraints as a pip constraints file, with a comment in
+/// place of every package in conflict.
+pub fn constraints_txt(report: &WorkspaceReport, root: &Path) -> String {
+    let members: Vec<&str> = report.members.iter().map(|member| member.name.as_str()).collect();
+    let mut out = format!("# Workspace constraints by pyhelper for {} ({})\n", root.display(), members.join(", "));
+    out.push_str(&merge::lines(&report.unified));
+    for shared in &report.conflicts {
+        out.push_str(&format!(
+            "# {}: no version satisfies {} together; align their requirements first\n",
+            shared.conflict.name,
+            shared.members.join(", ")
+        ));
+    }
+    out
+}
+
+/// The member whose directory is the deepest one containing `file`.
+fn member_of<'a>(members: &'a [Member], file: &Path) -> Option<&'a Member> {
+    members
+        .iter()
+        .filter(|member| member.dir.as_os_str().is_empty() || file.starts_with(&member.dir))
+        .max_by_key(|member| member.dir.components().count())
+}
+
+/// `root` as the discovered files spell it: `.` is left out of their paths.
+fn root_dir(root: &Path) -> PathBuf {
+    if root == Path::new(".") {
+        PathBuf::new()
+    } else {
+        root.to_path_buf()
+    }
+}
+
+fn member_name(dir: &Path) -> String {
+    let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+    if let Some(project) = local::project(dir) {
+        return project.name;
+    }
+    let absolute = fs::canonicalize(dir).unwrap_or_else(|_| dir.to_path_buf());
+    absolute.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_else(|| dir.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_workspace() -> Result<()> {
+        let root = std::env::temp_dir().join(format!("pyhelper-workspace-{}", std::process::id()));
+        for dir in ["services/api", "services/worker/requirements", "libs/core"] {
+            fs::create_dir_all(root.join(dir))?;
+        }
+        let pyproject = |name: &str, dependencies: &str| {
+            format!("[project]\nname = \"{}\"\nversion = \"1.0\"\ndependencies = [{}]\n", name, dependencies)
+        };
+        fs::write(root.join("services/api/pyproject.toml"), pyproject("api", "\"requests<2\", \"core>=1\", \"attrs>=22\""))?;
+        fs::write(root.join("services/worker/setup.cfg"), "[metadata]\nname = worker\n\n[options]\ninstall_requires =\n    attrs<24\n")?;
+        fs::write(root.join("services/worker/requirements/base.txt"), "requests>=2.31\n")?;
+        fs::write(root.join("libs/core/pyproject.toml"), pyproject("core", "\"attrs>=23\""))?;
+        fs::write(root.join("requirements-dev.txt"), "pytest\n")?;
+
+        let (members, errors) = discover(&root, &[])?;
+        assert!(errors.is_empty());
+        let names: Vec<&str> = members.iter().map(|member| member.name.as_str()).collect();
+        assert_eq!(names[1..], ["core", "api", "worker"]);
+        assert_eq!(members[3].requirements.len(), 2);
+
+        let report = check(members, PreReleasePolicy::default());
+        assert_eq!(report.conflicts.len(), 1);
+        assert_eq!(report.conflicts[0].members, vec!["api", "worker"]);
+        assert!(report.conflicts[0].is_cross_project());
+        let unified: Vec<String> = report.unified.iter().map(|merged| merged.package.to_string()).collect();
+        assert_eq!(unified, vec!["attrs >=23,<24", "pytest *"]);
+
+        let constraints = constraints_txt(&report, &root);
+        assert!(constraints.contains("attrs>=23,<24\n"), "{}", constraints);
+        assert!(constraints.contains("# requests: no version satisfies api, worker together"), "{}", constraints);
+        fs::remove_dir_all(&root)?;
+        Ok(())
+    }
+}